@@ -19,6 +19,10 @@ if %ERRORLEVEL% NEQ 0 (
 )
 
 echo [2/5] Building release version...
+if not defined IRIS_BUNDLE_PUBLIC_KEY if not exist bundle-public-key.txt (
+    echo WARNING: No signature bundle public key - server bundles will be rejected
+    echo See Server\scripts\iris-signing-key.md
+)
 cargo tauri build
 if %ERRORLEVEL% NEQ 0 (
     echo ERROR: Build failed!
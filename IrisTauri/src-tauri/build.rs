@@ -1,3 +1,25 @@
+use std::fs;
+
+/// Checked-in alternative to the IRIS_BUNDLE_PUBLIC_KEY environment variable
+const PUBLIC_KEY_FILE: &str = "bundle-public-key.txt";
+
 fn main() {
+    // Ed25519 public key of the signature bundles (hex), embedded into signatures.rs
+    println!("cargo:rerun-if-env-changed=IRIS_BUNDLE_PUBLIC_KEY");
+    println!("cargo:rerun-if-changed={}", PUBLIC_KEY_FILE);
+
+    let key = std::env::var("IRIS_BUNDLE_PUBLIC_KEY").ok()
+        .or_else(|| fs::read_to_string(PUBLIC_KEY_FILE).ok())
+        .map(|key| key.trim().to_lowercase())
+        .filter(|key| !key.is_empty());
+
+    match key {
+        Some(key) if key.len() == 64 && key.chars().all(|c| c.is_ascii_hexdigit()) => {
+            println!("cargo:rustc-env=IRIS_BUNDLE_PUBLIC_KEY={}", key);
+        }
+        Some(_) => panic!("IRIS_BUNDLE_PUBLIC_KEY must be 64 hex characters (raw Ed25519 public key)"),
+        None => println!("cargo:warning=No bundle public key: signature bundles will be rejected"),
+    }
+
     tauri_build::build()
 }
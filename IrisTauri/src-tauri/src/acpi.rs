@@ -0,0 +1,670 @@
+//! ACPI module - DMAR / IVRS parsing for IOMMU verification
+//!
+//! The firmware describes its IOMMUs in one ACPI table: DMAR for Intel VT-d, IVRS
+//! for AMD-Vi. It is only published while the IOMMU is enabled in setup, so a
+//! parsed table with at least one remapping unit is the basis for `iommu`. Both
+//! tables carry the platform opt-in for Kernel DMA Protection (DMAR flag
+//! DMA_CTRL_PLATFORM_OPT_IN, IVRS IVinfo DMA remap support): without it Windows
+//! never enforces the protection, whatever the registry says.
+//!
+//! Table bytes come from GetSystemFirmwareTable('ACPI') on Windows and from
+//! /sys/firmware/acpi/tables on Linux (root only), where /sys/class/iommu also
+//! shows the units the kernel actually enabled.
+
+use serde::{Deserialize, Serialize};
+
+const HEADER_SIZE: usize = 36;
+const STRUCTURES_OFFSET: usize = 48; // Remapping structures (DMAR) / IVDB blocks (IVRS)
+
+// DMAR flags
+const DMAR_INTR_REMAP: u8 = 1 << 0;
+const DMAR_X2APIC_OPT_OUT: u8 = 1 << 1;
+const DMAR_DMA_CTRL_PLATFORM_OPT_IN: u8 = 1 << 2;
+
+// DMAR remapping structure types
+const DMAR_DRHD: u16 = 0;
+const DMAR_RMRR: u16 = 1;
+
+// DRHD flags
+const DRHD_INCLUDE_PCI_ALL: u8 = 1 << 0;
+
+// IVinfo bits
+const IVINFO_DMA_REMAP: u32 = 1 << 1;
+
+/// IOMMU hardware unit (DMAR DRHD or IVRS IVHD)
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct RemappingUnit {
+    pub segment: u16,
+    pub register_base: String,
+    pub include_all: bool,         // Covers every device not listed under another unit
+    pub device_id: Option<String>, // PCI function of the IOMMU itself (IVRS)
+    pub scopes: usize,
+}
+
+/// Device placed behind a remapping unit
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceScope {
+    pub unit: usize,    // Index in remapping_units
+    pub kind: String,   // endpoint, bridge, ioapic, hpet, namespace, range
+    pub device: String, // segment:bus:device.function, extra hops as /dev.fn
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct IommuReport {
+    pub table: Option<String>, // DMAR or IVRS
+    pub oem_id: String,
+    pub address_width: u8,
+    pub remapping_units: Vec<RemappingUnit>,
+    pub device_scopes: Vec<DeviceScope>,
+    pub reserved_regions: usize, // RMRR / IVMD: memory kept identity-mapped for devices
+    pub interrupt_remapping: bool,
+    pub x2apic_opt_out: bool,
+    pub dma_platform_opt_in: bool,
+    pub active_units: Option<usize>,             // Linux: units enabled by the kernel
+    pub kernel_dma_protection: Option<bool>,     // Linux: thunderbolt iommu_dma_protection
+    pub error: Option<String>,                   // Table could not be read or parsed
+    pub reasons: Vec<String>,
+}
+
+impl IommuReport {
+    /// Firmware exposes at least one remapping unit
+    pub fn present(&self) -> bool {
+        !self.remapping_units.is_empty()
+    }
+
+    /// Whether the firmware allows Kernel DMA Protection (None when the table could not be read)
+    pub fn dma_protection_supported(&self) -> Option<bool> {
+        if self.table.is_none() && self.error.is_some() {
+            return None;
+        }
+        Some(self.present() && self.dma_platform_opt_in)
+    }
+}
+
+/// Parse a DMAR or IVRS table
+pub fn parse(table: &[u8]) -> Result<IommuReport, String> {
+    let table = checked_table(table)?;
+    let mut report = match &table[0..4] {
+        b"DMAR" => parse_dmar(table),
+        b"IVRS" => parse_ivrs(table),
+        other => return Err(format!("Not an IOMMU table: {}", String::from_utf8_lossy(other))),
+    };
+    report.oem_id = String::from_utf8_lossy(&table[10..16]).trim_end_matches(['\0', ' ']).to_string();
+    Ok(report)
+}
+
+/// Table cut to its header length, with a valid checksum
+fn checked_table(table: &[u8]) -> Result<&[u8], String> {
+    if table.len() < HEADER_SIZE {
+        return Err(format!("ACPI table of {} bytes", table.len()));
+    }
+    let length = dword(table, 4).unwrap_or(0) as usize;
+    if length < HEADER_SIZE || length > table.len() {
+        return Err(format!("ACPI table length {} out of range ({} bytes read)", length, table.len()));
+    }
+    let table = &table[..length];
+    if table.iter().fold(0u8, |sum, b| sum.wrapping_add(*b)) != 0 {
+        return Err(format!("{} checksum mismatch", String::from_utf8_lossy(&table[0..4])));
+    }
+    Ok(table)
+}
+
+fn parse_dmar(table: &[u8]) -> IommuReport {
+    let flags = table.get(37).copied().unwrap_or(0);
+    let mut report = IommuReport {
+        table: Some("DMAR".to_string()),
+        address_width: table.get(36).map(|w| w.saturating_add(1)).unwrap_or(0),
+        interrupt_remapping: flags & DMAR_INTR_REMAP != 0,
+        x2apic_opt_out: flags & DMAR_X2APIC_OPT_OUT != 0,
+        dma_platform_opt_in: flags & DMAR_DMA_CTRL_PLATFORM_OPT_IN != 0,
+        ..Default::default()
+    };
+
+    let mut offset = STRUCTURES_OFFSET;
+    while let (Some(kind), Some(length)) = (word(table, offset), word(table, offset + 2)) {
+        let length = length as usize;
+        if length < 4 || offset + length > table.len() {
+            break;
+        }
+        let structure = &table[offset..offset + length];
+
+        match kind {
+            DMAR_DRHD if length >= 16 => {
+                let segment = word(structure, 6).unwrap_or(0);
+                let unit = report.remapping_units.len();
+                let scopes = dmar_scopes(&structure[16..], segment, unit);
+                report.remapping_units.push(RemappingUnit {
+                    segment,
+                    register_base: format!("{:#x}", qword(structure, 8).unwrap_or(0)),
+                    include_all: structure[4] & DRHD_INCLUDE_PCI_ALL != 0,
+                    device_id: None,
+                    scopes: scopes.len(),
+                });
+                report.device_scopes.extend(scopes);
+            }
+            DMAR_RMRR => report.reserved_regions += 1,
+            _ => {} // ATSR, RHSA, ANDD, SATC
+        }
+        offset += length;
+    }
+    report
+}
+
+/// Device scope entries: type, length, reserved, enumeration id, start bus, (device, function) path
+fn dmar_scopes(data: &[u8], segment: u16, unit: usize) -> Vec<DeviceScope> {
+    let mut scopes = Vec::new();
+    let mut offset = 0;
+    while offset + 6 <= data.len() {
+        let length = data[offset + 1] as usize;
+        if length < 6 || offset + length > data.len() {
+            break;
+        }
+        let entry = &data[offset..offset + length];
+        let kind = match entry[0] {
+            1 => "endpoint",
+            2 => "bridge",
+            3 => "ioapic",
+            4 => "hpet",
+            5 => "namespace",
+            _ => "unknown",
+        };
+
+        let mut hops = entry[6..].chunks_exact(2);
+        let device = match hops.next() {
+            Some(first) => {
+                let mut device = format!("{:04x}:{:02x}:{:02x}.{:x}", segment, entry[5], first[0], first[1]);
+                for hop in hops {
+                    device.push_str(&format!("/{:02x}.{:x}", hop[0], hop[1]));
+                }
+                device
+            }
+            None => format!("{:04x}:{:02x}", segment, entry[5]),
+        };
+        scopes.push(DeviceScope { unit, kind: kind.to_string(), device });
+        offset += length;
+    }
+    scopes
+}
+
+fn parse_ivrs(table: &[u8]) -> IommuReport {
+    let ivinfo = dword(table, 36).unwrap_or(0);
+    let mut report = IommuReport {
+        table: Some("IVRS".to_string()),
+        address_width: ((ivinfo >> 8) & 0x7f) as u8, // Physical address size
+        dma_platform_opt_in: ivinfo & IVINFO_DMA_REMAP != 0,
+        ..Default::default()
+    };
+
+    let mut offset = STRUCTURES_OFFSET;
+    while let (Some(kind), Some(length)) = (table.get(offset).copied(), word(table, offset + 2)) {
+        let length = length as usize;
+        if length < 4 || offset + length > table.len() {
+            break;
+        }
+        let block = &table[offset..offset + length];
+
+        match kind {
+            0x10 | 0x11 | 0x40 => {
+                let entries_offset = if kind == 0x10 { 24 } else { 40 };
+                if length < entries_offset {
+                    offset += length;
+                    continue;
+                }
+                let segment = word(block, 16).unwrap_or(0);
+                let register_base = format!("{:#x}", qword(block, 8).unwrap_or(0));
+                // Firmware lists the same IOMMU once per IVHD type it supports
+                let known = report.remapping_units.iter()
+                    .any(|u| u.segment == segment && u.register_base == register_base);
+                if !known {
+                    let unit = report.remapping_units.len();
+                    let (scopes, include_all) = ivhd_entries(&block[entries_offset..], segment, unit);
+                    report.remapping_units.push(RemappingUnit {
+                        segment,
+                        register_base,
+                        include_all,
+                        device_id: Some(pci_id(segment, word(block, 4).unwrap_or(0))),
+                        scopes: scopes.len(),
+                    });
+                    report.device_scopes.extend(scopes);
+                }
+            }
+            0x20..=0x22 => report.reserved_regions += 1,
+            _ => {}
+        }
+        offset += length;
+    }
+    // AMD-Vi remaps interrupts only when the IVRS places the IOAPICs behind a unit
+    report.interrupt_remapping = report.device_scopes.iter().any(|s| s.kind == "ioapic");
+    report
+}
+
+/// IVHD device entries: 4-byte (type < 0x40), 8-byte (< 0x80) or variable-length
+fn ivhd_entries(data: &[u8], segment: u16, unit: usize) -> (Vec<DeviceScope>, bool) {
+    let mut scopes = Vec::new();
+    let mut include_all = false;
+    let mut range_start: Option<u16> = None;
+    let mut offset = 0;
+
+    while offset + 4 <= data.len() {
+        let kind = data[offset];
+        let id = word(data, offset + 1).unwrap_or(0);
+        let size = match kind {
+            0x00..=0x3f => 4,
+            0x40..=0x7f => 8,
+            // ACPI HID device: fixed part and a UID of the length in byte 21
+            0xf0 => match data.get(offset + 21) {
+                Some(uid_length) => 22 + *uid_length as usize,
+                None => break,
+            },
+            _ => break, // Unknown variable-length entry
+        };
+        if offset + size > data.len() {
+            break;
+        }
+
+        let mut push = |kind: &str, device: String| scopes.push(DeviceScope { unit, kind: kind.to_string(), device });
+        match kind {
+            0x01 => include_all = true,
+            0x02 | 0x42 | 0x46 => push("endpoint", pci_id(segment, id)),
+            0x03 | 0x43 | 0x47 => range_start = Some(id),
+            0x04 => {
+                if let Some(start) = range_start.take() {
+                    push("range", format!("{}-{}", pci_id(segment, start), pci_id(segment, id)));
+                }
+            }
+            0x48 => {
+                let source = word(data, offset + 5).unwrap_or(0);
+                let variety = if data[offset + 7] == 2 { "hpet" } else { "ioapic" };
+                push(variety, pci_id(segment, source));
+            }
+            0xf0 => push("namespace", pci_id(segment, id)),
+            _ => {}
+        }
+        offset += size;
+    }
+    (scopes, include_all)
+}
+
+/// segment:bus:device.function of a 16-bit PCI requester id
+fn pci_id(segment: u16, id: u16) -> String {
+    format!("{:04x}:{:02x}:{:02x}.{:x}", segment, id >> 8, (id >> 3) & 0x1f, id & 0x7)
+}
+
+/// Human-readable basis of the IOMMU and DMA protection verdicts
+pub fn explain(report: &mut IommuReport) {
+    let mut reasons = Vec::new();
+
+    match &report.table {
+        Some(table) => {
+            let vendor = if table == "DMAR" { "Intel VT-d" } else { "AMD-Vi" };
+            if report.present() {
+                reasons.push(format!("{} table ({}): {} remapping unit(s), {} device scope(s)",
+                                     table, vendor, report.remapping_units.len(), report.device_scopes.len()));
+            } else {
+                reasons.push(format!("{} table ({}) lists no remapping unit", table, vendor));
+            }
+            if report.dma_platform_opt_in {
+                reasons.push("Firmware opts in to Kernel DMA Protection".to_string());
+            } else {
+                reasons.push("Firmware does not opt in to Kernel DMA Protection".to_string());
+            }
+            if report.interrupt_remapping {
+                reasons.push("Interrupt remapping supported".to_string());
+            }
+            if report.reserved_regions > 0 {
+                reasons.push(format!("{} reserved memory region(s) left mapped for devices", report.reserved_regions));
+            }
+        }
+        None => match &report.error {
+            Some(e) => reasons.push(format!("IOMMU table unavailable: {}", e)),
+            None => reasons.push("No DMAR or IVRS table: IOMMU disabled in firmware".to_string()),
+        },
+    }
+
+    match report.active_units {
+        Some(0) => reasons.push("Kernel has not enabled any IOMMU".to_string()),
+        Some(count) => reasons.push(format!("Kernel enabled {} IOMMU unit(s)", count)),
+        None => {}
+    }
+    match report.kernel_dma_protection {
+        Some(true) => reasons.push("Kernel enforces DMA protection on external ports".to_string()),
+        Some(false) => reasons.push("Kernel does not enforce DMA protection on external ports".to_string()),
+        None => {}
+    }
+
+    report.reasons = reasons;
+}
+
+/// IOMMU report of the live system
+#[cfg(target_os = "windows")]
+pub fn iommu_report() -> IommuReport {
+    let mut report = table_report(read_table(b"DMAR").and_then(|dmar| match dmar {
+        Some(dmar) => Ok(Some(dmar)),
+        None => read_table(b"IVRS"),
+    }));
+    explain(&mut report);
+    report
+}
+
+#[cfg(target_os = "linux")]
+pub fn iommu_report() -> IommuReport {
+    read_linux_report(std::path::Path::new("/sys"))
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+pub fn iommu_report() -> IommuReport {
+    let mut report = IommuReport { error: Some("ACPI tables not supported on this platform".to_string()), ..Default::default() };
+    explain(&mut report);
+    report
+}
+
+/// Report of a table read (Ok(None) when the firmware does not publish one)
+fn table_report(table: Result<Option<Vec<u8>>, String>) -> IommuReport {
+    match table.and_then(|t| t.map(|t| parse(&t)).transpose()) {
+        Ok(Some(report)) => report,
+        Ok(None) => IommuReport::default(),
+        Err(e) => IommuReport { error: Some(e), ..Default::default() },
+    }
+}
+
+/// ACPI table by signature (None when the firmware does not publish it)
+#[cfg(target_os = "windows")]
+pub fn read_table(signature: &[u8; 4]) -> Result<Option<Vec<u8>>, String> {
+    use windows::Win32::System::SystemInformation::{GetSystemFirmwareTable, FIRMWARE_TABLE_PROVIDER};
+
+    let provider = FIRMWARE_TABLE_PROVIDER(0x41435049); // 'ACPI'
+    let id = u32::from_le_bytes(*signature);
+    unsafe {
+        let size = GetSystemFirmwareTable(provider, id, None);
+        if size == 0 {
+            return Ok(None);
+        }
+        let mut buffer = vec![0u8; size as usize];
+        let written = GetSystemFirmwareTable(provider, id, Some(&mut buffer));
+        if written == 0 || written > size {
+            return Err(format!("GetSystemFirmwareTable('{}') failed", String::from_utf8_lossy(signature)));
+        }
+        buffer.truncate(written as usize);
+        Ok(Some(buffer))
+    }
+}
+
+/// Report from a `/sys` tree: ACPI tables, enabled units and thunderbolt DMA protection
+#[cfg(target_os = "linux")]
+pub fn read_linux_report(sys: &std::path::Path) -> IommuReport {
+    let tables = sys.join("firmware/acpi/tables");
+    let read = |signature: &str| match std::fs::read(tables.join(signature)) {
+        Ok(bytes) => Ok(Some(bytes)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(format!("Cannot read {}: {}", tables.join(signature).display(), e)),
+    };
+    let mut report = table_report(read("DMAR").and_then(|dmar| match dmar {
+        Some(dmar) => Ok(Some(dmar)),
+        None => read("IVRS"),
+    }));
+
+    // dmar0, ivhd0... (world-readable, unlike the tables)
+    report.active_units = std::fs::read_dir(sys.join("class/iommu"))
+        .ok()
+        .map(|entries| entries.flatten().count());
+
+    // Set when the kernel forces the IOMMU on for external PCIe (the opt-in honoured)
+    let domains = std::fs::read_dir(sys.join("bus/thunderbolt/devices")).into_iter().flatten().flatten();
+    for domain in domains.filter(|d| d.file_name().to_string_lossy().starts_with("domain")) {
+        if let Ok(value) = std::fs::read_to_string(domain.path().join("iommu_dma_protection")) {
+            let enforced = value.trim() == "1";
+            report.kernel_dma_protection = Some(report.kernel_dma_protection.unwrap_or(true) && enforced);
+        }
+    }
+
+    explain(&mut report);
+    report
+}
+
+fn word(data: &[u8], offset: usize) -> Option<u16> {
+    let bytes = data.get(offset..offset + 2)?;
+    Some(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+fn dword(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset + 4)?;
+    Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+fn qword(data: &[u8], offset: usize) -> Option<u64> {
+    let bytes = data.get(offset..offset + 8)?;
+    let mut value = [0u8; 8];
+    value.copy_from_slice(bytes);
+    Some(u64::from_le_bytes(value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    /// Tables written by tests/fixtures/acpi/source/tables.py
+    fn fixture(name: &str) -> Vec<u8> {
+        std::fs::read(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/acpi").join(name)).unwrap()
+    }
+
+    /// Rewrite the checksum after editing a table
+    fn fix_checksum(table: &mut [u8]) {
+        table[9] = 0;
+        table[9] = table.iter().fold(0u8, |sum, b| sum.wrapping_sub(*b));
+    }
+
+    fn scopes(report: &IommuReport) -> Vec<(usize, &str, &str)> {
+        report.device_scopes.iter().map(|s| (s.unit, s.kind.as_str(), s.device.as_str())).collect()
+    }
+
+    #[test]
+    fn dmar() {
+        let report = parse(&fixture("dmar.bin")).unwrap();
+        assert_eq!(report.table.as_deref(), Some("DMAR"));
+        assert_eq!(report.oem_id, "IRIS");
+        assert_eq!(report.address_width, 39);
+        assert!(report.interrupt_remapping);
+        assert!(!report.x2apic_opt_out);
+        assert!(report.dma_platform_opt_in);
+        assert_eq!(report.dma_protection_supported(), Some(true));
+        assert_eq!(report.reserved_regions, 1); // The ATSR is not one
+
+        let units: Vec<(&str, bool, usize)> = report.remapping_units.iter()
+            .map(|u| (u.register_base.as_str(), u.include_all, u.scopes)).collect();
+        assert_eq!(units, [("0xfed90000", false, 1), ("0xfed91000", true, 3)]);
+        assert!(report.remapping_units.iter().all(|u| u.segment == 0 && u.device_id.is_none()));
+        assert_eq!(scopes(&report), [
+            (0, "endpoint", "0000:00:02.0"),
+            (1, "ioapic", "0000:00:1e.7"),
+            (1, "hpet", "0000:00:1e.6"),
+            (1, "bridge", "0000:00:1c.0/00.0"),
+        ]);
+    }
+
+    #[test]
+    fn dmar_without_platform_opt_in() {
+        let mut table = fixture("dmar.bin");
+        table[37] = DMAR_INTR_REMAP | DMAR_X2APIC_OPT_OUT;
+        fix_checksum(&mut table);
+        let report = parse(&table).unwrap();
+        assert!(report.present());
+        assert!(report.x2apic_opt_out);
+        assert_eq!(report.dma_protection_supported(), Some(false));
+    }
+
+    #[test]
+    fn ivrs() {
+        let report = parse(&fixture("ivrs.bin")).unwrap();
+        assert_eq!(report.table.as_deref(), Some("IVRS"));
+        assert_eq!(report.address_width, 48);
+        assert!(report.dma_platform_opt_in);
+        assert!(report.interrupt_remapping); // IOAPIC behind the first unit
+        assert_eq!(report.reserved_regions, 1);
+
+        // The first IOMMU is listed as IVHD 0x10, 0x11 and 0x40: entries of the first block only
+        let units: Vec<(&str, Option<&str>, bool, usize)> = report.remapping_units.iter()
+            .map(|u| (u.register_base.as_str(), u.device_id.as_deref(), u.include_all, u.scopes)).collect();
+        assert_eq!(units, [
+            ("0xfeb80000", Some("0000:00:00.2"), true, 4),
+            ("0xfeb81000", Some("0000:40:00.2"), false, 3),
+        ]);
+        assert_eq!(scopes(&report), [
+            (0, "endpoint", "0000:00:01.0"),
+            (0, "range", "0000:01:00.0-0000:01:1f.7"),
+            (0, "ioapic", "0000:00:14.0"),
+            (0, "hpet", "0000:00:14.0"),
+            (1, "endpoint", "0000:41:00.0"),
+            (1, "namespace", "0000:00:14.5"),
+            (1, "endpoint", "0000:42:00.0"), // Read past the variable-length ACPI HID entry
+        ]);
+    }
+
+    #[test]
+    fn ivrs_without_dma_remap_support() {
+        let mut table = fixture("ivrs.bin");
+        table[36] &= !(IVINFO_DMA_REMAP as u8);
+        fix_checksum(&mut table);
+        assert_eq!(parse(&table).unwrap().dma_protection_supported(), Some(false));
+    }
+
+    #[test]
+    fn rejects_bad_checksum_and_length() {
+        let mut table = fixture("dmar.bin");
+        table[60] ^= 1;
+        assert_eq!(parse(&table).unwrap_err(), "DMAR checksum mismatch");
+
+        let table = fixture("dmar.bin");
+        assert_eq!(parse(&table[..100]).unwrap_err(), "ACPI table length 154 out of range (100 bytes read)");
+        assert_eq!(parse(&table[..20]).unwrap_err(), "ACPI table of 20 bytes");
+
+        let mut short = table.clone();
+        short[4..8].copy_from_slice(&20u32.to_le_bytes());
+        assert_eq!(parse(&short).unwrap_err(), "ACPI table length 20 out of range (154 bytes read)");
+
+        let mut facp = table.clone();
+        facp[0..4].copy_from_slice(b"FACP");
+        fix_checksum(&mut facp);
+        assert_eq!(parse(&facp).unwrap_err(), "Not an IOMMU table: FACP");
+
+        // Bytes past the header length are ignored
+        let mut padded = table.clone();
+        padded.extend_from_slice(&[0xff; 16]);
+        assert_eq!(parse(&padded).unwrap().remapping_units.len(), 2);
+    }
+
+    #[test]
+    fn truncated_dmar_structures() {
+        // Second DRHD claims more bytes than the table holds: parsing stops before it
+        let mut table = fixture("dmar.bin");
+        let second = STRUCTURES_OFFSET + 24;
+        assert_eq!(word(&table, second + 8), Some(0x1000));
+        table[second + 2] = 0xff;
+        fix_checksum(&mut table);
+        let report = parse(&table).unwrap();
+        assert_eq!(report.remapping_units.len(), 1);
+        assert_eq!(report.reserved_regions, 0);
+
+        // Last scope of the second DRHD longer than the structure: the scopes before it are kept
+        let mut table = fixture("dmar.bin");
+        table[second + 16 + 8 + 8 + 1] = 12;
+        fix_checksum(&mut table);
+        let report = parse(&table).unwrap();
+        assert_eq!(report.remapping_units[1].scopes, 2);
+        assert_eq!(report.reserved_regions, 1);
+
+        // Zero-length structure
+        let mut table = fixture("dmar.bin");
+        table[STRUCTURES_OFFSET + 2] = 0;
+        fix_checksum(&mut table);
+        assert!(!parse(&table).unwrap().present());
+    }
+
+    #[test]
+    fn truncated_ivhd_entries() {
+        // IVHD 0x10 cut inside its range entries: what was read before is kept
+        let mut table = fixture("ivrs.bin");
+        table[STRUCTURES_OFFSET + 2] = 24 + 12;
+        table.truncate(STRUCTURES_OFFSET + 24 + 12);
+        let length = table.len() as u32;
+        table[4..8].copy_from_slice(&length.to_le_bytes());
+        fix_checksum(&mut table);
+        let report = parse(&table).unwrap();
+        assert_eq!(scopes(&report), [(0, "endpoint", "0000:00:01.0")]);
+        assert!(report.remapping_units[0].include_all);
+        assert!(!report.interrupt_remapping);
+
+        // ACPI HID entry with a UID running past the block, and an unknown variable-length entry
+        let data = [0x02, 0x08, 0x00, 0x00, 0xf0, 0xa5, 0x00, 0x00];
+        assert_eq!(ivhd_entries(&data, 0, 0).0.len(), 1);
+        let mut hid = data.to_vec();
+        hid.extend_from_slice(&[0; 17]);
+        hid.push(200); // UID length
+        assert_eq!(ivhd_entries(&hid, 0, 0).0.len(), 1);
+        let unknown = [0x80, 0x00, 0x00, 0x00, 0x02, 0x08, 0x00, 0x00];
+        assert!(ivhd_entries(&unknown, 0, 0).0.is_empty());
+
+        // IVHD 0x40 shorter than its fixed part
+        let mut table = fixture("ivrs.bin");
+        let tail = table.split_off(STRUCTURES_OFFSET);
+        table.extend_from_slice(&[0x40, 0xb0, 32, 0]);
+        table.extend_from_slice(&[0; 28]);
+        table.extend_from_slice(&tail);
+        let length = table.len() as u32;
+        table[4..8].copy_from_slice(&length.to_le_bytes());
+        fix_checksum(&mut table);
+        assert_eq!(parse(&table).unwrap().remapping_units.len(), 2);
+    }
+
+    #[test]
+    fn explains_the_verdicts() {
+        let mut report = parse(&fixture("dmar.bin")).unwrap();
+        explain(&mut report);
+        assert_eq!(report.reasons, [
+            "DMAR table (Intel VT-d): 2 remapping unit(s), 4 device scope(s)",
+            "Firmware opts in to Kernel DMA Protection",
+            "Interrupt remapping supported",
+            "1 reserved memory region(s) left mapped for devices",
+        ]);
+
+        let mut report = table_report(Ok(None));
+        explain(&mut report);
+        assert_eq!(report.dma_protection_supported(), Some(false));
+        assert_eq!(report.reasons, ["No DMAR or IVRS table: IOMMU disabled in firmware"]);
+
+        let mut report = table_report(Err("denied".to_string()));
+        explain(&mut report);
+        assert_eq!(report.dma_protection_supported(), None);
+        assert_eq!(report.reasons, ["IOMMU table unavailable: denied"]);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn linux_report_from_sysfs() {
+        use crate::sysfs::fixture::Tree;
+
+        let tree = Tree::new("iommu-dmar.txt");
+        let report = read_linux_report(&tree.0);
+        assert_eq!(report.table.as_deref(), Some("DMAR"));
+        assert_eq!(report.remapping_units.len(), 2);
+        assert_eq!(report.active_units, Some(2));
+        assert_eq!(report.kernel_dma_protection, Some(true));
+        assert_eq!(report.reasons[4..], ["Kernel enabled 2 IOMMU unit(s)", "Kernel enforces DMA protection on external ports"]);
+
+        let tree = Tree::new("iommu-ivrs.txt");
+        let report = read_linux_report(&tree.0);
+        assert_eq!(report.table.as_deref(), Some("IVRS"));
+        assert_eq!(report.active_units, Some(2));
+        assert_eq!(report.kernel_dma_protection, Some(false)); // domain1 is not protected
+
+        let tree = Tree::new("iommu-unreadable.txt");
+        let report = read_linux_report(&tree.0);
+        assert_eq!(report.table, None);
+        assert!(report.error.as_deref().unwrap().starts_with("Cannot read "), "{:?}", report.error);
+        assert_eq!(report.dma_protection_supported(), None);
+        assert_eq!((report.active_units, report.kernel_dma_protection), (None, None));
+    }
+}
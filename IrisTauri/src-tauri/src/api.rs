@@ -66,6 +66,15 @@ pub struct HeartbeatRequest {
     pub verification: Option<serde_json::Value>,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WhitelistResponse {
+    pub success: bool,
+    #[serde(default)]
+    pub entries: Vec<serde_json::Value>,
+    #[serde(skip)]
+    pub etag: Option<String>, // From the ETag header
+}
+
 impl IrisApiClient {
     pub fn new(is_dev: bool) -> Self {
         let base_url = if is_dev {
@@ -122,6 +131,29 @@ impl IrisApiClient {
         token: Option<&str>,
         body: Option<serde_json::Value>,
     ) -> Result<T, String> {
+        let response = self.build_request(method, path, token, body)?
+            .send()
+            .await
+            .map_err(|e| format!("Request failed: {}", e))?;
+
+        let status = response.status();
+        let text = response.text().await.map_err(|e| format!("Failed to read response: {}", e))?;
+
+        if !status.is_success() {
+            return Err(format!("API error {}: {}", status.as_u16(), text));
+        }
+
+        serde_json::from_str(&text).map_err(|e| format!("Failed to parse response: {}", e))
+    }
+
+    /// Signed request builder (security headers + optional token and body)
+    fn build_request(
+        &self,
+        method: &str,
+        path: &str,
+        token: Option<&str>,
+        body: Option<serde_json::Value>,
+    ) -> Result<reqwest::RequestBuilder, String> {
         let timestamp = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .unwrap()
@@ -159,19 +191,7 @@ impl IrisApiClient {
             request = request.json(&b);
         }
 
-        let response = request
-            .send()
-            .await
-            .map_err(|e| format!("Request failed: {}", e))?;
-
-        let status = response.status();
-        let text = response.text().await.map_err(|e| format!("Failed to read response: {}", e))?;
-
-        if !status.is_success() {
-            return Err(format!("API error {}: {}", status.as_u16(), text));
-        }
-
-        serde_json::from_str(&text).map_err(|e| format!("Failed to parse response: {}", e))
+        Ok(request)
     }
 
     /// Verify Iris token
@@ -185,10 +205,16 @@ impl IrisApiClient {
         token: &str,
         hardware_id: &str,
         system_info: serde_json::Value,
+        fingerprint: serde_json::Value,
+        attestation: Option<serde_json::Value>,
+        churn: Option<serde_json::Value>,
     ) -> Result<ApiResponse<serde_json::Value>, String> {
         let body = serde_json::json!({
             "hardwareId": hardware_id,
-            "systemInfo": system_info
+            "systemInfo": system_info,
+            "fingerprint": fingerprint,
+            "attestation": attestation,
+            "churn": churn
         });
         self.request("POST", obfstr!("/iris/register-hardware"), Some(token), Some(body)).await
     }
@@ -209,6 +235,52 @@ impl IrisApiClient {
         self.request("POST", obfstr!("/iris/heartbeat"), Some(token), Some(body)).await
     }
 
+    /// Get the signed signature bundle if newer than `current_version`
+    pub async fn get_signatures(
+        &self,
+        token: &str,
+        current_version: u64,
+    ) -> Result<ApiResponse<serde_json::Value>, String> {
+        self.request("GET", &format!("{}?version={}", obfstr!("/iris/signatures"), current_version), Some(token), None).await
+    }
+
+    /// Get the active whitelist entries. Ok(None) when `etag` is still current.
+    pub async fn get_whitelist(
+        &self,
+        token: &str,
+        etag: Option<&str>,
+    ) -> Result<Option<WhitelistResponse>, String> {
+        let mut request = self.build_request("GET", obfstr!("/iris/whitelist/active"), Some(token), None)?;
+        if let Some(etag) = etag {
+            request = request.header(obfstr!("If-None-Match"), etag);
+        }
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| format!("Request failed: {}", e))?;
+
+        let status = response.status();
+        if status == reqwest::StatusCode::NOT_MODIFIED {
+            return Ok(None);
+        }
+
+        let etag = response.headers()
+            .get(reqwest::header::ETAG)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.to_string());
+        let text = response.text().await.map_err(|e| format!("Failed to read response: {}", e))?;
+
+        if !status.is_success() {
+            return Err(format!("API error {}: {}", status.as_u16(), text));
+        }
+
+        let mut parsed: WhitelistResponse = serde_json::from_str(&text)
+            .map_err(|e| format!("Failed to parse response: {}", e))?;
+        parsed.etag = etag;
+        Ok(Some(parsed))
+    }
+
     /// Test basic connectivity (no auth required)
     pub async fn health_check(&self) -> Result<bool, String> {
         let url = format!("{}{}", self.base_url, obfstr!("/iris/health"));
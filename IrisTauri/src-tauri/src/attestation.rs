@@ -0,0 +1,824 @@
+//! Attestation module - TPM-backed device identity and PCR quotes
+//!
+//! The attestation key (AK) is an ECC P-256 restricted signing key created as a
+//! primary object of the endorsement hierarchy. It never leaves the TPM (fixedTPM)
+//! and, being derived from the endorsement seed and a fixed template, comes back
+//! identical on every start: nothing is stored on disk, and only clearing the
+//! endorsement seed changes it. At registration the server receives the AK public
+//! area, the EK public area and the EK certificates from NV; afterwards it sends
+//! nonces that are answered with TPM2_Quote over the requested PCRs.
+//!
+//! The server also encrypts a secret to the EK for the AK name (MakeCredential);
+//! only the TPM holding both keys can recover it with TPM2_ActivateCredential,
+//! which binds the AK to an EK whose certificate chains to a manufacturer root.
+//!
+//! Commands go through the kernel resource manager (`/dev/tpmrm0`) on Linux and
+//! TBS on Windows; `Transport` lets swtpm or recorded responses stand in.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+const TPM_ST_NO_SESSIONS: u16 = 0x8001;
+const TPM_ST_SESSIONS: u16 = 0x8002;
+const TPM_RS_PW: u32 = 0x4000_0009;
+const TPM_RH_ENDORSEMENT: u32 = 0x4000_000B;
+const TPM_RH_NULL: u32 = 0x4000_0007;
+const TPM_SE_POLICY: u8 = 0x01;
+
+const TPM_CC_NV_READ: u32 = 0x0000_014E;
+const TPM_CC_CREATE_PRIMARY: u32 = 0x0000_0131;
+const TPM_CC_ACTIVATE_CREDENTIAL: u32 = 0x0000_0147;
+const TPM_CC_POLICY_SECRET: u32 = 0x0000_0151;
+const TPM_CC_QUOTE: u32 = 0x0000_0158;
+const TPM_CC_FLUSH_CONTEXT: u32 = 0x0000_0165;
+const TPM_CC_NV_READ_PUBLIC: u32 = 0x0000_0169;
+const TPM_CC_START_AUTH_SESSION: u32 = 0x0000_0176;
+const TPM_CC_PCR_READ: u32 = 0x0000_017E;
+
+const TPM_ALG_AES: u16 = 0x0006;
+const TPM_ALG_SHA256: u16 = 0x000B;
+const TPM_ALG_NULL: u16 = 0x0010;
+const TPM_ALG_ECDSA: u16 = 0x0018;
+const TPM_ALG_ECC: u16 = 0x0023;
+const TPM_ALG_CFB: u16 = 0x0043;
+const TPM_ECC_NIST_P256: u16 = 0x0003;
+
+/// fixedTPM | fixedParent | sensitiveDataOrigin | userWithAuth | restricted | sign
+const AK_ATTRIBUTES: u32 = 0x0005_0072;
+/// fixedTPM | fixedParent | sensitiveDataOrigin | adminWithPolicy | restricted | decrypt
+const EK_ATTRIBUTES: u32 = 0x0003_00B2;
+/// PolicySecret(TPM_RH_ENDORSEMENT) of the TCG EK templates
+const EK_POLICY: [u8; 32] = [
+    0x83, 0x71, 0x97, 0x67, 0x44, 0x84, 0xB3, 0xF8, 0x1A, 0x90, 0xCC, 0x8D, 0x46, 0xA5, 0xD7, 0x24,
+    0xFD, 0x52, 0xD7, 0x6E, 0x06, 0x52, 0x0B, 0x64, 0xF2, 0xA1, 0xDA, 0x1B, 0x33, 0x14, 0x69, 0xAA,
+];
+
+/// NV indices of the EK certificates (RSA 2048, ECC P-256), TCG EK Credential Profile
+const EK_CERT_INDICES: &[u32] = &[0x01C0_0002, 0x01C0_000A];
+/// First NV index of the EK certificate chain (intermediates, up to 0x01C001FF)
+const EK_CHAIN_FIRST_INDEX: u32 = 0x01C0_0100;
+const MAX_CHAIN_INDICES: u32 = 8;
+
+/// PCRs quoted when the server does not choose: firmware, option ROMs, boot manager, Secure Boot policy
+pub const DEFAULT_QUOTE_PCRS: &[u32] = &[0, 2, 4, 7];
+const MAX_PCR: u32 = 23;
+/// Bytes per TPM2_NV_Read (below TPM_PT_NV_BUFFER_MAX of every TPM)
+const NV_CHUNK: usize = 512;
+/// TPM2B_DATA holds at most a SHA-512 digest plus its algorithm id
+const MAX_NONCE: usize = 64;
+
+/// Something that executes TPM commands
+pub trait Transport {
+    fn submit(&mut self, command: &[u8]) -> Result<Vec<u8>, String>;
+}
+
+/// Public material sent at registration
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct AttestationIdentity {
+    pub ak_public: String,            // TPMT_PUBLIC of the AK (hex)
+    pub ak_name: String,              // nameAlg || SHA-256(TPMT_PUBLIC) (hex)
+    pub ek_public: Option<String>,    // TPMT_PUBLIC of the ECC EK, template L-2 (hex)
+    pub ek_certificates: Vec<String>, // DER certificates from NV (base64)
+    pub ek_chain: Vec<String>,        // Chain NV indices, may hold several DER certificates (base64)
+}
+
+/// Answer to a server nonce
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct AttestationQuote {
+    pub nonce: String,
+    pub quoted: String,    // TPMS_ATTEST signed by the AK (hex)
+    pub signature: String, // ECDSA r || s, 32 bytes each (hex)
+    pub pcrs: Vec<PcrReading>,
+    pub error: Option<String>,
+}
+
+/// Credential the server made for the AK name with the EK public key (TPM2_MakeCredential)
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct CredentialChallenge {
+    pub credential_blob: String, // TPMS_ID_OBJECT: integrity HMAC and encrypted secret (hex)
+    pub secret: String,          // Ephemeral ECDH point as TPMS_ECC_POINT (hex)
+}
+
+/// Answer to a credential challenge
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct CredentialActivation {
+    pub secret: Option<String>, // Recovered credential (hex)
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PcrReading {
+    pub index: u32,
+    pub sha256: String,
+}
+
+/// Identity of the local TPM
+pub fn identity() -> Result<AttestationIdentity, String> {
+    let mut tpm = open()?;
+    identity_with(tpm.as_mut())
+}
+
+/// Quote the PCRs with the AK of the local TPM; failures are reported in `error`
+pub fn quote(nonce: &str, pcrs: &[u32]) -> AttestationQuote {
+    let result = open().and_then(|mut tpm| quote_with(tpm.as_mut(), nonce, pcrs));
+    match result {
+        Ok(quote) => {
+            println!("[Attestation] Quoted PCRs {:?}", pcrs);
+            quote
+        }
+        Err(e) => {
+            println!("[Attestation] Quote failed: {}", e);
+            AttestationQuote { nonce: nonce.to_string(), error: Some(e), ..Default::default() }
+        }
+    }
+}
+
+/// Recover a server credential with the AK and EK of the local TPM; failures are reported in `error`
+pub fn activate_credential(challenge: &CredentialChallenge) -> CredentialActivation {
+    match open().and_then(|mut tpm| activate_credential_with(tpm.as_mut(), challenge)) {
+        Ok(secret) => {
+            println!("[Attestation] Credential activated");
+            CredentialActivation { secret: Some(secret), error: None }
+        }
+        Err(e) => {
+            println!("[Attestation] Credential activation failed: {}", e);
+            CredentialActivation { secret: None, error: Some(e) }
+        }
+    }
+}
+
+/// TPMT_PUBLIC of the ECC endorsement key of the local TPM
+pub fn ek_public() -> Result<Vec<u8>, String> {
+    let mut tpm = open()?;
+    ek_public_with(tpm.as_mut())
+}
+
+pub fn identity_with(tpm: &mut dyn Transport) -> Result<AttestationIdentity, String> {
+    let (handle, ak_public) = create_primary(tpm, &ak_template())?;
+    flush(tpm, handle);
+
+    let ek_public = match ek_public_with(tpm) {
+        Ok(public) => Some(hex::encode(public)),
+        Err(e) => {
+            println!("[Attestation] EK not available: {}", e);
+            None
+        }
+    };
+
+    let b64 = |der: Vec<u8>| {
+        use base64::Engine;
+        base64::engine::general_purpose::STANDARD.encode(der)
+    };
+    let ek_certificates = EK_CERT_INDICES.iter()
+        .filter_map(|index| read_nv(tpm, *index).ok())
+        .map(b64)
+        .collect();
+    let ek_chain = (EK_CHAIN_FIRST_INDEX..EK_CHAIN_FIRST_INDEX + MAX_CHAIN_INDICES)
+        .map_while(|index| read_nv(tpm, index).ok())
+        .map(b64)
+        .collect();
+
+    let identity = AttestationIdentity {
+        ak_name: hex::encode(object_name(&ak_public)),
+        ak_public: hex::encode(ak_public),
+        ek_public,
+        ek_certificates,
+        ek_chain,
+    };
+    println!("[Attestation] AK {} ({} EK certificates, {} chain entries)",
+             identity.ak_name, identity.ek_certificates.len(), identity.ek_chain.len());
+    Ok(identity)
+}
+
+pub fn quote_with(tpm: &mut dyn Transport, nonce: &str, pcrs: &[u32]) -> Result<AttestationQuote, String> {
+    let qualifying = hex::decode(nonce).map_err(|_| "Nonce is not hex".to_string())?;
+    if qualifying.is_empty() || qualifying.len() > MAX_NONCE {
+        return Err(format!("Nonce of {} bytes", qualifying.len()));
+    }
+    let selection = pcr_selection(pcrs)?;
+
+    let (handle, _) = create_primary(tpm, &ak_template())?;
+    let mut params = tpm2b(&qualifying);
+    params.extend_from_slice(&TPM_ALG_NULL.to_be_bytes()); // Scheme of the key
+    params.extend_from_slice(&selection);
+    let response = tpm.submit(&command(TPM_CC_QUOTE, &[handle], &[TPM_RS_PW], &params));
+    flush(tpm, handle);
+
+    let response = response?;
+    let mut reader = Reader::params(&response, 0, true)?;
+    let quoted = reader.tpm2b()?.to_vec();
+    let signature_algorithm = reader.u16()?;
+    let hash = reader.u16()?;
+    if signature_algorithm != TPM_ALG_ECDSA || hash != TPM_ALG_SHA256 {
+        return Err(format!("Unexpected signature scheme 0x{:04x}/0x{:04x}", signature_algorithm, hash));
+    }
+    let mut signature = left_pad(reader.tpm2b()?, 32);
+    signature.extend(left_pad(reader.tpm2b()?, 32));
+
+    let mut readings = Vec::new();
+    for index in sorted_pcrs(pcrs) {
+        readings.push(PcrReading { index, sha256: hex::encode(read_pcr(tpm, index)?) });
+    }
+
+    Ok(AttestationQuote {
+        nonce: nonce.to_string(),
+        quoted: hex::encode(quoted),
+        signature: hex::encode(signature),
+        pcrs: readings,
+        error: None,
+    })
+}
+
+pub fn ek_public_with(tpm: &mut dyn Transport) -> Result<Vec<u8>, String> {
+    let (handle, public) = create_primary(tpm, &ek_template())?;
+    flush(tpm, handle);
+    Ok(public)
+}
+
+pub fn activate_credential_with(tpm: &mut dyn Transport, challenge: &CredentialChallenge) -> Result<String, String> {
+    let blob = hex::decode(&challenge.credential_blob).map_err(|_| "Credential blob is not hex".to_string())?;
+    let secret = hex::decode(&challenge.secret).map_err(|_| "Encrypted secret is not hex".to_string())?;
+
+    let (ak, _) = create_primary(tpm, &ak_template())?;
+    let result = create_primary(tpm, &ek_template()).and_then(|(ek, _)| {
+        let result = activate(tpm, ak, ek, &blob, &secret);
+        flush(tpm, ek);
+        result
+    });
+    flush(tpm, ak);
+    result.map(hex::encode)
+}
+
+/// TPM2_ActivateCredential: the AK authorizes with its empty password, the EK with its policy
+fn activate(tpm: &mut dyn Transport, ak: u32, ek: u32, blob: &[u8], secret: &[u8]) -> Result<Vec<u8>, String> {
+    let session = ek_policy_session(tpm)?;
+    let mut params = tpm2b(blob);
+    params.extend_from_slice(&tpm2b(secret));
+    let response = match tpm.submit(&command(TPM_CC_ACTIVATE_CREDENTIAL, &[ak, ek], &[TPM_RS_PW, session], &params)) {
+        Ok(response) => response,
+        Err(e) => {
+            // The session is only consumed by a successful command
+            flush(tpm, session);
+            return Err(e);
+        }
+    };
+    Ok(Reader::params(&response, 0, true)?.tpm2b()?.to_vec())
+}
+
+/// Policy session satisfying the EK policy, PolicySecret(TPM_RH_ENDORSEMENT)
+fn ek_policy_session(tpm: &mut dyn Transport) -> Result<u32, String> {
+    let mut params = tpm2b(&rand::random::<[u8; 16]>()); // nonceCaller
+    params.extend_from_slice(&[0x00, 0x00]); // encryptedSalt
+    params.push(TPM_SE_POLICY);
+    params.extend_from_slice(&TPM_ALG_NULL.to_be_bytes()); // symmetric
+    params.extend_from_slice(&TPM_ALG_SHA256.to_be_bytes()); // authHash
+    let response = tpm.submit(&command(TPM_CC_START_AUTH_SESSION, &[TPM_RH_NULL, TPM_RH_NULL], &[], &params))?;
+    let session = Reader::at(&response, 10).u32()?;
+
+    let mut params = vec![0x00; 6]; // Empty nonceTPM, cpHashA and policyRef
+    params.extend_from_slice(&0i32.to_be_bytes()); // expiration
+    if let Err(e) = tpm.submit(&command(TPM_CC_POLICY_SECRET, &[TPM_RH_ENDORSEMENT, session], &[TPM_RS_PW], &params)) {
+        flush(tpm, session);
+        return Err(e);
+    }
+    Ok(session)
+}
+
+/// TPM2_CreatePrimary in the endorsement hierarchy: (transient handle, TPMT_PUBLIC)
+fn create_primary(tpm: &mut dyn Transport, template: &[u8]) -> Result<(u32, Vec<u8>), String> {
+    let mut params = vec![0x00, 0x04, 0x00, 0x00, 0x00, 0x00]; // Empty userAuth and data
+    params.extend_from_slice(&tpm2b(template));
+    params.extend_from_slice(&[0x00, 0x00]); // outsideInfo
+    params.extend_from_slice(&0u32.to_be_bytes()); // creationPCR
+
+    let response = tpm.submit(&command(TPM_CC_CREATE_PRIMARY, &[TPM_RH_ENDORSEMENT], &[TPM_RS_PW], &params))?;
+    let handle = Reader::at(&response, 10).u32()?;
+    let public = Reader::params(&response, 1, true)?.tpm2b()?.to_vec();
+    Ok((handle, public))
+}
+
+fn flush(tpm: &mut dyn Transport, handle: u32) {
+    if let Err(e) = tpm.submit(&command(TPM_CC_FLUSH_CONTEXT, &[], &[], &handle.to_be_bytes())) {
+        println!("[Attestation] Flush of 0x{:08x} failed: {}", handle, e);
+    }
+}
+
+/// Content of an NV index (read with its own empty auth, as EK certificate indices allow)
+fn read_nv(tpm: &mut dyn Transport, index: u32) -> Result<Vec<u8>, String> {
+    let response = tpm.submit(&command(TPM_CC_NV_READ_PUBLIC, &[index], &[], &[]))?;
+    let mut public = Reader::params(&response, 0, false)?;
+    let mut nv_public = Reader::new(public.tpm2b()?);
+    nv_public.skip(4 + 2 + 4)?; // nvIndex, nameAlg, attributes
+    nv_public.tpm2b()?; // authPolicy
+    let size = nv_public.u16()? as usize;
+
+    let mut data = Vec::with_capacity(size);
+    while data.len() < size {
+        let chunk = (size - data.len()).min(NV_CHUNK);
+        let mut params = (chunk as u16).to_be_bytes().to_vec();
+        params.extend_from_slice(&(data.len() as u16).to_be_bytes());
+        let response = tpm.submit(&command(TPM_CC_NV_READ, &[index, index], &[TPM_RS_PW], &params))?;
+        let bytes = Reader::params(&response, 0, true)?.tpm2b()?.to_vec();
+        if bytes.is_empty() {
+            return Err(format!("Empty read of NV 0x{:08x}", index));
+        }
+        data.extend(bytes);
+    }
+    Ok(data)
+}
+
+/// SHA-256 bank value of one PCR
+fn read_pcr(tpm: &mut dyn Transport, index: u32) -> Result<Vec<u8>, String> {
+    let response = tpm.submit(&command(TPM_CC_PCR_READ, &[], &[], &pcr_selection(&[index])?))?;
+    let mut reader = Reader::params(&response, 0, false)?;
+    reader.skip(4)?; // pcrUpdateCounter
+    let selections = reader.u32()?;
+    for _ in 0..selections {
+        reader.skip(2)?;
+        let size = reader.u8()? as usize;
+        reader.skip(size)?;
+    }
+    if reader.u32()? != 1 {
+        return Err(format!("PCR {} not returned", index));
+    }
+    Ok(reader.tpm2b()?.to_vec())
+}
+
+/// Restricted ECDSA P-256 signing key
+fn ak_template() -> Vec<u8> {
+    let mut public = Vec::new();
+    public.extend_from_slice(&TPM_ALG_ECC.to_be_bytes());
+    public.extend_from_slice(&TPM_ALG_SHA256.to_be_bytes());
+    public.extend_from_slice(&AK_ATTRIBUTES.to_be_bytes());
+    public.extend_from_slice(&[0x00, 0x00]); // authPolicy
+    public.extend_from_slice(&TPM_ALG_NULL.to_be_bytes()); // symmetric
+    public.extend_from_slice(&TPM_ALG_ECDSA.to_be_bytes());
+    public.extend_from_slice(&TPM_ALG_SHA256.to_be_bytes());
+    public.extend_from_slice(&TPM_ECC_NIST_P256.to_be_bytes());
+    public.extend_from_slice(&TPM_ALG_NULL.to_be_bytes()); // kdf
+    public.extend_from_slice(&[0x00, 0x00, 0x00, 0x00]); // unique x, y
+    public
+}
+
+/// ECC EK, TCG template L-2 (matches the certificate at 0x01C0000A)
+fn ek_template() -> Vec<u8> {
+    let mut public = Vec::new();
+    public.extend_from_slice(&TPM_ALG_ECC.to_be_bytes());
+    public.extend_from_slice(&TPM_ALG_SHA256.to_be_bytes());
+    public.extend_from_slice(&EK_ATTRIBUTES.to_be_bytes());
+    public.extend_from_slice(&tpm2b(&EK_POLICY));
+    public.extend_from_slice(&TPM_ALG_AES.to_be_bytes());
+    public.extend_from_slice(&128u16.to_be_bytes());
+    public.extend_from_slice(&TPM_ALG_CFB.to_be_bytes());
+    public.extend_from_slice(&TPM_ALG_NULL.to_be_bytes()); // scheme
+    public.extend_from_slice(&TPM_ECC_NIST_P256.to_be_bytes());
+    public.extend_from_slice(&TPM_ALG_NULL.to_be_bytes()); // kdf
+    public.extend_from_slice(&tpm2b(&[0u8; 32])); // unique x
+    public.extend_from_slice(&tpm2b(&[0u8; 32])); // unique y
+    public
+}
+
+/// TPML_PCR_SELECTION of the SHA-256 bank
+fn pcr_selection(pcrs: &[u32]) -> Result<Vec<u8>, String> {
+    let mut bitmap = [0u8; 3];
+    for &pcr in pcrs {
+        if pcr > MAX_PCR {
+            return Err(format!("Invalid PCR {}", pcr));
+        }
+        bitmap[(pcr / 8) as usize] |= 1 << (pcr % 8);
+    }
+    let mut selection = 1u32.to_be_bytes().to_vec();
+    selection.extend_from_slice(&TPM_ALG_SHA256.to_be_bytes());
+    selection.push(bitmap.len() as u8);
+    selection.extend_from_slice(&bitmap);
+    Ok(selection)
+}
+
+fn sorted_pcrs(pcrs: &[u32]) -> Vec<u32> {
+    let mut sorted = pcrs.to_vec();
+    sorted.sort_unstable();
+    sorted.dedup();
+    sorted
+}
+
+/// Name of an object: nameAlg || SHA-256(TPMT_PUBLIC)
+fn object_name(public: &[u8]) -> Vec<u8> {
+    let mut name = TPM_ALG_SHA256.to_be_bytes().to_vec();
+    name.extend_from_slice(&Sha256::digest(public));
+    name
+}
+
+/// Command authorizing its first handles with `sessions`: the empty password
+/// (TPM_RS_PW) or a policy session, used once (continueSession clear)
+fn command(code: u32, handles: &[u32], sessions: &[u32], params: &[u8]) -> Vec<u8> {
+    let tag = if sessions.is_empty() { TPM_ST_NO_SESSIONS } else { TPM_ST_SESSIONS };
+    let mut body = Vec::new();
+    for handle in handles {
+        body.extend_from_slice(&handle.to_be_bytes());
+    }
+    if !sessions.is_empty() {
+        body.extend_from_slice(&((sessions.len() * 9) as u32).to_be_bytes());
+        for session in sessions {
+            body.extend_from_slice(&session.to_be_bytes());
+            body.extend_from_slice(&[0x00, 0x00, 0x00, 0x00, 0x00]); // nonce, attributes, hmac
+        }
+    }
+    body.extend_from_slice(params);
+
+    let mut command = tag.to_be_bytes().to_vec();
+    command.extend_from_slice(&((10 + body.len()) as u32).to_be_bytes());
+    command.extend_from_slice(&code.to_be_bytes());
+    command.extend(body);
+    command
+}
+
+fn tpm2b(data: &[u8]) -> Vec<u8> {
+    let mut buffer = (data.len() as u16).to_be_bytes().to_vec();
+    buffer.extend_from_slice(data);
+    buffer
+}
+
+fn left_pad(value: &[u8], size: usize) -> Vec<u8> {
+    let value = &value[value.len().saturating_sub(size)..];
+    let mut padded = vec![0u8; size - value.len()];
+    padded.extend_from_slice(value);
+    padded
+}
+
+/// Validate the response header: size matches, response code is success
+pub fn check_response(response: Vec<u8>) -> Result<Vec<u8>, String> {
+    if response.len() < 10 || u32::from_be_bytes([response[2], response[3], response[4], response[5]]) as usize != response.len() {
+        return Err(format!("Malformed TPM response ({} bytes)", response.len()));
+    }
+    match u32::from_be_bytes([response[6], response[7], response[8], response[9]]) {
+        0 => Ok(response),
+        rc => Err(format!("TPM error 0x{:03x}", rc)),
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl Transport for crate::tpm::Connection {
+    fn submit(&mut self, command: &[u8]) -> Result<Vec<u8>, String> {
+        self.transact(command)
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn open() -> Result<Box<dyn Transport>, String> {
+    Ok(Box::new(crate::tpm::Connection::open(std::path::Path::new("/dev/tpmrm0"))?))
+}
+
+#[cfg(target_os = "windows")]
+fn open() -> Result<Box<dyn Transport>, String> {
+    Ok(Box::new(TbsContext::open()?))
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+fn open() -> Result<Box<dyn Transport>, String> {
+    Err("TPM attestation not supported on this platform".to_string())
+}
+
+/// TBS_CONTEXT_PARAMS2
+#[cfg(target_os = "windows")]
+#[repr(C)]
+struct TbsContextParams2 {
+    version: u32,
+    flags: u32,
+}
+
+#[cfg(target_os = "windows")]
+#[link(name = "tbs")]
+extern "system" {
+    fn Tbsi_Context_Create(params: *const TbsContextParams2, context: *mut *mut std::ffi::c_void) -> u32;
+    fn Tbsip_Submit_Command(
+        context: *mut std::ffi::c_void,
+        locality: u32,
+        priority: u32,
+        command: *const u8,
+        command_size: u32,
+        result: *mut u8,
+        result_size: *mut u32,
+    ) -> u32;
+    fn Tbsip_Context_Close(context: *mut std::ffi::c_void) -> u32;
+}
+
+/// TPM Base Services context (transient objects are flushed when it is closed)
+#[cfg(target_os = "windows")]
+struct TbsContext {
+    handle: *mut std::ffi::c_void,
+}
+
+#[cfg(target_os = "windows")]
+impl TbsContext {
+    fn open() -> Result<Self, String> {
+        const TBS_CONTEXT_VERSION_TWO: u32 = 2;
+        const INCLUDE_TPM20: u32 = 1 << 2;
+        let params = TbsContextParams2 { version: TBS_CONTEXT_VERSION_TWO, flags: INCLUDE_TPM20 };
+        let mut handle = std::ptr::null_mut();
+        match unsafe { Tbsi_Context_Create(&params, &mut handle) } {
+            0 => Ok(TbsContext { handle }),
+            rc => Err(format!("Tbsi_Context_Create failed: 0x{:08x}", rc)),
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+impl Transport for TbsContext {
+    fn submit(&mut self, command: &[u8]) -> Result<Vec<u8>, String> {
+        const TBS_COMMAND_LOCALITY_ZERO: u32 = 0;
+        const TBS_COMMAND_PRIORITY_NORMAL: u32 = 200;
+        let mut response = vec![0u8; 4096];
+        let mut size = response.len() as u32;
+        let rc = unsafe {
+            Tbsip_Submit_Command(
+                self.handle,
+                TBS_COMMAND_LOCALITY_ZERO,
+                TBS_COMMAND_PRIORITY_NORMAL,
+                command.as_ptr(),
+                command.len() as u32,
+                response.as_mut_ptr(),
+                &mut size,
+            )
+        };
+        if rc != 0 {
+            return Err(format!("Tbsip_Submit_Command failed: 0x{:08x}", rc));
+        }
+        response.truncate(size as usize);
+        check_response(response)
+    }
+}
+
+#[cfg(target_os = "windows")]
+impl Drop for TbsContext {
+    fn drop(&mut self) {
+        unsafe {
+            Tbsip_Context_Close(self.handle);
+        }
+    }
+}
+
+/// Bounds-checked big-endian reader over a response
+struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Reader { data, offset: 0 }
+    }
+
+    fn at(data: &'a [u8], offset: usize) -> Self {
+        Reader { data, offset }
+    }
+
+    /// Parameter area after the header, `handles` output handles and, for a
+    /// session command, the parameterSize field
+    fn params(response: &'a [u8], handles: usize, sessions: bool) -> Result<Self, String> {
+        let mut reader = Reader::at(response, 10 + handles * 4);
+        if sessions {
+            let size = reader.u32()? as usize;
+            let start = reader.offset;
+            let end = start.checked_add(size).filter(|&end| end <= response.len()).ok_or("Truncated parameters")?;
+            return Ok(Reader::new(&response[start..end]));
+        }
+        Ok(reader)
+    }
+
+    fn bytes(&mut self, count: usize) -> Result<&'a [u8], String> {
+        let end = self.offset.checked_add(count)
+            .filter(|&end| end <= self.data.len())
+            .ok_or_else(|| format!("Truncated TPM response at {}", self.offset))?;
+        let bytes = &self.data[self.offset..end];
+        self.offset = end;
+        Ok(bytes)
+    }
+
+    fn skip(&mut self, count: usize) -> Result<(), String> {
+        self.bytes(count).map(|_| ())
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        self.bytes(2).map(|b| u16::from_be_bytes([b[0], b[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        self.bytes(4).map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn tpm2b(&mut self) -> Result<&'a [u8], String> {
+        let size = self.u16()? as usize;
+        self.bytes(size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AK_HANDLE: u32 = 0x8000_0000;
+    const EK_HANDLE: u32 = 0x8000_0001;
+    const SESSION_HANDLE: u32 = 0x0300_0000;
+    const TPM_RC_INTEGRITY: u32 = 0x0000_009F;
+
+    fn be_u32(bytes: &[u8]) -> u32 {
+        u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+    }
+
+    /// Response with an optional output handle and, for session commands, the
+    /// parameterSize field and one empty session
+    fn response(sessions: bool, handle: Option<u32>, params: &[u8]) -> Vec<u8> {
+        let mut body = Vec::new();
+        if let Some(handle) = handle {
+            body.extend_from_slice(&handle.to_be_bytes());
+        }
+        if sessions {
+            body.extend_from_slice(&(params.len() as u32).to_be_bytes());
+        }
+        body.extend_from_slice(params);
+        if sessions {
+            body.extend_from_slice(&[0x00, 0x00, 0x00, 0x00, 0x00]);
+        }
+        let tag = if sessions { TPM_ST_SESSIONS } else { TPM_ST_NO_SESSIONS };
+        let mut response = tag.to_be_bytes().to_vec();
+        response.extend_from_slice(&((10 + body.len()) as u32).to_be_bytes());
+        response.extend_from_slice(&[0, 0, 0, 0]);
+        response.extend(body);
+        response
+    }
+
+    /// Answers the commands of a credential activation and records them;
+    /// `fail` makes one command code return TPM_RC_INTEGRITY
+    struct Scripted {
+        commands: Vec<Vec<u8>>,
+        fail: Option<u32>,
+    }
+
+    impl Transport for Scripted {
+        fn submit(&mut self, command: &[u8]) -> Result<Vec<u8>, String> {
+            self.commands.push(command.to_vec());
+            let code = be_u32(&command[6..10]);
+            if self.fail == Some(code) {
+                let mut error = response(false, None, &[]);
+                error[6..10].copy_from_slice(&TPM_RC_INTEGRITY.to_be_bytes());
+                return check_response(error);
+            }
+            let response = match code {
+                TPM_CC_CREATE_PRIMARY => {
+                    let is_ek = command.windows(EK_POLICY.len()).any(|w| w == EK_POLICY);
+                    let template = if is_ek { ek_template() } else { ak_template() };
+                    response(true, Some(if is_ek { EK_HANDLE } else { AK_HANDLE }), &tpm2b(&template))
+                }
+                TPM_CC_START_AUTH_SESSION => response(false, Some(SESSION_HANDLE), &tpm2b(&[0xAA; 16])),
+                TPM_CC_POLICY_SECRET => response(true, None, &[0x00, 0x00, 0x80, 0x21, 0x40, 0x00, 0x00, 0x07, 0x00, 0x00]),
+                TPM_CC_ACTIVATE_CREDENTIAL => response(true, None, &tpm2b(b"credential")),
+                TPM_CC_FLUSH_CONTEXT => response(false, None, &[]),
+                _ => panic!("Unexpected command 0x{:x}", code),
+            };
+            check_response(response)
+        }
+    }
+
+    impl Scripted {
+        fn codes(&self) -> Vec<u32> {
+            self.commands.iter().map(|command| be_u32(&command[6..10])).collect()
+        }
+
+        fn flushed(&self) -> Vec<u32> {
+            self.commands.iter()
+                .filter(|command| be_u32(&command[6..10]) == TPM_CC_FLUSH_CONTEXT)
+                .map(|command| be_u32(&command[10..14]))
+                .collect()
+        }
+    }
+
+    fn challenge() -> CredentialChallenge {
+        CredentialChallenge { credential_blob: "0020".to_string() + &"11".repeat(32) + "beef", secret: "0002abcd".to_string() }
+    }
+
+    #[test]
+    fn activates_credential_with_ek_policy() {
+        let mut tpm = Scripted { commands: Vec::new(), fail: None };
+        let secret = activate_credential_with(&mut tpm, &challenge()).unwrap();
+        assert_eq!(secret, hex::encode(b"credential"));
+        assert_eq!(tpm.codes(), vec![
+            TPM_CC_CREATE_PRIMARY, TPM_CC_CREATE_PRIMARY, TPM_CC_START_AUTH_SESSION, TPM_CC_POLICY_SECRET,
+            TPM_CC_ACTIVATE_CREDENTIAL, TPM_CC_FLUSH_CONTEXT, TPM_CC_FLUSH_CONTEXT,
+        ]);
+        // The consumed policy session is not flushed again
+        assert_eq!(tpm.flushed(), vec![EK_HANDLE, AK_HANDLE]);
+
+        // Unsalted, unbound SHA-256 policy session
+        let start = &tpm.commands[2];
+        assert_eq!((be_u32(&start[10..14]), be_u32(&start[14..18])), (TPM_RH_NULL, TPM_RH_NULL));
+        assert_eq!(&start[18..20], &[0x00, 0x10]);
+        assert_eq!(&start[36..], &[0x00, 0x00, TPM_SE_POLICY, 0x00, 0x10, 0x00, 0x0B]);
+
+        // PolicySecret with the endorsement hierarchy's empty password
+        let policy = &tpm.commands[3];
+        assert_eq!((be_u32(&policy[10..14]), be_u32(&policy[14..18])), (TPM_RH_ENDORSEMENT, SESSION_HANDLE));
+        assert_eq!(be_u32(&policy[22..26]), TPM_RS_PW);
+
+        // AK with the password session, EK with the policy session
+        let activate = &tpm.commands[4];
+        assert_eq!((be_u32(&activate[10..14]), be_u32(&activate[14..18])), (AK_HANDLE, EK_HANDLE));
+        assert_eq!(be_u32(&activate[18..22]), 18);
+        assert_eq!(be_u32(&activate[22..26]), TPM_RS_PW);
+        assert_eq!(be_u32(&activate[31..35]), SESSION_HANDLE);
+        let mut params = tpm2b(&hex::decode(challenge().credential_blob).unwrap());
+        params.extend(tpm2b(&[0x00, 0x02, 0xAB, 0xCD]));
+        assert_eq!(&activate[40..], &params[..]);
+    }
+
+    #[test]
+    fn failed_activation_flushes_everything() {
+        let mut tpm = Scripted { commands: Vec::new(), fail: Some(TPM_CC_ACTIVATE_CREDENTIAL) };
+        assert_eq!(activate_credential_with(&mut tpm, &challenge()).unwrap_err(), "TPM error 0x09f");
+        assert_eq!(tpm.flushed(), vec![SESSION_HANDLE, EK_HANDLE, AK_HANDLE]);
+
+        let mut tpm = Scripted { commands: Vec::new(), fail: Some(TPM_CC_POLICY_SECRET) };
+        assert!(activate_credential_with(&mut tpm, &challenge()).is_err());
+        assert_eq!(tpm.flushed(), vec![SESSION_HANDLE, EK_HANDLE, AK_HANDLE]);
+        assert!(!tpm.codes().contains(&TPM_CC_ACTIVATE_CREDENTIAL));
+    }
+
+    #[test]
+    fn rejects_malformed_challenge() {
+        let mut tpm = Scripted { commands: Vec::new(), fail: None };
+        let challenge = CredentialChallenge { credential_blob: "zz".to_string(), secret: String::new() };
+        assert!(activate_credential_with(&mut tpm, &challenge).is_err());
+        assert!(tpm.commands.is_empty());
+    }
+
+    #[test]
+    fn templates_name_the_tcg_keys() {
+        // Restricted signing AK, L-2 EK with the PolicySecret(TPM_RH_ENDORSEMENT) policy
+        assert_eq!(be_u32(&ak_template()[4..8]), AK_ATTRIBUTES);
+        assert_eq!(be_u32(&ek_template()[4..8]), EK_ATTRIBUTES);
+        assert_eq!(&ek_template()[10..42], &EK_POLICY);
+        assert_eq!(object_name(&ak_template())[..2], TPM_ALG_SHA256.to_be_bytes());
+    }
+
+    /// TPM2_LoadExternal of the EK public area, then TPM2_MakeCredential, as the server does in software
+    #[cfg(target_os = "linux")]
+    fn make_credential(tpm: &mut dyn Transport, ek_public: &[u8], ak_name: &[u8], credential: &[u8]) -> CredentialChallenge {
+        const TPM_CC_LOAD_EXTERNAL: u32 = 0x0000_0167;
+        const TPM_CC_MAKE_CREDENTIAL: u32 = 0x0000_0168;
+
+        let mut params = vec![0x00, 0x00]; // No private part
+        params.extend(tpm2b(ek_public));
+        params.extend_from_slice(&TPM_RH_NULL.to_be_bytes());
+        let response = tpm.submit(&command(TPM_CC_LOAD_EXTERNAL, &[], &[], &params)).unwrap();
+        let handle = Reader::at(&response, 10).u32().unwrap();
+
+        let mut params = tpm2b(credential);
+        params.extend(tpm2b(ak_name));
+        let response = tpm.submit(&command(TPM_CC_MAKE_CREDENTIAL, &[handle], &[], &params));
+        flush(tpm, handle);
+        let response = response.unwrap();
+        let mut reader = Reader::params(&response, 0, false).unwrap();
+        CredentialChallenge {
+            credential_blob: hex::encode(reader.tpm2b().unwrap()),
+            secret: hex::encode(reader.tpm2b().unwrap()),
+        }
+    }
+
+    /// Needs a swtpm-backed device, see `tpm::tests::swtpm_manufacturer`:
+    /// `IRIS_TEST_TPM=/dev/tpmrmN cargo test -- --ignored swtpm`
+    #[test]
+    #[ignore]
+    #[cfg(target_os = "linux")]
+    fn swtpm_identity_quote_and_activation() {
+        let device = std::env::var("IRIS_TEST_TPM").expect("IRIS_TEST_TPM is not set");
+        let mut tpm = crate::tpm::Connection::open(std::path::Path::new(&device)).unwrap();
+
+        let identity = identity_with(&mut tpm).unwrap();
+        let ak_public = hex::decode(&identity.ak_public).unwrap();
+        assert_eq!(identity.ak_name, hex::encode(object_name(&ak_public)));
+        // Same template, same endorsement seed: same key on every start
+        assert_eq!(identity_with(&mut tpm).unwrap().ak_name, identity.ak_name);
+
+        let nonce = hex::encode([0x5A; 32]);
+        let quote = quote_with(&mut tpm, &nonce, DEFAULT_QUOTE_PCRS).unwrap();
+        assert_eq!(quote.signature.len(), 128);
+        assert_eq!(quote.pcrs.iter().map(|pcr| pcr.index).collect::<Vec<_>>(), DEFAULT_QUOTE_PCRS);
+
+        let ek_public = hex::decode(identity.ek_public.expect("No ECC EK")).unwrap();
+        let credential = [0x42; 32];
+        let challenge = make_credential(&mut tpm, &ek_public, &hex::decode(&identity.ak_name).unwrap(), &credential);
+        assert_eq!(activate_credential_with(&mut tpm, &challenge).unwrap(), hex::encode(credential));
+
+        // A credential made for another name does not activate
+        let challenge = make_credential(&mut tpm, &ek_public, &object_name(&ek_public), &credential);
+        assert!(activate_credential_with(&mut tpm, &challenge).is_err());
+    }
+}
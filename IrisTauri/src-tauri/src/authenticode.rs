@@ -0,0 +1,1122 @@
+//! Authenticode module - portable verification of embedded PE signatures
+//!
+//! Parses the PKCS#7 SignedData from the certificate table, recomputes the image
+//! digest, checks the signer's signature over the authenticated attributes and
+//! walks the embedded certificate chain up to a root, embedded or taken from the
+//! root certificates bundled in `roots/`. `root_key` is the SHA-256 of that root's
+//! SubjectPublicKeyInfo; a chain is only trusted when it is pinned (see
+//! `hardware::TRUSTED_ROOT_KEYS`, extended by the signature bundle).
+//!
+//! The certificates below the root must be valid at the signing time: the time of
+//! a timestamp countersignature (RFC 3161 or PKCS#9) whose signature verifies,
+//! otherwise now. The timestamping authority's own chain is not walked.
+//!
+//! On Windows, WinVerifyTrust must also accept the file and supplies the root of
+//! its own chain, and Microsoft's product roots are recognized with the
+//! Microsoft root chain policy. Files without an embedded signature are looked
+//! up in the system catalogs before being reported unsigned.
+
+use crate::pe;
+use crate::signatures::HashSignature;
+use ring::{digest, signature};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+/// Files larger than this are not verified
+const MAX_FILE_SIZE: u64 = 128 * 1024 * 1024;
+
+const MAX_CHAIN_LENGTH: usize = 8;
+const MAX_CACHE_ENTRIES: usize = 2048;
+
+/// Public roots of code-signing chains (DER). Signatures rarely embed their
+/// root, so these supply the key that verifies the top certificate; trust still
+/// depends on the pinned key hashes.
+const ROOT_CERTIFICATES: &[&[u8]] = &[
+    include_bytes!("../roots/baltimore-cybertrust-root.der"),
+    include_bytes!("../roots/certum-trusted-network-ca-2.der"),
+    include_bytes!("../roots/certum-trusted-network-ca.der"),
+    include_bytes!("../roots/comodo-aaa-services-root.der"),
+    include_bytes!("../roots/comodo-rsa-certification-authority.der"),
+    include_bytes!("../roots/digicert-assured-id-root-ca.der"),
+    include_bytes!("../roots/digicert-global-root-ca.der"),
+    include_bytes!("../roots/digicert-global-root-g2.der"),
+    include_bytes!("../roots/digicert-high-assurance-ev-root-ca.der"),
+    include_bytes!("../roots/digicert-trusted-root-g4.der"),
+    include_bytes!("../roots/entrust-root-certification-authority-g2.der"),
+    include_bytes!("../roots/entrust-root-certification-authority.der"),
+    include_bytes!("../roots/globalsign-root-ca-r3.der"),
+    include_bytes!("../roots/globalsign-root-ca-r6.der"),
+    include_bytes!("../roots/globalsign-root-ca.der"),
+    include_bytes!("../roots/globalsign-root-r46.der"),
+    include_bytes!("../roots/go-daddy-root-certificate-authority-g2.der"),
+    include_bytes!("../roots/microsoft-ecc-root-certificate-authority-2017.der"),
+    include_bytes!("../roots/microsoft-rsa-root-certificate-authority-2017.der"),
+    include_bytes!("../roots/quovadis-root-ca-2.der"),
+    include_bytes!("../roots/sslcom-ev-root-certification-authority-rsa-r2.der"),
+    include_bytes!("../roots/sslcom-root-certification-authority-ecc.der"),
+    include_bytes!("../roots/sslcom-root-certification-authority-rsa.der"),
+    include_bytes!("../roots/starfield-root-certificate-authority-g2.der"),
+    include_bytes!("../roots/usertrust-ecc-certification-authority.der"),
+    include_bytes!("../roots/usertrust-rsa-certification-authority.der"),
+];
+
+// DER encoded OID contents
+const OID_SIGNED_DATA: &[u8] = &[0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x07, 0x02];
+const OID_SPC_INDIRECT_DATA: &[u8] = &[0x2B, 0x06, 0x01, 0x04, 0x01, 0x82, 0x37, 0x02, 0x01, 0x04];
+const OID_MESSAGE_DIGEST: &[u8] = &[0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x09, 0x04];
+const OID_SIGNING_TIME: &[u8] = &[0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x09, 0x05];
+const OID_COUNTER_SIGNATURE: &[u8] = &[0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x09, 0x06];
+const OID_TST_INFO: &[u8] = &[0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x09, 0x10, 0x01, 0x04];
+const OID_RFC3161_COUNTER_SIGNATURE: &[u8] = &[0x2B, 0x06, 0x01, 0x04, 0x01, 0x82, 0x37, 0x03, 0x03, 0x01];
+const OID_SHA1: &[u8] = &[0x2B, 0x0E, 0x03, 0x02, 0x1A];
+const OID_SHA256: &[u8] = &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01];
+const OID_SHA384: &[u8] = &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x02];
+const OID_SHA512: &[u8] = &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x03];
+const OID_RSA: &[u8] = &[0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x01, 0x01];
+const OID_SHA1_RSA: &[u8] = &[0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x01, 0x05];
+const OID_SHA256_RSA: &[u8] = &[0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x01, 0x0B];
+const OID_SHA384_RSA: &[u8] = &[0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x01, 0x0C];
+const OID_SHA512_RSA: &[u8] = &[0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x01, 0x0D];
+const OID_EC_PUBLIC_KEY: &[u8] = &[0x2A, 0x86, 0x48, 0xCE, 0x3D, 0x02, 0x01];
+const OID_ECDSA_SHA256: &[u8] = &[0x2A, 0x86, 0x48, 0xCE, 0x3D, 0x04, 0x03, 0x02];
+const OID_ECDSA_SHA384: &[u8] = &[0x2A, 0x86, 0x48, 0xCE, 0x3D, 0x04, 0x03, 0x03];
+const OID_P256: &[u8] = &[0x2A, 0x86, 0x48, 0xCE, 0x3D, 0x03, 0x01, 0x07];
+const OID_P384: &[u8] = &[0x2B, 0x81, 0x04, 0x00, 0x22];
+const OID_COMMON_NAME: &[u8] = &[0x55, 0x04, 0x03];
+const OID_ORGANIZATION: &[u8] = &[0x55, 0x04, 0x0A];
+const OID_ORGANIZATIONAL_UNIT: &[u8] = &[0x55, 0x04, 0x0B];
+const OID_COUNTRY: &[u8] = &[0x55, 0x04, 0x06];
+const OID_KEY_USAGE: &[u8] = &[0x55, 0x1D, 0x0F];
+const OID_BASIC_CONSTRAINTS: &[u8] = &[0x55, 0x1D, 0x13];
+const OID_EXTENDED_KEY_USAGE: &[u8] = &[0x55, 0x1D, 0x25];
+const OID_ANY_EXTENDED_KEY_USAGE: &[u8] = &[0x55, 0x1D, 0x25, 0x00];
+const OID_CODE_SIGNING: &[u8] = &[0x2B, 0x06, 0x01, 0x05, 0x05, 0x07, 0x03, 0x03];
+
+// KeyUsage bits (first two bytes of the bit string, big-endian)
+const KEY_USAGE_DIGITAL_SIGNATURE: u16 = 0x8000;
+const KEY_USAGE_KEY_CERT_SIGN: u16 = 0x0400;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SignatureStatus {
+    /// Digest and signer signature check out
+    Valid,
+    /// No embedded signature (may still be catalog-signed on Windows)
+    Unsigned,
+    /// Image was modified after signing
+    DigestMismatch,
+    /// Signer signature or chain does not verify
+    InvalidSignature,
+    /// Signature blob could not be parsed (or uses an unsupported algorithm)
+    Malformed,
+    /// File could not be read
+    Unreadable,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CertificateInfo {
+    pub subject: String,
+    pub issuer: String,
+    pub serial: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SignatureInfo {
+    pub status: SignatureStatus,
+    pub digest_algorithm: Option<String>,
+    pub publisher: Option<String>,    // Signer organization (or common name)
+    pub chain: Vec<CertificateInfo>,  // Signer first
+    pub self_signed: bool,
+    pub root_key: Option<String>,     // SHA-256 of the root's SubjectPublicKeyInfo (hex)
+    pub microsoft_root: bool,         // Windows: chain ends at a Microsoft product root
+    pub catalog: bool,                // Windows: signed through a system catalog, not embedded
+    pub error: Option<String>,
+}
+
+impl SignatureInfo {
+    fn with_status(status: SignatureStatus, error: Option<String>) -> Self {
+        SignatureInfo {
+            status,
+            digest_algorithm: None,
+            publisher: None,
+            chain: Vec::new(),
+            self_signed: false,
+            root_key: None,
+            microsoft_root: false,
+            catalog: false,
+            error,
+        }
+    }
+
+    /// Valid signature chaining to a pinned root key
+    pub fn is_trusted(&self, pinned_roots: &[HashSignature]) -> bool {
+        self.status == SignatureStatus::Valid && !self.self_signed && self.is_pinned(pinned_roots)
+    }
+
+    fn is_pinned(&self, pinned_roots: &[HashSignature]) -> bool {
+        self.microsoft_root || self.root_key.as_deref()
+            .is_some_and(|key| pinned_roots.iter().any(|pin| pin.sha256.eq_ignore_ascii_case(key)))
+    }
+
+    /// Reason code for a module that is not properly signed (None when trusted)
+    pub fn problem(&self, pinned_roots: &[HashSignature]) -> Option<&'static str> {
+        match self.status {
+            SignatureStatus::Valid if self.self_signed => Some("self_signed"),
+            SignatureStatus::Valid if !self.is_pinned(pinned_roots) => Some("unknown_root"),
+            SignatureStatus::Valid | SignatureStatus::Unreadable => None,
+            SignatureStatus::Unsigned => Some("unsigned"),
+            SignatureStatus::DigestMismatch => Some("tampered"),
+            SignatureStatus::InvalidSignature | SignatureStatus::Malformed => Some("invalid_signature"),
+        }
+    }
+}
+
+// ====== CACHE ======
+
+struct CachedSignature {
+    size: u64,
+    modified: Option<SystemTime>,
+    info: SignatureInfo,
+}
+
+lazy_static::lazy_static! {
+    static ref SIGNATURE_CACHE: Mutex<HashMap<String, CachedSignature>> = Mutex::new(HashMap::new());
+    static ref ROOTS: Vec<Certificate<'static>> = ROOT_CERTIFICATES.iter()
+        .filter_map(|der| Der::parse(der).ok())
+        .filter_map(|(cert, _)| Certificate::parse(cert).ok())
+        .collect();
+}
+
+/// Verify a file on disk (cached by path, size and modification time)
+pub fn verify_file(path: &str) -> SignatureInfo {
+    let metadata = match std::fs::metadata(path) {
+        Ok(m) if m.is_file() => m,
+        Ok(_) => return SignatureInfo::with_status(SignatureStatus::Unreadable, Some("Not a file".to_string())),
+        Err(e) => return SignatureInfo::with_status(SignatureStatus::Unreadable, Some(e.to_string())),
+    };
+    let size = metadata.len();
+    let modified = metadata.modified().ok();
+    let key = path.to_lowercase();
+
+    if let Ok(cache) = SIGNATURE_CACHE.lock() {
+        if let Some(entry) = cache.get(&key) {
+            if entry.size == size && entry.modified == modified {
+                return entry.info.clone();
+            }
+        }
+    }
+
+    let info = if size > MAX_FILE_SIZE {
+        SignatureInfo::with_status(SignatureStatus::Unreadable, Some(format!("File too large ({} bytes)", size)))
+    } else {
+        match std::fs::read(path) {
+            Ok(data) => verify(&data),
+            Err(e) => SignatureInfo::with_status(SignatureStatus::Unreadable, Some(e.to_string())),
+        }
+    };
+    #[cfg(target_os = "windows")]
+    let info = match info.status {
+        SignatureStatus::Valid => windows_trust(path, info),
+        SignatureStatus::Unsigned => windows_catalog_trust(path).unwrap_or(info),
+        _ => info,
+    };
+
+    if let Ok(mut cache) = SIGNATURE_CACHE.lock() {
+        if cache.len() >= MAX_CACHE_ENTRIES {
+            cache.clear();
+        }
+        cache.insert(key, CachedSignature { size, modified, info: info.clone() });
+    }
+
+    info
+}
+
+/// Verify the embedded Authenticode signature of a PE image
+pub fn verify(data: &[u8]) -> SignatureInfo {
+    let layout = match pe::layout(data) {
+        Ok(layout) => layout,
+        Err(e) => return SignatureInfo::with_status(SignatureStatus::Malformed, Some(e)),
+    };
+
+    let table = match layout.certificate_table() {
+        Some(table) => table,
+        None => return SignatureInfo::with_status(SignatureStatus::Unsigned, None),
+    };
+    let pkcs7 = match find_pkcs7(data, table) {
+        Some(pkcs7) => pkcs7,
+        None => return SignatureInfo::with_status(SignatureStatus::Malformed, Some("Invalid certificate table".to_string())),
+    };
+
+    match verify_pkcs7(data, &layout, pkcs7) {
+        Ok(info) => info,
+        Err(e) => SignatureInfo::with_status(SignatureStatus::Malformed, Some(e)),
+    }
+}
+
+/// Subject, issuer and serial of a DER encoded X.509 certificate
+pub fn certificate_info(der: &[u8]) -> Result<CertificateInfo, String> {
+    let (cert, _) = Der::parse(der)?;
+    Ok(Certificate::parse(cert)?.info())
+}
+
+/// First PKCS#7 entry of the certificate table
+fn find_pkcs7(data: &[u8], (offset, size): (usize, usize)) -> Option<&[u8]> {
+    let table = data.get(offset..offset.checked_add(size)?)?;
+    let mut pos = 0;
+    while pos + 8 <= table.len() {
+        let length = u32::from_le_bytes([table[pos], table[pos + 1], table[pos + 2], table[pos + 3]]) as usize;
+        let cert_type = u16::from_le_bytes([table[pos + 6], table[pos + 7]]);
+        if length < 8 || pos + length > table.len() {
+            return None;
+        }
+        if cert_type == 0x0002 {
+            return Some(&table[pos + 8..pos + length]);
+        }
+        pos += (length + 7) & !7;
+    }
+    None
+}
+
+// ====== DER ======
+
+#[derive(Debug, Clone, Copy)]
+struct Der<'a> {
+    tag: u8,
+    content: &'a [u8],
+    raw: &'a [u8], // Tag + length + content
+}
+
+impl<'a> Der<'a> {
+    fn parse(data: &'a [u8]) -> Result<(Der<'a>, &'a [u8]), String> {
+        let tag = *data.first().ok_or("DER: unexpected end")?;
+        let first = *data.get(1).ok_or("DER: missing length")?;
+        let (length, header) = if first & 0x80 == 0 {
+            (first as usize, 2)
+        } else {
+            let count = (first & 0x7f) as usize;
+            if count == 0 || count > 4 {
+                return Err("DER: unsupported length".to_string());
+            }
+            let bytes = data.get(2..2 + count).ok_or("DER: truncated length")?;
+            (bytes.iter().fold(0usize, |acc, &b| (acc << 8) | b as usize), 2 + count)
+        };
+        let end = header.checked_add(length).filter(|&end| end <= data.len()).ok_or("DER: truncated value")?;
+        Ok((Der { tag, content: &data[header..end], raw: &data[..end] }, &data[end..]))
+    }
+
+    fn expect(self, tag: u8) -> Result<Self, String> {
+        if self.tag == tag {
+            Ok(self)
+        } else {
+            Err(format!("DER: expected tag 0x{:02x}, found 0x{:02x}", tag, self.tag))
+        }
+    }
+
+    fn children(&self) -> Result<Vec<Der<'a>>, String> {
+        let mut items = Vec::new();
+        let mut rest = self.content;
+        while !rest.is_empty() {
+            let (item, next) = Der::parse(rest)?;
+            items.push(item);
+            rest = next;
+        }
+        Ok(items)
+    }
+
+    fn child(&self, index: usize) -> Result<Der<'a>, String> {
+        self.children()?.get(index).copied().ok_or_else(|| format!("DER: missing element {}", index))
+    }
+
+    /// OID of an AlgorithmIdentifier
+    fn algorithm(&self) -> Result<&'a [u8], String> {
+        Ok(self.child(0)?.expect(0x06)?.content)
+    }
+}
+
+// ====== CERTIFICATES ======
+
+struct Certificate<'a> {
+    tbs: &'a [u8],
+    serial: &'a [u8],
+    issuer: Der<'a>,
+    subject: Der<'a>,
+    key_algorithm: &'a [u8],
+    key_curve: Option<&'a [u8]>,
+    spki: &'a [u8],
+    public_key: &'a [u8],
+    signature_algorithm: &'a [u8],
+    signature: &'a [u8],
+    not_before: i64,                           // Validity (seconds since the epoch)
+    not_after: i64,
+    ca: bool,                                  // basicConstraints cA
+    path_len: Option<usize>,                   // basicConstraints pathLenConstraint
+    key_usage: Option<u16>,                    // None when the extension is absent
+    extended_key_usage: Option<Vec<&'a [u8]>>, // None when the extension is absent
+}
+
+impl<'a> Certificate<'a> {
+    fn parse(cert: Der<'a>) -> Result<Self, String> {
+        let parts = cert.expect(0x30)?.children()?;
+        let tbs = parts.first().ok_or("Certificate: missing tbs")?.expect(0x30)?;
+        let signature_algorithm = parts.get(1).ok_or("Certificate: missing algorithm")?.algorithm()?;
+        let signature = bit_string(parts.get(2).ok_or("Certificate: missing signature")?)?;
+
+        let mut fields = tbs.children()?;
+        if fields.first().map(|f| f.tag) == Some(0xA0) {
+            fields.remove(0); // Explicit version
+        }
+        let field = |i: usize| fields.get(i).copied().ok_or_else(|| format!("Certificate: missing field {}", i));
+
+        let validity = field(3)?.expect(0x30)?;
+        let spki = field(5)?.expect(0x30)?;
+        let key_algorithm_id = spki.child(0)?;
+        let key_curve = key_algorithm_id.children()?.get(1)
+            .filter(|param| param.tag == 0x06)
+            .map(|param| param.content);
+
+        let mut cert = Certificate {
+            tbs: tbs.raw,
+            serial: field(0)?.expect(0x02)?.content,
+            issuer: field(2)?.expect(0x30)?,
+            subject: field(4)?.expect(0x30)?,
+            key_algorithm: key_algorithm_id.algorithm()?,
+            key_curve,
+            spki: spki.raw,
+            public_key: bit_string(&spki.child(1)?)?,
+            signature_algorithm,
+            signature,
+            not_before: der_time(&validity.child(0)?)?,
+            not_after: der_time(&validity.child(1)?)?,
+            ca: false,
+            path_len: None,
+            key_usage: None,
+            extended_key_usage: None,
+        };
+        if let Some(extensions) = fields.iter().skip(6).find(|f| f.tag == 0xA3) {
+            cert.parse_extensions(extensions.child(0)?.expect(0x30)?)?;
+        }
+        Ok(cert)
+    }
+
+    /// basicConstraints, keyUsage and extKeyUsage (other extensions are ignored)
+    fn parse_extensions(&mut self, extensions: Der<'a>) -> Result<(), String> {
+        for extension in extensions.children()? {
+            let parts = extension.expect(0x30)?.children()?;
+            let id = parts.first().ok_or("Extension: missing id")?.expect(0x06)?.content;
+            let (value, _) = Der::parse(parts.last().ok_or("Extension: missing value")?.expect(0x04)?.content)?;
+            match id {
+                OID_BASIC_CONSTRAINTS => {
+                    for field in value.expect(0x30)?.children()? {
+                        match field.tag {
+                            0x01 => self.ca = field.content.first().is_some_and(|&b| b != 0),
+                            0x02 => self.path_len = Some(field.content.iter().fold(0usize, |acc, &b| acc.saturating_mul(256).saturating_add(b as usize))),
+                            _ => {}
+                        }
+                    }
+                }
+                OID_KEY_USAGE => {
+                    let bits = value.expect(0x03)?.content.get(1..).unwrap_or_default();
+                    self.key_usage = Some(u16::from_be_bytes([bits.first().copied().unwrap_or(0), bits.get(1).copied().unwrap_or(0)]));
+                }
+                OID_EXTENDED_KEY_USAGE => {
+                    self.extended_key_usage = Some(value.expect(0x30)?.children()?.iter()
+                        .filter(|purpose| purpose.tag == 0x06)
+                        .map(|purpose| purpose.content)
+                        .collect());
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether an absent extension or one listing code signing (or any purpose)
+    fn allows_code_signing_purpose(&self) -> bool {
+        self.extended_key_usage.as_ref()
+            .is_none_or(|purposes| purposes.iter().any(|&p| p == OID_CODE_SIGNING || p == OID_ANY_EXTENDED_KEY_USAGE))
+    }
+
+    /// Signer: codeSigning EKU required, digitalSignature when keyUsage is present
+    fn is_code_signer(&self) -> bool {
+        self.extended_key_usage.is_some()
+            && self.allows_code_signing_purpose()
+            && self.key_usage.is_none_or(|usage| usage & KEY_USAGE_DIGITAL_SIGNATURE != 0)
+    }
+
+    /// Issuer of a certificate with `below` CA certificates between it and the
+    /// signer: cA with keyCertSign, a path length allowing them, and no EKU
+    /// excluding code signing
+    fn can_issue(&self, below: usize) -> bool {
+        self.ca
+            && self.key_usage.is_some_and(|usage| usage & KEY_USAGE_KEY_CERT_SIGN != 0)
+            && self.path_len.is_none_or(|max| below <= max)
+            && self.allows_code_signing_purpose()
+    }
+
+    fn is_valid_at(&self, time: i64) -> bool {
+        (self.not_before..=self.not_after).contains(&time)
+    }
+
+    fn is_self_issued(&self) -> bool {
+        self.issuer.raw == self.subject.raw
+    }
+
+    /// SHA-256 of the SubjectPublicKeyInfo (hex), the value roots are pinned by
+    fn key_hash(&self) -> String {
+        hex::encode(digest::digest(&digest::SHA256, self.spki))
+    }
+
+    fn info(&self) -> CertificateInfo {
+        CertificateInfo {
+            subject: format_name(&self.subject),
+            issuer: format_name(&self.issuer),
+            serial: hex::encode(self.serial),
+        }
+    }
+
+    /// Verify `signed` with this certificate's public key
+    fn verify(&self, hash: &[u8], signed: &[u8], sig: &[u8]) -> bool {
+        let algorithm: &dyn signature::VerificationAlgorithm = if self.key_algorithm == OID_RSA {
+            match hash {
+                OID_SHA1 => &signature::RSA_PKCS1_1024_8192_SHA1_FOR_LEGACY_USE_ONLY,
+                OID_SHA256 => &signature::RSA_PKCS1_1024_8192_SHA256_FOR_LEGACY_USE_ONLY,
+                OID_SHA384 => &signature::RSA_PKCS1_2048_8192_SHA384,
+                OID_SHA512 => &signature::RSA_PKCS1_1024_8192_SHA512_FOR_LEGACY_USE_ONLY,
+                _ => return false,
+            }
+        } else if self.key_algorithm == OID_EC_PUBLIC_KEY {
+            match (self.key_curve, hash) {
+                (Some(OID_P256), OID_SHA256) => &signature::ECDSA_P256_SHA256_ASN1,
+                (Some(OID_P256), OID_SHA384) => &signature::ECDSA_P256_SHA384_ASN1,
+                (Some(OID_P384), OID_SHA256) => &signature::ECDSA_P384_SHA256_ASN1,
+                (Some(OID_P384), OID_SHA384) => &signature::ECDSA_P384_SHA384_ASN1,
+                _ => return false,
+            }
+        } else {
+            return false;
+        };
+
+        signature::UnparsedPublicKey::new(algorithm, self.public_key).verify(signed, sig).is_ok()
+    }
+
+    /// Verify that `issuer` signed this certificate
+    fn signed_by(&self, issuer: &Certificate) -> bool {
+        let hash = match self.signature_algorithm {
+            OID_SHA1_RSA => OID_SHA1,
+            OID_SHA256_RSA | OID_ECDSA_SHA256 => OID_SHA256,
+            OID_SHA384_RSA | OID_ECDSA_SHA384 => OID_SHA384,
+            OID_SHA512_RSA => OID_SHA512,
+            _ => return false,
+        };
+        issuer.verify(hash, self.tbs, self.signature)
+    }
+}
+
+fn bit_string<'a>(der: &Der<'a>) -> Result<&'a [u8], String> {
+    let bits = der.expect(0x03)?.content;
+    match bits.split_first() {
+        Some((0, rest)) => Ok(rest),
+        _ => Err("DER: unsupported bit string".to_string()),
+    }
+}
+
+/// UTCTime or GeneralizedTime, as seconds since the epoch
+fn der_time(der: &Der) -> Result<i64, String> {
+    let text = std::str::from_utf8(der.content).ok().and_then(|t| t.strip_suffix('Z')).ok_or("DER: invalid time")?;
+    let text = match der.tag {
+        // Two-digit years 50-99 are 19xx (RFC 5280)
+        0x17 => format!("{}{}", if text.get(..2).ok_or("DER: invalid time")? >= "50" { "19" } else { "20" }, text),
+        0x18 => text.split('.').next().unwrap_or_default().to_string(),
+        tag => return Err(format!("DER: expected a time, found tag 0x{:02x}", tag)),
+    };
+    chrono::NaiveDateTime::parse_from_str(&text, "%Y%m%d%H%M%S")
+        .map(|time| time.and_utc().timestamp())
+        .map_err(|_| "DER: invalid time".to_string())
+}
+
+/// Decode a directory string (UTF8, Printable, IA5, T61, BMP)
+fn directory_string(der: &Der) -> String {
+    match der.tag {
+        0x1E => {
+            let units: Vec<u16> = der.content.chunks(2)
+                .filter(|c| c.len() == 2)
+                .map(|c| u16::from_be_bytes([c[0], c[1]]))
+                .collect();
+            String::from_utf16_lossy(&units)
+        }
+        _ => String::from_utf8_lossy(der.content).to_string(),
+    }
+}
+
+/// Attribute of an X.501 Name (first match)
+fn name_attribute(name: &Der, oid: &[u8]) -> Option<String> {
+    for rdn in name.children().ok()? {
+        for attribute in rdn.children().ok()? {
+            let parts = attribute.children().ok()?;
+            if parts.len() == 2 && parts[0].tag == 0x06 && parts[0].content == oid {
+                return Some(directory_string(&parts[1]));
+            }
+        }
+    }
+    None
+}
+
+/// "CN=..., O=..., OU=..., C=..." (known attributes only)
+fn format_name(name: &Der) -> String {
+    [("CN", OID_COMMON_NAME), ("O", OID_ORGANIZATION), ("OU", OID_ORGANIZATIONAL_UNIT), ("C", OID_COUNTRY)]
+        .iter()
+        .filter_map(|(label, oid)| name_attribute(name, oid).map(|value| format!("{}={}", label, value)))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+// ====== PKCS#7 ======
+
+fn digest_algorithm(oid: &[u8]) -> Option<(&'static digest::Algorithm, &'static str)> {
+    match oid {
+        OID_SHA1 => Some((&digest::SHA1_FOR_LEGACY_USE_ONLY, "sha1")),
+        OID_SHA256 => Some((&digest::SHA256, "sha256")),
+        OID_SHA384 => Some((&digest::SHA384, "sha384")),
+        OID_SHA512 => Some((&digest::SHA512, "sha512")),
+        _ => None,
+    }
+}
+
+/// Authenticode image digest: everything except the checksum, the security
+/// directory entry and the certificate table itself
+fn image_digest(data: &[u8], layout: &pe::PeLayout, algorithm: &'static digest::Algorithm) -> Option<Vec<u8>> {
+    let security_dir = layout.security_dir_offset?;
+    let (cert_offset, _) = layout.certificate_table()?;
+    if layout.checksum_offset + 4 > security_dir || security_dir + 8 > cert_offset || cert_offset > data.len() {
+        return None;
+    }
+
+    let mut context = digest::Context::new(algorithm);
+    context.update(&data[..layout.checksum_offset]);
+    context.update(&data[layout.checksum_offset + 4..security_dir]);
+    context.update(&data[security_dir + 8..cert_offset]);
+    Some(context.finish().as_ref().to_vec())
+}
+
+fn verify_pkcs7(data: &[u8], layout: &pe::PeLayout, pkcs7: &[u8]) -> Result<SignatureInfo, String> {
+    let (content_info, _) = Der::parse(pkcs7)?;
+    let content_info = content_info.expect(0x30)?;
+    if content_info.child(0)?.expect(0x06)?.content != OID_SIGNED_DATA {
+        return Err("Not a PKCS#7 SignedData".to_string());
+    }
+    let signed_data = content_info.child(1)?.expect(0xA0)?.child(0)?.expect(0x30)?;
+    let parts = signed_data.children()?;
+
+    // SpcIndirectDataContent carries the expected image digest
+    let encap = parts.get(2).ok_or("SignedData: missing content")?.expect(0x30)?;
+    if encap.child(0)?.expect(0x06)?.content != OID_SPC_INDIRECT_DATA {
+        return Err("Not an Authenticode signature".to_string());
+    }
+    let indirect = encap.child(1)?.expect(0xA0)?.child(0)?.expect(0x30)?;
+    let digest_info = indirect.child(1)?.expect(0x30)?;
+    let image_hash_oid = digest_info.child(0)?.algorithm()?;
+    let expected_image_digest = digest_info.child(1)?.expect(0x04)?.content;
+
+    let (image_algorithm, algorithm_name) = digest_algorithm(image_hash_oid)
+        .ok_or("Unsupported image digest algorithm")?;
+
+    let mut info = SignatureInfo::with_status(SignatureStatus::Valid, None);
+    info.digest_algorithm = Some(algorithm_name.to_string());
+
+    let certificates: Vec<Certificate> = parts.iter()
+        .find(|p| p.tag == 0xA0)
+        .map(|set| set.children())
+        .transpose()?
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|cert| Certificate::parse(cert).ok())
+        .collect();
+
+    let signer_info = parts.last().ok_or("SignedData: missing signer infos")?.expect(0x31)?.child(0)?.expect(0x30)?;
+    let signer_fields = signer_info.children()?;
+    let issuer_and_serial = signer_fields.get(1).ok_or("SignerInfo: missing issuer")?.expect(0x30)?;
+    let signer_issuer = issuer_and_serial.child(0)?;
+    let signer_serial = issuer_and_serial.child(1)?.expect(0x02)?.content;
+    let signer_hash_oid = signer_fields.get(2).ok_or("SignerInfo: missing digest algorithm")?.algorithm()?;
+    let authenticated = signer_fields.iter().find(|f| f.tag == 0xA0).ok_or("SignerInfo: missing authenticated attributes")?;
+    let encrypted_digest = signer_fields.iter().rev().find(|f| f.tag == 0x04).ok_or("SignerInfo: missing signature")?.content;
+
+    let signer = certificates.iter()
+        .find(|c| c.issuer.raw == signer_issuer.raw && c.serial == signer_serial)
+        .ok_or("Signer certificate not embedded")?;
+
+    info.publisher = name_attribute(&signer.subject, OID_ORGANIZATION)
+        .or_else(|| name_attribute(&signer.subject, OID_COMMON_NAME));
+    let signing_time = timestamp(&signer_fields, encrypted_digest, &certificates).unwrap_or_else(|| {
+        SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or(0)
+    });
+    info.chain = build_chain(signer, &certificates, signing_time, &mut info);
+
+    // 1. Image digest
+    match image_digest(data, layout, image_algorithm) {
+        Some(actual) if actual == expected_image_digest => {}
+        Some(_) => {
+            info.status = SignatureStatus::DigestMismatch;
+            return Ok(info);
+        }
+        None => return Err("Certificate table is not at the end of the image".to_string()),
+    }
+
+    // 2. messageDigest attribute = hash of the SpcIndirectDataContent value
+    let (signer_algorithm, _) = digest_algorithm(signer_hash_oid).ok_or("Unsupported signer digest algorithm")?;
+    let content_digest = digest::digest(signer_algorithm, indirect.content);
+    let message_digest = attribute(&authenticated.children()?, OID_MESSAGE_DIGEST)
+        .map(|value| value.content)
+        .ok_or("SignerInfo: missing messageDigest")?;
+    if message_digest != content_digest.as_ref() {
+        info.status = SignatureStatus::InvalidSignature;
+        info.error = Some("messageDigest mismatch".to_string());
+        return Ok(info);
+    }
+
+    // 3. Signer signature over the attributes (re-tagged as SET OF)
+    let mut signed_attributes = authenticated.raw.to_vec();
+    signed_attributes[0] = 0x31;
+    if !signer.verify(signer_hash_oid, &signed_attributes, encrypted_digest) {
+        info.status = SignatureStatus::InvalidSignature;
+        info.error = Some("Signer signature does not verify".to_string());
+    }
+
+    Ok(info)
+}
+
+/// First value of an attribute (authenticated or not) of a SignerInfo
+fn attribute<'a>(attributes: &[Der<'a>], oid: &[u8]) -> Option<Der<'a>> {
+    attributes.iter()
+        .find(|attr| attr.child(0).map(|id| id.content == oid).unwrap_or(false))
+        .and_then(|attr| attr.child(1).ok())
+        .and_then(|values| values.child(0).ok())
+}
+
+/// Authenticated attributes of a SignerInfo whose messageDigest is the hash of
+/// `content` and whose signature verifies with its certificate in `certificates`
+fn signed_attributes<'a>(signer_info: Der<'a>, content: &[u8], certificates: &[Certificate]) -> Option<Vec<Der<'a>>> {
+    let fields = signer_info.expect(0x30).ok()?.children().ok()?;
+    let issuer_and_serial = fields.get(1)?.expect(0x30).ok()?;
+    let (issuer, serial) = (issuer_and_serial.child(0).ok()?, issuer_and_serial.child(1).ok()?.content);
+    let hash_oid = fields.get(2)?.algorithm().ok()?;
+    let authenticated = fields.iter().find(|f| f.tag == 0xA0)?;
+    let signature = fields.iter().rev().find(|f| f.tag == 0x04)?.content;
+    let signer = certificates.iter().find(|c| c.issuer.raw == issuer.raw && c.serial == serial)?;
+
+    let (algorithm, _) = digest_algorithm(hash_oid)?;
+    let attributes = authenticated.children().ok()?;
+    if attribute(&attributes, OID_MESSAGE_DIGEST)?.content != digest::digest(algorithm, content).as_ref() {
+        return None;
+    }
+    let mut signed = authenticated.raw.to_vec();
+    signed[0] = 0x31;
+    signer.verify(hash_oid, &signed, signature).then_some(attributes)
+}
+
+/// Time of the timestamp countersignature over the signer's signature, when the
+/// timestamping authority's signature verifies
+fn timestamp(signer_fields: &[Der], encrypted_digest: &[u8], certificates: &[Certificate]) -> Option<i64> {
+    let unauthenticated = signer_fields.iter().find(|f| f.tag == 0xA1)?.children().ok()?;
+
+    // PKCS#9 countersignature: a SignerInfo over the signature with a signingTime
+    if let Some(counter_signer) = attribute(&unauthenticated, OID_COUNTER_SIGNATURE) {
+        let time = signed_attributes(counter_signer, encrypted_digest, certificates)
+            .and_then(|attributes| der_time(&attribute(&attributes, OID_SIGNING_TIME)?).ok());
+        if time.is_some() {
+            return time;
+        }
+    }
+
+    // RFC 3161: a SignedData over a TSTInfo whose messageImprint is the hash of the signature
+    let token = attribute(&unauthenticated, OID_RFC3161_COUNTER_SIGNATURE)?;
+    let signed_data = token.child(1).ok()?.expect(0xA0).ok()?.child(0).ok()?;
+    let parts = signed_data.children().ok()?;
+    let encap = parts.get(2)?;
+    if encap.child(0).ok()?.content != OID_TST_INFO {
+        return None;
+    }
+    let tst_info = encap.child(1).ok()?.expect(0xA0).ok()?.child(0).ok()?.expect(0x04).ok()?.content;
+    let (tst_info_der, _) = Der::parse(tst_info).ok()?;
+    let imprint = tst_info_der.child(2).ok()?;
+    let (imprint_algorithm, _) = digest_algorithm(imprint.child(0).ok()?.algorithm().ok()?)?;
+    if imprint.child(1).ok()?.content != digest::digest(imprint_algorithm, encrypted_digest).as_ref() {
+        return None;
+    }
+
+    let authority_certificates: Vec<Certificate> = parts.iter()
+        .find(|p| p.tag == 0xA0)
+        .and_then(|set| set.children().ok())
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|cert| Certificate::parse(cert).ok())
+        .collect();
+    signed_attributes(parts.last()?.expect(0x31).ok()?.child(0).ok()?, tst_info, &authority_certificates)?;
+    der_time(&tst_info_der.child(4).ok()?).ok()
+}
+
+/// Walk issuer links through the embedded certificates (signer first), then
+/// anchor the top certificate at its root. Certificates below the root must be
+/// valid at `signing_time`.
+fn build_chain(signer: &Certificate, certificates: &[Certificate], signing_time: i64, info: &mut SignatureInfo) -> Vec<CertificateInfo> {
+    let mut chain = vec![signer.info()];
+    let mut current = signer;
+
+    if !signer.is_code_signer() {
+        info.status = SignatureStatus::InvalidSignature;
+        info.error = Some("Signer certificate is not valid for code signing".to_string());
+    }
+
+    for _ in 0..MAX_CHAIN_LENGTH {
+        // Embedded roots are trust anchors like the bundled ones: their validity is not checked
+        let anchor = current.is_self_issued() && chain.len() > 1;
+        if !anchor && !current.is_valid_at(signing_time) && info.status == SignatureStatus::Valid {
+            info.status = SignatureStatus::InvalidSignature;
+            info.error = Some(format!("{} is not valid at the signing time", format_name(&current.subject)));
+        }
+        if current.is_self_issued() {
+            if current.signed_by(current) {
+                // Self-signed signer, or an embedded root
+                info.self_signed = chain.len() == 1;
+            } else {
+                info.status = SignatureStatus::InvalidSignature;
+                info.error = Some("Broken self-signed certificate".to_string());
+            }
+            break;
+        }
+
+        // Cross-signed CAs share a subject: take the candidate whose key verifies
+        let mut candidates = certificates.iter().filter(|c| c.subject.raw == current.issuer.raw).peekable();
+        if candidates.peek().is_none() {
+            break; // Root not embedded (normal)
+        }
+        match candidates.find(|issuer| current.signed_by(issuer)) {
+            Some(issuer) if !issuer.can_issue(chain.len() - 1) => {
+                info.status = SignatureStatus::InvalidSignature;
+                info.error = Some(format!("{} is not allowed to issue certificates", format_name(&issuer.subject)));
+                break;
+            }
+            Some(issuer) => {
+                chain.push(issuer.info());
+                current = issuer;
+            }
+            None => {
+                info.status = SignatureStatus::InvalidSignature;
+                info.error = Some(format!("Certificate not signed by {}", format_name(&current.issuer)));
+                break;
+            }
+        }
+    }
+
+    // Embedded root, or a bundled root whose key verifies the top certificate
+    let root = if current.is_self_issued() {
+        Some(current).filter(|_| info.status == SignatureStatus::Valid && !info.self_signed)
+    } else {
+        let root = ROOTS.iter().find(|root| root.subject.raw == current.issuer.raw && current.signed_by(root));
+        if let Some(root) = root {
+            chain.push(root.info());
+            if !root.can_issue(chain.len() - 2) {
+                info.status = SignatureStatus::InvalidSignature;
+                info.error = Some(format!("{} is not allowed to issue certificates", format_name(&root.subject)));
+            }
+        }
+        root.filter(|_| info.status == SignatureStatus::Valid)
+    };
+    info.root_key = root.map(|root| root.key_hash());
+
+    chain
+}
+
+/// Let WinVerifyTrust check the file against the system trust store and take
+/// the root of its chain instead of the portable one
+#[cfg(target_os = "windows")]
+fn windows_trust(path: &str, mut info: SignatureInfo) -> SignatureInfo {
+    use windows::core::PCWSTR;
+    use windows::Win32::Security::WinTrust::{WINTRUST_DATA_0, WINTRUST_FILE_INFO, WTD_CHOICE_FILE};
+
+    let wide: Vec<u16> = path.encode_utf16().chain(std::iter::once(0)).collect();
+    let mut file = WINTRUST_FILE_INFO {
+        cbStruct: std::mem::size_of::<WINTRUST_FILE_INFO>() as u32,
+        pcwszFilePath: PCWSTR(wide.as_ptr()),
+        ..Default::default()
+    };
+    // Untrusted root, revoked or expired certificate: the error carries the code
+    if !unsafe { win_verify_trust(WTD_CHOICE_FILE, WINTRUST_DATA_0 { pFile: &mut file }, &mut info) } {
+        info.status = SignatureStatus::InvalidSignature;
+    }
+    info
+}
+
+/// Files without an embedded signature, most inbox system files among them
+/// (WinSxS included), are signed through a system catalog listing their hash.
+/// Returns the catalog signature when WinVerifyTrust accepts it.
+#[cfg(target_os = "windows")]
+fn windows_catalog_trust(path: &str) -> Option<SignatureInfo> {
+    use std::os::windows::io::AsRawHandle;
+    use windows::core::{w, PCWSTR};
+    use windows::Win32::Foundation::HANDLE;
+    use windows::Win32::Security::Cryptography::Catalog::{
+        CryptCATAdminAcquireContext2, CryptCATAdminCalcHashFromFileHandle2, CryptCATAdminEnumCatalogFromHash,
+        CryptCATAdminReleaseCatalogContext, CryptCATAdminReleaseContext, CryptCATCatalogInfoFromContext, CATALOG_INFO,
+    };
+    use windows::Win32::Security::WinTrust::{WINTRUST_CATALOG_INFO, WINTRUST_DATA_0, WTD_CHOICE_CATALOG};
+
+    let file = std::fs::File::open(path).ok()?;
+    let handle = HANDLE(file.as_raw_handle() as isize);
+    let wide: Vec<u16> = path.encode_utf16().chain(std::iter::once(0)).collect();
+
+    unsafe {
+        let mut admin: isize = 0;
+        CryptCATAdminAcquireContext2(&mut admin, None, w!("SHA256"), None, 0).ok()?;
+
+        let mut result = None;
+        let mut hash = vec![0u8; 64];
+        let mut hash_size = hash.len() as u32;
+        if CryptCATAdminCalcHashFromFileHandle2(admin, handle, &mut hash_size, Some(hash.as_mut_ptr()), 0).is_ok() {
+            hash.truncate(hash_size as usize);
+            let catalog = CryptCATAdminEnumCatalogFromHash(admin, &hash, 0, None);
+            if catalog != 0 {
+                let mut catalog_info = CATALOG_INFO { cbStruct: std::mem::size_of::<CATALOG_INFO>() as u32, wszCatalogFile: [0; 260] };
+                if CryptCATCatalogInfoFromContext(catalog, &mut catalog_info, 0).is_ok() {
+                    let tag: Vec<u16> = hex::encode_upper(&hash).encode_utf16().chain(std::iter::once(0)).collect();
+                    let mut member = WINTRUST_CATALOG_INFO {
+                        cbStruct: std::mem::size_of::<WINTRUST_CATALOG_INFO>() as u32,
+                        pcwszCatalogFilePath: PCWSTR(catalog_info.wszCatalogFile.as_ptr()),
+                        pcwszMemberTag: PCWSTR(tag.as_ptr()),
+                        pcwszMemberFilePath: PCWSTR(wide.as_ptr()),
+                        hMemberFile: handle,
+                        pbCalculatedFileHash: hash.as_mut_ptr(),
+                        cbCalculatedFileHash: hash.len() as u32,
+                        hCatAdmin: admin,
+                        ..Default::default()
+                    };
+                    let mut info = SignatureInfo::with_status(SignatureStatus::Valid, None);
+                    info.digest_algorithm = Some("sha256".to_string());
+                    info.catalog = true;
+                    if win_verify_trust(WTD_CHOICE_CATALOG, WINTRUST_DATA_0 { pCatalog: &mut member }, &mut info) {
+                        result = Some(info);
+                    }
+                }
+                CryptCATAdminReleaseCatalogContext(admin, catalog, 0);
+            }
+        }
+        CryptCATAdminReleaseContext(admin, 0);
+        result
+    }
+}
+
+/// Run WinVerifyTrust on a file or catalog member and take the root key, the
+/// Microsoft root policy and, for catalogs, the chain and publisher of the
+/// signer. Returns whether the signature was accepted.
+#[cfg(target_os = "windows")]
+unsafe fn win_verify_trust(
+    choice: windows::Win32::Security::WinTrust::WINTRUST_DATA_UNION_CHOICE,
+    subject: windows::Win32::Security::WinTrust::WINTRUST_DATA_0,
+    info: &mut SignatureInfo,
+) -> bool {
+    use windows::core::GUID;
+    use windows::Win32::Foundation::{BOOL, HWND};
+    use windows::Win32::Security::Cryptography::{
+        CertVerifyCertificateChainPolicy, CERT_CHAIN_POLICY_FLAGS, CERT_CHAIN_POLICY_MICROSOFT_ROOT,
+        CERT_CHAIN_POLICY_PARA, CERT_CHAIN_POLICY_STATUS, MICROSOFT_ROOT_CERT_CHAIN_POLICY_CHECK_APPLICATION_ROOT_FLAG,
+    };
+    use windows::Win32::Security::WinTrust::{
+        WTHelperGetProvSignerFromChain, WTHelperProvDataFromStateData, WinVerifyTrust,
+        WINTRUST_ACTION_GENERIC_VERIFY_V2, WINTRUST_DATA, WTD_CACHE_ONLY_URL_RETRIEVAL, WTD_REVOKE_NONE,
+        WTD_STATEACTION_CLOSE, WTD_STATEACTION_VERIFY, WTD_UI_NONE,
+    };
+
+    let mut data = WINTRUST_DATA {
+        cbStruct: std::mem::size_of::<WINTRUST_DATA>() as u32,
+        dwUIChoice: WTD_UI_NONE,
+        fdwRevocationChecks: WTD_REVOKE_NONE,
+        dwUnionChoice: choice,
+        Anonymous: subject,
+        dwStateAction: WTD_STATEACTION_VERIFY,
+        dwProvFlags: WTD_CACHE_ONLY_URL_RETRIEVAL,
+        ..Default::default()
+    };
+    let mut action: GUID = WINTRUST_ACTION_GENERIC_VERIFY_V2;
+
+    info.root_key = None;
+    let status = WinVerifyTrust(HWND::default(), &mut action, &mut data as *mut _ as *mut _);
+    if status != 0 {
+        info.error = Some(format!("WinVerifyTrust: 0x{:08X}", status as u32));
+    } else {
+        let provider = WTHelperProvDataFromStateData(data.hWVTStateData);
+        let signer = if provider.is_null() {
+            std::ptr::null_mut()
+        } else {
+            WTHelperGetProvSignerFromChain(provider, 0, BOOL(0), 0)
+        };
+        if let Some(signer) = signer.as_ref() {
+            let certificates: Vec<&[u8]> = (0..signer.csCertChain as usize)
+                .filter_map(|i| signer.pasCertChain.add(i).as_ref())
+                .filter_map(|entry| entry.pCert.as_ref())
+                .map(|cert| std::slice::from_raw_parts(cert.pbCertEncoded as *const u8, cert.cbCertEncoded as usize))
+                .collect();
+
+            // Last certificate of the chain is the root
+            info.root_key = certificates.last()
+                .and_then(|der| Der::parse(der).ok())
+                .and_then(|(cert, _)| Certificate::parse(cert).ok())
+                .map(|root| root.key_hash());
+            if info.chain.is_empty() {
+                info.chain = certificates.iter().filter_map(|der| certificate_info(der).ok()).collect();
+                info.publisher = certificates.first()
+                    .and_then(|der| Der::parse(der).ok())
+                    .and_then(|(cert, _)| Certificate::parse(cert).ok())
+                    .and_then(|signer| name_attribute(&signer.subject, OID_ORGANIZATION)
+                        .or_else(|| name_attribute(&signer.subject, OID_COMMON_NAME)));
+            }
+
+            let para = CERT_CHAIN_POLICY_PARA {
+                cbSize: std::mem::size_of::<CERT_CHAIN_POLICY_PARA>() as u32,
+                dwFlags: CERT_CHAIN_POLICY_FLAGS(MICROSOFT_ROOT_CERT_CHAIN_POLICY_CHECK_APPLICATION_ROOT_FLAG),
+                ..Default::default()
+            };
+            let mut policy = CERT_CHAIN_POLICY_STATUS {
+                cbSize: std::mem::size_of::<CERT_CHAIN_POLICY_STATUS>() as u32,
+                ..Default::default()
+            };
+            info.microsoft_root = !signer.pChainContext.is_null()
+                && CertVerifyCertificateChainPolicy(CERT_CHAIN_POLICY_MICROSOFT_ROOT, signer.pChainContext, &para, &mut policy).as_bool()
+                && policy.dwError == 0;
+        }
+    }
+
+    data.dwStateAction = WTD_STATEACTION_CLOSE;
+    WinVerifyTrust(HWND::default(), &mut action, &mut data as *mut _ as *mut _);
+    status == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundled_roots_are_pinned() {
+        assert_eq!(ROOTS.len(), ROOT_CERTIFICATES.len(), "a bundled root does not parse");
+        for root in ROOTS.iter() {
+            let key = root.key_hash();
+            assert!(crate::hardware::TRUSTED_ROOT_KEYS.iter().any(|(pin, _)| *pin == key),
+                    "{} ({}) is not pinned", format_name(&root.subject), key);
+            assert!(root.signed_by(root), "{} is not self-signed", format_name(&root.subject));
+            assert!(root.can_issue(0), "{} is not a CA", format_name(&root.subject));
+        }
+    }
+
+    /// Signed by tests/fixtures/pe/source/sign.py
+    fn fixture(name: &str) -> Vec<u8> {
+        std::fs::read(std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/pe").join(name)).unwrap()
+    }
+
+    #[test]
+    fn valid_chain_anchors_at_the_embedded_root() {
+        let info = verify(&fixture("authenticode-valid.exe"));
+        assert_eq!(info.status, SignatureStatus::Valid, "{:?}", info.error);
+        assert_eq!(info.digest_algorithm.as_deref(), Some("sha256"));
+        assert_eq!(info.publisher.as_deref(), Some("Iris Fixtures"));
+        assert!(!info.self_signed);
+        let subjects: Vec<&str> = info.chain.iter().map(|c| c.subject.as_str()).collect();
+        assert_eq!(subjects, [
+            "CN=Iris Fixture Publisher, O=Iris Fixtures",
+            "CN=Iris Fixture CA, O=Iris Fixtures",
+            "CN=Iris Fixture Root, O=Iris Fixtures",
+        ]);
+
+        let root_key = info.root_key.clone().expect("root key");
+        assert_eq!(info.problem(&[]), Some("unknown_root"));
+        let pin = HashSignature { sha256: root_key.to_uppercase(), name: "Iris Fixture Root".to_string() };
+        assert!(info.is_trusted(std::slice::from_ref(&pin)));
+        assert_eq!(info.problem(&[pin]), None);
+    }
+
+    #[test]
+    fn modified_image_is_a_digest_mismatch() {
+        let mut data = fixture("authenticode-valid.exe");
+        data[0x400] ^= 0x01; // .text
+        assert_eq!(verify(&data).status, SignatureStatus::DigestMismatch);
+        assert_eq!(verify(&data).problem(&[]), Some("tampered"));
+    }
+
+    #[test]
+    fn self_signed_signer() {
+        let info = verify(&fixture("authenticode-selfsigned.exe"));
+        assert_eq!(info.status, SignatureStatus::Valid, "{:?}", info.error);
+        assert!(info.self_signed);
+        assert_eq!(info.root_key, None);
+        assert_eq!(info.problem(&[]), Some("self_signed"));
+    }
+
+    #[test]
+    fn broken_chain_is_an_invalid_signature() {
+        let info = verify(&fixture("authenticode-broken-chain.exe"));
+        assert_eq!(info.status, SignatureStatus::InvalidSignature);
+        assert_eq!(info.error.as_deref(), Some("Certificate not signed by CN=Iris Fixture CA, O=Iris Fixtures"));
+        assert_eq!(info.root_key, None);
+    }
+
+    #[test]
+    fn end_entity_certificate_cannot_issue() {
+        let info = verify(&fixture("authenticode-ee-issuer.exe"));
+        assert_eq!(info.status, SignatureStatus::InvalidSignature);
+        assert_eq!(info.error.as_deref(), Some("CN=www.iris-fixture.test, O=Iris Fixtures is not allowed to issue certificates"));
+        assert_eq!(info.root_key, None);
+        assert_eq!(info.problem(&[]), Some("invalid_signature"));
+    }
+
+    #[test]
+    fn path_length_constraint_is_enforced() {
+        let info = verify(&fixture("authenticode-pathlen.exe"));
+        assert_eq!(info.status, SignatureStatus::InvalidSignature);
+        assert_eq!(info.error.as_deref(), Some("CN=Iris Fixture CA, O=Iris Fixtures is not allowed to issue certificates"));
+    }
+
+    #[test]
+    fn signer_needs_the_code_signing_purpose() {
+        let info = verify(&fixture("authenticode-tls-signer.exe"));
+        assert_eq!(info.status, SignatureStatus::InvalidSignature);
+        assert_eq!(info.error.as_deref(), Some("Signer certificate is not valid for code signing"));
+        assert_eq!(info.root_key, None);
+    }
+
+    #[test]
+    fn expired_signer_without_timestamp_is_invalid() {
+        let info = verify(&fixture("authenticode-expired.exe"));
+        assert_eq!(info.status, SignatureStatus::InvalidSignature);
+        assert_eq!(info.error.as_deref(), Some("CN=Iris Fixture Publisher, O=Iris Fixtures is not valid at the signing time"));
+        assert_eq!(info.root_key, None);
+        assert_eq!(info.problem(&[]), Some("invalid_signature"));
+    }
+
+    #[test]
+    fn timestamp_dates_the_signature() {
+        for name in ["authenticode-timestamped.exe", "authenticode-countersigned.exe"] {
+            let info = verify(&fixture(name));
+            assert_eq!(info.status, SignatureStatus::Valid, "{}: {:?}", name, info.error);
+            assert!(info.root_key.is_some(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn forged_timestamp_is_ignored() {
+        let info = verify(&fixture("authenticode-forged-timestamp.exe"));
+        assert_eq!(info.status, SignatureStatus::InvalidSignature);
+        assert_eq!(info.error.as_deref(), Some("CN=Iris Fixture Publisher, O=Iris Fixtures is not valid at the signing time"));
+    }
+
+    #[test]
+    fn parses_der_times() {
+        let time = |tag: u8, text: &str| der_time(&Der { tag, content: text.as_bytes(), raw: &[] });
+        assert_eq!(time(0x17, "200601120000Z"), Ok(1591012800));
+        assert_eq!(time(0x17, "991231235959Z"), Ok(946684799));
+        assert_eq!(time(0x18, "20200601120000.123Z"), Ok(1591012800));
+        assert!(time(0x18, "20200601120000").is_err());
+        assert!(time(0x04, "200601120000Z").is_err());
+    }
+}
@@ -1,6 +1,7 @@
 //! Tauri commands - exposed to frontend via invoke()
 
-use crate::{api, hardware, store};
+use crate::{api, attestation, detectors, fingerprint, hardware, journal, risk, signatures, store, whitelist};
+use crate::snapshot::SystemSnapshot;
 use serde::{Deserialize, Serialize};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Mutex;
@@ -14,9 +15,19 @@ use std::sync::atomic::AtomicU64;
 static LAST_SCREENSHOT_TIME: AtomicU64 = AtomicU64::new(0);
 const SCREENSHOT_INTERVAL_SECS: u64 = 300; // 5 minutes for production
 
+// Check for a newer signature bundle every 20 cycles (10 minutes)
+const SIGNATURE_REFRESH_CYCLES: u32 = 20;
+
+// Check for whitelist changes every 4 cycles (2 minutes, ETag makes it cheap)
+const WHITELIST_REFRESH_CYCLES: u32 = 4;
+
 // Store previous security status to detect changes
 lazy_static::lazy_static! {
     static ref PREVIOUS_SECURITY: Mutex<Option<hardware::SecurityStatus>> = Mutex::new(None);
+    // Nonce and PCRs of the last attestation challenge, answered with the next data heartbeat
+    static ref ATTESTATION_REQUEST: Mutex<Option<(String, Vec<u32>)>> = Mutex::new(None);
+    // Credential encrypted to the EK at registration, activated with the next data heartbeat
+    static ref CREDENTIAL_CHALLENGE: Mutex<Option<attestation::CredentialChallenge>> = Mutex::new(None);
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -246,6 +257,9 @@ pub async fn verify_session() -> Result<SessionResult, String> {
                         "version": security.tpm.version
                     },
                     "secureBoot": security.secure_boot.enabled,
+                    "secureBootSetupMode": security.secure_boot.setup_mode,
+                    "legacyBios": security.secure_boot.legacy_bios,
+                    "secureBootKeys": security.secure_boot.keys,
                     "virtualization": security.virtualization.enabled,
                     "iommu": security.virtualization.iommu,
                     "kernelDmaProtection": security.virtualization.kernel_dma_protection,
@@ -264,6 +278,7 @@ pub async fn verify_session() -> Result<SessionResult, String> {
                 ).await {
                     // Check if server requests immediate screenshots (scan mode + reconnection)
                     if let Some(data) = response.data {
+                        store_attestation_request(&data);
                         // Update scan mode state
                         if let Some(scan) = data.get("scanModeEnabled") {
                             if let Some(enabled) = scan.as_bool() {
@@ -282,20 +297,14 @@ pub async fn verify_session() -> Result<SessionResult, String> {
                             if request_screenshots.as_bool() == Some(true) {
                                 println!("[Iris] Server requested immediate screenshots from verify_session - capturing...");
                                 
-                                let cheat_detection = hardware::detect_cheats();
+                                let mut system_info = collect_detection_results(false);
                                 let screenshots = hardware::capture_all_screens_medium_quality();
-                                let processes = hardware::get_all_processes();
-                                let usb_devices = hardware::get_all_usb_devices();
-                                println!("[Iris] Captured {} screenshot(s), {} processes, {} USB devices", 
-                                         screenshots.len(), processes.len(), usb_devices.len());
+                                println!("[Iris] Captured {} screenshot(s), {} detection results",
+                                         screenshots.len(), system_info.len());
                                 
-                                let system_info = serde_json::json!({
-                                    "cheatDetection": cheat_detection,
-                                    "scanMode": true,
-                                    "screenshots": screenshots,
-                                    "processes": processes,
-                                    "usbDevices": usb_devices
-                                });
+                                system_info.insert("scanMode".to_string(), serde_json::json!(true));
+                                system_info.insert("screenshots".to_string(), serde_json::json!(screenshots));
+                                let system_info = serde_json::Value::Object(system_info);
                                 
                                 // Send screenshots immediately
                                 if let Err(e) = api_client.send_heartbeat(&token, &hardware_id, security_json, Some(system_info)).await {
@@ -406,6 +415,10 @@ fn has_security_changed(current: &hardware::SecurityStatus, previous: &hardware:
     if current.secure_boot.enabled != previous.secure_boot.enabled {
         changes.push(format!("Secure Boot: {} → {}", previous.secure_boot.enabled, current.secure_boot.enabled));
     }
+    let key_class = |status: &hardware::SecureBootStatus| status.keys.as_ref().map(|keys| keys.class);
+    if key_class(&current.secure_boot) != key_class(&previous.secure_boot) {
+        changes.push(format!("Secure Boot keys: {:?} → {:?}", key_class(&previous.secure_boot), key_class(&current.secure_boot)));
+    }
     if current.virtualization.enabled != previous.virtualization.enabled {
         changes.push(format!("Virtualization: {} → {}", previous.virtualization.enabled, current.virtualization.enabled));
     }
@@ -422,6 +435,50 @@ fn has_security_changed(current: &hardware::SecurityStatus, previous: &hardware:
     changes
 }
 
+/// Collect a snapshot and run every registered detector over it
+fn collect_detection_results(force: bool) -> serde_json::Map<String, serde_json::Value> {
+    let snapshot = SystemSnapshot::collect();
+    let detection = detectors::run_all(&snapshot, force);
+    let mut results = detection.values;
+    results.insert("riskAssessment".to_string(), serde_json::json!(risk::assess(&detection.findings)));
+    results.insert("signatureVersion".to_string(), serde_json::json!(signatures::version()));
+    results.insert("whitelistEntries".to_string(), serde_json::json!(whitelist::entry_count()));
+    let request = ATTESTATION_REQUEST.lock().ok().and_then(|mut request| request.take());
+    if let Some((nonce, pcrs)) = request {
+        results.insert("attestation".to_string(), serde_json::json!(attestation::quote(&nonce, &pcrs)));
+    }
+    let challenge = CREDENTIAL_CHALLENGE.lock().ok().and_then(|mut challenge| challenge.take());
+    if let Some(challenge) = challenge {
+        results.insert("attestationActivation".to_string(), serde_json::json!(attestation::activate_credential(&challenge)));
+    }
+    results
+}
+
+/// Remember an attestation challenge from a registration or heartbeat response
+fn store_attestation_request(data: &serde_json::Value) {
+    let challenge = data.get("attestationCredential")
+        .and_then(|c| serde_json::from_value::<attestation::CredentialChallenge>(c.clone()).ok());
+    if let Some(challenge) = challenge {
+        println!("[Iris] Attestation credential received");
+        if let Ok(mut pending) = CREDENTIAL_CHALLENGE.lock() {
+            *pending = Some(challenge);
+        }
+    }
+
+    let nonce = match data.get("attestationNonce").and_then(|n| n.as_str()) {
+        Some(nonce) => nonce,
+        None => return,
+    };
+    let pcrs = data.get("attestationPcrs")
+        .and_then(|p| p.as_array())
+        .map(|p| p.iter().filter_map(|pcr| pcr.as_u64()).map(|pcr| pcr as u32).collect())
+        .unwrap_or_else(|| attestation::DEFAULT_QUOTE_PCRS.to_vec());
+    println!("[Iris] Attestation requested for PCRs {:?}", pcrs);
+    if let Ok(mut request) = ATTESTATION_REQUEST.lock() {
+        *request = Some((nonce.to_string(), pcrs));
+    }
+}
+
 /// Start heartbeat (ping + data every 30 seconds)
 #[tauri::command]
 pub async fn start_heartbeat(app: AppHandle) -> Result<(), String> {
@@ -459,9 +516,47 @@ pub async fn start_heartbeat(app: AppHandle) -> Result<(), String> {
                 }
             };
             
+            // Pull the latest signature bundle before the first scan
+            if let Err(e) = signatures::refresh(&api_client, &token).await {
+                println!("[Iris] Signature update failed (using v{}): {}", signatures::version(), e);
+            }
+            if let Err(e) = whitelist::refresh(&api_client, &token).await {
+                println!("[Iris] Whitelist sync failed: {}", e);
+            }
+            
             let security = hardware::get_full_security_status();
             let hardware_id = hardware::generate_hardware_id();
             
+            // Register the fingerprint breakdown and bind the hardware id to the TPM attestation key (once per launch)
+            let identity = match attestation::identity() {
+                Ok(identity) => serde_json::to_value(identity).ok(),
+                Err(e) => {
+                    println!("[Iris] No TPM attestation key: {}", e);
+                    None
+                }
+            };
+            // Component history since the first launch: frequent serial/MAC changes mean a spoofer
+            let hardware_fingerprint = fingerprint::fingerprint();
+            let churn = match journal::record_session(&hardware_fingerprint) {
+                Ok(report) => serde_json::to_value(report).ok(),
+                Err(e) => {
+                    println!("[Iris] Hardware journal unavailable: {}", e);
+                    None
+                }
+            };
+            let system_info = serde_json::to_value(&security).unwrap_or_default();
+            let fingerprint = serde_json::to_value(&hardware_fingerprint).unwrap_or_default();
+            match api_client.register_hardware(&token, &hardware_id, system_info, fingerprint, identity, churn).await {
+                Ok(response) if response.success => {
+                    println!("[Iris] Hardware registered");
+                    if let Some(data) = response.data {
+                        store_attestation_request(&data);
+                    }
+                }
+                Ok(response) => println!("[Iris] Hardware registration refused: {:?}", response.message),
+                Err(e) => println!("[Iris] Hardware registration failed: {}", e),
+            }
+            
             println!("[Iris] Sending initial security status...");
             println!("[Iris] TPM: present={}, enabled={}", security.tpm.present, security.tpm.enabled);
             println!("[Iris] SecureBoot: {}", security.secure_boot.enabled);
@@ -481,6 +576,9 @@ pub async fn start_heartbeat(app: AppHandle) -> Result<(), String> {
                     "version": security.tpm.version
                 },
                 "secureBoot": security.secure_boot.enabled,
+                "secureBootSetupMode": security.secure_boot.setup_mode,
+                "legacyBios": security.secure_boot.legacy_bios,
+                "secureBootKeys": security.secure_boot.keys,
                 "virtualization": security.virtualization.enabled,
                 "iommu": security.virtualization.iommu,
                 "kernelDmaProtection": security.virtualization.kernel_dma_protection,
@@ -492,37 +590,9 @@ pub async fn start_heartbeat(app: AppHandle) -> Result<(), String> {
             
             // On connection: immediately send ALL data (processes, USB, detection modules)
             // Screenshots only if scan mode is already enabled
+            // The only forced run: later captures reuse results of detectors that are not due
             println!("[Iris] Collecting initial data on connection...");
-            let cheat_detection = hardware::detect_cheats();
-            let processes = hardware::get_all_processes();
-            let usb_devices = hardware::get_all_usb_devices();
-            let network_monitor = hardware::check_network_monitor();
-            let registry_scan = hardware::scan_registry();
-            let driver_integrity = hardware::check_driver_integrity();
-            let macro_detection = hardware::detect_macros();
-            let overlay_detection = hardware::detect_overlays();
-            let dll_injection = hardware::detect_dll_injection();
-            let vm_detection = hardware::detect_vm();
-            let cloud_pc_detection = hardware::detect_cloud_pc();
-            let cheat_window_detection = hardware::detect_cheat_windows();
-            
-            println!("[Iris] Initial data: {} processes, {} USB devices", processes.len(), usb_devices.len());
-            
-            let initial_system_info = serde_json::json!({
-                "cheatDetection": cheat_detection,
-                "processes": processes,
-                "usbDevices": usb_devices,
-                "networkMonitor": network_monitor,
-                "registryScan": registry_scan,
-                "driverIntegrity": driver_integrity,
-                "macroDetection": macro_detection,
-                "overlayDetection": overlay_detection,
-                "dllInjection": dll_injection,
-                "vmDetection": vm_detection,
-                "cloudPcDetection": cloud_pc_detection,
-                "cheatWindowDetection": cheat_window_detection,
-                "gameDetection": hardware::detect_game_with_activity()
-            });
+            let initial_system_info = serde_json::Value::Object(collect_detection_results(true));
             
             match api_client.send_heartbeat(&token, &hardware_id, security_json.clone(), Some(initial_system_info)).await {
                 Ok(response) => {
@@ -530,6 +600,7 @@ pub async fn start_heartbeat(app: AppHandle) -> Result<(), String> {
                     
                     // Check if server requests immediate screenshots (scan mode + reconnection)
                     if let Some(data) = response.data {
+                        store_attestation_request(&data);
                         // Update scan mode state
                         if let Some(scan) = data.get("scanModeEnabled") {
                             if let Some(enabled) = scan.as_bool() {
@@ -548,20 +619,14 @@ pub async fn start_heartbeat(app: AppHandle) -> Result<(), String> {
                             if request_screenshots.as_bool() == Some(true) {
                                 println!("[Iris] Server requested immediate screenshots - capturing...");
                                 
-                                let cheat_detection = hardware::detect_cheats();
+                                let mut system_info = collect_detection_results(false);
                                 let screenshots = hardware::capture_all_screens_medium_quality();
-                                let processes = hardware::get_all_processes();
-                                let usb_devices = hardware::get_all_usb_devices();
-                                println!("[Iris] Captured {} screenshot(s), {} processes, {} USB devices", 
-                                         screenshots.len(), processes.len(), usb_devices.len());
+                                println!("[Iris] Captured {} screenshot(s), {} detection results",
+                                         screenshots.len(), system_info.len());
                                 
-                                let system_info = serde_json::json!({
-                                    "cheatDetection": cheat_detection,
-                                    "scanMode": true,
-                                    "screenshots": screenshots,
-                                    "processes": processes,
-                                    "usbDevices": usb_devices
-                                });
+                                system_info.insert("scanMode".to_string(), serde_json::json!(true));
+                                system_info.insert("screenshots".to_string(), serde_json::json!(screenshots));
+                                let system_info = serde_json::Value::Object(system_info);
                                 
                                 // Send screenshots immediately
                                 match api_client.send_heartbeat(&token, &hardware_id, security_json, Some(system_info)).await {
@@ -590,7 +655,7 @@ pub async fn start_heartbeat(app: AppHandle) -> Result<(), String> {
             }
             
             // Wait 30 seconds between each cycle
-            tokio::time::sleep(tokio::time::Duration::from_secs(30)).await;
+            tokio::time::sleep(detectors::HEARTBEAT_INTERVAL).await;
             
             if !HEARTBEAT_RUNNING.load(Ordering::SeqCst) {
                 break;
@@ -606,6 +671,18 @@ pub async fn start_heartbeat(app: AppHandle) -> Result<(), String> {
                 }
             };
             
+            if cycle_count % SIGNATURE_REFRESH_CYCLES == 0 {
+                if let Err(e) = signatures::refresh(&api_client, &token).await {
+                    println!("[Iris Heartbeat] Signature update failed (using v{}): {}", signatures::version(), e);
+                }
+            }
+
+            if cycle_count % WHITELIST_REFRESH_CYCLES == 0 {
+                if let Err(e) = whitelist::refresh(&api_client, &token).await {
+                    println!("[Iris Heartbeat] Whitelist sync failed: {}", e);
+                }
+            }
+            
             // Send ping every 30 seconds (alive signal)
             match api_client.send_ping(&token).await {
                 Ok(response) => {
@@ -642,6 +719,9 @@ pub async fn start_heartbeat(app: AppHandle) -> Result<(), String> {
                                         "version": security.tpm.version
                                     },
                                     "secureBoot": security.secure_boot.enabled,
+                                    "secureBootSetupMode": security.secure_boot.setup_mode,
+                                    "legacyBios": security.secure_boot.legacy_bios,
+                                    "secureBootKeys": security.secure_boot.keys,
                                     "virtualization": security.virtualization.enabled,
                                     "iommu": security.virtualization.iommu,
                                     "kernelDmaProtection": security.virtualization.kernel_dma_protection,
@@ -652,20 +732,14 @@ pub async fn start_heartbeat(app: AppHandle) -> Result<(), String> {
                                 });
                                 
                                 // Capture screenshots immediately
-                                let cheat_detection = hardware::detect_cheats();
+                                let mut system_info = collect_detection_results(false);
                                 let screenshots = hardware::capture_all_screens_medium_quality();
-                                let processes = hardware::get_all_processes();
-                                let usb_devices = hardware::get_all_usb_devices();
-                                println!("[Iris Ping] Captured {} screenshot(s), {} processes, {} USB devices", 
-                                         screenshots.len(), processes.len(), usb_devices.len());
+                                println!("[Iris Ping] Captured {} screenshot(s), {} detection results",
+                                         screenshots.len(), system_info.len());
                                 
-                                let system_info = serde_json::json!({
-                                    "cheatDetection": cheat_detection,
-                                    "scanMode": true,
-                                    "screenshots": screenshots,
-                                    "processes": processes,
-                                    "usbDevices": usb_devices
-                                });
+                                system_info.insert("scanMode".to_string(), serde_json::json!(true));
+                                system_info.insert("screenshots".to_string(), serde_json::json!(screenshots));
+                                let system_info = serde_json::Value::Object(system_info);
                                 
                                 // Send screenshots immediately via heartbeat
                                 match api_client.send_heartbeat(&token, &hardware_id, security_json, Some(system_info)).await {
@@ -726,6 +800,9 @@ pub async fn start_heartbeat(app: AppHandle) -> Result<(), String> {
                         "version": security.tpm.version
                     },
                     "secureBoot": security.secure_boot.enabled,
+                    "secureBootSetupMode": security.secure_boot.setup_mode,
+                    "legacyBios": security.secure_boot.legacy_bios,
+                    "secureBootKeys": security.secure_boot.keys,
                     "virtualization": security.virtualization.enabled,
                     "iommu": security.virtualization.iommu,
                     "kernelDmaProtection": security.virtualization.kernel_dma_protection,
@@ -736,28 +813,18 @@ pub async fn start_heartbeat(app: AppHandle) -> Result<(), String> {
                 });
                 
                 // Build system info (with screenshots if scan mode enabled)
-                // Always run new detection modules (network, registry, drivers, macros, overlays, dll injection, vm, cloud)
-                let network_monitor = hardware::check_network_monitor();
-                let registry_scan = hardware::scan_registry();
-                let driver_integrity = hardware::check_driver_integrity();
-                let macro_detection = hardware::detect_macros();
-                let overlay_detection = hardware::detect_overlays();
-                let dll_injection = hardware::detect_dll_injection();
-                let vm_detection = hardware::detect_vm();
-                let cloud_pc_detection = hardware::detect_cloud_pc();
-                let cheat_window_detection = hardware::detect_cheat_windows();
-                
-                println!("[Iris Heartbeat] Extended scans: network(vpn={},proxy={}), registry(traces={}), drivers({}), macros({}), overlays({}), dll({}), vm({}), cloud({}), cheat_windows({})",
-                         network_monitor.vpn_detected, network_monitor.proxy_detected,
-                         registry_scan.traces.len(), driver_integrity.suspicious_drivers.len(),
-                         macro_detection.detected_software.len(),
-                         overlay_detection.suspicious_overlays.len(),
-                         dll_injection.suspicious_dlls.len(),
-                         vm_detection.vm_detected,
-                         cloud_pc_detection.cloud_pc_detected,
-                         cheat_window_detection.cheats_found);
-                
-                let system_info = if scan_mode {
+                // Every registered detector runs over a single snapshot collected for this cycle
+                let mut system_info = collect_detection_results(false);
+
+                if system_info.get("cheatDetection").and_then(|c| c.get("found")).and_then(|f| f.as_bool()) == Some(true) {
+                    let cheat_detection = &system_info["cheatDetection"];
+                    println!("[Iris Heartbeat] CHEAT DETECTED: {}", cheat_detection);
+                    if let Some(window) = app.get_webview_window("main") {
+                        let _ = window.emit("cheat-detected", cheat_detection);
+                    }
+                }
+
+                if scan_mode {
                     // Check if 5 minutes have passed since last screenshot
                     let now_secs = std::time::SystemTime::now()
                         .duration_since(std::time::UNIX_EPOCH)
@@ -782,62 +849,21 @@ pub async fn start_heartbeat(app: AppHandle) -> Result<(), String> {
                         LAST_SCREENSHOT_TIME.store(now_secs, Ordering::SeqCst);
                         
                         // When scan mode: send screenshots
-                        Some(serde_json::json!({
-                            "scanMode": true,
-                            "screenshots": screenshots
-                        }))
+                        system_info.insert("screenshots".to_string(), serde_json::json!(screenshots));
                     } else {
-                        // Scan mode on but not time for screenshots - send minimal heartbeat
+                        // Scan mode on but not time for screenshots
                         let time_until_next = SCREENSHOT_INTERVAL_SECS - (now_secs - last_screenshot);
                         println!("[Iris Heartbeat] Scan mode: next screenshot in {} seconds", time_until_next);
-                        Some(serde_json::json!({
-                            "scanMode": true
-                        }))
-                    }
-                } else {
-                    // Without scan mode: send all data EXCEPT screenshots
-                    let cheat_detection = hardware::detect_cheats();
-                    let processes = hardware::get_all_processes();
-                    let usb_devices = hardware::get_all_usb_devices();
-                    
-                    println!("[Iris Heartbeat] Collecting data (no screenshots): {} processes, {} USB devices",
-                             processes.len(), usb_devices.len());
-                    
-                    if cheat_detection.found {
-                        println!("[Iris Heartbeat] CHEAT DETECTED: {:?}", cheat_detection);
-                        if let Some(window) = app.get_webview_window("main") {
-                            let _ = window.emit("cheat-detected", &cheat_detection);
-                        }
                     }
-                    
-                    Some(serde_json::json!({
-                        "cheatDetection": cheat_detection,
-                        "processes": processes,
-                        "usbDevices": usb_devices,
-                        "networkMonitor": network_monitor,
-                        "registryScan": registry_scan,
-                        "driverIntegrity": driver_integrity,
-                        "macroDetection": macro_detection,
-                        "overlayDetection": overlay_detection,
-                        "dllInjection": dll_injection,
-                        "vmDetection": vm_detection,
-                        "cloudPcDetection": cloud_pc_detection,
-                        "cheatWindowDetection": cheat_window_detection,
-                        "gameDetection": hardware::detect_game_with_activity()
-                    }))
-                };
+                    system_info.insert("scanMode".to_string(), serde_json::json!(true));
+                }
                 
                 // Add security changes to request if any
-                let final_system_info = if !security_changes.is_empty() {
+                if !security_changes.is_empty() {
                     println!("[Iris Heartbeat] Security state changed: {:?}", security_changes);
-                    let mut info = system_info.unwrap_or(serde_json::json!({}));
-                    if let Some(obj) = info.as_object_mut() {
-                        obj.insert("securityChanges".to_string(), serde_json::json!(security_changes));
-                    }
-                    Some(info)
-                } else {
-                    system_info
-                };
+                    system_info.insert("securityChanges".to_string(), serde_json::json!(security_changes));
+                }
+                let final_system_info = Some(serde_json::Value::Object(system_info));
                 
                 // Send heartbeat (with retry for screenshot payloads)
                 let mut send_result = api_client.send_heartbeat(
@@ -870,6 +896,7 @@ pub async fn start_heartbeat(app: AppHandle) -> Result<(), String> {
                         
                         // Check if server enabled/disabled scan mode
                         if let Some(data) = response.data {
+                            store_attestation_request(&data);
                             if let Some(scan) = data.get("scanModeEnabled") {
                                 if let Some(enabled) = scan.as_bool() {
                                     let previous = SCAN_MODE_ENABLED.load(Ordering::SeqCst);
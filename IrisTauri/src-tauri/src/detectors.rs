@@ -0,0 +1,255 @@
+//! Detector registry - pluggable detection modules run over a shared SystemSnapshot
+//!
+//! Every heartbeat path calls `run_all`, so the set of keys sent to the server is
+//! always the same. Detectors whose interval has not elapsed reuse their last result
+//! (and its risk findings).
+
+use crate::{eventlog, hardware, patterns, pci, reputation, spoofing, usbtopology};
+use crate::risk::{Finding, RiskSource};
+use crate::snapshot::SystemSnapshot;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Heartbeat cycle length (matches the 30s loop in commands.rs)
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Rough cost of running a detector
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CostClass {
+    /// Pure filtering over the snapshot
+    Cheap,
+    /// Extra system queries (registry lookups, small file reads)
+    Moderate,
+    /// Walks large parts of the system (registry trees, files on disk)
+    Expensive,
+}
+
+/// A detection module
+pub trait Detector: Send + Sync {
+    /// Human readable name (logs)
+    fn name(&self) -> &'static str;
+    /// JSON key used in the heartbeat systemInfo
+    fn key(&self) -> &'static str;
+    /// Minimum time between two runs
+    fn default_interval(&self) -> Duration;
+    fn cost(&self) -> CostClass;
+    fn run(&self, snapshot: &SystemSnapshot) -> DetectorOutput;
+}
+
+/// Result of one detector run
+pub struct DetectorOutput {
+    pub value: serde_json::Value, // Raw result sent under the detector key
+    pub findings: Vec<Finding>,   // Input of the risk engine
+}
+
+/// Detector backed by a plain function returning a serializable result
+pub struct FnDetector<T: Serialize + RiskSource> {
+    name: &'static str,
+    key: &'static str,
+    interval: Duration,
+    cost: CostClass,
+    func: fn(&SystemSnapshot) -> T,
+}
+
+impl<T: Serialize + RiskSource> FnDetector<T> {
+    pub fn new(
+        name: &'static str,
+        key: &'static str,
+        interval: Duration,
+        cost: CostClass,
+        func: fn(&SystemSnapshot) -> T,
+    ) -> Self {
+        Self { name, key, interval, cost, func }
+    }
+}
+
+impl<T: Serialize + RiskSource> Detector for FnDetector<T> {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn key(&self) -> &'static str {
+        self.key
+    }
+
+    fn default_interval(&self) -> Duration {
+        self.interval
+    }
+
+    fn cost(&self) -> CostClass {
+        self.cost
+    }
+
+    fn run(&self, snapshot: &SystemSnapshot) -> DetectorOutput {
+        let result = (self.func)(snapshot);
+        DetectorOutput {
+            value: serde_json::to_value(&result).unwrap_or(serde_json::Value::Null),
+            findings: result.findings(),
+        }
+    }
+}
+
+/// Results of every detector for one heartbeat
+#[derive(Default)]
+pub struct DetectionResults {
+    pub values: serde_json::Map<String, serde_json::Value>,
+    pub findings: Vec<Finding>,
+}
+
+/// Registered detectors and their last results
+pub struct DetectorRegistry {
+    detectors: Vec<Box<dyn Detector>>,
+    last_results: HashMap<&'static str, (Instant, DetectorOutput)>,
+}
+
+impl DetectorRegistry {
+    pub fn new() -> Self {
+        Self {
+            detectors: Vec::new(),
+            last_results: HashMap::new(),
+        }
+    }
+
+    /// Registry with every built-in detector
+    pub fn with_defaults() -> Self {
+        let mut registry = Self::new();
+
+        registry.register(FnDetector::new("Cheat devices/processes", "cheatDetection", HEARTBEAT_INTERVAL, CostClass::Cheap, hardware::detect_cheats));
+        registry.register(FnDetector::new("Processes", "processes", HEARTBEAT_INTERVAL, CostClass::Cheap, |s| s.processes.clone()));
+        registry.register(FnDetector::new("USB devices", "usbDevices", HEARTBEAT_INTERVAL, CostClass::Cheap, |s| s.usb_devices.clone()));
+        registry.register(FnDetector::new("Network monitor", "networkMonitor", HEARTBEAT_INTERVAL, CostClass::Moderate, hardware::check_network_monitor));
+        registry.register(FnDetector::new("Registry scan", "registryScan", Duration::from_secs(120), CostClass::Expensive, |_| hardware::scan_registry()));
+        registry.register(FnDetector::new("HWID consistency", "hwidConsistency", Duration::from_secs(300), CostClass::Moderate, |_| spoofing::check_hwid_consistency()));
+        registry.register(FnDetector::new("DMA devices", "dmaDeviceDetection", HEARTBEAT_INTERVAL, CostClass::Cheap, |s| pci::detect_dma_devices(&s.pci_devices)));
+        registry.register(FnDetector::new("USB topology", "usbTopology", HEARTBEAT_INTERVAL, CostClass::Cheap, |s| usbtopology::detect(&s.usb_devices, s.collected_at)));
+        registry.register(FnDetector::new("Driver integrity", "driverIntegrity", HEARTBEAT_INTERVAL, CostClass::Moderate, hardware::check_driver_integrity));
+        registry.register(FnDetector::new("Macro detection", "macroDetection", HEARTBEAT_INTERVAL, CostClass::Moderate, hardware::detect_macros));
+        registry.register(FnDetector::new("Overlay detection", "overlayDetection", HEARTBEAT_INTERVAL, CostClass::Cheap, hardware::detect_overlays));
+        registry.register(FnDetector::new("DLL injection", "dllInjection", HEARTBEAT_INTERVAL, CostClass::Cheap, hardware::detect_dll_injection));
+        registry.register(FnDetector::new("VM detection", "vmDetection", HEARTBEAT_INTERVAL, CostClass::Moderate, hardware::detect_vm));
+        registry.register(FnDetector::new("Cloud PC detection", "cloudPcDetection", HEARTBEAT_INTERVAL, CostClass::Moderate, hardware::detect_cloud_pc));
+        registry.register(FnDetector::new("Cheat windows", "cheatWindowDetection", HEARTBEAT_INTERVAL, CostClass::Cheap, hardware::detect_cheat_windows));
+        registry.register(FnDetector::new("Hash reputation", "hashReputation", HEARTBEAT_INTERVAL, CostClass::Moderate, reputation::check_hash_reputation));
+        registry.register(FnDetector::new("Byte patterns", "bytePatternScan", Duration::from_secs(60), CostClass::Expensive, patterns::scan_executables));
+        registry.register(FnDetector::new("Measured boot", "measuredBoot", Duration::from_secs(600), CostClass::Moderate, |_| eventlog::check_measured_boot()));
+        registry.register(FnDetector::new("Game detection", "gameDetection", HEARTBEAT_INTERVAL, CostClass::Cheap, hardware::detect_game_with_activity));
+
+        registry
+    }
+
+    pub fn register<D: Detector + 'static>(&mut self, detector: D) {
+        self.detectors.push(Box::new(detector));
+    }
+
+    /// Run every detector that is due (or all of them when `force` is set) and
+    /// return the results keyed by wire key. Detectors that are not due reuse
+    /// their previous result so every heartbeat carries the same keys.
+    pub fn run_all(&mut self, snapshot: &SystemSnapshot, force: bool) -> DetectionResults {
+        let now = Instant::now();
+        let mut results = DetectionResults::default();
+
+        for detector in &self.detectors {
+            // Small margin so a 30s interval is not skipped by timer jitter
+            let due = match self.last_results.get(detector.key()) {
+                Some((last_run, _)) => now.duration_since(*last_run) + Duration::from_secs(1) >= detector.default_interval(),
+                None => true,
+            };
+
+            if force || due {
+                let output = detector.run(snapshot);
+                // Log only when the number of findings changes
+                let previous = self.last_results.get(detector.key()).map(|(_, last)| last.findings.len());
+                if previous.unwrap_or(0) != output.findings.len() {
+                    println!("[Detectors] {}: {} finding(s)", detector.name(), output.findings.len());
+                }
+                self.last_results.insert(detector.key(), (now, output));
+            }
+
+            if let Some((_, output)) = self.last_results.get(detector.key()) {
+                results.values.insert(detector.key().to_string(), output.value.clone());
+                results.findings.extend(output.findings.iter().cloned());
+            }
+        }
+
+        results
+    }
+}
+
+impl Default for DetectorRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref REGISTRY: Mutex<DetectorRegistry> = Mutex::new(DetectorRegistry::with_defaults());
+}
+
+/// Run the global registry over a snapshot
+pub fn run_all(snapshot: &SystemSnapshot, force: bool) -> DetectionResults {
+    match REGISTRY.lock() {
+        Ok(mut registry) => registry.run_all(snapshot, force),
+        Err(_) => DetectionResults::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hardware::VmDetectionResult;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static SLOW_RUNS: AtomicUsize = AtomicUsize::new(0);
+
+    fn keys(results: &DetectionResults) -> Vec<&str> {
+        results.values.keys().map(|k| k.as_str()).collect()
+    }
+
+    /// Detector with a finding, due once an hour
+    fn slow(_: &SystemSnapshot) -> VmDetectionResult {
+        SLOW_RUNS.fetch_add(1, Ordering::SeqCst);
+        VmDetectionResult { vm_detected: true, vm_type: Some("QEMU/KVM".to_string()), risk_score: 90, ..Default::default() }
+    }
+
+    #[test]
+    fn detectors_that_are_not_due_reuse_their_result() {
+        let mut registry = DetectorRegistry::new();
+        registry.register(FnDetector::new("Processes", "processes", HEARTBEAT_INTERVAL, CostClass::Cheap, |s| s.processes.clone()));
+        registry.register(FnDetector::new("Slow", "slow", Duration::from_secs(3600), CostClass::Expensive, slow));
+        let snapshot = SystemSnapshot::default();
+
+        let first = registry.run_all(&snapshot, false);
+        let second = registry.run_all(&snapshot, false);
+        assert_eq!(SLOW_RUNS.load(Ordering::SeqCst), 1);
+        assert_eq!(keys(&first), ["processes", "slow"]);
+        assert_eq!(keys(&second), keys(&first));
+        assert_eq!(second.values["slow"], first.values["slow"]);
+        assert_eq!(second.findings.len(), first.findings.len());
+        assert!(!second.findings.is_empty());
+
+        let forced = registry.run_all(&snapshot, true);
+        assert_eq!(SLOW_RUNS.load(Ordering::SeqCst), 2);
+        assert_eq!(keys(&forced), keys(&first));
+    }
+
+    #[test]
+    fn every_heartbeat_sends_every_key() {
+        let mut registry = DetectorRegistry::with_defaults();
+        let mut registered: Vec<&str> = registry.detectors.iter().map(|d| d.key()).collect();
+        registered.sort();
+        let count = registered.len();
+        registered.dedup();
+        assert_eq!(registered.len(), count, "duplicate detector key");
+
+        // First (scheduled) run, a run where only some detectors are due, a forced run
+        let snapshot = SystemSnapshot::default();
+        for force in [false, false, true] {
+            let results = registry.run_all(&snapshot, force);
+            let mut sent = keys(&results);
+            sent.sort();
+            assert_eq!(sent, registered, "force={}", force);
+        }
+    }
+}
@@ -0,0 +1,391 @@
+//! Efisig module - Secure Boot key databases (PK, KEK, db, dbx)
+//!
+//! Each variable holds a sequence of EFI_SIGNATURE_LIST: a signature type guid,
+//! the list/header/entry sizes, then entries made of an owner guid and the data
+//! (a DER certificate for EFI_CERT_X509, a digest for EFI_CERT_SHA256). "Enabled"
+//! Secure Boot only means something with the vendor key set: a self-enrolled
+//! platform key can sign any bootloader. Certificate names are hints, like the
+//! known roots of authenticode - a forged "Microsoft" subject is not detected.
+//! The variables are only readable on Linux (Windows requires the
+//! SeSystemEnvironmentPrivilege), so elsewhere the keys stay unknown.
+
+#![cfg_attr(not(target_os = "linux"), allow(dead_code))]
+
+use crate::authenticode;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// EFI_GLOBAL_VARIABLE vendor guid (SecureBoot, SetupMode, PK, KEK)
+pub const GLOBAL_VARIABLE_GUID: &str = "8be4df61-93ca-11d2-aa0d-00e098032b8c";
+/// EFI_IMAGE_SECURITY_DATABASE vendor guid (db, dbx)
+pub const IMAGE_SECURITY_DATABASE_GUID: &str = "d719b2cb-3d3a-4596-a3bc-dad00e67656f";
+
+const CERT_X509_GUID: &str = "a5c059a1-94e4-4aa7-87b5-ab155c2bf072";
+const CERT_SHA256_GUID: &str = "c1c41626-504c-4092-aca9-41f936934328";
+
+/// EFI_SIGNATURE_LIST header: type guid + ListSize + HeaderSize + SignatureSize
+const LIST_HEADER_SIZE: usize = 28;
+const OWNER_SIZE: usize = 16;
+
+/// Microsoft KEK CAs (presence means Windows updates can still manage db/dbx)
+const MICROSOFT_KEK: &[&str] = &["Microsoft Corporation KEK CA 2011", "Microsoft Corporation KEK 2K CA 2023"];
+
+/// Organizations that ship platform keys and db entries (matched on whole words)
+const KNOWN_VENDORS: &[&str] = &[
+    "Microsoft", "ASUSTeK", "Lenovo", "Dell", "HP", "Hewlett-Packard", "Acer", "Micro-Star", "MSI",
+    "GIGABYTE", "ASRock", "Intel", "AMD", "Fujitsu", "Toshiba", "Dynabook", "Samsung", "Supermicro",
+    "Apple", "Google", "Razer", "Framework", "Panasonic", "VAIO", "Huawei", "Xiaomi", "Medion",
+    "LG Electronics", "Alienware", "Getac", "Clevo", "American Megatrends", "Insyde", "Phoenix",
+    "VMware", "Oracle", "Red Hat", "Canonical", "SUSE", "Amazon",
+];
+
+/// Subject markers of firmware sample keys that leaked into production (PKfail)
+const TEST_KEY_MARKERS: &[&str] = &["DO NOT TRUST", "DO NOT SHIP"];
+
+/// Classification of the enrolled key set
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum KeySetClass {
+    /// OEM platform key with the Microsoft KEK, db limited to vendor certificates
+    Standard,
+    /// Keys enrolled by someone else than the OEM or Microsoft
+    Custom,
+    /// Platform key is a firmware sample key (private key is public)
+    TestKey,
+    /// No platform key: setup mode, nothing is enforced
+    NoPlatformKey,
+}
+
+/// Certificate entry of a signature database
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct EfiCertificate {
+    pub subject: String,
+    pub issuer: String,
+    pub sha256: String,
+    pub owner: String, // Owner guid of the entry
+}
+
+/// Secure Boot key set found in the firmware variables
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SecureBootKeys {
+    pub class: KeySetClass,
+    pub reasons: Vec<String>, // Why the key set is not standard
+    pub pk: Vec<EfiCertificate>,
+    pub kek: Vec<EfiCertificate>,
+    pub db: Vec<EfiCertificate>,
+    pub db_hashes: usize, // SHA-256 image digests allowed by db
+    pub dbx_count: usize, // Revoked digests and certificates
+    pub microsoft_kek: bool,
+}
+
+/// Data of one signature entry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignatureData<'a> {
+    X509(&'a [u8]),
+    Sha256(&'a [u8]),
+    Other(String, &'a [u8]), // Signature type guid
+}
+
+/// Entry of an EFI_SIGNATURE_LIST
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EfiSignature<'a> {
+    pub owner: String,
+    pub data: SignatureData<'a>,
+}
+
+/// Entries of a variable made of EFI_SIGNATURE_LIST structures
+pub fn parse_signature_lists(mut data: &[u8]) -> Result<Vec<EfiSignature<'_>>, String> {
+    let mut signatures = Vec::new();
+
+    while !data.is_empty() {
+        if data.len() < LIST_HEADER_SIZE {
+            return Err(format!("Truncated signature list header ({} bytes)", data.len()));
+        }
+        let kind = format_guid(&data[0..16]);
+        let list_size = le_u32(&data[16..20]) as usize;
+        let header_size = le_u32(&data[20..24]) as usize;
+        let signature_size = le_u32(&data[24..28]) as usize;
+
+        let entries_start = LIST_HEADER_SIZE.checked_add(header_size).filter(|&start| start <= list_size)
+            .ok_or("Signature list header larger than the list")?;
+        if list_size > data.len() {
+            return Err(format!("Signature list of {} bytes, {} left", list_size, data.len()));
+        }
+        if signature_size <= OWNER_SIZE || !(list_size - entries_start).is_multiple_of(signature_size) {
+            return Err(format!("Invalid signature size {}", signature_size));
+        }
+
+        for entry in data[entries_start..list_size].chunks(signature_size) {
+            let (owner, value) = entry.split_at(OWNER_SIZE);
+            let data = match kind.as_str() {
+                CERT_X509_GUID => SignatureData::X509(value),
+                CERT_SHA256_GUID => SignatureData::Sha256(value),
+                _ => SignatureData::Other(kind.clone(), value),
+            };
+            signatures.push(EfiSignature { owner: format_guid(owner), data });
+        }
+        data = &data[list_size..];
+    }
+
+    Ok(signatures)
+}
+
+/// Classify the key set from the raw PK, KEK, db and dbx values
+pub fn inspect(pk: &[u8], kek: &[u8], db: &[u8], dbx: &[u8]) -> SecureBootKeys {
+    let mut reasons = Vec::new();
+    let mut parse = |name: &str, data| match parse_signature_lists(data) {
+        Ok(signatures) => signatures,
+        Err(e) => {
+            reasons.push(format!("Malformed {}: {}", name, e));
+            Vec::new()
+        }
+    };
+    let pk = parse("PK", pk);
+    let kek = parse("KEK", kek);
+    let db = parse("db", db);
+    let dbx_count = parse("dbx", dbx).len();
+
+    let mut keys = SecureBootKeys {
+        class: KeySetClass::Standard,
+        reasons: Vec::new(),
+        pk: certificates(&pk),
+        kek: certificates(&kek),
+        db: certificates(&db),
+        db_hashes: db.iter().filter(|s| matches!(s.data, SignatureData::Sha256(_))).count(),
+        dbx_count,
+        microsoft_kek: false,
+    };
+    keys.microsoft_kek = keys.kek.iter().any(|cert| MICROSOFT_KEK.iter().any(|name| cert.subject.contains(name)));
+    keys.class = classify(&keys, &mut reasons);
+    keys.reasons = reasons;
+
+    println!("[EFI] Key set: {:?} (PK={}, KEK={}, db={}+{} hashes, dbx={})",
+             keys.class, keys.pk.len(), keys.kek.len(), keys.db.len(), keys.db_hashes, keys.dbx_count);
+    keys
+}
+
+/// Class of the key set; reasons already holds the parse errors
+fn classify(keys: &SecureBootKeys, reasons: &mut Vec<String>) -> KeySetClass {
+    if keys.pk.is_empty() {
+        reasons.push("No platform key enrolled".to_string());
+        return KeySetClass::NoPlatformKey;
+    }
+    if let Some(cert) = keys.pk.iter().find(|cert| is_test_key(cert)) {
+        reasons.push(format!("Platform key '{}' is a firmware sample key", cert.subject));
+        return KeySetClass::TestKey;
+    }
+
+    for cert in keys.pk.iter().filter(|cert| !is_known_vendor(cert)) {
+        reasons.push(format!("Platform key '{}' is not from a known vendor", cert.subject));
+    }
+    if !keys.kek.is_empty() && !keys.microsoft_kek {
+        reasons.push("KEK has no Microsoft KEK CA".to_string());
+    }
+    for (name, certs) in [("KEK", &keys.kek), ("db", &keys.db)] {
+        for cert in certs.iter().filter(|cert| !is_known_vendor(cert)) {
+            reasons.push(format!("{} trusts '{}'", name, cert.subject));
+        }
+    }
+
+    if reasons.is_empty() { KeySetClass::Standard } else { KeySetClass::Custom }
+}
+
+/// Certificate entries (digests are only counted)
+fn certificates(signatures: &[EfiSignature]) -> Vec<EfiCertificate> {
+    signatures.iter()
+        .filter_map(|signature| match signature.data {
+            SignatureData::X509(der) => Some((der, &signature.owner)),
+            _ => None,
+        })
+        .map(|(der, owner)| {
+            let (subject, issuer) = match authenticode::certificate_info(der) {
+                Ok(info) => (info.subject, info.issuer),
+                Err(e) => (format!("<unparsed: {}>", e), String::new()),
+            };
+            EfiCertificate {
+                subject,
+                issuer,
+                sha256: hex::encode(Sha256::digest(der)),
+                owner: owner.clone(),
+            }
+        })
+        .collect()
+}
+
+fn is_test_key(cert: &EfiCertificate) -> bool {
+    let names = format!("{} {}", cert.subject, cert.issuer).to_uppercase();
+    TEST_KEY_MARKERS.iter().any(|marker| names.contains(marker))
+}
+
+/// Whole-word match of a known vendor in the subject ("HP" matches "HP Inc." only)
+fn is_known_vendor(cert: &EfiCertificate) -> bool {
+    let subject = format!(" {} ", words(&cert.subject));
+    KNOWN_VENDORS.iter().any(|vendor| subject.contains(&format!(" {} ", words(vendor))))
+}
+
+fn words(text: &str) -> String {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Mixed-endian EFI_GUID ("8be4df61-93ca-11d2-aa0d-00e098032b8c")
+fn format_guid(bytes: &[u8]) -> String {
+    format!(
+        "{:08x}-{:04x}-{:04x}-{}-{}",
+        le_u32(&bytes[0..4]),
+        u16::from_le_bytes([bytes[4], bytes[5]]),
+        u16::from_le_bytes([bytes[6], bytes[7]]),
+        hex::encode(&bytes[8..10]),
+        hex::encode(&bytes[10..16])
+    )
+}
+
+fn le_u32(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::Path;
+
+    const MICROSOFT_OWNER: &str = "77fa9abd-0359-4d32-bd60-28f4e78f784b";
+
+    /// Value of a fixture variable (efivarfs file without the attribute mask)
+    fn variable(case: &str, name: &str, guid: &str) -> Vec<u8> {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/efivars").join(case).join("efivars")
+            .join(format!("{}-{}", name, guid));
+        match fs::read(&path) {
+            Ok(raw) => raw[4..].to_vec(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    fn inspect_case(case: &str) -> SecureBootKeys {
+        inspect(
+            &variable(case, "PK", GLOBAL_VARIABLE_GUID),
+            &variable(case, "KEK", GLOBAL_VARIABLE_GUID),
+            &variable(case, "db", IMAGE_SECURITY_DATABASE_GUID),
+            &variable(case, "dbx", IMAGE_SECURITY_DATABASE_GUID),
+        )
+    }
+
+    /// One EFI_SIGNATURE_LIST of same-sized entries
+    fn signature_list(kind: &[u8; 16], entries: &[&[u8]]) -> Vec<u8> {
+        let signature_size = OWNER_SIZE + entries[0].len();
+        let list_size = LIST_HEADER_SIZE + signature_size * entries.len();
+        let mut list = kind.to_vec();
+        for value in [list_size, 0, signature_size] {
+            list.extend_from_slice(&(value as u32).to_le_bytes());
+        }
+        for entry in entries {
+            list.extend_from_slice(&[0x11; OWNER_SIZE]);
+            list.extend_from_slice(entry);
+        }
+        list
+    }
+
+    const SHA256_KIND: [u8; 16] = [
+        0x26, 0x16, 0xc4, 0xc1, 0x4c, 0x50, 0x92, 0x40, 0xac, 0xa9, 0x41, 0xf9, 0x36, 0x93, 0x43, 0x28,
+    ];
+
+    #[test]
+    fn guids_are_mixed_endian() {
+        assert_eq!(format_guid(&SHA256_KIND), CERT_SHA256_GUID);
+    }
+
+    #[test]
+    fn parses_digest_lists() {
+        let data = [signature_list(&SHA256_KIND, &[&[1; 32], &[2; 32]]), signature_list(&[9; 16], &[&[3; 4]])].concat();
+        let signatures = parse_signature_lists(&data).unwrap();
+        assert_eq!(signatures.len(), 3);
+        assert_eq!(signatures[0].owner, "11111111-1111-1111-1111-111111111111");
+        assert_eq!(signatures[1].data, SignatureData::Sha256(&[2; 32]));
+        assert_eq!(signatures[2].data, SignatureData::Other("09090909-0909-0909-0909-090909090909".to_string(), &[3; 4]));
+        assert!(parse_signature_lists(&[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn rejects_malformed_lists() {
+        let list = signature_list(&SHA256_KIND, &[&[1; 32]]);
+        assert!(parse_signature_lists(&list[..20]).unwrap_err().contains("Truncated"));
+        assert!(parse_signature_lists(&list[..list.len() - 1]).unwrap_err().contains("left"));
+
+        let mut oversized_header = list.clone();
+        oversized_header[20..24].copy_from_slice(&1000u32.to_le_bytes());
+        assert!(parse_signature_lists(&oversized_header).is_err());
+
+        // Entries that do not fill the list exactly, and entries without data
+        for signature_size in [40u32, 16, 0] {
+            let mut bad_size = list.clone();
+            bad_size[24..28].copy_from_slice(&signature_size.to_le_bytes());
+            assert!(parse_signature_lists(&bad_size).unwrap_err().contains("Invalid signature size"));
+        }
+    }
+
+    #[test]
+    fn oem_keys_are_standard() {
+        let keys = inspect_case("standard");
+        assert_eq!(keys.class, KeySetClass::Standard, "{:?}", keys.reasons);
+        assert!(keys.reasons.is_empty());
+        assert!(keys.microsoft_kek);
+        assert_eq!(keys.pk[0].subject, "CN=ASUSTeK MotherBoard PK Certificate, O=ASUSTeK COMPUTER INC.");
+        assert_eq!(keys.kek[0].owner, MICROSOFT_OWNER);
+        assert_eq!(keys.db.len(), 1);
+        assert_eq!(keys.db_hashes, 2);
+        assert_eq!(keys.dbx_count, 3);
+    }
+
+    #[test]
+    fn self_enrolled_keys_are_custom() {
+        let keys = inspect_case("custom");
+        assert_eq!(keys.class, KeySetClass::Custom);
+        assert!(!keys.microsoft_kek);
+        assert!(keys.reasons.contains(&"Platform key 'CN=My Platform Key' is not from a known vendor".to_string()));
+        assert!(keys.reasons.contains(&"KEK has no Microsoft KEK CA".to_string()));
+        assert!(keys.reasons.contains(&"db trusts 'CN=My Platform Key'".to_string()));
+        // The Microsoft db certificate kept next to the own key is not a reason
+        assert_eq!(keys.db.len(), 2);
+        assert_eq!(keys.reasons.len(), 4, "{:?}", keys.reasons);
+    }
+
+    #[test]
+    fn sample_platform_key_is_detected() {
+        let keys = inspect_case("testkey");
+        assert_eq!(keys.class, KeySetClass::TestKey);
+        assert_eq!(keys.reasons, vec!["Platform key 'CN=DO NOT TRUST - AMI Test PK' is a firmware sample key".to_string()]);
+    }
+
+    #[test]
+    fn setup_mode_has_no_platform_key() {
+        let keys = inspect_case("setup");
+        assert_eq!(keys.class, KeySetClass::NoPlatformKey);
+        assert!(keys.pk.is_empty());
+        assert!(keys.microsoft_kek);
+    }
+
+    #[test]
+    fn truncated_db_is_reported() {
+        let keys = inspect_case("truncated");
+        assert!(keys.db.is_empty());
+        assert!(keys.reasons.iter().any(|reason| reason.starts_with("Malformed db: Signature list of")), "{:?}", keys.reasons);
+        assert_eq!(keys.class, KeySetClass::Custom);
+    }
+
+    #[test]
+    fn vendors_match_whole_words() {
+        let cert = |subject: &str| EfiCertificate { subject: subject.to_string(), ..Default::default() };
+        assert!(is_known_vendor(&cert("HP Inc. PK")));
+        assert!(is_known_vendor(&cert("Lenovo Ltd. PK CA 2012")));
+        assert!(!is_known_vendor(&cert("SHPK Root")));
+        assert!(!is_known_vendor(&cert("Microsoftware PK")));
+        assert!(is_test_key(&EfiCertificate { issuer: "do not ship".to_string(), ..Default::default() }));
+    }
+}
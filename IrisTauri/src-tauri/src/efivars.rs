@@ -0,0 +1,125 @@
+//! Efivars module - Linux firmware status from /sys/firmware/efi
+//!
+//! A system booted through legacy BIOS (or CSM) has no `/sys/firmware/efi`. On UEFI
+//! the global variables are files of `efivars/<Name>-<vendor guid>`: a 4-byte
+//! attribute mask followed by the value. `SecureBoot` is 1 when signatures are
+//! enforced, `SetupMode` is 1 while no platform key is enrolled (the firmware then
+//! accepts any custom keys). PK/KEK/db/dbx are handed to efisig for the key set.
+//! The root is a parameter so a fixture tree can be read.
+
+use crate::efisig::{self, SecureBootKeys, GLOBAL_VARIABLE_GUID, IMAGE_SECURITY_DATABASE_GUID};
+use crate::hardware::SecureBootStatus;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+
+/// Secure Boot status of the live system
+pub fn check_secure_boot() -> SecureBootStatus {
+    read_secure_boot(Path::new("/sys/firmware/efi"))
+}
+
+/// Secure Boot status from a `/sys/firmware/efi` tree
+pub fn read_secure_boot(efi_root: &Path) -> SecureBootStatus {
+    let mut status = SecureBootStatus::default();
+
+    if !efi_root.is_dir() {
+        status.legacy_bios = true;
+        println!("[EFI] No {} - legacy BIOS boot", efi_root.display());
+        return status;
+    }
+
+    // Firmware without Secure Boot support does not define the variable
+    if let Some(secure_boot) = read_global_u8(efi_root, "SecureBoot") {
+        status.supported = true;
+        status.enabled = secure_boot == 1;
+    }
+    status.setup_mode = read_global_u8(efi_root, "SetupMode");
+    if status.supported {
+        status.keys = read_keys(efi_root);
+    }
+
+    println!("[EFI] Secure Boot: supported={}, enabled={}, setup_mode={:?}",
+             status.supported, status.enabled, status.setup_mode);
+    status
+}
+
+/// Key set of PK, KEK, db and dbx (None when a variable cannot be read)
+fn read_keys(efi_root: &Path) -> Option<SecureBootKeys> {
+    let read = |name: &str, guid: &str| match read_variable(efi_root, name, guid) {
+        Ok(value) => Some(value),
+        Err(e) => {
+            println!("[EFI] {}", e);
+            None
+        }
+    };
+    Some(efisig::inspect(
+        &read("PK", GLOBAL_VARIABLE_GUID)?,
+        &read("KEK", GLOBAL_VARIABLE_GUID)?,
+        &read("db", IMAGE_SECURITY_DATABASE_GUID)?,
+        &read("dbx", IMAGE_SECURITY_DATABASE_GUID)?,
+    ))
+}
+
+/// Value of an EFI variable, attributes stripped (empty when not defined)
+pub fn read_variable(efi_root: &Path, name: &str, guid: &str) -> Result<Vec<u8>, String> {
+    let path = efi_root.join("efivars").join(format!("{}-{}", name, guid));
+    match fs::read(&path) {
+        Ok(raw) => Ok(raw.get(4..).unwrap_or_default().to_vec()),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(format!("Cannot read {}: {}", path.display(), e)),
+    }
+}
+
+fn read_global_u8(efi_root: &Path, name: &str) -> Option<u8> {
+    read_variable(efi_root, name, GLOBAL_VARIABLE_GUID).ok()?.first().copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::efisig::KeySetClass;
+    use std::path::PathBuf;
+
+    fn fixture(case: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/efivars").join(case)
+    }
+
+    #[test]
+    fn legacy_boot_has_no_efi_tree() {
+        let status = read_secure_boot(&fixture("missing"));
+        assert!(status.legacy_bios);
+        assert!(!status.supported);
+        assert!(status.keys.is_none());
+    }
+
+    #[test]
+    fn enabled_with_oem_keys() {
+        let status = read_secure_boot(&fixture("standard"));
+        assert!(status.supported && status.enabled);
+        assert_eq!(status.setup_mode, Some(0));
+        assert_eq!(status.keys.unwrap().class, KeySetClass::Standard);
+    }
+
+    #[test]
+    fn setup_mode_is_reported() {
+        let status = read_secure_boot(&fixture("setup"));
+        assert!(status.supported && !status.enabled);
+        assert_eq!(status.setup_mode, Some(1));
+        assert_eq!(status.keys.unwrap().class, KeySetClass::NoPlatformKey);
+    }
+
+    #[test]
+    fn firmware_without_secure_boot() {
+        let status = read_secure_boot(&fixture("unsupported"));
+        assert!(!status.legacy_bios && !status.supported);
+        assert_eq!(status.setup_mode, None);
+        assert!(status.keys.is_none());
+    }
+
+    #[test]
+    fn variables_lose_their_attributes() {
+        let root = fixture("standard");
+        assert_eq!(read_variable(&root, "SecureBoot", GLOBAL_VARIABLE_GUID).unwrap(), vec![1]);
+        assert!(read_variable(&root, "PKDefault", GLOBAL_VARIABLE_GUID).unwrap().is_empty());
+    }
+}
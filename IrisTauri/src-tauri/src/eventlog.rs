@@ -0,0 +1,731 @@
+//! Event log module - TCG PC Client measured boot log (crypto-agile and SHA-1)
+//!
+//! The firmware, boot manager and loader record every measurement they extend into
+//! the TPM. Replaying the log gives the expected PCR values; when they match the
+//! TPM the log is genuine and tells what actually booted: boot applications and
+//! drivers (PCR 2/4), the Secure Boot configuration and the authorities used to
+//! verify images (PCR 7), and the Windows boot configuration (SIPA events, PCR 12-14)
+//! with test-signing and debugger flags. Sources are files so a captured log can
+//! be parsed offline:
+//! - Linux: `/sys/kernel/security/tpm0/binary_bios_measurements` (root), PCRs from
+//!   `/sys/class/tpm/tpm0/pcr-sha256/<n>` (Linux 5.12+)
+//! - Windows: newest `C:\Windows\Logs\MeasuredBoot\*.log` (administrators); the
+//!   PCRs are not read there, so a Windows log is at best Unverified
+
+use crate::authenticode;
+use ring::digest;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+// Event types (TCG PC Client Platform Firmware Profile)
+const EV_NO_ACTION: u32 = 0x0000_0003;
+const EV_ACTION: u32 = 0x0000_0005;
+const EV_EVENT_TAG: u32 = 0x0000_0006;
+const EV_EFI_VARIABLE_DRIVER_CONFIG: u32 = 0x8000_0001;
+const EV_EFI_BOOT_SERVICES_APPLICATION: u32 = 0x8000_0003;
+const EV_EFI_BOOT_SERVICES_DRIVER: u32 = 0x8000_0004;
+const EV_EFI_RUNTIME_SERVICES_DRIVER: u32 = 0x8000_0005;
+const EV_EFI_ACTION: u32 = 0x8000_0007;
+const EV_EFI_VARIABLE_AUTHORITY: u32 = 0x8000_00E0;
+
+// TPM_ALG_ID
+const TPM_ALG_SHA1: u16 = 0x0004;
+const TPM_ALG_SHA256: u16 = 0x000B;
+const TPM_ALG_SHA384: u16 = 0x000C;
+const TPM_ALG_SHA512: u16 = 0x000D;
+
+const SPEC_ID_SIGNATURE: &[u8] = b"Spec ID Event03\0";
+const STARTUP_LOCALITY_SIGNATURE: &[u8] = b"StartupLocality\0";
+
+/// Firmware in debug mode measures this EV_EFI_ACTION into PCR 7
+const UEFI_DEBUG_MODE: &str = "UEFI Debug Mode";
+
+// Windows boot configuration log (SIPA) events
+const SIPA_AGGREGATION: u32 = 0x4000_0000;
+const SIPA_BOOT_DEBUGGING: u32 = 0x0004_0001;
+const SIPA_OS_KERNEL_DEBUG: u32 = 0x0005_0001;
+const SIPA_TEST_SIGNING: u32 = 0x0005_0003;
+const SIPA_SAFE_MODE: u32 = 0x0005_0005;
+const SIPA_WINPE: u32 = 0x0005_0006;
+const SIPA_HYPERVISOR_DEBUG: u32 = 0x0005_000D;
+const SIPA_FLIGHT_SIGNING: u32 = 0x0005_0021;
+
+/// Boolean SIPA flags that weaken the boot chain
+const SIPA_INDICATORS: &[(u32, &str)] = &[
+    (SIPA_BOOT_DEBUGGING, "Boot debugging enabled"),
+    (SIPA_OS_KERNEL_DEBUG, "Kernel debugging enabled"),
+    (SIPA_TEST_SIGNING, "Test signing enabled"),
+    (SIPA_SAFE_MODE, "Safe mode boot"),
+    (SIPA_WINPE, "WinPE boot"),
+    (SIPA_HYPERVISOR_DEBUG, "Hypervisor debugging enabled"),
+    (SIPA_FLIGHT_SIGNING, "Flight signing enabled"),
+];
+
+/// PCRs that only hold pre-OS measurements (later PCRs are extended by the OS too)
+const PRE_OS_PCRS: u32 = 10;
+
+const MAX_EVENT_SIZE: usize = 16 * 1024 * 1024;
+const MAX_SIPA_DEPTH: usize = 8;
+
+/// Outcome of the boot chain inspection
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum BootVerdict {
+    /// Log matches the TPM, Secure Boot measured on, no weakening flag
+    Trusted,
+    /// Secure Boot measured off, a debug/test-signing flag, or a forged log
+    Insecure,
+    /// Log parsed but something needed for a verdict is missing
+    Unverified,
+    /// No event log (no TPM, legacy BIOS, or not readable)
+    #[default]
+    Unavailable,
+}
+
+/// Replayed value of one PCR bank
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PcrValue {
+    pub index: u32,
+    pub algorithm: String,
+    pub digest: String,
+}
+
+/// Boot application or driver loaded before the OS
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BootComponent {
+    pub pcr: u32,
+    pub kind: String, // application, driver, runtime_driver
+    pub path: Option<String>,
+    pub digest: String, // SHA-256 when logged, else the first bank
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct MeasuredBootReport {
+    pub verdict: BootVerdict,
+    pub reasons: Vec<String>,
+    pub source: Option<String>,
+    pub error: Option<String>,
+    pub crypto_agile: bool,
+    pub algorithms: Vec<String>,
+    pub event_count: usize,
+    pub pcrs: Vec<PcrValue>,
+    pub tpm_verified: Option<bool>, // None when the TPM PCRs cannot be read
+    pub pcr_mismatches: Vec<u32>,
+    pub components: Vec<BootComponent>,
+    pub secure_boot: Option<bool>, // SecureBoot variable as measured in PCR 7
+    pub secure_boot_variables: Vec<String>,
+    pub authorities: Vec<String>,  // Certificates (or shim variables) that verified images
+    pub indicators: Vec<String>,   // Debug / test-signing flags found in the log
+}
+
+/// One parsed event
+#[derive(Debug, Clone)]
+pub struct TcgEvent<'a> {
+    pub pcr: u32,
+    pub event_type: u32,
+    pub digests: Vec<(u16, &'a [u8])>,
+    pub data: &'a [u8],
+}
+
+/// Parsed event log
+#[derive(Debug, Clone)]
+pub struct EventLog<'a> {
+    pub crypto_agile: bool,
+    pub algorithms: Vec<(u16, usize)>, // Algorithm id and digest size
+    pub events: Vec<TcgEvent<'a>>,
+}
+
+/// Measured boot report of the live system
+#[cfg(target_os = "linux")]
+pub fn check_measured_boot() -> MeasuredBootReport {
+    read_report(
+        Path::new("/sys/kernel/security/tpm0/binary_bios_measurements"),
+        Some(Path::new("/sys/class/tpm/tpm0/pcr-sha256")),
+    )
+}
+
+/// Measured boot report of the live system (the boot log of the current boot is the newest)
+#[cfg(target_os = "windows")]
+pub fn check_measured_boot() -> MeasuredBootReport {
+    let dir = Path::new("C:\\Windows\\Logs\\MeasuredBoot");
+    let newest = fs::read_dir(dir).into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext.eq_ignore_ascii_case("log")))
+        .filter_map(|entry| Some((entry.metadata().ok()?.modified().ok()?, entry.path())))
+        .max_by_key(|(modified, _)| *modified);
+
+    match newest {
+        Some((_, path)) => read_report(&path, None),
+        None => unavailable(format!("No boot log in {}", dir.display())),
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+pub fn check_measured_boot() -> MeasuredBootReport {
+    unavailable("Measured boot log not supported on this platform".to_string())
+}
+
+/// Report from a log file and an optional directory of TPM SHA-256 PCR values
+pub fn read_report(log_path: &Path, pcr_dir: Option<&Path>) -> MeasuredBootReport {
+    let data = match fs::read(log_path) {
+        Ok(data) => data,
+        Err(e) => return unavailable(format!("Cannot read {}: {}", log_path.display(), e)),
+    };
+    let tpm_pcrs = pcr_dir.map(read_tpm_pcrs).unwrap_or_default();
+
+    let mut report = analyze(&data, &tpm_pcrs);
+    report.source = Some(log_path.display().to_string());
+    println!("[MeasuredBoot] {:?}: {} events, {} components, secure_boot={:?}, indicators={:?}",
+             report.verdict, report.event_count, report.components.len(), report.secure_boot, report.indicators);
+    report
+}
+
+/// Report from raw log bytes and the SHA-256 PCRs read from the TPM (index -> value)
+pub fn analyze(data: &[u8], tpm_pcrs: &BTreeMap<u32, Vec<u8>>) -> MeasuredBootReport {
+    let log = match parse(data) {
+        Ok(log) => log,
+        Err(e) => {
+            let mut report = unavailable(e.clone());
+            report.verdict = BootVerdict::Unverified;
+            report.reasons = vec![format!("Malformed event log: {}", e)];
+            return report;
+        }
+    };
+
+    let mut report = MeasuredBootReport {
+        crypto_agile: log.crypto_agile,
+        algorithms: log.algorithms.iter().map(|(alg, _)| algorithm_name(*alg)).collect(),
+        event_count: log.events.len(),
+        ..Default::default()
+    };
+
+    let banks = replay(&log);
+    for ((alg, index), value) in &banks {
+        report.pcrs.push(PcrValue { index: *index, algorithm: algorithm_name(*alg), digest: hex::encode(value) });
+    }
+    if !tpm_pcrs.is_empty() {
+        report.pcr_mismatches = (0..PRE_OS_PCRS)
+            .filter(|index| {
+                let replayed = banks.get(&(TPM_ALG_SHA256, *index)).cloned().unwrap_or_else(|| vec![0; 32]);
+                tpm_pcrs.get(index).is_some_and(|actual| *actual != replayed)
+            })
+            .collect();
+        report.tpm_verified = Some(report.pcr_mismatches.is_empty());
+    }
+
+    for event in &log.events {
+        inspect_event(event, &mut report);
+    }
+
+    let (verdict, reasons) = verdict(&report);
+    report.verdict = verdict;
+    report.reasons = reasons;
+    report
+}
+
+/// Parse a log in either format
+pub fn parse(data: &[u8]) -> Result<EventLog<'_>, String> {
+    let mut reader = Reader { data, offset: 0 };
+
+    // The first event always uses the SHA-1 format; in a crypto-agile log it is
+    // the Spec ID event listing the digest sizes of the following events
+    let first = read_sha1_event(&mut reader)?;
+    let spec_id = (first.event_type == EV_NO_ACTION && first.data.starts_with(SPEC_ID_SIGNATURE))
+        .then(|| parse_spec_id(first.data))
+        .transpose()?;
+
+    let mut log = match spec_id {
+        Some(algorithms) => EventLog { crypto_agile: true, algorithms, events: Vec::new() },
+        None => EventLog { crypto_agile: false, algorithms: vec![(TPM_ALG_SHA1, 20)], events: vec![first] },
+    };
+
+    while reader.remaining() > 0 {
+        // Some firmware pads the log with 0xFF (or zeros) after the last event
+        if reader.data[reader.offset..].iter().all(|b| *b == 0xFF || *b == 0) {
+            break;
+        }
+        let event = if log.crypto_agile {
+            read_agile_event(&mut reader, &log.algorithms)?
+        } else {
+            read_sha1_event(&mut reader)?
+        };
+        log.events.push(event);
+    }
+
+    Ok(log)
+}
+
+/// Digest algorithms of a TCG_EfiSpecIDEvent
+fn parse_spec_id(data: &[u8]) -> Result<Vec<(u16, usize)>, String> {
+    let mut reader = Reader { data, offset: SPEC_ID_SIGNATURE.len() };
+    reader.skip(8)?; // platformClass, version minor/major, errata, uintnSize
+    let count = reader.u32()?;
+    if count == 0 || count > 16 {
+        return Err(format!("Invalid algorithm count {}", count));
+    }
+    (0..count).map(|_| Ok((reader.u16()?, reader.u16()? as usize))).collect()
+}
+
+/// TCG_PCR_EVENT (SHA-1 format)
+fn read_sha1_event<'a>(reader: &mut Reader<'a>) -> Result<TcgEvent<'a>, String> {
+    let pcr = reader.u32()?;
+    let event_type = reader.u32()?;
+    let digest = reader.bytes(20)?;
+    let data = read_event_data(reader)?;
+    Ok(TcgEvent { pcr, event_type, digests: vec![(TPM_ALG_SHA1, digest)], data })
+}
+
+/// TCG_PCR_EVENT2 (crypto-agile format)
+fn read_agile_event<'a>(reader: &mut Reader<'a>, algorithms: &[(u16, usize)]) -> Result<TcgEvent<'a>, String> {
+    let pcr = reader.u32()?;
+    let event_type = reader.u32()?;
+    let count = reader.u32()?;
+    if count as usize > algorithms.len() {
+        return Err(format!("Event at {} has {} digests", reader.offset, count));
+    }
+    let mut digests = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let alg = reader.u16()?;
+        let size = algorithms.iter()
+            .find(|(id, _)| *id == alg)
+            .map(|(_, size)| *size)
+            .ok_or_else(|| format!("Digest algorithm 0x{:04x} not in the Spec ID event", alg))?;
+        digests.push((alg, reader.bytes(size)?));
+    }
+    let data = read_event_data(reader)?;
+    Ok(TcgEvent { pcr, event_type, digests, data })
+}
+
+fn read_event_data<'a>(reader: &mut Reader<'a>) -> Result<&'a [u8], String> {
+    let size = reader.u32()? as usize;
+    if size > MAX_EVENT_SIZE {
+        return Err(format!("Event of {} bytes at offset {}", size, reader.offset));
+    }
+    reader.bytes(size)
+}
+
+/// Replay every bank: PCR = H(PCR || digest), EV_NO_ACTION events are not extended
+fn replay(log: &EventLog) -> BTreeMap<(u16, u32), Vec<u8>> {
+    let mut locality = 0u8;
+    if let Some(event) = log.events.iter().find(|e| e.event_type == EV_NO_ACTION && e.data.starts_with(STARTUP_LOCALITY_SIGNATURE)) {
+        locality = event.data.get(STARTUP_LOCALITY_SIGNATURE.len()).copied().unwrap_or(0);
+    }
+
+    let mut banks: BTreeMap<(u16, u32), Vec<u8>> = BTreeMap::new();
+    for event in log.events.iter().filter(|e| e.event_type != EV_NO_ACTION) {
+        for (alg, measured) in &event.digests {
+            let algorithm = match digest_algorithm(*alg) {
+                Some(algorithm) => algorithm,
+                None => continue,
+            };
+            let pcr = banks.entry((*alg, event.pcr)).or_insert_with(|| {
+                let mut initial = vec![0u8; algorithm.output_len()];
+                // Startup locality 3 (or 4 for an H-CRTM) starts PCR 0 at ...03 / ...04
+                if let (0, Some(last)) = (event.pcr, initial.last_mut()) {
+                    *last = locality;
+                }
+                initial
+            });
+            let mut context = digest::Context::new(algorithm);
+            context.update(pcr);
+            context.update(measured);
+            *pcr = context.finish().as_ref().to_vec();
+        }
+    }
+    banks
+}
+
+fn inspect_event(event: &TcgEvent, report: &mut MeasuredBootReport) {
+    match event.event_type {
+        EV_EFI_BOOT_SERVICES_APPLICATION | EV_EFI_BOOT_SERVICES_DRIVER | EV_EFI_RUNTIME_SERVICES_DRIVER => {
+            let kind = match event.event_type {
+                EV_EFI_BOOT_SERVICES_APPLICATION => "application",
+                EV_EFI_BOOT_SERVICES_DRIVER => "driver",
+                _ => "runtime_driver",
+            };
+            let digest = event.digests.iter()
+                .find(|(alg, _)| *alg == TPM_ALG_SHA256)
+                .or(event.digests.first())
+                .map(|(_, value)| hex::encode(value))
+                .unwrap_or_default();
+            report.components.push(BootComponent {
+                pcr: event.pcr,
+                kind: kind.to_string(),
+                path: image_path(event.data),
+                digest,
+            });
+        }
+        EV_EFI_VARIABLE_DRIVER_CONFIG => {
+            if let Some((name, value)) = variable_data(event.data) {
+                if name == "SecureBoot" {
+                    report.secure_boot = value.first().map(|v| *v == 1);
+                }
+                push_unique(&mut report.secure_boot_variables, name);
+            }
+        }
+        EV_EFI_VARIABLE_AUTHORITY => {
+            if let Some((name, value)) = variable_data(event.data) {
+                // db entries are EFI_SIGNATURE_DATA: owner guid + certificate
+                let authority = value.get(16..)
+                    .filter(|cert| cert.first() == Some(&0x30))
+                    .and_then(|cert| authenticode::certificate_info(cert).ok())
+                    .map(|info| info.subject)
+                    .unwrap_or(name);
+                push_unique(&mut report.authorities, authority);
+            }
+        }
+        EV_EFI_ACTION | EV_ACTION if String::from_utf8_lossy(event.data).trim_end_matches('\0') == UEFI_DEBUG_MODE => {
+            push_unique(&mut report.indicators, "Firmware in UEFI debug mode".to_string());
+        }
+        EV_EVENT_TAG => sipa_indicators(event.data, 0, &mut report.indicators),
+        _ => {}
+    }
+}
+
+/// Walk SIPA events (type, size, data), recursing into aggregations
+fn sipa_indicators(mut data: &[u8], depth: usize, indicators: &mut Vec<String>) {
+    while data.len() >= 8 && depth < MAX_SIPA_DEPTH {
+        let sipa_type = le_u32(&data[0..4]);
+        let size = le_u32(&data[4..8]) as usize;
+        let value = match data.get(8..8 + size) {
+            Some(value) => value,
+            None => return,
+        };
+
+        if sipa_type & SIPA_AGGREGATION != 0 {
+            sipa_indicators(value, depth + 1, indicators);
+        } else if let Some((_, label)) = SIPA_INDICATORS.iter().find(|(id, _)| *id == sipa_type) {
+            if value.first().is_some_and(|flag| *flag != 0) {
+                push_unique(indicators, label.to_string());
+            }
+        }
+        data = &data[8 + size..];
+    }
+}
+
+fn verdict(report: &MeasuredBootReport) -> (BootVerdict, Vec<String>) {
+    let mut insecure = Vec::new();
+    if !report.pcr_mismatches.is_empty() {
+        insecure.push(format!("Event log does not match TPM PCRs {:?}", report.pcr_mismatches));
+    }
+    if report.secure_boot == Some(false) {
+        insecure.push("Secure Boot measured as disabled".to_string());
+    }
+    insecure.extend(report.indicators.iter().cloned());
+    if !insecure.is_empty() {
+        return (BootVerdict::Insecure, insecure);
+    }
+
+    let mut unverified = Vec::new();
+    if report.secure_boot.is_none() {
+        unverified.push("No Secure Boot configuration measured".to_string());
+    }
+    if !report.pcrs.iter().any(|pcr| pcr.index == 7) {
+        unverified.push("Nothing measured into PCR 7".to_string());
+    }
+    // A self-consistent log file proves nothing until the TPM vouches for it
+    if report.tpm_verified != Some(true) {
+        unverified.push("Log not checked against TPM PCRs".to_string());
+    }
+    if unverified.is_empty() {
+        (BootVerdict::Trusted, Vec::new())
+    } else {
+        (BootVerdict::Unverified, unverified)
+    }
+}
+
+/// UEFI_VARIABLE_DATA: guid, name length (chars), data length, UTF-16 name, data
+fn variable_data(data: &[u8]) -> Option<(String, &[u8])> {
+    let mut reader = Reader { data, offset: 16 };
+    let name_length = usize::try_from(reader.u64().ok()?).ok()?;
+    let data_length = usize::try_from(reader.u64().ok()?).ok()?;
+    let name = reader.bytes(name_length.checked_mul(2)?).ok()?;
+    let value = reader.bytes(data_length).ok()?;
+    Some((utf16_le(name), value))
+}
+
+/// File path of a UEFI_IMAGE_LOAD_EVENT (media file path nodes of the device path)
+fn image_path(data: &[u8]) -> Option<String> {
+    let mut reader = Reader { data, offset: 24 };
+    let length = usize::try_from(reader.u64().ok()?).ok()?;
+    let mut path = reader.bytes(length).ok()?;
+
+    let mut parts = Vec::new();
+    while path.len() >= 4 {
+        let (node_type, subtype) = (path[0], path[1]);
+        let node_length = u16::from_le_bytes([path[2], path[3]]) as usize;
+        if node_type == 0x7F || node_length < 4 || node_length > path.len() {
+            break;
+        }
+        if node_type == 0x04 && subtype == 0x04 {
+            parts.push(utf16_le(&path[4..node_length]));
+        }
+        path = &path[node_length..];
+    }
+    (!parts.is_empty()).then(|| parts.join(""))
+}
+
+/// TPM SHA-256 PCRs from a sysfs `pcr-sha256` directory (one hex file per index)
+fn read_tpm_pcrs(dir: &Path) -> BTreeMap<u32, Vec<u8>> {
+    (0..PRE_OS_PCRS)
+        .filter_map(|index| {
+            let value = fs::read_to_string(dir.join(index.to_string())).ok()?;
+            Some((index, hex::decode(value.trim()).ok()?))
+        })
+        .collect()
+}
+
+fn push_unique(list: &mut Vec<String>, value: String) {
+    if !list.contains(&value) {
+        list.push(value);
+    }
+}
+
+fn unavailable(error: String) -> MeasuredBootReport {
+    println!("[MeasuredBoot] {}", error);
+    MeasuredBootReport {
+        reasons: vec![error.clone()],
+        error: Some(error),
+        ..Default::default()
+    }
+}
+
+fn digest_algorithm(alg: u16) -> Option<&'static digest::Algorithm> {
+    match alg {
+        TPM_ALG_SHA1 => Some(&digest::SHA1_FOR_LEGACY_USE_ONLY),
+        TPM_ALG_SHA256 => Some(&digest::SHA256),
+        TPM_ALG_SHA384 => Some(&digest::SHA384),
+        TPM_ALG_SHA512 => Some(&digest::SHA512),
+        _ => None,
+    }
+}
+
+fn algorithm_name(alg: u16) -> String {
+    match alg {
+        TPM_ALG_SHA1 => "sha1".to_string(),
+        TPM_ALG_SHA256 => "sha256".to_string(),
+        TPM_ALG_SHA384 => "sha384".to_string(),
+        TPM_ALG_SHA512 => "sha512".to_string(),
+        0x0012 => "sm3_256".to_string(),
+        other => format!("0x{:04x}", other),
+    }
+}
+
+fn utf16_le(bytes: &[u8]) -> String {
+    let units: Vec<u16> = bytes.chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .take_while(|unit| *unit != 0)
+        .collect();
+    String::from_utf16_lossy(&units)
+}
+
+fn le_u32(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+/// Bounds-checked little-endian reader
+struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn remaining(&self) -> usize {
+        self.data.len().saturating_sub(self.offset)
+    }
+
+    fn bytes(&mut self, count: usize) -> Result<&'a [u8], String> {
+        let end = self.offset.checked_add(count)
+            .filter(|&end| end <= self.data.len())
+            .ok_or_else(|| format!("Truncated at offset {} (need {} bytes)", self.offset, count))?;
+        let bytes = &self.data[self.offset..end];
+        self.offset = end;
+        Ok(bytes)
+    }
+
+    fn skip(&mut self, count: usize) -> Result<(), String> {
+        self.bytes(count).map(|_| ())
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        self.bytes(2).map(|b| u16::from_le_bytes([b[0], b[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        self.bytes(4).map(le_u32)
+    }
+
+    fn u64(&mut self) -> Result<u64, String> {
+        self.bytes(8).map(|b| u64::from_le_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/eventlog").join(name)
+    }
+
+    fn linux_report(pcr_dir: &str) -> MeasuredBootReport {
+        read_report(&fixture("linux/binary_bios_measurements"), Some(&fixture(pcr_dir)))
+    }
+
+    #[test]
+    fn linux_boot_matching_the_tpm_is_trusted() {
+        let report = linux_report("linux/pcr-sha256");
+        assert_eq!(report.verdict, BootVerdict::Trusted, "{:?}", report.reasons);
+        assert!(report.crypto_agile);
+        assert_eq!(report.algorithms, vec!["sha1", "sha256"]);
+        assert_eq!(report.tpm_verified, Some(true));
+        assert!(report.pcr_mismatches.is_empty());
+        assert_eq!(report.secure_boot, Some(true));
+        assert_eq!(report.secure_boot_variables, vec!["SecureBoot", "PK", "KEK", "db", "dbx"]);
+        // db certificate subject for the firmware, variable names for shim
+        assert_eq!(report.authorities[0], "CN=Microsoft Windows Production PCA 2011, O=Microsoft Corporation");
+        assert_eq!(report.authorities[1..], ["Shim", "SbatLevel"]);
+        assert!(report.indicators.is_empty());
+    }
+
+    #[test]
+    fn boot_components_are_listed() {
+        let report = linux_report("linux/pcr-sha256");
+        let paths: Vec<_> = report.components.iter().map(|c| (c.pcr, c.kind.as_str(), c.path.as_deref())).collect();
+        assert_eq!(paths, vec![
+            (2, "driver", None),
+            (4, "application", Some("\\EFI\\fedora\\shimx64.efi")),
+            (4, "application", Some("\\EFI\\fedora\\grubx64.efi")),
+        ]);
+        // SHA-256 bank preferred over the first (SHA-1) one
+        assert_eq!(report.components[1].digest, hex::encode(digest::digest(&digest::SHA256, b"shimx64")));
+    }
+
+    #[test]
+    fn replay_starts_pcr0_at_the_startup_locality() {
+        let data = fs::read(fixture("linux/binary_bios_measurements")).unwrap();
+        let log = parse(&data).unwrap();
+        let banks = replay(&log);
+        let tpm_pcr0 = fs::read_to_string(fixture("linux/pcr-sha256/0")).unwrap();
+        assert_eq!(hex::encode(&banks[&(TPM_ALG_SHA256, 0)]), tpm_pcr0.trim().to_lowercase());
+        assert_eq!(banks[&(TPM_ALG_SHA1, 0)].len(), 20);
+        // EV_NO_ACTION events (Spec ID, StartupLocality) are not extended
+        assert_eq!(log.events.iter().filter(|e| e.event_type == EV_NO_ACTION).count(), 1);
+    }
+
+    #[test]
+    fn log_not_matching_the_tpm_is_insecure() {
+        let report = linux_report("linux/pcr-sha256-forged");
+        assert_eq!(report.verdict, BootVerdict::Insecure);
+        assert_eq!(report.tpm_verified, Some(false));
+        assert_eq!(report.pcr_mismatches, vec![4]);
+        assert_eq!(report.reasons, vec!["Event log does not match TPM PCRs [4]".to_string()]);
+    }
+
+    #[test]
+    fn unreadable_tpm_leaves_the_log_unverified() {
+        let report = linux_report("linux/missing");
+        assert_eq!(report.tpm_verified, None);
+        assert_eq!(report.verdict, BootVerdict::Unverified);
+        assert_eq!(report.reasons, vec!["Log not checked against TPM PCRs".to_string()]);
+    }
+
+    #[test]
+    fn windows_test_signing_is_flagged() {
+        let report = read_report(&fixture("windows-testsigning.log"), None);
+        assert_eq!(report.verdict, BootVerdict::Insecure);
+        assert_eq!(report.algorithms, vec!["sha256"]);
+        // Kernel debug and flight signing are logged as off, WinPE has no value
+        assert_eq!(report.indicators, vec!["Test signing enabled".to_string()]);
+        assert_eq!(report.reasons, report.indicators);
+        assert_eq!(report.components.last().unwrap().path.as_deref(), Some("\\EFI\\Microsoft\\Boot\\bootmgfw.efi"));
+    }
+
+    #[test]
+    fn debug_firmware_without_secure_boot() {
+        let report = read_report(&fixture("debug-mode.log"), None);
+        assert_eq!(report.verdict, BootVerdict::Insecure);
+        assert_eq!(report.secure_boot, Some(false));
+        assert!(report.authorities.is_empty());
+        assert_eq!(report.reasons, vec![
+            "Secure Boot measured as disabled".to_string(),
+            "Firmware in UEFI debug mode".to_string(),
+        ]);
+    }
+
+    #[test]
+    fn sha1_bios_log_is_unverified() {
+        let report = read_report(&fixture("legacy-sha1.log"), None);
+        assert!(!report.crypto_agile);
+        assert_eq!(report.algorithms, vec!["sha1"]);
+        // Trailing 0xFF padding is not an event
+        assert_eq!(report.event_count, 13);
+        assert_eq!(report.verdict, BootVerdict::Unverified);
+        assert_eq!(report.reasons, vec![
+            "No Secure Boot configuration measured".to_string(),
+            "Log not checked against TPM PCRs".to_string(),
+        ]);
+        assert!(report.pcrs.iter().all(|pcr| pcr.algorithm == "sha1"));
+    }
+
+    #[test]
+    fn truncated_log_is_malformed() {
+        let report = read_report(&fixture("truncated.log"), None);
+        assert_eq!(report.verdict, BootVerdict::Unverified);
+        assert!(report.reasons[0].starts_with("Malformed event log: Truncated at offset"), "{:?}", report.reasons);
+        assert_eq!(report.event_count, 0);
+    }
+
+    #[test]
+    fn missing_log_is_unavailable() {
+        let report = read_report(&fixture("missing.log"), None);
+        assert_eq!(report.verdict, BootVerdict::Unavailable);
+        assert!(report.error.unwrap().starts_with("Cannot read"));
+    }
+
+    #[test]
+    fn rejects_inconsistent_logs() {
+        let data = fs::read(fixture("linux/binary_bios_measurements")).unwrap();
+        let spec_size = 32 + le_u32(&data[28..32]) as usize;
+
+        // Algorithm count of the Spec ID event
+        let mut no_algorithms = data.clone();
+        no_algorithms[32 + SPEC_ID_SIGNATURE.len() + 8..][..4].copy_from_slice(&0u32.to_le_bytes());
+        assert_eq!(parse(&no_algorithms).unwrap_err(), "Invalid algorithm count 0");
+
+        // Digest of an algorithm the Spec ID event does not declare
+        let mut unknown_digest = data.clone();
+        unknown_digest[spec_size + 12..][..2].copy_from_slice(&TPM_ALG_SHA384.to_le_bytes());
+        assert!(parse(&unknown_digest).unwrap_err().contains("0x000c not in the Spec ID event"));
+
+        // Event size above the limit
+        let mut huge_event = data[..32].to_vec();
+        huge_event[28..32].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(parse(&huge_event).unwrap_err().starts_with("Event of"));
+    }
+
+    #[test]
+    fn nested_sipa_flags_are_found() {
+        let sipa = |kind: u32, value: &[u8]| [&kind.to_le_bytes()[..], &(value.len() as u32).to_le_bytes(), value].concat();
+        let data = sipa(0x4001_0002, &sipa(0x4001_0001, &[sipa(SIPA_OS_KERNEL_DEBUG, &[1]), sipa(SIPA_SAFE_MODE, &[1])].concat()));
+        let mut indicators = Vec::new();
+        sipa_indicators(&data, 0, &mut indicators);
+        assert_eq!(indicators, vec!["Kernel debugging enabled", "Safe mode boot"]);
+
+        // Aggregations deeper than the limit are ignored
+        let mut deep = sipa(SIPA_TEST_SIGNING, &[1]);
+        for _ in 0..MAX_SIPA_DEPTH {
+            deep = sipa(0x4001_0001, &deep);
+        }
+        let mut indicators = Vec::new();
+        sipa_indicators(&deep, 0, &mut indicators);
+        assert!(indicators.is_empty());
+    }
+}
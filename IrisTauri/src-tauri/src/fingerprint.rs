@@ -0,0 +1,441 @@
+//! Fingerprint module - versioned hardware id built from named components
+//!
+//! The id is SHA-256 over the normalized values of the components every launch can
+//! read (`ID_COMPONENTS`), prefixed with the fingerprint version so a change of
+//! inputs or normalization is a new version instead of a silent re-identification.
+//! Components that can be missing on unchanged hardware (TPM EK while the TPM is
+//! busy, Linux DMI serials without root, disks, NICs) only go to the per-component
+//! breakdown: taking them into the id would change it between launches.
+//!
+//! The server receives one SHA-256 per value, which is enough to see which parts
+//! of a known machine changed (new disk, new NIC) and to find the same parts on
+//! another account. The hashes are unkeyed so that they compare across accounts:
+//! they hide serials from a casual reader, not from someone who enumerates a
+//! vendor's MAC or serial range.
+//!
+//! Version 2 replaced the `DefaultHasher` id of earlier clients, which re-identifies
+//! every machine once. The fingerprint carries that legacy id (Windows, the only
+//! platform those clients ran on) so the server can map the old registration.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+
+/// Bump when components, sources or normalization change
+pub const FINGERPRINT_VERSION: u32 = 2;
+
+/// Components the id is computed from (when present)
+#[cfg(not(target_os = "linux"))]
+const ID_COMPONENTS: &[&str] = &["machineGuid", "smbiosUuid", "cpuId"];
+/// Linux: the DMI product UUID is only readable by root
+#[cfg(target_os = "linux")]
+const ID_COMPONENTS: &[&str] = &["machineGuid", "cpuId"];
+
+/// Placeholder values firmware and drivers report instead of a real serial
+const JUNK_VALUES: &[&str] = &[
+    "", "0", "NONE", "NULL", "N/A", "NA", "DEFAULT STRING", "TO BE FILLED BY O.E.M.", "SYSTEM SERIAL NUMBER",
+    "SYSTEM PRODUCT NAME", "BASE BOARD SERIAL NUMBER", "CHASSIS SERIAL NUMBER", "NOT APPLICABLE", "NOT SPECIFIED",
+    "INVALID", "0123456789", "1234567890", "OEM", "O.E.M.", "UNKNOWN",
+    "00000000-0000-0000-0000-000000000000", "FFFFFFFF-FFFF-FFFF-FFFF-FFFFFFFFFFFF",
+    "03000200-0400-0500-0006-000700080009", // AMI sample UUID
+    "00:00:00:00:00:00",
+];
+
+/// Named component values read from the machine (raw, not yet normalized)
+pub type Components = Vec<(&'static str, Vec<String>)>;
+
+/// Hardware id and its per-component breakdown
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct HardwareFingerprint {
+    pub version: u32,
+    pub id: String,
+    pub components: BTreeMap<String, Vec<String>>, // Component name -> SHA-256 of each value
+    pub legacy_id: Option<String>,                  // Id of pre-v2 clients on this machine
+}
+
+lazy_static::lazy_static! {
+    // Collected once per launch: WMI, sysfs and TPM reads are too slow for every heartbeat
+    static ref FINGERPRINT: HardwareFingerprint = HardwareFingerprint { legacy_id: legacy_id(), ..compute(&collect()) };
+}
+
+/// Fingerprint of this machine
+pub fn fingerprint() -> HardwareFingerprint {
+    FINGERPRINT.clone()
+}
+
+/// Fingerprint of already collected components (components without a usable value
+/// are left out, the id only covers `ID_COMPONENTS`)
+pub fn compute(components: &Components) -> HardwareFingerprint {
+    let mut normalized: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+    for (name, values) in components {
+        let mut values: Vec<String> = values.iter().filter_map(|value| normalize(value)).collect();
+        values.sort();
+        values.dedup();
+        if !values.is_empty() {
+            normalized.entry(name).or_default().extend(values);
+        }
+    }
+
+    let mut hasher = Sha256::new();
+    hasher.update(format!("iris-hwid/v{}", FINGERPRINT_VERSION));
+    for (name, values) in normalized.iter().filter(|(name, _)| ID_COMPONENTS.contains(name)) {
+        update_field(&mut hasher, name);
+        hasher.update((values.len() as u32).to_be_bytes());
+        for value in values {
+            update_field(&mut hasher, value);
+        }
+    }
+
+    let breakdown = normalized.iter()
+        .map(|(name, values)| (name.to_string(), values.iter().map(|value| component_hash(name, value)).collect()))
+        .collect();
+
+    HardwareFingerprint {
+        version: FINGERPRINT_VERSION,
+        id: format!("v{}-{}", FINGERPRINT_VERSION, hex::encode(hasher.finalize())),
+        components: breakdown,
+        legacy_id: None,
+    }
+}
+
+/// Length-prefixed so that ("ab", "c") and ("a", "bc") hash differently
+fn update_field(hasher: &mut Sha256, field: &str) {
+    hasher.update((field.len() as u32).to_be_bytes());
+    hasher.update(field.as_bytes());
+}
+
+/// Hash of one value, prefixed with version and component so equal serials of different parts differ
+fn component_hash(name: &str, value: &str) -> String {
+    hex::encode(Sha256::digest(format!("iris-hwid/v{}/{}/{}", FINGERPRINT_VERSION, name, value)))
+}
+
+/// Hardware id of pre-v2 clients: `DefaultHasher` over the machine GUID and TPM manufacturer
+#[cfg(target_os = "windows")]
+fn legacy_id() -> Option<String> {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    let mut hasher = DefaultHasher::new();
+    crate::hardware::get_machine_guid().hash(&mut hasher);
+    crate::hardware::check_tpm().manufacturer.hash(&mut hasher);
+    Some(format!("{:016x}", hasher.finish()))
+}
+
+#[cfg(not(target_os = "windows"))]
+fn legacy_id() -> Option<String> {
+    None
+}
+
+/// Placeholder or missing serial ("To be filled by O.E.M.", all-zero UUID)
+pub fn is_placeholder(value: &str) -> bool {
+    normalize(value).is_none()
+}
+
+/// Trimmed, upper-cased value; None for placeholders and repeated digits
+fn normalize(value: &str) -> Option<String> {
+    let value = value.trim_matches(|c: char| c.is_whitespace() || c == '\0').to_uppercase();
+    if JUNK_VALUES.contains(&value.as_str()) {
+        return None;
+    }
+    let symbols: Vec<char> = value.chars().filter(|c| c.is_ascii_alphanumeric()).collect();
+    if symbols.is_empty() || symbols.iter().all(|&c| c == symbols[0]) {
+        return None; // "000000", "FFFFFFFF", "XXXX"
+    }
+    Some(value)
+}
+
+/// CPUID signature and feature flags in the format of Win32_Processor.ProcessorId
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+fn cpu_id() -> Vec<String> {
+    #[cfg(target_arch = "x86")]
+    use std::arch::x86::__cpuid;
+    #[cfg(target_arch = "x86_64")]
+    use std::arch::x86_64::__cpuid;
+
+    #[allow(unused_unsafe)]
+    let leaf = unsafe { __cpuid(1) };
+    vec![format!("{:08X}{:08X}", leaf.edx, leaf.eax)]
+}
+
+#[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+fn cpu_id() -> Vec<String> {
+    Vec::new()
+}
+
+/// SHA-256 of the endorsement key public area
+fn tpm_ek() -> Vec<String> {
+    match crate::attestation::ek_public() {
+        Ok(public) => vec![hex::encode(Sha256::digest(public))],
+        Err(e) => {
+            println!("[Fingerprint] No TPM EK: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn collect() -> Components {
+    use wmi::{COMLibrary, WMIConnection};
+
+    #[derive(Deserialize)]
+    #[allow(non_snake_case)]
+    struct Win32ComputerSystemProduct {
+        UUID: Option<String>,
+        IdentifyingNumber: Option<String>,
+    }
+
+    #[derive(Deserialize)]
+    #[allow(non_snake_case)]
+    struct Win32BaseBoard {
+        SerialNumber: Option<String>,
+    }
+
+    #[derive(Deserialize)]
+    #[allow(non_snake_case)]
+    struct Win32DiskDrive {
+        SerialNumber: Option<String>,
+        InterfaceType: Option<String>,
+    }
+
+    #[derive(Deserialize)]
+    #[allow(non_snake_case)]
+    struct Win32NetworkAdapter {
+        MACAddress: Option<String>,
+        PNPDeviceID: Option<String>,
+    }
+
+    let mut smbios_uuid = Vec::new();
+    let mut system_serial = Vec::new();
+    let mut board_serial = Vec::new();
+    let mut disk_serials = Vec::new();
+    let mut mac_addresses = Vec::new();
+
+    if let Ok(com_con) = COMLibrary::new() {
+        if let Ok(wmi_con) = WMIConnection::new(com_con) {
+            if let Ok(results) = wmi_con.raw_query::<Win32ComputerSystemProduct>(
+                "SELECT UUID, IdentifyingNumber FROM Win32_ComputerSystemProduct"
+            ) {
+                smbios_uuid.extend(results.iter().filter_map(|p| p.UUID.clone()));
+                system_serial.extend(results.iter().filter_map(|p| p.IdentifyingNumber.clone()));
+            }
+            if let Ok(results) = wmi_con.raw_query::<Win32BaseBoard>("SELECT SerialNumber FROM Win32_BaseBoard") {
+                board_serial.extend(results.into_iter().filter_map(|b| b.SerialNumber));
+            }
+            // Internal disks only: a USB stick must not change the id
+            if let Ok(results) = wmi_con.raw_query::<Win32DiskDrive>(
+                "SELECT SerialNumber, InterfaceType FROM Win32_DiskDrive"
+            ) {
+                disk_serials.extend(results.into_iter()
+                    .filter(|d| d.InterfaceType.as_deref() != Some("USB"))
+                    .filter_map(|d| d.SerialNumber));
+            }
+            // Physical PCI adapters (no USB dongles, VPN or Hyper-V adapters)
+            if let Ok(results) = wmi_con.raw_query::<Win32NetworkAdapter>(
+                "SELECT MACAddress, PNPDeviceID FROM Win32_NetworkAdapter WHERE PhysicalAdapter = TRUE"
+            ) {
+                mac_addresses.extend(results.into_iter()
+                    .filter(|a| a.PNPDeviceID.as_deref().is_some_and(|id| id.to_uppercase().starts_with("PCI\\")))
+                    .filter_map(|a| a.MACAddress));
+            }
+        }
+    }
+
+    vec![
+        ("machineGuid", vec![crate::hardware::get_machine_guid()]),
+        ("smbiosUuid", smbios_uuid),
+        ("systemSerial", system_serial),
+        ("boardSerial", board_serial),
+        ("diskSerials", disk_serials),
+        ("macAddresses", mac_addresses),
+        ("cpuId", cpu_id()),
+        ("tpmEk", tpm_ek()),
+    ]
+}
+
+#[cfg(target_os = "linux")]
+fn collect() -> Components {
+    let mut components = read_linux_components(std::path::Path::new("/"));
+    components.push(("cpuId", cpu_id()));
+    components.push(("tpmEk", tpm_ek()));
+    components
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+fn collect() -> Components {
+    vec![("cpuId", cpu_id()), ("tpmEk", tpm_ek())]
+}
+
+/// Machine id, DMI serials, internal disk serials and permanent MACs under a root
+/// (DMI serials are only readable by root, so they are often missing)
+#[cfg(target_os = "linux")]
+pub fn read_linux_components(root: &std::path::Path) -> Components {
+    use std::fs;
+
+    let read = |path: &str| fs::read_to_string(root.join(path)).map(|v| vec![v]).unwrap_or_default();
+    let dmi = |attr: &str| read(&format!("sys/class/dmi/id/{}", attr));
+
+    let disk_serials = read_linux_disk_serials(root).into_iter().map(|(_, serial)| serial).collect();
+
+    let mut mac_addresses = Vec::new();
+    let net = root.join("sys/class/net");
+    for name in list_dir(&net) {
+        let dir = net.join(&name);
+        // Physical devices only, with the burned-in address (0 = NET_ADDR_PERM)
+        if !dir.join("device").exists() || is_usb(&dir) || read_trimmed(&dir.join("addr_assign_type")).as_deref() != Some("0") {
+            continue;
+        }
+        mac_addresses.extend(read_trimmed(&dir.join("address")));
+    }
+
+    vec![
+        ("machineGuid", read("etc/machine-id")),
+        ("smbiosUuid", dmi("product_uuid")),
+        ("systemSerial", dmi("product_serial")),
+        ("boardSerial", dmi("board_serial")),
+        ("diskSerials", disk_serials),
+        ("macAddresses", mac_addresses),
+    ]
+}
+
+/// (block device, serial) of the internal disks under a root
+#[cfg(target_os = "linux")]
+pub fn read_linux_disk_serials(root: &std::path::Path) -> Vec<(String, String)> {
+    let block = root.join("sys/block");
+    let mut disks = Vec::new();
+    for name in list_dir(&block) {
+        if ["loop", "ram", "zram", "dm-", "md", "sr", "nbd"].iter().any(|prefix| name.starts_with(prefix)) {
+            continue;
+        }
+        let dir = block.join(&name);
+        if read_trimmed(&dir.join("removable")).as_deref() == Some("1") || is_usb(&dir) {
+            continue;
+        }
+        let serial = read_trimmed(&dir.join("device/serial"))
+            .or_else(|| std::fs::read(dir.join("device/vpd_pg80")).ok().and_then(|page| unit_serial(&page)));
+        if let Some(serial) = serial {
+            disks.push((name, serial));
+        }
+    }
+    disks
+}
+
+#[cfg(target_os = "linux")]
+pub fn list_dir(dir: &std::path::Path) -> Vec<String> {
+    let mut names: Vec<String> = std::fs::read_dir(dir)
+        .map(|entries| entries.flatten().map(|e| e.file_name().to_string_lossy().into_owned()).collect())
+        .unwrap_or_default();
+    names.sort();
+    names
+}
+
+#[cfg(target_os = "linux")]
+pub fn read_trimmed(path: &std::path::Path) -> Option<String> {
+    std::fs::read_to_string(path).ok().map(|v| v.trim().to_string()).filter(|v| !v.is_empty())
+}
+
+/// Device sits behind a USB controller (sysfs links resolve through .../usbN/...)
+#[cfg(target_os = "linux")]
+pub fn is_usb(dir: &std::path::Path) -> bool {
+    std::fs::canonicalize(dir.join("device"))
+        .map(|path| path.components().any(|c| c.as_os_str().to_string_lossy().starts_with("usb")))
+        .unwrap_or(false)
+}
+
+/// Serial of a SCSI Unit Serial Number VPD page (0x80)
+#[cfg(target_os = "linux")]
+fn unit_serial(page: &[u8]) -> Option<String> {
+    if page.len() < 4 || page[1] != 0x80 {
+        return None;
+    }
+    let serial = page.get(4..4 + page[3] as usize)?;
+    Some(String::from_utf8_lossy(serial).trim().to_string()).filter(|s| !s.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn components() -> Components {
+        vec![
+            ("machineGuid", vec!["6f3b2a1e-9c4d-4e8f-b1a2-3c4d5e6f7a8b".to_string()]),
+            ("cpuId", vec!["BFEBFBFF000906EA".to_string()]),
+            ("boardSerial", vec!["/7XQ4N32/CNFCW0011C00KB/".to_string()]),
+            ("systemSerial", vec!["To Be Filled By O.E.M.".to_string()]),
+            ("diskSerials", vec!["S4EWNX0R312345".to_string(), "WD-WCC4Y0000001".to_string()]),
+            ("macAddresses", vec!["8C:16:45:12:34:56".to_string()]),
+            ("tpmEk", vec!["a1b2c3".to_string()]),
+        ]
+    }
+
+    fn without(mut components: Components, name: &str) -> Components {
+        components.retain(|(n, _)| *n != name);
+        components
+    }
+
+    #[test]
+    fn normalizes_values_and_drops_placeholders() {
+        assert_eq!(normalize("  wd-wcc4y0000001\0\0").as_deref(), Some("WD-WCC4Y0000001"));
+        assert_eq!(normalize("8c:16:45:12:34:56").as_deref(), Some("8C:16:45:12:34:56"));
+        for placeholder in ["", "  ", "To be filled by O.E.M.", "Default string", "0", "00000000", "FFFFFFFF",
+                            "XXXXXXXX", "00000000-0000-0000-0000-000000000000", "03000200-0400-0500-0006-000700080009",
+                            "00:00:00:00:00:00", "0123456789"] {
+            assert!(is_placeholder(placeholder), "{:?}", placeholder);
+        }
+    }
+
+    /// Golden vector: changing it re-identifies every machine, which needs a version bump
+    #[test]
+    fn id_is_pinned_for_fixed_components() {
+        let fingerprint = compute(&components());
+        assert_eq!(fingerprint.version, 2);
+        assert_eq!(fingerprint.id, "v2-ee33dadecbd6b651275e6632c9752720d3a4aae2bb08767a2d00f4980b2dbb0f");
+        assert_eq!(fingerprint.components["cpuId"], ["3ac86315522fde51ac25690f327f0bffd1b5bffc96647be93838d3c00fbc8390"]);
+        assert_eq!(fingerprint.components["diskSerials"].len(), 2);
+        assert!(!fingerprint.components.contains_key("systemSerial"), "placeholder kept");
+    }
+
+    #[test]
+    fn id_ignores_components_that_can_be_missing() {
+        let id = compute(&components()).id;
+        for name in ["tpmEk", "boardSerial", "diskSerials", "macAddresses"] {
+            assert_eq!(compute(&without(components(), name)).id, id, "{} changes the id", name);
+        }
+        assert_ne!(compute(&without(components(), "machineGuid")).id, id);
+        assert_ne!(compute(&without(components(), "cpuId")).id, id);
+    }
+
+    #[test]
+    fn id_does_not_depend_on_order_case_or_duplicates() {
+        let mut shuffled = components();
+        shuffled.reverse();
+        shuffled[6].1 = vec!["  6F3B2A1E-9C4D-4E8F-B1A2-3C4D5E6F7A8B".to_string(); 2];
+        assert_eq!(compute(&shuffled).id, compute(&components()).id);
+    }
+
+    #[test]
+    fn smbios_uuid_is_in_the_id_where_always_readable() {
+        let mut with_uuid = components();
+        with_uuid.push(("smbiosUuid", vec!["4C4C4544-004A-3510-804C-B4C04F4E3332".to_string()]));
+        let changes_id = compute(&with_uuid).id != compute(&components()).id;
+        assert_eq!(changes_id, !cfg!(target_os = "linux"));
+        assert_eq!(compute(&with_uuid).components["smbiosUuid"].len(), 1);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn reads_linux_components_from_a_root() {
+        let tree = crate::sysfs::fixture::Tree::new("fingerprint-root.txt");
+        let components: BTreeMap<&str, Vec<String>> = read_linux_components(&tree.0).into_iter().collect();
+        assert_eq!(components["machineGuid"], ["4c4c4544004a3510804cb4c04f4e3332\n"]);
+        assert_eq!(components["smbiosUuid"], ["4C4C4544-004A-3510-804C-B4C04F4E3332\n"]);
+        // Internal disks only, NVMe serial attribute and SATA VPD page
+        assert_eq!(components["diskSerials"], ["S4EWNX0R312345", "WD-WCC4Y0000001"]);
+        // Burned-in address of the PCI NIC, not the randomized Wi-Fi or USB adapter
+        assert_eq!(components["macAddresses"], ["8c:16:45:12:34:56"]);
+
+        let fingerprint = compute(&read_linux_components(&tree.0));
+        assert!(!fingerprint.components.contains_key("systemSerial"));
+        assert_eq!(fingerprint.components["boardSerial"].len(), 1);
+    }
+}
@@ -1,9 +1,27 @@
 //! Hardware detection module - Native Windows API access for security checks
 
 use serde::{Deserialize, Serialize};
+#[cfg(target_os = "windows")]
 use std::collections::HashMap;
+#[cfg(target_os = "windows")]
 use std::ptr;
 
+use crate::acpi::{self, IommuReport};
+use crate::authenticode::{self, SignatureInfo};
+use crate::efisig::SecureBootKeys;
+use crate::fingerprint;
+use crate::hid;
+use crate::smbios;
+use crate::pe::{self, PeInfo};
+#[cfg(target_os = "linux")]
+use crate::{efivars, procfs, sysfs, tpm};
+use crate::risk::RiskLevel;
+use crate::rules::{self, MatchRule, Subject};
+use crate::{reputation, signatures};
+use crate::signatures::SignatureBundle;
+use crate::snapshot::SystemSnapshot;
+use crate::whitelist::{self, Suppression, WhitelistType};
+
 #[cfg(target_os = "windows")]
 use windows::Win32::System::Registry::{
     RegCloseKey, RegOpenKeyExW, RegQueryValueExW, RegEnumKeyExW, HKEY, HKEY_LOCAL_MACHINE, HKEY_CURRENT_USER, KEY_READ, REG_DWORD, REG_VALUE_TYPE,
@@ -36,6 +54,12 @@ pub struct TpmStatus {
 pub struct SecureBootStatus {
     pub enabled: bool,
     pub supported: bool,
+    #[serde(default)]
+    pub setup_mode: Option<u8>, // Raw SetupMode variable: 1 = no platform key, custom keys accepted
+    #[serde(default)]
+    pub legacy_bios: bool,
+    #[serde(default)]
+    pub keys: Option<SecureBootKeys>, // PK/KEK/db/dbx classification (Linux only)
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
@@ -45,6 +69,8 @@ pub struct VirtualizationStatus {
     pub amd_v: bool,
     pub iommu: bool,
     pub kernel_dma_protection: bool, // Key for blocking DMA cheats - enforces IOMMU at OS level
+    #[serde(default)]
+    pub iommu_report: Option<IommuReport>, // Parsed DMAR / IVRS table behind iommu and kernel_dma_protection
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
@@ -68,7 +94,11 @@ pub struct CheatDetection {
     pub devices: Vec<DetectedDevice>,
     pub processes: Vec<DetectedProcess>,
     pub risk_score: u32,
-    pub risk_level: String,
+    pub risk_level: RiskLevel,
+    #[serde(default)]
+    pub suppressed: Vec<Suppression>, // Whitelist entries that fired
+    #[serde(default)]
+    pub warnings: Vec<String>, // Weak indicators that are not a detection on their own
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -78,6 +108,10 @@ pub struct DetectedDevice {
     pub device_type: String,
     pub vid: Option<String>,
     pub pid: Option<String>,
+    #[serde(default)]
+    pub hid_fingerprint: Option<String>, // Report-descriptor fingerprint of HID detections
+    #[serde(default)]
+    pub topology: Option<String>, // USB path from the root hub (topology detections)
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -86,6 +120,7 @@ pub struct DetectedProcess {
     pub name: String,
     pub matched_cheat: String,
     pub pid: u32,
+    pub pe_info: Option<PeInfo>, // Headers of the process image (when readable)
 }
 
 // ====== NEW DETECTION STRUCTS ======
@@ -99,6 +134,8 @@ pub struct NetworkMonitorResult {
     pub vpn_processes: Vec<String>,
     pub proxy_settings: Option<String>,
     pub risk_score: u32,
+    #[serde(default)]
+    pub suppressed: Vec<Suppression>, // Whitelist entries that fired
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -115,6 +152,8 @@ pub struct RegistryScanResult {
     pub traces_found: bool,
     pub traces: Vec<RegistryTrace>,
     pub risk_score: u32,
+    #[serde(default)]
+    pub suppressed: Vec<Suppression>, // Whitelist entries that fired
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -123,7 +162,8 @@ pub struct SuspiciousDriver {
     pub name: String,
     pub display_name: String,
     pub path: Option<String>,
-    pub reason: String, // "cheat_driver", "unsigned", "interception"
+    pub reason: String, // Signature reason, or "unsigned", "self_signed", "tampered", "invalid_signature", "unknown_root"
+    pub signature: Option<SignatureInfo>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
@@ -132,6 +172,8 @@ pub struct DriverIntegrityResult {
     pub suspicious_found: bool,
     pub suspicious_drivers: Vec<SuspiciousDriver>,
     pub risk_score: u32,
+    #[serde(default)]
+    pub suppressed: Vec<Suppression>, // Whitelist entries that fired
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -148,6 +190,8 @@ pub struct MacroDetectionResult {
     pub macros_detected: bool,
     pub detected_software: Vec<DetectedMacro>,
     pub risk_score: u32,
+    #[serde(default)]
+    pub suppressed: Vec<Suppression>, // Whitelist entries that fired
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -165,6 +209,8 @@ pub struct OverlayDetectionResult {
     pub overlays_found: bool,
     pub suspicious_overlays: Vec<SuspiciousOverlay>,
     pub risk_score: u32,
+    #[serde(default)]
+    pub suppressed: Vec<Suppression>, // Whitelist entries that fired
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -172,7 +218,9 @@ pub struct OverlayDetectionResult {
 pub struct SuspiciousDll {
     pub name: String,
     pub path: Option<String>,
-    pub reason: String, // "known_injector", "unsigned", "suspicious_location", "hook_dll"
+    pub reason: String, // Signature reason, or "unsigned", "self_signed", "tampered", "invalid_signature", "unknown_root"
+    pub pe_info: Option<PeInfo>,
+    pub signature: Option<SignatureInfo>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
@@ -181,6 +229,8 @@ pub struct DllInjectionResult {
     pub injection_detected: bool,
     pub suspicious_dlls: Vec<SuspiciousDll>,
     pub risk_score: u32,
+    #[serde(default)]
+    pub suppressed: Vec<Suppression>, // Whitelist entries that fired
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
@@ -217,6 +267,8 @@ pub struct CheatWindowDetectionResult {
     pub cheats_found: bool,
     pub detected_windows: Vec<DetectedCheatWindow>,
     pub risk_score: u32,
+    #[serde(default)]
+    pub suppressed: Vec<Suppression>, // Whitelist entries that fired
 }
 
 // ====== GAME DETECTION (Anti-Bypass) ======
@@ -332,7 +384,12 @@ pub fn check_tpm() -> TpmStatus {
     status
 }
 
-#[cfg(not(target_os = "windows"))]
+#[cfg(target_os = "linux")]
+pub fn check_tpm() -> TpmStatus {
+    tpm::check_status()
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
 pub fn check_tpm() -> TpmStatus {
     TpmStatus::default()
 }
@@ -486,7 +543,12 @@ pub fn check_secure_boot() -> SecureBootStatus {
     status
 }
 
-#[cfg(not(target_os = "windows"))]
+#[cfg(target_os = "linux")]
+pub fn check_secure_boot() -> SecureBootStatus {
+    efivars::check_secure_boot()
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
 pub fn check_secure_boot() -> SecureBootStatus {
     SecureBootStatus::default()
 }
@@ -580,12 +642,25 @@ pub fn check_virtualization() -> VirtualizationStatus {
     status.vt_x = is_intel && status.enabled;
     status.amd_v = is_amd && status.enabled;
     
-    // Method 4: Check registry for IOMMU
-    status.iommu = check_iommu_registry();
+    // Method 4: IOMMU from the ACPI DMAR (VT-d) / IVRS (AMD-Vi) table - only published while enabled
+    let iommu = acpi::iommu_report();
+    for reason in &iommu.reasons {
+        println!("[Hardware] IOMMU: {}", reason);
+    }
+    status.iommu = iommu.present();
     
     // Method 5: Check Kernel DMA Protection (CRITICAL for blocking DMA cheats)
-    // IOMMU present doesn't mean it's enforced - Kernel DMA Protection does the actual blocking
-    status.kernel_dma_protection = check_kernel_dma_protection();
+    // IOMMU present doesn't mean it's enforced - Kernel DMA Protection does the actual blocking,
+    // and Windows only enforces it when the firmware table opts in
+    let os_dma_protection = check_kernel_dma_protection();
+    status.kernel_dma_protection = match iommu.dma_protection_supported() {
+        Some(false) if os_dma_protection => {
+            println!("[Hardware] Kernel DMA Protection reported by Windows but the firmware does not support it");
+            false
+        }
+        _ => os_dma_protection,
+    };
+    status.iommu_report = Some(iommu);
     
     println!("[Hardware] Virtualization check: enabled={}, vt_x={}, amd_v={}, iommu={}, kernel_dma_protection={}", 
              status.enabled, status.vt_x, status.amd_v, status.iommu, status.kernel_dma_protection);
@@ -593,101 +668,48 @@ pub fn check_virtualization() -> VirtualizationStatus {
     status
 }
 
-/// Check IOMMU (VT-d/AMD-Vi) via multiple methods
+/// Check Kernel DMA Protection (DMA Guard) - CRITICAL for blocking DMA cheats
+/// IOMMU being "present" doesn't mean it's enforced. Kernel DMA Protection actually blocks unauthorized DMA.
+/// Without this, a DMA cheat device can still read game memory even with IOMMU "enabled".
 #[cfg(target_os = "windows")]
-fn check_iommu_registry() -> bool {
-    // Method 1: Enumerate FIRMWARE\ACPI tables for DMAR signature (most reliable for Intel VT-d)
-    if check_acpi_firmware_tables() {
-        return true;
-    }
-    
-    // Method 2: ACPI DMAR table (Intel VT-d) - Check multiple possible paths
-    let dmar_paths = [
-        "HARDWARE\\ACPI\\DMAR",
-        "HARDWARE\\ACPI\\DSDT\\INTL\\DMAR",
-        "HARDWARE\\ACPI\\FADT\\DMAR",
-        "HARDWARE\\ACPI\\RSDT\\DMAR",
-        "HARDWARE\\ACPI\\XSDT\\DMAR",
-    ];
-    
-    for dmar_path in dmar_paths {
-        unsafe {
-            let key_path: Vec<u16> = format!("{}\0", dmar_path).encode_utf16().collect();
-            let mut hkey: HKEY = HKEY::default();
-            let result = RegOpenKeyExW(
-                HKEY_LOCAL_MACHINE,
-                PCWSTR(key_path.as_ptr()),
-                0,
-                KEY_READ,
-                &mut hkey,
-            );
-
-            if result.is_ok() {
-                let _ = RegCloseKey(hkey);
-                println!("[Hardware] IOMMU detected via ACPI DMAR table at {}", dmar_path);
-                return true;
-            }
-        }
-    }
-
-    // Method 3: ACPI IVRS table (AMD-Vi equivalent)
-    let ivrs_paths = [
-        "HARDWARE\\ACPI\\IVRS",
-        "HARDWARE\\ACPI\\DSDT\\AMDM\\IVRS",
-        "HARDWARE\\ACPI\\RSDT\\IVRS",
-        "HARDWARE\\ACPI\\XSDT\\IVRS",
-    ];
+fn check_kernel_dma_protection() -> bool {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x08000000;
     
-    for ivrs_path in ivrs_paths {
-        unsafe {
-            let key_path: Vec<u16> = format!("{}\0", ivrs_path).encode_utf16().collect();
-            let mut hkey: HKEY = HKEY::default();
-            let result = RegOpenKeyExW(
-                HKEY_LOCAL_MACHINE,
-                PCWSTR(key_path.as_ptr()),
-                0,
-                KEY_READ,
-                &mut hkey,
-            );
-
-            if result.is_ok() {
-                let _ = RegCloseKey(hkey);
-                println!("[Hardware] IOMMU detected via ACPI IVRS table at {}", ivrs_path);
-                return true;
-            }
-        }
-    }
-
-    // Method 4: WMI DeviceGuard AvailableSecurityProperties
+    // Method 1: Check DeviceGuard WMI for DmaGuardPolicy (most reliable)
     if let Ok(com_con) = COMLibrary::new() {
         if let Ok(wmi_con) = WMIConnection::with_namespace_path("root\\Microsoft\\Windows\\DeviceGuard", com_con) {
             #[derive(Deserialize)]
             #[allow(non_snake_case)]
             struct Win32DeviceGuard {
                 AvailableSecurityProperties: Option<Vec<i32>>,
+                SecurityServicesRunning: Option<Vec<i32>>,
             }
 
             if let Ok(results) = wmi_con.query::<Win32DeviceGuard>() {
                 if let Some(dg) = results.first() {
-                    if let Some(props) = &dg.AvailableSecurityProperties {
-                        println!("[Hardware] DeviceGuard AvailableSecurityProperties: {:?}", props);
-                        // 3 = DMA protection available, 6 = Kernel DMA Guard, 7 = MAT/IOMMU
-                        if props.contains(&3) || props.contains(&6) || props.contains(&7) {
-                            println!("[Hardware] IOMMU detected via DeviceGuard properties");
+                    // Check AvailableSecurityProperties - value 7 = DmaProtection is available
+                    // Check SecurityServicesRunning - value 7 = DmaProtection is running
+                    if let Some(running) = &dg.SecurityServicesRunning {
+                        if running.contains(&7) {
+                            println!("[Hardware] Kernel DMA Protection ACTIVE via DeviceGuard WMI");
                             return true;
                         }
                     }
+                    if let Some(available) = &dg.AvailableSecurityProperties {
+                        println!("[Hardware] DeviceGuard Available Properties: {:?}", available);
+                    }
                 }
             }
         }
     }
-
-    // Method 5: Check Kernel DMA Protection registry
+    
+    // Method 2: Check registry for DMA Guard policy state
     unsafe {
         let key_path: Vec<u16> = "SYSTEM\\CurrentControlSet\\Control\\DmaSecurity\0"
             .encode_utf16()
             .collect();
-        let value_name: Vec<u16> = "DmaKernelProtection\0".encode_utf16().collect();
+        let value_name: Vec<u16> = "DmaGuardPolicyEnabled\0".encode_utf16().collect();
 
         let mut hkey: HKEY = HKEY::default();
         let result = RegOpenKeyExW(
@@ -715,18 +737,17 @@ fn check_iommu_registry() -> bool {
             let _ = RegCloseKey(hkey);
 
             if query_result.is_ok() && data_type == REG_DWORD && data == 1 {
-                println!("[Hardware] IOMMU detected via Kernel DMA Protection");
+                println!("[Hardware] Kernel DMA Protection enabled via DmaGuardPolicyEnabled registry");
                 return true;
             }
         }
     }
-
-    // Method 6: Check DMA Guard policy requirement
+    
+    // Method 3: Check DeviceGuard Scenarios policy for DMA
     unsafe {
-        let key_path: Vec<u16> = "SYSTEM\\CurrentControlSet\\Control\\DeviceGuard\0"
+        let key_path: Vec<u16> = "SYSTEM\\CurrentControlSet\\Control\\DeviceGuard\\Scenarios\\KernelShadowStacks\0"
             .encode_utf16()
             .collect();
-        let value_name: Vec<u16> = "RequirePlatformSecurityFeatures\0".encode_utf16().collect();
 
         let mut hkey: HKEY = HKEY::default();
         let result = RegOpenKeyExW(
@@ -738,547 +759,115 @@ fn check_iommu_registry() -> bool {
         );
 
         if result.is_ok() {
-            let mut data: u32 = 0;
-            let mut data_size: u32 = 4;
-            let mut data_type: REG_VALUE_TYPE = REG_VALUE_TYPE(0);
-
-            let query_result = RegQueryValueExW(
-                hkey,
-                PCWSTR(value_name.as_ptr()),
-                Some(ptr::null_mut()),
-                Some(&mut data_type),
-                Some(&mut data as *mut u32 as *mut u8),
-                Some(&mut data_size),
-            );
-
             let _ = RegCloseKey(hkey);
-
-            if query_result.is_ok() && data_type == REG_DWORD && data >= 3 {
-                println!("[Hardware] IOMMU detected via DMA Guard requirement");
-                return true;
-            }
+            // Kernel Shadow Stacks requires Kernel DMA Protection
+            println!("[Hardware] Kernel Shadow Stacks present - DMA protection likely enabled");
         }
     }
     
-    // Method 7: Check via WMI Processor feature flags
-    if let Ok(com_con) = COMLibrary::new() {
-        if let Ok(wmi_con) = WMIConnection::new(com_con) {
-            #[derive(Deserialize)]
-            #[allow(non_snake_case)]
-            struct Win32Processor {
-                SecondLevelAddressTranslationExtensions: Option<bool>,
-            }
-
-            if let Ok(results) = wmi_con.query::<Win32Processor>() {
-                if let Some(proc) = results.first() {
-                    // SLAT is required for IOMMU to work
-                    if proc.SecondLevelAddressTranslationExtensions.unwrap_or(false) {
-                        println!("[Hardware] IOMMU likely available - SLAT extensions detected via WMI");
-                        return true;
-                    }
-                }
-            }
+    // Method 4: PowerShell msinfo32 check for Kernel DMA Protection
+    if let Ok(output) = std::process::Command::new("powershell")
+        .args(["-NoProfile", "-Command", 
+               "(Get-CimInstance -Namespace root/Microsoft/Windows/DeviceGuard -ClassName Win32_DeviceGuard).SecurityServicesRunning -contains 7"])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+    {
+        let stdout = String::from_utf8_lossy(&output.stdout).trim().to_lowercase();
+        if stdout == "true" {
+            println!("[Hardware] Kernel DMA Protection confirmed via PowerShell CIM query");
+            return true;
         }
     }
     
-    // Method 8: Check ACPI DSDT tables enumeration (enumerate all manufacturer tables)
-    unsafe {
-        let key_path: Vec<u16> = "HARDWARE\\ACPI\\DSDT\0"
-            .encode_utf16()
-            .collect();
-
-        let mut hkey: HKEY = HKEY::default();
-        let result = RegOpenKeyExW(
-            HKEY_LOCAL_MACHINE,
-            PCWSTR(key_path.as_ptr()),
-            0,
-            KEY_READ,
-            &mut hkey,
-        );
-
-        if result.is_ok() {
-            // Enumerate subkeys to find DMAR or IVRS
-            let mut index: u32 = 0;
-            let mut name_buffer: [u16; 256] = [0; 256];
-            
-            loop {
-                let mut name_len: u32 = 256;
-                let enum_result = RegEnumKeyExW(
-                    hkey,
-                    index,
-                    windows::core::PWSTR(name_buffer.as_mut_ptr()),
-                    &mut name_len,
-                    Some(ptr::null_mut()),
-                    windows::core::PWSTR::null(),
-                    Some(ptr::null_mut()),
-                    Some(ptr::null_mut()),
-                );
-                
-                if enum_result.is_err() {
-                    break;
-                }
-                
-                let subkey_name = String::from_utf16_lossy(&name_buffer[..name_len as usize]);
-                println!("[Hardware] Found ACPI DSDT subkey: {}", subkey_name);
-                
-                // Check if this manufacturer has DMAR table
-                let dmar_check_path: Vec<u16> = format!("HARDWARE\\ACPI\\DSDT\\{}\\DMAR\0", subkey_name)
-                    .encode_utf16()
-                    .collect();
-                
-                let mut dmar_hkey: HKEY = HKEY::default();
-                let dmar_result = RegOpenKeyExW(
-                    HKEY_LOCAL_MACHINE,
-                    PCWSTR(dmar_check_path.as_ptr()),
-                    0,
-                    KEY_READ,
-                    &mut dmar_hkey,
-                );
-                
-                if dmar_result.is_ok() {
-                    let _ = RegCloseKey(dmar_hkey);
-                    let _ = RegCloseKey(hkey);
-                    println!("[Hardware] IOMMU detected via DSDT/{}/DMAR", subkey_name);
-                    return true;
-                }
-                
-                index += 1;
-                if index > 100 { break; } // Safety limit
-            }
-            
-            let _ = RegCloseKey(hkey);
+    // Method 5: Check for Kernel DMA Protection in system info
+    // This is what msinfo32.exe displays as "Kernel DMA Protection: On"
+    if let Ok(output) = std::process::Command::new("powershell")
+        .args(["-NoProfile", "-Command", 
+               "$dma = (Get-ItemProperty -Path 'HKLM:\\SYSTEM\\CurrentControlSet\\Control\\DmaSecurity' -Name 'DmaGuardPolicyEnabled' -ErrorAction SilentlyContinue).DmaGuardPolicyEnabled; if ($dma -eq 1) { 'Enabled' } else { 'Disabled' }"])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+    {
+        let stdout = String::from_utf8_lossy(&output.stdout).trim().to_lowercase();
+        if stdout == "enabled" {
+            println!("[Hardware] Kernel DMA Protection enabled via PowerShell registry check");
+            return true;
         }
     }
     
-    // Method 9: Check msinfo32 SystemSummary for VT-d/IOMMU indication via WMI
+    println!("[Hardware] Kernel DMA Protection NOT detected - DMA cheats may be possible even with IOMMU!");
+    false
+}
+
+/// IOMMU and DMA protection from the ACPI tables and the units the kernel enabled
+#[cfg(target_os = "linux")]
+pub fn check_virtualization() -> VirtualizationStatus {
+    let iommu = acpi::iommu_report();
+    for reason in &iommu.reasons {
+        println!("[Hardware] IOMMU: {}", reason);
+    }
+
+    // The tables need root; the kernel's own units are readable by anyone
+    let enabled_units = iommu.active_units.unwrap_or(0) > 0;
+    let status = VirtualizationStatus {
+        iommu: enabled_units || (iommu.present() && iommu.active_units.is_none()),
+        kernel_dma_protection: iommu.kernel_dma_protection
+            .unwrap_or(enabled_units && iommu.dma_platform_opt_in),
+        iommu_report: Some(iommu),
+        ..Default::default()
+    };
+
+    println!("[Hardware] Virtualization check: iommu={}, kernel_dma_protection={}",
+             status.iommu, status.kernel_dma_protection);
+    status
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+pub fn check_virtualization() -> VirtualizationStatus {
+    VirtualizationStatus::default()
+}
+
+/// Check Windows Defender status
+#[cfg(target_os = "windows")]
+pub fn check_defender() -> DefenderStatus {
+    let mut status = DefenderStatus::default();
+
+    // Method 1: WMI (most accurate - runtime status)
     if let Ok(com_con) = COMLibrary::new() {
-        if let Ok(wmi_con) = WMIConnection::new(com_con) {
+        if let Ok(wmi_con) = WMIConnection::with_namespace_path("root\\Microsoft\\Windows\\Defender", com_con) {
             #[derive(Deserialize)]
             #[allow(non_snake_case)]
-            struct Win32ComputerSystem {
-                HypervisorPresent: Option<bool>,
+            struct MpComputerStatus {
+                AntivirusEnabled: Option<bool>,
+                RealTimeProtectionEnabled: Option<bool>,
+                IsTamperProtected: Option<bool>,
             }
 
-            if let Ok(results) = wmi_con.query::<Win32ComputerSystem>() {
-                if let Some(cs) = results.first() {
-                    // If Hyper-V/hypervisor is running, IOMMU is likely enabled
-                    // as modern hypervisors require IOMMU for passthrough
-                    if cs.HypervisorPresent.unwrap_or(false) {
-                        println!("[Hardware] Hypervisor present - IOMMU likely enabled");
-                        // Don't return true yet, this is just a hint
-                    }
+            if let Ok(results) = wmi_con.raw_query::<MpComputerStatus>(
+                "SELECT AntivirusEnabled, RealTimeProtectionEnabled, IsTamperProtected FROM MSFT_MpComputerStatus"
+            ) {
+                if let Some(mp) = results.first() {
+                    status.enabled = mp.AntivirusEnabled.unwrap_or(false);
+                    status.real_time_protection = mp.RealTimeProtectionEnabled.unwrap_or(false);
+                    status.tamper_protection = mp.IsTamperProtected.unwrap_or(false);
+                    println!("[Hardware] Defender via WMI: enabled={}, realtime={}, tamper={}", 
+                             status.enabled, status.real_time_protection, status.tamper_protection);
+                    return status;
                 }
             }
         }
     }
-    
-    // Method 10: Check PnP devices for IOMMU-related devices
-    if check_iommu_pnp_devices() {
-        return true;
-    }
-    
-    println!("[Hardware] IOMMU not detected via standard methods");
-    false
-}
 
-/// Check ACPI firmware tables for DMAR/IVRS signatures (most reliable method)
-#[cfg(target_os = "windows")]
-fn check_acpi_firmware_tables() -> bool {
-    use std::os::windows::process::CommandExt;
-    const CREATE_NO_WINDOW: u32 = 0x08000000;
-    
-    // Method 1: Use PowerShell to query ACPI tables directly (most reliable)
-    if let Ok(output) = std::process::Command::new("powershell")
-        .args(["-NoProfile", "-Command", 
-               "Get-WmiObject -Namespace root\\WMI -Class MSAcpi_Table -ErrorAction SilentlyContinue | Where-Object { $_.Name -eq 'DMAR' -or $_.Name -eq 'IVRS' } | Select-Object -First 1 | ForEach-Object { $_.Name }"])
-        .creation_flags(CREATE_NO_WINDOW)
-        .output()
-    {
-        let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
-        if stdout == "DMAR" || stdout == "IVRS" {
-            println!("[Hardware] IOMMU detected via WMI MSAcpi_Table: {}", stdout);
-            return true;
-        }
-    }
-    
-    // Method 2: Check EnumSystemFirmwareTables for DMAR signature
-    if check_firmware_table_dmar() {
-        return true;
-    }
+    // Method 2: Registry fallback - check if Defender is disabled via policy/registry
+    // This catches cases where WMI namespace is inaccessible (Group Policy, Windows Server, etc.)
+    println!("[Hardware] Defender WMI unavailable, falling back to registry");
     
-    // Method 3: Enumerate registry FIRMWARE\ACPI keys
+    // Check if Defender is NOT disabled via policy
+    let mut defender_disabled = false;
     unsafe {
-        let key_path: Vec<u16> = "HARDWARE\\FIRMWARE\\ACPI\0"
+        let key_path: Vec<u16> = "SOFTWARE\\Policies\\Microsoft\\Windows Defender\0"
             .encode_utf16()
             .collect();
-
-        let mut hkey: HKEY = HKEY::default();
-        let result = RegOpenKeyExW(
-            HKEY_LOCAL_MACHINE,
-            PCWSTR(key_path.as_ptr()),
-            0,
-            KEY_READ,
-            &mut hkey,
-        );
-
-        if result.is_ok() {
-            let mut index: u32 = 0;
-            let mut name_buffer: [u16; 256] = [0; 256];
-            
-            loop {
-                let mut name_len: u32 = 256;
-                let enum_result = RegEnumKeyExW(
-                    hkey,
-                    index,
-                    windows::core::PWSTR(name_buffer.as_mut_ptr()),
-                    &mut name_len,
-                    Some(ptr::null_mut()),
-                    windows::core::PWSTR::null(),
-                    Some(ptr::null_mut()),
-                    Some(ptr::null_mut()),
-                );
-                
-                if enum_result.is_err() {
-                    break;
-                }
-                
-                let table_sig = String::from_utf16_lossy(&name_buffer[..name_len as usize]);
-                println!("[Hardware] Found ACPI firmware table: {}", table_sig);
-                
-                // DMAR = Intel VT-d DMA Remapping table
-                // IVRS = AMD I/O Virtualization Reporting Structure
-                if table_sig == "DMAR" || table_sig == "IVRS" {
-                    let _ = RegCloseKey(hkey);
-                    println!("[Hardware] IOMMU detected via FIRMWARE\\ACPI\\{} table", table_sig);
-                    return true;
-                }
-                
-                index += 1;
-                if index > 100 { break; }
-            }
-            
-            let _ = RegCloseKey(hkey);
-        }
-    }
-    false
-}
-
-/// Check for DMAR table using EnumSystemFirmwareTables API
-#[cfg(target_os = "windows")]
-fn check_firmware_table_dmar() -> bool {
-    use windows::Win32::System::SystemInformation::{EnumSystemFirmwareTables, FIRMWARE_TABLE_PROVIDER};
-    
-    unsafe {
-        // ACPI signature (little-endian 'ACPI')
-        let acpi_provider = FIRMWARE_TABLE_PROVIDER(0x41435049); // 'ACPI'
-        
-        // First call to get buffer size
-        let size = EnumSystemFirmwareTables(acpi_provider, None);
-        if size == 0 {
-            println!("[Hardware] EnumSystemFirmwareTables returned 0");
-            return false;
-        }
-        
-        let mut buffer: Vec<u8> = vec![0; size as usize];
-        let actual_size = EnumSystemFirmwareTables(acpi_provider, Some(&mut buffer));
-        
-        if actual_size == 0 {
-            return false;
-        }
-        
-        // Each table signature is 4 bytes
-        for chunk in buffer.chunks(4) {
-            if chunk.len() == 4 {
-                let sig = String::from_utf8_lossy(chunk).to_string();
-                println!("[Hardware] EnumSystemFirmwareTables found: {}", sig);
-                
-                if sig == "DMAR" || sig == "IVRS" {
-                    println!("[Hardware] IOMMU detected via EnumSystemFirmwareTables: {}", sig);
-                    return true;
-                }
-            }
-        }
-    }
-    
-    false
-}
-
-/// Check PnP devices for IOMMU-related device classes
-#[cfg(target_os = "windows")]
-fn check_iommu_pnp_devices() -> bool {
-    // Check for ACPI\DMAR or related device IDs in device manager
-    let device_patterns = [
-        "SYSTEM\\CurrentControlSet\\Enum\\ACPI\\DMAR",
-        "SYSTEM\\CurrentControlSet\\Enum\\ACPI\\IVRS",
-        "SYSTEM\\CurrentControlSet\\Enum\\ACPI_HAL\\DMAR",
-    ];
-    
-    for pattern in device_patterns {
-        unsafe {
-            let key_path: Vec<u16> = format!("{}\0", pattern).encode_utf16().collect();
-            let mut hkey: HKEY = HKEY::default();
-            let result = RegOpenKeyExW(
-                HKEY_LOCAL_MACHINE,
-                PCWSTR(key_path.as_ptr()),
-                0,
-                KEY_READ,
-                &mut hkey,
-            );
-
-            if result.is_ok() {
-                let _ = RegCloseKey(hkey);
-                println!("[Hardware] IOMMU detected via PnP device at {}", pattern);
-                return true;
-            }
-        }
-    }
-    
-    // Check for HAL DMA remapping capability via HAL extension
-    unsafe {
-        let key_path: Vec<u16> = "SYSTEM\\CurrentControlSet\\Control\\HAL\0"
-            .encode_utf16()
-            .collect();
-        let value_name: Vec<u16> = "HalDmaRemapping\0".encode_utf16().collect();
-
-        let mut hkey: HKEY = HKEY::default();
-        let result = RegOpenKeyExW(
-            HKEY_LOCAL_MACHINE,
-            PCWSTR(key_path.as_ptr()),
-            0,
-            KEY_READ,
-            &mut hkey,
-        );
-
-        if result.is_ok() {
-            let mut data: u32 = 0;
-            let mut data_size: u32 = 4;
-            let mut data_type: REG_VALUE_TYPE = REG_VALUE_TYPE(0);
-
-            let query_result = RegQueryValueExW(
-                hkey,
-                PCWSTR(value_name.as_ptr()),
-                Some(ptr::null_mut()),
-                Some(&mut data_type),
-                Some(&mut data as *mut u32 as *mut u8),
-                Some(&mut data_size),
-            );
-
-            let _ = RegCloseKey(hkey);
-
-            if query_result.is_ok() && data_type == REG_DWORD && data > 0 {
-                println!("[Hardware] IOMMU detected via HAL DMA Remapping flag");
-                return true;
-            }
-        }
-    }
-    
-    // Check boot configuration for DMA remapping
-    unsafe {
-        let key_path: Vec<u16> = "SYSTEM\\CurrentControlSet\\Control\\Session Manager\\Memory Management\0"
-            .encode_utf16()
-            .collect();
-        let _value_name: Vec<u16> = "FeatureSettingsOverride\0".encode_utf16().collect();
-
-        let mut hkey: HKEY = HKEY::default();
-        let result = RegOpenKeyExW(
-            HKEY_LOCAL_MACHINE,
-            PCWSTR(key_path.as_ptr()),
-            0,
-            KEY_READ,
-            &mut hkey,
-        );
-
-        if result.is_ok() {
-            // Check if mitigations requiring IOMMU are enabled
-            let _ = RegCloseKey(hkey);
-        }
-    }
-    
-    false
-}
-
-/// Check Kernel DMA Protection (DMA Guard) - CRITICAL for blocking DMA cheats
-/// IOMMU being "present" doesn't mean it's enforced. Kernel DMA Protection actually blocks unauthorized DMA.
-/// Without this, a DMA cheat device can still read game memory even with IOMMU "enabled".
-#[cfg(target_os = "windows")]
-fn check_kernel_dma_protection() -> bool {
-    use std::os::windows::process::CommandExt;
-    const CREATE_NO_WINDOW: u32 = 0x08000000;
-    
-    // Method 1: Check DeviceGuard WMI for DmaGuardPolicy (most reliable)
-    if let Ok(com_con) = COMLibrary::new() {
-        if let Ok(wmi_con) = WMIConnection::with_namespace_path("root\\Microsoft\\Windows\\DeviceGuard", com_con) {
-            #[derive(Deserialize)]
-            #[allow(non_snake_case)]
-            struct Win32DeviceGuard {
-                AvailableSecurityProperties: Option<Vec<i32>>,
-                SecurityServicesRunning: Option<Vec<i32>>,
-            }
-
-            if let Ok(results) = wmi_con.query::<Win32DeviceGuard>() {
-                if let Some(dg) = results.first() {
-                    // Check AvailableSecurityProperties - value 7 = DmaProtection is available
-                    // Check SecurityServicesRunning - value 7 = DmaProtection is running
-                    if let Some(running) = &dg.SecurityServicesRunning {
-                        if running.contains(&7) {
-                            println!("[Hardware] Kernel DMA Protection ACTIVE via DeviceGuard WMI");
-                            return true;
-                        }
-                    }
-                    if let Some(available) = &dg.AvailableSecurityProperties {
-                        println!("[Hardware] DeviceGuard Available Properties: {:?}", available);
-                    }
-                }
-            }
-        }
-    }
-    
-    // Method 2: Check registry for DMA Guard policy state
-    unsafe {
-        let key_path: Vec<u16> = "SYSTEM\\CurrentControlSet\\Control\\DmaSecurity\0"
-            .encode_utf16()
-            .collect();
-        let value_name: Vec<u16> = "DmaGuardPolicyEnabled\0".encode_utf16().collect();
-
-        let mut hkey: HKEY = HKEY::default();
-        let result = RegOpenKeyExW(
-            HKEY_LOCAL_MACHINE,
-            PCWSTR(key_path.as_ptr()),
-            0,
-            KEY_READ,
-            &mut hkey,
-        );
-
-        if result.is_ok() {
-            let mut data: u32 = 0;
-            let mut data_size: u32 = 4;
-            let mut data_type: REG_VALUE_TYPE = REG_VALUE_TYPE(0);
-
-            let query_result = RegQueryValueExW(
-                hkey,
-                PCWSTR(value_name.as_ptr()),
-                Some(ptr::null_mut()),
-                Some(&mut data_type),
-                Some(&mut data as *mut u32 as *mut u8),
-                Some(&mut data_size),
-            );
-
-            let _ = RegCloseKey(hkey);
-
-            if query_result.is_ok() && data_type == REG_DWORD && data == 1 {
-                println!("[Hardware] Kernel DMA Protection enabled via DmaGuardPolicyEnabled registry");
-                return true;
-            }
-        }
-    }
-    
-    // Method 3: Check DeviceGuard Scenarios policy for DMA
-    unsafe {
-        let key_path: Vec<u16> = "SYSTEM\\CurrentControlSet\\Control\\DeviceGuard\\Scenarios\\KernelShadowStacks\0"
-            .encode_utf16()
-            .collect();
-
-        let mut hkey: HKEY = HKEY::default();
-        let result = RegOpenKeyExW(
-            HKEY_LOCAL_MACHINE,
-            PCWSTR(key_path.as_ptr()),
-            0,
-            KEY_READ,
-            &mut hkey,
-        );
-
-        if result.is_ok() {
-            let _ = RegCloseKey(hkey);
-            // Kernel Shadow Stacks requires Kernel DMA Protection
-            println!("[Hardware] Kernel Shadow Stacks present - DMA protection likely enabled");
-        }
-    }
-    
-    // Method 4: PowerShell msinfo32 check for Kernel DMA Protection
-    if let Ok(output) = std::process::Command::new("powershell")
-        .args(["-NoProfile", "-Command", 
-               "(Get-CimInstance -Namespace root/Microsoft/Windows/DeviceGuard -ClassName Win32_DeviceGuard).SecurityServicesRunning -contains 7"])
-        .creation_flags(CREATE_NO_WINDOW)
-        .output()
-    {
-        let stdout = String::from_utf8_lossy(&output.stdout).trim().to_lowercase();
-        if stdout == "true" {
-            println!("[Hardware] Kernel DMA Protection confirmed via PowerShell CIM query");
-            return true;
-        }
-    }
-    
-    // Method 5: Check for Kernel DMA Protection in system info
-    // This is what msinfo32.exe displays as "Kernel DMA Protection: On"
-    if let Ok(output) = std::process::Command::new("powershell")
-        .args(["-NoProfile", "-Command", 
-               "$dma = (Get-ItemProperty -Path 'HKLM:\\SYSTEM\\CurrentControlSet\\Control\\DmaSecurity' -Name 'DmaGuardPolicyEnabled' -ErrorAction SilentlyContinue).DmaGuardPolicyEnabled; if ($dma -eq 1) { 'Enabled' } else { 'Disabled' }"])
-        .creation_flags(CREATE_NO_WINDOW)
-        .output()
-    {
-        let stdout = String::from_utf8_lossy(&output.stdout).trim().to_lowercase();
-        if stdout == "enabled" {
-            println!("[Hardware] Kernel DMA Protection enabled via PowerShell registry check");
-            return true;
-        }
-    }
-    
-    println!("[Hardware] Kernel DMA Protection NOT detected - DMA cheats may be possible even with IOMMU!");
-    false
-}
-
-#[cfg(not(target_os = "windows"))]
-fn check_kernel_dma_protection() -> bool {
-    false
-}
-
-#[cfg(not(target_os = "windows"))]
-pub fn check_virtualization() -> VirtualizationStatus {
-    VirtualizationStatus::default()
-}
-
-/// Check Windows Defender status
-#[cfg(target_os = "windows")]
-pub fn check_defender() -> DefenderStatus {
-    let mut status = DefenderStatus::default();
-
-    // Method 1: WMI (most accurate - runtime status)
-    if let Ok(com_con) = COMLibrary::new() {
-        if let Ok(wmi_con) = WMIConnection::with_namespace_path("root\\Microsoft\\Windows\\Defender", com_con) {
-            #[derive(Deserialize)]
-            #[allow(non_snake_case)]
-            struct MpComputerStatus {
-                AntivirusEnabled: Option<bool>,
-                RealTimeProtectionEnabled: Option<bool>,
-                IsTamperProtected: Option<bool>,
-            }
-
-            if let Ok(results) = wmi_con.raw_query::<MpComputerStatus>(
-                "SELECT AntivirusEnabled, RealTimeProtectionEnabled, IsTamperProtected FROM MSFT_MpComputerStatus"
-            ) {
-                if let Some(mp) = results.first() {
-                    status.enabled = mp.AntivirusEnabled.unwrap_or(false);
-                    status.real_time_protection = mp.RealTimeProtectionEnabled.unwrap_or(false);
-                    status.tamper_protection = mp.IsTamperProtected.unwrap_or(false);
-                    println!("[Hardware] Defender via WMI: enabled={}, realtime={}, tamper={}", 
-                             status.enabled, status.real_time_protection, status.tamper_protection);
-                    return status;
-                }
-            }
-        }
-    }
-
-    // Method 2: Registry fallback - check if Defender is disabled via policy/registry
-    // This catches cases where WMI namespace is inaccessible (Group Policy, Windows Server, etc.)
-    println!("[Hardware] Defender WMI unavailable, falling back to registry");
-    
-    // Check if Defender is NOT disabled via policy
-    let mut defender_disabled = false;
-    unsafe {
-        let key_path: Vec<u16> = "SOFTWARE\\Policies\\Microsoft\\Windows Defender\0"
-            .encode_utf16()
-            .collect();
-        let value_name: Vec<u16> = "DisableAntiSpyware\0".encode_utf16().collect();
+        let value_name: Vec<u16> = "DisableAntiSpyware\0".encode_utf16().collect();
 
         let mut hkey: HKEY = HKEY::default();
         let result = RegOpenKeyExW(
@@ -1521,24 +1110,9 @@ pub fn get_machine_guid() -> String {
     String::new()
 }
 
-#[cfg(not(target_os = "windows"))]
-pub fn get_machine_guid() -> String {
-    String::new()
-}
-
-/// Generate hardware ID from system components
+/// Generate hardware ID from system components (see fingerprint)
 pub fn generate_hardware_id() -> String {
-    use std::collections::hash_map::DefaultHasher;
-    use std::hash::{Hash, Hasher};
-
-    let machine_guid = get_machine_guid();
-    let tpm = check_tpm();
-
-    let mut hasher = DefaultHasher::new();
-    machine_guid.hash(&mut hasher);
-    tpm.manufacturer.hash(&mut hasher);
-
-    format!("{:016x}", hasher.finish())
+    fingerprint::fingerprint().id
 }
 
 /// Get full security status
@@ -1564,169 +1138,231 @@ pub fn get_full_security_status() -> SecurityStatus {
     }
 }
 
+// ====== REGISTRY HELPERS ======
+
+/// Check whether a key exists under HKEY_LOCAL_MACHINE
+pub fn hklm_key_exists(path: &str) -> bool {
+    #[cfg(target_os = "windows")]
+    {
+        registry_key_exists(HKEY_LOCAL_MACHINE, path)
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        let _ = path;
+        false
+    }
+}
+
+/// Check whether a key exists under HKEY_CURRENT_USER
+pub fn hkcu_key_exists(path: &str) -> bool {
+    #[cfg(target_os = "windows")]
+    {
+        registry_key_exists(HKEY_CURRENT_USER, path)
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        let _ = path;
+        false
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn registry_key_exists(root: HKEY, path: &str) -> bool {
+    unsafe {
+        let key_path: Vec<u16> = format!("{}\0", path).encode_utf16().collect();
+        let mut hkey: HKEY = HKEY::default();
+
+        let open_result = RegOpenKeyExW(
+            root,
+            PCWSTR(key_path.as_ptr()),
+            0,
+            KEY_READ,
+            &mut hkey,
+        );
+
+        if open_result.is_ok() {
+            let _ = RegCloseKey(hkey);
+            true
+        } else {
+            false
+        }
+    }
+}
+
 // ====== CHEAT DETECTION ======
+// The signature lists in this file are the compiled-in fallback for the
+// server-delivered bundle (see signatures.rs). Detectors read signatures::current().
+// Entries are match rules (see rules.rs): bare values match the whole name.
 
-const CHEAT_DEVICES: &[(&str, &str, Option<&str>)] = &[
+pub(crate) const CHEAT_DEVICES: &[(&str, &str)] = &[
     // Cronus devices
-    ("2341", "Cronus Zen", Some("8036")),
-    ("2341", "Cronus Max", Some("8037")),
+    ("usb:2341:8036", "Cronus Zen"),
+    ("usb:2341:8037", "Cronus Max"),
     // XIM devices
-    ("0738", "XIM Apex", Some("cb14")),
-    ("0738", "XIM 4", Some("cb12")),
+    ("usb:0738:cb14", "XIM Apex"),
+    ("usb:0738:cb12", "XIM 4"),
     // Titan devices
-    ("2341", "Titan One", Some("0001")),
-    ("2341", "Titan Two", Some("0002")),
+    ("usb:2341:0001", "Titan One"),
+    ("usb:2341:0002", "Titan Two"),
     // ReaSnow
-    ("0483", "ReaSnow S1", Some("5740")),
+    ("usb:0483:5740", "ReaSnow S1"),
 ];
 
-const CHEAT_PROCESSES: &[&str] = &[
+pub(crate) const CHEAT_PROCESSES: &[&str] = &[
     // Cronus/XIM software
-    "zen studio", "zenith", "cronus", "xim apex manager", "xim4 manager",
-    "titan one", "titan two", "gtuner", "t1 flasher", "t2 suite",
-    "reasnow", "s1 setup",
+    "prefix:zen studio", "prefix:zenith", "prefix:cronus",
+    "prefix:xim apex manager", "prefix:xim4 manager",
+    "prefix:titan one", "prefix:titan two", "prefix:gtuner",
+    "prefix:t1 flasher", "prefix:t2 suite",
+    "prefix:reasnow", "prefix:s1 setup",
     // DS4Windows and similar
-    "ds4windows", "ds4window", "ds4", "ds4tool",
-    "inputmapper", "scptoolkit", "scpserver",
-    "betterjoyforcemu", "betterjoy",
-    "x360ce", "vigembus", "hidhide",
+    "prefix:ds4window", "re:^ds4(tool)?(\\.exe)?$",
+    "prefix:inputmapper", "prefix:scptoolkit", "prefix:scpserver",
+    "prefix:betterjoy",
+    "prefix:x360ce", "prefix:vigembus", "prefix:hidhide",
     // Macros
-    "autohotkey", "ahk", "macro recorder", "tinytask",
-    "rewasd", "antimicro", "xpadder", "joytokey",
+    "prefix:autohotkey", "re:^ahk[\\w-]*\\.exe$", "prefix:macro recorder", "prefix:tinytask",
+    "prefix:rewasd", "prefix:antimicro", "prefix:xpadder", "prefix:joytokey",
 ];
 
 /// Detect cheat devices and processes
-#[cfg(target_os = "windows")]
-pub fn detect_cheats() -> CheatDetection {
+pub fn detect_cheats(snapshot: &SystemSnapshot) -> CheatDetection {
     let mut detection = CheatDetection {
         found: false,
         devices: Vec::new(),
         processes: Vec::new(),
         risk_score: 0,
-        risk_level: "low".to_string(),
+        risk_level: RiskLevel::Low,
+        suppressed: Vec::new(),
+        warnings: Vec::new(),
     };
 
-    // Check USB devices
-    if let Ok(com_con) = COMLibrary::new() {
-        if let Ok(wmi_con) = WMIConnection::new(com_con) {
-            #[derive(Deserialize)]
-            #[allow(non_snake_case)]
-            struct Win32PnpDevice {
-                DeviceID: Option<String>,
-                Name: Option<String>,
-            }
+    let sigs = signatures::current();
+    let allowed = whitelist::current();
 
-            if let Ok(devices) = wmi_con.raw_query::<Win32PnpDevice>(
-                "SELECT DeviceID, Name FROM Win32_PnPEntity WHERE DeviceID LIKE 'USB%'"
-            ) {
-                for device in devices {
-                    let device_id = device.DeviceID.clone().unwrap_or_default().to_uppercase();
-                    let name = device.Name.clone().unwrap_or_default();
-
-                    for (vid, cheat_name, pid) in CHEAT_DEVICES {
-                        let vid_upper = vid.to_uppercase();
-                        if device_id.contains(&format!("VID_{}", vid_upper)) {
-                            if let Some(p) = pid {
-                                if device_id.contains(&format!("PID_{}", p.to_uppercase())) {
-                                    detection.found = true;
-                                    detection.devices.push(DetectedDevice {
-                                        name: name.clone(),
-                                        device_type: cheat_name.to_string(),
-                                        vid: Some(vid.to_string()),
-                                        pid: Some(p.to_string()),
-                                    });
-                                    detection.risk_score += 100;
-                                }
-                            }
-                        }
-                    }
+    // Check USB devices
+    for device in &snapshot.usb_devices {
+        let (vid, pid) = rules::parse_usb_ids(&device.device_id);
+        let subject = Subject::usb(vid.as_deref(), pid.as_deref());
+
+        for sig in &sigs.cheat_devices {
+            if sig.rule.matches(&subject) {
+                let vid_pid = match (&vid, &pid) {
+                    (Some(vid), Some(pid)) => format!("{}:{}", vid, pid),
+                    _ => String::new(),
+                };
+                let candidates = whitelist::usb_subjects(&vid_pid, vid.as_deref(), pid.as_deref(), &sig.name);
+                if allowed.suppress(WhitelistType::UsbDevice, &candidates, &mut detection.suppressed) {
+                    break;
                 }
+
+                detection.found = true;
+                detection.devices.push(DetectedDevice {
+                    name: device.name.clone(),
+                    device_type: sig.name.clone(),
+                    vid: vid.clone(),
+                    pid: pid.clone(),
+                    hid_fingerprint: None,
+                    topology: None,
+                });
+                detection.risk_score += 100;
+                break;
             }
         }
     }
 
-    // Check running processes
-    if let Ok(com_con) = COMLibrary::new() {
-        if let Ok(wmi_con) = WMIConnection::new(com_con) {
-            #[derive(Deserialize)]
-            #[allow(non_snake_case)]
-            struct Win32Process {
-                Name: Option<String>,
-                ProcessId: Option<u32>,
-            }
+    // Check HID report descriptors (injectors reflashed with retail VID/PIDs)
+    for analysis in hid::analyze(&snapshot.hid_devices) {
+        let (vid, pid) = (analysis.vid.clone(), analysis.pid.clone());
+        if !analysis.injector {
+            detection.warnings.extend(analysis.indicators.iter()
+                .map(|i| format!("HID device {} ({}:{}): {}", analysis.name, vid, pid, i.detail)));
+            continue;
+        }
+        if detection.devices.iter().any(|d| d.vid.as_deref() == Some(vid.as_str()) && d.pid.as_deref() == Some(pid.as_str())) {
+            continue; // Already matched by a VID/PID rule
+        }
+        let vid_pid = format!("{}:{}", vid, pid);
+        let candidates = whitelist::usb_subjects(&vid_pid, Some(&vid), Some(&pid), &analysis.name);
+        if allowed.suppress(WhitelistType::UsbDevice, &candidates, &mut detection.suppressed) {
+            continue;
+        }
 
-            if let Ok(processes) = wmi_con.raw_query::<Win32Process>(
-                "SELECT Name, ProcessId FROM Win32_Process"
-            ) {
-                for process in processes {
-                    let proc_name = process.Name.clone().unwrap_or_default().to_lowercase();
-                    
-                    for cheat in CHEAT_PROCESSES {
-                        if proc_name.contains(cheat) {
-                            detection.found = true;
-                            detection.processes.push(DetectedProcess {
-                                name: process.Name.clone().unwrap_or_default(),
-                                matched_cheat: cheat.to_string(),
-                                pid: process.ProcessId.unwrap_or(0),
-                            });
-                            detection.risk_score += 75;
-                            break;
-                        }
-                    }
+        let reasons: Vec<&str> = analysis.indicators.iter().map(|i| i.detail.as_str()).collect();
+        println!("[Cheat] HID device {} ({}) flagged: {}", analysis.name, vid_pid, reasons.join("; "));
+        detection.found = true;
+        detection.devices.push(DetectedDevice {
+            name: analysis.name.clone(),
+            device_type: format!("HID injector: {}", reasons.join("; ")),
+            vid: Some(vid),
+            pid: Some(pid),
+            hid_fingerprint: Some(analysis.fingerprint.clone()),
+            topology: None,
+        });
+        detection.risk_score += analysis.risk_score;
+    }
+
+    // Check running processes (sha256 rules use hashes cached by reputation.rs)
+    for process in &snapshot.processes {
+        let sha256 = process.path.as_deref().and_then(reputation::cached_sha256);
+        let subject = Subject {
+            sha256: sha256.as_deref(),
+            ..Subject::file(&process.name, process.rule_path())
+        };
+
+        for cheat in &sigs.cheat_processes {
+            if cheat.matches(&subject) {
+                if allowed.suppress(WhitelistType::Process, &[subject], &mut detection.suppressed) {
+                    break;
                 }
+
+                detection.found = true;
+                detection.processes.push(DetectedProcess {
+                    name: process.name.clone(),
+                    matched_cheat: cheat.to_string(),
+                    pid: process.pid,
+                    pe_info: pe::try_inspect(process.path.as_deref()),
+                });
+                detection.risk_score += 75;
+                break;
             }
         }
     }
 
-    // Calculate risk level
-    detection.risk_level = if detection.risk_score >= 100 {
-        "critical".to_string()
-    } else if detection.risk_score >= 50 {
-        "high".to_string()
-    } else if detection.risk_score >= 25 {
-        "medium".to_string()
-    } else {
-        "low".to_string()
-    };
+    detection.risk_level = RiskLevel::from_score(detection.risk_score, &sigs.risk.levels);
 
     detection
 }
 
-#[cfg(not(target_os = "windows"))]
-pub fn detect_cheats() -> CheatDetection {
-    CheatDetection {
-        found: false,
-        devices: Vec::new(),
-        processes: Vec::new(),
-        risk_score: 0,
-        risk_level: "low".to_string(),
-    }
-}
-
 // ====== NETWORK MONITOR (VPN/Proxy Detection) ======
 
-const VPN_ADAPTER_KEYWORDS: &[&str] = &[
-    "tap-windows", "tap0901", "wintun", "wireguard",
-    "nordlynx", "proton", "surfshark", "cyberghost",
-    "windscribe", "mullvad", "private internet access",
-    "pia", "expressvpn", "tunnelbear", "hotspot shield",
-    "hamachi", "zerotier", "softether", "openvpn",
-    "fortinet", "cisco anyconnect", "vpn adapter",
-    "virtual ethernet", "phantom",
+// Matched against both the adapter name and its description
+pub(crate) const VPN_ADAPTER_KEYWORDS: &[&str] = &[
+    "glob:*tap-windows*", "glob:*tap0901*", "glob:*wintun*", "glob:*wireguard*",
+    "glob:*nordlynx*", "re:\\bproton(vpn)?\\b", "glob:*surfshark*", "glob:*cyberghost*",
+    "glob:*windscribe*", "glob:*mullvad*", "glob:*private internet access*",
+    "re:\\bpia\\b", "glob:*expressvpn*", "glob:*tunnelbear*", "glob:*hotspot shield*",
+    "glob:*hamachi*", "glob:*zerotier*", "glob:*softether*", "glob:*openvpn*",
+    "glob:*fortinet*", "glob:*cisco anyconnect*", "glob:*vpn adapter*",
+    "glob:*virtual ethernet* !glob:*hyper-v*", "re:\\bphantom\\b",
 ];
 
 const VPN_PROCESSES: &[&str] = &[
-    "openvpn", "openvpnserv", "nordvpn", "nordlynx",
-    "expressvpn", "expressvpnd", "surfshark",
-    "cyberghost", "windscribe", "mullvad",
-    "protonvpn", "tunnelbear", "hotspotshield",
-    "wireguard", "wg-quick", "softether",
-    "zerotier", "hamachi", "privoxy",
-    "tor.exe", "i2p", "proxifier", "proxycap",
-    "ultrasurf", "psiphon", "lantern",
-    "cloudflare-warp", "warp-cli",
+    "prefix:openvpn", "prefix:nordvpn", "prefix:nordlynx",
+    "prefix:expressvpn", "prefix:surfshark",
+    "prefix:cyberghost", "prefix:windscribe", "prefix:mullvad",
+    "prefix:protonvpn", "prefix:tunnelbear", "prefix:hotspotshield",
+    "prefix:wireguard", "prefix:wg-quick", "prefix:softether",
+    "prefix:zerotier", "prefix:hamachi", "prefix:privoxy",
+    "tor.exe", "prefix:i2p", "prefix:proxifier", "prefix:proxycap",
+    "prefix:ultrasurf", "prefix:psiphon", "prefix:lantern",
+    "prefix:cloudflare-warp", "prefix:warp-cli",
 ];
 
+lazy_static! {
+    static ref VPN_PROCESS_RULES: Vec<MatchRule> = rules::compile(VPN_PROCESSES);
+}
+
 // Windows system processes that should NOT be flagged as VPN
 const VPN_PROCESS_WHITELIST: &[&str] = &[
     "aggregatorhost.exe",
@@ -1742,81 +1378,75 @@ const VPN_PROCESS_WHITELIST: &[&str] = &[
 ];
 
 /// Detect VPN/Proxy usage
-#[cfg(target_os = "windows")]
-pub fn check_network_monitor() -> NetworkMonitorResult {
+pub fn check_network_monitor(snapshot: &SystemSnapshot) -> NetworkMonitorResult {
     let mut result = NetworkMonitorResult::default();
+    let sigs = signatures::current();
+    let allowed = whitelist::current();
 
     // 1. Check network adapters for VPN interfaces
-    if let Ok(com_con) = COMLibrary::new() {
-        if let Ok(wmi_con) = WMIConnection::new(com_con) {
-            #[derive(Deserialize)]
-            #[allow(non_snake_case, dead_code)]
-            struct Win32NetworkAdapter {
-                Name: Option<String>,
-                Description: Option<String>,
-                NetConnectionStatus: Option<u16>,
-            }
+    for adapter in &snapshot.network_adapters {
+        let name = Subject::name(&adapter.name);
+        let desc = Subject::name(&adapter.description);
 
-            if let Ok(adapters) = wmi_con.raw_query::<Win32NetworkAdapter>(
-                "SELECT Name, Description, NetConnectionStatus FROM Win32_NetworkAdapter WHERE NetConnectionStatus IS NOT NULL"
-            ) {
-                for adapter in adapters {
-                    let name = adapter.Name.clone().unwrap_or_default().to_lowercase();
-                    let desc = adapter.Description.clone().unwrap_or_default().to_lowercase();
-
-                    for keyword in VPN_ADAPTER_KEYWORDS {
-                        if name.contains(keyword) || desc.contains(keyword) {
-                            let adapter_name = adapter.Name.clone().unwrap_or_default();
-                            if !result.vpn_adapters.contains(&adapter_name) {
-                                result.vpn_adapters.push(adapter_name);
-                                result.vpn_detected = true;
-                                result.risk_score += 40;
-                            }
-                            break;
-                        }
-                    }
+        for keyword in &sigs.vpn_adapter_keywords {
+            if keyword.matches(&name) || keyword.matches(&desc) {
+                if allowed.suppress(WhitelistType::VpnAdapter, &[name, desc], &mut result.suppressed) {
+                    break;
                 }
+                if !result.vpn_adapters.contains(&adapter.name) {
+                    result.vpn_adapters.push(adapter.name.clone());
+                    result.vpn_detected = true;
+                    result.risk_score += 40;
+                }
+                break;
             }
         }
     }
 
     // 2. Check for VPN processes
-    if let Ok(com_con) = COMLibrary::new() {
-        if let Ok(wmi_con) = WMIConnection::new(com_con) {
-            #[derive(Deserialize)]
-            #[allow(non_snake_case)]
-            struct Win32Process {
-                Name: Option<String>,
-            }
+    for process in &snapshot.processes {
+        let proc_name = process.name.to_lowercase();
 
-            if let Ok(processes) = wmi_con.raw_query::<Win32Process>(
-                "SELECT Name FROM Win32_Process"
-            ) {
-                for process in processes {
-                    let proc_name = process.Name.clone().unwrap_or_default().to_lowercase();
-                    
-                    // Skip whitelisted Windows system processes
-                    if VPN_PROCESS_WHITELIST.iter().any(|&w| proc_name == w) {
-                        continue;
-                    }
+        // Skip whitelisted Windows system processes
+        if VPN_PROCESS_WHITELIST.iter().any(|&w| proc_name == w) {
+            continue;
+        }
 
-                    for vpn in VPN_PROCESSES {
-                        if proc_name.contains(vpn) {
-                            let name = process.Name.clone().unwrap_or_default();
-                            if !result.vpn_processes.contains(&name) {
-                                result.vpn_processes.push(name);
-                                result.vpn_detected = true;
-                                result.risk_score += 30;
-                            }
-                            break;
-                        }
-                    }
+        let subject = Subject::file(&process.name, process.rule_path());
+        for vpn in VPN_PROCESS_RULES.iter() {
+            if vpn.matches(&subject) {
+                if allowed.suppress(WhitelistType::VpnProcess, &[subject], &mut result.suppressed) {
+                    break;
                 }
+                if !result.vpn_processes.contains(&process.name) {
+                    result.vpn_processes.push(process.name.clone());
+                    result.vpn_detected = true;
+                    result.risk_score += 30;
+                }
+                break;
             }
         }
     }
 
     // 3. Check Windows proxy settings in registry
+    if let Some(proxy_server) = read_proxy_settings() {
+        result.proxy_detected = true;
+        result.risk_score += 20;
+        result.proxy_settings = proxy_server;
+    }
+
+    println!("[Hardware] Network Monitor: vpn={}, proxy={}, adapters={}, processes={}, score={}",
+             result.vpn_detected, result.proxy_detected,
+             result.vpn_adapters.len(), result.vpn_processes.len(), result.risk_score);
+
+    result
+}
+
+/// Read the user proxy settings - Some(server) when ProxyEnable is set
+#[cfg(target_os = "windows")]
+fn read_proxy_settings() -> Option<Option<String>> {
+    let mut proxy = None;
+
     unsafe {
         let key_path: Vec<u16> = "Software\\Microsoft\\Windows\\CurrentVersion\\Internet Settings\0"
             .encode_utf16()
@@ -1847,8 +1477,7 @@ pub fn check_network_monitor() -> NetworkMonitorResult {
             );
 
             if query_result.is_ok() && data_type == REG_DWORD && data == 1 {
-                result.proxy_detected = true;
-                result.risk_score += 20;
+                let mut proxy_server = None;
 
                 // Try to read proxy server value
                 let proxy_value: Vec<u16> = "ProxyServer\0".encode_utf16().collect();
@@ -1867,30 +1496,28 @@ pub fn check_network_monitor() -> NetworkMonitorResult {
 
                 if proxy_result.is_ok() {
                     let len = proxy_buffer.iter().position(|&c| c == 0).unwrap_or(proxy_buffer.len());
-                    result.proxy_settings = Some(String::from_utf16_lossy(&proxy_buffer[..len]));
+                    proxy_server = Some(String::from_utf16_lossy(&proxy_buffer[..len]));
                 }
+
+                proxy = Some(proxy_server);
             }
 
             let _ = RegCloseKey(hkey);
         }
     }
 
-    println!("[Hardware] Network Monitor: vpn={}, proxy={}, adapters={}, processes={}, score={}",
-             result.vpn_detected, result.proxy_detected,
-             result.vpn_adapters.len(), result.vpn_processes.len(), result.risk_score);
-
-    result
+    proxy
 }
 
 #[cfg(not(target_os = "windows"))]
-pub fn check_network_monitor() -> NetworkMonitorResult {
-    NetworkMonitorResult::default()
+fn read_proxy_settings() -> Option<Option<String>> {
+    None
 }
 
 // ====== REGISTRY SCAN (Cheat Traces) ======
 
 /// Registry paths to scan for cheat software traces
-const REGISTRY_CHEAT_TRACES: &[(&str, &str, &str)] = &[
+pub(crate) const REGISTRY_CHEAT_TRACES: &[(&str, &str, &str)] = &[
     // (registry_subkey, cheat_name, trace_type)
     // Cronus
     ("SOFTWARE\\Collective Minds", "Cronus Zen", "install"),
@@ -1939,31 +1566,39 @@ const UNINSTALL_PATHS: &[&str] = &[
 
 /// Keywords to match in uninstall entries
 const UNINSTALL_CHEAT_KEYWORDS: &[(&str, &str)] = &[
-    ("cronus", "Cronus"),
-    ("zen studio", "Cronus Zen Studio"),
-    ("xim", "XIM"),
-    ("titan", "Titan"),
-    ("consoletuner", "ConsoleTuner"),
-    ("reasnow", "ReaSnow"),
-    ("ds4windows", "DS4Windows"),
-    ("inputmapper", "InputMapper"),
-    ("scptoolkit", "ScpToolkit"),
-    ("autohotkey", "AutoHotkey"),
-    ("rewasd", "reWASD"),
-    ("antimicro", "AntiMicro"),
-    ("x360ce", "x360ce"),
-    ("hidhide", "HidHide"),
-    ("vigem", "ViGEm"),
+    ("glob:*cronus*", "Cronus"),
+    ("glob:*zen studio*", "Cronus Zen Studio"),
+    ("re:\\bxim(4| apex)?\\b", "XIM"),
+    ("re:\\btitan ?(one|two)\\b", "Titan"),
+    ("glob:*consoletuner*", "ConsoleTuner"),
+    ("glob:*reasnow*", "ReaSnow"),
+    ("glob:*ds4windows*", "DS4Windows"),
+    ("glob:*inputmapper*", "InputMapper"),
+    ("glob:*scptoolkit*", "ScpToolkit"),
+    ("glob:*autohotkey*", "AutoHotkey"),
+    ("glob:*rewasd*", "reWASD"),
+    ("glob:*antimicro*", "AntiMicro"),
+    ("glob:*x360ce*", "x360ce"),
+    ("glob:*hidhide*", "HidHide"),
+    ("glob:*vigem*", "ViGEm"),
 ];
 
+lazy_static! {
+    static ref UNINSTALL_CHEAT_RULES: Vec<(MatchRule, &'static str)> = UNINSTALL_CHEAT_KEYWORDS.iter()
+        .map(|(rule, name)| (MatchRule::builtin(rule), *name))
+        .collect();
+}
+
 /// Scan registry for cheat software traces
 #[cfg(target_os = "windows")]
 pub fn scan_registry() -> RegistryScanResult {
     let mut result = RegistryScanResult::default();
+    let sigs = signatures::current();
+    let allowed = whitelist::current();
 
     // 1. Check known registry paths
-    for (subkey, cheat_name, trace_type) in REGISTRY_CHEAT_TRACES {
-        let key_path: Vec<u16> = format!("{}\0", subkey).encode_utf16().collect();
+    for trace in &sigs.registry_cheat_traces {
+        let key_path: Vec<u16> = format!("{}\0", trace.path).encode_utf16().collect();
 
         unsafe {
             let mut hkey: HKEY = HKEY::default();
@@ -1977,13 +1612,18 @@ pub fn scan_registry() -> RegistryScanResult {
 
             if open_result.is_ok() {
                 let _ = RegCloseKey(hkey);
+                let path = format!("HKLM\\{}", trace.path);
+                if allowed.suppress(WhitelistType::Registry, &[Subject::file(&trace.name, Some(&path))], &mut result.suppressed) {
+                    continue;
+                }
+
                 result.traces_found = true;
                 result.traces.push(RegistryTrace {
-                    path: format!("HKLM\\{}", subkey),
-                    cheat_name: cheat_name.to_string(),
-                    trace_type: trace_type.to_string(),
+                    path,
+                    cheat_name: trace.name.clone(),
+                    trace_type: trace.trace_type.clone(),
                 });
-                let score = match *trace_type {
+                let score = match trace.trace_type.as_str() {
                     "spoofer" => 80,
                     "driver" => 60,
                     _ => 50,
@@ -2031,9 +1671,13 @@ pub fn scan_registry() -> RegistryScanResult {
 
                     let subkey_name = String::from_utf16_lossy(&name_buf[..name_len as usize]).to_lowercase();
 
-                    for (keyword, cheat_name) in UNINSTALL_CHEAT_KEYWORDS {
-                        if subkey_name.contains(keyword) {
+                    for (keyword, cheat_name) in UNINSTALL_CHEAT_RULES.iter() {
+                        if keyword.matches(&Subject::name(&subkey_name)) {
                             let trace_path = format!("HKLM\\{}\\{}", uninstall_path, subkey_name);
+                            let subject = Subject::file(cheat_name, Some(&trace_path));
+                            if allowed.suppress(WhitelistType::Registry, &[subject], &mut result.suppressed) {
+                                break;
+                            }
                             // Avoid duplicates
                             if !result.traces.iter().any(|t| t.path == trace_path) {
                                 result.traces_found = true;
@@ -2067,107 +1711,333 @@ pub fn scan_registry() -> RegistryScanResult {
     RegistryScanResult::default()
 }
 
+/// Executables referenced by uninstall entries (DisplayIcon, UninstallString)
+#[cfg(target_os = "windows")]
+pub fn uninstall_executables() -> Vec<String> {
+    let mut paths: Vec<String> = Vec::new();
+
+    for uninstall_path in UNINSTALL_PATHS {
+        let key_path: Vec<u16> = format!("{}\0", uninstall_path).encode_utf16().collect();
+
+        unsafe {
+            let mut hkey: HKEY = HKEY::default();
+            if RegOpenKeyExW(HKEY_LOCAL_MACHINE, PCWSTR(key_path.as_ptr()), 0, KEY_READ, &mut hkey).is_err() {
+                continue;
+            }
+
+            let mut index: u32 = 0;
+            loop {
+                let mut name_buf = [0u16; 256];
+                let mut name_len: u32 = 256;
+
+                let enum_result = RegEnumKeyExW(
+                    hkey,
+                    index,
+                    windows::core::PWSTR(name_buf.as_mut_ptr()),
+                    &mut name_len,
+                    None,
+                    windows::core::PWSTR(ptr::null_mut()),
+                    None,
+                    None,
+                );
+                if enum_result.is_err() {
+                    break;
+                }
+                index += 1;
+
+                let subkey_name = String::from_utf16_lossy(&name_buf[..name_len as usize]);
+                let entry_path = format!("{}\\{}", uninstall_path, subkey_name);
+
+                for value in ["DisplayIcon", "UninstallString"] {
+                    if let Some(exe) = read_hklm_string(&entry_path, value).and_then(|v| executable_from_command(&v)) {
+                        if !paths.iter().any(|p| p.eq_ignore_ascii_case(&exe)) {
+                            paths.push(exe);
+                        }
+                    }
+                }
+            }
+
+            let _ = RegCloseKey(hkey);
+        }
+    }
+
+    paths
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn uninstall_executables() -> Vec<String> {
+    Vec::new()
+}
+
+/// Read a REG_SZ / REG_EXPAND_SZ value under HKLM (environment variables are not expanded)
+#[cfg(target_os = "windows")]
+pub fn read_hklm_string(path: &str, value: &str) -> Option<String> {
+    let key_path: Vec<u16> = format!("{}\0", path).encode_utf16().collect();
+    let value_name: Vec<u16> = format!("{}\0", value).encode_utf16().collect();
+
+    unsafe {
+        let mut hkey: HKEY = HKEY::default();
+        if RegOpenKeyExW(HKEY_LOCAL_MACHINE, PCWSTR(key_path.as_ptr()), 0, KEY_READ, &mut hkey).is_err() {
+            return None;
+        }
+
+        let mut buffer = [0u16; 1024];
+        let mut size: u32 = (buffer.len() * 2) as u32;
+        let mut data_type: REG_VALUE_TYPE = REG_VALUE_TYPE(0);
+
+        let query_result = RegQueryValueExW(
+            hkey,
+            PCWSTR(value_name.as_ptr()),
+            Some(ptr::null_mut()),
+            Some(&mut data_type),
+            Some(buffer.as_mut_ptr() as *mut u8),
+            Some(&mut size),
+        );
+        let _ = RegCloseKey(hkey);
+
+        if query_result.is_err() {
+            return None;
+        }
+        let len = buffer.iter().position(|&c| c == 0).unwrap_or(buffer.len());
+        Some(String::from_utf16_lossy(&buffer[..len]))
+    }
+}
+
+/// Executable path of a command line or icon reference (`"C:\x\unins000.exe" /S`, `C:\x\app.exe,0`)
+#[cfg(target_os = "windows")]
+fn executable_from_command(command: &str) -> Option<String> {
+    let command = command.trim();
+    let path = if let Some(rest) = command.strip_prefix('"') {
+        rest.split('"').next()?
+    } else {
+        let lower = command.to_lowercase();
+        let end = lower.find(".exe").map(|i| i + 4)?;
+        &command[..end]
+    };
+    let path = path.split(',').next()?.trim();
+
+    let lower = path.to_lowercase();
+    // MsiExec entries point to the Windows installer, not the product
+    if !lower.ends_with(".exe") || lower.ends_with("\\msiexec.exe") || lower == "msiexec.exe" {
+        return None;
+    }
+    Some(path.to_string())
+}
+
 // ====== DRIVER INTEGRITY (Suspicious Drivers) ======
 
-const SUSPICIOUS_DRIVER_NAMES: &[(&str, &str)] = &[
+// Matched against the service name, display name and image file name (without .sys)
+pub(crate) const SUSPICIOUS_DRIVER_NAMES: &[(&str, &str)] = &[
     // Interception driver (keyboard/mouse interception - used by cheat devices)
-    ("interception", "Interception driver - HID interception"),
+    ("prefix:interception", "Interception driver - HID interception"),
     // HidHide (hides USB devices from detection)
-    ("hidhide", "HidHide - Device hiding driver"),
+    ("prefix:hidhide", "HidHide - Device hiding driver"),
     // ViGEm (virtual gamepad emulation)
-    ("vigembus", "ViGEmBus - Virtual gamepad emulator"),
-    ("vigem", "ViGEm - Virtual controller driver"),
+    ("prefix:vigembus", "ViGEmBus - Virtual gamepad emulator"),
+    ("prefix:vigem", "ViGEm - Virtual controller driver"),
     // Nefarius (HidHide / ViGEm developer)
-    ("nefcon", "Nefarius Config driver"),
+    ("prefix:nefcon", "Nefarius Config driver"),
     // Various HID spoofing drivers
-    ("hidguardian", "HidGuardian - HID device filter"),
+    ("prefix:hidguardian", "HidGuardian - HID device filter"),
     // ScpVBus (SCP virtual bus for DS3/DS4)
-    ("scpvbus", "ScpVBus - SCP virtual controller"),
+    ("prefix:scpvbus", "ScpVBus - SCP virtual controller"),
     // Xb360 filter driver (used by some cheats)
-    ("xb1usb", "XB1 USB filter driver"),
+    ("prefix:xb1usb", "XB1 USB filter driver"),
     // FairFight bypass drivers
     ("capcom", "Capcom.sys - Known exploit driver"),
+    ("path:*\\capcom.sys", "Capcom.sys - Known exploit driver"),
     // Common HWID spoofer drivers
-    ("spoofmaster", "SpoofMaster - HWID spoofer driver"),
-    ("hwid", "HWID manipulation driver"),
-    ("serialchanger", "Serial changer driver"),
+    ("prefix:spoofmaster", "SpoofMaster - HWID spoofer driver"),
+    ("re:^hwid[_-]?(spoof|change|mask)", "HWID manipulation driver"),
+    ("glob:*serialchanger*", "Serial changer driver"),
     // Kernel-level cheat drivers
-    ("kdmapper", "KDMapper - Kernel driver mapper"),
-    ("dse_patch", "DSE Patch - Driver signature bypass"),
+    ("prefix:kdmapper", "KDMapper - Kernel driver mapper"),
+    ("glob:*dse_patch*", "DSE Patch - Driver signature bypass"),
+];
+
+/// Publishers whose validly signed modules are not flagged for their location or signature
+pub(crate) const TRUSTED_PUBLISHERS: &[&str] = &[
+    "Microsoft Corporation",
+    "Microsoft Windows",
+    "Microsoft Windows Hardware Compatibility Publisher",
+    "Microsoft Windows Publisher",
+    "NVIDIA Corporation",
+    "Advanced Micro Devices, Inc.",
+    "glob:intel corporation*",
+    "Realtek Semiconductor Corp.",
+    "Logitech",
+    "glob:logitech *",
+    "Razer USA Ltd.",
+    "Corsair Memory, Inc.",
+    "Valve",
+    "Valve Corp.",
+    "Discord Inc.",
+    "Google LLC",
+    "Activision Publishing Inc",
+    "Blizzard Entertainment, Inc.",
+];
+
+/// (SHA-256 of the SubjectPublicKeyInfo, name) of the public roots that anchor
+/// code-signing chains. Microsoft's product roots are recognized by Windows itself.
+pub(crate) const TRUSTED_ROOT_KEYS: &[(&str, &str)] = &[
+    ("23f2edff3ede90259a9e30f40af8f912a5e5b3694e6938440341f6060e014ffa", "DigiCert Assured ID Root CA"),
+    ("aff988906dde12955d9bebbf928fdcc31cce328d5b9384f21c8941ca26e20391", "DigiCert Global Root CA"),
+    ("8bb593a93be1d0e8a822bb887c547890c3e706aad2dab76254f97fb36b82fc26", "DigiCert Global Root G2"),
+    ("5a889647220e54d6bd8a16817224520bb5c78e58984bd570506388b9de0f075f", "DigiCert High Assurance EV Root CA"),
+    ("59df317bfa9f4f0ab7ca514d7772296aa2c765b87664d08b96e57399e364729c", "DigiCert Trusted Root G4"),
+    ("c784333d20bcd742b9fdc3236f4e509b8937070e73067e254dd3bf9c45bf4dde", "USERTrust RSA Certification Authority"),
+    ("2021917e98263945c859c43f1d73cb4139053c414fa03ca3bc7ee88614298f3b", "USERTrust ECC Certification Authority"),
+    ("bd153ed7b0434f6886b17bce8bbe84ed340c7132d702a8f4fa318f756ecbd6f3", "AAA Certificate Services"),
+    ("82b5f84daf47a59c7ab521e4982aefa40a53406a3aec26039efa6b2e0e7244c1", "COMODO RSA Certification Authority"),
+    ("2bcee858158cf5465fc9d76f0dfa312fef25a4dca8501da9b46b67d1fbfa1b64", "GlobalSign Root CA"),
+    ("706bb1017c855c59169bad5c1781cf597f12d2cad2f63d1a4aa37493800ffb80", "GlobalSign Root CA - R3"),
+    ("682747f8ba621b87cdd3bc295ed5cabce722a1c0c0363d1d68b38928d2787f1e", "GlobalSign Root CA - R6"),
+    ("ae7f962cb9e6a7dbf7b833fb18fa9b71a89175df949c232b6a9ef7cb3df2bbfc", "GlobalSign Root R46"),
+    ("6dbfae00d37b9cd73f8fb47de65917af00e0dddf42dbceac20c17c0275ee2095", "Entrust Root Certification Authority"),
+    ("76ee8590374c715437bbca6bba6028eadde2dc6dbbb8c3f610e851f11d1ab7f5", "Entrust Root Certification Authority - G2"),
+    ("aa2630a7b617b04d0a294bab7a8caaa5016e6dbe604837a83a85719fab667eb5", "Certum Trusted Network CA"),
+    ("6b3b57e9ec88d1bb3d01637ff33c7698b3c9758255e9f01ea9178f3e7f3b2b52", "Certum Trusted Network CA 2"),
+    ("d1c45377ebdcd618cd1651dc2e02c21d751e5aa9fcd1b3431ff6ecf6a31348fa", "SSL.com Root Certification Authority RSA"),
+    ("7cd67c248f69d83fc2f9bb01dcb1f7ad67a363d046043796d0984c3a231f6bb0", "SSL.com EV Root Certification Authority RSA R2"),
+    ("a320f4d534d7be97c1ae8dd0499735bc895c323add2d388bfccf662c23d7f99a", "SSL.com Root Certification Authority ECC"),
+    ("2a8f2d8af0eb123898f74c866ac3fa669054e23c17bc7a95bd0234192dc635d0", "Go Daddy Root Certificate Authority - G2"),
+    ("808d68b3fab4884a5f971ace7d10550d7a95a163774f3ec36afffb213fbe4c74", "Starfield Root Certificate Authority - G2"),
+    ("8fd112c3c8370f147d5ccd3a7d865eb8dd540783bac69fc60088e3743ff33378", "QuoVadis Root CA 2"),
+    ("63d9af9b47b1064d49a10e7b7fd566dbc8caa399459bfc2829c571ad8c6ef34a", "Baltimore CyberTrust Root"),
+    ("b2f7298b52bf2c3cac4ddfe72de4d682ac58957595982f2b62301af597c699c5", "Microsoft RSA Root Certificate Authority 2017"),
+    ("35f53ce1264611e03340fe37e1ec7d4cc986c5613dca70fd04aa44545f2daf28", "Microsoft ECC Root Certificate Authority 2017"),
 ];
 
+/// Reason code and risk for a module whose embedded signature is not acceptable
+fn signature_problem(info: &SignatureInfo, sigs: &SignatureBundle) -> Option<(&'static str, u32)> {
+    if is_trusted_publisher(info, sigs) {
+        return None;
+    }
+    let reason = info.problem(&sigs.trusted_root_keys)?;
+    Some((reason, signature_risk(reason).unwrap_or(30)))
+}
+
+/// Risk of a signature problem reason ("tampered", "unsigned", ...)
+pub(crate) fn signature_risk(reason: &str) -> Option<u32> {
+    match reason {
+        "tampered" => Some(80),
+        "self_signed" | "invalid_signature" => Some(60),
+        "unsigned" => Some(40),
+        "unknown_root" => Some(30),
+        _ => None,
+    }
+}
+
+/// Valid signature chaining to a pinned root, from an allowlisted publisher
+fn is_trusted_publisher(info: &SignatureInfo, sigs: &SignatureBundle) -> bool {
+    info.is_trusted(&sigs.trusted_root_keys) && info.publisher.as_deref()
+        .map(|publisher| sigs.trusted_publishers.iter().any(|rule| rule.matches(&Subject::name(publisher))))
+        .unwrap_or(false)
+}
+
+/// Win32_SystemDriver PathName to a file system path (`\SystemRoot\...`, `\??\C:\...`, `system32\...`)
+pub(crate) fn resolve_driver_path(path: &str) -> String {
+    let path = path.trim().trim_matches('"');
+    let system_root = std::env::var("SystemRoot").unwrap_or_else(|_| "C:\\Windows".to_string());
+    let lower = path.to_lowercase();
+
+    if let Some(rest) = path.strip_prefix("\\??\\") {
+        rest.to_string()
+    } else if lower.starts_with("\\systemroot\\") {
+        format!("{}{}", system_root, &path["\\SystemRoot".len()..])
+    } else if lower.starts_with("system32\\") {
+        format!("{}\\{}", system_root, path)
+    } else {
+        path.to_string()
+    }
+}
+
+/// Inbox drivers and DLLs (WinSxS assemblies included) are catalog-signed and
+/// carry no embedded signature
+fn is_windows_path(path: &str) -> bool {
+    let system_root = std::env::var("SystemRoot").unwrap_or_else(|_| "C:\\Windows".to_string());
+    path.to_lowercase().starts_with(&format!("{}\\", system_root.to_lowercase()))
+}
+
 /// Check driver integrity - detect suspicious/cheat-related kernel drivers
-#[cfg(target_os = "windows")]
-pub fn check_driver_integrity() -> DriverIntegrityResult {
+pub fn check_driver_integrity(snapshot: &SystemSnapshot) -> DriverIntegrityResult {
     let mut result = DriverIntegrityResult::default();
+    let sigs = signatures::current();
+    let allowed = whitelist::current();
+
+    for driver in &snapshot.drivers {
+        let path = driver.path.as_deref();
+        let file_stem = path
+            .and_then(|p| p.rsplit(['\\', '/']).next())
+            .map(|f| f.trim_matches('"'))
+            .map(|f| f.strip_suffix(".sys").or_else(|| f.strip_suffix(".SYS")).unwrap_or(f))
+            .unwrap_or_default();
+        let subjects = [
+            Subject::file(&driver.name, path),
+            Subject::file(&driver.display_name, path),
+            Subject::file(file_stem, path),
+        ];
+        let image = path.map(resolve_driver_path);
+
+        if let Some(sig) = sigs.suspicious_driver_names.iter()
+            .find(|sig| subjects.iter().any(|subject| sig.rule.matches(subject))) {
+            if allowed.suppress(WhitelistType::Driver, &subjects, &mut result.suppressed) {
+                continue;
+            }
+            result.suspicious_found = true;
+            result.suspicious_drivers.push(SuspiciousDriver {
+                name: driver.name.clone(),
+                display_name: driver.display_name.clone(),
+                path: driver.path.clone(),
+                reason: sig.reason.clone(),
+                signature: image.as_deref().map(authenticode::verify_file),
+            });
+            result.risk_score += 70;
+            continue;
+        }
 
-    if let Ok(com_con) = COMLibrary::new() {
-        if let Ok(wmi_con) = WMIConnection::new(com_con) {
-            #[derive(Deserialize)]
-            #[allow(non_snake_case, dead_code)]
-            struct Win32SystemDriver {
-                Name: Option<String>,
-                DisplayName: Option<String>,
-                PathName: Option<String>,
-                State: Option<String>,
-                StartMode: Option<String>,
-            }
+        // Signature of loaded drivers only (stopped services can't run code)
+        let running = driver.state.as_deref().is_some_and(|s| s.eq_ignore_ascii_case("running"));
+        let image = match image {
+            Some(image) if running => image,
+            _ => continue,
+        };
 
-            if let Ok(drivers) = wmi_con.raw_query::<Win32SystemDriver>(
-                "SELECT Name, DisplayName, PathName, State, StartMode FROM Win32_SystemDriver"
-            ) {
-                for driver in drivers {
-                    let name = driver.Name.clone().unwrap_or_default().to_lowercase();
-                    let display = driver.DisplayName.clone().unwrap_or_default().to_lowercase();
-                    let path = driver.PathName.clone().unwrap_or_default().to_lowercase();
-
-                    for (suspicious_name, reason) in SUSPICIOUS_DRIVER_NAMES {
-                        if name.contains(suspicious_name) || display.contains(suspicious_name) || path.contains(suspicious_name) {
-                            result.suspicious_found = true;
-                            result.suspicious_drivers.push(SuspiciousDriver {
-                                name: driver.Name.clone().unwrap_or_default(),
-                                display_name: driver.DisplayName.clone().unwrap_or_default(),
-                                path: driver.PathName.clone(),
-                                reason: reason.to_string(),
-                            });
-                            result.risk_score += 70;
-                            break;
-                        }
-                    }
-                }
+        let info = authenticode::verify_file(&image);
+        if let Some((reason, risk)) = signature_problem(&info, &sigs) {
+            if reason == "unsigned" && is_windows_path(&image) {
+                continue;
             }
+            if allowed.suppress(WhitelistType::Driver, &subjects, &mut result.suppressed) {
+                continue;
+            }
+            result.suspicious_found = true;
+            result.suspicious_drivers.push(SuspiciousDriver {
+                name: driver.name.clone(),
+                display_name: driver.display_name.clone(),
+                path: driver.path.clone(),
+                reason: reason.to_string(),
+                signature: Some(info),
+            });
+            result.risk_score += risk;
         }
     }
 
     // Also check for unsigned/suspicious drivers via registry
     // Check Interception specifically (common cheat enabler)
-    unsafe {
-        let key_path: Vec<u16> = "SYSTEM\\CurrentControlSet\\Services\\interception\0"
-            .encode_utf16()
-            .collect();
-
-        let mut hkey: HKEY = HKEY::default();
-        let open_result = RegOpenKeyExW(
-            HKEY_LOCAL_MACHINE,
-            PCWSTR(key_path.as_ptr()),
-            0,
-            KEY_READ,
-            &mut hkey,
-        );
-
-        if open_result.is_ok() {
-            let _ = RegCloseKey(hkey);
-            // Check if not already detected
-            if !result.suspicious_drivers.iter().any(|d| d.name.to_lowercase().contains("interception")) {
-                result.suspicious_found = true;
-                result.suspicious_drivers.push(SuspiciousDriver {
-                    name: "interception".to_string(),
-                    display_name: "Interception Driver (Registry)".to_string(),
-                    path: None,
-                    reason: "Interception driver - HID interception (registry trace)".to_string(),
-                });
-                result.risk_score += 70;
-            }
+    if hklm_key_exists("SYSTEM\\CurrentControlSet\\Services\\interception") {
+        // Check if not already detected (or whitelisted)
+        if !result.suspicious_drivers.iter().any(|d| d.name.to_lowercase().contains("interception"))
+            && !allowed.suppress(WhitelistType::Driver, &[Subject::name("interception")], &mut result.suppressed) {
+            result.suspicious_found = true;
+            result.suspicious_drivers.push(SuspiciousDriver {
+                name: "interception".to_string(),
+                display_name: "Interception Driver (Registry)".to_string(),
+                path: None,
+                reason: "Interception driver - HID interception (registry trace)".to_string(),
+                signature: None,
+            });
+            result.risk_score += 70;
         }
     }
 
@@ -2177,131 +2047,96 @@ pub fn check_driver_integrity() -> DriverIntegrityResult {
     result
 }
 
-#[cfg(not(target_os = "windows"))]
-pub fn check_driver_integrity() -> DriverIntegrityResult {
-    DriverIntegrityResult::default()
-}
-
 // ====== MACRO DETECTION (AutoHotkey, Logitech, Razer, etc.) ======
 
-const MACRO_PROCESSES: &[(&str, &str)] = &[
-    // AutoHotkey family
-    ("autohotkey", "ahk"),
-    ("ahk", "ahk"),
-    ("autohotkey64", "ahk"),
-    ("autohotkey32", "ahk"),
+pub(crate) const MACRO_PROCESSES: &[(&str, &str)] = &[
+    // AutoHotkey family (covers AutoHotkey64/32/U64 and compiled scripts)
+    ("prefix:autohotkey", "ahk"),
+    ("re:^ahk[\\w-]*\\.exe$", "ahk"),
+    ("path:*\\autohotkey\\*.exe", "ahk"),
     // Logitech
-    ("lghub", "logitech"),
-    ("lghub_agent", "logitech"),
-    ("lghub_updater", "logitech"),
-    ("lcore", "logitech"),
-    ("ghub", "logitech"),
+    ("prefix:lghub", "logitech"),
+    ("lcore.exe", "logitech"),
+    ("prefix:ghub", "logitech"),
     // Razer Synapse
-    ("razersynapse", "razer"),
-    ("razer synapse 3", "razer"),
-    ("razer synapse service", "razer"),
-    ("rzsynapse", "razer"),
-    ("razercentral", "razer"),
+    ("prefix:razersynapse", "razer"),
+    ("prefix:razer synapse", "razer"),
+    ("prefix:rzsynapse", "razer"),
+    ("prefix:razercentral", "razer"),
     // Corsair iCUE
-    ("icue", "corsair"),
-    ("corsair", "corsair"),
+    ("prefix:icue", "corsair"),
+    ("prefix:corsair", "corsair"),
     // Generic macro tools
-    ("macro recorder", "generic"),
-    ("tinytask", "generic"),
-    ("pulover", "generic"),
-    ("macrocreator", "generic"),
-    ("jitbit", "generic"),
+    ("prefix:macro recorder", "generic"),
+    ("prefix:tinytask", "generic"),
+    ("prefix:pulover", "generic"),
+    ("prefix:macrocreator", "generic"),
+    ("prefix:jitbit", "generic"),
     // Remapping / macro tools
-    ("rewasd", "generic"),
-    ("antimicro", "generic"),
-    ("xpadder", "generic"),
-    ("joytokey", "generic"),
-    ("x360ce", "generic"),
-    ("keysticks", "generic"),
+    ("prefix:rewasd", "generic"),
+    ("prefix:antimicro", "generic"),
+    ("prefix:xpadder", "generic"),
+    ("prefix:joytokey", "generic"),
+    ("prefix:x360ce", "generic"),
+    ("prefix:keysticks", "generic"),
     // Mouse macro software
-    ("x-mouse", "generic"),
-    ("xmousebuttoncontrol", "generic"),
+    ("prefix:x-mouse", "generic"),
+    ("prefix:xmousebuttoncontrol", "generic"),
 ];
 
 /// Detect macro software
-#[cfg(target_os = "windows")]
-pub fn detect_macros() -> MacroDetectionResult {
+pub fn detect_macros(snapshot: &SystemSnapshot) -> MacroDetectionResult {
     let mut result = MacroDetectionResult::default();
     let mut seen_names: Vec<String> = Vec::new();
+    let sigs = signatures::current();
+    let allowed = whitelist::current();
 
     // 1. Check running processes for macro software
-    if let Ok(com_con) = COMLibrary::new() {
-        if let Ok(wmi_con) = WMIConnection::new(com_con) {
-            #[derive(Deserialize)]
-            #[allow(non_snake_case)]
-            struct Win32Process {
-                Name: Option<String>,
-                ExecutablePath: Option<String>,
-            }
+    for process in &snapshot.processes {
+        let proc_name = process.name.to_lowercase();
+        let subject = Subject::file(&process.name, process.rule_path());
 
-            if let Ok(processes) = wmi_con.raw_query::<Win32Process>(
-                "SELECT Name, ExecutablePath FROM Win32_Process"
-            ) {
-                for process in processes {
-                    let proc_name = process.Name.clone().unwrap_or_default().to_lowercase();
-                    let proc_path = process.ExecutablePath.clone().unwrap_or_default().to_lowercase();
-
-                    for (keyword, macro_type) in MACRO_PROCESSES {
-                        if proc_name.contains(keyword) || proc_path.contains(keyword) {
-                            let name = process.Name.clone().unwrap_or_default();
-                            if !seen_names.contains(&name.to_lowercase()) {
-                                seen_names.push(name.to_lowercase());
-                                result.macros_detected = true;
-                                result.detected_software.push(DetectedMacro {
-                                    name,
-                                    macro_type: macro_type.to_string(),
-                                    source: "process".to_string(),
-                                });
-                                let score = match *macro_type {
-                                    "ahk" => 80,        // AHK is high risk
-                                    "generic" => 60,     // Generic macro tools
-                                    "logitech" | "razer" | "corsair" => 20, // Peripheral software (lower risk)
-                                    _ => 30,
-                                };
-                                result.risk_score += score;
-                            }
-                            break;
-                        }
-                    }
+        for sig in &sigs.macro_processes {
+            if sig.rule.matches(&subject) {
+                if allowed.suppress(WhitelistType::Macro, &[subject], &mut result.suppressed) {
+                    break;
                 }
+                if !seen_names.contains(&proc_name) {
+                    seen_names.push(proc_name.clone());
+                    result.macros_detected = true;
+                    result.detected_software.push(DetectedMacro {
+                        name: process.name.clone(),
+                        macro_type: sig.macro_type.clone(),
+                        source: "process".to_string(),
+                    });
+                    let score = match sig.macro_type.as_str() {
+                        "ahk" => 80,        // AHK is high risk
+                        "generic" => 60,     // Generic macro tools
+                        "logitech" | "razer" | "corsair" => 20, // Peripheral software (lower risk)
+                        _ => 30,
+                    };
+                    result.risk_score += score;
+                }
+                break;
             }
-        }
-    }
-
-    // 2. Check for AutoHotkey scripts in common locations via registry
-    // AutoHotkey registers .ahk file association
-    unsafe {
-        let key_path: Vec<u16> = "SOFTWARE\\Classes\\.ahk\0"
-            .encode_utf16()
-            .collect();
-
-        let mut hkey: HKEY = HKEY::default();
-        let open_result = RegOpenKeyExW(
-            HKEY_LOCAL_MACHINE,
-            PCWSTR(key_path.as_ptr()),
-            0,
-            KEY_READ,
-            &mut hkey,
-        );
-
-        if open_result.is_ok() {
-            let _ = RegCloseKey(hkey);
-            // AHK is installed (file association exists)
-            if !seen_names.contains(&"autohotkey_installed".to_string()) {
-                seen_names.push("autohotkey_installed".to_string());
-                result.macros_detected = true;
-                result.detected_software.push(DetectedMacro {
-                    name: "AutoHotkey (installed)".to_string(),
-                    macro_type: "ahk".to_string(),
-                    source: "registry".to_string(),
-                });
-                result.risk_score += 50;
-            }
+        }
+    }
+
+    // 2. Check for AutoHotkey scripts in common locations via registry
+    // AutoHotkey registers .ahk file association
+    if hklm_key_exists("SOFTWARE\\Classes\\.ahk") {
+        // AHK is installed (file association exists)
+        let subject = Subject::name("AutoHotkey (installed)");
+        if !seen_names.contains(&"autohotkey_installed".to_string())
+            && !allowed.suppress(WhitelistType::Macro, &[subject], &mut result.suppressed) {
+            seen_names.push("autohotkey_installed".to_string());
+            result.macros_detected = true;
+            result.detected_software.push(DetectedMacro {
+                name: "AutoHotkey (installed)".to_string(),
+                macro_type: "ahk".to_string(),
+                source: "registry".to_string(),
+            });
+            result.risk_score += 50;
         }
     }
 
@@ -2311,105 +2146,51 @@ pub fn detect_macros() -> MacroDetectionResult {
     result
 }
 
-#[cfg(not(target_os = "windows"))]
-pub fn detect_macros() -> MacroDetectionResult {
-    MacroDetectionResult::default()
-}
-
 // ====== OVERLAY DETECTION (Cheat Overlays, ESP, Aimbot Visual) ======
 
+// Matched against the process name and the window title
 const SUSPICIOUS_OVERLAY_PROCESSES: &[&str] = &[
     // Known cheat overlays
-    "overlay", "esp", "aimbot", "hack", "cheat",
-    "unknowncheats", "mpgh", "elitepvpers",
+    "re:\\boverlay\\b", "re:\\besp\\b", "glob:*aimbot*", "re:\\bhack(s|er)?\\b", "re:\\bcheats?\\b",
+    "glob:*unknowncheats*", "re:\\bmpgh\\b", "glob:*elitepvpers*",
     // Generic suspicious
-    "injector", "loader", "trainer",
+    "glob:*injector*", "re:\\bloader\\b", "re:\\btrainer\\b",
     // Drawing libraries often used by cheats
-    "d3d", "directx", "opengl",
+    "re:\\bd3d(9|11|12)?\\b", "re:\\bdirectx\\b", "re:\\bopengl\\b",
 ];
 
 const SUSPICIOUS_WINDOW_CLASSES: &[&str] = &[
     // Qt (often used for cheat GUIs)
-    "qt_", "qwidget", "qmainwindow",
+    "prefix:qt_", "prefix:qwidget", "prefix:qmainwindow",
     // Direct3D/OpenGL overlays
-    "d3dwindowclass", "nvidia_overlay",
+    "prefix:d3dwindowclass", "prefix:nvidia_overlay",
     // Known cheat framework classes
-    "imgui", "dear imgui",
+    "glob:*imgui*",
     // Transparent window classes
-    "transparent", "overlay",
+    "glob:*transparent*", "glob:*overlay*",
 ];
 
+lazy_static! {
+    static ref OVERLAY_PROCESS_RULES: Vec<MatchRule> = rules::compile(SUSPICIOUS_OVERLAY_PROCESSES);
+    static ref WINDOW_CLASS_RULES: Vec<MatchRule> = rules::compile(SUSPICIOUS_WINDOW_CLASSES);
+}
+
 /// Detect suspicious overlay windows
-#[cfg(target_os = "windows")]
-pub fn detect_overlays() -> OverlayDetectionResult {
-    use windows::Win32::UI::WindowsAndMessaging::{
-        EnumWindows, GetWindowLongW, GetWindowTextW, GetClassNameW, GetWindowThreadProcessId,
-        GWL_EXSTYLE, WS_EX_TOPMOST, WS_EX_LAYERED, WS_EX_TRANSPARENT, IsWindowVisible,
-    };
-    use windows::Win32::Foundation::{HWND, LPARAM, BOOL};
-    use windows::Win32::System::Threading::{OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION};
-    use windows::Win32::System::ProcessStatus::GetModuleBaseNameW;
-    use std::sync::Mutex;
-    
-    lazy_static::lazy_static! {
-        static ref OVERLAYS: Mutex<Vec<SuspiciousOverlay>> = Mutex::new(Vec::new());
-    }
-    
-    // Clear previous results
-    if let Ok(mut overlays) = OVERLAYS.lock() {
-        overlays.clear();
-    }
-    
-    unsafe extern "system" fn enum_window_callback(hwnd: HWND, _: LPARAM) -> BOOL {
-        // Skip invisible windows
-        if IsWindowVisible(hwnd).as_bool() == false {
-            return BOOL(1);
-        }
-        
-        // Get window extended style
-        let ex_style = GetWindowLongW(hwnd, GWL_EXSTYLE) as u32;
-        
-        // Check for overlay characteristics
-        let is_topmost = (ex_style & WS_EX_TOPMOST.0) != 0;
-        let is_layered = (ex_style & WS_EX_LAYERED.0) != 0;
-        let is_transparent = (ex_style & WS_EX_TRANSPARENT.0) != 0;
-        
+pub fn detect_overlays(snapshot: &SystemSnapshot) -> OverlayDetectionResult {
+    let mut result = OverlayDetectionResult::default();
+    let allowed = whitelist::current();
+
+    for window in &snapshot.windows {
         // Skip if not suspicious (topmost + layered/transparent)
-        if !is_topmost || (!is_layered && !is_transparent) {
-            return BOOL(1);
-        }
-        
-        // Get window title
-        let mut title_buf = [0u16; 512];
-        let title_len = GetWindowTextW(hwnd, &mut title_buf);
-        let title = String::from_utf16_lossy(&title_buf[..title_len as usize]);
-        
-        // Get class name
-        let mut class_buf = [0u16; 256];
-        let class_len = GetClassNameW(hwnd, &mut class_buf);
-        let class_name = String::from_utf16_lossy(&class_buf[..class_len as usize]);
-        
-        // Get process name
-        let mut pid: u32 = 0;
-        GetWindowThreadProcessId(hwnd, Some(&mut pid));
-        let mut process_name = String::new();
-        
-        if pid > 0 {
-            if let Ok(handle) = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid) {
-                let mut name_buf = [0u16; 256];
-                let len = GetModuleBaseNameW(handle, None, &mut name_buf);
-                if len > 0 {
-                    process_name = String::from_utf16_lossy(&name_buf[..len as usize]);
-                }
-                let _ = windows::Win32::Foundation::CloseHandle(handle);
-            }
+        if !window.topmost || (!window.layered && !window.transparent) {
+            continue;
         }
-        
+
         // Skip system windows
-        let proc_lower = process_name.to_lowercase();
-        let title_lower = title.to_lowercase();
-        let class_lower = class_name.to_lowercase();
-        
+        let proc_lower = window.process_name.to_lowercase();
+        let title_lower = window.title.to_lowercase();
+        let class_lower = window.class_name.to_lowercase();
+
         // Skip known legitimate overlays
         if proc_lower.contains("explorer") || proc_lower.contains("dwm") ||
            proc_lower.contains("searchhost") || proc_lower.contains("shellexperiencehost") ||
@@ -2417,817 +2198,657 @@ pub fn detect_overlays() -> OverlayDetectionResult {
            proc_lower.contains("geforce") || proc_lower.contains("discord") ||
            proc_lower.contains("steam") || proc_lower.contains("obs") ||
            title_lower.is_empty() || class_lower.contains("tooltips_class") {
-            return BOOL(1);
+            continue;
         }
-        
+
         // Determine reason
-        let mut reason = if is_transparent {
+        let mut reason = if window.transparent {
             "transparent_topmost"
         } else {
             "layered_topmost"
         };
-        
+
         // Check for suspicious process names
-        for suspicious in SUSPICIOUS_OVERLAY_PROCESSES {
-            if proc_lower.contains(suspicious) || title_lower.contains(suspicious) {
+        let process = Subject::name(&window.process_name);
+        let title = Subject::name(&window.title);
+        for suspicious in OVERLAY_PROCESS_RULES.iter() {
+            if suspicious.matches(&process) || suspicious.matches(&title) {
                 reason = "cheat_process";
                 break;
             }
         }
-        
+
         // Check for suspicious class names
-        for suspicious_class in SUSPICIOUS_WINDOW_CLASSES {
-            if class_lower.contains(suspicious_class) {
+        let class = Subject::name(&window.class_name);
+        for suspicious_class in WINDOW_CLASS_RULES.iter() {
+            if suspicious_class.matches(&class) {
                 reason = "suspicious_class";
                 break;
             }
         }
-        
-        if let Ok(mut overlays) = OVERLAYS.lock() {
-            overlays.push(SuspiciousOverlay {
-                window_title: title,
-                process_name,
-                class_name,
-                reason: reason.to_string(),
-            });
-        }
-        
-        BOOL(1) // Continue enumeration
-    }
-    
-    let mut result = OverlayDetectionResult::default();
-    
-    unsafe {
-        let _ = EnumWindows(Some(enum_window_callback), LPARAM(0));
-    }
-    
-    // Collect results
-    if let Ok(overlays) = OVERLAYS.lock() {
-        for overlay in overlays.iter() {
-            result.overlays_found = true;
-            result.suspicious_overlays.push(overlay.clone());
-            
-            let score = match overlay.reason.as_str() {
-                "cheat_process" => 90,
-                "suspicious_class" => 70,
-                "transparent_topmost" => 50,
-                _ => 30,
-            };
-            result.risk_score += score;
+
+        if allowed.suppress(WhitelistType::Overlay, &[process, title, class], &mut result.suppressed) {
+            continue;
         }
+
+        let score = match reason {
+            "cheat_process" => 90,
+            "suspicious_class" => 70,
+            "transparent_topmost" => 50,
+            _ => 30,
+        };
+
+        result.overlays_found = true;
+        result.suspicious_overlays.push(SuspiciousOverlay {
+            window_title: window.title.clone(),
+            process_name: window.process_name.clone(),
+            class_name: window.class_name.clone(),
+            reason: reason.to_string(),
+        });
+        result.risk_score += score;
     }
-    
+
     println!("[Hardware] Overlay Detection: found={}, count={}, score={}",
              result.overlays_found, result.suspicious_overlays.len(), result.risk_score);
-    
-    result
-}
 
-#[cfg(not(target_os = "windows"))]
-pub fn detect_overlays() -> OverlayDetectionResult {
-    OverlayDetectionResult::default()
+    result
 }
 
 // ====== DLL INJECTION DETECTION ======
 
-const SUSPICIOUS_DLL_NAMES: &[(&str, &str)] = &[
+pub(crate) const SUSPICIOUS_DLL_NAMES: &[(&str, &str)] = &[
     // Known injectors and hooking libraries
-    ("detours", "Detours (API hooking library)"),
-    ("minhook", "MinHook (hooking library)"),
-    ("easyhook", "EasyHook (injection framework)"),
-    ("injector", "Generic injector DLL"),
-    ("hook", "Generic hook DLL"),
+    ("prefix:detours", "Detours (API hooking library)"),
+    ("prefix:minhook", "MinHook (hooking library)"),
+    ("prefix:easyhook", "EasyHook (injection framework)"),
+    ("glob:*injector*.dll", "Generic injector DLL"),
+    ("re:^hook(s|lib)?[._-]?(32|64|x86|x64)?\\.dll$", "Generic hook DLL"),
     // Cheat-related
-    ("aimbot", "Aimbot DLL"),
-    ("wallhack", "Wallhack DLL"),
-    ("esp", "ESP DLL"),
-    ("cheat", "Cheat DLL"),
-    ("hack", "Hack DLL"),
-    ("trainer", "Trainer DLL"),
+    ("glob:*aimbot*", "Aimbot DLL"),
+    ("glob:*wallhack*", "Wallhack DLL"),
+    ("re:^esp[._-]?(32|64|x86|x64)?\\.dll$", "ESP DLL"),
+    ("re:^(\\w+[_-])?cheats?([_-]\\w+)?\\.dll$", "Cheat DLL"),
+    ("re:^(\\w+[_-])?hacks?([_-]\\w+)?\\.dll$", "Hack DLL"),
+    ("re:^(\\w+[_-])?trainer([_-]\\w+)?\\.dll$", "Trainer DLL"),
     // Memory manipulation
-    ("memory", "Memory manipulation DLL"),
-    ("memoryedit", "Memory editor DLL"),
-    ("ce_", "Cheat Engine DLL"),
-    ("cheatengine", "Cheat Engine DLL"),
+    ("memory.dll", "Memory manipulation DLL"),
+    ("prefix:memoryedit", "Memory editor DLL"),
+    ("prefix:ce_", "Cheat Engine DLL"),
+    ("prefix:cheatengine", "Cheat Engine DLL"),
     // Script engines (can be used for injection)
-    ("lua51", "Lua scripting (suspicious in games)"),
-    ("python", "Python (suspicious in games)"),
+    ("lua51.dll", "Lua scripting (suspicious in games)"),
+    ("re:^python\\d*\\.dll$", "Python (suspicious in games)"),
     // Known bad actors
-    ("unknowncheats", "UnknownCheats DLL"),
-    ("mpgh", "MPGH DLL"),
-    ("d3d9_proxy", "D3D9 proxy (common for cheats)"),
-    ("d3d11_proxy", "D3D11 proxy (common for cheats)"),
-    ("dinput8_proxy", "DInput proxy (input injection)"),
+    ("glob:*unknowncheats*", "UnknownCheats DLL"),
+    ("prefix:mpgh", "MPGH DLL"),
+    ("prefix:d3d9_proxy", "D3D9 proxy (common for cheats)"),
+    ("prefix:d3d11_proxy", "D3D11 proxy (common for cheats)"),
+    ("prefix:dinput8_proxy", "DInput proxy (input injection)"),
+];
+
+pub(crate) const INJECTION_TOOLS: &[(&str, &str)] = &[
+    ("prefix:cheatengine", "Cheat Engine running"),
+    ("prefix:ce-x64", "Cheat Engine (64-bit) running"),
+    ("prefix:ce-x86", "Cheat Engine (32-bit) running"),
+    ("prefix:processhacker", "Process Hacker running"),
+    ("prefix:x64dbg", "x64dbg debugger running"),
+    ("prefix:x32dbg", "x32dbg debugger running"),
+    ("prefix:ollydbg", "OllyDbg debugger running"),
+    ("re:^ida(64|q|q64|w|w64|t|t64)?\\.exe$", "IDA Pro running"),
+    ("glob:*injector*", "Injector tool running"),
+    ("glob:*dll inject*", "DLL injection tool running"),
 ];
 
+lazy_static! {
+    static ref INJECTION_TOOL_RULES: Vec<(MatchRule, &'static str)> = INJECTION_TOOLS.iter()
+        .map(|(rule, reason)| (MatchRule::builtin(rule), *reason))
+        .collect();
+}
+
 /// Detect suspicious DLLs that may indicate injection
-#[cfg(target_os = "windows")]
-pub fn detect_dll_injection() -> DllInjectionResult {
-    use windows::Win32::System::ProcessStatus::{EnumProcessModules, GetModuleFileNameExW};
-    use windows::Win32::System::Threading::GetCurrentProcess;
-    use windows::Win32::Foundation::HMODULE;
-    
+pub fn detect_dll_injection(snapshot: &SystemSnapshot) -> DllInjectionResult {
     let mut result = DllInjectionResult::default();
     let mut seen_dlls: Vec<String> = Vec::new();
-    
-    // Whitelist for our own Iris anticheat files - never flag these
-    const IRIS_DLL_WHITELIST: &[&str] = &[
-        "iris-anticheat",
-        "iris_anticheat",
-        "iris.exe",
-        "\\appdata\\local\\iris\\",
-        "\\.tauri\\",
-    ];
-    
+    let sigs = signatures::current();
+    let allowed = whitelist::current(); // Also never flags our own Iris files
+
     // Check loaded modules in current process
-    unsafe {
-        let process = GetCurrentProcess();
-        let mut modules: [HMODULE; 1024] = [HMODULE::default(); 1024];
-        let mut needed: u32 = 0;
-        
-        if EnumProcessModules(
-            process,
-            modules.as_mut_ptr(),
-            (modules.len() * std::mem::size_of::<HMODULE>()) as u32,
-            &mut needed
-        ).is_ok() {
-            let count = (needed as usize) / std::mem::size_of::<HMODULE>();
-            
-            for i in 0..count.min(modules.len()) {
-                let mut path_buf = [0u16; 512];
-                let len = GetModuleFileNameExW(process, modules[i], &mut path_buf);
-                
-                if len > 0 {
-                    let path = String::from_utf16_lossy(&path_buf[..len as usize]);
-                    let path_lower = path.to_lowercase();
-                    let name = path.split('\\').last().unwrap_or(&path).to_string();
-                    let name_lower = name.to_lowercase();
-                    
-                    // Skip if already checked
-                    if seen_dlls.contains(&name_lower) {
-                        continue;
-                    }
-                    seen_dlls.push(name_lower.clone());
-                    
-                    // Skip our own Iris anticheat files - never flag ourselves!
-                    let is_iris = IRIS_DLL_WHITELIST.iter().any(|w| 
-                        name_lower.contains(w) || path_lower.contains(w)
-                    );
-                    if is_iris {
-                        continue;
-                    }
-                    
-                    // Check against suspicious DLL list
-                    for (pattern, reason) in SUSPICIOUS_DLL_NAMES {
-                        if name_lower.contains(pattern) {
-                            result.injection_detected = true;
-                            result.suspicious_dlls.push(SuspiciousDll {
-                                name: name.clone(),
-                                path: Some(path.clone()),
-                                reason: reason.to_string(),
-                            });
-                            result.risk_score += 80;
-                            break;
-                        }
-                    }
-                    
-                    // Check for DLLs loaded from suspicious locations
-                    // (not in System32, SysWOW64, or Program Files)
-                    let is_system_path = path_lower.contains("\\windows\\system32") ||
-                                          path_lower.contains("\\windows\\syswow64") ||
-                                          path_lower.contains("\\program files") ||
-                                          path_lower.contains("\\programdata") ||
-                                          path_lower.contains(".tauri") ||
-                                          path_lower.contains("\\appdata\\local\\iris");
-                    
-                    if !is_system_path && !name_lower.ends_with(".exe") {
-                        // Check if it's from Temp or AppData\Local\Temp
-                        if path_lower.contains("\\temp\\") || path_lower.contains("\\tmp\\") {
-                            result.injection_detected = true;
-                            result.suspicious_dlls.push(SuspiciousDll {
-                                name,
-                                path: Some(path),
-                                reason: "DLL loaded from temp directory".to_string(),
-                            });
-                            result.risk_score += 60;
-                        }
-                    }
-                }
-            }
+    for module in &snapshot.modules {
+        let path_lower = module.path.to_lowercase();
+        let name_lower = module.name.to_lowercase();
+
+        // Skip if already checked
+        if seen_dlls.contains(&name_lower) {
+            continue;
+        }
+        seen_dlls.push(name_lower.clone());
+
+        // Check against suspicious DLL list
+        let subject = Subject::file(&module.name, Some(&module.path));
+        if let Some(sig) = sigs.suspicious_dll_names.iter().find(|sig| sig.rule.matches(&subject)) {
+            if allowed.suppress(WhitelistType::Dll, &[subject], &mut result.suppressed) {
+                continue;
+            }
+            result.injection_detected = true;
+            result.suspicious_dlls.push(SuspiciousDll {
+                name: module.name.clone(),
+                path: Some(module.path.clone()),
+                reason: sig.reason.clone(),
+                pe_info: pe::try_inspect(Some(&module.path)),
+                signature: Some(authenticode::verify_file(&module.path)),
+            });
+            result.risk_score += 80;
+            continue;
+        }
+
+        // Check for DLLs loaded from suspicious locations
+        // (not in System32, SysWOW64, or Program Files)
+        let is_system_path = path_lower.contains("\\windows\\system32") ||
+                              path_lower.contains("\\windows\\syswow64") ||
+                              path_lower.contains("\\program files") ||
+                              path_lower.contains("\\programdata") ||
+                              path_lower.contains(".tauri") ||
+                              path_lower.contains("\\appdata\\local\\iris");
+
+        if is_system_path || name_lower.ends_with(".exe") {
+            continue;
+        }
+
+        // A DLL loaded from Temp is flagged even when a trusted publisher signed it
+        let info = authenticode::verify_file(&module.path);
+        let in_temp = path_lower.contains("\\temp\\") || path_lower.contains("\\tmp\\");
+        let problem = signature_problem(&info, &sigs)
+            .filter(|(reason, _)| !(*reason == "unsigned" && is_windows_path(&module.path)));
+        if (in_temp || problem.is_some())
+            && allowed.suppress(WhitelistType::Dll, &[subject], &mut result.suppressed) {
+            continue;
+        }
+
+        // Check if it's from Temp or AppData\Local\Temp
+        if in_temp {
+            result.injection_detected = true;
+            result.suspicious_dlls.push(SuspiciousDll {
+                name: module.name.clone(),
+                path: Some(module.path.clone()),
+                reason: "DLL loaded from temp directory".to_string(),
+                pe_info: pe::try_inspect(Some(&module.path)),
+                signature: Some(info),
+            });
+            result.risk_score += 60;
+        } else if let Some((reason, risk)) = problem {
+            result.injection_detected = true;
+            result.suspicious_dlls.push(SuspiciousDll {
+                name: module.name.clone(),
+                path: Some(module.path.clone()),
+                reason: reason.to_string(),
+                pe_info: pe::try_inspect(Some(&module.path)),
+                signature: Some(info),
+            });
+            result.risk_score += risk;
         }
     }
-    
-    // Also check via WMI for more comprehensive view
-    if let Ok(com_con) = COMLibrary::new() {
-        if let Ok(wmi_con) = WMIConnection::new(com_con) {
-            #[derive(Deserialize)]
-            #[allow(non_snake_case, dead_code)]
-            struct Win32Process {
-                Name: Option<String>,
-                ExecutablePath: Option<String>,
-            }
-            
-            // Look for known injector processes
-            if let Ok(processes) = wmi_con.raw_query::<Win32Process>(
-                "SELECT Name, ExecutablePath FROM Win32_Process"
-            ) {
-                for proc in processes {
-                    let proc_name = proc.Name.clone();
-                    let name = proc_name.clone().unwrap_or_default().to_lowercase();
-                    let path = proc.ExecutablePath.clone().unwrap_or_default().to_lowercase();
-                    
-                    // Skip our own Iris anticheat process
-                    if name.contains("iris-anticheat") || name.contains("iris_anticheat") || 
-                       path.contains("\\appdata\\local\\iris\\") {
-                        continue;
-                    }
-                    
-                    // Check for known injection tools running
-                    let injection_tools = [
-                        ("cheatengine", "Cheat Engine running"),
-                        ("ce-x64", "Cheat Engine (64-bit) running"),
-                        ("ce-x86", "Cheat Engine (32-bit) running"),
-                        ("processhacker", "Process Hacker running"),
-                        ("x64dbg", "x64dbg debugger running"),
-                        ("x32dbg", "x32dbg debugger running"),
-                        ("ollydbg", "OllyDbg debugger running"),
-                        ("ida", "IDA Pro running"),
-                        ("injector", "Injector tool running"),
-                        ("dll inject", "DLL injection tool running"),
-                    ];
-                    
-                    for (pattern, reason) in injection_tools {
-                        if name.contains(pattern) {
-                            let dll_name = proc_name.clone().unwrap_or_default();
-                            if !result.suspicious_dlls.iter().any(|d| d.name.to_lowercase() == dll_name.to_lowercase()) {
-                                result.injection_detected = true;
-                                result.suspicious_dlls.push(SuspiciousDll {
-                                    name: dll_name,
-                                    path: proc.ExecutablePath.clone(),
-                                    reason: reason.to_string(),
-                                });
-                                result.risk_score += 90;
-                            }
-                            break;
-                        }
-                    }
+
+    // Check known injection tools running
+    for process in &snapshot.processes {
+        let name = process.name.to_lowercase();
+        let subject = Subject::file(&process.name, process.rule_path());
+        for (pattern, reason) in INJECTION_TOOL_RULES.iter() {
+            if pattern.matches(&subject) {
+                if allowed.suppress(WhitelistType::Dll, &[subject], &mut result.suppressed) {
+                    break;
+                }
+                if !result.suspicious_dlls.iter().any(|d| d.name.to_lowercase() == name) {
+                    result.injection_detected = true;
+                    result.suspicious_dlls.push(SuspiciousDll {
+                        name: process.name.clone(),
+                        path: process.path.clone(),
+                        reason: reason.to_string(),
+                        pe_info: pe::try_inspect(process.path.as_deref()),
+                        signature: None,
+                    });
+                    result.risk_score += 90;
                 }
+                break;
             }
         }
     }
-    
+
     println!("[Hardware] DLL Injection Detection: found={}, count={}, score={}",
              result.injection_detected, result.suspicious_dlls.len(), result.risk_score);
-    
-    result
-}
 
-#[cfg(not(target_os = "windows"))]
-pub fn detect_dll_injection() -> DllInjectionResult {
-    DllInjectionResult::default()
+    result
 }
 
 // ====== VM DETECTION (Virtual Machine) ======
 
 /// Detect if running in a virtual machine
-#[cfg(target_os = "windows")]
-pub fn detect_vm() -> VmDetectionResult {
+pub fn detect_vm(snapshot: &SystemSnapshot) -> VmDetectionResult {
     let mut result = VmDetectionResult::default();
-    
-    // 1. Check via WMI for computer system information
-    if let Ok(com_con) = COMLibrary::new() {
-        if let Ok(wmi_con) = WMIConnection::new(com_con) {
-            #[derive(Deserialize)]
-            #[allow(non_snake_case, dead_code)]
-            struct Win32ComputerSystem {
-                Manufacturer: Option<String>,
-                Model: Option<String>,
-            }
-            
-            if let Ok(systems) = wmi_con.raw_query::<Win32ComputerSystem>(
-                "SELECT Manufacturer, Model FROM Win32_ComputerSystem"
-            ) {
-                for system in systems {
-                    let manufacturer = system.Manufacturer.unwrap_or_default().to_lowercase();
-                    let model = system.Model.unwrap_or_default().to_lowercase();
-                    
-                    // VMware detection
-                    if manufacturer.contains("vmware") || model.contains("vmware") {
-                        result.vm_detected = true;
-                        result.vm_type = Some("VMware".to_string());
-                        result.vm_indicators.push("WMI: VMware computer system".to_string());
-                        result.risk_score += 100;
-                    }
-                    // VirtualBox detection
-                    else if manufacturer.contains("innotek") || model.contains("virtualbox") {
-                        result.vm_detected = true;
-                        result.vm_type = Some("VirtualBox".to_string());
-                        result.vm_indicators.push("WMI: VirtualBox computer system".to_string());
-                        result.risk_score += 100;
-                    }
-                    // Hyper-V detection
-                    else if manufacturer.contains("microsoft") && model.contains("virtual") {
-                        result.vm_detected = true;
-                        result.vm_type = Some("Hyper-V".to_string());
-                        result.vm_indicators.push("WMI: Hyper-V computer system".to_string());
-                        result.risk_score += 100;
-                    }
-                    // QEMU/KVM detection
-                    else if manufacturer.contains("qemu") || model.contains("qemu") || model.contains("kvm") {
-                        result.vm_detected = true;
-                        result.vm_type = Some("QEMU/KVM".to_string());
-                        result.vm_indicators.push("WMI: QEMU/KVM computer system".to_string());
-                        result.risk_score += 100;
-                    }
-                    // Xen detection
-                    else if manufacturer.contains("xen") || model.contains("xen") {
-                        result.vm_detected = true;
-                        result.vm_type = Some("Xen".to_string());
-                        result.vm_indicators.push("WMI: Xen computer system".to_string());
-                        result.risk_score += 100;
-                    }
-                    // Parallels detection
-                    else if manufacturer.contains("parallels") || model.contains("parallels") {
-                        result.vm_detected = true;
-                        result.vm_type = Some("Parallels".to_string());
-                        result.vm_indicators.push("WMI: Parallels computer system".to_string());
-                        result.risk_score += 100;
-                    }
-                }
-            }
-            
-            // Check BIOS information
-            #[derive(Deserialize)]
-            #[allow(non_snake_case, dead_code)]
-            struct Win32Bios {
-                Manufacturer: Option<String>,
-                SerialNumber: Option<String>,
-                Version: Option<String>,
-            }
-            
-            if let Ok(bioses) = wmi_con.raw_query::<Win32Bios>(
-                "SELECT Manufacturer, SerialNumber, Version FROM Win32_BIOS"
-            ) {
-                for bios in bioses {
-                    let manufacturer = bios.Manufacturer.unwrap_or_default().to_lowercase();
-                    let version = bios.Version.unwrap_or_default().to_lowercase();
-                    let serial = bios.SerialNumber.unwrap_or_default().to_lowercase();
-                    
-                    if manufacturer.contains("vmware") || version.contains("vmware") {
-                        if result.vm_type.is_none() {
-                            result.vm_detected = true;
-                            result.vm_type = Some("VMware".to_string());
-                            result.risk_score += 80;
-                        }
-                        result.vm_indicators.push("WMI: VMware BIOS".to_string());
-                    }
-                    if manufacturer.contains("virtualbox") || version.contains("vbox") || serial.contains("vbox") {
-                        if result.vm_type.is_none() {
-                            result.vm_detected = true;
-                            result.vm_type = Some("VirtualBox".to_string());
-                            result.risk_score += 80;
-                        }
-                        result.vm_indicators.push("WMI: VirtualBox BIOS".to_string());
-                    }
-                    if version.contains("hyper-v") || manufacturer.contains("microsoft") && version.contains("hyper") {
-                        if result.vm_type.is_none() {
-                            result.vm_detected = true;
-                            result.vm_type = Some("Hyper-V".to_string());
-                            result.risk_score += 80;
-                        }
-                        result.vm_indicators.push("WMI: Hyper-V BIOS".to_string());
-                    }
-                }
-            }
-            
-            // Check for VM-specific processes
-            #[derive(Deserialize)]
-            #[allow(non_snake_case, dead_code)]
-            struct Win32Process {
-                Name: Option<String>,
-            }
-            
-            if let Ok(processes) = wmi_con.raw_query::<Win32Process>(
-                "SELECT Name FROM Win32_Process"
-            ) {
-                let vm_processes = [
-                    ("vmtoolsd.exe", "VMware"),
-                    ("vmwaretray.exe", "VMware"),
-                    ("vmwareuser.exe", "VMware"),
-                    ("vboxservice.exe", "VirtualBox"),
-                    ("vboxtray.exe", "VirtualBox"),
-                    ("vboxclient.exe", "VirtualBox"),
-                    ("xenservice.exe", "Xen"),
-                    ("prl_tools.exe", "Parallels"),
-                    ("prl_cc.exe", "Parallels"),
-                    ("vmcompute.exe", "Hyper-V"),
-                ];
-                
-                for proc in processes {
-                    let name = proc.Name.unwrap_or_default().to_lowercase();
-                    for (vm_proc, vm_name) in vm_processes {
-                        if name == vm_proc {
-                            result.vm_detected = true;
-                            if result.vm_type.is_none() {
-                                result.vm_type = Some(vm_name.to_string());
-                                result.risk_score += 90;
-                            }
-                            result.vm_indicators.push(format!("Process: {} ({})", vm_proc, vm_name));
-                            break;
-                        }
-                    }
-                }
+    let platform = &snapshot.platform;
+
+    // 1. Check computer system information
+    let manufacturer = platform.manufacturer.to_lowercase();
+    let model = platform.model.to_lowercase();
+
+    // VMware detection
+    if manufacturer.contains("vmware") || model.contains("vmware") {
+        result.vm_detected = true;
+        result.vm_type = Some("VMware".to_string());
+        result.vm_indicators.push("WMI: VMware computer system".to_string());
+        result.risk_score += 100;
+    }
+    // VirtualBox detection
+    else if manufacturer.contains("innotek") || model.contains("virtualbox") {
+        result.vm_detected = true;
+        result.vm_type = Some("VirtualBox".to_string());
+        result.vm_indicators.push("WMI: VirtualBox computer system".to_string());
+        result.risk_score += 100;
+    }
+    // Hyper-V detection
+    else if manufacturer.contains("microsoft") && model.contains("virtual") {
+        result.vm_detected = true;
+        result.vm_type = Some("Hyper-V".to_string());
+        result.vm_indicators.push("WMI: Hyper-V computer system".to_string());
+        result.risk_score += 100;
+    }
+    // QEMU/KVM detection
+    else if manufacturer.contains("qemu") || model.contains("qemu") || model.contains("kvm") {
+        result.vm_detected = true;
+        result.vm_type = Some("QEMU/KVM".to_string());
+        result.vm_indicators.push("WMI: QEMU/KVM computer system".to_string());
+        result.risk_score += 100;
+    }
+    // Xen detection
+    else if manufacturer.contains("xen") || model.contains("xen") {
+        result.vm_detected = true;
+        result.vm_type = Some("Xen".to_string());
+        result.vm_indicators.push("WMI: Xen computer system".to_string());
+        result.risk_score += 100;
+    }
+    // Parallels detection
+    else if manufacturer.contains("parallels") || model.contains("parallels") {
+        result.vm_detected = true;
+        result.vm_type = Some("Parallels".to_string());
+        result.vm_indicators.push("WMI: Parallels computer system".to_string());
+        result.risk_score += 100;
+    }
+
+    // Check BIOS information
+    let bios_manufacturer = platform.bios_manufacturer.to_lowercase();
+    let version = platform.bios_version.to_lowercase();
+    let serial = platform.bios_serial.to_lowercase();
+
+    if bios_manufacturer.contains("vmware") || version.contains("vmware") {
+        if result.vm_type.is_none() {
+            result.vm_detected = true;
+            result.vm_type = Some("VMware".to_string());
+            result.risk_score += 80;
+        }
+        result.vm_indicators.push("WMI: VMware BIOS".to_string());
+    }
+    if bios_manufacturer.contains("virtualbox") || version.contains("vbox") || serial.contains("vbox") {
+        if result.vm_type.is_none() {
+            result.vm_detected = true;
+            result.vm_type = Some("VirtualBox".to_string());
+            result.risk_score += 80;
+        }
+        result.vm_indicators.push("WMI: VirtualBox BIOS".to_string());
+    }
+    if version.contains("hyper-v") || bios_manufacturer.contains("microsoft") && version.contains("hyper") {
+        if result.vm_type.is_none() {
+            result.vm_detected = true;
+            result.vm_type = Some("Hyper-V".to_string());
+            result.risk_score += 80;
+        }
+        result.vm_indicators.push("WMI: Hyper-V BIOS".to_string());
+    }
+
+    // Raw SMBIOS table: vendor strings of every structure, UUID layout, memory devices
+    if let Some(info) = &platform.smbios {
+        for hint in smbios::platform_hints(info).into_iter().filter(|h| !h.cloud) {
+            if !hint.supporting && result.vm_type.is_none() {
+                result.vm_detected = true;
+                result.vm_type = Some(hint.platform.to_string());
+                result.risk_score += if hint.platform == "Unknown hypervisor" { 60 } else { 90 };
             }
+            result.vm_indicators.push(hint.indicator);
         }
     }
-    
-    // 2. Check registry for VM indicators
-    unsafe {
-        let vm_registry_keys = [
-            ("SYSTEM\\CurrentControlSet\\Services\\VMTools", "VMware"),
-            ("SOFTWARE\\VMware, Inc.\\VMware Tools", "VMware"),
-            ("SYSTEM\\CurrentControlSet\\Services\\VBoxGuest", "VirtualBox"),
-            ("SYSTEM\\CurrentControlSet\\Services\\VBoxMouse", "VirtualBox"),
-            ("SYSTEM\\CurrentControlSet\\Services\\VBoxService", "VirtualBox"),
-            ("SOFTWARE\\Oracle\\VirtualBox Guest Additions", "VirtualBox"),
-            ("SYSTEM\\CurrentControlSet\\Services\\hv_vmbus", "Hyper-V"),
-            ("SOFTWARE\\Microsoft\\Virtual Machine\\Guest\\Parameters", "Hyper-V"),
-        ];
-        
-        for (key_path, vm_name) in vm_registry_keys {
-            let key_path_w: Vec<u16> = format!("{}\0", key_path).encode_utf16().collect();
-            let mut hkey: HKEY = HKEY::default();
-            
-            let open_result = RegOpenKeyExW(
-                HKEY_LOCAL_MACHINE,
-                PCWSTR(key_path_w.as_ptr()),
-                0,
-                KEY_READ,
-                &mut hkey,
-            );
-            
-            if open_result.is_ok() {
-                let _ = RegCloseKey(hkey);
+
+    // Check for VM-specific processes
+    let vm_processes = [
+        ("vmtoolsd.exe", "VMware"),
+        ("vmwaretray.exe", "VMware"),
+        ("vmwareuser.exe", "VMware"),
+        ("vboxservice.exe", "VirtualBox"),
+        ("vboxtray.exe", "VirtualBox"),
+        ("vboxclient.exe", "VirtualBox"),
+        ("xenservice.exe", "Xen"),
+        ("prl_tools.exe", "Parallels"),
+        ("prl_cc.exe", "Parallels"),
+        ("vmcompute.exe", "Hyper-V"),
+    ];
+
+    for proc in &snapshot.processes {
+        let name = proc.name.to_lowercase();
+        for (vm_proc, vm_name) in vm_processes {
+            if name == vm_proc {
                 result.vm_detected = true;
                 if result.vm_type.is_none() {
                     result.vm_type = Some(vm_name.to_string());
-                    result.risk_score += 70;
+                    result.risk_score += 90;
                 }
-                result.vm_indicators.push(format!("Registry: {} ({})", key_path, vm_name));
+                result.vm_indicators.push(format!("Process: {} ({})", vm_proc, vm_name));
+                break;
             }
         }
     }
-    
-    println!("[Hardware] VM Detection: detected={}, type={:?}, indicators={}, score={}",
-             result.vm_detected, result.vm_type, result.vm_indicators.len(), result.risk_score);
-    
-    result
-}
-
-#[cfg(not(target_os = "windows"))]
-pub fn detect_vm() -> VmDetectionResult {
-    VmDetectionResult::default()
-}
-
-// ====== CLOUD PC DETECTION (Shadow, GeForce NOW, etc.) ======
-
-/// Detect if running on a cloud gaming PC
-#[cfg(target_os = "windows")]
-pub fn detect_cloud_pc() -> CloudPcDetectionResult {
-    let mut result = CloudPcDetectionResult::default();
-    
-    // 1. Check via WMI for cloud provider indicators
-    if let Ok(com_con) = COMLibrary::new() {
-        if let Ok(wmi_con) = WMIConnection::new(com_con) {
-            #[derive(Deserialize)]
-            #[allow(non_snake_case, dead_code)]
-            struct Win32ComputerSystem {
-                Manufacturer: Option<String>,
-                Model: Option<String>,
-            }
-            
-            if let Ok(systems) = wmi_con.raw_query::<Win32ComputerSystem>(
-                "SELECT Manufacturer, Model FROM Win32_ComputerSystem"
-            ) {
-                for system in systems {
-                    let manufacturer = system.Manufacturer.unwrap_or_default().to_lowercase();
-                    let model = system.Model.unwrap_or_default().to_lowercase();
-                    
-                    // Shadow PC detection
-                    if manufacturer.contains("shadow") || model.contains("shadow") {
-                        result.cloud_pc_detected = true;
-                        result.cloud_provider = Some("Shadow".to_string());
-                        result.is_gaming_cloud = true;
-                        result.cloud_indicators.push("WMI: Shadow cloud PC".to_string());
-                        result.risk_score += 80;
-                    }
-                    // AWS (Amazon Web Services)
-                    else if manufacturer.contains("amazon") || model.contains("aws") || model.contains("ec2") {
-                        result.cloud_pc_detected = true;
-                        result.cloud_provider = Some("AWS".to_string());
-                        result.cloud_indicators.push("WMI: AWS cloud instance".to_string());
-                        result.risk_score += 70;
-                    }
-                    // Azure
-                    else if manufacturer.contains("microsoft") && (model.contains("azure") || model.contains("virtual machine")) {
-                        result.cloud_pc_detected = true;
-                        result.cloud_provider = Some("Azure".to_string());
-                        result.cloud_indicators.push("WMI: Azure cloud instance".to_string());
-                        result.risk_score += 70;
-                    }
-                    // Google Cloud
-                    else if manufacturer.contains("google") || model.contains("google") {
-                        result.cloud_pc_detected = true;
-                        result.cloud_provider = Some("Google Cloud".to_string());
-                        result.cloud_indicators.push("WMI: Google Cloud instance".to_string());
-                        result.risk_score += 70;
-                    }
-                    // OVH
-                    else if manufacturer.contains("ovh") || model.contains("ovh") {
-                        result.cloud_pc_detected = true;
-                        result.cloud_provider = Some("OVH".to_string());
-                        result.cloud_indicators.push("WMI: OVH cloud instance".to_string());
-                        result.risk_score += 60;
-                    }
-                    // Paperspace (for gaming)
-                    else if manufacturer.contains("paperspace") || model.contains("paperspace") {
-                        result.cloud_pc_detected = true;
-                        result.cloud_provider = Some("Paperspace".to_string());
-                        result.is_gaming_cloud = true;
-                        result.cloud_indicators.push("WMI: Paperspace cloud PC".to_string());
-                        result.risk_score += 80;
-                    }
-                    // Maximum Settings (gaming cloud)
-                    else if manufacturer.contains("maximum") || model.contains("maximum settings") {
-                        result.cloud_pc_detected = true;
-                        result.cloud_provider = Some("Maximum Settings".to_string());
-                        result.is_gaming_cloud = true;
-                        result.cloud_indicators.push("WMI: Maximum Settings cloud PC".to_string());
-                        result.risk_score += 80;
-                    }
-                }
-            }
-            
-            // Check for cloud gaming software processes
-            #[derive(Deserialize)]
-            #[allow(non_snake_case, dead_code)]
-            struct Win32Process {
-                Name: Option<String>,
-            }
-            
-            if let Ok(processes) = wmi_con.raw_query::<Win32Process>(
-                "SELECT Name FROM Win32_Process"
-            ) {
-                let cloud_processes = [
-                    // GeForce NOW
-                    ("geforcenow.exe", "GeForce NOW", true),
-                    ("geforcenowstreamer.exe", "GeForce NOW", true),
-                    ("nvidia geforce now.exe", "GeForce NOW", true),
-                    // Shadow
-                    ("shadow.exe", "Shadow", true),
-                    ("shadowstreamer.exe", "Shadow", true),
-                    ("shadow launcher.exe", "Shadow", true),
-                    // Parsec (can be used for cloud gaming)
-                    ("parsecd.exe", "Parsec", true),
-                    ("parsec.exe", "Parsec", true),
-                    // Xbox Cloud Gaming / xCloud
-                    ("xbox.exe", "Xbox Cloud Gaming", true),
-                    ("xboxgamebarwidget.exe", "Xbox Cloud Gaming", false),
-                    // Amazon Luna
-                    ("luna.exe", "Amazon Luna", true),
-                    // Boosteroid
-                    ("boosteroid.exe", "Boosteroid", true),
-                    // PlutoSphere
-                    ("plutosphere.exe", "PlutoSphere", true),
-                    // Loudplay
-                    ("loudplay.exe", "Loudplay", true),
-                    // Blacknut
-                    ("blacknut.exe", "Blacknut", true),
-                    // Rainway
-                    ("rainway.exe", "Rainway", true),
-                    // Moonlight (client for streaming)
-                    ("moonlight.exe", "Moonlight", true),
-                    // Stadia (RIP but might still exist)
-                    ("stadia.exe", "Stadia", true),
-                    // Remote desktop indicators (not gaming but cloud access)
-                    ("mstsc.exe", "Remote Desktop", false),
-                    ("anydesk.exe", "AnyDesk", false),
-                    ("teamviewer.exe", "TeamViewer", false),
-                ];
-                
-                for proc in processes {
-                    let name = proc.Name.unwrap_or_default().to_lowercase();
-                    for (cloud_proc, provider, is_gaming) in cloud_processes {
-                        if name == cloud_proc {
-                            if result.cloud_provider.is_none() || is_gaming {
-                                result.cloud_pc_detected = true;
-                                result.cloud_provider = Some(provider.to_string());
-                                result.is_gaming_cloud = result.is_gaming_cloud || is_gaming;
-                                result.risk_score += if is_gaming { 70 } else { 40 };
-                            }
-                            result.cloud_indicators.push(format!("Process: {} ({})", cloud_proc, provider));
-                            break;
-                        }
-                    }
-                }
-            }
-            
-            // Check GPU - cloud gaming often uses datacenter GPUs
-            #[derive(Deserialize)]
-            #[allow(non_snake_case, dead_code)]
-            struct Win32VideoController {
-                Name: Option<String>,
-                AdapterRAM: Option<u64>,
-            }
-            
-            if let Ok(gpus) = wmi_con.raw_query::<Win32VideoController>(
-                "SELECT Name, AdapterRAM FROM Win32_VideoController"
-            ) {
-                for gpu in gpus {
-                    let name = gpu.Name.unwrap_or_default().to_lowercase();
-                    
-                    // Tesla GPUs (datacenter)
-                    if name.contains("tesla") {
-                        result.cloud_pc_detected = true;
-                        result.cloud_indicators.push(format!("GPU: Tesla (datacenter GPU)"));
-                        result.risk_score += 60;
-                    }
-                    // A100, A10, A16, A40 (datacenter)
-                    else if name.contains("nvidia a100") || name.contains("nvidia a10") || 
-                            name.contains("nvidia a16") || name.contains("nvidia a40") {
-                        result.cloud_pc_detected = true;
-                        result.cloud_indicators.push(format!("GPU: {} (datacenter GPU)", name));
-                        result.risk_score += 60;
-                    }
-                    // GRID (virtual GPU)
-                    else if name.contains("grid") {
-                        result.cloud_pc_detected = true;
-                        result.cloud_indicators.push(format!("GPU: {} (virtual GPU)", name));
-                        result.risk_score += 70;
-                    }
-                    // Quadro RTX in datacenter context
-                    else if name.contains("quadro rtx") {
-                        result.cloud_indicators.push(format!("GPU: {} (possible datacenter)", name));
-                        // Lower risk - Quadro can be local workstation too
-                        result.risk_score += 20;
-                    }
-                }
+
+    // 2. Check registry for VM indicators
+    let vm_registry_keys = [
+        ("SYSTEM\\CurrentControlSet\\Services\\VMTools", "VMware"),
+        ("SOFTWARE\\VMware, Inc.\\VMware Tools", "VMware"),
+        ("SYSTEM\\CurrentControlSet\\Services\\VBoxGuest", "VirtualBox"),
+        ("SYSTEM\\CurrentControlSet\\Services\\VBoxMouse", "VirtualBox"),
+        ("SYSTEM\\CurrentControlSet\\Services\\VBoxService", "VirtualBox"),
+        ("SOFTWARE\\Oracle\\VirtualBox Guest Additions", "VirtualBox"),
+        ("SYSTEM\\CurrentControlSet\\Services\\hv_vmbus", "Hyper-V"),
+        ("SOFTWARE\\Microsoft\\Virtual Machine\\Guest\\Parameters", "Hyper-V"),
+    ];
+
+    for (key_path, vm_name) in vm_registry_keys {
+        if hklm_key_exists(key_path) {
+            result.vm_detected = true;
+            if result.vm_type.is_none() {
+                result.vm_type = Some(vm_name.to_string());
+                result.risk_score += 70;
             }
+            result.vm_indicators.push(format!("Registry: {} ({})", key_path, vm_name));
         }
     }
-    
-    // 2. Check registry for cloud gaming installations
-    unsafe {
-        let cloud_registry_keys = [
-            ("SOFTWARE\\NVIDIA Corporation\\GeForce NOW", "GeForce NOW", true),
-            ("SOFTWARE\\Shadow\\Shadow", "Shadow", true),
-            ("SOFTWARE\\Parsec", "Parsec", true),
-            ("SOFTWARE\\Boosteroid", "Boosteroid", true),
-            ("SOFTWARE\\Rainway", "Rainway", true),
-        ];
-        
-        for (key_path, provider, is_gaming) in cloud_registry_keys {
-            let key_path_w: Vec<u16> = format!("{}\0", key_path).encode_utf16().collect();
-            let mut hkey: HKEY = HKEY::default();
-            
-            let open_result = RegOpenKeyExW(
-                HKEY_LOCAL_MACHINE,
-                PCWSTR(key_path_w.as_ptr()),
-                0,
-                KEY_READ,
-                &mut hkey,
-            );
-            
-            if open_result.is_ok() {
-                let _ = RegCloseKey(hkey);
+
+    println!("[Hardware] VM Detection: detected={}, type={:?}, indicators={}, score={}",
+             result.vm_detected, result.vm_type, result.vm_indicators.len(), result.risk_score);
+
+    result
+}
+
+// ====== CLOUD PC DETECTION (Shadow, GeForce NOW, etc.) ======
+
+/// Detect if running on a cloud gaming PC
+pub fn detect_cloud_pc(snapshot: &SystemSnapshot) -> CloudPcDetectionResult {
+    let mut result = CloudPcDetectionResult::default();
+
+    // 1. Check computer system information for cloud provider indicators
+    let manufacturer = snapshot.platform.manufacturer.to_lowercase();
+    let model = snapshot.platform.model.to_lowercase();
+
+    // Shadow PC detection
+    if manufacturer.contains("shadow") || model.contains("shadow") {
+        result.cloud_pc_detected = true;
+        result.cloud_provider = Some("Shadow".to_string());
+        result.is_gaming_cloud = true;
+        result.cloud_indicators.push("WMI: Shadow cloud PC".to_string());
+        result.risk_score += 80;
+    }
+    // AWS (Amazon Web Services)
+    else if manufacturer.contains("amazon") || model.contains("aws") || model.contains("ec2") {
+        result.cloud_pc_detected = true;
+        result.cloud_provider = Some("AWS".to_string());
+        result.cloud_indicators.push("WMI: AWS cloud instance".to_string());
+        result.risk_score += 70;
+    }
+    // Azure
+    else if manufacturer.contains("microsoft") && (model.contains("azure") || model.contains("virtual machine")) {
+        result.cloud_pc_detected = true;
+        result.cloud_provider = Some("Azure".to_string());
+        result.cloud_indicators.push("WMI: Azure cloud instance".to_string());
+        result.risk_score += 70;
+    }
+    // Google Cloud
+    else if manufacturer.contains("google") || model.contains("google") {
+        result.cloud_pc_detected = true;
+        result.cloud_provider = Some("Google Cloud".to_string());
+        result.cloud_indicators.push("WMI: Google Cloud instance".to_string());
+        result.risk_score += 70;
+    }
+    // OVH
+    else if manufacturer.contains("ovh") || model.contains("ovh") {
+        result.cloud_pc_detected = true;
+        result.cloud_provider = Some("OVH".to_string());
+        result.cloud_indicators.push("WMI: OVH cloud instance".to_string());
+        result.risk_score += 60;
+    }
+    // Paperspace (for gaming)
+    else if manufacturer.contains("paperspace") || model.contains("paperspace") {
+        result.cloud_pc_detected = true;
+        result.cloud_provider = Some("Paperspace".to_string());
+        result.is_gaming_cloud = true;
+        result.cloud_indicators.push("WMI: Paperspace cloud PC".to_string());
+        result.risk_score += 80;
+    }
+    // Maximum Settings (gaming cloud)
+    else if manufacturer.contains("maximum") || model.contains("maximum settings") {
+        result.cloud_pc_detected = true;
+        result.cloud_provider = Some("Maximum Settings".to_string());
+        result.is_gaming_cloud = true;
+        result.cloud_indicators.push("WMI: Maximum Settings cloud PC".to_string());
+        result.risk_score += 80;
+    }
+
+    // Raw SMBIOS table: cloud host vendor strings, EC2 UUIDs, Azure asset tag
+    if let Some(info) = &snapshot.platform.smbios {
+        for hint in smbios::platform_hints(info).into_iter().filter(|h| h.cloud) {
+            let known = result.cloud_provider.as_deref() == Some(hint.platform);
+            if !known && (result.cloud_provider.is_none() || hint.gaming) {
+                result.cloud_pc_detected = true;
+                result.cloud_provider = Some(hint.platform.to_string());
+                result.is_gaming_cloud = result.is_gaming_cloud || hint.gaming;
+                result.risk_score += if hint.gaming { 80 } else { 70 };
+            }
+            result.cloud_indicators.push(hint.indicator);
+        }
+    }
+
+    // Check for cloud gaming software processes
+    let cloud_processes = [
+        // GeForce NOW
+        ("geforcenow.exe", "GeForce NOW", true),
+        ("geforcenowstreamer.exe", "GeForce NOW", true),
+        ("nvidia geforce now.exe", "GeForce NOW", true),
+        // Shadow
+        ("shadow.exe", "Shadow", true),
+        ("shadowstreamer.exe", "Shadow", true),
+        ("shadow launcher.exe", "Shadow", true),
+        // Parsec (can be used for cloud gaming)
+        ("parsecd.exe", "Parsec", true),
+        ("parsec.exe", "Parsec", true),
+        // Xbox Cloud Gaming / xCloud
+        ("xbox.exe", "Xbox Cloud Gaming", true),
+        ("xboxgamebarwidget.exe", "Xbox Cloud Gaming", false),
+        // Amazon Luna
+        ("luna.exe", "Amazon Luna", true),
+        // Boosteroid
+        ("boosteroid.exe", "Boosteroid", true),
+        // PlutoSphere
+        ("plutosphere.exe", "PlutoSphere", true),
+        // Loudplay
+        ("loudplay.exe", "Loudplay", true),
+        // Blacknut
+        ("blacknut.exe", "Blacknut", true),
+        // Rainway
+        ("rainway.exe", "Rainway", true),
+        // Moonlight (client for streaming)
+        ("moonlight.exe", "Moonlight", true),
+        // Stadia (RIP but might still exist)
+        ("stadia.exe", "Stadia", true),
+        // Remote desktop indicators (not gaming but cloud access)
+        ("mstsc.exe", "Remote Desktop", false),
+        ("anydesk.exe", "AnyDesk", false),
+        ("teamviewer.exe", "TeamViewer", false),
+    ];
+
+    for proc in &snapshot.processes {
+        let name = proc.name.to_lowercase();
+        for (cloud_proc, provider, is_gaming) in cloud_processes {
+            if name == cloud_proc {
                 if result.cloud_provider.is_none() || is_gaming {
                     result.cloud_pc_detected = true;
                     result.cloud_provider = Some(provider.to_string());
                     result.is_gaming_cloud = result.is_gaming_cloud || is_gaming;
-                    result.risk_score += if is_gaming { 50 } else { 30 };
+                    result.risk_score += if is_gaming { 70 } else { 40 };
                 }
-                result.cloud_indicators.push(format!("Registry: {} ({})", key_path, provider));
+                result.cloud_indicators.push(format!("Process: {} ({})", cloud_proc, provider));
+                break;
             }
-            
-            // Also check HKCU
-            let open_result_cu = RegOpenKeyExW(
-                HKEY_CURRENT_USER,
-                PCWSTR(key_path_w.as_ptr()),
-                0,
-                KEY_READ,
-                &mut hkey,
-            );
-            
-            if open_result_cu.is_ok() {
-                let _ = RegCloseKey(hkey);
-                if !result.cloud_indicators.iter().any(|i| i.contains(key_path)) {
-                    if result.cloud_provider.is_none() || is_gaming {
-                        result.cloud_pc_detected = true;
-                        result.cloud_provider = Some(provider.to_string());
-                        result.is_gaming_cloud = result.is_gaming_cloud || is_gaming;
-                        result.risk_score += if is_gaming { 50 } else { 30 };
-                    }
-                    result.cloud_indicators.push(format!("Registry (HKCU): {} ({})", key_path, provider));
-                }
+        }
+    }
+
+    // Check GPU - cloud gaming often uses datacenter GPUs
+    for gpu in &snapshot.platform.gpus {
+        let name = gpu.to_lowercase();
+
+        // Tesla GPUs (datacenter)
+        if name.contains("tesla") {
+            result.cloud_pc_detected = true;
+            result.cloud_indicators.push("GPU: Tesla (datacenter GPU)".to_string());
+            result.risk_score += 60;
+        }
+        // A100, A10, A16, A40 (datacenter)
+        else if name.contains("nvidia a100") || name.contains("nvidia a10") ||
+                name.contains("nvidia a16") || name.contains("nvidia a40") {
+            result.cloud_pc_detected = true;
+            result.cloud_indicators.push(format!("GPU: {} (datacenter GPU)", name));
+            result.risk_score += 60;
+        }
+        // GRID (virtual GPU)
+        else if name.contains("grid") {
+            result.cloud_pc_detected = true;
+            result.cloud_indicators.push(format!("GPU: {} (virtual GPU)", name));
+            result.risk_score += 70;
+        }
+        // Quadro RTX in datacenter context
+        else if name.contains("quadro rtx") {
+            result.cloud_indicators.push(format!("GPU: {} (possible datacenter)", name));
+            // Lower risk - Quadro can be local workstation too
+            result.risk_score += 20;
+        }
+    }
+
+    // 2. Check registry for cloud gaming installations
+    let cloud_registry_keys = [
+        ("SOFTWARE\\NVIDIA Corporation\\GeForce NOW", "GeForce NOW", true),
+        ("SOFTWARE\\Shadow\\Shadow", "Shadow", true),
+        ("SOFTWARE\\Parsec", "Parsec", true),
+        ("SOFTWARE\\Boosteroid", "Boosteroid", true),
+        ("SOFTWARE\\Rainway", "Rainway", true),
+    ];
+
+    for (key_path, provider, is_gaming) in cloud_registry_keys {
+        if hklm_key_exists(key_path) {
+            if result.cloud_provider.is_none() || is_gaming {
+                result.cloud_pc_detected = true;
+                result.cloud_provider = Some(provider.to_string());
+                result.is_gaming_cloud = result.is_gaming_cloud || is_gaming;
+                result.risk_score += if is_gaming { 50 } else { 30 };
+            }
+            result.cloud_indicators.push(format!("Registry: {} ({})", key_path, provider));
+        }
+
+        // Also check HKCU
+        if hkcu_key_exists(key_path) && !result.cloud_indicators.iter().any(|i| i.contains(key_path)) {
+            if result.cloud_provider.is_none() || is_gaming {
+                result.cloud_pc_detected = true;
+                result.cloud_provider = Some(provider.to_string());
+                result.is_gaming_cloud = result.is_gaming_cloud || is_gaming;
+                result.risk_score += if is_gaming { 50 } else { 30 };
             }
+            result.cloud_indicators.push(format!("Registry (HKCU): {} ({})", key_path, provider));
         }
     }
-    
+
     println!("[Hardware] Cloud PC Detection: detected={}, provider={:?}, gaming={}, indicators={}, score={}",
-             result.cloud_pc_detected, result.cloud_provider, result.is_gaming_cloud, 
+             result.cloud_pc_detected, result.cloud_provider, result.is_gaming_cloud,
              result.cloud_indicators.len(), result.risk_score);
-    
-    result
-}
 
-#[cfg(not(target_os = "windows"))]
-pub fn detect_cloud_pc() -> CloudPcDetectionResult {
-    CloudPcDetectionResult::default()
+    result
 }
 
 // ====== CHEAT WINDOW/PANEL DETECTION (CoD specific) ======
 
-// Whitelist - Our own processes and windows that should never be flagged
-const IRIS_WHITELIST: &[&str] = &[
-    "iris-anticheat",
-    "iris anticheat",
-    "iris - nomercy",
-    "nomercy anticheat",
-];
-
-// Known cheat software window titles and process names
-const CHEAT_WINDOW_KEYWORDS: &[(&str, &str, &str)] = &[
+// Known cheat software window titles (short or ambiguous terms are word-anchored)
+pub(crate) const CHEAT_WINDOW_KEYWORDS: &[(&str, &str, &str)] = &[
     // Critical - Known CoD cheat providers
-    ("engineowning", "EngineOwning", "critical"),
-    ("skycheats", "SkyCheats", "critical"),
-    ("ring-1", "Ring-1", "critical"),
-    ("battlelog", "Battlelog Cheats", "critical"),
-    ("iwantcheats", "IWantCheats", "critical"),
-    ("wallhax", "Wallhax", "critical"),
-    ("aimjunkies", "AimJunkies", "critical"),
-    ("unknowncheats", "UnknownCheats", "critical"),
-    ("phantom overlay", "Phantom Overlay", "critical"),
-    ("pasted", "Pasted Cheats", "critical"),
-    ("elitepvpers", "ElitePvPers", "critical"),
-    ("artificialaiming", "ArtificialAiming", "critical"),
-    ("aimware", "Aimware", "critical"),
-    ("onetap", "OneTap", "critical"),
-    ("fatality", "Fatality", "critical"),
-    ("neverlose", "Neverlose", "critical"),
-    ("gamesense", "GameSense", "critical"),
-    ("iniuria", "Iniuria", "critical"),
-    ("interium", "Interium", "critical"),
-    ("nixware", "Nixware", "critical"),
-    ("spirthack", "Spirthack", "critical"),
+    ("glob:*engineowning*", "EngineOwning", "critical"),
+    ("glob:*skycheats*", "SkyCheats", "critical"),
+    ("re:\\bring-1\\b", "Ring-1", "critical"),
+    ("re:\\bbattlelog\\b", "Battlelog Cheats", "critical"),
+    ("glob:*iwantcheats*", "IWantCheats", "critical"),
+    ("glob:*wallhax*", "Wallhax", "critical"),
+    ("glob:*aimjunkies*", "AimJunkies", "critical"),
+    ("glob:*unknowncheats*", "UnknownCheats", "critical"),
+    ("glob:*phantom overlay*", "Phantom Overlay", "critical"),
+    ("re:\\bpasted\\b", "Pasted Cheats", "critical"),
+    ("glob:*elitepvpers*", "ElitePvPers", "critical"),
+    ("glob:*artificialaiming*", "ArtificialAiming", "critical"),
+    ("re:\\baimware\\b", "Aimware", "critical"),
+    ("re:\\bonetap\\b", "OneTap", "critical"),
+    ("re:\\bfatality\\b", "Fatality", "critical"),
+    ("re:\\bneverlose\\b", "Neverlose", "critical"),
+    ("re:\\bgamesense\\b", "GameSense", "critical"),
+    ("re:\\biniuria\\b", "Iniuria", "critical"),
+    ("re:\\binterium\\b", "Interium", "critical"),
+    ("re:\\bnixware\\b", "Nixware", "critical"),
+    ("glob:*spirthack*", "Spirthack", "critical"),
     
     // High risk - Generic cheat terms
-    ("aimbot", "Aimbot Panel", "high"),
-    ("wallhack", "Wallhack", "high"),
-    ("esp hack", "ESP Hack", "high"),
-    ("triggerbot", "Triggerbot", "high"),
-    ("no recoil", "No Recoil", "high"),
-    ("silent aim", "Silent Aim", "high"),
-    ("rage hack", "Rage Hack", "high"),
-    ("legit hack", "Legit Hack", "high"),
-    ("hvh", "HvH Cheat", "high"),
-    ("spinbot", "Spinbot", "high"),
-    ("cheat menu", "Cheat Menu", "high"),
-    ("hack menu", "Hack Menu", "high"),
-    ("cheat panel", "Cheat Panel", "high"),
-    ("hack panel", "Hack Panel", "high"),
-    ("unlock all", "Unlock All Tool", "high"),
-    ("camo unlocker", "Camo Unlocker", "high"),
-    ("prestige hack", "Prestige Hack", "high"),
+    ("glob:*aimbot*", "Aimbot Panel", "high"),
+    ("glob:*wallhack*", "Wallhack", "high"),
+    ("glob:*esp hack*", "ESP Hack", "high"),
+    ("glob:*triggerbot*", "Triggerbot", "high"),
+    ("glob:*no recoil*", "No Recoil", "high"),
+    ("glob:*silent aim*", "Silent Aim", "high"),
+    ("glob:*rage hack*", "Rage Hack", "high"),
+    ("glob:*legit hack*", "Legit Hack", "high"),
+    ("re:\\bhvh\\b", "HvH Cheat", "high"),
+    ("glob:*spinbot*", "Spinbot", "high"),
+    ("glob:*cheat menu*", "Cheat Menu", "high"),
+    ("glob:*hack menu*", "Hack Menu", "high"),
+    ("glob:*cheat panel*", "Cheat Panel", "high"),
+    ("glob:*hack panel*", "Hack Panel", "high"),
+    ("glob:*unlock all*", "Unlock All Tool", "high"),
+    ("glob:*camo unlocker*", "Camo Unlocker", "high"),
+    ("glob:*prestige hack*", "Prestige Hack", "high"),
     
     // Medium risk - Injection/Debug tools often used with cheats
-    ("extreme injector", "Extreme Injector", "medium"),
-    ("process hacker", "Process Hacker", "medium"),
-    ("cheat engine", "Cheat Engine", "medium"),
-    ("x64dbg", "x64dbg", "medium"),
-    ("x32dbg", "x32dbg", "medium"),
-    ("ollydbg", "OllyDbg", "medium"),
-    ("ida pro", "IDA Pro", "medium"),
-    ("ida64", "IDA", "medium"),
-    ("ghidra", "Ghidra", "medium"),
-    ("windbg", "WinDbg", "medium"),
-    ("reclass", "ReClass", "medium"),
-    ("dll injector", "DLL Injector", "medium"),
-    ("manual map", "Manual Map Injector", "medium"),
+    ("glob:*extreme injector*", "Extreme Injector", "medium"),
+    ("glob:*process hacker*", "Process Hacker", "medium"),
+    ("glob:*cheat engine*", "Cheat Engine", "medium"),
+    ("re:\\bx64dbg\\b", "x64dbg", "medium"),
+    ("re:\\bx32dbg\\b", "x32dbg", "medium"),
+    ("re:\\bollydbg\\b", "OllyDbg", "medium"),
+    ("glob:*ida pro*", "IDA Pro", "medium"),
+    ("re:\\bida64\\b", "IDA", "medium"),
+    ("re:\\bghidra\\b", "Ghidra", "medium"),
+    ("re:\\bwindbg\\b", "WinDbg", "medium"),
+    ("re:\\breclass\\b", "ReClass", "medium"),
+    ("glob:*dll injector*", "DLL Injector", "medium"),
+    ("glob:*manual map*", "Manual Map Injector", "medium"),
     
     // CoD specific tools
-    ("cod tool", "CoD Tool", "high"),
-    ("mw tool", "MW Tool", "high"),
-    ("warzone tool", "Warzone Tool", "high"),
-    ("bo6 tool", "BO6 Tool", "high"),
-    ("black ops tool", "Black Ops Tool", "high"),
-    ("plutonium", "Plutonium (Mod Client)", "medium"),
-    ("h1-mod", "H1-Mod", "medium"),
+    ("glob:*cod tool*", "CoD Tool", "high"),
+    ("glob:*mw tool*", "MW Tool", "high"),
+    ("glob:*warzone tool*", "Warzone Tool", "high"),
+    ("glob:*bo6 tool*", "BO6 Tool", "high"),
+    ("glob:*black ops tool*", "Black Ops Tool", "high"),
+    ("re:\\bplutonium\\b", "Plutonium (Mod Client)", "medium"),
+    ("re:\\bh1-mod\\b", "H1-Mod", "medium"),
 ];
 
 // Known cheat process names (executable names)
 const CHEAT_PROCESS_NAMES: &[(&str, &str, &str)] = &[
     // Cheat loaders/injectors
-    ("eo loader", "EngineOwning Loader", "critical"),
+    ("prefix:eo loader", "EngineOwning Loader", "critical"),
     ("eo.exe", "EngineOwning", "critical"),
     ("loader.exe", "Suspicious Loader", "high"),
     ("injector.exe", "Suspicious Injector", "high"),
@@ -3236,153 +2857,101 @@ const CHEAT_PROCESS_NAMES: &[(&str, &str, &str)] = &[
     ("trainer.exe", "Trainer", "high"),
     
     // Memory tools
-    ("cheatengine", "Cheat Engine", "medium"),
-    ("processhacker", "Process Hacker", "medium"),
-    ("extremeinjector", "Extreme Injector", "medium"),
+    ("prefix:cheatengine", "Cheat Engine", "medium"),
+    ("prefix:processhacker", "Process Hacker", "medium"),
+    ("prefix:extremeinjector", "Extreme Injector", "medium"),
     
     // Known cheat executables
     ("phantom.exe", "Phantom Overlay", "critical"),
     ("ring1.exe", "Ring-1", "critical"),
-    ("skycheats", "SkyCheats", "critical"),
+    ("prefix:skycheats", "SkyCheats", "critical"),
 ];
 
+lazy_static! {
+    static ref CHEAT_PROCESS_RULES: Vec<(MatchRule, &'static str, &'static str)> = CHEAT_PROCESS_NAMES.iter()
+        .map(|(rule, name, risk)| (MatchRule::builtin(rule), *name, *risk))
+        .collect();
+}
+
 /// Detect cheat windows and panels by scanning window titles
-#[cfg(target_os = "windows")]
-pub fn detect_cheat_windows() -> CheatWindowDetectionResult {
-    use windows::Win32::UI::WindowsAndMessaging::{
-        EnumWindows, GetWindowTextW, GetWindowThreadProcessId, IsWindowVisible,
-    };
-    use windows::Win32::System::Threading::{
-        OpenProcess, PROCESS_QUERY_INFORMATION, PROCESS_VM_READ,
-    };
-    use windows::Win32::System::ProcessStatus::GetModuleBaseNameW;
-    use windows::Win32::Foundation::{HWND, BOOL, LPARAM, CloseHandle};
-    use std::sync::Mutex;
-    
+pub fn detect_cheat_windows(snapshot: &SystemSnapshot) -> CheatWindowDetectionResult {
     let mut result = CheatWindowDetectionResult::default();
-    let detected_windows: Mutex<Vec<DetectedCheatWindow>> = Mutex::new(Vec::new());
-    
-    unsafe extern "system" fn enum_window_proc(hwnd: HWND, lparam: LPARAM) -> BOOL {
-        let detected = &*(lparam.0 as *const Mutex<Vec<DetectedCheatWindow>>);
-        
-        // Check if window is visible
-        if !IsWindowVisible(hwnd).as_bool() {
-            return BOOL(1); // Continue
-        }
-        
-        // Get window title
-        let mut title_buf = [0u16; 512];
-        let title_len = GetWindowTextW(hwnd, &mut title_buf);
-        if title_len == 0 {
-            return BOOL(1);
-        }
-        let title = String::from_utf16_lossy(&title_buf[..title_len as usize]).to_lowercase();
-        
-        // Skip very short titles
+    let mut detected: Vec<DetectedCheatWindow> = Vec::new();
+    let sigs = signatures::current();
+    let allowed = whitelist::current(); // Also covers our own Iris windows
+
+    for window in &snapshot.windows {
+        let title = window.title.to_lowercase();
+
+        // Skip untitled and very short titles
         if title.len() < 3 {
-            return BOOL(1);
+            continue;
         }
-        
-        // Get process name
-        let mut process_id: u32 = 0;
-        GetWindowThreadProcessId(hwnd, Some(&mut process_id));
-        
-        let mut process_name = String::new();
-        if process_id > 0 {
-            if let Ok(handle) = OpenProcess(
-                PROCESS_QUERY_INFORMATION | PROCESS_VM_READ,
-                false,
-                process_id
-            ) {
-                let mut name_buf = [0u16; 256];
-                let name_len = GetModuleBaseNameW(handle, None, &mut name_buf);
-                if name_len > 0 {
-                    process_name = String::from_utf16_lossy(&name_buf[..name_len as usize]).to_lowercase();
+
+        let process_name = window.process_name.to_lowercase();
+
+        // Check against known cheat window keywords
+        let title_subject = Subject::name(&window.title);
+        let process_subject = Subject::name(&window.process_name);
+        for sig in &sigs.cheat_window_keywords {
+            if sig.rule.matches(&title_subject) {
+                let candidates = [Subject::name(&sig.name), title_subject, process_subject];
+                if allowed.suppress(WhitelistType::CheatWindow, &candidates, &mut result.suppressed) {
+                    break;
                 }
-                let _ = CloseHandle(handle);
-            }
-        }
-        
-        // Skip our own Iris anticheat windows/processes (whitelist)
-        for whitelist_item in IRIS_WHITELIST {
-            if title.contains(whitelist_item) || process_name.contains(whitelist_item) {
-                return BOOL(1); // Skip - it's us!
+                // Avoid duplicates
+                if !detected.iter().any(|w| w.window_title.to_lowercase() == title) {
+                    detected.push(DetectedCheatWindow {
+                        window_title: title.clone(),
+                        process_name: process_name.clone(),
+                        matched_cheat: sig.name.clone(),
+                        risk_level: sig.risk.clone(),
+                    });
+                }
+                break;
             }
         }
-        
-        // Check against known cheat window keywords
-        for (keyword, cheat_name, risk) in CHEAT_WINDOW_KEYWORDS {
-            if title.contains(keyword) {
-                if let Ok(mut windows) = detected.lock() {
+
+        // Check process name against known cheat processes
+        if !process_name.is_empty() {
+            for (keyword, cheat_name, risk) in CHEAT_PROCESS_RULES.iter() {
+                if keyword.matches(&process_subject) {
+                    let candidates = [Subject::name(cheat_name), title_subject, process_subject];
+                    if allowed.suppress(WhitelistType::CheatWindow, &candidates, &mut result.suppressed) {
+                        break;
+                    }
                     // Avoid duplicates
-                    if !windows.iter().any(|w| w.window_title.to_lowercase() == title) {
-                        windows.push(DetectedCheatWindow {
+                    if !detected.iter().any(|w| w.process_name.to_lowercase() == process_name) {
+                        detected.push(DetectedCheatWindow {
                             window_title: title.clone(),
                             process_name: process_name.clone(),
                             matched_cheat: cheat_name.to_string(),
                             risk_level: risk.to_string(),
                         });
                     }
-                }
-                break;
-            }
-        }
-        
-        // Check process name against known cheat processes
-        if !process_name.is_empty() {
-            for (keyword, cheat_name, risk) in CHEAT_PROCESS_NAMES {
-                if process_name.contains(keyword) {
-                    if let Ok(mut windows) = detected.lock() {
-                        // Avoid duplicates
-                        if !windows.iter().any(|w| w.process_name.to_lowercase() == process_name) {
-                            windows.push(DetectedCheatWindow {
-                                window_title: title.clone(),
-                                process_name: process_name.clone(),
-                                matched_cheat: cheat_name.to_string(),
-                                risk_level: risk.to_string(),
-                            });
-                        }
-                    }
                     break;
                 }
             }
         }
-        
-        BOOL(1) // Continue enumeration
-    }
-    
-    unsafe {
-        let _ = EnumWindows(
-            Some(enum_window_proc),
-            LPARAM(&detected_windows as *const _ as isize)
-        );
     }
-    
-    // Get results from mutex
-    if let Ok(windows) = detected_windows.lock() {
-        result.detected_windows = windows.clone();
-        result.cheats_found = !windows.is_empty();
-        
-        // Calculate risk score
-        for window in windows.iter() {
-            match window.risk_level.as_str() {
-                "critical" => result.risk_score += 100,
-                "high" => result.risk_score += 75,
-                "medium" => result.risk_score += 40,
-                _ => result.risk_score += 25,
-            }
+
+    result.cheats_found = !detected.is_empty();
+
+    // Calculate risk score
+    for window in &detected {
+        match window.risk_level.as_str() {
+            "critical" => result.risk_score += 100,
+            "high" => result.risk_score += 75,
+            "medium" => result.risk_score += 40,
+            _ => result.risk_score += 25,
         }
     }
-    
+    result.detected_windows = detected;
+
     println!("[Hardware] Cheat Window Detection: found={}, count={}, score={}",
              result.cheats_found, result.detected_windows.len(), result.risk_score);
-    
-    result
-}
 
-#[cfg(not(target_os = "windows"))]
-pub fn detect_cheat_windows() -> CheatWindowDetectionResult {
-    CheatWindowDetectionResult::default()
+    result
 }
 
 // ====== SCREENSHOT CAPTURE ======
@@ -3672,99 +3241,127 @@ pub fn capture_all_screens_medium_quality() -> Vec<ScreenshotData> {
 
 // ====== PROCESS AND USB DEVICE LISTING (for scan mode) ======
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
 pub struct ProcessInfo {
     pub name: String,
     pub pid: u32,
-    pub path: Option<String>,
+    pub path: Option<String>, // Image file (the host file under Wine)
+    pub cmdline: Option<String>,
+    pub ppid: Option<u32>,
+    pub uid: Option<u32>,
+    pub start_time: Option<u64>, // ms since epoch
+    #[serde(default)]
+    pub wine: bool, // Windows image running under Wine/Proton (name is the Windows one)
+    #[serde(default)]
+    pub windows_path: Option<String>, // Wine: `C:\...` path of the image
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+impl ProcessInfo {
+    /// Path matched by rules and whitelists (the Windows one under Wine)
+    pub fn rule_path(&self) -> Option<&str> {
+        self.windows_path.as_deref().or(self.path.as_deref())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct UsbDeviceInfo {
     pub name: String,
     pub device_id: String,
     pub manufacturer: Option<String>,
+    pub serial: Option<String>,
+    #[serde(default)]
+    pub drivers: Vec<String>, // Kernel drivers bound to the interfaces (Linux)
+    #[serde(default)]
+    pub parent_id: Option<String>, // Device id of the hub (or of the device, for an interface)
+    #[serde(default)]
+    pub location: Option<String>, // Port path (sysfs name on Linux, location information on Windows)
 }
 
 /// Get list of all running processes
 #[cfg(target_os = "windows")]
-pub fn get_all_processes() -> Vec<ProcessInfo> {
+pub fn get_all_processes(wmi_con: &WMIConnection) -> Vec<ProcessInfo> {
     let mut processes = Vec::new();
-    
-    if let Ok(com_con) = COMLibrary::new() {
-        if let Ok(wmi_con) = WMIConnection::new(com_con) {
-            #[derive(Deserialize)]
-            #[allow(non_snake_case)]
-            struct Win32Process {
-                Name: Option<String>,
-                ProcessId: Option<u32>,
-                ExecutablePath: Option<String>,
-            }
-            
-            if let Ok(results) = wmi_con.raw_query::<Win32Process>(
-                "SELECT Name, ProcessId, ExecutablePath FROM Win32_Process"
-            ) {
-                for proc in results {
-                    let name = proc.Name.unwrap_or_default();
-                    if !name.is_empty() {
-                        processes.push(ProcessInfo {
-                            name,
-                            pid: proc.ProcessId.unwrap_or(0),
-                            path: proc.ExecutablePath,
-                        });
-                    }
-                }
+
+    #[derive(Deserialize)]
+    #[allow(non_snake_case)]
+    struct Win32Process {
+        Name: Option<String>,
+        ProcessId: Option<u32>,
+        ExecutablePath: Option<String>,
+    }
+
+    if let Ok(results) = wmi_con.raw_query::<Win32Process>(
+        "SELECT Name, ProcessId, ExecutablePath FROM Win32_Process"
+    ) {
+        for proc in results {
+            let name = proc.Name.unwrap_or_default();
+            if !name.is_empty() {
+                processes.push(ProcessInfo {
+                    name,
+                    pid: proc.ProcessId.unwrap_or(0),
+                    path: proc.ExecutablePath,
+                    ..Default::default()
+                });
             }
         }
     }
-    
-    println!("[Hardware] Found {} running processes", processes.len());
+
     processes
 }
 
-#[cfg(not(target_os = "windows"))]
+#[cfg(target_os = "linux")]
+pub fn get_all_processes() -> Vec<ProcessInfo> {
+    procfs::list_processes()
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
 pub fn get_all_processes() -> Vec<ProcessInfo> {
     Vec::new()
 }
 
 /// Get list of all USB devices
 #[cfg(target_os = "windows")]
-pub fn get_all_usb_devices() -> Vec<UsbDeviceInfo> {
+pub fn get_all_usb_devices(wmi_con: &WMIConnection) -> Vec<UsbDeviceInfo> {
     let mut devices = Vec::new();
-    
-    if let Ok(com_con) = COMLibrary::new() {
-        if let Ok(wmi_con) = WMIConnection::new(com_con) {
-            #[derive(Deserialize)]
-            #[allow(non_snake_case)]
-            struct Win32PnpDevice {
-                DeviceID: Option<String>,
-                Name: Option<String>,
-                Manufacturer: Option<String>,
-            }
-            
-            if let Ok(results) = wmi_con.raw_query::<Win32PnpDevice>(
-                "SELECT DeviceID, Name, Manufacturer FROM Win32_PnPEntity WHERE DeviceID LIKE 'USB%'"
-            ) {
-                for dev in results {
-                    let name = dev.Name.unwrap_or_default();
-                    let device_id = dev.DeviceID.unwrap_or_default();
-                    if !device_id.is_empty() {
-                        devices.push(UsbDeviceInfo {
-                            name,
-                            device_id,
-                            manufacturer: dev.Manufacturer,
-                        });
-                    }
-                }
+
+    #[derive(Deserialize)]
+    #[allow(non_snake_case)]
+    struct Win32PnpDevice {
+        DeviceID: Option<String>,
+        Name: Option<String>,
+        Manufacturer: Option<String>,
+    }
+
+    if let Ok(results) = wmi_con.raw_query::<Win32PnpDevice>(
+        "SELECT DeviceID, Name, Manufacturer FROM Win32_PnPEntity WHERE DeviceID LIKE 'USB%'"
+    ) {
+        for dev in results {
+            let name = dev.Name.unwrap_or_default();
+            let device_id = dev.DeviceID.unwrap_or_default();
+            if !device_id.is_empty() {
+                let (parent_id, location) = crate::usbtopology::device_topology(&device_id);
+                devices.push(UsbDeviceInfo {
+                    name,
+                    device_id,
+                    manufacturer: dev.Manufacturer,
+                    parent_id,
+                    location,
+                    ..Default::default()
+                });
             }
         }
     }
-    
-    println!("[Hardware] Found {} USB devices", devices.len());
+
     devices
 }
 
-#[cfg(not(target_os = "windows"))]
+#[cfg(target_os = "linux")]
+pub fn get_all_usb_devices() -> Vec<UsbDeviceInfo> {
+    sysfs::list_usb_devices()
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
 pub fn get_all_usb_devices() -> Vec<UsbDeviceInfo> {
     Vec::new()
 }
@@ -3773,121 +3370,65 @@ pub fn get_all_usb_devices() -> Vec<UsbDeviceInfo> {
 
 /// Detect if Call of Duty / Black Ops is running on this machine
 /// Used to prevent bypass attempts (running Iris on a different PC than the game)
-#[cfg(target_os = "windows")]
-pub fn detect_game_running() -> GameDetectionResult {
-    use windows::Win32::UI::WindowsAndMessaging::{
-        GetForegroundWindow, GetWindowThreadProcessId,
-    };
-    use windows::Win32::System::Threading::{
-        OpenProcess, PROCESS_QUERY_INFORMATION, PROCESS_VM_READ,
-    };
-    use windows::Win32::System::ProcessStatus::GetModuleBaseNameW;
-    use windows::Win32::Foundation::CloseHandle;
+pub fn detect_game_running(snapshot: &SystemSnapshot) -> GameDetectionResult {
     use std::time::{SystemTime, UNIX_EPOCH};
-    
+
     let mut result = GameDetectionResult::default();
-    let mut detected_game_process: Option<(String, u32)> = None;
-    
+
     // Scan all processes for game executables
-    if let Ok(com_con) = COMLibrary::new() {
-        if let Ok(wmi_con) = WMIConnection::new(com_con) {
-            #[derive(Deserialize)]
-            #[allow(non_snake_case)]
-            struct Win32Process {
-                Name: Option<String>,
-                ProcessId: Option<u32>,
+    for process in &snapshot.processes {
+        let proc_name = process.name.to_lowercase();
+
+        // Check against known game processes
+        for (game_exe, game_name) in GAME_PROCESSES {
+            if proc_name == *game_exe || proc_name.contains(game_exe) {
+                result.game_running = true;
+                result.game_name = Some(game_name.to_string());
+                result.game_pid = Some(process.pid);
+                println!("[Hardware] Game detected: {} (PID: {})", game_name, process.pid);
+                break;
             }
+        }
 
-            if let Ok(processes) = wmi_con.raw_query::<Win32Process>(
-                "SELECT Name, ProcessId FROM Win32_Process"
-            ) {
-                for process in processes {
-                    let proc_name = process.Name.clone().unwrap_or_default().to_lowercase();
-                    let proc_pid = process.ProcessId.unwrap_or(0);
-                    
-                    // Check against known game processes
-                    for (game_exe, game_name) in GAME_PROCESSES {
-                        if proc_name == *game_exe || proc_name.contains(game_exe) {
-                            detected_game_process = Some((game_name.to_string(), proc_pid));
-                            result.game_running = true;
-                            result.game_name = Some(game_name.to_string());
-                            result.game_pid = Some(proc_pid);
-                            println!("[Hardware] Game detected: {} (PID: {})", game_name, proc_pid);
-                            break;
-                        }
-                    }
-                    
-                    if result.game_running {
-                        break;
-                    }
-                }
-            }
+        if result.game_running {
+            break;
         }
     }
-    
+
     // Check if game window is currently focused (active)
-    if result.game_running {
-        unsafe {
-            let foreground_hwnd = GetForegroundWindow();
-            if foreground_hwnd.0 != 0 {
-                let mut foreground_pid: u32 = 0;
-                GetWindowThreadProcessId(foreground_hwnd, Some(&mut foreground_pid));
-                
-                // Check if foreground window belongs to the game process
-                if let Some((_, game_pid)) = &detected_game_process {
-                    if foreground_pid == *game_pid {
-                        result.game_window_active = true;
-                        println!("[Hardware] Game window is active/focused");
-                    } else {
-                        // Also check by process name in case PID changed
-                        if foreground_pid > 0 {
-                            if let Ok(handle) = OpenProcess(
-                                PROCESS_QUERY_INFORMATION | PROCESS_VM_READ,
-                                false,
-                                foreground_pid
-                            ) {
-                                let mut name_buf = [0u16; 256];
-                                let name_len = GetModuleBaseNameW(handle, None, &mut name_buf);
-                                if name_len > 0 {
-                                    let fg_name = String::from_utf16_lossy(&name_buf[..name_len as usize]).to_lowercase();
-                                    for (game_exe, _) in GAME_PROCESSES {
-                                        if fg_name == *game_exe || fg_name.contains(game_exe) {
-                                            result.game_window_active = true;
-                                            println!("[Hardware] Game window active (by name): {}", fg_name);
-                                            break;
-                                        }
-                                    }
-                                }
-                                let _ = CloseHandle(handle);
-                            }
-                        }
-                    }
+    if let (Some(game_pid), Some(foreground_pid)) = (result.game_pid, snapshot.foreground_pid) {
+        // Check if foreground window belongs to the game process
+        if foreground_pid == game_pid {
+            result.game_window_active = true;
+            println!("[Hardware] Game window is active/focused");
+        } else if let Some(foreground) = snapshot.process(foreground_pid) {
+            // Also check by process name in case PID changed
+            let fg_name = foreground.name.to_lowercase();
+            for (game_exe, _) in GAME_PROCESSES {
+                if fg_name == *game_exe || fg_name.contains(game_exe) {
+                    result.game_window_active = true;
+                    println!("[Hardware] Game window active (by name): {}", fg_name);
+                    break;
                 }
             }
         }
     }
-    
+
     // Set timestamp
     result.last_detected = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .map(|d| d.as_millis() as u64)
         .unwrap_or(0);
-    
-    println!("[Hardware] Game Detection: running={}, name={:?}, active={}", 
+
+    println!("[Hardware] Game Detection: running={}, name={:?}, active={}",
              result.game_running, result.game_name, result.game_window_active);
-    
-    result
-}
 
-#[cfg(not(target_os = "windows"))]
-pub fn detect_game_running() -> GameDetectionResult {
-    GameDetectionResult::default()
+    result
 }
 
 /// Detect game and track window activity over time
 /// Returns extended GameSessionActivity with activity percentage tracking
-#[cfg(target_os = "windows")]
-pub fn detect_game_with_activity() -> GameSessionActivity {
+pub fn detect_game_with_activity(snapshot: &SystemSnapshot) -> GameSessionActivity {
     use std::time::{SystemTime, UNIX_EPOCH};
     
     let now = SystemTime::now()
@@ -3896,7 +3437,7 @@ pub fn detect_game_with_activity() -> GameSessionActivity {
         .unwrap_or(0);
     
     // Get basic game detection
-    let basic = detect_game_running();
+    let basic = detect_game_running(snapshot);
     
     // Update session tracker
     let mut tracker = GAME_SESSION_TRACKER.lock().unwrap();
@@ -3960,11 +3501,6 @@ pub fn detect_game_with_activity() -> GameSessionActivity {
     result
 }
 
-#[cfg(not(target_os = "windows"))]
-pub fn detect_game_with_activity() -> GameSessionActivity {
-    GameSessionActivity::default()
-}
-
 /// Reset the game activity tracker (call when match ends or user wants to reset)
 pub fn reset_game_activity_tracker() {
     if let Ok(mut tracker) = GAME_SESSION_TRACKER.lock() {
@@ -3972,3 +3508,150 @@ pub fn reset_game_activity_tracker() {
         println!("[Hardware] Game activity tracker reset");
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hid::HidDeviceInfo;
+    use crate::snapshot::PlatformInfo;
+    use std::path::Path;
+
+    fn fixture(path: &str) -> std::path::PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(path)
+    }
+
+    fn process(pid: u32, name: &str) -> ProcessInfo {
+        ProcessInfo { name: name.to_string(), pid, ..Default::default() }
+    }
+
+    fn usb(name: &str, device_id: &str) -> UsbDeviceInfo {
+        UsbDeviceInfo { name: name.to_string(), device_id: device_id.to_string(), ..Default::default() }
+    }
+
+    /// HID interface profiled from a descriptor in tests/fixtures/hid
+    fn hid_device(vid: &str, pid: &str, name: &str, descriptor: &str) -> HidDeviceInfo {
+        let descriptor = std::fs::read(fixture("hid").join(descriptor)).unwrap();
+        HidDeviceInfo { device_key: format!("usb-{}:{}", vid, pid), vid: vid.to_string(), pid: pid.to_string(),
+                        name: name.to_string(), collections: hid::parse_descriptor(&descriptor), descriptor }
+    }
+
+    /// Platform of a `/sys/firmware/dmi/tables` dump in tests/fixtures/smbios
+    fn smbios_platform(name: &str) -> PlatformInfo {
+        let table = smbios::read_linux_table(&fixture("smbios").join(name)).unwrap();
+        PlatformInfo { smbios: Some(smbios::decode(&table).unwrap()), ..Default::default() }
+    }
+
+    #[test]
+    fn clean_snapshot_has_no_cheats() {
+        let snapshot = SystemSnapshot {
+            processes: vec![process(1, "explorer.exe"), process(2, "steam.exe")],
+            usb_devices: vec![usb("USB Keyboard", "USB\\VID_046D&PID_C31C\\5&1A2B3C&0&1")],
+            hid_devices: vec![hid_device("046d", "c08b", "G502 HERO", "g502-hero-if0.bin"),
+                              hid_device("046d", "c08b", "G502 HERO", "g502-hero-if1.bin")],
+            ..Default::default()
+        };
+        let detection = detect_cheats(&snapshot);
+        assert!(!detection.found);
+        assert!(detection.devices.is_empty() && detection.processes.is_empty() && detection.warnings.is_empty());
+        assert_eq!((detection.risk_score, detection.risk_level), (0, RiskLevel::Low));
+    }
+
+    #[test]
+    fn detects_cheat_devices_injectors_and_processes() {
+        let snapshot = SystemSnapshot {
+            processes: vec![process(1, "explorer.exe"), process(40, "DS4Windows.exe")],
+            usb_devices: vec![usb("Cronus Zen", "USB\\VID_2341&PID_8036\\6&2F1E&0&2")],
+            hid_devices: vec![hid_device("046d", "c08b", "Gaming Mouse", "arduino-leonardo.bin")],
+            ..Default::default()
+        };
+        let detection = detect_cheats(&snapshot);
+        assert!(detection.found);
+
+        let devices: Vec<(&str, Option<&str>)> = detection.devices.iter()
+            .map(|d| (d.name.as_str(), d.pid.as_deref()))
+            .collect();
+        assert_eq!(devices, vec![("Cronus Zen", Some("8036")), ("Gaming Mouse", Some("c08b"))]);
+        assert_eq!(detection.devices[0].device_type, "Cronus Zen");
+        assert!(detection.devices[1].device_type.starts_with("HID injector: Keyboard descriptor of the Arduino HID library"));
+        assert!(detection.devices[1].hid_fingerprint.is_some());
+
+        assert_eq!(detection.processes.len(), 1);
+        assert_eq!((detection.processes[0].pid, detection.processes[0].matched_cheat.as_str()), (40, "prefix:ds4window"));
+        // Cronus 100, HID injector capped at 100, process 75
+        assert_eq!((detection.risk_score, detection.risk_level), (275, RiskLevel::Critical));
+    }
+
+    #[test]
+    fn weak_hid_indicators_are_warnings() {
+        let snapshot = SystemSnapshot {
+            hid_devices: vec![hid_device("2341", "8037", "Leonardo", "g502-hero-if1.bin")],
+            ..Default::default()
+        };
+        let detection = detect_cheats(&snapshot);
+        assert!(!detection.found);
+        assert_eq!(detection.warnings, vec!["HID device Leonardo (2341:8037): Arduino board exposing a mouse or keyboard"]);
+    }
+
+    #[test]
+    fn bare_metal_is_not_a_vm_or_cloud_pc() {
+        let snapshot = SystemSnapshot {
+            platform: PlatformInfo { manufacturer: "ASUS".to_string(), model: "ROG STRIX B550-F".to_string(),
+                                     gpus: vec!["NVIDIA GeForce RTX 4070".to_string()], ..smbios_platform("bare-metal") },
+            ..Default::default()
+        };
+        let vm = detect_vm(&snapshot);
+        assert!(!vm.vm_detected, "{:?}", vm.vm_indicators);
+        assert_eq!(vm.risk_score, 0);
+        let cloud = detect_cloud_pc(&snapshot);
+        assert!(!cloud.cloud_pc_detected, "{:?}", cloud.cloud_indicators);
+        assert_eq!(cloud.risk_score, 0);
+    }
+
+    #[test]
+    fn smbios_hints_name_the_hypervisor() {
+        // WMI strings empty: the raw table alone identifies the platform
+        let snapshot = SystemSnapshot { platform: smbios_platform("qemu"), ..Default::default() };
+        let vm = detect_vm(&snapshot);
+        assert!(vm.vm_detected);
+        assert_eq!(vm.vm_type.as_deref(), Some("QEMU/KVM"));
+        assert_eq!(vm.risk_score, 90);
+        assert!(!detect_cloud_pc(&snapshot).cloud_pc_detected);
+
+        // WMI names VMware first; the SMBIOS hints add indicators, not score
+        let snapshot = SystemSnapshot {
+            platform: PlatformInfo { manufacturer: "VMware, Inc.".to_string(), model: "VMware20,1".to_string(),
+                                     ..smbios_platform("vmware") },
+            processes: vec![process(7, "vmtoolsd.exe")],
+            ..Default::default()
+        };
+        let vm = detect_vm(&snapshot);
+        assert_eq!(vm.vm_type.as_deref(), Some("VMware"));
+        assert_eq!(vm.risk_score, 100);
+        assert_eq!(vm.vm_indicators, vec![
+            "WMI: VMware computer system",
+            "SMBIOS system manufacturer: VMware, Inc.",
+            "SMBIOS BIOS characteristics: virtual machine",
+            "Process: vmtoolsd.exe (VMware)",
+        ]);
+    }
+
+    #[test]
+    fn smbios_hints_name_the_cloud_provider() {
+        let snapshot = SystemSnapshot { platform: smbios_platform("shadow"), ..Default::default() };
+        let cloud = detect_cloud_pc(&snapshot);
+        assert!(cloud.cloud_pc_detected && cloud.is_gaming_cloud);
+        assert_eq!(cloud.cloud_provider.as_deref(), Some("Shadow"));
+        assert_eq!(cloud.risk_score, 80);
+
+        // A gaming client outranks a remote desktop tool, a datacenter GPU adds to the score
+        let snapshot = SystemSnapshot {
+            processes: vec![process(3, "AnyDesk.exe"), process(4, "parsecd.exe")],
+            platform: PlatformInfo { gpus: vec!["NVIDIA Tesla T4".to_string()], ..Default::default() },
+            ..Default::default()
+        };
+        let cloud = detect_cloud_pc(&snapshot);
+        assert_eq!(cloud.cloud_provider.as_deref(), Some("Parsec"));
+        assert!(cloud.is_gaming_cloud);
+        assert_eq!(cloud.risk_score, 40 + 70 + 60);
+    }
+}
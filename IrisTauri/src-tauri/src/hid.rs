@@ -0,0 +1,729 @@
+//! HID module - report descriptor fingerprinting of mice and keyboards
+//!
+//! VID/PID rules miss injectors (Arduino Leonardo, KMBox, Teensy, CH9329 bridges)
+//! reflashed with the ids of a retail mouse. Their report descriptors give them
+//! away: the Arduino HID library puts a report-id 1 mouse (8-bit relative X/Y,
+//! -127..127) and a report-id 2 keyboard (key array up to 0x73) in one descriptor,
+//! while gaming mice report 12/16-bit axes and carry vendor-defined collections.
+//! The 8-bit report-id 1 mouse alone also describes plain office mice, so it and the
+//! board vendor are weak indicators that only count together.
+//!
+//! Each top-level collection is reduced to a profile (usage, report ids, buttons,
+//! axis size and range, key range). Linux reads raw descriptors from hidraw sysfs;
+//! Windows rebuilds the same profile from HidP_* capabilities (one device interface
+//! per collection). The fingerprint hashes the profiles, so it is platform-neutral.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+const PAGE_GENERIC_DESKTOP: u16 = 0x01;
+const PAGE_KEYBOARD: u16 = 0x07;
+const PAGE_BUTTON: u16 = 0x09;
+const PAGE_VENDOR_MIN: u16 = 0xff00;
+const USAGE_MOUSE: u16 = 0x02;
+const USAGE_KEYBOARD: u16 = 0x06;
+const USAGE_X: u16 = 0x30;
+const USAGE_Y: u16 = 0x31;
+
+/// Vendors of microcontroller boards and serial-to-HID bridges
+const MICROCONTROLLER_VENDORS: &[(&str, &str)] = &[
+    ("2341", "Arduino"),
+    ("2a03", "Arduino"),
+    ("1b4f", "SparkFun"),
+    ("239a", "Adafruit"),
+    ("16c0", "Teensy / V-USB"),
+    ("2e8a", "Raspberry Pi"),
+    ("1a86", "WCH (CH9329 / CH55x)"),
+    ("303a", "Espressif"),
+];
+
+/// Gaming peripheral vendors whose ids injectors borrow
+const GAMING_VENDORS: &[(&str, &str)] = &[
+    ("046d", "Logitech"),
+    ("1532", "Razer"),
+    ("1038", "SteelSeries"),
+    ("1b1c", "Corsair"),
+    ("1e7d", "ROCCAT"),
+];
+
+/// Genuine mice: (vid, pid, name, minimum X/Y bits, has vendor-defined collections)
+const GENUINE_MICE: &[(&str, &str, &str, u16, bool)] = &[
+    ("046d", "c08b", "Logitech G502 HERO", 12, true),
+    ("046d", "c07d", "Logitech G502 Proteus Core", 12, true),
+    ("046d", "c332", "Logitech G502 Proteus Spectrum", 12, true),
+    ("046d", "c084", "Logitech G203 Prodigy", 12, true),
+    ("046d", "c092", "Logitech G203 Lightsync", 12, true),
+    ("046d", "c094", "Logitech PRO X Superlight", 12, true),
+    ("046d", "c539", "Logitech Lightspeed receiver", 12, true),
+    ("046d", "c53f", "Logitech Lightspeed receiver", 12, true),
+    ("046d", "c547", "Logitech Lightspeed receiver", 12, true),
+    ("1532", "0084", "Razer DeathAdder V2", 12, false),
+];
+
+/// Top-level collection of a report descriptor
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct HidCollection {
+    pub usage_page: u16,
+    pub usage: u16,
+    pub report_ids: Vec<u8>,
+    pub buttons: u32,
+    pub axis_bits: u16, // Report size of X/Y (0 without pointer axes)
+    pub relative: bool,
+    pub axis_min: i32,
+    pub axis_max: i32,
+    pub key_max: i32, // Highest key code of a keyboard array (0 without one)
+}
+
+impl HidCollection {
+    pub fn is_mouse(&self) -> bool {
+        self.usage_page == PAGE_GENERIC_DESKTOP && self.usage == USAGE_MOUSE
+    }
+
+    pub fn is_keyboard(&self) -> bool {
+        self.usage_page == PAGE_GENERIC_DESKTOP && self.usage == USAGE_KEYBOARD
+    }
+
+    pub fn is_vendor(&self) -> bool {
+        self.usage_page >= PAGE_VENDOR_MIN
+    }
+
+    /// Canonical text form (input of the fingerprint)
+    pub fn profile(&self) -> String {
+        format!("{:04x}:{:04x} ids={:?} buttons={} axes={}{}[{},{}] keys={}",
+                self.usage_page, self.usage, self.report_ids, self.buttons, self.axis_bits,
+                if self.relative { "r" } else { "a" }, self.axis_min, self.axis_max, self.key_max)
+    }
+}
+
+/// One HID interface as enumerated by the OS
+#[derive(Debug, Clone, Default)]
+pub struct HidDeviceInfo {
+    pub device_key: String, // Same for every interface of one physical device
+    pub vid: String,
+    pub pid: String,
+    pub name: String,
+    pub descriptor: Vec<u8>, // Raw report descriptor (Linux)
+    pub collections: Vec<HidCollection>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum HidIndicatorKind {
+    InjectorDescriptor, // Keyboard descriptor of the Arduino HID library
+    DescriptorMismatch, // Descriptor does not fit the claimed VID/PID
+    Microcontroller,    // Mouse / keyboard from a microcontroller or bridge vendor (weak)
+    GenericComposite,   // Mouse and keyboard with generic descriptors on one device (weak)
+    BasicMouse,         // Report-1 8-bit mouse of the Arduino library, and of many office mice (weak)
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HidIndicator {
+    pub kind: HidIndicatorKind,
+    pub detail: String,
+}
+
+impl HidIndicator {
+    /// Risk points of the indicator
+    pub fn weight(&self) -> u32 {
+        match self.kind {
+            HidIndicatorKind::InjectorDescriptor => 100,
+            HidIndicatorKind::DescriptorMismatch => 80,
+            HidIndicatorKind::Microcontroller => 30,
+            HidIndicatorKind::GenericComposite => 30,
+            HidIndicatorKind::BasicMouse => 30,
+        }
+    }
+}
+
+/// Verdict for one physical HID device (all of its interfaces)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HidAnalysis {
+    pub name: String,
+    pub vid: String,
+    pub pid: String,
+    pub fingerprint: String,
+    pub descriptor_sha256: Option<String>, // Raw descriptors (Linux only)
+    pub indicators: Vec<HidIndicator>,
+    pub risk_score: u32,
+    pub injector: bool, // Enough evidence for a detection; otherwise the indicators are informational
+}
+
+/// Analyze every physical device of a HID inventory, returning the suspicious ones
+pub fn analyze(devices: &[HidDeviceInfo]) -> Vec<HidAnalysis> {
+    let mut keys: Vec<&str> = devices.iter().map(|d| d.device_key.as_str()).collect();
+    keys.sort();
+    keys.dedup();
+
+    keys.into_iter()
+        .filter_map(|key| {
+            let interfaces: Vec<&HidDeviceInfo> = devices.iter().filter(|d| d.device_key == key).collect();
+            analyze_device(&interfaces)
+        })
+        .collect()
+}
+
+/// Indicators of the interfaces of one physical device (None when nothing is wrong)
+pub fn analyze_device(interfaces: &[&HidDeviceInfo]) -> Option<HidAnalysis> {
+    let first = interfaces.first()?;
+    let collections: Vec<&HidCollection> = interfaces.iter().flat_map(|i| i.collections.iter()).collect();
+    let mice: Vec<&&HidCollection> = collections.iter().filter(|c| c.is_mouse()).collect();
+    let keyboards: Vec<&&HidCollection> = collections.iter().filter(|c| c.is_keyboard()).collect();
+    if mice.is_empty() && keyboards.is_empty() {
+        return None;
+    }
+    let has_vendor = collections.iter().any(|c| c.is_vendor());
+    let (vid, pid) = (first.vid.to_lowercase(), first.pid.to_lowercase());
+
+    let mut indicators = Vec::new();
+    let mut push = |kind: HidIndicatorKind, detail: String| indicators.push(HidIndicator { kind, detail });
+
+    // Arduino HID library: report-id 2 keyboard with keys up to 0x73, report-id 1 8-bit mouse
+    if keyboards.iter().any(|k| k.report_ids == [2] && k.key_max == 0x73) {
+        push(HidIndicatorKind::InjectorDescriptor, "Keyboard descriptor of the Arduino HID library (report 2, keys up to 0x73)".to_string());
+    } else if mice.iter().any(|m| m.report_ids == [1] && m.axis_bits == 8 && m.relative && m.axis_min == -127 && m.axis_max == 127 && m.buttons <= 5) {
+        push(HidIndicatorKind::BasicMouse, "Mouse descriptor like the Arduino HID library (report 1, 8-bit axes)".to_string());
+    }
+
+    if let Some((_, vendor)) = MICROCONTROLLER_VENDORS.iter().find(|(v, _)| *v == vid) {
+        push(HidIndicatorKind::Microcontroller, format!("{} board exposing a mouse or keyboard", vendor));
+    }
+
+    let generic_mouse = |m: &&&HidCollection| m.axis_bits <= 8;
+    match GENUINE_MICE.iter().find(|(v, p, _, _, _)| *v == vid && *p == pid) {
+        Some((_, _, name, min_bits, vendor_collections)) => {
+            if let Some(mouse) = mice.iter().find(|m| m.axis_bits < *min_bits) {
+                push(HidIndicatorKind::DescriptorMismatch,
+                     format!("{} reports {}-bit axes, genuine ones at least {}", name, mouse.axis_bits, min_bits));
+            }
+            if *vendor_collections && !has_vendor {
+                push(HidIndicatorKind::DescriptorMismatch, format!("{} without its vendor-defined collections", name));
+            }
+        }
+        None => {
+            // Unknown product of a gaming vendor: only a generic composite is suspicious
+            if let Some((_, vendor)) = GAMING_VENDORS.iter().find(|(v, _)| *v == vid) {
+                if !mice.is_empty() && !keyboards.is_empty() && !has_vendor && mice.iter().all(generic_mouse) {
+                    push(HidIndicatorKind::DescriptorMismatch, format!("Claims {} but only has generic descriptors", vendor));
+                }
+            }
+        }
+    }
+
+    if !mice.is_empty() && !keyboards.is_empty() && !has_vendor && mice.iter().all(generic_mouse) {
+        push(HidIndicatorKind::GenericComposite, "Mouse and keyboard with generic descriptors on one device".to_string());
+    }
+
+    if indicators.is_empty() {
+        return None;
+    }
+    let risk_score = indicators.iter().map(|i| i.weight()).sum::<u32>().min(100);
+    Some(HidAnalysis {
+        name: first.name.clone(),
+        vid,
+        pid,
+        fingerprint: fingerprint(&collections),
+        descriptor_sha256: descriptor_sha256(interfaces),
+        injector: is_injector(&indicators),
+        indicators,
+        risk_score,
+    })
+}
+
+/// A descriptor that gives the device away, or a microcontroller board backed by a
+/// generic descriptor. Generic descriptors alone also describe office mice and
+/// keyboard/mouse combos, and a board vendor alone describes hobby keyboards.
+fn is_injector(indicators: &[HidIndicator]) -> bool {
+    let has = |kind: HidIndicatorKind| indicators.iter().any(|i| i.kind == kind);
+    has(HidIndicatorKind::InjectorDescriptor)
+        || has(HidIndicatorKind::DescriptorMismatch)
+        || (has(HidIndicatorKind::Microcontroller) && (has(HidIndicatorKind::GenericComposite) || has(HidIndicatorKind::BasicMouse)))
+}
+
+/// SHA-256 of the sorted collection profiles
+pub fn fingerprint(collections: &[&HidCollection]) -> String {
+    let mut profiles: Vec<String> = collections.iter().map(|c| c.profile()).collect();
+    profiles.sort();
+    hex::encode(Sha256::digest(profiles.join("\n")))
+}
+
+/// SHA-256 of the raw descriptors of all interfaces (None without raw descriptors)
+fn descriptor_sha256(interfaces: &[&HidDeviceInfo]) -> Option<String> {
+    let mut descriptors: Vec<&[u8]> = interfaces.iter().map(|i| i.descriptor.as_slice()).filter(|d| !d.is_empty()).collect();
+    if descriptors.is_empty() {
+        return None;
+    }
+    descriptors.sort();
+    Some(hex::encode(Sha256::digest(descriptors.concat())))
+}
+
+/// Top-level application collections of a raw report descriptor
+pub fn parse_descriptor(descriptor: &[u8]) -> Vec<HidCollection> {
+    let mut collections: Vec<HidCollection> = Vec::new();
+    // Global state
+    let (mut usage_page, mut report_size, mut report_count, mut report_id) = (0u16, 0u32, 0u32, 0u8);
+    let (mut logical_min, mut logical_max) = (0i32, 0i32);
+    // Local state (cleared by every main item)
+    let mut usages: Vec<(u16, u16)> = Vec::new();
+    let (mut usage_min, mut usage_max) = (0u32, 0u32);
+    let mut depth = 0usize;
+
+    let mut offset = 0;
+    while offset < descriptor.len() {
+        let prefix = descriptor[offset];
+        if prefix == 0xfe {
+            // Long item: size, tag, data
+            let size = descriptor.get(offset + 1).copied().unwrap_or(0) as usize;
+            offset += 3 + size;
+            continue;
+        }
+        let size = match prefix & 0x03 { 3 => 4, n => n as usize };
+        let data = match descriptor.get(offset + 1..offset + 1 + size) {
+            Some(data) => data,
+            None => break,
+        };
+        offset += 1 + size;
+
+        let unsigned = data.iter().rev().fold(0u32, |v, b| (v << 8) | *b as u32);
+        let signed = match size {
+            1 => data[0] as i8 as i32,
+            2 => i16::from_le_bytes([data[0], data[1]]) as i32,
+            4 => unsigned as i32,
+            _ => 0,
+        };
+
+        match prefix & 0xfc {
+            // Main items
+            0xa0 => {
+                // Collection: an application collection at the top starts a new profile
+                if depth == 0 && unsigned == 0x01 {
+                    let usage = usages.first().copied().unwrap_or((usage_page, 0));
+                    collections.push(HidCollection { usage_page: usage.0, usage: usage.1, ..Default::default() });
+                }
+                depth += 1;
+                usages.clear();
+            }
+            0xc0 => {
+                depth = depth.saturating_sub(1);
+                usages.clear();
+            }
+            0x80 => {
+                if let Some(collection) = collections.last_mut().filter(|_| depth > 0) {
+                    if report_id != 0 && !collection.report_ids.contains(&report_id) {
+                        collection.report_ids.push(report_id);
+                    }
+                    let constant = unsigned & 0x01 != 0;
+                    let variable = unsigned & 0x02 != 0;
+                    let page = usages.first().map(|u| u.0).unwrap_or(usage_page);
+                    if !constant && page == PAGE_BUTTON {
+                        collection.buttons += if variable { report_count } else { usage_max.saturating_sub(usage_min) + 1 };
+                    }
+                    if !constant && usages.iter().any(|u| *u == (PAGE_GENERIC_DESKTOP, USAGE_X) || *u == (PAGE_GENERIC_DESKTOP, USAGE_Y)) {
+                        collection.axis_bits = collection.axis_bits.max(report_size as u16);
+                        collection.relative = unsigned & 0x04 != 0;
+                        collection.axis_min = logical_min;
+                        collection.axis_max = logical_max;
+                    }
+                    if !constant && !variable && page == PAGE_KEYBOARD {
+                        collection.key_max = collection.key_max.max(logical_max.max(usage_max as i32));
+                    }
+                }
+                usages.clear();
+                usage_min = 0;
+                usage_max = 0;
+            }
+            0x90 | 0xb0 => {
+                usages.clear();
+                usage_min = 0;
+                usage_max = 0;
+            }
+            // Global items
+            0x04 => usage_page = unsigned as u16,
+            0x14 => logical_min = signed,
+            0x24 => logical_max = signed,
+            0x74 => report_size = unsigned,
+            0x84 => report_id = unsigned as u8,
+            0x94 => report_count = unsigned,
+            // Local items (4-byte usages carry their own page)
+            0x08 => usages.push(if size == 4 { ((unsigned >> 16) as u16, unsigned as u16) } else { (usage_page, unsigned as u16) }),
+            0x18 => {
+                usage_min = unsigned & 0xffff;
+                if usages.is_empty() {
+                    usages.push((usage_page, usage_min as u16));
+                }
+            }
+            0x28 => usage_max = unsigned & 0xffff,
+            _ => {}
+        }
+    }
+    collections
+}
+
+/// HID interfaces of the live system
+#[cfg(target_os = "windows")]
+pub fn list_devices() -> Vec<HidDeviceInfo> {
+    use windows::core::PCWSTR;
+    use windows::Win32::Devices::DeviceAndDriverInstallation::{
+        SetupDiDestroyDeviceInfoList, SetupDiEnumDeviceInterfaces, SetupDiGetClassDevsW,
+        SetupDiGetDeviceInterfaceDetailW, DIGCF_DEVICEINTERFACE, DIGCF_PRESENT, SP_DEVICE_INTERFACE_DATA,
+        SP_DEVICE_INTERFACE_DETAIL_DATA_W,
+    };
+    use windows::Win32::Foundation::{CloseHandle, HANDLE, HWND};
+    use windows::Win32::Storage::FileSystem::{
+        CreateFileW, FILE_FLAGS_AND_ATTRIBUTES, FILE_SHARE_READ, FILE_SHARE_WRITE, OPEN_EXISTING,
+    };
+    use windows::Win32::Devices::HumanInterfaceDevice::HidD_GetHidGuid;
+
+    let mut devices = Vec::new();
+    unsafe {
+        let guid = HidD_GetHidGuid();
+        let set = match SetupDiGetClassDevsW(Some(&guid), PCWSTR::null(), HWND::default(), DIGCF_PRESENT | DIGCF_DEVICEINTERFACE) {
+            Ok(set) => set,
+            Err(e) => {
+                println!("[HID] SetupDiGetClassDevs failed: {}", e);
+                return devices;
+            }
+        };
+
+        let mut index = 0;
+        loop {
+            let mut interface = SP_DEVICE_INTERFACE_DATA { cbSize: std::mem::size_of::<SP_DEVICE_INTERFACE_DATA>() as u32, ..Default::default() };
+            if SetupDiEnumDeviceInterfaces(set, None, &guid, index, &mut interface).is_err() {
+                break;
+            }
+            index += 1;
+
+            // SP_DEVICE_INTERFACE_DETAIL_DATA_W: cbSize, then the NUL-terminated path
+            let mut buffer = vec![0u64; 256];
+            let detail = buffer.as_mut_ptr() as *mut SP_DEVICE_INTERFACE_DETAIL_DATA_W;
+            (*detail).cbSize = std::mem::size_of::<SP_DEVICE_INTERFACE_DETAIL_DATA_W>() as u32;
+            if SetupDiGetDeviceInterfaceDetailW(set, &interface, Some(detail), (buffer.len() * 8) as u32, None, None).is_err() {
+                continue;
+            }
+            let path_ptr = std::ptr::addr_of!((*detail).DevicePath) as *const u16;
+
+            // No read/write access needed for attributes and capabilities (works on mice and keyboards)
+            let handle = match CreateFileW(
+                PCWSTR(path_ptr),
+                0,
+                FILE_SHARE_READ | FILE_SHARE_WRITE,
+                None,
+                OPEN_EXISTING,
+                FILE_FLAGS_AND_ATTRIBUTES(0),
+                HANDLE::default(),
+            ) {
+                Ok(handle) => handle,
+                Err(_) => continue,
+            };
+            if let Some(device) = query_interface(handle) {
+                devices.push(device);
+            }
+            let _ = CloseHandle(handle);
+        }
+
+        let _ = SetupDiDestroyDeviceInfoList(set);
+    }
+
+    devices
+}
+
+/// Attributes and capabilities of an open HID interface
+#[cfg(target_os = "windows")]
+unsafe fn query_interface(handle: windows::Win32::Foundation::HANDLE) -> Option<HidDeviceInfo> {
+    use windows::Win32::Devices::HumanInterfaceDevice::{
+        HidD_FreePreparsedData, HidD_GetAttributes, HidD_GetPreparsedData, HidD_GetProductString,
+        HidP_GetButtonCaps, HidP_GetCaps, HidP_GetValueCaps, HidP_Input, HIDD_ATTRIBUTES, HIDP_BUTTON_CAPS,
+        HIDP_CAPS, HIDP_STATUS_SUCCESS, HIDP_VALUE_CAPS, PHIDP_PREPARSED_DATA,
+    };
+
+    let mut attributes = HIDD_ATTRIBUTES { Size: std::mem::size_of::<HIDD_ATTRIBUTES>() as u32, ..Default::default() };
+    if !HidD_GetAttributes(handle, &mut attributes).as_bool() {
+        return None;
+    }
+
+    let mut name_buf = [0u16; 128];
+    let name = if HidD_GetProductString(handle, name_buf.as_mut_ptr() as *mut _, (name_buf.len() * 2) as u32).as_bool() {
+        let len = name_buf.iter().position(|c| *c == 0).unwrap_or(name_buf.len());
+        String::from_utf16_lossy(&name_buf[..len])
+    } else {
+        String::new()
+    };
+
+    let mut preparsed = PHIDP_PREPARSED_DATA::default();
+    if !HidD_GetPreparsedData(handle, &mut preparsed).as_bool() {
+        return None;
+    }
+    let mut caps = HIDP_CAPS::default();
+    let mut collection = None;
+    if HidP_GetCaps(preparsed, &mut caps) == HIDP_STATUS_SUCCESS {
+        let mut profile = HidCollection { usage_page: caps.UsagePage, usage: caps.Usage, ..Default::default() };
+
+        let mut count = caps.NumberInputButtonCaps;
+        let mut buttons = vec![HIDP_BUTTON_CAPS::default(); count as usize];
+        if count > 0 && HidP_GetButtonCaps(HidP_Input, buttons.as_mut_ptr(), &mut count, preparsed) == HIDP_STATUS_SUCCESS {
+            for button in buttons.iter().take(count as usize) {
+                let (usage_min, usage_max) = if button.IsRange.as_bool() {
+                    (button.Anonymous.Range.UsageMin, button.Anonymous.Range.UsageMax)
+                } else {
+                    (button.Anonymous.NotRange.Usage, button.Anonymous.NotRange.Usage)
+                };
+                // BitField holds the flags of the input main item (bit 1: variable)
+                profile.add_button_caps(&ButtonCaps {
+                    report_id: button.ReportID,
+                    usage_page: button.UsagePage,
+                    usage_min,
+                    usage_max,
+                    variable: button.BitField & 0x02 != 0,
+                });
+            }
+        }
+
+        let mut count = caps.NumberInputValueCaps;
+        let mut values = vec![HIDP_VALUE_CAPS::default(); count as usize];
+        if count > 0 && HidP_GetValueCaps(HidP_Input, values.as_mut_ptr(), &mut count, preparsed) == HIDP_STATUS_SUCCESS {
+            for value in values.iter().take(count as usize) {
+                profile.add_value_caps(&ValueCaps {
+                    report_id: value.ReportID,
+                    usage_page: value.UsagePage,
+                    usage: if value.IsRange.as_bool() { value.Anonymous.Range.UsageMin } else { value.Anonymous.NotRange.Usage },
+                    bit_size: value.BitSize,
+                    relative: !value.IsAbsolute.as_bool(),
+                    logical_min: value.LogicalMin,
+                    logical_max: value.LogicalMax,
+                });
+            }
+        }
+        collection = Some(profile);
+    }
+    HidD_FreePreparsedData(preparsed);
+
+    let vid = format!("{:04x}", attributes.VendorID);
+    let pid = format!("{:04x}", attributes.ProductID);
+    Some(HidDeviceInfo {
+        device_key: format!("{}:{}", vid, pid),
+        name: if name.is_empty() { format!("HID device {}:{}", vid, pid) } else { name },
+        vid,
+        pid,
+        descriptor: Vec::new(),
+        collections: collection.into_iter().collect(),
+    })
+}
+
+/// Input button capability of a collection, as HidP_GetButtonCaps reports it
+#[cfg(any(target_os = "windows", test))]
+struct ButtonCaps {
+    report_id: u8,
+    usage_page: u16,
+    usage_min: u16,
+    usage_max: u16, // usage_min for a single usage
+    variable: bool, // Bitmap like the keyboard modifiers, not an array
+}
+
+/// Input value capability of a collection, as HidP_GetValueCaps reports it
+#[cfg(any(target_os = "windows", test))]
+struct ValueCaps {
+    report_id: u8,
+    usage_page: u16,
+    usage: u16,
+    bit_size: u16,
+    relative: bool,
+    logical_min: i32,
+    logical_max: i32,
+}
+
+/// Profile from capabilities, counted like the main items in parse_descriptor
+#[cfg(any(target_os = "windows", test))]
+impl HidCollection {
+    fn add_button_caps(&mut self, caps: &ButtonCaps) {
+        self.add_report_id(caps.report_id);
+        if caps.usage_page == PAGE_BUTTON {
+            self.buttons += caps.usage_max.saturating_sub(caps.usage_min) as u32 + 1;
+        }
+        if caps.usage_page == PAGE_KEYBOARD && !caps.variable {
+            self.key_max = self.key_max.max(caps.usage_max as i32);
+        }
+    }
+
+    fn add_value_caps(&mut self, caps: &ValueCaps) {
+        self.add_report_id(caps.report_id);
+        if caps.usage_page == PAGE_GENERIC_DESKTOP && (caps.usage == USAGE_X || caps.usage == USAGE_Y) {
+            self.axis_bits = self.axis_bits.max(caps.bit_size);
+            self.relative = caps.relative;
+            self.axis_min = caps.logical_min;
+            self.axis_max = caps.logical_max;
+        }
+    }
+
+    fn add_report_id(&mut self, report_id: u8) {
+        if report_id != 0 && !self.report_ids.contains(&report_id) {
+            self.report_ids.push(report_id);
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub fn list_devices() -> Vec<HidDeviceInfo> {
+    crate::sysfs::list_hid_devices()
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+pub fn list_devices() -> Vec<HidDeviceInfo> {
+    Vec::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn mouse(report_id: u8, bits: u16) -> HidCollection {
+        let max = (1i32 << (bits - 1)) - 1;
+        HidCollection { usage_page: PAGE_GENERIC_DESKTOP, usage: USAGE_MOUSE, report_ids: vec![report_id],
+                        buttons: 5, axis_bits: bits, relative: true, axis_min: -max, axis_max: max, key_max: 0 }
+    }
+
+    fn keyboard(report_id: u8, key_max: i32) -> HidCollection {
+        HidCollection { usage_page: PAGE_GENERIC_DESKTOP, usage: USAGE_KEYBOARD, report_ids: vec![report_id],
+                        key_max, ..Default::default() }
+    }
+
+    fn device(vid: &str, pid: &str, collections: Vec<HidCollection>) -> HidDeviceInfo {
+        HidDeviceInfo { device_key: format!("{}:{}", vid, pid), vid: vid.to_string(), pid: pid.to_string(),
+                        name: "Test device".to_string(), descriptor: Vec::new(), collections }
+    }
+
+    fn fixture(name: &str) -> Vec<u8> {
+        std::fs::read(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/hid").join(name)).unwrap()
+    }
+
+    /// Interface of one physical device, profiled from its raw descriptor
+    fn interface(vid: &str, pid: &str, descriptor: Vec<u8>) -> HidDeviceInfo {
+        HidDeviceInfo { collections: parse_descriptor(&descriptor), descriptor, device_key: "usb-0000:00:14.0-1".to_string(),
+                        ..device(vid, pid, Vec::new()) }
+    }
+
+    fn kinds(analysis: &HidAnalysis) -> Vec<HidIndicatorKind> {
+        analysis.indicators.iter().map(|i| i.kind).collect()
+    }
+
+    #[test]
+    fn office_mouse_is_informational() {
+        let office = device("413c", "301a", vec![mouse(1, 8)]);
+        let analysis = analyze_device(&[&office]).unwrap();
+        assert_eq!(kinds(&analysis), vec![HidIndicatorKind::BasicMouse]);
+        assert!(!analysis.injector);
+    }
+
+    #[test]
+    fn generic_combo_is_informational() {
+        let combo = device("04f2", "0939", vec![keyboard(1, 0xff), mouse(2, 8)]);
+        let analysis = analyze_device(&[&combo]).unwrap();
+        assert_eq!(kinds(&analysis), vec![HidIndicatorKind::GenericComposite]);
+        assert!(!analysis.injector);
+    }
+
+    #[test]
+    fn hobby_keyboard_is_informational() {
+        let keyboard = device("2e8a", "0003", vec![keyboard(1, 0xff)]);
+        let analysis = analyze_device(&[&keyboard]).unwrap();
+        assert_eq!(kinds(&analysis), vec![HidIndicatorKind::Microcontroller]);
+        assert!(!analysis.injector);
+    }
+
+    #[test]
+    fn arduino_keyboard_descriptor_is_injector() {
+        let leonardo = device("1234", "5678", vec![mouse(1, 8), keyboard(2, 0x73)]);
+        let analysis = analyze_device(&[&leonardo]).unwrap();
+        assert!(kinds(&analysis).contains(&HidIndicatorKind::InjectorDescriptor));
+        assert!(analysis.injector);
+    }
+
+    #[test]
+    fn board_with_generic_mouse_is_injector() {
+        let board = device("2341", "8036", vec![mouse(1, 8)]);
+        let analysis = analyze_device(&[&board]).unwrap();
+        assert_eq!(kinds(&analysis), vec![HidIndicatorKind::BasicMouse, HidIndicatorKind::Microcontroller]);
+        assert!(analysis.injector);
+    }
+
+    #[test]
+    fn spoofed_gaming_mouse_is_injector() {
+        let spoofed = device("046d", "c08b", vec![mouse(1, 8)]);
+        let analysis = analyze_device(&[&spoofed]).unwrap();
+        assert!(kinds(&analysis).contains(&HidIndicatorKind::DescriptorMismatch));
+        assert!(analysis.injector);
+    }
+
+    #[test]
+    fn gaming_mouse_is_clean() {
+        let vendor = HidCollection { usage_page: 0xff00, usage: 1, report_ids: vec![0x11], ..Default::default() };
+        let g502 = device("046d", "c08b", vec![mouse(2, 16), vendor]);
+        assert!(analyze_device(&[&g502]).is_none());
+    }
+
+    #[test]
+    fn profiles_arduino_library_descriptor() {
+        let collections = parse_descriptor(&fixture("arduino-leonardo.bin"));
+        // The modifier bitmap (0xe0-0xe7) is not part of the key array
+        assert_eq!(collections, vec![mouse(1, 8), keyboard(2, 0x73)]);
+
+        let leonardo = interface("2341", "8036", fixture("arduino-leonardo.bin"));
+        let analysis = analyze(&[leonardo]);
+        assert_eq!(analysis.len(), 1);
+        assert_eq!(kinds(&analysis[0]), vec![HidIndicatorKind::InjectorDescriptor, HidIndicatorKind::Microcontroller,
+                                             HidIndicatorKind::GenericComposite]);
+        assert!(analysis[0].injector);
+        assert!(analysis[0].descriptor_sha256.is_some());
+    }
+
+    #[test]
+    fn profiles_g502_descriptors() {
+        let pointer = parse_descriptor(&fixture("g502-hero-if0.bin"));
+        assert_eq!(pointer, vec![HidCollection { usage_page: PAGE_GENERIC_DESKTOP, usage: USAGE_MOUSE, report_ids: vec![],
+                                                 buttons: 16, axis_bits: 16, relative: true, axis_min: -32767,
+                                                 axis_max: 32767, key_max: 0 }]);
+
+        let profiles: Vec<String> = parse_descriptor(&fixture("g502-hero-if1.bin")).iter().map(|c| c.profile()).collect();
+        assert_eq!(profiles, vec![
+            "0001:0006 ids=[1] buttons=0 axes=0a[0,0] keys=164",
+            "000c:0001 ids=[3] buttons=0 axes=0a[0,0] keys=0",
+            "0001:0080 ids=[4] buttons=0 axes=0a[0,0] keys=0",
+            "ff00:0001 ids=[16] buttons=0 axes=0a[0,0] keys=0",
+            "ff00:0002 ids=[17] buttons=0 axes=0a[0,0] keys=0",
+        ]);
+
+        let g502 = [interface("046d", "c08b", fixture("g502-hero-if0.bin")), interface("046d", "c08b", fixture("g502-hero-if1.bin"))];
+        assert!(analyze(&g502).is_empty());
+    }
+
+    #[test]
+    fn leonardo_with_g502_ids_is_injector() {
+        let spoofed = interface("046d", "c08b", fixture("arduino-leonardo.bin"));
+        let analysis = analyze_device(&[&spoofed]).unwrap();
+        assert_eq!(kinds(&analysis), vec![HidIndicatorKind::InjectorDescriptor, HidIndicatorKind::DescriptorMismatch,
+                                          HidIndicatorKind::DescriptorMismatch, HidIndicatorKind::GenericComposite]);
+        assert!(analysis.injector);
+    }
+
+    #[test]
+    fn windows_caps_of_leonardo_match_its_descriptor() {
+        // Windows opens one interface per top-level collection
+        let mut pointer = HidCollection { usage_page: PAGE_GENERIC_DESKTOP, usage: USAGE_MOUSE, ..Default::default() };
+        pointer.add_button_caps(&ButtonCaps { report_id: 1, usage_page: PAGE_BUTTON, usage_min: 1, usage_max: 5, variable: true });
+        for usage in [USAGE_X, USAGE_Y, 0x38] {
+            pointer.add_value_caps(&ValueCaps { report_id: 1, usage_page: PAGE_GENERIC_DESKTOP, usage, bit_size: 8,
+                                                relative: true, logical_min: -127, logical_max: 127 });
+        }
+        let mut keys = HidCollection { usage_page: PAGE_GENERIC_DESKTOP, usage: USAGE_KEYBOARD, ..Default::default() };
+        keys.add_button_caps(&ButtonCaps { report_id: 2, usage_page: PAGE_KEYBOARD, usage_min: 0xe0, usage_max: 0xe7, variable: true });
+        keys.add_button_caps(&ButtonCaps { report_id: 2, usage_page: PAGE_KEYBOARD, usage_min: 0x00, usage_max: 0x73, variable: false });
+        assert_eq!(vec![pointer.clone(), keys.clone()], parse_descriptor(&fixture("arduino-leonardo.bin")));
+
+        let (pointer, keys) = (device("2341", "8036", vec![pointer]), device("2341", "8036", vec![keys]));
+        let analysis = analyze_device(&[&pointer, &keys]).unwrap();
+        assert!(kinds(&analysis).contains(&HidIndicatorKind::InjectorDescriptor));
+        assert!(analysis.injector);
+    }
+}
@@ -0,0 +1,339 @@
+//! Journal module - encrypted local history of hardware components
+//!
+//! Every launch records the fingerprint component hashes with first-seen and
+//! last-seen times. The churn report derived from it tells a one-off upgrade (one
+//! component changes once) from a spoofer (serials or MACs change every session,
+//! or flip back to the real values when it is turned off). The journal is sealed
+//! with AES-256-GCM under a key kept in the OS keyring; a journal that disappears
+//! or no longer opens while its key exists is reported as a reset.
+
+use crate::fingerprint::HardwareFingerprint;
+use crate::store;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+const JOURNAL_FILE: &str = "hardware.journal";
+const JOURNAL_AAD: &[u8] = b"iris-hardware-journal-v1";
+const MAX_VALUES_PER_COMPONENT: usize = 32;
+const MAX_CHANGES: usize = 256;
+const RECENT_SECS: u64 = 30 * 24 * 3600;
+
+/// One hashed value of a component
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SeenValue {
+    pub hash: String,
+    pub first_seen: u64,
+    pub last_seen: u64,
+}
+
+/// A component whose values differ from the previous session
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ComponentChange {
+    pub component: String,
+    pub at: u64,
+    pub added: usize,
+    pub removed: usize,
+    pub reverted: bool, // A value seen in an earlier session came back
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Journal {
+    pub fingerprint_version: u32, // Component hash version of the recorded values
+    pub created_at: u64,
+    pub sessions: u32,
+    pub last_session: u64,
+    pub current: BTreeMap<String, Vec<String>>, // Values of the last session
+    pub values: BTreeMap<String, Vec<SeenValue>>,
+    pub changes: Vec<ComponentChange>,
+}
+
+/// How often one component changed
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ComponentChurn {
+    pub component: String,
+    pub changes: u32,
+    pub recent_changes: u32, // Last 30 days
+    pub reverts: u32,
+    pub distinct_values: usize,
+    pub last_change: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ChurnReport {
+    pub tracking_since: u64,
+    pub sessions: u32,
+    pub journal_reset: bool,       // Journal missing or unreadable while its key exists
+    pub changed_now: Vec<String>,  // Components that differ from the previous session
+    pub components: Vec<ComponentChurn>,
+}
+
+/// Record this launch's fingerprint and report the churn so far
+pub fn record_session(fingerprint: &HardwareFingerprint) -> Result<ChurnReport, String> {
+    let (key, created) = store::journal_key()?;
+    let path = store::data_dir().join(JOURNAL_FILE);
+
+    let (mut journal, reset) = match std::fs::read(&path) {
+        Ok(sealed) => match open(&key, &sealed) {
+            Ok(journal) => (journal, false),
+            Err(e) => {
+                println!("[Journal] Journal rejected, starting over: {}", e);
+                (Journal::default(), true)
+            }
+        },
+        Err(_) => (Journal::default(), !created),
+    };
+    // Hashes of another fingerprint version cannot be compared
+    if journal.fingerprint_version != fingerprint.version {
+        journal = Journal { fingerprint_version: fingerprint.version, ..Default::default() };
+    }
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let changed = record(&mut journal, &fingerprint.components, now);
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    std::fs::write(&path, seal(&key, &journal)?).map_err(|e| e.to_string())?;
+
+    if !changed.is_empty() || reset {
+        println!("[Journal] Changed since last session: {:?} (reset: {})", changed, reset);
+    }
+    Ok(churn_report(&journal, changed, reset, now))
+}
+
+/// Add a session to the journal, returning the components that changed since the previous one
+pub fn record(journal: &mut Journal, components: &BTreeMap<String, Vec<String>>, now: u64) -> Vec<String> {
+    let mut changed = Vec::new();
+
+    // Components missing this session keep their last values: DMI serials need
+    // root and the TPM can be busy, which is not a change of hardware
+    for (name, values) in components {
+        let seen = journal.values.entry(name.clone()).or_default();
+
+        if let Some(previous) = journal.current.get(name) {
+            let added: Vec<&String> = values.iter().filter(|v| !previous.contains(v)).collect();
+            let removed = previous.iter().filter(|v| !values.contains(v)).count();
+            if !added.is_empty() || removed > 0 {
+                journal.changes.push(ComponentChange {
+                    component: name.clone(),
+                    at: now,
+                    added: added.len(),
+                    removed,
+                    reverted: added.iter().any(|v| seen.iter().any(|s| &&s.hash == v)),
+                });
+                changed.push(name.clone());
+            }
+        }
+
+        for value in values {
+            match seen.iter_mut().find(|s| &s.hash == value) {
+                Some(existing) => existing.last_seen = now,
+                None => seen.push(SeenValue { hash: value.clone(), first_seen: now, last_seen: now }),
+            }
+        }
+        if seen.len() > MAX_VALUES_PER_COMPONENT {
+            seen.sort_by_key(|s| std::cmp::Reverse(s.last_seen));
+            seen.truncate(MAX_VALUES_PER_COMPONENT);
+        }
+        journal.current.insert(name.clone(), values.clone());
+    }
+
+    if journal.changes.len() > MAX_CHANGES {
+        let excess = journal.changes.len() - MAX_CHANGES;
+        journal.changes.drain(..excess);
+    }
+    if journal.created_at == 0 {
+        journal.created_at = now;
+    }
+    journal.sessions += 1;
+    journal.last_session = now;
+    changed
+}
+
+/// Per-component change counts of a journal
+pub fn churn_report(journal: &Journal, changed_now: Vec<String>, journal_reset: bool, now: u64) -> ChurnReport {
+    let components = journal.values.iter()
+        .map(|(name, seen)| {
+            let changes: Vec<&ComponentChange> = journal.changes.iter().filter(|c| &c.component == name).collect();
+            ComponentChurn {
+                component: name.clone(),
+                changes: changes.len() as u32,
+                recent_changes: changes.iter().filter(|c| c.at + RECENT_SECS >= now).count() as u32,
+                reverts: changes.iter().filter(|c| c.reverted).count() as u32,
+                distinct_values: seen.len(),
+                last_change: changes.last().map(|c| c.at),
+            }
+        })
+        .collect();
+
+    ChurnReport {
+        tracking_since: journal.created_at,
+        sessions: journal.sessions,
+        journal_reset,
+        changed_now,
+        components,
+    }
+}
+
+/// Nonce followed by the AES-256-GCM sealed JSON journal
+pub fn seal(key: &[u8], journal: &Journal) -> Result<Vec<u8>, String> {
+    let key = LessSafeKey::new(UnboundKey::new(&AES_256_GCM, key).map_err(|_| "Invalid journal key".to_string())?);
+    let mut nonce = [0u8; NONCE_LEN];
+    SystemRandom::new().fill(&mut nonce).map_err(|_| "Failed to generate nonce".to_string())?;
+
+    let mut data = serde_json::to_vec(journal).map_err(|e| e.to_string())?;
+    key.seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::from(JOURNAL_AAD), &mut data)
+        .map_err(|_| "Failed to seal journal".to_string())?;
+
+    let mut sealed = nonce.to_vec();
+    sealed.extend(data);
+    Ok(sealed)
+}
+
+/// Journal of a sealed file (fails when it was modified or sealed under another key)
+pub fn open(key: &[u8], sealed: &[u8]) -> Result<Journal, String> {
+    let key = LessSafeKey::new(UnboundKey::new(&AES_256_GCM, key).map_err(|_| "Invalid journal key".to_string())?);
+    if sealed.len() < NONCE_LEN {
+        return Err("Journal too short".to_string());
+    }
+    let (nonce, data) = sealed.split_at(NONCE_LEN);
+    let nonce = Nonce::try_assume_unique_for_key(nonce).map_err(|_| "Invalid nonce".to_string())?;
+
+    let mut data = data.to_vec();
+    let plain = key.open_in_place(nonce, Aad::from(JOURNAL_AAD), &mut data)
+        .map_err(|_| "Journal authentication failed".to_string())?;
+    serde_json::from_slice(plain).map_err(|e| format!("Invalid journal: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: u64 = 24 * 3600;
+    const KEY: [u8; 32] = [7; 32];
+
+    fn session(components: &[(&str, &[&str])]) -> BTreeMap<String, Vec<String>> {
+        components.iter()
+            .map(|(name, values)| (name.to_string(), values.iter().map(|v| v.to_string()).collect()))
+            .collect()
+    }
+
+    fn churn<'a>(report: &'a ChurnReport, component: &str) -> &'a ComponentChurn {
+        report.components.iter().find(|c| c.component == component).unwrap()
+    }
+
+    #[test]
+    fn detects_changes_and_reverts_across_sessions() {
+        let mut journal = Journal::default();
+        assert!(record(&mut journal, &session(&[("diskSerials", &["a"]), ("macAddresses", &["m"])]), DAY).is_empty());
+        assert_eq!(journal.created_at, DAY);
+
+        // Spoofer on: new serial
+        assert_eq!(record(&mut journal, &session(&[("diskSerials", &["b"]), ("macAddresses", &["m"])]), 2 * DAY),
+                   ["diskSerials"]);
+        // Spoofer off: the original serial comes back
+        assert_eq!(record(&mut journal, &session(&[("diskSerials", &["a"]), ("macAddresses", &["m"])]), 3 * DAY),
+                   ["diskSerials"]);
+
+        assert_eq!(journal.sessions, 3);
+        assert_eq!(journal.changes.len(), 2);
+        assert_eq!((journal.changes[0].added, journal.changes[0].removed, journal.changes[0].reverted), (1, 1, false));
+        assert!(journal.changes[1].reverted);
+
+        let report = churn_report(&journal, vec!["diskSerials".to_string()], false, 3 * DAY);
+        assert_eq!(report.tracking_since, DAY);
+        let disks = churn(&report, "diskSerials");
+        assert_eq!((disks.changes, disks.reverts, disks.distinct_values, disks.last_change), (2, 1, 2, Some(3 * DAY)));
+        assert_eq!(churn(&report, "macAddresses").changes, 0);
+    }
+
+    #[test]
+    fn missing_components_are_not_changes() {
+        let mut journal = Journal::default();
+        record(&mut journal, &session(&[("tpmEk", &["ek"]), ("boardSerial", &["b"])]), DAY);
+        // TPM busy, DMI not readable: nothing changed
+        assert!(record(&mut journal, &session(&[]), 2 * DAY).is_empty());
+        assert!(record(&mut journal, &session(&[("boardSerial", &["b"])]), 3 * DAY).is_empty());
+        // Back with the same value
+        assert!(record(&mut journal, &session(&[("tpmEk", &["ek"])]), 4 * DAY).is_empty());
+        assert!(journal.changes.is_empty());
+        assert_eq!(journal.current["tpmEk"], ["ek"]);
+    }
+
+    #[test]
+    fn trims_values_and_changes() {
+        let mut journal = Journal::default();
+        let sessions = MAX_CHANGES + 10;
+        for i in 0..sessions {
+            let value = format!("mac-{}", i);
+            record(&mut journal, &session(&[("macAddresses", &[value.as_str()])]), (i as u64 + 1) * DAY);
+        }
+
+        // Most recently seen values are kept
+        let seen = &journal.values["macAddresses"];
+        assert_eq!(seen.len(), MAX_VALUES_PER_COMPONENT);
+        assert!(seen.iter().any(|s| s.hash == format!("mac-{}", sessions - 1)));
+        assert!(!seen.iter().any(|s| s.hash == "mac-0"));
+
+        // Oldest changes are dropped
+        assert_eq!(journal.changes.len(), MAX_CHANGES);
+        assert_eq!(journal.changes.last().unwrap().at, sessions as u64 * DAY);
+        assert_eq!(journal.changes[0].at, (sessions - MAX_CHANGES + 1) as u64 * DAY);
+    }
+
+    #[test]
+    fn recent_changes_cover_the_last_30_days() {
+        let mut journal = Journal::default();
+        let now = 100 * DAY;
+        for (i, at) in [DAY, 2 * DAY, now - RECENT_SECS, now - DAY, now].into_iter().enumerate() {
+            let value = format!("serial-{}", i);
+            record(&mut journal, &session(&[("diskSerials", &[value.as_str()])]), at);
+        }
+
+        let report = churn_report(&journal, Vec::new(), false, now);
+        let disks = churn(&report, "diskSerials");
+        assert_eq!(disks.changes, 4);
+        assert_eq!(disks.recent_changes, 3); // 30 days ago (inclusive), yesterday, today
+    }
+
+    #[test]
+    fn seal_and_open_round_trip() {
+        let mut journal = Journal { fingerprint_version: 2, ..Default::default() };
+        record(&mut journal, &session(&[("diskSerials", &["a"])]), DAY);
+        record(&mut journal, &session(&[("diskSerials", &["b"])]), 2 * DAY);
+
+        let sealed = seal(&KEY, &journal).unwrap();
+        assert!(!sealed.windows(b"diskSerials".len()).any(|w| w == b"diskSerials"), "journal is not encrypted");
+        let opened = open(&KEY, &sealed).unwrap();
+        assert_eq!(opened.fingerprint_version, 2);
+        assert_eq!(opened.sessions, 2);
+        assert_eq!(opened.current, journal.current);
+        assert_eq!(opened.changes.len(), 1);
+
+        // Fresh nonce per seal
+        assert_ne!(seal(&KEY, &journal).unwrap()[..NONCE_LEN], sealed[..NONCE_LEN]);
+    }
+
+    #[test]
+    fn open_rejects_modified_journal_or_wrong_key() {
+        let sealed = seal(&KEY, &Journal::default()).unwrap();
+        for i in [0, NONCE_LEN, sealed.len() - 1] {
+            let mut modified = sealed.clone();
+            modified[i] ^= 0x01;
+            assert_eq!(open(&KEY, &modified).unwrap_err(), "Journal authentication failed");
+        }
+        assert_eq!(open(&[8; 32], &sealed).unwrap_err(), "Journal authentication failed");
+        assert_eq!(open(&KEY, &sealed[..NONCE_LEN - 1]).unwrap_err(), "Journal too short");
+        assert!(open(&KEY, &sealed[..sealed.len() - 1]).is_err());
+    }
+}
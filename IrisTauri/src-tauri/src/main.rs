@@ -9,6 +9,35 @@ mod store;
 mod commands;
 mod updater;
 mod behavioral;
+mod snapshot;
+mod detectors;
+mod signatures;
+mod rules;
+mod reputation;
+mod pe;
+mod authenticode;
+mod patterns;
+mod whitelist;
+mod risk;
+mod efisig;
+mod eventlog;
+mod attestation;
+mod fingerprint;
+mod journal;
+mod smbios;
+mod acpi;
+mod pci;
+mod hid;
+mod usbtopology;
+mod spoofing;
+#[cfg(target_os = "linux")]
+mod efivars;
+#[cfg(target_os = "linux")]
+mod procfs;
+#[cfg(target_os = "linux")]
+mod sysfs;
+#[cfg(target_os = "linux")]
+mod tpm;
 
 use tauri::{Manager, Emitter};
 
@@ -0,0 +1,1046 @@
+//! Byte pattern module - YARA-style rules matched against executables on disk
+//!
+//! A rule has named strings and a condition:
+//!
+//! - `{ 4D 5A ?? 4? [2-8] ( 90 | CC CC ) ~00 }`  hex bytes, wildcards, jumps, alternatives
+//! - `"iqvw64e.sys" nocase wide ascii`            text (`wide` = UTF-16LE)
+//! - `/load(er|lib)[0-9]+/ nocase`                regex over raw bytes
+//!
+//! Conditions: `$a and ($b or not $c)`, `any of them`, `all of ($s*)`, `2 of ($a, $b, $c)`,
+//! `filesize < 4MB`. Rules ship in the signature bundle; a few are compiled in.
+//!
+//! Scanned files are cached by (path, size, mtime, bundle version) and new files are
+//! read within a per-cycle size and time budget.
+
+use crate::hardware;
+use crate::rules::Subject;
+use crate::signatures;
+use crate::snapshot::SystemSnapshot;
+use crate::whitelist::{self, Suppression, WhitelistType};
+use regex::bytes::{RegexSet, RegexSetBuilder};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Files larger than this are not scanned
+const MAX_FILE_SIZE: u64 = 32 * 1024 * 1024;
+
+/// Bytes read per cycle (the rest wait for the next run)
+const MAX_BYTES_PER_CYCLE: u64 = 96 * 1024 * 1024;
+
+/// Time spent reading and matching per cycle
+const MAX_SCAN_TIME: Duration = Duration::from_secs(2);
+
+/// Longest bounded jump in a hex string
+const MAX_JUMP: usize = 1024;
+
+/// (name, description, strings, condition, risk)
+type BuiltinRule = (&'static str, &'static str, &'static [(&'static str, &'static str)], &'static str, u32);
+
+/// Rules compiled into the binary
+pub(crate) const BUILTIN_RULES: &[BuiltinRule] = &[
+    (
+        "kdmapper",
+        "KDMapper - maps unsigned drivers through the vulnerable Intel iqvw64e.sys",
+        &[
+            ("$driver", "\"iqvw64e.sys\" nocase wide ascii"),
+            ("$name", "\"kdmapper\" nocase wide ascii"),
+            ("$device", r#""\\\\.\\Nal" wide ascii"#),
+        ],
+        "$driver and ($name or $device)",
+        90,
+    ),
+    (
+        "cheat_engine_dbk",
+        "Cheat Engine kernel driver loader (DBK)",
+        &[
+            ("$dbk32", "\"dbk32.sys\" nocase wide ascii"),
+            ("$dbk64", "\"dbk64.sys\" nocase wide ascii"),
+            ("$ce", "\"Cheat Engine\" wide ascii"),
+        ],
+        "$ce and ($dbk32 or $dbk64)",
+        80,
+    ),
+];
+
+// ====== RULES ======
+
+/// Rule as written in the signature bundle
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PatternRuleSource {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub strings: Vec<PatternString>,
+    pub condition: String,
+    #[serde(default = "default_risk")]
+    pub risk: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PatternString {
+    pub id: String, // "$name"
+    pub pattern: String,
+}
+
+fn default_risk() -> u32 {
+    80
+}
+
+/// A validated byte pattern rule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "PatternRuleSource", into = "PatternRuleSource")]
+pub struct PatternRule {
+    source: PatternRuleSource,
+    regexes: Vec<String>, // One per string, same order
+    condition: Condition,
+}
+
+impl PatternRule {
+    pub fn parse(source: PatternRuleSource) -> Result<Self, String> {
+        if source.strings.is_empty() {
+            return Err(format!("Rule '{}' has no strings", source.name));
+        }
+
+        let mut ids: Vec<&str> = Vec::new();
+        let mut regexes = Vec::new();
+        for string in &source.strings {
+            if !string.id.starts_with('$') || string.id.len() < 2 || ids.contains(&string.id.as_str()) {
+                return Err(format!("Rule '{}': invalid or duplicate id '{}'", source.name, string.id));
+            }
+            ids.push(&string.id);
+            regexes.push(compile_string(&string.pattern)
+                .map_err(|e| format!("Rule '{}' {}: {}", source.name, string.id, e))?);
+        }
+
+        // Each rule must compile on its own so a bad entry names its rule
+        build_set(&regexes).map_err(|e| format!("Rule '{}': {}", source.name, e))?;
+
+        let condition = ConditionParser::new(&source.condition, &ids)
+            .parse()
+            .map_err(|e| format!("Rule '{}' condition: {}", source.name, e))?;
+
+        Ok(PatternRule { source, regexes, condition })
+    }
+
+    /// Parse a compiled-in rule (invalid built-in rules are a programming error)
+    pub fn builtin(name: &str, description: &str, strings: &[(&str, &str)], condition: &str, risk: u32) -> Self {
+        let source = PatternRuleSource {
+            name: name.to_string(),
+            description: description.to_string(),
+            strings: strings.iter().map(|(id, pattern)| PatternString {
+                id: id.to_string(),
+                pattern: pattern.to_string(),
+            }).collect(),
+            condition: condition.to_string(),
+            risk,
+        };
+        Self::parse(source).unwrap_or_else(|e| panic!("Invalid built-in pattern rule: {}", e))
+    }
+}
+
+impl TryFrom<PatternRuleSource> for PatternRule {
+    type Error = String;
+
+    fn try_from(source: PatternRuleSource) -> Result<Self, Self::Error> {
+        PatternRule::parse(source)
+    }
+}
+
+impl From<PatternRule> for PatternRuleSource {
+    fn from(rule: PatternRule) -> Self {
+        rule.source
+    }
+}
+
+/// Compiled-in rule table
+pub fn builtin_rules() -> Vec<PatternRule> {
+    BUILTIN_RULES.iter()
+        .map(|(name, description, strings, condition, risk)| PatternRule::builtin(name, description, strings, condition, *risk))
+        .collect()
+}
+
+fn build_set(regexes: &[String]) -> Result<RegexSet, String> {
+    RegexSetBuilder::new(regexes)
+        .unicode(false)
+        .size_limit(16 << 20)
+        .dfa_size_limit(16 << 20)
+        .build()
+        .map_err(|e| e.to_string())
+}
+
+// ====== STRINGS ======
+
+/// Translate a rule string to a byte regex
+fn compile_string(pattern: &str) -> Result<String, String> {
+    let pattern = pattern.trim();
+    if pattern.starts_with('{') {
+        compile_hex(pattern)
+    } else if pattern.starts_with('"') {
+        compile_text(pattern)
+    } else if pattern.starts_with('/') {
+        compile_regex(pattern)
+    } else {
+        Err(format!("Unknown string type: '{}'", pattern))
+    }
+}
+
+fn compile_hex(pattern: &str) -> Result<String, String> {
+    let body = pattern.strip_prefix('{').and_then(|p| p.strip_suffix('}'))
+        .ok_or("Hex string must be enclosed in { }")?;
+    let chars: Vec<char> = body.chars().filter(|c| !c.is_whitespace()).collect();
+
+    let mut regex = String::from("(?s-u)");
+    let mut literal_run = 0;
+    let mut longest_run = 0;
+    let mut depth = 0i32;
+    let mut i = 0;
+
+    while i < chars.len() {
+        match chars[i] {
+            '(' => { regex.push_str("(?:"); depth += 1; literal_run = 0; i += 1; }
+            ')' => { regex.push(')'); depth -= 1; literal_run = 0; i += 1; }
+            '|' if depth > 0 => { regex.push('|'); literal_run = 0; i += 1; }
+            '[' => {
+                let end = chars[i..].iter().position(|&c| c == ']').ok_or("Unclosed jump")? + i;
+                let jump: String = chars[i + 1..end].iter().collect();
+                regex.push_str(&compile_jump(&jump)?);
+                literal_run = 0;
+                i = end + 1;
+            }
+            '~' => {
+                let byte = hex_pair(&chars, i + 1)?;
+                regex.push_str(&format!("[^\\x{:02x}]", byte.ok_or("Negated wildcard")?));
+                literal_run = 0;
+                i += 3;
+            }
+            _ => {
+                let (hi, lo) = (chars[i], *chars.get(i + 1).ok_or("Odd number of hex digits")?);
+                match (hi.to_digit(16), lo.to_digit(16)) {
+                    (Some(h), Some(l)) => {
+                        regex.push_str(&format!("\\x{:02x}", h * 16 + l));
+                        literal_run += 1;
+                        longest_run = longest_run.max(literal_run);
+                    }
+                    (None, None) if hi == '?' && lo == '?' => {
+                        regex.push_str("(?s:.)");
+                        literal_run = 0;
+                    }
+                    (Some(h), None) if lo == '?' => {
+                        regex.push_str(&format!("[\\x{:02x}-\\x{:02x}]", h * 16, h * 16 + 15));
+                        literal_run = 0;
+                    }
+                    (None, Some(l)) if hi == '?' => {
+                        let class: String = (0..16).map(|h| format!("\\x{:02x}", h * 16 + l)).collect();
+                        regex.push_str(&format!("[{}]", class));
+                        literal_run = 0;
+                    }
+                    _ => return Err(format!("Invalid hex token '{}{}'", hi, lo)),
+                }
+                i += 2;
+            }
+        }
+        if depth < 0 {
+            return Err("Unbalanced parentheses".to_string());
+        }
+    }
+
+    if depth != 0 {
+        return Err("Unbalanced parentheses".to_string());
+    }
+    // A fixed atom keeps the scan fast and the rule specific
+    if longest_run < 2 {
+        return Err("Hex string needs at least 2 consecutive fixed bytes".to_string());
+    }
+    Ok(regex)
+}
+
+/// Byte value of a hex pair (None for `??`)
+fn hex_pair(chars: &[char], at: usize) -> Result<Option<u8>, String> {
+    let pair: String = chars.get(at..at + 2).ok_or("Truncated hex byte")?.iter().collect();
+    if pair == "??" {
+        return Ok(None);
+    }
+    u8::from_str_radix(&pair, 16).map(Some).map_err(|_| format!("Invalid hex byte '{}'", pair))
+}
+
+/// `[4]`, `[2-8]`, `[2-]`
+fn compile_jump(jump: &str) -> Result<String, String> {
+    let parse = |s: &str| s.parse::<usize>().map_err(|_| format!("Invalid jump '[{}]'", jump));
+    let (min, max) = match jump.split_once('-') {
+        Some((min, "")) => (parse(min)?, None),
+        Some((min, max)) => (parse(min)?, Some(parse(max)?)),
+        None => (parse(jump)?, Some(parse(jump)?)),
+    };
+    match max {
+        Some(max) if max < min => Err(format!("Invalid jump '[{}]'", jump)),
+        Some(max) if max > MAX_JUMP => Err(format!("Jump '[{}]' exceeds {} bytes", jump, MAX_JUMP)),
+        Some(max) => Ok(format!("(?s:.){{{},{}}}", min, max)),
+        None => Ok(format!("(?s:.){{{},}}?", min)),
+    }
+}
+
+fn compile_text(pattern: &str) -> Result<String, String> {
+    let mut bytes = Vec::new();
+    let mut chars = pattern[1..].chars();
+    let mut closed = false;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => { closed = true; break; }
+            '\\' => match chars.next() {
+                Some('x') => {
+                    let hex: String = chars.by_ref().take(2).collect();
+                    bytes.push(u8::from_str_radix(&hex, 16).map_err(|_| format!("Invalid escape '\\x{}'", hex))?);
+                }
+                Some('n') => bytes.push(b'\n'),
+                Some('r') => bytes.push(b'\r'),
+                Some('t') => bytes.push(b'\t'),
+                Some(c @ ('"' | '\\')) => bytes.push(c as u8),
+                other => return Err(format!("Invalid escape '\\{}'", other.unwrap_or(' '))),
+            },
+            c => {
+                let mut buf = [0u8; 4];
+                bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            }
+        }
+    }
+
+    if !closed {
+        return Err("Unclosed text string".to_string());
+    }
+    if bytes.len() < 2 {
+        return Err("Text string needs at least 2 bytes".to_string());
+    }
+
+    let modifiers: Vec<&str> = chars.as_str().split_whitespace().collect();
+    if let Some(unknown) = modifiers.iter().find(|m| !["nocase", "wide", "ascii"].contains(m)) {
+        return Err(format!("Unknown modifier '{}'", unknown));
+    }
+    let nocase = modifiers.contains(&"nocase");
+    let wide = modifiers.contains(&"wide");
+    let ascii = !wide || modifiers.contains(&"ascii");
+
+    let encode = |wide: bool| -> String {
+        bytes.iter().map(|&b| {
+            let byte = if nocase && b.is_ascii_alphabetic() {
+                format!("[\\x{:02x}\\x{:02x}]", b.to_ascii_lowercase(), b.to_ascii_uppercase())
+            } else {
+                format!("\\x{:02x}", b)
+            };
+            if wide { format!("{}\\x00", byte) } else { byte }
+        }).collect()
+    };
+
+    let forms: Vec<String> = [(ascii, false), (wide, true)].iter()
+        .filter(|(enabled, _)| *enabled)
+        .map(|(_, wide)| encode(*wide))
+        .collect();
+    Ok(format!("(?-u)(?:{})", forms.join("|")))
+}
+
+fn compile_regex(pattern: &str) -> Result<String, String> {
+    let end = pattern.rfind('/').filter(|&end| end > 0).ok_or("Unclosed regex")?;
+    let body = &pattern[1..end];
+    let modifiers: Vec<&str> = pattern[end + 1..].split_whitespace().collect();
+    if let Some(unknown) = modifiers.iter().find(|m| **m != "nocase") {
+        return Err(format!("Unknown modifier '{}'", unknown));
+    }
+    if body.is_empty() {
+        return Err("Empty regex".to_string());
+    }
+    let flags = if modifiers.contains(&"nocase") { "i-u" } else { "-u" };
+    Ok(format!("(?{}:{})", flags, body))
+}
+
+// ====== CONDITIONS ======
+
+#[derive(Debug, Clone)]
+enum Condition {
+    String(usize),
+    Not(Box<Condition>),
+    And(Vec<Condition>),
+    Or(Vec<Condition>),
+    Of { min: usize, strings: Vec<usize> },
+    FileSize(Comparison, u64),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Comparison {
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Equal,
+}
+
+impl Condition {
+    fn eval(&self, matched: &[bool], file_size: u64) -> bool {
+        match self {
+            Condition::String(index) => matched[*index],
+            Condition::Not(inner) => !inner.eval(matched, file_size),
+            Condition::And(items) => items.iter().all(|c| c.eval(matched, file_size)),
+            Condition::Or(items) => items.iter().any(|c| c.eval(matched, file_size)),
+            Condition::Of { min, strings } => strings.iter().filter(|&&i| matched[i]).count() >= *min,
+            Condition::FileSize(comparison, size) => match comparison {
+                Comparison::Less => file_size < *size,
+                Comparison::LessEqual => file_size <= *size,
+                Comparison::Greater => file_size > *size,
+                Comparison::GreaterEqual => file_size >= *size,
+                Comparison::Equal => file_size == *size,
+            },
+        }
+    }
+}
+
+struct ConditionParser<'a> {
+    tokens: Vec<String>,
+    pos: usize,
+    ids: &'a [&'a str],
+}
+
+impl<'a> ConditionParser<'a> {
+    fn new(text: &str, ids: &'a [&'a str]) -> Self {
+        let mut tokens = Vec::new();
+        let mut current = String::new();
+        let mut chars = text.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '(' | ')' | ',' => {
+                    if !current.is_empty() {
+                        tokens.push(std::mem::take(&mut current));
+                    }
+                    tokens.push(c.to_string());
+                }
+                '<' | '>' | '=' => {
+                    if !current.is_empty() {
+                        tokens.push(std::mem::take(&mut current));
+                    }
+                    let mut op = c.to_string();
+                    if chars.peek() == Some(&'=') {
+                        op.push('=');
+                        chars.next();
+                    }
+                    tokens.push(op);
+                }
+                c if c.is_whitespace() => {
+                    if !current.is_empty() {
+                        tokens.push(std::mem::take(&mut current));
+                    }
+                }
+                c => current.push(c),
+            }
+        }
+        if !current.is_empty() {
+            tokens.push(current);
+        }
+
+        ConditionParser { tokens, pos: 0, ids }
+    }
+
+    fn parse(mut self) -> Result<Condition, String> {
+        let condition = self.or()?;
+        match self.peek() {
+            None => Ok(condition),
+            Some(token) => Err(format!("Unexpected '{}'", token)),
+        }
+    }
+
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.pos).map(|t| t.as_str())
+    }
+
+    fn next(&mut self) -> Result<String, String> {
+        let token = self.tokens.get(self.pos).cloned().ok_or("Unexpected end of condition")?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn expect(&mut self, expected: &str) -> Result<(), String> {
+        let token = self.next()?;
+        if token.eq_ignore_ascii_case(expected) {
+            Ok(())
+        } else {
+            Err(format!("Expected '{}', found '{}'", expected, token))
+        }
+    }
+
+    fn or(&mut self) -> Result<Condition, String> {
+        let mut items = vec![self.and()?];
+        while self.peek().is_some_and(|t| t.eq_ignore_ascii_case("or")) {
+            self.pos += 1;
+            items.push(self.and()?);
+        }
+        Ok(if items.len() == 1 { items.remove(0) } else { Condition::Or(items) })
+    }
+
+    fn and(&mut self) -> Result<Condition, String> {
+        let mut items = vec![self.factor()?];
+        while self.peek().is_some_and(|t| t.eq_ignore_ascii_case("and")) {
+            self.pos += 1;
+            items.push(self.factor()?);
+        }
+        Ok(if items.len() == 1 { items.remove(0) } else { Condition::And(items) })
+    }
+
+    fn factor(&mut self) -> Result<Condition, String> {
+        let token = self.next()?;
+        let lower = token.to_lowercase();
+
+        match lower.as_str() {
+            "not" => Ok(Condition::Not(Box::new(self.factor()?))),
+            "(" => {
+                let inner = self.or()?;
+                self.expect(")")?;
+                Ok(inner)
+            }
+            "filesize" => {
+                let comparison = match self.next()?.as_str() {
+                    "<" => Comparison::Less,
+                    "<=" => Comparison::LessEqual,
+                    ">" => Comparison::Greater,
+                    ">=" => Comparison::GreaterEqual,
+                    "==" => Comparison::Equal,
+                    other => return Err(format!("Invalid comparison '{}'", other)),
+                };
+                Ok(Condition::FileSize(comparison, parse_size(&self.next()?)?))
+            }
+            "all" | "any" => {
+                let strings = self.string_set()?;
+                let min = if lower == "all" { strings.len() } else { 1 };
+                Ok(Condition::Of { min, strings })
+            }
+            _ if token.starts_with('$') => self.string_index(&token).map(Condition::String),
+            _ => {
+                let min: usize = token.parse().map_err(|_| format!("Unexpected '{}'", token))?;
+                let strings = self.string_set()?;
+                if min == 0 || min > strings.len() {
+                    return Err(format!("'{} of' with {} strings", min, strings.len()));
+                }
+                Ok(Condition::Of { min, strings })
+            }
+        }
+    }
+
+    /// `of them` / `of ($a, $b*)`
+    fn string_set(&mut self) -> Result<Vec<usize>, String> {
+        self.expect("of")?;
+        if self.peek().is_some_and(|t| t.eq_ignore_ascii_case("them")) {
+            self.pos += 1;
+            return Ok((0..self.ids.len()).collect());
+        }
+
+        self.expect("(")?;
+        let mut strings = Vec::new();
+        loop {
+            let token = self.next()?;
+            match token.strip_suffix('*') {
+                Some(prefix) => {
+                    let matching: Vec<usize> = (0..self.ids.len()).filter(|&i| self.ids[i].starts_with(prefix)).collect();
+                    if matching.is_empty() {
+                        return Err(format!("No string matches '{}'", token));
+                    }
+                    strings.extend(matching);
+                }
+                None => strings.push(self.string_index(&token)?),
+            }
+            match self.next()?.as_str() {
+                "," => continue,
+                ")" => break,
+                other => return Err(format!("Expected ',' or ')', found '{}'", other)),
+            }
+        }
+        // A string listed twice (`$a, $a` or `$a, $a*`) counts once
+        strings.sort_unstable();
+        strings.dedup();
+        Ok(strings)
+    }
+
+    fn string_index(&self, id: &str) -> Result<usize, String> {
+        self.ids.iter().position(|&known| known == id).ok_or_else(|| format!("Unknown string '{}'", id))
+    }
+}
+
+/// `4096`, `512KB`, `4MB`
+fn parse_size(text: &str) -> Result<u64, String> {
+    let upper = text.to_uppercase();
+    let (number, multiplier) = if let Some(n) = upper.strip_suffix("MB") {
+        (n, 1024 * 1024)
+    } else if let Some(n) = upper.strip_suffix("KB") {
+        (n, 1024)
+    } else {
+        (upper.as_str(), 1)
+    };
+    number.parse::<u64>().map(|n| n * multiplier).map_err(|_| format!("Invalid size '{}'", text))
+}
+
+// ====== SCANNER ======
+
+/// Every string of every rule in one set, so a file is matched in a single pass
+pub struct PatternScanner {
+    rules: Vec<PatternRule>,
+    offsets: Vec<usize>, // Index of each rule's first string in the set
+    set: RegexSet,
+}
+
+/// Rule that matched a buffer
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RuleHit {
+    pub rule: String,
+    pub description: String,
+    pub strings: Vec<String>, // Ids of the strings found
+    pub risk: u32,
+}
+
+impl PatternScanner {
+    pub fn new(rules: &[PatternRule]) -> Result<Self, String> {
+        let mut offsets = Vec::new();
+        let mut regexes = Vec::new();
+        for rule in rules {
+            offsets.push(regexes.len());
+            regexes.extend(rule.regexes.iter().cloned());
+        }
+        Ok(PatternScanner {
+            rules: rules.to_vec(),
+            offsets,
+            set: build_set(&regexes)?,
+        })
+    }
+
+    /// Rules whose condition holds for `data`
+    pub fn scan(&self, data: &[u8]) -> Vec<RuleHit> {
+        let found = self.set.matches(data);
+        let mut hits = Vec::new();
+
+        for (rule, &offset) in self.rules.iter().zip(&self.offsets) {
+            let matched: Vec<bool> = (0..rule.regexes.len()).map(|i| found.matched(offset + i)).collect();
+            if rule.condition.eval(&matched, data.len() as u64) {
+                hits.push(RuleHit {
+                    rule: rule.source.name.clone(),
+                    description: rule.source.description.clone(),
+                    strings: rule.source.strings.iter().zip(&matched)
+                        .filter(|(_, &m)| m)
+                        .map(|(s, _)| s.id.clone())
+                        .collect(),
+                    risk: rule.source.risk,
+                });
+            }
+        }
+        hits
+    }
+
+    /// Scan a file on disk
+    pub fn scan_file(&self, path: &str) -> Result<Vec<RuleHit>, String> {
+        let data = std::fs::read(path).map_err(|e| e.to_string())?;
+        Ok(self.scan(&data))
+    }
+}
+
+// ====== DETECTOR ======
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PatternMatch {
+    pub path: String,
+    pub source: String, // "process", "driver", "uninstall"
+    pub process_name: Option<String>,
+    pub pid: Option<u32>,
+    pub rule: String,
+    pub description: String,
+    pub strings: Vec<String>,
+    pub risk: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct PatternScanResult {
+    pub matches_found: bool,
+    pub matches: Vec<PatternMatch>,
+    pub rule_count: u32,
+    pub scanned_this_cycle: u32,
+    pub bytes_scanned: u64,
+    pub cached_count: u32,
+    pub pending_count: u32,   // Files left for the next cycles (budget)
+    pub skipped_count: u32,   // Unreadable or too large
+    pub risk_score: u32,
+    #[serde(default)]
+    pub suppressed: Vec<Suppression>, // Whitelist entries that fired
+}
+
+struct CachedScan {
+    size: u64,
+    modified: Option<SystemTime>,
+    bundle_version: u64,
+    hits: Vec<RuleHit>,
+}
+
+struct Target {
+    path: String,
+    source: &'static str,
+    process_name: Option<String>,
+    pid: Option<u32>,
+}
+
+lazy_static::lazy_static! {
+    static ref SCAN_CACHE: Mutex<HashMap<String, CachedScan>> = Mutex::new(HashMap::new());
+    static ref SCANNER: Mutex<Option<(u64, Arc<PatternScanner>)>> = Mutex::new(None);
+}
+
+/// Scanner for the active bundle (rebuilt when the bundle changes)
+fn current_scanner(bundle: &signatures::SignatureBundle) -> Option<Arc<PatternScanner>> {
+    let mut cached = SCANNER.lock().ok()?;
+    if let Some((version, scanner)) = cached.as_ref() {
+        if *version == bundle.version {
+            return Some(scanner.clone());
+        }
+    }
+
+    match PatternScanner::new(&bundle.byte_patterns) {
+        Ok(scanner) => {
+            let scanner = Arc::new(scanner);
+            *cached = Some((bundle.version, scanner.clone()));
+            Some(scanner)
+        }
+        Err(e) => {
+            println!("[Patterns] Failed to build scanner: {}", e);
+            None
+        }
+    }
+}
+
+/// Scan process images, driver images and installed programs with the bundle's byte rules
+pub fn scan_executables(snapshot: &SystemSnapshot) -> PatternScanResult {
+    let mut result = PatternScanResult::default();
+    let sigs = signatures::current();
+    let allowed = whitelist::current();
+    result.rule_count = sigs.byte_patterns.len() as u32;
+
+    let scanner = match current_scanner(&sigs) {
+        Some(scanner) if !sigs.byte_patterns.is_empty() => scanner,
+        _ => return result,
+    };
+
+    let mut cache = match SCAN_CACHE.lock() {
+        Ok(cache) => cache,
+        Err(_) => return result,
+    };
+
+    let started = Instant::now();
+    let mut seen_paths: HashSet<String> = HashSet::new();
+    let mut reported: HashSet<(String, String)> = HashSet::new();
+
+    for target in collect_targets(snapshot) {
+        let key = target.path.to_lowercase();
+        let first_seen = seen_paths.insert(key.clone());
+
+        let (size, modified) = match file_stamp(&target.path) {
+            Some(stamp) => stamp,
+            None => {
+                if first_seen {
+                    result.skipped_count += 1;
+                }
+                continue;
+            }
+        };
+
+        let fresh = cache.get(&key)
+            .is_some_and(|c| c.size == size && c.modified == modified && c.bundle_version == sigs.version);
+
+        if fresh {
+            if first_seen {
+                result.cached_count += 1;
+            }
+        } else if size > MAX_FILE_SIZE {
+            if first_seen {
+                result.skipped_count += 1;
+            }
+            continue;
+        } else if result.bytes_scanned + size > MAX_BYTES_PER_CYCLE || started.elapsed() > MAX_SCAN_TIME {
+            if first_seen {
+                result.pending_count += 1;
+            }
+            continue;
+        } else {
+            match scanner.scan_file(&target.path) {
+                Ok(hits) => {
+                    result.scanned_this_cycle += 1;
+                    result.bytes_scanned += size;
+                    cache.insert(key.clone(), CachedScan { size, modified, bundle_version: sigs.version, hits });
+                }
+                Err(_) => {
+                    if first_seen {
+                        result.skipped_count += 1;
+                    }
+                    continue;
+                }
+            }
+        }
+
+        let hits = match cache.get(&key) {
+            Some(entry) => &entry.hits,
+            None => continue,
+        };
+
+        let file_name = target.path.rsplit(['\\', '/']).next().unwrap_or_default();
+        let subject = Subject::file(target.process_name.as_deref().unwrap_or(file_name), Some(&target.path));
+        let kind = if target.source == "driver" { WhitelistType::Driver } else { WhitelistType::Process };
+        if !hits.is_empty() && allowed.suppress(kind, &[subject], &mut result.suppressed) {
+            continue;
+        }
+
+        for hit in hits {
+            // Same image under several PIDs: reported once per rule
+            if !reported.insert((key.clone(), hit.rule.clone())) {
+                continue;
+            }
+            result.matches_found = true;
+            result.matches.push(PatternMatch {
+                path: target.path.clone(),
+                source: target.source.to_string(),
+                process_name: target.process_name.clone(),
+                pid: target.pid,
+                rule: hit.rule.clone(),
+                description: hit.description.clone(),
+                strings: hit.strings.clone(),
+                risk: hit.risk,
+            });
+            result.risk_score += hit.risk;
+        }
+    }
+
+    // Forget files that are no longer referenced
+    cache.retain(|path, _| seen_paths.contains(path));
+
+    println!("[Patterns] Byte scan: matches={}, scanned={} ({} KB), cached={}, pending={}, skipped={}",
+             result.matches.len(), result.scanned_this_cycle, result.bytes_scanned / 1024,
+             result.cached_count, result.pending_count, result.skipped_count);
+
+    result
+}
+
+/// Running images first (most relevant), then loaded drivers, then installed programs
+fn collect_targets(snapshot: &SystemSnapshot) -> Vec<Target> {
+    // Our own image embeds the compiled-in rule strings
+    let own_image = std::env::current_exe().ok()
+        .map(|p| p.to_string_lossy().to_lowercase());
+    let mut targets = Vec::new();
+
+    for process in &snapshot.processes {
+        if let Some(path) = process.path.as_ref().filter(|p| !p.is_empty()) {
+            targets.push(Target {
+                path: path.clone(),
+                source: "process",
+                process_name: Some(process.name.clone()),
+                pid: Some(process.pid),
+            });
+        }
+    }
+
+    for driver in &snapshot.drivers {
+        if let Some(path) = &driver.path {
+            targets.push(Target {
+                path: hardware::resolve_driver_path(path),
+                source: "driver",
+                process_name: None,
+                pid: None,
+            });
+        }
+    }
+
+    for path in hardware::uninstall_executables() {
+        targets.push(Target { path, source: "uninstall", process_name: None, pid: None });
+    }
+
+    targets.retain(|t| own_image.as_deref() != Some(t.path.to_lowercase().as_str()));
+    targets
+}
+
+fn file_stamp(path: &str) -> Option<(u64, Option<SystemTime>)> {
+    let metadata = std::fs::metadata(path).ok()?;
+    if !metadata.is_file() {
+        return None;
+    }
+    Some((metadata.len(), metadata.modified().ok()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hardware::ProcessInfo;
+    use std::path::{Path, PathBuf};
+
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/patterns").join(name)
+    }
+
+    fn scan(scanner: &PatternScanner, name: &str) -> Vec<(String, Vec<String>)> {
+        scanner.scan_file(fixture(name).to_str().unwrap()).unwrap().into_iter()
+            .map(|hit| (hit.rule, hit.strings))
+            .collect()
+    }
+
+    fn rule(strings: &[(&str, &str)], condition: &str) -> Result<PatternRule, String> {
+        PatternRule::parse(PatternRuleSource {
+            name: "test".to_string(),
+            description: String::new(),
+            strings: strings.iter().map(|(id, pattern)| PatternString { id: id.to_string(), pattern: pattern.to_string() }).collect(),
+            condition: condition.to_string(),
+            risk: default_risk(),
+        })
+    }
+
+    fn matches(rule: PatternRule, data: &[u8]) -> bool {
+        !PatternScanner::new(&[rule]).unwrap().scan(data).is_empty()
+    }
+
+    #[test]
+    fn builtin_rules_on_samples() {
+        let scanner = PatternScanner::new(&builtin_rules()).unwrap();
+        assert_eq!(scan(&scanner, "mapper.exe"), vec![("kdmapper".to_string(), vec!["$driver".to_string(), "$device".to_string()])]);
+        assert_eq!(scan(&scanner, "cheatengine.exe"), vec![("cheat_engine_dbk".to_string(), vec!["$dbk64".to_string(), "$ce".to_string()])]);
+        // One string of the rule is not enough
+        assert!(scan(&scanner, "intel-installer.exe").is_empty());
+        assert!(scan(&scanner, "ce-tutorial.exe").is_empty());
+        assert!(scanner.scan_file(fixture("missing.exe").to_str().unwrap()).is_err());
+    }
+
+    #[test]
+    fn bundle_rule_with_hex_and_regex() {
+        let json = r#"{
+            "name": "loader",
+            "strings": [
+                { "id": "$code", "pattern": "{ 48 83 EC 28 E8 ?? ?? ?? ?? ( 90 | C3 ) [1-2] 48 8? ~00 }" },
+                { "id": "$name", "pattern": "/load(er|lib)[0-9]+/ nocase" }
+            ],
+            "condition": "$code and $name and filesize < 1KB"
+        }"#;
+        let rule: PatternRule = serde_json::from_str(json).unwrap();
+        let scanner = PatternScanner::new(&[rule]).unwrap();
+        let hits = scanner.scan_file(fixture("loader.bin").to_str().unwrap()).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].strings, vec!["$code", "$name"]);
+        assert_eq!(hits[0].risk, 80);
+
+        let bad = json.replace("?? ?? ?? ??", "?? ?? ??").replace("1-2", "3-4");
+        assert!(scan(&PatternScanner::new(&[serde_json::from_str(&bad).unwrap()]).unwrap(), "loader.bin").is_empty());
+        assert!(serde_json::from_str::<PatternRule>(&json.replace("[1-2]", "[2000]")).is_err());
+    }
+
+    #[test]
+    fn hex_tokens() {
+        let hex = |pattern: &str| rule(&[("$a", pattern)], "$a").unwrap();
+        assert!(matches(hex("{ 4D 5A ?? 4? }"), b"xxMZ\x00\x41"));
+        assert!(!matches(hex("{ 4D 5A ?? 4? }"), b"xxMZ\x00\x51"));
+        assert!(matches(hex("{ 4D 5A ?1 }"), b"MZ\xf1"));
+        assert!(!matches(hex("{ 4D 5A ~00 }"), b"MZ\x00"));
+        assert!(matches(hex("{ 4D 5A [2-] 90 }"), b"MZ\x00\x00\x00\x90"));
+        assert!(!matches(hex("{ 4D 5A [2-] 90 }"), b"MZ\x00\x90"));
+        assert!(matches(hex("{ 4D 5A [4] ( 90 | CC CC ) }"), b"MZ\n\n\n\n\xcc\xcc"));
+    }
+
+    #[test]
+    fn invalid_hex_strings() {
+        let error = |pattern: &str| rule(&[("$a", pattern)], "$a").unwrap_err();
+        assert!(error("{ 4D 5A ( 90 }").ends_with("Unbalanced parentheses"));
+        assert!(error("{ 4D ?? 5A }").ends_with("at least 2 consecutive fixed bytes"));
+        assert!(error("{ 4D 5A [8-2] }").ends_with("Invalid jump '[8-2]'"));
+        assert!(error("{ 4D 5A [0-4096] }").ends_with("exceeds 1024 bytes"));
+        assert!(error("{ 4D 5A 9 }").ends_with("Odd number of hex digits"));
+        assert!(error("{ 4D 5A ZZ }").ends_with("Invalid hex token 'ZZ'"));
+        assert!(error("{ 4D 5A ~?? }").ends_with("Negated wildcard"));
+        assert!(error("4D 5A").contains("Unknown string type"));
+    }
+
+    #[test]
+    fn text_modifiers() {
+        let text = |pattern: &str| rule(&[("$a", pattern)], "$a").unwrap();
+        assert!(matches(text("\"Nal\""), b"\\\\.\\Nal"));
+        assert!(!matches(text("\"Nal\""), b"N\0a\0l\0"));
+        assert!(matches(text("\"Nal\" wide"), b"N\0a\0l\0"));
+        assert!(!matches(text("\"Nal\" wide"), b"Nal"));
+        assert!(matches(text("\"Nal\" wide ascii nocase"), b"n\0A\0L\0"));
+        assert!(matches(text(r#""a\"b\x00\\" ascii"#), b"a\"b\0\\"));
+
+        let error = |pattern: &str| rule(&[("$a", pattern)], "$a").unwrap_err();
+        assert!(error("\"abc").ends_with("Unclosed text string"));
+        assert!(error("\"a\"").ends_with("at least 2 bytes"));
+        assert!(error("\"abc\" fullword").ends_with("Unknown modifier 'fullword'"));
+        assert!(error(r#""a\qb""#).ends_with("Invalid escape '\\q'"));
+        assert!(error("/ab/ wide").ends_with("Unknown modifier 'wide'"));
+        assert!(error("/ab").ends_with("Unclosed regex"));
+        assert!(error("//").ends_with("Empty regex"));
+    }
+
+    #[test]
+    fn conditions() {
+        let strings = [("$s1", "\"one\""), ("$s2", "\"two\""), ("$x", "\"three\"")];
+        let check = |condition: &str, data: &[u8]| matches(rule(&strings, condition).unwrap(), data);
+
+        assert!(check("any of them", b"two"));
+        assert!(!check("all of them", b"one two"));
+        assert!(check("all of ($s*)", b"one two"));
+        assert!(check("2 of ($s1, $s2, $x)", b"three one"));
+        assert!(!check("2 of ($s1, $s2, $x)", b"three"));
+        assert!(!check("2 of ($s1, $s2, $s1)", b"one"));
+        assert!(!check("2 of ($s1, $x, $s*)", b"one"));
+        assert!(check("2 of ($s1, $x, $s*)", b"one two"));
+        assert!(!check("all of ($s2, $s*)", b"two"));
+        assert!(check("$s1 and not ($s2 or $x)", b"one"));
+        assert!(!check("$s1 and not ($s2 or $x)", b"one three"));
+        assert!(check("$s1 AND filesize <= 3", b"one"));
+        assert!(!check("$s1 and filesize > 3", b"one"));
+        assert!(check("$s1 or $s2 and $x", b"one"));
+    }
+
+    #[test]
+    fn invalid_rules() {
+        let strings = [("$a", "\"abc\""), ("$b", "\"def\"")];
+        let error = |condition: &str| rule(&strings, condition).unwrap_err();
+        assert!(error("$a and $c").ends_with("Unknown string '$c'"));
+        assert!(error("3 of them").ends_with("'3 of' with 2 strings"));
+        assert!(error("2 of ($a, $a)").ends_with("'2 of' with 1 strings"));
+        assert!(error("any of ($z*)").ends_with("No string matches '$z*'"));
+        assert!(error("($a or $b").ends_with("Unexpected end of condition"));
+        assert!(error("$a $b").ends_with("Unexpected '$b'"));
+        assert!(error("filesize ! 3").contains("Invalid comparison"));
+        assert!(error("filesize < 4GB").ends_with("Invalid size '4GB'"));
+
+        assert!(rule(&[], "any of them").unwrap_err().ends_with("has no strings"));
+        assert!(rule(&[("$a", "\"abc\""), ("$a", "\"def\"")], "$a").unwrap_err().contains("duplicate id '$a'"));
+        assert!(rule(&[("a", "\"abc\"")], "any of them").unwrap_err().contains("invalid or duplicate id 'a'"));
+        assert_eq!(parse_size("512KB"), Ok(512 * 1024));
+        assert_eq!(parse_size("4mb"), Ok(4 * 1024 * 1024));
+    }
+
+    #[test]
+    fn scans_process_images_once_per_rule() {
+        let image = |name: &str| fixture(name).to_string_lossy().into_owned();
+        let process = |pid: u32, name: &str, path: String| ProcessInfo { name: name.to_string(), pid, path: Some(path), ..Default::default() };
+        let snapshot = SystemSnapshot {
+            processes: vec![
+                process(100, "mapper.exe", image("mapper.exe")),
+                process(101, "mapper.exe", image("mapper.exe")),
+                process(102, "ce-tutorial.exe", image("ce-tutorial.exe")),
+                process(103, "gone.exe", image("gone.exe")),
+            ],
+            ..Default::default()
+        };
+
+        let result = scan_executables(&snapshot);
+        assert!(result.matches_found);
+        assert_eq!(result.matches.len(), 1);
+        assert_eq!((result.matches[0].pid, result.matches[0].rule.as_str()), (Some(100), "kdmapper"));
+        assert_eq!(result.risk_score, 90);
+        assert_eq!((result.scanned_this_cycle, result.cached_count, result.skipped_count), (2, 0, 1));
+
+        // Unchanged files are not read again
+        let again = scan_executables(&snapshot);
+        assert_eq!((again.scanned_this_cycle, again.cached_count, again.bytes_scanned), (0, 2, 0));
+        assert_eq!(again.matches.len(), 1);
+    }
+}
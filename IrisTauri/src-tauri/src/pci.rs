@@ -0,0 +1,655 @@
+//! PCI module - PCIe DMA card detection from the PCI device inventory
+//!
+//! DMA cheat cards (Squirrel, Screamer, LeetDMA, Xilinx/Lattice boards) are FPGAs
+//! behind a PCIe link that read game memory from a second PC. Their firmware
+//! usually pretends to be a network or USB controller, and rarely does it fully:
+//! - vendor/device or subsystem ids of the FPGA vendor or the PCILeech default core
+//! - a well-known retail id reported with another class code, or without subsystem ids
+//! - a configuration space no real device of that kind has (wrong header type,
+//!   broken capability list, no PCI Express capability below a PCIe root or switch port, no BAR)
+//! - no driver, or a driver that fails to start, for a device Windows/Linux knows
+//!
+//! The inventory comes from SetupAPI on Windows (ids, class, driver, problem code)
+//! and from /sys/bus/pci/devices on Linux, which also exposes the config space
+//! (first 64 bytes for users, 256 or 4096 for root).
+
+use serde::{Deserialize, Serialize};
+
+const PCI_CAPABILITY_LIST: u8 = 0x10; // Status register bit
+const CAP_ID_PCI_EXPRESS: u8 = 0x10;
+/// Device/port types of the PCI Express capability whose links lead to PCIe functions
+const PCIE_ROOT_PORT: u8 = 0x4;
+const PCIE_DOWNSTREAM_PORT: u8 = 0x6;
+
+/// FPGA vendors whose ids have no business on a gaming PC: (vendor id, name)
+const FPGA_VENDORS: &[(u16, &str)] = &[
+    (0x10ee, "Xilinx"),
+    (0x1204, "Lattice"),
+    (0x1172, "Altera"),
+    (0x11aa, "Microsemi"),
+];
+
+/// Ids of DMA card firmware and FPGA PCIe cores left at their defaults: (vendor, device, name)
+const DMA_CARD_SIGNATURES: &[(u16, u16, &str)] = &[
+    (0x10ee, 0x0666, "PCILeech FPGA (Screamer / Squirrel / LeetDMA default)"),
+    (0x10ee, 0x7011, "Xilinx 7-series PCIe core default"),
+    (0x10ee, 0x7012, "Xilinx 7-series PCIe core default"),
+    (0x10ee, 0x7014, "Xilinx 7-series PCIe core default"),
+    (0x10ee, 0x7018, "Xilinx 7-series PCIe core default"),
+    (0x10ee, 0x7021, "Xilinx 7-series PCIe core default"),
+    (0x10ee, 0x7022, "Xilinx 7-series PCIe core default"),
+    (0x10ee, 0x7024, "Xilinx 7-series PCIe core default"),
+    (0x10ee, 0x7028, "Xilinx 7-series PCIe core default"),
+];
+
+/// Retail devices DMA firmware commonly impersonates: (vendor, device, class without prog-if, name)
+const KNOWN_DEVICES: &[(u16, u16, u16, &str)] = &[
+    (0x10ec, 0x8168, 0x0200, "Realtek RTL8111/8168 Ethernet"),
+    (0x10ec, 0x8125, 0x0200, "Realtek RTL8125 2.5GbE"),
+    (0x10ec, 0x8136, 0x0200, "Realtek RTL810x Fast Ethernet"),
+    (0x8086, 0x1533, 0x0200, "Intel I210 Ethernet"),
+    (0x8086, 0x1539, 0x0200, "Intel I211 Ethernet"),
+    (0x8086, 0x15f3, 0x0200, "Intel I225-V Ethernet"),
+    (0x8086, 0x125c, 0x0200, "Intel I226-V Ethernet"),
+    (0x8086, 0x10d3, 0x0200, "Intel 82574L Ethernet"),
+    (0x8086, 0x2723, 0x0280, "Intel Wi-Fi 6 AX200"),
+    (0x8086, 0x2725, 0x0280, "Intel Wi-Fi 6E AX210"),
+    (0x10ec, 0xb852, 0x0280, "Realtek RTL8852BE Wi-Fi"),
+    (0x14c3, 0x0616, 0x0280, "MediaTek MT7922 Wi-Fi"),
+    (0x1b21, 0x1242, 0x0c03, "ASMedia ASM1142 USB 3.1"),
+    (0x1b21, 0x2142, 0x0c03, "ASMedia ASM2142 USB 3.1"),
+    (0x1912, 0x0014, 0x0c03, "Renesas uPD720201 USB 3.0"),
+    (0x1912, 0x0015, 0x0c03, "Renesas uPD720202 USB 3.0"),
+    (0x1102, 0x0012, 0x0403, "Creative Sound Core3D"),
+];
+
+/// PCI function as enumerated by the OS
+#[derive(Debug, Clone, Default)]
+pub struct PciDeviceInfo {
+    pub location: String, // segment:bus:device.function
+    pub name: String,
+    pub vendor_id: u16,
+    pub device_id: u16,
+    pub subsystem_vendor_id: u16,
+    pub subsystem_id: u16,
+    pub revision: u8,
+    pub class_code: u32,           // Base class, subclass, prog-if
+    pub driver: Option<String>,    // Bound driver / service
+    pub problem_code: Option<u32>, // Windows device manager problem (Code 10, 28...)
+    pub resources: Option<usize>,  // Assigned memory / IO ranges (Linux)
+    pub config: Vec<u8>,           // Configuration space, as far as readable (Linux)
+    pub upstream: Option<String>,  // Location of the bridge the function sits behind (Linux)
+}
+
+impl PciDeviceInfo {
+    fn ids(&self) -> String {
+        format!("{:04x}:{:04x}", self.vendor_id, self.device_id)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DmaIndicatorKind {
+    DmaCardId,          // Known DMA firmware / default FPGA core id
+    FpgaVendor,         // Vendor or subsystem vendor is an FPGA vendor
+    ClassMismatch,      // Well-known id reported with another class code
+    SubsystemMismatch,  // Claimed retail device without subsystem ids
+    ImplausibleConfig,  // Configuration space or resources no such device has
+    DriverMissing,      // Known device without a working driver
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DmaIndicator {
+    pub kind: DmaIndicatorKind,
+    pub detail: String,
+}
+
+impl DmaIndicator {
+    /// Risk points of the indicator
+    pub fn weight(&self) -> u32 {
+        match self.kind {
+            DmaIndicatorKind::DmaCardId => 100,
+            DmaIndicatorKind::FpgaVendor => 70,
+            DmaIndicatorKind::ClassMismatch => 80,
+            DmaIndicatorKind::SubsystemMismatch => 30,
+            DmaIndicatorKind::ImplausibleConfig => 50,
+            DmaIndicatorKind::DriverMissing => 30,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SuspiciousPciDevice {
+    pub location: String,
+    pub name: String,
+    pub ids: String,       // vendor:device
+    pub subsystem: String, // subsystem vendor:subsystem device
+    pub class_code: String,
+    pub driver: Option<String>,
+    pub indicators: Vec<DmaIndicator>,
+    pub risk_score: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct DmaDeviceResult {
+    pub dma_device_detected: bool,
+    pub devices_scanned: usize,
+    pub suspicious_devices: Vec<SuspiciousPciDevice>,
+    pub risk_score: u32,
+}
+
+/// Check the PCI inventory of the snapshot for DMA cards
+pub fn detect_dma_devices(devices: &[PciDeviceInfo]) -> DmaDeviceResult {
+    let mut result = DmaDeviceResult { devices_scanned: devices.len(), ..Default::default() };
+
+    for device in devices {
+        let upstream = device.upstream.as_ref()
+            .and_then(|location| devices.iter().find(|d| &d.location == location));
+        let indicators = inspect(device, upstream);
+        if indicators.is_empty() {
+            continue;
+        }
+        let risk_score = indicators.iter().map(|i| i.weight()).sum::<u32>().min(100);
+        result.risk_score += risk_score;
+        result.suspicious_devices.push(SuspiciousPciDevice {
+            location: device.location.clone(),
+            name: device.name.clone(),
+            ids: device.ids(),
+            subsystem: format!("{:04x}:{:04x}", device.subsystem_vendor_id, device.subsystem_id),
+            class_code: format!("{:06x}", device.class_code),
+            driver: device.driver.clone(),
+            indicators,
+            risk_score,
+        });
+    }
+
+    // A lone weak indicator (no driver, zeroed subsystem) is not a DMA card on its own
+    result.dma_device_detected = result.suspicious_devices.iter().any(|d| d.risk_score >= 60);
+    if result.dma_device_detected {
+        println!("[PCI] {} suspicious PCI device(s) out of {}", result.suspicious_devices.len(), devices.len());
+    }
+    result
+}
+
+/// Indicators of one PCI function (`upstream`: the bridge it sits behind, when known)
+pub fn inspect(device: &PciDeviceInfo, upstream: Option<&PciDeviceInfo>) -> Vec<DmaIndicator> {
+    let mut indicators = Vec::new();
+    let mut push = |kind: DmaIndicatorKind, detail: String| indicators.push(DmaIndicator { kind, detail });
+    let class = (device.class_code >> 8) as u16;
+    let fpga_vendor = |id: u16| FPGA_VENDORS.iter().find(|(vendor, _)| *vendor == id).map(|(_, name)| *name);
+
+    // Ids
+    if let Some((_, _, name)) = DMA_CARD_SIGNATURES.iter().find(|(v, d, _)| *v == device.vendor_id && *d == device.device_id) {
+        push(DmaIndicatorKind::DmaCardId, format!("{} is {}", device.ids(), name));
+    } else if let Some(vendor) = fpga_vendor(device.vendor_id) {
+        push(DmaIndicatorKind::FpgaVendor, format!("Vendor {:04x} is {}", device.vendor_id, vendor));
+    }
+    if fpga_vendor(device.vendor_id).is_none() {
+        if let Some(vendor) = fpga_vendor(device.subsystem_vendor_id) {
+            push(DmaIndicatorKind::FpgaVendor, format!("{} with subsystem vendor {:04x} ({})",
+                                                       device.ids(), device.subsystem_vendor_id, vendor));
+        }
+    }
+
+    // Claimed identity
+    let known = KNOWN_DEVICES.iter().find(|(v, d, _, _)| *v == device.vendor_id && *d == device.device_id);
+    if let Some((_, _, expected, name)) = known {
+        if class != *expected {
+            push(DmaIndicatorKind::ClassMismatch, format!("{} ({}) reports class {:04x}, expected {:04x}",
+                                                          device.ids(), name, class, expected));
+        }
+        // Only missing subsystem ids are flagged: boards and OEMs put their own vendor there
+        // (ASUS 1043, MSI 1462, Dell 1028...), so it is not compared with the chip vendor
+        if device.subsystem_vendor_id == 0 || device.subsystem_vendor_id == 0xffff {
+            push(DmaIndicatorKind::SubsystemMismatch, format!("{} ({}) has no subsystem ids", device.ids(), name));
+        }
+        if device.driver.is_none() {
+            push(DmaIndicatorKind::DriverMissing, format!("No driver for {} ({})", device.ids(), name));
+        } else if let Some(problem) = device.problem_code.filter(|p| *p != 0) {
+            push(DmaIndicatorKind::DriverMissing, format!("{} ({}) driver failed with code {}", device.ids(), name, problem));
+        }
+    }
+
+    // Configuration space (Linux)
+    for detail in config_anomalies(device, upstream) {
+        push(DmaIndicatorKind::ImplausibleConfig, detail);
+    }
+    // Functional devices (storage, network, display, multimedia, serial bus) decode at least one BAR
+    let functional = matches!(class >> 8, 0x01..=0x04) || class == 0x0c03;
+    if functional && device.resources == Some(0) {
+        push(DmaIndicatorKind::ImplausibleConfig, format!("{} class {:04x} has no memory or IO resource", device.ids(), class));
+    }
+
+    indicators
+}
+
+/// Inconsistencies of the readable part of the configuration space
+fn config_anomalies(device: &PciDeviceInfo, upstream: Option<&PciDeviceInfo>) -> Vec<String> {
+    let config = &device.config;
+    let mut anomalies = Vec::new();
+    if config.len() < 64 {
+        return anomalies;
+    }
+    let word = |offset: usize| u16::from_le_bytes([config[offset], config[offset + 1]]);
+
+    if word(0) != device.vendor_id || word(2) != device.device_id {
+        anomalies.push(format!("Config space reads {:04x}:{:04x}, enumerated as {}", word(0), word(2), device.ids()));
+    }
+
+    // PCI-to-PCI bridges use header type 1, CardBus bridges 2, everything else 0
+    let header_type = config[0x0e] & 0x7f;
+    let expected = match device.class_code >> 8 {
+        0x0604 | 0x0609 => 1,
+        0x0607 => 2,
+        _ => 0,
+    };
+    if header_type != expected {
+        anomalies.push(format!("Header type {} does not match class {:06x}", header_type, device.class_code));
+    }
+    if header_type != 0 {
+        return anomalies; // Bridges and CardBus have another layout
+    }
+
+    let pointer = config[0x34] & 0xfc;
+    if has_capability_list(config) && pointer != 0 && pointer < 0x40 {
+        anomalies.push(format!("Capability pointer {:#04x} inside the standard header", pointer));
+    }
+
+    // The capability list itself needs the 256-byte space (root)
+    if config.len() >= 256 {
+        let capabilities = match capabilities(config) {
+            Ok(capabilities) => capabilities,
+            Err(offset) => {
+                anomalies.push(format!("Broken capability list at {:#04x}", offset));
+                return anomalies;
+            }
+        };
+        // Below a PCIe root or switch port every function is PCIe; behind a PCIe-to-PCI
+        // bridge (or with an unknown parent) conventional PCI devices are legitimate
+        let pcie_link = upstream.and_then(|bridge| pcie_port_type(&bridge.config))
+            .is_some_and(|port| port == PCIE_ROOT_PORT || port == PCIE_DOWNSTREAM_PORT);
+        if pcie_link && !capabilities.iter().any(|&(id, _)| id == CAP_ID_PCI_EXPRESS) {
+            anomalies.push("No PCI Express capability on an add-in card".to_string());
+        }
+    }
+    anomalies
+}
+
+fn has_capability_list(config: &[u8]) -> bool {
+    config.len() >= 64 && u16::from_le_bytes([config[0x06], config[0x07]]) & PCI_CAPABILITY_LIST as u16 != 0
+}
+
+/// (id, offset) of the capabilities; Err(offset) where the list leaves the 256-byte space or loops
+fn capabilities(config: &[u8]) -> Result<Vec<(u8, usize)>, usize> {
+    let mut capabilities = Vec::new();
+    if !has_capability_list(config) {
+        return Ok(capabilities);
+    }
+    let mut offset = (config[0x34] & 0xfc) as usize;
+    while offset != 0 {
+        if offset < 0x40 || offset + 1 >= config.len() || capabilities.len() >= 48 {
+            return Err(offset);
+        }
+        capabilities.push((config[offset], offset));
+        offset = (config[offset + 1] & 0xfc) as usize;
+    }
+    Ok(capabilities)
+}
+
+/// Device/port type of the PCI Express capability (root port, switch port, PCIe-to-PCI bridge...)
+fn pcie_port_type(config: &[u8]) -> Option<u8> {
+    if config.len() < 256 {
+        return None;
+    }
+    let (_, offset) = capabilities(config).ok()?.into_iter().find(|&(id, _)| id == CAP_ID_PCI_EXPRESS)?;
+    Some(config.get(offset + 2)? >> 4)
+}
+
+/// Vendor, device, subsystem vendor, subsystem device and revision of a PnP hardware id
+/// (`PCI\VEN_10EE&DEV_0666&SUBSYS_000710EE&REV_02`)
+pub fn parse_hardware_id(id: &str) -> Option<(u16, u16, u16, u16, u8)> {
+    let upper = id.to_uppercase();
+    let field = |name: &str, digits: usize| -> Option<u32> {
+        let start = upper.find(name)? + name.len();
+        u32::from_str_radix(upper.get(start..start + digits)?, 16).ok()
+    };
+    let vendor = field("VEN_", 4)? as u16;
+    let device = field("DEV_", 4)? as u16;
+    // SUBSYS_ddddvvvv: subsystem device, then subsystem vendor
+    let subsys = field("SUBSYS_", 8).unwrap_or(0);
+    let revision = field("REV_", 2).unwrap_or(0) as u8;
+    Some((vendor, device, subsys as u16, (subsys >> 16) as u16, revision))
+}
+
+/// Class code of a PnP compatible id (`PCI\CC_020000`, or `CC_0200` without prog-if)
+pub fn parse_class_code(id: &str) -> Option<u32> {
+    let upper = id.to_uppercase();
+    let start = upper.find("CC_")? + 3;
+    let digits: String = upper[start..].chars().take_while(|c| c.is_ascii_hexdigit()).collect();
+    match digits.len() {
+        6 => u32::from_str_radix(&digits, 16).ok(),
+        4 => u32::from_str_radix(&digits, 16).ok().map(|class| class << 8),
+        _ => None,
+    }
+}
+
+/// PCI devices of the live system
+#[cfg(target_os = "windows")]
+pub fn list_devices() -> Vec<PciDeviceInfo> {
+    use windows::Win32::Devices::DeviceAndDriverInstallation::{
+        CM_Get_DevNode_Status, SetupDiDestroyDeviceInfoList, SetupDiEnumDeviceInfo, SetupDiGetClassDevsW,
+        SetupDiGetDeviceRegistryPropertyW, CM_DEVNODE_STATUS_FLAGS, CM_PROBLEM, CR_SUCCESS, DIGCF_ALLCLASSES,
+        DIGCF_PRESENT, HDEVINFO, SETUP_DI_REGISTRY_PROPERTY, SPDRP_ADDRESS, SPDRP_BUSNUMBER, SPDRP_COMPATIBLEIDS,
+        SPDRP_DEVICEDESC, SPDRP_FRIENDLYNAME, SPDRP_HARDWAREID, SPDRP_SERVICE, SP_DEVINFO_DATA,
+    };
+    use windows::Win32::Foundation::HWND;
+    use windows::core::PCWSTR;
+
+    /// Raw registry property of a device
+    unsafe fn property(set: HDEVINFO, data: &SP_DEVINFO_DATA, prop: SETUP_DI_REGISTRY_PROPERTY) -> Option<Vec<u8>> {
+        let mut buffer = vec![0u8; 2048];
+        let mut size: u32 = 0;
+        SetupDiGetDeviceRegistryPropertyW(set, data, prop, None, Some(&mut buffer), Some(&mut size)).ok()?;
+        buffer.truncate(size as usize);
+        Some(buffer)
+    }
+
+    /// REG_SZ / REG_MULTI_SZ property as strings
+    unsafe fn strings(set: HDEVINFO, data: &SP_DEVINFO_DATA, prop: SETUP_DI_REGISTRY_PROPERTY) -> Vec<String> {
+        let bytes = property(set, data, prop).unwrap_or_default();
+        let wide: Vec<u16> = bytes.chunks_exact(2).map(|c| u16::from_le_bytes([c[0], c[1]])).collect();
+        wide.split(|c| *c == 0)
+            .map(String::from_utf16_lossy)
+            .filter(|s| !s.is_empty())
+            .collect()
+    }
+
+    unsafe fn number(set: HDEVINFO, data: &SP_DEVINFO_DATA, prop: SETUP_DI_REGISTRY_PROPERTY) -> Option<u32> {
+        let bytes = property(set, data, prop)?;
+        Some(u32::from_le_bytes(bytes.get(0..4)?.try_into().ok()?))
+    }
+
+    let mut devices = Vec::new();
+    let enumerator: Vec<u16> = "PCI\0".encode_utf16().collect();
+
+    unsafe {
+        let set = match SetupDiGetClassDevsW(None, PCWSTR(enumerator.as_ptr()), HWND::default(), DIGCF_PRESENT | DIGCF_ALLCLASSES) {
+            Ok(set) => set,
+            Err(e) => {
+                println!("[PCI] SetupDiGetClassDevs failed: {}", e);
+                return devices;
+            }
+        };
+
+        let mut index = 0;
+        loop {
+            let mut data = SP_DEVINFO_DATA { cbSize: std::mem::size_of::<SP_DEVINFO_DATA>() as u32, ..Default::default() };
+            if SetupDiEnumDeviceInfo(set, index, &mut data).is_err() {
+                break;
+            }
+            index += 1;
+
+            let hardware_ids = strings(set, &data, SPDRP_HARDWAREID);
+            let (vendor_id, device_id, subsystem_vendor_id, subsystem_id, revision) =
+                match hardware_ids.iter().find_map(|id| parse_hardware_id(id)) {
+                    Some(ids) => ids,
+                    None => continue,
+                };
+            let class_code = strings(set, &data, SPDRP_COMPATIBLEIDS).iter()
+                .chain(hardware_ids.iter())
+                .find_map(|id| parse_class_code(id))
+                .unwrap_or(0);
+
+            let bus = number(set, &data, SPDRP_BUSNUMBER).unwrap_or(0);
+            let address = number(set, &data, SPDRP_ADDRESS).unwrap_or(0); // (device << 16) | function
+            let name = strings(set, &data, SPDRP_FRIENDLYNAME).into_iter().next()
+                .or_else(|| strings(set, &data, SPDRP_DEVICEDESC).into_iter().next())
+                .unwrap_or_else(|| format!("PCI device {:04x}:{:04x}", vendor_id, device_id));
+
+            let mut status = CM_DEVNODE_STATUS_FLAGS(0);
+            let mut problem = CM_PROBLEM(0);
+            let problem_code = if CM_Get_DevNode_Status(&mut status, &mut problem, data.DevInst, 0) == CR_SUCCESS {
+                Some(problem.0)
+            } else {
+                None
+            };
+
+            devices.push(PciDeviceInfo {
+                location: format!("0000:{:02x}:{:02x}.{:x}", bus, address >> 16, address & 0xffff),
+                name,
+                vendor_id,
+                device_id,
+                subsystem_vendor_id,
+                subsystem_id,
+                revision,
+                class_code,
+                driver: strings(set, &data, SPDRP_SERVICE).into_iter().next(),
+                problem_code,
+                resources: None,
+                config: Vec::new(),
+                upstream: None,
+            });
+        }
+
+        let _ = SetupDiDestroyDeviceInfoList(set);
+    }
+
+    devices
+}
+
+#[cfg(target_os = "linux")]
+pub fn list_devices() -> Vec<PciDeviceInfo> {
+    crate::sysfs::list_pci_devices()
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+pub fn list_devices() -> Vec<PciDeviceInfo> {
+    Vec::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 256-byte configuration space (root read) with a capability chain from 0x40
+    fn config(vendor: u16, device: u16, header_type: u8, capabilities: &[(u8, u8)]) -> Vec<u8> {
+        let mut config = vec![0u8; 256];
+        config[0..2].copy_from_slice(&vendor.to_le_bytes());
+        config[2..4].copy_from_slice(&device.to_le_bytes());
+        config[0x0e] = header_type;
+        if !capabilities.is_empty() {
+            config[0x06] = PCI_CAPABILITY_LIST;
+            config[0x34] = 0x40;
+        }
+        for (index, &(id, register)) in capabilities.iter().enumerate() {
+            let offset = 0x40 + index * 0x10;
+            config[offset] = id;
+            config[offset + 1] = if index + 1 < capabilities.len() { (offset + 0x10) as u8 } else { 0 };
+            config[offset + 2] = register;
+        }
+        config
+    }
+
+    fn bridge(location: &str, port_type: u8) -> PciDeviceInfo {
+        PciDeviceInfo {
+            location: location.to_string(),
+            vendor_id: 0x8086,
+            device_id: 0x7ab8,
+            class_code: 0x060400,
+            config: config(0x8086, 0x7ab8, 1, &[(CAP_ID_PCI_EXPRESS, port_type << 4 | 2)]),
+            ..Default::default()
+        }
+    }
+
+    /// Conventional PCI sound card (power management capability only)
+    fn sound_card(upstream: Option<&str>) -> PciDeviceInfo {
+        PciDeviceInfo {
+            location: "0000:05:01.0".to_string(),
+            vendor_id: 0x1274,
+            device_id: 0x5000,
+            subsystem_vendor_id: 0x1274,
+            subsystem_id: 0x2000,
+            class_code: 0x040100,
+            driver: Some("snd_ens1370".to_string()),
+            resources: Some(1),
+            config: config(0x1274, 0x5000, 0, &[(0x01, 0x02)]),
+            upstream: upstream.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    fn missing_pcie(devices: &[PciDeviceInfo]) -> bool {
+        detect_dma_devices(devices).suspicious_devices.iter()
+            .flat_map(|device| &device.indicators)
+            .any(|indicator| indicator.detail == "No PCI Express capability on an add-in card")
+    }
+
+    #[test]
+    fn conventional_pci_behind_pcie_to_pci_bridge() {
+        let devices = [bridge("0000:00:1c.0", PCIE_ROOT_PORT), bridge("0000:04:00.0", 0x7), sound_card(Some("0000:04:00.0"))];
+        let result = detect_dma_devices(&devices);
+        assert!(!missing_pcie(&devices));
+        assert!(!result.dma_device_detected);
+    }
+
+    #[test]
+    fn conventional_function_on_a_pcie_link() {
+        assert!(missing_pcie(&[bridge("0000:00:1c.0", PCIE_ROOT_PORT), sound_card(Some("0000:00:1c.0"))]));
+        assert!(missing_pcie(&[bridge("0000:02:08.0", PCIE_DOWNSTREAM_PORT), sound_card(Some("0000:02:08.0"))]));
+
+        // A PCIe function below the same port is fine
+        let mut card = sound_card(Some("0000:00:1c.0"));
+        card.config = config(0x1274, 0x5000, 0, &[(0x01, 0x02), (CAP_ID_PCI_EXPRESS, 0x02)]);
+        assert!(!missing_pcie(&[bridge("0000:00:1c.0", PCIE_ROOT_PORT), card]));
+    }
+
+    #[test]
+    fn unknown_upstream_is_not_judged() {
+        assert!(!missing_pcie(&[sound_card(None)]));
+        // Parent that was not enumerated, or whose config space is not readable
+        assert!(!missing_pcie(&[sound_card(Some("0000:00:1e.0"))]));
+        let mut hidden = bridge("0000:00:1e.0", PCIE_ROOT_PORT);
+        hidden.config.truncate(64);
+        assert!(!missing_pcie(&[hidden, sound_card(Some("0000:00:1e.0"))]));
+    }
+
+    #[test]
+    fn broken_capability_list() {
+        let mut card = sound_card(None);
+        card.config[0x41] = 0x40; // Points back to itself
+        let anomalies = config_anomalies(&card, None);
+        assert_eq!(anomalies, vec!["Broken capability list at 0x40".to_string()]);
+    }
+
+    /// Retail function with a driver and its resources
+    fn device(vendor: u16, device: u16, class_code: u32, subsystem: (u16, u16)) -> PciDeviceInfo {
+        PciDeviceInfo {
+            location: "0000:03:00.0".to_string(),
+            vendor_id: vendor,
+            device_id: device,
+            subsystem_vendor_id: subsystem.0,
+            subsystem_id: subsystem.1,
+            class_code,
+            driver: Some("driver".to_string()),
+            resources: Some(2),
+            ..Default::default()
+        }
+    }
+
+    fn kinds(device: &PciDeviceInfo) -> Vec<DmaIndicatorKind> {
+        inspect(device, None).iter().map(|i| i.kind).collect()
+    }
+
+    #[test]
+    fn retail_devices_are_clean() {
+        let devices = [
+            device(0x8086, 0x1533, 0x020000, (0x1043, 0x8672)), // I210 on an ASUS board
+            device(0x10ec, 0x8168, 0x020000, (0x1462, 0x7c56)),
+            device(0x1b21, 0x2142, 0x0c0330, (0x1b21, 0x2142)),
+        ];
+        let result = detect_dma_devices(&devices);
+        assert_eq!(result.devices_scanned, 3);
+        assert!(result.suspicious_devices.is_empty());
+        assert!(!result.dma_device_detected);
+    }
+
+    #[test]
+    fn dma_card_signature() {
+        let card = device(0x10ee, 0x0666, 0x020000, (0x10ee, 0x0007));
+        let indicators = inspect(&card, None);
+        // The FPGA vendor is not counted twice
+        assert_eq!(indicators.iter().map(|i| i.kind).collect::<Vec<_>>(), vec![DmaIndicatorKind::DmaCardId]);
+        assert_eq!(indicators[0].detail, "10ee:0666 is PCILeech FPGA (Screamer / Squirrel / LeetDMA default)");
+        for &(vendor, device_id, _) in DMA_CARD_SIGNATURES {
+            assert_eq!(kinds(&device(vendor, device_id, 0x058000, (0, 0))), vec![DmaIndicatorKind::DmaCardId]);
+        }
+
+        let result = detect_dma_devices(&[card]);
+        assert!(result.dma_device_detected);
+        assert_eq!(result.risk_score, 100);
+        assert_eq!(result.suspicious_devices[0].subsystem, "10ee:0007");
+        assert_eq!(result.suspicious_devices[0].class_code, "020000");
+    }
+
+    #[test]
+    fn fpga_vendor_and_subsystem_vendor() {
+        let core = device(0x1204, 0x9c25, 0x058000, (0x1204, 0x0001));
+        let indicators = inspect(&core, None);
+        assert_eq!(indicators.len(), 1);
+        assert_eq!((indicators[0].kind, indicators[0].detail.as_str()), (DmaIndicatorKind::FpgaVendor, "Vendor 1204 is Lattice"));
+
+        // Realtek NIC whose firmware kept the Xilinx subsystem vendor
+        let nic = device(0x10ec, 0x8168, 0x020000, (0x10ee, 0x0007));
+        let indicators = inspect(&nic, None);
+        assert_eq!(indicators.len(), 1);
+        assert_eq!((indicators[0].kind, indicators[0].detail.as_str()),
+                   (DmaIndicatorKind::FpgaVendor, "10ec:8168 with subsystem vendor 10ee (Xilinx)"));
+        assert!(detect_dma_devices(&[nic]).dma_device_detected);
+    }
+
+    #[test]
+    fn class_mismatch() {
+        let fake = device(0x8086, 0x1533, 0x0c0330, (0x8086, 0x0001));
+        let indicators = inspect(&fake, None);
+        assert_eq!(indicators.iter().map(|i| i.kind).collect::<Vec<_>>(), vec![DmaIndicatorKind::ClassMismatch]);
+        assert_eq!(indicators[0].detail, "8086:1533 (Intel I210 Ethernet) reports class 0c03, expected 0200");
+        assert!(detect_dma_devices(&[fake]).dma_device_detected);
+    }
+
+    #[test]
+    fn weak_indicators_alone_are_not_a_detection() {
+        let mut bare = device(0x10ec, 0x8125, 0x020000, (0, 0));
+        assert_eq!(kinds(&bare), vec![DmaIndicatorKind::SubsystemMismatch]);
+        assert!(!detect_dma_devices(std::slice::from_ref(&bare)).dma_device_detected);
+
+        bare.problem_code = Some(10);
+        assert_eq!(inspect(&bare, None)[1].detail, "10ec:8125 (Realtek RTL8125 2.5GbE) driver failed with code 10");
+        bare.driver = None;
+        bare.resources = Some(0);
+        assert_eq!(kinds(&bare), vec![DmaIndicatorKind::SubsystemMismatch, DmaIndicatorKind::DriverMissing,
+                                      DmaIndicatorKind::ImplausibleConfig]);
+        assert!(detect_dma_devices(&[bare]).dma_device_detected);
+    }
+
+    #[test]
+    fn parses_hardware_ids() {
+        // SUBSYS_ddddvvvv: subsystem device first, then subsystem vendor
+        assert_eq!(parse_hardware_id("PCI\\VEN_10EE&DEV_0666&SUBSYS_000710EE&REV_02"), Some((0x10ee, 0x0666, 0x10ee, 0x0007, 2)));
+        assert_eq!(parse_hardware_id("pci\\ven_8086&dev_1533&subsys_86721043&rev_03"), Some((0x8086, 0x1533, 0x1043, 0x8672, 3)));
+        assert_eq!(parse_hardware_id("PCI\\VEN_10EC&DEV_8168"), Some((0x10ec, 0x8168, 0, 0, 0)));
+        assert_eq!(parse_hardware_id("PCI\\VEN_10EC&DEV_81"), None);
+        assert_eq!(parse_hardware_id("PCI\\CC_020000"), None);
+    }
+
+    #[test]
+    fn parses_class_codes() {
+        assert_eq!(parse_class_code("PCI\\VEN_8086&DEV_1533&CC_020000"), Some(0x020000));
+        assert_eq!(parse_class_code("PCI\\CC_0c0330"), Some(0x0c0330));
+        assert_eq!(parse_class_code("PCI\\CC_0200"), Some(0x020000));
+        assert_eq!(parse_class_code("PCI\\CC_02"), None);
+        assert_eq!(parse_class_code("PCI\\VEN_8086&DEV_1533"), None);
+    }
+}
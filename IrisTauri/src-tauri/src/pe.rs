@@ -0,0 +1,707 @@
+//! PE inspector module - pure-Rust parsing of Windows executables and DLLs
+//!
+//! Reads VERSIONINFO strings, imports, sections (with entropy), the compile
+//! timestamp and the Authenticode certificate table. Works on any platform.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+/// Files larger than this are not inspected
+const MAX_FILE_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Cached inspections before the cache is reset
+const MAX_CACHE_ENTRIES: usize = 2048;
+
+const MAX_IMPORTED_DLLS: usize = 256;
+const MAX_IMPORTED_FUNCTIONS: usize = 2048;
+const MAX_SECTIONS: usize = 96;
+
+const DIR_IMPORT: usize = 1;
+const DIR_RESOURCE: usize = 2;
+const DIR_SECURITY: usize = 4;
+
+const RT_VERSION: u32 = 16;
+const WIN_CERT_TYPE_PKCS_SIGNED_DATA: u16 = 0x0002;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct VersionInfo {
+    pub original_filename: Option<String>,
+    pub company_name: Option<String>,
+    pub product_name: Option<String>,
+    pub file_description: Option<String>,
+    pub file_version: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PeSection {
+    pub name: String,
+    pub virtual_size: u32,
+    pub raw_size: u32,
+    pub entropy: f32,      // Shannon entropy of the raw data (0-8, >7.2 usually packed/encrypted)
+    pub executable: bool,
+    pub writable: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PeImport {
+    pub dll: String,
+    pub functions: Vec<String>, // "#123" for imports by ordinal
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct PeInfo {
+    pub machine: String,
+    pub is_64bit: bool,
+    pub is_dll: bool,
+    pub compile_timestamp: u32, // COFF TimeDateStamp (hash on reproducible builds)
+    pub version_info: Option<VersionInfo>,
+    pub sections: Vec<PeSection>,
+    pub imports: Vec<PeImport>,
+    pub has_authenticode: bool,
+}
+
+/// Raw header layout (offsets into the file) used by the Authenticode verifier
+#[derive(Debug, Clone)]
+pub struct PeLayout {
+    pub is_64bit: bool,
+    pub is_dll: bool,
+    pub machine: u16,
+    pub timestamp: u32,
+    pub checksum_offset: usize,
+    pub security_dir_offset: Option<usize>, // Offset of the security data directory entry
+    pub size_of_headers: usize,
+    pub sections: Vec<SectionHeader>,
+    directories: Vec<(u32, u32)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SectionHeader {
+    pub name: String,
+    pub virtual_size: u32,
+    pub virtual_address: u32,
+    pub raw_size: u32,
+    pub raw_offset: u32,
+    pub characteristics: u32,
+}
+
+impl PeLayout {
+    /// (file offset, size) of a data directory, if present
+    fn directory(&self, index: usize) -> Option<(u32, u32)> {
+        self.directories.get(index).copied().filter(|(rva, size)| *rva != 0 && *size != 0)
+    }
+
+    /// Certificate table (file offset, size). The security directory holds a file offset, not an RVA.
+    pub fn certificate_table(&self) -> Option<(usize, usize)> {
+        self.directory(DIR_SECURITY).map(|(offset, size)| (offset as usize, size as usize))
+    }
+
+    fn rva_to_offset(&self, rva: u32) -> Option<usize> {
+        if (rva as usize) < self.size_of_headers {
+            return Some(rva as usize);
+        }
+        self.sections.iter()
+            .find(|s| rva >= s.virtual_address && rva < s.virtual_address.saturating_add(s.virtual_size.max(s.raw_size)))
+            .map(|s| (rva - s.virtual_address) as usize + s.raw_offset as usize)
+    }
+}
+
+// ====== LITTLE-ENDIAN READERS ======
+
+fn u16_at(data: &[u8], offset: usize) -> Option<u16> {
+    data.get(offset..offset + 2).map(|b| u16::from_le_bytes([b[0], b[1]]))
+}
+
+fn u32_at(data: &[u8], offset: usize) -> Option<u32> {
+    data.get(offset..offset + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+fn u64_at(data: &[u8], offset: usize) -> Option<u64> {
+    data.get(offset..offset + 8).map(|b| {
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(b);
+        u64::from_le_bytes(bytes)
+    })
+}
+
+/// NUL-terminated ASCII string (bounded)
+fn cstr_at(data: &[u8], offset: usize, max_len: usize) -> Option<String> {
+    let bytes = data.get(offset..)?;
+    let end = bytes.iter().take(max_len).position(|&b| b == 0)?;
+    Some(String::from_utf8_lossy(&bytes[..end]).to_string())
+}
+
+/// NUL-terminated UTF-16LE string, returns the string and its length in bytes (terminator included)
+fn wstr_at(data: &[u8], offset: usize) -> Option<(String, usize)> {
+    let mut units = Vec::new();
+    let mut pos = offset;
+    loop {
+        let unit = u16_at(data, pos)?;
+        pos += 2;
+        if unit == 0 {
+            break;
+        }
+        units.push(unit);
+        if units.len() > 1024 {
+            return None;
+        }
+    }
+    Some((String::from_utf16_lossy(&units), pos - offset))
+}
+
+fn align4(value: usize) -> usize {
+    (value + 3) & !3
+}
+
+// ====== HEADERS ======
+
+/// Parse DOS/COFF/optional headers and the section table
+pub fn layout(data: &[u8]) -> Result<PeLayout, String> {
+    if data.get(0..2) != Some(b"MZ") {
+        return Err("Not a PE file (missing MZ)".to_string());
+    }
+    let pe_offset = u32_at(data, 0x3C).ok_or("Truncated DOS header")? as usize;
+    if data.get(pe_offset..pe_offset + 4) != Some(b"PE\0\0") {
+        return Err("Not a PE file (missing PE signature)".to_string());
+    }
+
+    let coff = pe_offset + 4;
+    let machine = u16_at(data, coff).ok_or("Truncated COFF header")?;
+    let section_count = u16_at(data, coff + 2).ok_or("Truncated COFF header")? as usize;
+    let timestamp = u32_at(data, coff + 4).ok_or("Truncated COFF header")?;
+    let optional_size = u16_at(data, coff + 16).ok_or("Truncated COFF header")? as usize;
+    let characteristics = u16_at(data, coff + 18).ok_or("Truncated COFF header")?;
+
+    let optional = coff + 20;
+    let is_64bit = match u16_at(data, optional).ok_or("Truncated optional header")? {
+        0x10b => false,
+        0x20b => true,
+        magic => return Err(format!("Unknown optional header magic 0x{:x}", magic)),
+    };
+
+    let size_of_headers = u32_at(data, optional + 60).ok_or("Truncated optional header")? as usize;
+    let checksum_offset = optional + 64;
+    let (count_offset, dirs_offset) = if is_64bit { (optional + 108, optional + 112) } else { (optional + 92, optional + 96) };
+    let dir_count = (u32_at(data, count_offset).unwrap_or(0) as usize).min(16);
+
+    let mut directories = Vec::with_capacity(dir_count);
+    for i in 0..dir_count {
+        let entry = dirs_offset + i * 8;
+        if entry + 8 > optional + optional_size {
+            break;
+        }
+        directories.push((u32_at(data, entry).unwrap_or(0), u32_at(data, entry + 4).unwrap_or(0)));
+    }
+    let security_dir_offset = if directories.len() > DIR_SECURITY {
+        Some(dirs_offset + DIR_SECURITY * 8)
+    } else {
+        None
+    };
+
+    let table = optional + optional_size;
+    let mut sections = Vec::new();
+    for i in 0..section_count.min(MAX_SECTIONS) {
+        let header = table + i * 40;
+        let raw_name = data.get(header..header + 8).ok_or("Truncated section table")?;
+        let name_len = raw_name.iter().position(|&b| b == 0).unwrap_or(8);
+        sections.push(SectionHeader {
+            name: String::from_utf8_lossy(&raw_name[..name_len]).to_string(),
+            virtual_size: u32_at(data, header + 8).unwrap_or(0),
+            virtual_address: u32_at(data, header + 12).unwrap_or(0),
+            raw_size: u32_at(data, header + 16).unwrap_or(0),
+            raw_offset: u32_at(data, header + 20).unwrap_or(0),
+            characteristics: u32_at(data, header + 36).unwrap_or(0),
+        });
+    }
+
+    Ok(PeLayout {
+        is_64bit,
+        is_dll: characteristics & 0x2000 != 0,
+        machine,
+        timestamp,
+        checksum_offset,
+        security_dir_offset,
+        size_of_headers,
+        sections,
+        directories,
+    })
+}
+
+// ====== INSPECTION ======
+
+/// Parse a PE image held in memory
+pub fn parse(data: &[u8]) -> Result<PeInfo, String> {
+    let layout = layout(data)?;
+
+    let sections = layout.sections.iter().map(|s| {
+        let start = s.raw_offset as usize;
+        let end = start.saturating_add(s.raw_size as usize).min(data.len());
+        PeSection {
+            name: s.name.clone(),
+            virtual_size: s.virtual_size,
+            raw_size: s.raw_size,
+            entropy: if start < end { entropy(&data[start..end]) } else { 0.0 },
+            executable: s.characteristics & 0x2000_0000 != 0,
+            writable: s.characteristics & 0x8000_0000 != 0,
+        }
+    }).collect();
+
+    let has_authenticode = layout.certificate_table()
+        .filter(|(offset, size)| offset.saturating_add(*size) <= data.len() && *size >= 8)
+        .and_then(|(offset, _)| u16_at(data, offset + 6))
+        .map(|cert_type| cert_type == WIN_CERT_TYPE_PKCS_SIGNED_DATA)
+        .unwrap_or(false);
+
+    Ok(PeInfo {
+        machine: machine_name(layout.machine),
+        is_64bit: layout.is_64bit,
+        is_dll: layout.is_dll,
+        compile_timestamp: layout.timestamp,
+        version_info: parse_version_info(data, &layout),
+        sections,
+        imports: parse_imports(data, &layout),
+        has_authenticode,
+    })
+}
+
+/// Parse a PE file from disk
+pub fn inspect_file(path: &str) -> Result<PeInfo, String> {
+    let size = std::fs::metadata(path).map_err(|e| e.to_string())?.len();
+    if size > MAX_FILE_SIZE {
+        return Err(format!("File too large ({} bytes)", size));
+    }
+    let data = std::fs::read(path).map_err(|e| e.to_string())?;
+    parse(&data)
+}
+
+// ====== CACHE ======
+
+struct CachedInspection {
+    size: u64,
+    modified: Option<SystemTime>,
+    info: Option<PeInfo>,
+}
+
+lazy_static::lazy_static! {
+    static ref INSPECTION_CACHE: Mutex<HashMap<String, CachedInspection>> = Mutex::new(HashMap::new());
+}
+
+/// Inspect a file, logging failures instead of returning them (detector helper).
+/// Results are cached by path, size and modification time, so an unchanged file
+/// is parsed (and its failure logged) once.
+pub fn try_inspect(path: Option<&str>) -> Option<PeInfo> {
+    let path = path.filter(|p| !p.is_empty())?;
+    let metadata = std::fs::metadata(path).ok();
+    let size = metadata.as_ref().map(|m| m.len()).unwrap_or(0);
+    let modified = metadata.as_ref().and_then(|m| m.modified().ok());
+    let key = path.to_lowercase();
+
+    if let Ok(cache) = INSPECTION_CACHE.lock() {
+        if let Some(entry) = cache.get(&key) {
+            if entry.size == size && entry.modified == modified {
+                return entry.info.clone();
+            }
+        }
+    }
+
+    let info = match inspect_file(path) {
+        Ok(info) => Some(info),
+        Err(e) => {
+            println!("[PE] Could not inspect {}: {}", path, e);
+            None
+        }
+    };
+
+    if let Ok(mut cache) = INSPECTION_CACHE.lock() {
+        if cache.len() >= MAX_CACHE_ENTRIES {
+            cache.clear();
+        }
+        cache.insert(key, CachedInspection { size, modified, info: info.clone() });
+    }
+
+    info
+}
+
+fn machine_name(machine: u16) -> String {
+    match machine {
+        0x014c => "x86".to_string(),
+        0x8664 => "x64".to_string(),
+        0xaa64 => "arm64".to_string(),
+        0x01c4 => "arm".to_string(),
+        other => format!("0x{:04x}", other),
+    }
+}
+
+/// Shannon entropy in bits per byte
+pub fn entropy(data: &[u8]) -> f32 {
+    if data.is_empty() {
+        return 0.0;
+    }
+    let mut counts = [0u64; 256];
+    for &byte in data {
+        counts[byte as usize] += 1;
+    }
+    let len = data.len() as f64;
+    let value: f64 = counts.iter()
+        .filter(|&&c| c > 0)
+        .map(|&c| {
+            let p = c as f64 / len;
+            -p * p.log2()
+        })
+        .sum();
+    value as f32
+}
+
+fn parse_imports(data: &[u8], layout: &PeLayout) -> Vec<PeImport> {
+    let mut imports = Vec::new();
+    let (rva, _) = match layout.directory(DIR_IMPORT) {
+        Some(dir) => dir,
+        None => return imports,
+    };
+    let mut descriptor = match layout.rva_to_offset(rva) {
+        Some(offset) => offset,
+        None => return imports,
+    };
+
+    let thunk_size = if layout.is_64bit { 8 } else { 4 };
+    let ordinal_flag: u64 = if layout.is_64bit { 1 << 63 } else { 1 << 31 };
+    let mut total_functions = 0;
+
+    while imports.len() < MAX_IMPORTED_DLLS {
+        let original_thunk = u32_at(data, descriptor).unwrap_or(0);
+        let name_rva = u32_at(data, descriptor + 12).unwrap_or(0);
+        let first_thunk = u32_at(data, descriptor + 16).unwrap_or(0);
+        if name_rva == 0 && first_thunk == 0 {
+            break;
+        }
+        descriptor += 20;
+
+        let dll = match layout.rva_to_offset(name_rva).and_then(|o| cstr_at(data, o, 256)) {
+            Some(name) => name,
+            None => continue,
+        };
+
+        // Bound imports may only have FirstThunk
+        let thunk_rva = if original_thunk != 0 { original_thunk } else { first_thunk };
+        let mut functions = Vec::new();
+        if let Some(mut thunk) = layout.rva_to_offset(thunk_rva) {
+            while total_functions < MAX_IMPORTED_FUNCTIONS {
+                let value = if layout.is_64bit {
+                    u64_at(data, thunk)
+                } else {
+                    u32_at(data, thunk).map(u64::from)
+                };
+                let value = match value {
+                    Some(0) | None => break,
+                    Some(v) => v,
+                };
+                thunk += thunk_size;
+                total_functions += 1;
+
+                if value & ordinal_flag != 0 {
+                    functions.push(format!("#{}", value & 0xffff));
+                } else if let Some(name) = layout.rva_to_offset(value as u32)
+                    .and_then(|o| cstr_at(data, o + 2, 256)) // Skip the hint
+                {
+                    functions.push(name);
+                }
+            }
+        }
+
+        imports.push(PeImport { dll, functions });
+    }
+
+    imports
+}
+
+// ====== RESOURCES / VERSIONINFO ======
+
+/// First entry of a resource directory matching `id` (any entry when None).
+/// Returns (is_subdirectory, offset relative to the resource section).
+fn resource_entry(data: &[u8], base: usize, dir: usize, id: Option<u32>) -> Option<(bool, usize)> {
+    let named = u16_at(data, base + dir + 12)? as usize;
+    let ids = u16_at(data, base + dir + 14)? as usize;
+
+    for i in 0..(named + ids).min(4096) {
+        let entry = base + dir + 16 + i * 8;
+        let name = u32_at(data, entry)?;
+        let target = u32_at(data, entry + 4)?;
+        let matches = match id {
+            Some(id) => name & 0x8000_0000 == 0 && name == id,
+            None => true,
+        };
+        if matches {
+            return Some((target & 0x8000_0000 != 0, (target & 0x7fff_ffff) as usize));
+        }
+    }
+    None
+}
+
+fn version_resource(data: &[u8], layout: &PeLayout) -> Option<(usize, usize)> {
+    let (rva, _) = layout.directory(DIR_RESOURCE)?;
+    let base = layout.rva_to_offset(rva)?;
+
+    // Type (RT_VERSION) -> name -> language -> data entry
+    let (is_dir, names) = resource_entry(data, base, 0, Some(RT_VERSION))?;
+    if !is_dir {
+        return None;
+    }
+    let (is_dir, languages) = resource_entry(data, base, names, None)?;
+    if !is_dir {
+        return None;
+    }
+    let (is_dir, data_entry) = resource_entry(data, base, languages, None)?;
+    if is_dir {
+        return None;
+    }
+
+    let data_rva = u32_at(data, base + data_entry)?;
+    let size = u32_at(data, base + data_entry + 4)? as usize;
+    let offset = layout.rva_to_offset(data_rva)?;
+    if offset.checked_add(size)? > data.len() {
+        return None;
+    }
+    Some((offset, size))
+}
+
+/// Walk a VS_VERSIONINFO block and collect every String entry
+fn collect_version_strings(data: &[u8], offset: usize, end: usize, depth: usize, out: &mut HashMap<String, String>) {
+    if depth > 4 {
+        return;
+    }
+
+    let mut pos = offset;
+    while pos + 6 <= end {
+        let length = match u16_at(data, pos) {
+            Some(0) | None => return,
+            Some(l) => l as usize,
+        };
+        let block_end = (pos + length).min(end);
+        let value_length = u16_at(data, pos + 2).unwrap_or(0) as usize;
+        let value_type = u16_at(data, pos + 4).unwrap_or(0);
+
+        let (key, key_len) = match wstr_at(data, pos + 6) {
+            Some(key) => key,
+            None => return,
+        };
+        let value_start = align4(pos + 6 + key_len);
+
+        match key.as_str() {
+            "VS_VERSION_INFO" => {
+                // Binary VS_FIXEDFILEINFO value, children follow
+                let children = align4(value_start + value_length);
+                collect_version_strings(data, children, block_end, depth + 1, out);
+            }
+            "StringFileInfo" => collect_version_strings(data, value_start, block_end, depth + 1, out),
+            "VarFileInfo" => {}
+            _ if depth >= 3 => {
+                // String entry (value length is in UTF-16 units)
+                if value_type == 1 && value_length > 0 {
+                    if let Some((value, _)) = wstr_at(data, value_start) {
+                        out.insert(key, value.trim().to_string());
+                    }
+                }
+            }
+            _ => {
+                // StringTable ("040904b0") - its children are the strings
+                collect_version_strings(data, value_start, block_end, depth + 1, out);
+            }
+        }
+
+        pos = align4(pos + length);
+    }
+}
+
+fn parse_version_info(data: &[u8], layout: &PeLayout) -> Option<VersionInfo> {
+    let (offset, size) = version_resource(data, layout)?;
+    let mut strings = HashMap::new();
+    collect_version_strings(data, offset, offset + size, 0, &mut strings);
+
+    if strings.is_empty() {
+        return None;
+    }
+
+    let get = |key: &str| strings.get(key).filter(|v| !v.is_empty()).cloned();
+    Some(VersionInfo {
+        original_filename: get("OriginalFilename"),
+        company_name: get("CompanyName"),
+        product_name: get("ProductName"),
+        file_description: get("FileDescription"),
+        file_version: get("FileVersion"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::{Path, PathBuf};
+
+    /// Binaries built from tests/fixtures/pe/source
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/pe").join(name)
+    }
+
+    fn inspect(name: &str) -> PeInfo {
+        inspect_file(fixture(name).to_str().unwrap()).unwrap()
+    }
+
+    fn section<'a>(info: &'a PeInfo, name: &str) -> &'a PeSection {
+        info.sections.iter().find(|s| s.name == name).unwrap()
+    }
+
+    #[test]
+    fn console_exe_x64() {
+        let info = inspect("hello64.exe");
+        assert_eq!(info.machine, "x64");
+        assert!(info.is_64bit && !info.is_dll);
+        assert_eq!(info.compile_timestamp, 1_700_000_000);
+        assert!(!info.has_authenticode);
+
+        let names: Vec<_> = info.sections.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec![".text", ".rdata", ".data", ".rsrc"]);
+        assert!(section(&info, ".text").executable && !section(&info, ".text").writable);
+        assert!(section(&info, ".data").writable && !section(&info, ".data").executable);
+    }
+
+    #[test]
+    fn imports_by_name_and_ordinal() {
+        let info = inspect("hello64.exe");
+        let imports: Vec<_> = info.imports.iter().map(|i| (i.dll.as_str(), i.functions.clone())).collect();
+        assert_eq!(imports, vec![
+            ("kernel32.dll", vec!["ExitProcess".to_string(), "GetStdHandle".to_string(), "WriteFile".to_string()]),
+            ("user32.dll", vec!["MessageBoxW".to_string()]),
+            ("ws2_32.dll", vec!["#115".to_string()]),
+        ]);
+    }
+
+    #[test]
+    fn version_info_strings() {
+        let version = inspect("hello64.exe").version_info.unwrap();
+        assert_eq!(version.original_filename.as_deref(), Some("hello64.exe"));
+        assert_eq!(version.company_name.as_deref(), Some("Iris Test Fixtures"));
+        assert_eq!(version.product_name.as_deref(), Some("Iris PE fixtures"));
+        assert_eq!(version.file_description.as_deref(), Some("Hello world console program"));
+        assert_eq!(version.file_version.as_deref(), Some("1.2.3.4"));
+    }
+
+    #[test]
+    fn dll_x86() {
+        let info = inspect("helper32.dll");
+        assert_eq!(info.machine, "x86");
+        assert!(!info.is_64bit && info.is_dll);
+        assert_eq!(info.compile_timestamp, 1_262_304_000);
+        assert!(info.version_info.is_none());
+        assert_eq!(info.imports.len(), 1);
+        assert_eq!(info.imports[0].dll, "kernel32.dll");
+        assert_eq!(info.imports[0].functions, vec!["GetTickCount", "Sleep"]);
+    }
+
+    #[test]
+    fn packed_sections() {
+        let info = inspect("packed.exe");
+        assert!(info.imports.is_empty() && info.version_info.is_none());
+        assert_eq!(info.compile_timestamp, 0);
+
+        // Unpacking target: virtual only, nothing on disk
+        let target = section(&info, "UPX0");
+        assert_eq!((target.raw_size, target.virtual_size), (0, 0x40000));
+        assert_eq!(target.entropy, 0.0);
+
+        let payload = section(&info, "UPX1");
+        assert!(payload.executable && payload.writable);
+        assert!(payload.entropy > 7.9, "entropy {}", payload.entropy);
+        assert!(section(&info, ".text").entropy < 4.0);
+    }
+
+    #[test]
+    fn certificate_table() {
+        let data = fs_read("hello64-signed.exe");
+        let info = parse(&data).unwrap();
+        assert!(info.has_authenticode);
+        // Signing only appends the table: everything else is unchanged
+        assert_eq!(info.imports.len(), inspect("hello64.exe").imports.len());
+
+        let (offset, size) = layout(&data).unwrap().certificate_table().unwrap();
+        assert_eq!(offset + size, data.len());
+        assert_eq!(u16_at(&data, offset + 4), Some(0x0200));
+        assert!(layout(&fs_read("hello64.exe")).unwrap().certificate_table().is_none());
+
+        // Certificate table past the end of the file
+        let truncated = &data[..data.len() - 8];
+        assert!(!parse(truncated).unwrap().has_authenticode);
+    }
+
+    #[test]
+    fn rejects_non_pe_files() {
+        assert_eq!(parse(b"\x7fELF").unwrap_err(), "Not a PE file (missing MZ)");
+        assert_eq!(parse(b"MZ").unwrap_err(), "Truncated DOS header");
+
+        let data = fs_read("hello64.exe");
+        let pe_offset = u32_at(&data, 0x3C).unwrap() as usize;
+        let mut no_signature = data.clone();
+        no_signature[pe_offset] = b'X';
+        assert_eq!(parse(&no_signature).unwrap_err(), "Not a PE file (missing PE signature)");
+
+        let mut bad_magic = data.clone();
+        bad_magic[pe_offset + 24] = 0x07;
+        assert_eq!(parse(&bad_magic).unwrap_err(), "Unknown optional header magic 0x207");
+
+        assert_eq!(parse(&data[..pe_offset + 30]).unwrap_err(), "Truncated optional header");
+        assert_eq!(parse(&data[..pe_offset + 300]).unwrap_err(), "Truncated section table");
+    }
+
+    #[test]
+    fn truncated_image_keeps_headers() {
+        // Sections past the end of the file: no entropy, imports and resources skipped
+        let data = fs_read("hello64.exe");
+        let info = parse(&data[..0x400]).unwrap();
+        assert_eq!(info.sections.len(), 4);
+        assert!(info.imports.is_empty());
+        assert!(info.version_info.is_none());
+    }
+
+    #[test]
+    fn try_inspect_logs_failures() {
+        assert!(try_inspect(None).is_none());
+        assert!(try_inspect(Some("")).is_none());
+        assert!(try_inspect(fixture("missing.exe").to_str()).is_none());
+        assert!(try_inspect(fixture("source/version.rc").to_str()).is_none());
+        assert!(try_inspect(fixture("helper32.dll").to_str()).is_some());
+    }
+
+    #[test]
+    fn entropy_bounds() {
+        assert_eq!(entropy(&[]), 0.0);
+        assert_eq!(entropy(&[0x90; 4096]), 0.0);
+        let every_byte: Vec<u8> = (0..=255).collect();
+        assert!((entropy(&every_byte) - 8.0).abs() < 1e-6);
+        assert!((entropy(b"abab") - 1.0).abs() < 1e-6);
+    }
+
+    fn fs_read(name: &str) -> Vec<u8> {
+        std::fs::read(fixture(name)).unwrap()
+    }
+
+    #[test]
+    fn inspections_are_cached() {
+        let path = fixture("hello64.exe");
+        let path = path.to_str().unwrap();
+        let first = try_inspect(Some(path)).unwrap();
+        let cached = INSPECTION_CACHE.lock().unwrap().get(&path.to_lowercase()).and_then(|e| e.info.clone()).unwrap();
+        assert_eq!(cached.compile_timestamp, first.compile_timestamp);
+        assert_eq!(try_inspect(Some(path)).unwrap().sections.len(), first.sections.len());
+
+        // Failures are cached too, so they are logged once
+        let missing = fixture("missing.exe");
+        let missing = missing.to_str().unwrap();
+        assert!(try_inspect(Some(missing)).is_none());
+        assert!(INSPECTION_CACHE.lock().unwrap().contains_key(&missing.to_lowercase()));
+        assert!(try_inspect(Some(missing)).is_none());
+        assert!(try_inspect(Some("")).is_none());
+    }
+}
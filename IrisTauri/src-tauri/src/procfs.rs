@@ -0,0 +1,280 @@
+//! Procfs module - Linux process enumeration from /proc
+//!
+//! Every `/proc/<pid>` directory gives name, exe path, cmdline, parent pid, uid and
+//! start time. Processes running under Wine/Proton show up as `wine64-preloader`;
+//! for those the name and `windows_path` come from the Windows image in argv[0],
+//! so the rules see `cod.exe` and `C:\...\cod.exe` like on Windows, while `path`
+//! is the host file (from the memory maps or the Wine prefix) that hashing and
+//! byte scanning read. The root is a parameter so a copied or fake tree can be
+//! read the same way.
+
+use crate::hardware::ProcessInfo;
+use std::fs;
+use std::path::Path;
+
+/// Clock ticks per second of /proc/<pid>/stat (USER_HZ, 100 on every Linux ABI)
+const CLOCK_TICKS: u64 = 100;
+
+/// Executables that host a Windows image (Wine and Proton)
+const WINE_LOADERS: &[&str] = &["wine", "wine64", "wine-preloader", "wine64-preloader"];
+
+/// Running processes of the live system
+pub fn list_processes() -> Vec<ProcessInfo> {
+    read_processes(Path::new("/proc"))
+}
+
+/// Processes of a procfs tree (kernel threads and zombies are skipped)
+pub fn read_processes(root: &Path) -> Vec<ProcessInfo> {
+    let entries = match fs::read_dir(root) {
+        Ok(entries) => entries,
+        Err(e) => {
+            println!("[Procfs] Cannot read {}: {}", root.display(), e);
+            return Vec::new();
+        }
+    };
+    let boot_time = read_boot_time(root);
+
+    let mut processes: Vec<ProcessInfo> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let pid = entry.file_name().to_str()?.parse::<u32>().ok()?;
+            read_process(&entry.path(), pid, boot_time)
+        })
+        .collect();
+    processes.sort_by_key(|p| p.pid);
+    processes
+}
+
+/// One /proc/<pid> directory (None when the process exited or has no user image)
+fn read_process(dir: &Path, pid: u32, boot_time: Option<u64>) -> Option<ProcessInfo> {
+    let args = read_cmdline(dir);
+    let exe = fs::read_link(dir.join("exe")).ok()
+        .map(|p| p.to_string_lossy().trim_end_matches(" (deleted)").to_string());
+
+    // Kernel threads have neither an image nor arguments
+    if args.is_empty() && exe.is_none() {
+        return None;
+    }
+
+    let comm = fs::read_to_string(dir.join("comm")).ok()
+        .map(|c| c.trim_end_matches('\n').to_string())
+        .unwrap_or_default();
+    let (ppid, start_ticks) = fs::read_to_string(dir.join("stat")).ok()
+        .and_then(|stat| parse_stat(&stat))
+        .unzip();
+    let uid = fs::read_to_string(dir.join("status")).ok()
+        .and_then(|status| parse_uid(&status));
+    let start_time = start_ticks.zip(boot_time)
+        .map(|(ticks, boot)| (boot * 1000).saturating_add(ticks * 1000 / CLOCK_TICKS));
+
+    let loader = exe.as_deref().or(args.first().map(String::as_str)).map(file_name).unwrap_or_default();
+    let (name, path, windows_path) = match wine_image(loader, &args) {
+        Some(image) => (windows_file_name(image).to_string(), wine_host_path(dir, image), Some(image.to_string())),
+        None => {
+            let name = exe.as_deref().map(file_name)
+                .filter(|n| !n.is_empty())
+                .map(str::to_string)
+                .unwrap_or(comm);
+            (name, exe, None)
+        }
+    };
+
+    Some(ProcessInfo {
+        name,
+        pid,
+        path,
+        cmdline: (!args.is_empty()).then(|| args.join(" ")),
+        ppid,
+        uid,
+        start_time,
+        wine: windows_path.is_some(),
+        windows_path,
+    })
+}
+
+fn read_cmdline(dir: &Path) -> Vec<String> {
+    fs::read(dir.join("cmdline"))
+        .map(|raw| {
+            raw.split(|b| *b == 0)
+                .filter(|arg| !arg.is_empty())
+                .map(|arg| String::from_utf8_lossy(arg).into_owned())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// (ppid, starttime in ticks) from /proc/<pid>/stat. The name field may contain
+/// spaces and parentheses, so fields are counted after the last ')'.
+fn parse_stat(stat: &str) -> Option<(u32, u64)> {
+    let rest = &stat[stat.rfind(')')? + 1..];
+    let fields: Vec<&str> = rest.split_whitespace().collect();
+    // fields[0] is the state (field 3), ppid is field 4, starttime field 22
+    let ppid = fields.get(1)?.parse().ok()?;
+    let start = fields.get(19)?.parse().ok()?;
+    Some((ppid, start))
+}
+
+/// Real uid from the "Uid:" line of /proc/<pid>/status
+fn parse_uid(status: &str) -> Option<u32> {
+    status.lines()
+        .find_map(|line| line.strip_prefix("Uid:"))
+        .and_then(|ids| ids.split_whitespace().next())
+        .and_then(|uid| uid.parse().ok())
+}
+
+/// Boot time (seconds since epoch) from the "btime" line of /proc/stat
+fn read_boot_time(root: &Path) -> Option<u64> {
+    fs::read_to_string(root.join("stat")).ok()?
+        .lines()
+        .find_map(|line| line.strip_prefix("btime "))
+        .and_then(|secs| secs.trim().parse().ok())
+}
+
+/// Windows image hosted by a Wine loader: argv[0] once the preloader runs it,
+/// argv[1] while `wine foo.exe` is still starting
+fn wine_image<'a>(loader: &str, args: &'a [String]) -> Option<&'a str> {
+    if !WINE_LOADERS.contains(&loader) {
+        return None;
+    }
+    args.iter().take(2).map(String::as_str).find(|arg| is_windows_image(arg))
+}
+
+/// Host file of a Wine image: the mapping with the same file name in
+/// /proc/<pid>/maps, else the image under `$WINEPREFIX/dosdevices`
+fn wine_host_path(dir: &Path, image: &str) -> Option<String> {
+    let file = windows_file_name(image);
+    // address perms offset dev inode pathname (padded with spaces)
+    let mapped = fs::read_to_string(dir.join("maps")).ok().and_then(|maps| {
+        maps.lines()
+            .filter_map(|line| line.splitn(6, ' ').nth(5).map(str::trim_start))
+            .find(|path| path.starts_with('/') && file_name(path).eq_ignore_ascii_case(file))
+            .map(str::to_string)
+    });
+    mapped.or_else(|| dosdevices_path(dir, image))
+}
+
+/// `C:\Games\cod.exe` -> `<prefix>/dosdevices/c:/Games/cod.exe`, if that file exists
+fn dosdevices_path(dir: &Path, image: &str) -> Option<String> {
+    let environ = fs::read(dir.join("environ")).ok()?;
+    let var = |key: &str| environ.split(|b| *b == 0)
+        .filter_map(|entry| std::str::from_utf8(entry).ok())
+        .find_map(|entry| entry.strip_prefix(key).and_then(|rest| rest.strip_prefix('=')))
+        .map(str::to_string);
+    let prefix = var("WINEPREFIX").or_else(|| var("HOME").map(|home| format!("{}/.wine", home)))?;
+
+    let (drive, rest) = image.split_once(':').filter(|(drive, _)| drive.len() == 1)?;
+    let host = format!("{}/dosdevices/{}:{}", prefix.trim_end_matches('/'), drive.to_lowercase(), rest.replace('\\', "/"));
+    Path::new(&host).is_file().then_some(host)
+}
+
+fn is_windows_image(arg: &str) -> bool {
+    let bytes = arg.as_bytes();
+    let drive_path = bytes.len() > 3 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' && matches!(bytes[2], b'\\' | b'/');
+    drive_path || arg.to_lowercase().ends_with(".exe")
+}
+
+fn file_name(path: &str) -> &str {
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+fn windows_file_name(path: &str) -> &str {
+    path.rsplit(['\\', '/']).next().unwrap_or(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture() -> Vec<ProcessInfo> {
+        read_processes(&Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/procfs"))
+    }
+
+    fn process(processes: &[ProcessInfo], pid: u32) -> &ProcessInfo {
+        processes.iter().find(|p| p.pid == pid).unwrap_or_else(|| panic!("pid {} missing", pid))
+    }
+
+    #[test]
+    fn skips_kernel_threads_and_other_entries() {
+        let pids: Vec<u32> = fixture().iter().map(|p| p.pid).collect();
+        assert_eq!(pids, vec![1, 4200, 4242, 5000, 6000]);
+    }
+
+    #[test]
+    fn reads_native_process() {
+        let processes = fixture();
+        let init = process(&processes, 1);
+        assert_eq!(init.name, "systemd");
+        assert_eq!(init.path.as_deref(), Some("/usr/lib/systemd/systemd"));
+        assert_eq!(init.cmdline.as_deref(), Some("/sbin/init splash"));
+        assert_eq!((init.ppid, init.uid), (Some(0), Some(0)));
+        assert_eq!(init.start_time, Some(1_700_000_000_000 + 10 * 10));
+        assert!(!init.wine);
+        assert_eq!(init.windows_path, None);
+    }
+
+    #[test]
+    fn parses_stat_after_last_parenthesis() {
+        let processes = fixture();
+        let weird = process(&processes, 5000);
+        assert_eq!(weird.name, "my proc");
+        assert_eq!(weird.ppid, Some(1));
+        assert_eq!(weird.start_time, Some(1_700_000_000_000 + 200 * 10));
+    }
+
+    #[test]
+    fn falls_back_to_comm_without_exe() {
+        let processes = fixture();
+        let agent = process(&processes, 6000);
+        assert_eq!(agent.name, "ssh-agent");
+        assert_eq!(agent.path, None);
+        assert_eq!(agent.uid, Some(1001));
+    }
+
+    #[test]
+    fn reports_wine_image_with_host_path() {
+        let processes = fixture();
+        let game = process(&processes, 4242);
+        assert!(game.wine);
+        assert_eq!(game.name, "cod.exe");
+        assert_eq!(game.windows_path.as_deref(), Some("C:\\Games\\CoD\\cod.exe"));
+        assert_eq!(game.path.as_deref(), Some("/home/player/.wine/drive_c/Games/CoD/cod.exe"));
+        assert_eq!(game.rule_path(), Some("C:\\Games\\CoD\\cod.exe"));
+        assert_eq!(game.start_time, Some(1_700_000_000_000 + 123_456 * 10));
+
+        // `wine launcher.exe` still starting: image from argv[1], no host file found
+        let launcher = process(&processes, 4200);
+        assert!(launcher.wine);
+        assert_eq!(launcher.name, "launcher.exe");
+        assert_eq!(launcher.path, None);
+    }
+
+    #[test]
+    fn resolves_image_under_wine_prefix() {
+        let dir = std::env::temp_dir().join(format!("iris-procfs-{}", std::process::id()));
+        let prefix = dir.join("pfx");
+        fs::create_dir_all(prefix.join("drive_c/Games/Tool")).unwrap();
+        fs::create_dir_all(prefix.join("dosdevices")).unwrap();
+        std::os::unix::fs::symlink("../drive_c", prefix.join("dosdevices/c:")).unwrap();
+        fs::write(prefix.join("drive_c/Games/Tool/tool.exe"), b"MZ").unwrap();
+        fs::write(dir.join("environ"), format!("HOME=/nonexistent\0WINEPREFIX={}\0", prefix.display())).unwrap();
+
+        let host = wine_host_path(&dir, "C:\\Games\\Tool\\tool.exe");
+        let missing = wine_host_path(&dir, "C:\\Games\\Tool\\other.exe");
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(host, Some(format!("{}/dosdevices/c:/Games/Tool/tool.exe", prefix.display())));
+        assert_eq!(missing, None);
+    }
+
+    #[test]
+    fn serializes_camel_case() {
+        let json = serde_json::to_value(ProcessInfo {
+            start_time: Some(1),
+            windows_path: Some("C:\\a.exe".to_string()),
+            ..Default::default()
+        }).unwrap();
+        for key in ["name", "pid", "path", "startTime", "windowsPath", "wine"] {
+            assert!(json.get(key).is_some(), "missing {}", key);
+        }
+    }
+}
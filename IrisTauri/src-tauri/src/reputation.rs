@@ -0,0 +1,378 @@
+//! Hash reputation module - SHA-256 of running process images checked against
+//! the known-bad / known-good lists of the signature bundle
+//!
+//! Hashes are cached by (path, size, mtime) so an image is only read again when
+//! it changes on disk, including after the process exits and starts again. New
+//! images are hashed within a per-cycle byte and time budget.
+
+use crate::rules::Subject;
+use crate::signatures::{self, SignatureBundle};
+use crate::snapshot::SystemSnapshot;
+use crate::whitelist::{self, Suppression, Whitelist, WhitelistType};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::io::Read;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+
+/// Images larger than this are not hashed (a quarter of the cycle budget, so one
+/// large image cannot hold back every other new image)
+const MAX_IMAGE_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Bytes hashed per cycle (the rest wait for the next heartbeat)
+const MAX_BYTES_PER_CYCLE: u64 = 256 * 1024 * 1024;
+
+/// Time spent hashing per cycle
+const MAX_HASH_TIME: Duration = Duration::from_secs(2);
+
+/// Cached hashes kept; the least recently seen images are forgotten first
+const MAX_CACHED_HASHES: usize = 4096;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HashMatch {
+    pub name: String,
+    pub pid: u32,
+    pub path: String,
+    pub sha256: String,
+    pub matched_name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct HashReputationResult {
+    pub bad_found: bool,
+    pub known_bad: Vec<HashMatch>,
+    pub known_good_count: u32,
+    pub unknown_count: u32,
+    pub hashed_this_cycle: u32,
+    pub bytes_hashed: u64,
+    pub pending_count: u32,   // Images left for the next cycles (budget)
+    pub skipped_count: u32,   // Unreadable or too large
+    pub risk_score: u32,
+    #[serde(default)]
+    pub suppressed: Vec<Suppression>, // Whitelist entries that fired
+}
+
+#[derive(Debug, Clone)]
+struct CachedHash {
+    size: u64,
+    modified: Option<SystemTime>,
+    sha256: String,
+    last_seen: Instant,
+}
+
+/// Work done by the current cycle
+struct CycleBudget {
+    started: Instant,
+    hashed: u32,
+    bytes: u64,
+}
+
+/// Image hashes by `cache_key`
+type HashCache = Mutex<HashMap<String, CachedHash>>;
+
+lazy_static::lazy_static! {
+    static ref HASH_CACHE: HashCache = Mutex::new(HashMap::new());
+}
+
+/// Cache key of an image path (Windows paths are case-insensitive)
+fn cache_key(path: &str) -> String {
+    if cfg!(windows) {
+        path.to_lowercase()
+    } else {
+        path.to_string()
+    }
+}
+
+/// Cached SHA-256 of an image, if it was hashed and has not changed since
+pub fn cached_sha256(path: &str) -> Option<String> {
+    let (size, modified) = file_stamp(path)?;
+    HASH_CACHE.lock().ok()?
+        .get(&cache_key(path))
+        .filter(|entry| entry.size == size && entry.modified == modified)
+        .map(|entry| entry.sha256.clone())
+}
+
+/// Hash running process images and check them against the reputation lists
+pub fn check_hash_reputation(snapshot: &SystemSnapshot) -> HashReputationResult {
+    let result = check_images(snapshot, &signatures::current(), &whitelist::current(), &HASH_CACHE);
+
+    println!("[Reputation] Hashes: bad={}, good={}, unknown={}, hashed={}, pending={}, skipped={}",
+             result.known_bad.len(), result.known_good_count, result.unknown_count,
+             result.hashed_this_cycle, result.pending_count, result.skipped_count);
+
+    result
+}
+
+/// Reputation of the images of `snapshot` against `sigs`, hashing through `cache`
+/// (locked for lookups and inserts only, not while a file is hashed)
+fn check_images(snapshot: &SystemSnapshot, sigs: &SignatureBundle, allowed: &Whitelist,
+                cache: &HashCache) -> HashReputationResult {
+    let mut result = HashReputationResult::default();
+
+    let known_bad: HashMap<String, &str> = sigs.known_bad_hashes.iter()
+        .map(|h| (h.sha256.to_lowercase(), h.name.as_str()))
+        .collect();
+    let known_good: HashSet<String> = sigs.known_good_hashes.iter()
+        .map(|h| h.sha256.to_lowercase())
+        .collect();
+
+    let mut seen_paths: HashSet<String> = HashSet::new();
+    let mut budget = CycleBudget { started: Instant::now(), hashed: 0, bytes: 0 };
+
+    for process in &snapshot.processes {
+        let path = match &process.path {
+            Some(path) if !path.is_empty() => path,
+            _ => continue,
+        };
+        let key = cache_key(path);
+
+        // Same image running several times: counted once
+        let first_seen = seen_paths.insert(key.clone());
+
+        let sha256 = match image_hash(cache, &key, path, &mut budget) {
+            ImageHash::Known(sha256) => sha256,
+            ImageHash::Pending => {
+                if first_seen {
+                    result.pending_count += 1;
+                }
+                continue;
+            }
+            ImageHash::Skipped => {
+                if first_seen {
+                    result.skipped_count += 1;
+                }
+                continue;
+            }
+        };
+
+        let subject = Subject {
+            sha256: Some(&sha256),
+            ..Subject::file(&process.name, process.rule_path())
+        };
+        if known_bad.contains_key(&sha256)
+            && allowed.suppress(WhitelistType::Process, &[subject], &mut result.suppressed) {
+            continue;
+        }
+
+        if let Some(name) = known_bad.get(&sha256) {
+            result.bad_found = true;
+            result.known_bad.push(HashMatch {
+                name: process.name.clone(),
+                pid: process.pid,
+                path: path.clone(),
+                sha256,
+                matched_name: name.to_string(),
+            });
+            result.risk_score += 100;
+        } else if first_seen {
+            if known_good.contains(&sha256) {
+                result.known_good_count += 1;
+            } else {
+                result.unknown_count += 1;
+            }
+        }
+    }
+
+    // Images that stopped running stay cached (they are usually started again);
+    // the cache is only trimmed when it grows past its limit
+    if let Some(mut cache) = cache.lock().ok().filter(|cache| cache.len() > MAX_CACHED_HASHES) {
+        let mut by_age: Vec<(Instant, String)> = cache.iter()
+            .map(|(path, entry)| (entry.last_seen, path.clone()))
+            .collect();
+        by_age.sort();
+        for (_, path) in by_age.into_iter().take(cache.len() - MAX_CACHED_HASHES) {
+            cache.remove(&path);
+        }
+    }
+    result.hashed_this_cycle = budget.hashed;
+    result.bytes_hashed = budget.bytes;
+    result
+}
+
+enum ImageHash {
+    Known(String),
+    Pending,
+    Skipped,
+}
+
+fn image_hash(cache: &HashCache, key: &str, path: &str, budget: &mut CycleBudget) -> ImageHash {
+    let (size, modified) = match file_stamp(path) {
+        Some(stamp) => stamp,
+        None => return ImageHash::Skipped,
+    };
+
+    if let Ok(mut cache) = cache.lock() {
+        if let Some(entry) = cache.get_mut(key) {
+            if entry.size == size && entry.modified == modified {
+                entry.last_seen = Instant::now();
+                return ImageHash::Known(entry.sha256.clone());
+            }
+        }
+    }
+
+    if size > MAX_IMAGE_SIZE {
+        return ImageHash::Skipped;
+    }
+    if budget.bytes + size > MAX_BYTES_PER_CYCLE || budget.started.elapsed() > MAX_HASH_TIME {
+        return ImageHash::Pending;
+    }
+
+    budget.hashed += 1;
+    budget.bytes += size;
+    match hash_file(path) {
+        Ok(sha256) => {
+            let entry = CachedHash { size, modified, sha256: sha256.clone(), last_seen: Instant::now() };
+            if let Ok(mut cache) = cache.lock() {
+                cache.insert(key.to_string(), entry);
+            }
+            ImageHash::Known(sha256)
+        }
+        Err(_) => ImageHash::Skipped,
+    }
+}
+
+fn file_stamp(path: &str) -> Option<(u64, Option<SystemTime>)> {
+    let metadata = std::fs::metadata(path).ok()?;
+    if !metadata.is_file() {
+        return None;
+    }
+    Some((metadata.len(), metadata.modified().ok()))
+}
+
+/// Streamed SHA-256 of a file (lowercase hex)
+pub fn hash_file(path: &str) -> Result<String, String> {
+    let mut file = std::fs::File::open(path).map_err(|e| e.to_string())?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+
+    loop {
+        let read = file.read(&mut buffer).map_err(|e| e.to_string())?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+
+    Ok(hex::encode(hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hardware::ProcessInfo;
+    use crate::signatures::HashSignature;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    struct TempDir(PathBuf);
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn process(pid: u32, path: &Path) -> ProcessInfo {
+        ProcessInfo {
+            name: path.file_name().unwrap().to_string_lossy().into_owned(),
+            pid,
+            path: Some(path.to_string_lossy().into_owned()),
+            ..Default::default()
+        }
+    }
+
+    fn sha256(data: &[u8]) -> String {
+        hex::encode(Sha256::digest(data))
+    }
+
+    fn cached_sha256_in(cache: &HashCache, path: &Path) -> Option<String> {
+        cache.lock().unwrap().get(&cache_key(&path.to_string_lossy())).map(|entry| entry.sha256.clone())
+    }
+
+    fn touch(path: &Path, seconds: u64) {
+        let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(seconds);
+        fs::File::options().write(true).open(path).unwrap().set_modified(modified).unwrap();
+    }
+
+    #[test]
+    fn flags_known_bad_images_and_hashes_only_changed_ones() {
+        let dir = TempDir(std::env::temp_dir().join(format!("iris-reputation-{}", std::process::id())));
+        fs::create_dir_all(&dir.0).unwrap();
+        let (bad, good, other, large) = (dir.0.join("loader.exe"), dir.0.join("game.exe"), dir.0.join("tool.exe"), dir.0.join("huge.exe"));
+        fs::write(&bad, b"cheat loader").unwrap();
+        fs::write(&good, b"game client").unwrap();
+        fs::write(&other, b"unknown tool").unwrap();
+        fs::File::create(&large).unwrap().set_len(MAX_IMAGE_SIZE + 1).unwrap(); // Sparse
+
+        let mut sigs = SignatureBundle::builtin();
+        sigs.known_bad_hashes.push(HashSignature { sha256: sha256(b"cheat loader").to_uppercase(), name: "Loader".to_string() });
+        sigs.known_good_hashes.push(HashSignature { sha256: sha256(b"game client"), name: "Game".to_string() });
+        let allowed = whitelist::current();
+        let snapshot = SystemSnapshot {
+            processes: vec![process(10, &bad), process(11, &good), process(12, &other), process(13, &large), process(14, &good)],
+            ..Default::default()
+        };
+        let cache = Mutex::new(HashMap::new());
+
+        let result = check_images(&snapshot, &sigs, &allowed, &cache);
+        assert!(result.bad_found);
+        assert_eq!(result.known_bad.len(), 1);
+        assert_eq!((result.known_bad[0].pid, result.known_bad[0].matched_name.as_str()), (10, "Loader"));
+        assert_eq!(result.known_bad[0].sha256, sha256(b"cheat loader"));
+        assert_eq!((result.known_good_count, result.unknown_count, result.skipped_count), (1, 1, 1)); // game.exe counted once
+        assert_eq!((result.hashed_this_cycle, result.bytes_hashed), (3, 35));
+        assert_eq!(result.risk_score, 100);
+
+        // Unchanged files come from the cache
+        let result = check_images(&snapshot, &sigs, &allowed, &cache);
+        assert_eq!(result.hashed_this_cycle, 0);
+        assert!(result.bad_found);
+        assert_eq!(cached_sha256_in(&cache, &good), Some(sha256(b"game client")));
+
+        // Same size, new content and mtime: hashed again
+        fs::write(&bad, b"clean loader").unwrap();
+        touch(&bad, 1_700_000_000);
+        let result = check_images(&snapshot, &sigs, &allowed, &cache);
+        assert_eq!(result.hashed_this_cycle, 1);
+        assert!(!result.bad_found);
+        assert_eq!(result.unknown_count, 2);
+
+        // Only the modification time changed
+        touch(&other, 1_700_000_000);
+        assert_eq!(check_images(&snapshot, &sigs, &allowed, &cache).hashed_this_cycle, 1);
+        assert_eq!(check_images(&snapshot, &sigs, &allowed, &cache).hashed_this_cycle, 0);
+    }
+
+    #[test]
+    fn new_images_past_the_cycle_budget_are_pending() {
+        let dir = TempDir(std::env::temp_dir().join(format!("iris-reputation-budget-{}", std::process::id())));
+        fs::create_dir_all(&dir.0).unwrap();
+        let path = dir.0.join("tool.exe");
+        fs::write(&path, b"tool").unwrap();
+        let path = path.to_string_lossy().into_owned();
+
+        let cache = Mutex::new(HashMap::new());
+        let mut spent = CycleBudget { started: Instant::now(), hashed: 4, bytes: MAX_BYTES_PER_CYCLE - 3 };
+        assert!(matches!(image_hash(&cache, "tool.exe", &path, &mut spent), ImageHash::Pending));
+
+        let mut budget = CycleBudget { started: Instant::now(), hashed: 0, bytes: MAX_BYTES_PER_CYCLE - 4 };
+        assert!(matches!(image_hash(&cache, "tool.exe", &path, &mut budget), ImageHash::Known(_)));
+        assert_eq!((budget.hashed, budget.bytes), (1, MAX_BYTES_PER_CYCLE));
+
+        // Cached images do not use the budget
+        assert!(matches!(image_hash(&cache, "tool.exe", &path, &mut spent), ImageHash::Known(_)));
+    }
+
+    #[test]
+    fn cache_keys_ignore_case_on_windows_only() {
+        if cfg!(windows) {
+            assert_eq!(cache_key("C:\\Games\\Game.EXE"), "c:\\games\\game.exe");
+        } else {
+            assert_eq!(cache_key("/opt/Game/game"), "/opt/Game/game");
+            assert_ne!(cache_key("/opt/Game/game"), cache_key("/opt/game/game"));
+        }
+    }
+}
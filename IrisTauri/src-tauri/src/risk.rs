@@ -0,0 +1,722 @@
+//! Risk engine module - one weighted, explainable score over typed findings
+//!
+//! Detectors keep their raw results; each result type lists its findings through
+//! `RiskSource`. The engine applies the weights, per-kind caps, overall cap and
+//! level thresholds of the signature bundle (server-configurable, signed). Findings
+//! that are no longer observed decay with a half-life instead of vanishing at once,
+//! so toggling a cheat between heartbeats does not reset the score.
+
+use crate::hardware::{
+    self, CheatDetection, CheatWindowDetectionResult, CloudPcDetectionResult, DllInjectionResult,
+    DriverIntegrityResult, GameSessionActivity, MacroDetectionResult, NetworkMonitorResult,
+    OverlayDetectionResult, ProcessInfo, RegistryScanResult, UsbDeviceInfo, VmDetectionResult,
+};
+use crate::eventlog::MeasuredBootReport;
+use crate::patterns::PatternScanResult;
+use crate::pci::DmaDeviceResult;
+use crate::reputation::HashReputationResult;
+use crate::spoofing::HwidConsistencyResult;
+use crate::usbtopology::UsbTopologyResult;
+use crate::signatures;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Decayed findings below this many points are forgotten
+const MIN_DECAYED_POINTS: f64 = 1.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum RiskLevel {
+    #[default]
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+impl RiskLevel {
+    pub fn from_score(score: u32, levels: &LevelThresholds) -> Self {
+        if score >= levels.critical {
+            RiskLevel::Critical
+        } else if score >= levels.high {
+            RiskLevel::High
+        } else if score >= levels.medium {
+            RiskLevel::Medium
+        } else {
+            RiskLevel::Low
+        }
+    }
+
+    /// Parse a level name as used by the signature lists ("critical", "high", ...)
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "low" => Some(RiskLevel::Low),
+            "medium" => Some(RiskLevel::Medium),
+            "high" => Some(RiskLevel::High),
+            "critical" => Some(RiskLevel::Critical),
+            _ => None,
+        }
+    }
+}
+
+/// Minimum score of each level
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LevelThresholds {
+    pub medium: u32,
+    pub high: u32,
+    pub critical: u32,
+}
+
+impl Default for LevelThresholds {
+    fn default() -> Self {
+        LevelThresholds { medium: 25, high: 50, critical: 100 }
+    }
+}
+
+/// Scoring parameters (part of the signature bundle)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RiskConfig {
+    pub weights: HashMap<String, f64>, // Multiplier per finding kind (1.0 when missing)
+    pub caps: HashMap<String, u32>,    // Max points per finding kind
+    pub max_score: u32,                // Cap of the overall score
+    pub decay_half_life_secs: u64,     // Unobserved findings lose half their points per period
+    pub levels: LevelThresholds,
+}
+
+impl Default for RiskConfig {
+    fn default() -> Self {
+        RiskConfig {
+            weights: HashMap::new(),
+            caps: [
+                (FindingKind::VpnAdapter, 80),
+                (FindingKind::VpnProcess, 60),
+                (FindingKind::Overlay, 180),
+                (FindingKind::CloudPc, 80),
+            ]
+            .iter()
+            .map(|(kind, cap)| (kind.key().to_string(), *cap))
+            .collect(),
+            max_score: 1000,
+            decay_half_life_secs: 300,
+            levels: LevelThresholds::default(),
+        }
+    }
+}
+
+impl RiskConfig {
+    fn weight(&self, kind: FindingKind) -> f64 {
+        self.weights.get(kind.key()).copied().filter(|w| w.is_finite() && *w >= 0.0).unwrap_or(1.0)
+    }
+}
+
+/// What a finding is about (keys of `RiskConfig.weights` / `caps`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FindingKind {
+    CheatDevice,
+    CheatProcess,
+    VpnAdapter,
+    VpnProcess,
+    Proxy,
+    RegistryTrace,
+    SuspiciousDriver,
+    Macro,
+    Overlay,
+    SuspiciousDll,
+    InjectionTool,
+    VirtualMachine,
+    CloudPc,
+    CheatWindow,
+    KnownBadHash,
+    BytePattern,
+    BootIntegrity,
+    HwidSpoofing,
+    DmaDevice,
+    UsbTopology,
+}
+
+impl FindingKind {
+    pub fn key(&self) -> &'static str {
+        match self {
+            FindingKind::CheatDevice => "cheat_device",
+            FindingKind::CheatProcess => "cheat_process",
+            FindingKind::VpnAdapter => "vpn_adapter",
+            FindingKind::VpnProcess => "vpn_process",
+            FindingKind::Proxy => "proxy",
+            FindingKind::RegistryTrace => "registry_trace",
+            FindingKind::SuspiciousDriver => "suspicious_driver",
+            FindingKind::Macro => "macro",
+            FindingKind::Overlay => "overlay",
+            FindingKind::SuspiciousDll => "suspicious_dll",
+            FindingKind::InjectionTool => "injection_tool",
+            FindingKind::VirtualMachine => "virtual_machine",
+            FindingKind::CloudPc => "cloud_pc",
+            FindingKind::CheatWindow => "cheat_window",
+            FindingKind::KnownBadHash => "known_bad_hash",
+            FindingKind::BytePattern => "byte_pattern",
+            FindingKind::BootIntegrity => "boot_integrity",
+            FindingKind::HwidSpoofing => "hwid_spoofing",
+            FindingKind::DmaDevice => "dma_device",
+            FindingKind::UsbTopology => "usb_topology",
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            FindingKind::CheatDevice => "Cheat device",
+            FindingKind::CheatProcess => "Cheat process",
+            FindingKind::VpnAdapter => "VPN adapter",
+            FindingKind::VpnProcess => "VPN process",
+            FindingKind::Proxy => "Proxy",
+            FindingKind::RegistryTrace => "Registry trace",
+            FindingKind::SuspiciousDriver => "Suspicious driver",
+            FindingKind::Macro => "Macro software",
+            FindingKind::Overlay => "Overlay",
+            FindingKind::SuspiciousDll => "Suspicious DLL",
+            FindingKind::InjectionTool => "Injection tool",
+            FindingKind::VirtualMachine => "Virtual machine",
+            FindingKind::CloudPc => "Cloud PC",
+            FindingKind::CheatWindow => "Cheat window",
+            FindingKind::KnownBadHash => "Known-bad hash",
+            FindingKind::BytePattern => "Byte pattern",
+            FindingKind::BootIntegrity => "Boot integrity",
+            FindingKind::HwidSpoofing => "HWID spoofing",
+            FindingKind::DmaDevice => "DMA device",
+            FindingKind::UsbTopology => "USB topology",
+        }
+    }
+}
+
+/// A single thing a detector found
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Finding {
+    pub kind: FindingKind,
+    pub subject: String, // Process, device, driver... that was found
+    pub detail: String,  // Why it was flagged
+    pub base: u32,       // Points before weight, decay and caps
+}
+
+impl Finding {
+    fn new(kind: FindingKind, subject: impl Into<String>, detail: impl Into<String>, base: u32) -> Self {
+        Finding { kind, subject: subject.into(), detail: detail.into(), base }
+    }
+
+    fn id(&self) -> String {
+        format!("{}:{}", self.kind.key(), self.subject.to_lowercase())
+    }
+}
+
+/// A finding with its contribution to the score
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScoredFinding {
+    pub kind: FindingKind,
+    pub subject: String,
+    pub detail: String,
+    pub base: u32,
+    pub weight: f64,
+    pub decay: f64,          // 1.0 while observed
+    pub last_seen_secs: u64, // 0 while observed
+    pub points: u32,
+    pub explanation: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct RiskAssessment {
+    pub score: u32,
+    pub level: RiskLevel,
+    pub findings: Vec<ScoredFinding>, // Highest contribution first
+    pub config_version: u64,          // Signature bundle providing the weights
+}
+
+/// Results that contribute findings to the risk engine
+pub trait RiskSource {
+    fn findings(&self) -> Vec<Finding> {
+        Vec::new()
+    }
+}
+
+struct Remembered {
+    finding: Finding,
+    last_seen: Instant,
+}
+
+/// Scores findings and remembers them for decay
+pub struct RiskEngine {
+    history: HashMap<String, Remembered>,
+}
+
+impl RiskEngine {
+    pub fn new() -> Self {
+        RiskEngine { history: HashMap::new() }
+    }
+
+    pub fn assess(&mut self, findings: &[Finding], config: &RiskConfig, now: Instant) -> RiskAssessment {
+        for finding in findings {
+            let id = finding.id();
+            // Same subject reported twice in a cycle: the strongest finding wins
+            let keep_previous = self.history.get(&id)
+                .is_some_and(|r| r.last_seen == now && r.finding.base >= finding.base);
+            if !keep_previous {
+                self.history.insert(id, Remembered { finding: finding.clone(), last_seen: now });
+            }
+        }
+
+        let half_life = config.decay_half_life_secs.max(1) as f64;
+        let mut scored: Vec<(f64, ScoredFinding)> = Vec::new();
+        self.history.retain(|_, remembered| {
+            let age = now.saturating_duration_since(remembered.last_seen);
+            let decay = if age == Duration::ZERO { 1.0 } else { 0.5f64.powf(age.as_secs_f64() / half_life) };
+            let finding = &remembered.finding;
+            let weight = config.weight(finding.kind);
+            let raw = finding.base as f64 * weight * decay;
+            if decay < 1.0 && raw < MIN_DECAYED_POINTS {
+                return false;
+            }
+
+            scored.push((raw, ScoredFinding {
+                kind: finding.kind,
+                subject: finding.subject.clone(),
+                detail: finding.detail.clone(),
+                base: finding.base,
+                weight,
+                decay,
+                last_seen_secs: age.as_secs(),
+                points: 0,
+                explanation: String::new(),
+            }));
+            true
+        });
+
+        // Strongest first so caps cut the weakest findings of a kind
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0).then_with(|| a.1.subject.cmp(&b.1.subject)));
+
+        let mut used: HashMap<FindingKind, u32> = HashMap::new();
+        let mut total: u32 = 0;
+        let mut findings = Vec::with_capacity(scored.len());
+        for (raw, mut finding) in scored {
+            let points = raw.round() as u32;
+            let cap = config.caps.get(finding.kind.key()).copied();
+            let already = used.entry(finding.kind).or_insert(0);
+            finding.points = match cap {
+                Some(cap) => points.min(cap.saturating_sub(*already)),
+                None => points,
+            };
+            *already += finding.points;
+            total = total.saturating_add(finding.points);
+            finding.explanation = explain(&finding, points, cap);
+            findings.push(finding);
+        }
+
+        let score = total.min(config.max_score);
+        RiskAssessment {
+            score,
+            level: RiskLevel::from_score(score, &config.levels),
+            findings,
+            config_version: 0,
+        }
+    }
+}
+
+impl Default for RiskEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn explain(finding: &ScoredFinding, uncapped: u32, cap: Option<u32>) -> String {
+    let mut text = format!("{} '{}'", finding.kind.label(), finding.subject);
+    if !finding.detail.is_empty() {
+        text.push_str(&format!(", {}", finding.detail));
+    }
+    text.push_str(&format!(": {} x {:.2}", finding.base, finding.weight));
+    if finding.decay < 1.0 {
+        text.push_str(&format!(" x {:.2} decay (last seen {}s ago)", finding.decay, finding.last_seen_secs));
+    }
+    text.push_str(&format!(" = {}", uncapped));
+    if finding.points < uncapped {
+        text.push_str(&format!(", {} cap {} reached -> {}", finding.kind.key(), cap.unwrap_or_default(), finding.points));
+    }
+    text
+}
+
+lazy_static::lazy_static! {
+    static ref ENGINE: Mutex<RiskEngine> = Mutex::new(RiskEngine::new());
+}
+
+/// Score findings with the weights of the active signature bundle
+pub fn assess(findings: &[Finding]) -> RiskAssessment {
+    let sigs = signatures::current();
+    let mut assessment = match ENGINE.lock() {
+        Ok(mut engine) => engine.assess(findings, &sigs.risk, Instant::now()),
+        Err(_) => RiskAssessment::default(),
+    };
+    assessment.config_version = sigs.version;
+
+    println!("[Risk] Score {} ({:?}) from {} findings", assessment.score, assessment.level, assessment.findings.len());
+    assessment
+}
+
+// ====== FINDINGS PER RESULT TYPE ======
+
+impl RiskSource for CheatDetection {
+    fn findings(&self) -> Vec<Finding> {
+        let devices = self.devices.iter().map(|d| {
+            let ids = match (&d.vid, &d.pid) {
+                (Some(vid), Some(pid)) => format!("{}:{}", vid, pid),
+                (Some(vid), None) => vid.clone(),
+                _ => String::new(),
+            };
+            Finding::new(FindingKind::CheatDevice, &d.name, format!("{} {}", d.device_type, ids).trim(), 100)
+        });
+        let processes = self.processes.iter()
+            .map(|p| Finding::new(FindingKind::CheatProcess, &p.name, format!("matched {}", p.matched_cheat), 75));
+        devices.chain(processes).collect()
+    }
+}
+
+impl RiskSource for NetworkMonitorResult {
+    fn findings(&self) -> Vec<Finding> {
+        let mut findings: Vec<Finding> = self.vpn_adapters.iter()
+            .map(|a| Finding::new(FindingKind::VpnAdapter, a, "VPN network adapter", 40))
+            .chain(self.vpn_processes.iter().map(|p| Finding::new(FindingKind::VpnProcess, p, "VPN client running", 30)))
+            .collect();
+        if self.proxy_detected {
+            let server = self.proxy_settings.clone().unwrap_or_else(|| "system proxy".to_string());
+            findings.push(Finding::new(FindingKind::Proxy, server, "proxy enabled", 20));
+        }
+        findings
+    }
+}
+
+impl RiskSource for RegistryScanResult {
+    fn findings(&self) -> Vec<Finding> {
+        self.traces.iter().map(|t| {
+            let base = match t.trace_type.as_str() {
+                "spoofer" => 80,
+                "driver" => 60,
+                "uninstall" => 40,
+                _ => 50,
+            };
+            Finding::new(FindingKind::RegistryTrace, &t.path, format!("{} ({})", t.cheat_name, t.trace_type), base)
+        }).collect()
+    }
+}
+
+impl RiskSource for DriverIntegrityResult {
+    fn findings(&self) -> Vec<Finding> {
+        self.suspicious_drivers.iter().map(|d| {
+            let base = hardware::signature_risk(&d.reason).unwrap_or(70);
+            Finding::new(FindingKind::SuspiciousDriver, &d.name, &d.reason, base)
+        }).collect()
+    }
+}
+
+impl RiskSource for MacroDetectionResult {
+    fn findings(&self) -> Vec<Finding> {
+        self.detected_software.iter().map(|m| {
+            let base = match (m.source.as_str(), m.macro_type.as_str()) {
+                ("registry", _) => 50,
+                (_, "ahk") => 80,
+                (_, "generic") => 60,
+                (_, "logitech" | "razer" | "corsair") => 20,
+                _ => 30,
+            };
+            Finding::new(FindingKind::Macro, &m.name, format!("{} ({})", m.macro_type, m.source), base)
+        }).collect()
+    }
+}
+
+impl RiskSource for OverlayDetectionResult {
+    fn findings(&self) -> Vec<Finding> {
+        self.suspicious_overlays.iter().map(|o| {
+            let base = match o.reason.as_str() {
+                "cheat_process" => 90,
+                "suspicious_class" => 70,
+                "transparent_topmost" => 50,
+                _ => 30,
+            };
+            let subject = format!("{} - {}", o.process_name, o.window_title);
+            Finding::new(FindingKind::Overlay, subject, format!("{}, class {}", o.reason, o.class_name), base)
+        }).collect()
+    }
+}
+
+impl RiskSource for DllInjectionResult {
+    fn findings(&self) -> Vec<Finding> {
+        self.suspicious_dlls.iter().map(|d| {
+            let subject = d.path.clone().unwrap_or_else(|| d.name.clone());
+            if hardware::INJECTION_TOOLS.iter().any(|(_, reason)| *reason == d.reason) {
+                return Finding::new(FindingKind::InjectionTool, subject, &d.reason, 90);
+            }
+            let base = match d.reason.as_str() {
+                "DLL loaded from temp directory" => 60,
+                reason => hardware::signature_risk(reason).unwrap_or(80),
+            };
+            Finding::new(FindingKind::SuspiciousDll, subject, &d.reason, base)
+        }).collect()
+    }
+}
+
+impl RiskSource for VmDetectionResult {
+    fn findings(&self) -> Vec<Finding> {
+        if !self.vm_detected {
+            return Vec::new();
+        }
+        let vm_type = self.vm_type.clone().unwrap_or_else(|| "unknown".to_string());
+        vec![Finding::new(FindingKind::VirtualMachine, vm_type, self.vm_indicators.join("; "), 100)]
+    }
+}
+
+impl RiskSource for CloudPcDetectionResult {
+    fn findings(&self) -> Vec<Finding> {
+        if !self.cloud_pc_detected {
+            return Vec::new();
+        }
+        let base = match (&self.cloud_provider, self.is_gaming_cloud) {
+            (_, true) => 80,
+            (Some(_), false) => 60,
+            (None, false) => 40, // Datacenter GPU only
+        };
+        let provider = self.cloud_provider.clone().unwrap_or_else(|| "unknown".to_string());
+        vec![Finding::new(FindingKind::CloudPc, provider, self.cloud_indicators.join("; "), base)]
+    }
+}
+
+impl RiskSource for CheatWindowDetectionResult {
+    fn findings(&self) -> Vec<Finding> {
+        self.detected_windows.iter().map(|w| {
+            let base = match RiskLevel::from_name(&w.risk_level) {
+                Some(RiskLevel::Critical) => 100,
+                Some(RiskLevel::High) => 75,
+                Some(RiskLevel::Medium) => 40,
+                _ => 25,
+            };
+            let subject = format!("{} - {}", w.process_name, w.window_title);
+            Finding::new(FindingKind::CheatWindow, subject, format!("matched {}", w.matched_cheat), base)
+        }).collect()
+    }
+}
+
+impl RiskSource for HashReputationResult {
+    fn findings(&self) -> Vec<Finding> {
+        self.known_bad.iter()
+            .map(|h| Finding::new(FindingKind::KnownBadHash, &h.path, format!("{} ({})", h.matched_name, h.sha256), 100))
+            .collect()
+    }
+}
+
+impl RiskSource for PatternScanResult {
+    fn findings(&self) -> Vec<Finding> {
+        self.matches.iter()
+            .map(|m| Finding::new(FindingKind::BytePattern, format!("{} [{}]", m.path, m.rule), &m.description, m.risk))
+            .collect()
+    }
+}
+
+impl RiskSource for MeasuredBootReport {
+    fn findings(&self) -> Vec<Finding> {
+        // Secure Boot itself is part of the security status; only flags that weaken
+        // a measured boot and a log the TPM does not confirm are scored here
+        let mut findings: Vec<Finding> = self.indicators.iter()
+            .map(|indicator| {
+                let base = if indicator.starts_with("Test signing") { 80 } else { 60 };
+                Finding::new(FindingKind::BootIntegrity, indicator, "measured boot log", base)
+            })
+            .collect();
+        if !self.pcr_mismatches.is_empty() {
+            let detail = format!("replayed PCRs {:?} differ from the TPM", self.pcr_mismatches);
+            findings.push(Finding::new(FindingKind::BootIntegrity, "Forged event log", detail, 100));
+        }
+        findings
+    }
+}
+
+impl RiskSource for HwidConsistencyResult {
+    fn findings(&self) -> Vec<Finding> {
+        self.indicators.iter()
+            .map(|i| Finding::new(FindingKind::HwidSpoofing, &i.identifier, &i.detail, i.weight()))
+            .collect()
+    }
+}
+
+impl RiskSource for DmaDeviceResult {
+    fn findings(&self) -> Vec<Finding> {
+        self.suspicious_devices.iter()
+            .map(|d| {
+                let detail = d.indicators.iter().map(|i| i.detail.as_str()).collect::<Vec<_>>().join("; ");
+                Finding::new(FindingKind::DmaDevice, format!("{} @ {}", d.ids, d.location), detail, d.risk_score)
+            })
+            .collect()
+    }
+}
+
+impl RiskSource for UsbTopologyResult {
+    fn findings(&self) -> Vec<Finding> {
+        self.matches.iter()
+            .filter(|m| m.correlated)
+            .map(|m| {
+                let detail = m.indicators.iter().map(|i| i.detail.as_str()).collect::<Vec<_>>().join("; ");
+                let subject = m.device.topology.clone().unwrap_or_else(|| m.device.name.clone());
+                Finding::new(FindingKind::UsbTopology, subject, detail, m.risk_score)
+            })
+            .collect()
+    }
+}
+
+// Inventories: reported raw, no findings
+impl RiskSource for Vec<ProcessInfo> {}
+impl RiskSource for Vec<UsbDeviceInfo> {}
+impl RiskSource for GameSessionActivity {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hardware::SuspiciousOverlay;
+
+    fn finding(kind: FindingKind, subject: &str, base: u32) -> Finding {
+        Finding::new(kind, subject, "", base)
+    }
+
+    fn points(assessment: &RiskAssessment, subject: &str) -> u32 {
+        assessment.findings.iter().find(|f| f.subject == subject).map(|f| f.points).unwrap()
+    }
+
+    #[test]
+    fn same_subject_twice_in_a_cycle_keeps_the_strongest() {
+        let mut engine = RiskEngine::new();
+        let findings = [
+            finding(FindingKind::CheatProcess, "cheat.exe", 75),
+            finding(FindingKind::CheatProcess, "CHEAT.EXE", 90),
+            finding(FindingKind::CheatProcess, "cheat.exe", 40),
+            finding(FindingKind::KnownBadHash, "cheat.exe", 100), // Another kind is another finding
+        ];
+        let assessment = engine.assess(&findings, &RiskConfig::default(), Instant::now());
+        assert_eq!(assessment.findings.len(), 2);
+        assert_eq!(assessment.score, 190);
+        assert_eq!(assessment.findings[0].kind, FindingKind::KnownBadHash);
+        assert_eq!(assessment.findings[1].base, 90);
+    }
+
+    #[test]
+    fn unobserved_findings_decay_with_the_half_life() {
+        let mut engine = RiskEngine::new();
+        let config = RiskConfig::default();
+        let start = Instant::now();
+        let half_life = Duration::from_secs(config.decay_half_life_secs);
+
+        let observed = engine.assess(&[finding(FindingKind::CheatProcess, "cheat.exe", 80)], &config, start);
+        assert_eq!(observed.score, 80);
+        assert_eq!((observed.findings[0].decay, observed.findings[0].last_seen_secs), (1.0, 0));
+
+        let half = engine.assess(&[], &config, start + half_life);
+        assert_eq!(half.score, 40);
+        assert_eq!((half.findings[0].decay, half.findings[0].last_seen_secs), (0.5, 300));
+        assert!(half.findings[0].explanation.contains("x 0.50 decay (last seen 300s ago)"), "{}", half.findings[0].explanation);
+
+        assert_eq!(engine.assess(&[], &config, start + half_life * 2).score, 20);
+
+        // Seen again: full points
+        let again = engine.assess(&[finding(FindingKind::CheatProcess, "cheat.exe", 80)], &config, start + half_life * 3);
+        assert_eq!(again.score, 80);
+
+        // Forgotten once below one point: 80 / 2^6 = 1.25, 80 / 2^7 = 0.63
+        let later = start + half_life * 3;
+        let faint = engine.assess(&[], &config, later + half_life * 6);
+        assert_eq!(faint.findings.iter().map(|f| f.points).collect::<Vec<_>>(), [1]);
+        assert!(engine.assess(&[], &config, later + half_life * 7).findings.is_empty());
+    }
+
+    #[test]
+    fn server_weights_override_the_default() {
+        let mut config = RiskConfig::default();
+        config.weights.insert("macro".to_string(), 0.5);
+        config.weights.insert("proxy".to_string(), 2.0);
+        config.weights.insert("overlay".to_string(), f64::NAN); // Ignored
+        config.weights.insert("vpn_process".to_string(), -1.0);  // Ignored
+
+        let findings = [
+            finding(FindingKind::Macro, "ahk.exe", 80),
+            finding(FindingKind::Proxy, "proxy:8080", 20),
+            finding(FindingKind::Overlay, "overlay", 50),
+            finding(FindingKind::VpnProcess, "vpn.exe", 30),
+        ];
+        let assessment = RiskEngine::new().assess(&findings, &config, Instant::now());
+        assert_eq!(points(&assessment, "ahk.exe"), 40);
+        assert_eq!(points(&assessment, "proxy:8080"), 40);
+        assert_eq!(points(&assessment, "overlay"), 50);
+        assert_eq!(points(&assessment, "vpn.exe"), 30);
+        assert_eq!(assessment.score, 160);
+        let macro_finding = assessment.findings.iter().find(|f| f.subject == "ahk.exe").unwrap();
+        assert_eq!(macro_finding.weight, 0.5);
+        assert_eq!(macro_finding.explanation, "Macro software 'ahk.exe': 80 x 0.50 = 40");
+    }
+
+    #[test]
+    fn per_kind_caps_cut_the_weakest_findings() {
+        let config = RiskConfig::default();
+        let network = NetworkMonitorResult {
+            vpn_adapters: vec!["TAP-Windows Adapter V9".to_string(), "WireGuard Tunnel".to_string(), "Mullvad".to_string()],
+            ..Default::default()
+        };
+        let assessment = RiskEngine::new().assess(&network.findings(), &config, Instant::now());
+        assert_eq!(assessment.score, 80); // 3 x 40, VpnAdapter cap 80
+        let capped = assessment.findings.iter().find(|f| f.points == 0).unwrap();
+        assert!(capped.explanation.ends_with("= 40, vpn_adapter cap 80 reached -> 0"), "{}", capped.explanation);
+
+        let overlays = OverlayDetectionResult {
+            suspicious_overlays: ["a", "b", "c"].iter().map(|name| SuspiciousOverlay {
+                window_title: String::new(),
+                process_name: name.to_string(),
+                class_name: String::new(),
+                reason: "cheat_process".to_string(),
+            }).collect(),
+            ..Default::default()
+        };
+        let assessment = RiskEngine::new().assess(&overlays.findings(), &config, Instant::now());
+        let mut awarded: Vec<u32> = assessment.findings.iter().map(|f| f.points).collect();
+        awarded.sort();
+        assert_eq!(awarded, [0, 90, 90]); // Overlay cap 180
+        assert_eq!(assessment.score, 180);
+
+        // Uncapped kinds add up
+        let processes = [finding(FindingKind::CheatProcess, "a", 75), finding(FindingKind::CheatProcess, "b", 75),
+                         finding(FindingKind::CheatProcess, "c", 75)];
+        assert_eq!(RiskEngine::new().assess(&processes, &config, Instant::now()).score, 225);
+    }
+
+    #[test]
+    fn score_is_capped_at_max_score() {
+        let config = RiskConfig { max_score: 250, ..Default::default() };
+        let findings: Vec<Finding> = (0..5).map(|i| finding(FindingKind::KnownBadHash, &format!("bad{}.exe", i), 100)).collect();
+        let assessment = RiskEngine::new().assess(&findings, &config, Instant::now());
+        assert_eq!(assessment.score, 250);
+        assert_eq!(assessment.findings.iter().map(|f| f.points).sum::<u32>(), 500); // Per finding points are kept
+        assert_eq!(assessment.level, RiskLevel::Critical);
+    }
+
+    #[test]
+    fn levels_follow_the_thresholds() {
+        let levels = LevelThresholds::default();
+        for (score, level) in [(0, RiskLevel::Low), (24, RiskLevel::Low), (25, RiskLevel::Medium), (49, RiskLevel::Medium),
+                               (50, RiskLevel::High), (99, RiskLevel::High), (100, RiskLevel::Critical), (1000, RiskLevel::Critical)] {
+            assert_eq!(RiskLevel::from_score(score, &levels), level, "score {}", score);
+        }
+
+        let mut engine = RiskEngine::new();
+        let config = RiskConfig::default();
+        assert_eq!(engine.assess(&[], &config, Instant::now()).level, RiskLevel::Low);
+        let assessment = engine.assess(&[finding(FindingKind::Proxy, "proxy", 25)], &config, Instant::now());
+        assert_eq!((assessment.score, assessment.level), (25, RiskLevel::Medium));
+
+        let strict = RiskConfig { levels: LevelThresholds { medium: 10, high: 20, critical: 30 }, ..Default::default() };
+        let assessment = RiskEngine::new().assess(&[finding(FindingKind::Proxy, "proxy", 25)], &strict, Instant::now());
+        assert_eq!(assessment.level, RiskLevel::High);
+    }
+}
@@ -0,0 +1,360 @@
+//! Match rules module - precise matching for signature entries
+//!
+//! Rules are written as `<kind>:<value>` with optional exclusions, each starting
+//! with ` !<kind>:`:
+//!
+//! - `exact:ds4windows.exe`       whole name
+//! - `prefix:cronus`              name starts with
+//! - `glob:*aimbot*`              `*` / `?` wildcards over the whole name
+//! - `re:^python\d+\.dll$`        case-insensitive regex over the name
+//! - `path:*\temp\*.dll`          glob over the full path (`/` normalized to `\`)
+//! - `usb:2341:8036`              USB VID (and optional PID)
+//! - `sha256:<hex>`               image hash
+//!
+//! `glob:*virtual ethernet* !glob:*hyper-v*` matches unless an exclusion matches.
+//! A ` !` not followed by a kind is part of the value, and a `re:` pattern runs to
+//! the end of the rule, so it can only be the last part.
+//! Names, paths and patterns are compared case-insensitively.
+
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+/// What a rule is matched against
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Subject<'a> {
+    pub name: &'a str,
+    pub path: Option<&'a str>,
+    pub vid: Option<&'a str>,
+    pub pid: Option<&'a str>,
+    pub sha256: Option<&'a str>,
+}
+
+impl<'a> Subject<'a> {
+    pub fn name(name: &'a str) -> Self {
+        Subject { name, ..Default::default() }
+    }
+
+    pub fn file(name: &'a str, path: Option<&'a str>) -> Self {
+        Subject { name, path, ..Default::default() }
+    }
+
+    pub fn usb(vid: Option<&'a str>, pid: Option<&'a str>) -> Self {
+        Subject { vid, pid, ..Default::default() }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Matcher {
+    Exact(String),
+    Prefix(String),
+    Glob(String),
+    Regex(Regex),
+    Path(String),
+    Usb { vid: String, pid: Option<String> },
+    Hash(String),
+}
+
+impl Matcher {
+    fn parse(text: &str) -> Result<Self, String> {
+        let text = text.trim();
+        let (kind, value) = match text.split_once(':') {
+            Some((kind, value)) if KINDS.contains(&kind) => (kind, value),
+            _ => ("exact", text),
+        };
+
+        if value.is_empty() {
+            return Err(format!("Empty rule value: '{}'", text));
+        }
+
+        Ok(match kind {
+            "prefix" => Matcher::Prefix(value.to_lowercase()),
+            "glob" => Matcher::Glob(value.to_lowercase()),
+            "re" => Matcher::Regex(
+                RegexBuilder::new(value)
+                    .case_insensitive(true)
+                    .size_limit(1 << 20)
+                    .build()
+                    .map_err(|e| format!("Invalid regex '{}': {}", value, e))?,
+            ),
+            "path" => Matcher::Path(normalize_path(value)),
+            "usb" => {
+                let mut ids = value.split(':');
+                let vid = ids.next().unwrap_or_default().to_lowercase();
+                let pid = ids.next().map(|p| p.to_lowercase());
+                if vid.len() != 4 || pid.as_ref().is_some_and(|p| p.len() != 4) {
+                    return Err(format!("Invalid USB id '{}'", value));
+                }
+                Matcher::Usb { vid, pid }
+            }
+            "sha256" => {
+                if value.len() != 64 || !value.chars().all(|c| c.is_ascii_hexdigit()) {
+                    return Err(format!("Invalid SHA-256 '{}'", value));
+                }
+                Matcher::Hash(value.to_lowercase())
+            }
+            _ => Matcher::Exact(value.to_lowercase()),
+        })
+    }
+
+    fn matches(&self, subject: &Subject) -> bool {
+        let name = subject.name.to_lowercase();
+
+        match self {
+            Matcher::Exact(value) => !name.is_empty() && name == *value,
+            Matcher::Prefix(value) => !name.is_empty() && name.starts_with(value.as_str()),
+            Matcher::Glob(pattern) => !name.is_empty() && glob_match(pattern, &name),
+            Matcher::Regex(regex) => !name.is_empty() && regex.is_match(subject.name),
+            Matcher::Path(pattern) => subject.path
+                .map(|path| glob_match(pattern, &normalize_path(path)))
+                .unwrap_or(false),
+            Matcher::Usb { vid, pid } => match subject.vid {
+                Some(v) if v.eq_ignore_ascii_case(vid) => match pid {
+                    Some(p) => subject.pid.is_some_and(|sp| sp.eq_ignore_ascii_case(p)),
+                    None => true,
+                },
+                _ => false,
+            },
+            Matcher::Hash(hash) => subject.sha256.is_some_and(|h| h.eq_ignore_ascii_case(hash)),
+        }
+    }
+}
+
+const KINDS: &[&str] = &["exact", "prefix", "glob", "re", "path", "usb", "sha256"];
+
+/// A signature rule with optional negative exclusions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct MatchRule {
+    source: String,
+    matcher: Matcher,
+    exclusions: Vec<Matcher>,
+}
+
+impl MatchRule {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut parts = split_exclusions(text).into_iter();
+        let matcher = Matcher::parse(parts.next().unwrap_or_default())?;
+        let exclusions = parts.map(Matcher::parse).collect::<Result<Vec<_>, _>>()?;
+
+        Ok(MatchRule {
+            source: text.trim().to_string(),
+            matcher,
+            exclusions,
+        })
+    }
+
+    /// Whole-name rule taking `text` literally (no kind prefix, no exclusions)
+    pub fn literal(text: &str) -> Self {
+        let text = text.trim();
+        MatchRule {
+            source: text.to_string(),
+            matcher: Matcher::Exact(text.to_lowercase()),
+            exclusions: Vec::new(),
+        }
+    }
+
+    /// Parse a compiled-in rule (invalid built-in rules are a programming error)
+    pub fn builtin(text: &str) -> Self {
+        Self::parse(text).unwrap_or_else(|e| panic!("Invalid built-in rule: {}", e))
+    }
+
+    pub fn matches(&self, subject: &Subject) -> bool {
+        self.matcher.matches(subject) && !self.exclusions.iter().any(|e| e.matches(subject))
+    }
+}
+
+impl std::fmt::Display for MatchRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl TryFrom<String> for MatchRule {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<Self, Self::Error> {
+        MatchRule::parse(&text)
+    }
+}
+
+impl From<MatchRule> for String {
+    fn from(rule: MatchRule) -> Self {
+        rule.source
+    }
+}
+
+/// Rule text cut before each ` !<kind>:` exclusion, never inside a `re:` pattern
+fn split_exclusions(text: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut rest = text;
+    loop {
+        if rest.trim_start().starts_with("re:") {
+            break;
+        }
+        let next = rest.match_indices(" !").map(|(i, _)| i).find(|&i| {
+            rest[i + 2..].split_once(':').is_some_and(|(kind, _)| KINDS.contains(&kind))
+        });
+        match next {
+            Some(i) => {
+                parts.push(&rest[..i]);
+                rest = &rest[i + 2..];
+            }
+            None => break,
+        }
+    }
+    parts.push(rest);
+    parts
+}
+
+/// Parse a compiled-in rule table
+pub fn compile(list: &[&str]) -> Vec<MatchRule> {
+    list.iter().map(|rule| MatchRule::builtin(rule)).collect()
+}
+
+/// Extract VID/PID from a PnP device id (`USB\VID_2341&PID_8036\...`)
+pub fn parse_usb_ids(device_id: &str) -> (Option<String>, Option<String>) {
+    let upper = device_id.to_uppercase();
+    let extract = |tag: &str| {
+        upper.find(tag)
+            .map(|i| &upper[i + tag.len()..])
+            .filter(|rest| rest.len() >= 4 && rest[..4].chars().all(|c| c.is_ascii_hexdigit()))
+            .map(|rest| rest[..4].to_lowercase())
+    };
+    (extract("VID_"), extract("PID_"))
+}
+
+fn normalize_path(path: &str) -> String {
+    path.replace('/', "\\").to_lowercase()
+}
+
+/// `*` / `?` wildcard match over the whole string
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let p: Vec<char> = pattern.chars().collect();
+    let t: Vec<char> = text.chars().collect();
+    let (mut pi, mut ti) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+
+    while ti < t.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi] == t[ti]) {
+            pi += 1;
+            ti += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            star = Some((pi, ti));
+            pi += 1;
+        } else if let Some((star_pi, star_ti)) = star {
+            // Let the last '*' absorb one more character
+            pi = star_pi + 1;
+            ti = star_ti + 1;
+            star = Some((star_pi, star_ti + 1));
+        } else {
+            return false;
+        }
+    }
+
+    p[pi..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(text: &str) -> MatchRule {
+        MatchRule::parse(text).unwrap()
+    }
+
+    #[test]
+    fn glob() {
+        assert!(glob_match("*aimbot*", "my_aimbot_v2.exe"));
+        assert!(glob_match("cheat?.exe", "cheat1.exe"));
+        assert!(!glob_match("cheat?.exe", "cheat.exe"));
+        assert!(glob_match("*.dll", ".dll"));
+        assert!(glob_match("a*b*c", "aXbYbZc"));
+        assert!(!glob_match("a*b*c", "aXbYbZ"));
+        assert!(glob_match("**", ""));
+        assert!(!glob_match("", "x"));
+        assert!(glob_match("ünï*", "ünïcode")); // Characters, not bytes
+    }
+
+    #[test]
+    fn paths_are_normalized() {
+        assert_eq!(normalize_path("C:/Users/Bob/AppData/Local/Temp/X.DLL"), "c:\\users\\bob\\appdata\\local\\temp\\x.dll");
+        let temp = rule("path:*/temp/*.dll");
+        assert!(temp.matches(&Subject::file("x.dll", Some("C:\\Users\\Bob\\AppData\\Local\\Temp\\X.DLL"))));
+        assert!(temp.matches(&Subject::file("x.dll", Some("/home/bob/TEMP/x.dll"))));
+        assert!(!temp.matches(&Subject::file("x.dll", Some("C:\\Windows\\x.dll"))));
+        assert!(!temp.matches(&Subject::name("x.dll"))); // No path
+    }
+
+    #[test]
+    fn kinds() {
+        assert!(rule("DS4Windows.exe").matches(&Subject::name("ds4windows.exe")));
+        assert!(!rule("exact:ds4windows").matches(&Subject::name("ds4windows.exe")));
+        assert!(rule("prefix:cronus").matches(&Subject::name("CronusZen.exe")));
+        assert!(rule("re:^python\\d+\\.dll$").matches(&Subject::name("Python311.dll")));
+        assert!(!rule("prefix:x").matches(&Subject::name(""))); // Empty names never match
+        assert!(MatchRule::parse("glob:").unwrap_err().contains("Empty rule value"));
+        assert!(MatchRule::parse("re:(").unwrap_err().starts_with("Invalid regex '('"));
+        assert_eq!(rule(" glob:*x* ").to_string(), "glob:*x*");
+    }
+
+    #[test]
+    fn usb() {
+        let device = rule("usb:2341:8036");
+        assert!(device.matches(&Subject::usb(Some("2341"), Some("8036"))));
+        assert!(!device.matches(&Subject::usb(Some("2341"), Some("0043"))));
+        assert!(!device.matches(&Subject::usb(Some("2341"), None)));
+
+        let vendor = rule("usb:2341");
+        assert!(vendor.matches(&Subject::usb(Some("2341"), None)));
+        assert!(vendor.matches(&Subject::usb(Some("2341"), Some("0043"))));
+        assert!(!vendor.matches(&Subject::usb(Some("046d"), Some("c077"))));
+        assert!(!vendor.matches(&Subject::name("2341")));
+
+        assert!(rule("usb:1D6B:0002").matches(&Subject::usb(Some("1d6b"), Some("0002"))));
+        for bad in ["usb:234", "usb:2341:80", "usb:2341:80366"] {
+            assert!(MatchRule::parse(bad).unwrap_err().starts_with("Invalid USB id"), "{}", bad);
+        }
+        assert_eq!(parse_usb_ids("USB\\VID_2341&PID_8036\\1-1.2"), (Some("2341".to_string()), Some("8036".to_string())));
+        assert_eq!(parse_usb_ids("HID\\VID_046D"), (Some("046d".to_string()), None));
+    }
+
+    #[test]
+    fn sha256() {
+        let hash = "E3B0C44298FC1C149AFBF4C8996FB92427AE41E4649B934CA495991B7852B855";
+        let subject = Subject { sha256: Some(hash), ..Subject::name("x.exe") };
+        assert!(rule(&format!("sha256:{}", hash.to_lowercase())).matches(&subject));
+        assert!(!rule(&format!("sha256:{}", "0".repeat(64))).matches(&subject));
+        assert!(!rule(&format!("sha256:{}", hash)).matches(&Subject::name("x.exe")));
+        assert!(MatchRule::parse(&format!("sha256:{}", &hash[1..])).unwrap_err().starts_with("Invalid SHA-256"));
+        assert!(MatchRule::parse(&format!("sha256:{}g", &hash[1..])).unwrap_err().starts_with("Invalid SHA-256"));
+    }
+
+    #[test]
+    fn exclusions() {
+        let adapter = rule("glob:*virtual ethernet* !glob:*hyper-v* !exact:vethernet (wsl) virtual ethernet");
+        assert!(adapter.matches(&Subject::name("SoftEther Virtual Ethernet Adapter")));
+        assert!(!adapter.matches(&Subject::name("Hyper-V Virtual Ethernet Adapter")));
+        assert!(!adapter.matches(&Subject::name("vEthernet (WSL) Virtual Ethernet")));
+        assert!(MatchRule::parse("glob:*x* !usb:12").is_err());
+
+        // A regex exclusion is the last part
+        let loader = rule("prefix:loader !re:^loader(32|64)\\.dll$");
+        assert!(loader.matches(&Subject::name("loader_x.exe")));
+        assert!(!loader.matches(&Subject::name("loader64.dll")));
+    }
+
+    #[test]
+    fn exclamation_marks_inside_values() {
+        // Not followed by a kind: part of the literal name
+        let name = rule("exact:hey !cheat.exe");
+        assert!(name.matches(&Subject::name("HEY !cheat.exe")));
+        assert!(!name.matches(&Subject::name("hey")));
+
+        // Regex bodies are never split
+        let regex = rule("re:^(hack|cheat) !glob:x$");
+        assert!(regex.matches(&Subject::name("cheat !glob:x")));
+        assert!(!regex.matches(&Subject::name("cheat")));
+        assert!(rule("re:^a !b$").matches(&Subject::name("a !b")));
+        assert_eq!(rule("re:[ !]").to_string(), "re:[ !]");
+    }
+}
@@ -0,0 +1,456 @@
+//! Signature database module - server-delivered, signed detection lists
+//!
+//! The bundle is downloaded from the API, verified against the embedded Ed25519
+//! public key, cached on disk and swapped in at runtime. The lists compiled into
+//! hardware.rs are used until a valid bundle is available.
+//!
+//! The public key is the maintainers' signing key, embedded at build time by
+//! build.rs (see Server/scripts/iris-signing-key.md). Builds without it only use
+//! the compiled-in lists.
+
+use crate::patterns::{self, PatternRule};
+use crate::risk::RiskConfig;
+use crate::rules::MatchRule;
+use crate::{api, hardware, rules, store};
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+
+/// Ed25519 public key used to verify signature bundles (hex, set by build.rs)
+const BUNDLE_PUBLIC_KEY: Option<&str> = option_env!("IRIS_BUNDLE_PUBLIC_KEY");
+
+const CACHE_FILE: &str = "signatures.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceSignature {
+    pub rule: MatchRule, // usb:VID[:PID]
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegistryTraceSignature {
+    pub path: String,
+    pub name: String,
+    pub trace_type: String,
+}
+
+/// Rule with a human readable reason (drivers, DLLs)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReasonSignature {
+    pub rule: MatchRule,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MacroSignature {
+    pub rule: MatchRule,
+    pub macro_type: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WindowSignature {
+    pub rule: MatchRule,
+    pub name: String,
+    pub risk: String,
+}
+
+/// SHA-256 of an executable image (lowercase hex)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HashSignature {
+    pub sha256: String,
+    pub name: String,
+}
+
+/// Detection lists (version 0 = compiled-in fallback)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignatureBundle {
+    pub version: u64,
+    pub cheat_devices: Vec<DeviceSignature>,
+    pub cheat_processes: Vec<MatchRule>,
+    pub vpn_adapter_keywords: Vec<MatchRule>,
+    pub registry_cheat_traces: Vec<RegistryTraceSignature>,
+    pub suspicious_driver_names: Vec<ReasonSignature>,
+    pub macro_processes: Vec<MacroSignature>,
+    pub suspicious_dll_names: Vec<ReasonSignature>,
+    pub cheat_window_keywords: Vec<WindowSignature>,
+    #[serde(default)]
+    pub known_bad_hashes: Vec<HashSignature>,
+    #[serde(default)]
+    pub known_good_hashes: Vec<HashSignature>,
+    #[serde(default = "default_trusted_publishers")]
+    pub trusted_publishers: Vec<MatchRule>, // Matched against the Authenticode signer
+    #[serde(default = "default_trusted_root_keys")]
+    pub trusted_root_keys: Vec<HashSignature>, // SHA-256 of pinned root SubjectPublicKeyInfos
+    #[serde(default = "patterns::builtin_rules")]
+    pub byte_patterns: Vec<PatternRule>,
+    #[serde(default)]
+    pub risk: RiskConfig, // Weights, caps and levels of the risk engine
+}
+
+/// Signed envelope as delivered by the API and stored on disk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedBundle {
+    pub version: u64,
+    pub payload: String,   // Base64 JSON of SignatureBundle
+    pub signature: String, // Base64 Ed25519 signature over the decoded payload
+}
+
+impl SignatureBundle {
+    /// Lists compiled into the binary
+    pub fn builtin() -> Self {
+        SignatureBundle {
+            version: 0,
+            cheat_devices: hardware::CHEAT_DEVICES.iter().map(|(rule, name)| DeviceSignature {
+                rule: MatchRule::builtin(rule),
+                name: name.to_string(),
+            }).collect(),
+            cheat_processes: rules::compile(hardware::CHEAT_PROCESSES),
+            vpn_adapter_keywords: rules::compile(hardware::VPN_ADAPTER_KEYWORDS),
+            registry_cheat_traces: hardware::REGISTRY_CHEAT_TRACES.iter().map(|(path, name, trace_type)| RegistryTraceSignature {
+                path: path.to_string(),
+                name: name.to_string(),
+                trace_type: trace_type.to_string(),
+            }).collect(),
+            suspicious_driver_names: to_reason_signatures(hardware::SUSPICIOUS_DRIVER_NAMES),
+            macro_processes: hardware::MACRO_PROCESSES.iter().map(|(rule, macro_type)| MacroSignature {
+                rule: MatchRule::builtin(rule),
+                macro_type: macro_type.to_string(),
+            }).collect(),
+            suspicious_dll_names: to_reason_signatures(hardware::SUSPICIOUS_DLL_NAMES),
+            cheat_window_keywords: hardware::CHEAT_WINDOW_KEYWORDS.iter().map(|(rule, name, risk)| WindowSignature {
+                rule: MatchRule::builtin(rule),
+                name: name.to_string(),
+                risk: risk.to_string(),
+            }).collect(),
+            // Hash lists only come from the server bundle
+            known_bad_hashes: Vec::new(),
+            known_good_hashes: Vec::new(),
+            trusted_publishers: default_trusted_publishers(),
+            trusted_root_keys: default_trusted_root_keys(),
+            byte_patterns: patterns::builtin_rules(),
+            risk: RiskConfig::default(),
+        }
+    }
+}
+
+fn default_trusted_publishers() -> Vec<MatchRule> {
+    rules::compile(hardware::TRUSTED_PUBLISHERS)
+}
+
+fn default_trusted_root_keys() -> Vec<HashSignature> {
+    hardware::TRUSTED_ROOT_KEYS.iter().map(|(sha256, name)| HashSignature {
+        sha256: sha256.to_string(),
+        name: name.to_string(),
+    }).collect()
+}
+
+fn to_reason_signatures(list: &[(&str, &str)]) -> Vec<ReasonSignature> {
+    list.iter().map(|(rule, reason)| ReasonSignature {
+        rule: MatchRule::builtin(rule),
+        reason: reason.to_string(),
+    }).collect()
+}
+
+lazy_static::lazy_static! {
+    static ref CURRENT: RwLock<Arc<SignatureBundle>> = RwLock::new(Arc::new(load_initial()));
+}
+
+/// Bundle currently used by the detectors
+pub fn current() -> Arc<SignatureBundle> {
+    CURRENT.read()
+        .map(|bundle| bundle.clone())
+        .unwrap_or_else(|_| Arc::new(SignatureBundle::builtin()))
+}
+
+/// Version of the active bundle (0 = compiled-in lists)
+pub fn version() -> u64 {
+    current().version
+}
+
+/// Verify a signed envelope against the embedded key and decode the bundle it carries
+pub fn verify(signed: &SignedBundle) -> Result<SignatureBundle, String> {
+    let public_key = BUNDLE_PUBLIC_KEY
+        .ok_or("No bundle public key in this build (IRIS_BUNDLE_PUBLIC_KEY)")
+        .and_then(|key| hex::decode(key).map_err(|_| "Invalid bundle public key"))?;
+    verify_with_key(signed, &public_key)
+}
+
+/// Verify a signed envelope against an Ed25519 public key (raw 32 bytes)
+fn verify_with_key(signed: &SignedBundle, public_key: &[u8]) -> Result<SignatureBundle, String> {
+    let engine = base64::engine::general_purpose::STANDARD;
+    let payload = engine.decode(&signed.payload)
+        .map_err(|e| format!("Invalid payload encoding: {}", e))?;
+    let signature = engine.decode(&signed.signature)
+        .map_err(|e| format!("Invalid signature encoding: {}", e))?;
+
+    ring::signature::UnparsedPublicKey::new(&ring::signature::ED25519, public_key)
+        .verify(&payload, &signature)
+        .map_err(|_| "Signature verification failed".to_string())?;
+
+    let bundle: SignatureBundle = serde_json::from_slice(&payload)
+        .map_err(|e| format!("Invalid bundle: {}", e))?;
+
+    if bundle.version != signed.version {
+        return Err(format!("Version mismatch: envelope {} / payload {}", signed.version, bundle.version));
+    }
+
+    if let Some(bad) = bundle.known_bad_hashes.iter().chain(&bundle.known_good_hashes).chain(&bundle.trusted_root_keys)
+        .find(|h| h.sha256.len() != 64 || !h.sha256.chars().all(|c| c.is_ascii_hexdigit())) {
+        return Err(format!("Invalid hash entry for '{}'", bad.name));
+    }
+
+    Ok(bundle)
+}
+
+/// Verify, cache and activate a bundle. Older or equal versions are ignored.
+pub fn install(signed: &SignedBundle) -> Result<u64, String> {
+    let bundle = verify(signed)?;
+    let active = version();
+
+    if bundle.version <= active {
+        return Ok(active);
+    }
+
+    if let Err(e) = save_cache(signed) {
+        println!("[Signatures] Failed to cache bundle: {}", e);
+    }
+
+    let installed = bundle.version;
+    if let Ok(mut current) = CURRENT.write() {
+        *current = Arc::new(bundle);
+    }
+
+    println!("[Signatures] Bundle v{} active (was v{})", installed, active);
+    Ok(installed)
+}
+
+/// Ask the API for a newer bundle and install it
+pub async fn refresh(api_client: &api::IrisApiClient, token: &str) -> Result<u64, String> {
+    let active = version();
+    let response = api_client.get_signatures(token, active).await?;
+
+    let bundle = response.data
+        .as_ref()
+        .and_then(|data| data.get("bundle"))
+        .filter(|bundle| !bundle.is_null());
+
+    match bundle {
+        Some(bundle) => {
+            let signed: SignedBundle = serde_json::from_value(bundle.clone())
+                .map_err(|e| format!("Invalid bundle envelope: {}", e))?;
+            install(&signed)
+        }
+        None => Ok(active), // Already up to date
+    }
+}
+
+fn cache_path() -> PathBuf {
+    store::data_dir().join(CACHE_FILE)
+}
+
+fn save_cache(signed: &SignedBundle) -> Result<(), String> {
+    let path = cache_path();
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let json = serde_json::to_string(signed).map_err(|e| e.to_string())?;
+    std::fs::write(&path, json).map_err(|e| e.to_string())
+}
+
+/// Cached bundle if present and still valid, compiled-in lists otherwise
+fn load_initial() -> SignatureBundle {
+    let cached = std::fs::read_to_string(cache_path())
+        .ok()
+        .and_then(|json| serde_json::from_str::<SignedBundle>(&json).ok());
+
+    if let Some(signed) = cached {
+        match verify(&signed) {
+            Ok(bundle) => {
+                println!("[Signatures] Loaded cached bundle v{}", bundle.version);
+                return bundle;
+            }
+            Err(e) => println!("[Signatures] Cached bundle rejected: {}", e),
+        }
+    }
+
+    SignatureBundle::builtin()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::Subject;
+
+    // ====== LEGITIMATE SOFTWARE (must never match) ======
+    // Most entries are names that the old substring matching flagged.
+
+    const LEGITIMATE_PROCESSES: &[&str] = &[
+        "steam.exe", "discord.exe", "chrome.exe", "explorer.exe", "svchost.exe",
+        "searchhost.exe", "obs64.exe", "cod.exe", "blackops6.exe", "titanfall2.exe",
+        "hackmd.exe", "espeak.exe", "espeak-ng.exe", "hwmonitor.exe", "aida64.exe",
+        "nvidia share.exe", "nvidia web helper.exe", "ximalaya.exe",
+    ];
+
+    const LEGITIMATE_DLLS: &[&str] = &[
+        "kernel32.dll", "ntdll.dll", "user32.dll", "d3d11.dll", "dinput8.dll",
+        "webview2loader.dll", "discordhook64.dll", "rtsshooks64.dll",
+        "gameoverlayrenderer64.dll", "pythoncom311.dll", "lua54.dll",
+    ];
+
+    const LEGITIMATE_ADAPTERS: &[&str] = &[
+        "Hyper-V Virtual Ethernet Adapter", "vEthernet (Default Switch)",
+        "Intel(R) Ethernet Connection (7) I219-V", "Realtek PCIe GbE Family Controller",
+        "Killer E2600 Gigabit Ethernet Controller", "Intel(R) Wi-Fi 6 AX201 160MHz",
+    ];
+
+    const LEGITIMATE_WINDOW_TITLES: &[&str] = &[
+        "Steam", "Discord", "Battle.net", "Call of Duty® HQ", "HWMonitor",
+        "OBS 30.0.2 - Profile: Untitled", "HackMD - Google Chrome",
+        "Ring-10 Studio", "Onetapper - Clicker game", "Task Manager",
+    ];
+
+    const LEGITIMATE_DRIVERS: &[&str] = &[
+        "HidUsb", "hidclass", "mouhid", "kbdhid", "vhdmp", "intelppm", "nvlddmkm",
+        "xboxgip", "xinputhid",
+    ];
+
+    /// (VID, PID) of common non-cheat devices sharing vendors with cheat hardware
+    const LEGITIMATE_USB_DEVICES: &[(&str, &str)] = &[
+        ("2341", "0043"), // Arduino Uno
+        ("2341", "0042"), // Arduino Mega 2560
+        ("054c", "05c4"), // DualShock 4
+        ("054c", "0ce6"), // DualSense
+        ("045e", "02ea"), // Xbox One controller
+        ("0483", "3748"), // ST-Link V2
+    ];
+
+    /// Rules of a bundle that match an entry of the legitimate software lists
+    fn false_positives(bundle: &SignatureBundle) -> Vec<String> {
+        let driver_paths: Vec<String> = LEGITIMATE_DRIVERS.iter()
+            .map(|name| format!("\\SystemRoot\\System32\\drivers\\{}.sys", name))
+            .collect();
+
+        let processes: Vec<Subject> = LEGITIMATE_PROCESSES.iter().map(|name| Subject::name(name)).collect();
+        let dlls: Vec<Subject> = LEGITIMATE_DLLS.iter().map(|name| Subject::name(name)).collect();
+        let adapters: Vec<Subject> = LEGITIMATE_ADAPTERS.iter().map(|name| Subject::name(name)).collect();
+        let titles: Vec<Subject> = LEGITIMATE_WINDOW_TITLES.iter().map(|name| Subject::name(name)).collect();
+        let drivers: Vec<Subject> = LEGITIMATE_DRIVERS.iter().zip(&driver_paths)
+            .map(|(name, path)| Subject::file(name, Some(path)))
+            .collect();
+        let devices: Vec<Subject> = LEGITIMATE_USB_DEVICES.iter()
+            .map(|(vid, pid)| Subject::usb(Some(vid), Some(pid)))
+            .collect();
+
+        let mut hits = Vec::new();
+        for sig in &bundle.cheat_devices {
+            flag(&mut hits, &sig.rule, &devices);
+        }
+        for rule in &bundle.cheat_processes {
+            flag(&mut hits, rule, &processes);
+        }
+        for rule in &bundle.vpn_adapter_keywords {
+            flag(&mut hits, rule, &adapters);
+        }
+        for sig in &bundle.suspicious_driver_names {
+            flag(&mut hits, &sig.rule, &drivers);
+        }
+        for sig in &bundle.macro_processes {
+            flag(&mut hits, &sig.rule, &processes);
+        }
+        for sig in &bundle.suspicious_dll_names {
+            flag(&mut hits, &sig.rule, &dlls);
+        }
+        for sig in &bundle.cheat_window_keywords {
+            flag(&mut hits, &sig.rule, &titles);
+        }
+        hits
+    }
+
+    fn flag(hits: &mut Vec<String>, rule: &MatchRule, legitimate: &[Subject]) {
+        if let Some(subject) = legitimate.iter().find(|subject| rule.matches(subject)) {
+            let what = match (subject.vid, subject.pid) {
+                (Some(vid), Some(pid)) => format!("usb {}:{}", vid, pid),
+                _ => subject.name.to_string(),
+            };
+            hits.push(format!("'{}' matches legitimate '{}'", rule, what));
+        }
+    }
+
+    #[test]
+    fn builtin_rules_spare_legitimate_software() {
+        let hits = false_positives(&SignatureBundle::builtin());
+        assert!(hits.is_empty(), "{}", hits.join("\n"));
+    }
+
+    /// Server bundle, checked when the crate is built inside the full repository
+    #[test]
+    fn bundled_rules_spare_legitimate_software() {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../../Server/iris-signatures/bundle.json");
+        let Ok(json) = std::fs::read_to_string(&path) else {
+            println!("{} not found, skipped", path.display());
+            return;
+        };
+        let bundle: SignatureBundle = serde_json::from_str(&json)
+            .expect("bundle.json must parse as a SignatureBundle");
+        let hits = false_positives(&bundle);
+        assert!(hits.is_empty(), "{}", hits.join("\n"));
+    }
+
+    /// Envelope of a bundle signed with a test key, and that key's public half
+    fn signed(bundle: &SignatureBundle, seed: u8) -> (SignedBundle, Vec<u8>) {
+        use ring::signature::KeyPair;
+        let key = ring::signature::Ed25519KeyPair::from_seed_unchecked(&[seed; 32]).unwrap();
+        let payload = serde_json::to_vec(bundle).unwrap();
+        let engine = base64::engine::general_purpose::STANDARD;
+        let envelope = SignedBundle {
+            version: bundle.version,
+            payload: engine.encode(&payload),
+            signature: engine.encode(key.sign(&payload)),
+        };
+        (envelope, key.public_key().as_ref().to_vec())
+    }
+
+    fn bundle(version: u64) -> SignatureBundle {
+        SignatureBundle { version, ..SignatureBundle::builtin() }
+    }
+
+    #[test]
+    fn verifies_a_signed_bundle() {
+        let (envelope, key) = signed(&bundle(7), 1);
+        let verified = verify_with_key(&envelope, &key).unwrap();
+        assert_eq!(verified.version, 7);
+        assert_eq!(verified.cheat_processes.len(), SignatureBundle::builtin().cheat_processes.len());
+    }
+
+    #[test]
+    fn rejects_a_tampered_payload() {
+        let (mut envelope, key) = signed(&bundle(7), 1);
+        let mut tampered = bundle(7);
+        tampered.cheat_processes.clear();
+        envelope.payload = base64::engine::general_purpose::STANDARD.encode(serde_json::to_vec(&tampered).unwrap());
+        assert_eq!(verify_with_key(&envelope, &key).unwrap_err(), "Signature verification failed");
+    }
+
+    #[test]
+    fn rejects_another_key() {
+        let (envelope, _) = signed(&bundle(7), 1);
+        let (_, other_key) = signed(&bundle(7), 2);
+        assert_eq!(verify_with_key(&envelope, &other_key).unwrap_err(), "Signature verification failed");
+    }
+
+    #[test]
+    fn rejects_an_old_bundle_under_a_newer_version() {
+        // The envelope version is not signed: replaying v3 as v9 must fail
+        let (mut envelope, key) = signed(&bundle(3), 1);
+        envelope.version = 9;
+        assert_eq!(verify_with_key(&envelope, &key).unwrap_err(), "Version mismatch: envelope 9 / payload 3");
+    }
+}
@@ -0,0 +1,619 @@
+//! SMBIOS module - raw firmware table parsing
+//!
+//! The table is read straight from the firmware copy (GetSystemFirmwareTable 'RSMB'
+//! on Windows, /sys/firmware/dmi/tables on Linux) instead of WMI or the registry,
+//! which are derived from it and easier to patch. Each structure is a formatted
+//! area (type, length, handle, fields) followed by NUL-terminated strings that
+//! fields reference by 1-based index; the string set ends with an extra NUL.
+//!
+//! Decoded structures: BIOS (0), system (1), baseboard (2), chassis (3), processor
+//! (4) and memory device (17). Hypervisors and cloud hosts fill them with their own
+//! vendor strings, UUID layouts and few or no memory devices, which `platform_hints`
+//! turns into VM / cloud PC indicators (missing memory devices only back the others).
+
+use serde::{Deserialize, Serialize};
+
+const HEADER_SIZE: usize = 4;
+const TYPE_BIOS: u8 = 0;
+const TYPE_SYSTEM: u8 = 1;
+const TYPE_BASEBOARD: u8 = 2;
+const TYPE_CHASSIS: u8 = 3;
+const TYPE_PROCESSOR: u8 = 4;
+const TYPE_MEMORY_DEVICE: u8 = 17;
+const TYPE_END: u8 = 127;
+
+// Asset tag Hyper-V writes on every Azure virtual machine
+const AZURE_ASSET_TAG: &str = "7783-7084-3265-9085-8269-3286-77";
+
+/// Vendor strings of hypervisors and cloud hosts: (needle, platform, cloud, gaming cloud)
+const PLATFORM_STRINGS: &[(&str, &str, bool, bool)] = &[
+    ("vmware", "VMware", false, false),
+    ("virtualbox", "VirtualBox", false, false),
+    ("innotek", "VirtualBox", false, false),
+    ("qemu", "QEMU/KVM", false, false),
+    ("kvm", "QEMU/KVM", false, false),
+    ("bochs", "QEMU/KVM", false, false),
+    ("seabios", "QEMU/KVM", false, false),
+    ("hyper-v", "Hyper-V", false, false),
+    ("xen", "Xen", false, false),
+    ("parallels", "Parallels", false, false),
+    ("bhyve", "bhyve", false, false),
+    ("amazon ec2", "AWS", true, false),
+    ("google compute engine", "Google Cloud", true, false),
+    ("openstack", "OpenStack", true, false),
+    ("ovh", "OVH", true, false),
+    ("shadow", "Shadow", true, true),
+    ("paperspace", "Paperspace", true, true),
+];
+
+/// Raw structure table and the SMBIOS version it follows
+#[derive(Debug, Clone, Default)]
+pub struct SmbiosTable {
+    pub major: u8,
+    pub minor: u8,
+    pub data: Vec<u8>,
+}
+
+/// One structure of the table
+#[derive(Debug, Clone)]
+pub struct Structure<'a> {
+    pub kind: u8,
+    pub handle: u16,
+    pub formatted: &'a [u8], // Whole formatted area, header included
+    pub strings: Vec<String>,
+}
+
+impl Structure<'_> {
+    pub fn byte(&self, offset: usize) -> Option<u8> {
+        self.formatted.get(offset).copied()
+    }
+
+    pub fn word(&self, offset: usize) -> Option<u16> {
+        let bytes = self.formatted.get(offset..offset + 2)?;
+        Some(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    pub fn dword(&self, offset: usize) -> Option<u32> {
+        let bytes = self.formatted.get(offset..offset + 4)?;
+        Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    /// String referenced by the byte at `offset` (None for index 0 or a missing string)
+    pub fn string(&self, offset: usize) -> Option<String> {
+        let index = self.byte(offset)? as usize;
+        let value = self.strings.get(index.checked_sub(1)?)?.trim();
+        if value.is_empty() { None } else { Some(value.to_string()) }
+    }
+}
+
+/// System (type 1) and baseboard (type 2) identifiers
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct SystemIdentity {
+    pub manufacturer: Option<String>,
+    pub product: Option<String>,
+    pub serial: Option<String>,
+    pub uuid: Option<String>,
+    pub board_manufacturer: Option<String>,
+    pub board_product: Option<String>,
+    pub board_serial: Option<String>,
+    #[serde(default)]
+    pub family: Option<String>,
+    #[serde(default)]
+    pub sku: Option<String>,
+}
+
+/// BIOS information (type 0)
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct BiosInfo {
+    pub vendor: Option<String>,
+    pub version: Option<String>,
+    pub release_date: Option<String>,
+    pub virtual_machine: bool, // Characteristics extension byte 2, bit 4
+}
+
+/// System enclosure (type 3)
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ChassisInfo {
+    pub manufacturer: Option<String>,
+    pub chassis_type: u8, // 3 = desktop, 10 = notebook, 1 = other...
+    pub serial: Option<String>,
+    pub asset_tag: Option<String>,
+}
+
+/// Processor (type 4)
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ProcessorInfo {
+    pub socket: Option<String>,
+    pub manufacturer: Option<String>,
+    pub version: Option<String>,
+    pub max_speed_mhz: u16,
+    pub current_speed_mhz: u16,
+    pub populated: bool,
+    pub core_count: Option<u8>,
+    pub thread_count: Option<u8>,
+}
+
+/// Memory device (type 17)
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryDevice {
+    pub locator: Option<String>,
+    pub bank: Option<String>,
+    pub size_mb: Option<u32>, // 0 = empty slot, None = unknown
+    pub memory_type: u8,      // 0x1A = DDR4, 0x22 = DDR5...
+    pub speed_mts: u16,
+    pub manufacturer: Option<String>,
+    pub serial: Option<String>,
+    pub part_number: Option<String>,
+}
+
+/// Decoded table
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct SmbiosInfo {
+    pub version: String,
+    pub bios: BiosInfo,
+    pub system: SystemIdentity,
+    pub chassis: ChassisInfo,
+    pub processors: Vec<ProcessorInfo>,
+    pub memory_devices: Vec<MemoryDevice>,
+}
+
+/// Hypervisor or cloud host recognized in the table
+#[derive(Debug, Clone)]
+pub struct PlatformHint {
+    pub platform: &'static str, // "VMware", "AWS"... ("Unknown hypervisor" for generic signs)
+    pub cloud: bool,
+    pub gaming: bool,
+    pub supporting: bool, // Only backs the other hints, never a detection on its own
+    pub indicator: String,
+}
+
+/// Structures of a table, up to the end-of-table marker
+pub fn structures(data: &[u8]) -> Result<Vec<Structure<'_>>, String> {
+    let mut structures = Vec::new();
+    let mut offset = 0;
+
+    while offset + HEADER_SIZE <= data.len() {
+        let kind = data[offset];
+        let length = data[offset + 1] as usize;
+        if length < HEADER_SIZE || offset + length > data.len() {
+            return Err(format!("Structure of type {} at {} has length {}", kind, offset, length));
+        }
+        let handle = u16::from_le_bytes([data[offset + 2], data[offset + 3]]);
+        let formatted = &data[offset..offset + length];
+
+        // String set: NUL-terminated strings, then one more NUL (two NULs when empty)
+        let strings_start = offset + length;
+        let end = data[strings_start..].windows(2).position(|pair| pair == [0, 0])
+            .map(|position| strings_start + position + 2)
+            .ok_or_else(|| format!("Unterminated strings of type {} at {}", kind, offset))?;
+        let strings = data[strings_start..end - 2].split(|&b| b == 0)
+            .filter(|s| !s.is_empty())
+            .map(|s| String::from_utf8_lossy(s).into_owned())
+            .collect();
+
+        structures.push(Structure { kind, handle, formatted, strings });
+        if kind == TYPE_END {
+            break;
+        }
+        offset = end;
+    }
+
+    Ok(structures)
+}
+
+/// System and baseboard identifiers of a table
+pub fn system_identity(table: &SmbiosTable) -> Result<SystemIdentity, String> {
+    Ok(identity_of(&structures(&table.data)?, table))
+}
+
+fn identity_of(structures: &[Structure], table: &SmbiosTable) -> SystemIdentity {
+    let mut identity = SystemIdentity::default();
+
+    if let Some(system) = structures.iter().find(|s| s.kind == TYPE_SYSTEM) {
+        identity.manufacturer = system.string(0x04);
+        identity.product = system.string(0x05);
+        identity.serial = system.string(0x07);
+        identity.uuid = system.formatted.get(0x08..0x18).and_then(|uuid| format_uuid(uuid, table));
+        identity.sku = system.string(0x19);
+        identity.family = system.string(0x1A);
+    }
+    if let Some(board) = structures.iter().find(|s| s.kind == TYPE_BASEBOARD) {
+        identity.board_manufacturer = board.string(0x04);
+        identity.board_product = board.string(0x05);
+        identity.board_serial = board.string(0x07);
+    }
+
+    identity
+}
+
+/// Decode the BIOS, system, chassis, processor and memory device structures
+pub fn decode(table: &SmbiosTable) -> Result<SmbiosInfo, String> {
+    let structures = structures(&table.data)?;
+    let mut info = SmbiosInfo {
+        version: format!("{}.{}", table.major, table.minor),
+        system: identity_of(&structures, table),
+        ..Default::default()
+    };
+
+    for s in &structures {
+        match s.kind {
+            TYPE_BIOS => {
+                info.bios = BiosInfo {
+                    vendor: s.string(0x04),
+                    version: s.string(0x05),
+                    release_date: s.string(0x08),
+                    virtual_machine: s.byte(0x13).is_some_and(|b| b & 0x10 != 0),
+                };
+            }
+            TYPE_CHASSIS => {
+                info.chassis = ChassisInfo {
+                    manufacturer: s.string(0x04),
+                    chassis_type: s.byte(0x05).unwrap_or(0) & 0x7F,
+                    serial: s.string(0x07),
+                    asset_tag: s.string(0x08),
+                };
+            }
+            TYPE_PROCESSOR => info.processors.push(ProcessorInfo {
+                socket: s.string(0x04),
+                manufacturer: s.string(0x07),
+                version: s.string(0x10),
+                max_speed_mhz: s.word(0x14).unwrap_or(0),
+                current_speed_mhz: s.word(0x16).unwrap_or(0),
+                populated: s.byte(0x18).is_some_and(|b| b & 0x40 != 0),
+                core_count: s.byte(0x23).filter(|&c| c != 0),
+                thread_count: s.byte(0x25).filter(|&c| c != 0),
+            }),
+            TYPE_MEMORY_DEVICE => info.memory_devices.push(MemoryDevice {
+                locator: s.string(0x10),
+                bank: s.string(0x11),
+                size_mb: memory_size_mb(s),
+                memory_type: s.byte(0x12).unwrap_or(0),
+                speed_mts: s.word(0x15).unwrap_or(0),
+                manufacturer: s.string(0x17),
+                serial: s.string(0x18),
+                part_number: s.string(0x1A),
+            }),
+            _ => {}
+        }
+    }
+
+    Ok(info)
+}
+
+/// Size of a memory device: KB granularity when bit 15 is set, extended size past 32 GB
+fn memory_size_mb(device: &Structure) -> Option<u32> {
+    match device.word(0x0C)? {
+        0xFFFF => None,
+        0x7FFF => device.dword(0x1C).map(|size| size & 0x7FFF_FFFF),
+        size if size & 0x8000 != 0 => Some(u32::from(size & 0x7FFF) / 1024),
+        size => Some(u32::from(size)),
+    }
+}
+
+/// Hypervisors and cloud hosts the decoded table points to
+pub fn platform_hints(info: &SmbiosInfo) -> Vec<PlatformHint> {
+    let mut hints: Vec<PlatformHint> = Vec::new();
+    let mut push = |platform: &'static str, cloud: bool, gaming: bool, indicator: String| {
+        if !hints.iter().any(|h| h.platform == platform) {
+            hints.push(PlatformHint { platform, cloud, gaming, supporting: false, indicator });
+        }
+    };
+
+    // Vendor strings, firmware-wide
+    let system = &info.system;
+    let mut fields: Vec<(&str, &Option<String>)> = vec![
+        ("system manufacturer", &system.manufacturer),
+        ("system product", &system.product),
+        ("system family", &system.family),
+        ("system serial", &system.serial),
+        ("board manufacturer", &system.board_manufacturer),
+        ("board product", &system.board_product),
+        ("BIOS vendor", &info.bios.vendor),
+        ("BIOS version", &info.bios.version),
+        ("chassis manufacturer", &info.chassis.manufacturer),
+    ];
+    fields.extend(info.processors.iter().map(|p| ("processor manufacturer", &p.manufacturer)));
+    fields.extend(info.processors.iter().map(|p| ("processor version", &p.version)));
+    fields.extend(info.memory_devices.iter().map(|m| ("memory manufacturer", &m.manufacturer)));
+
+    for (label, value) in fields {
+        let value = match value {
+            Some(value) => value,
+            None => continue,
+        };
+        let lower = value.to_lowercase();
+        for (needle, platform, cloud, gaming) in PLATFORM_STRINGS {
+            if lower.contains(needle) {
+                push(platform, *cloud, *gaming, format!("SMBIOS {}: {}", label, value));
+            }
+        }
+    }
+
+    // UUID and asset tag layouts of cloud hosts
+    if let Some(uuid) = &system.uuid {
+        if uuid.starts_with("EC2") {
+            push("AWS", true, false, format!("SMBIOS UUID: {}", uuid));
+        }
+    }
+    if info.chassis.asset_tag.as_deref() == Some(AZURE_ASSET_TAG) {
+        push("Azure", true, false, format!("SMBIOS chassis asset tag: {}", AZURE_ASSET_TAG));
+    }
+
+    // Generic sign: the firmware says so
+    if info.bios.virtual_machine {
+        push("Unknown hypervisor", false, false, "SMBIOS BIOS characteristics: virtual machine".to_string());
+    }
+
+    // No physical memory module at all: hypervisors often omit them, but so do some
+    // boards with sloppy tables, so it only supports another hint
+    if !hints.is_empty() && !info.memory_devices.iter().any(|m| m.size_mb.unwrap_or(1) > 0) {
+        hints.push(PlatformHint {
+            platform: "Unknown hypervisor",
+            cloud: false,
+            gaming: false,
+            supporting: true,
+            indicator: "SMBIOS: no memory device entries".to_string(),
+        });
+    }
+
+    hints
+}
+
+/// UUID as Windows and Linux print it: since SMBIOS 2.6 the first three fields are little-endian
+fn format_uuid(bytes: &[u8], table: &SmbiosTable) -> Option<String> {
+    if bytes.iter().all(|&b| b == 0x00) || bytes.iter().all(|&b| b == 0xFF) {
+        return None; // Not present / not set
+    }
+    let mut b = bytes.to_vec();
+    if (table.major, table.minor) >= (2, 6) {
+        b[0..4].reverse();
+        b[4..6].reverse();
+        b[6..8].reverse();
+    }
+    Some(format!(
+        "{}-{}-{}-{}-{}",
+        hex::encode_upper(&b[0..4]),
+        hex::encode_upper(&b[4..6]),
+        hex::encode_upper(&b[6..8]),
+        hex::encode_upper(&b[8..10]),
+        hex::encode_upper(&b[10..16])
+    ))
+}
+
+lazy_static::lazy_static! {
+    static ref FIRMWARE: Option<SmbiosInfo> = match read_table().and_then(|table| decode(&table)) {
+        Ok(info) => Some(info),
+        Err(e) => {
+            println!("[SMBIOS] Table unavailable: {}", e);
+            None
+        }
+    };
+}
+
+/// Decoded table of the running firmware (read once, it does not change while running)
+pub fn firmware_info() -> Option<SmbiosInfo> {
+    FIRMWARE.clone()
+}
+
+/// Table of the running firmware
+#[cfg(target_os = "windows")]
+pub fn read_table() -> Result<SmbiosTable, String> {
+    use windows::Win32::System::SystemInformation::{GetSystemFirmwareTable, FIRMWARE_TABLE_PROVIDER};
+
+    // RawSMBIOSData: calling method, major, minor, DMI revision, length, table
+    let provider = FIRMWARE_TABLE_PROVIDER(0x52534D42); // 'RSMB'
+    let raw = unsafe {
+        let size = GetSystemFirmwareTable(provider, 0, None);
+        if size == 0 {
+            return Err("GetSystemFirmwareTable('RSMB') returned 0".to_string());
+        }
+        let mut buffer = vec![0u8; size as usize];
+        let written = GetSystemFirmwareTable(provider, 0, Some(&mut buffer));
+        buffer.truncate(written as usize);
+        buffer
+    };
+    if raw.len() < 8 {
+        return Err(format!("RawSMBIOSData of {} bytes", raw.len()));
+    }
+    let length = u32::from_le_bytes([raw[4], raw[5], raw[6], raw[7]]) as usize;
+    let data = raw.get(8..8 + length).ok_or("RawSMBIOSData shorter than its table")?.to_vec();
+    Ok(SmbiosTable { major: raw[1], minor: raw[2], data })
+}
+
+#[cfg(target_os = "linux")]
+pub fn read_table() -> Result<SmbiosTable, String> {
+    read_linux_table(std::path::Path::new("/sys/firmware/dmi/tables"))
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+pub fn read_table() -> Result<SmbiosTable, String> {
+    Err("SMBIOS not supported on this platform".to_string())
+}
+
+/// Table from a `/sys/firmware/dmi/tables` directory (readable by root only)
+#[cfg(target_os = "linux")]
+pub fn read_linux_table(dir: &std::path::Path) -> Result<SmbiosTable, String> {
+    let read = |name: &str| std::fs::read(dir.join(name)).map_err(|e| format!("Cannot read {}: {}", dir.join(name).display(), e));
+    let entry = read("smbios_entry_point")?;
+    let (major, minor) = if entry.starts_with(b"_SM3_") && entry.len() > 8 {
+        (entry[7], entry[8])
+    } else if entry.starts_with(b"_SM_") && entry.len() > 7 {
+        (entry[6], entry[7])
+    } else {
+        return Err("Unknown SMBIOS entry point".to_string());
+    };
+    Ok(SmbiosTable { major, minor, data: read("DMI")? })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/smbios").join(name)
+    }
+
+    /// Table of a `/sys/firmware/dmi/tables` dump
+    fn table(name: &str) -> SmbiosTable {
+        let entry = fs::read(fixture(name).join("smbios_entry_point")).unwrap();
+        // Version bytes follow the anchor, checksum and length
+        let at = if entry.starts_with(b"_SM3_") { 7 } else { 6 };
+        SmbiosTable { major: entry[at], minor: entry[at + 1], data: fs::read(fixture(name).join("DMI")).unwrap() }
+    }
+
+    fn platforms(hints: &[PlatformHint]) -> Vec<(&str, bool)> {
+        hints.iter().map(|h| (h.platform, h.supporting)).collect()
+    }
+
+    #[test]
+    fn vmware() {
+        let info = decode(&table("vmware")).unwrap();
+        assert_eq!(info.version, "2.7");
+        assert!(info.bios.virtual_machine);
+        assert_eq!(info.system.product.as_deref(), Some("VMware20,1"));
+        assert_eq!(info.system.uuid.as_deref(), Some("564D8F3C-2B1A-9E77-0C55-E129A4B361F2"));
+        assert_eq!(info.system.board_product.as_deref(), Some("440BX Desktop Reference Platform"));
+        assert_eq!(info.chassis.chassis_type, 1);
+        assert_eq!(info.processors.len(), 2);
+        assert!(info.processors[0].populated && !info.processors[1].populated);
+        assert_eq!(info.processors[1].core_count, None);
+        assert_eq!(info.memory_devices.iter().map(|m| m.size_mb).collect::<Vec<_>>(), vec![Some(8192), Some(0)]);
+
+        let hints = platform_hints(&info);
+        assert_eq!(platforms(&hints), vec![("VMware", false), ("Unknown hypervisor", false)]);
+        assert_eq!(hints[0].indicator, "SMBIOS system manufacturer: VMware, Inc.");
+    }
+
+    #[test]
+    fn virtualbox() {
+        let info = decode(&table("virtualbox")).unwrap();
+        assert_eq!(info.version, "2.5");
+        assert!(!info.bios.virtual_machine);
+        // Before SMBIOS 2.6 the UUID is stored in network order
+        assert_eq!(info.system.uuid.as_deref(), Some("9F3C6B2E-8A14-4D7B-B0E5-3C2A71D9E084"));
+        assert_eq!(info.system.family.as_deref(), Some("Virtual Machine"));
+        assert!(info.processors.is_empty() && info.memory_devices.is_empty());
+
+        let hints = platform_hints(&info);
+        assert_eq!(platforms(&hints), vec![("VirtualBox", false), ("Unknown hypervisor", true)]);
+        assert_eq!(hints[0].indicator, "SMBIOS system manufacturer: innotek GmbH");
+        assert_eq!(hints[1].indicator, "SMBIOS: no memory device entries");
+    }
+
+    #[test]
+    fn qemu() {
+        let info = decode(&table("qemu")).unwrap();
+        assert_eq!(info.version, "3.0");
+        assert_eq!(info.bios.vendor.as_deref(), Some("SeaBIOS"));
+        assert_eq!(info.system.uuid.as_deref(), Some("1B4E28BA-2FA1-11D2-883F-0016D3CCA427"));
+        assert_eq!(info.system.serial, None);
+        assert_eq!(info.system.board_manufacturer, None); // q35 has no baseboard structure
+        assert_eq!(info.processors[0].manufacturer.as_deref(), Some("QEMU"));
+        assert_eq!(info.memory_devices.len(), 2);
+        assert_eq!(info.memory_devices[0].bank, None);
+
+        let hints = platform_hints(&info);
+        assert_eq!(platforms(&hints), vec![("QEMU/KVM", false), ("Unknown hypervisor", false)]);
+        assert!(hints.iter().all(|h| !h.cloud));
+    }
+
+    #[test]
+    fn shadow() {
+        let info = decode(&table("shadow")).unwrap();
+        assert_eq!(info.system.manufacturer.as_deref(), Some("Shadow"));
+        assert_eq!(info.system.sku.as_deref(), Some("Boost"));
+        assert!(info.memory_devices.is_empty());
+
+        let hints = platform_hints(&info);
+        assert_eq!(platforms(&hints), vec![("Shadow", false), ("Unknown hypervisor", true)]);
+        assert!(hints[0].cloud && hints[0].gaming);
+    }
+
+    #[test]
+    fn bare_metal() {
+        let mut info = decode(&table("bare-metal")).unwrap();
+        assert_eq!(info.version, "3.4");
+        assert_eq!(info.bios.vendor.as_deref(), Some("American Megatrends Inc."));
+        assert_eq!(info.system.board_product.as_deref(), Some("ROG STRIX B550-F GAMING"));
+        assert_eq!(info.system.uuid.as_deref(), Some("03C00218-044D-0547-1806-6F0700080009"));
+        assert_eq!(info.chassis.chassis_type, 3);
+
+        let cpu = &info.processors[0];
+        assert_eq!(cpu.version.as_deref(), Some("AMD Ryzen 7 5800X 8-Core Processor"));
+        assert_eq!((cpu.max_speed_mhz, cpu.current_speed_mhz, cpu.core_count, cpu.thread_count), (4850, 3800, Some(8), Some(16)));
+
+        let populated: Vec<_> = info.memory_devices.iter().filter(|m| m.size_mb.unwrap_or(0) > 0).collect();
+        assert_eq!(info.memory_devices.len(), 4);
+        assert_eq!(populated.len(), 2);
+        assert_eq!(populated[0].locator.as_deref(), Some("DIMM_A2"));
+        assert_eq!((populated[0].memory_type, populated[0].speed_mts), (0x1A, 3600));
+        assert_eq!(populated[0].part_number.as_deref(), Some("F4-3600C16-16GTZNC"));
+        assert!(platform_hints(&info).is_empty());
+
+        // Missing memory devices alone do not make a VM
+        info.memory_devices.clear();
+        assert!(platform_hints(&info).is_empty());
+    }
+
+    #[test]
+    fn cloud_uuid_and_asset_tag() {
+        let mut info = decode(&table("bare-metal")).unwrap();
+        info.system.uuid = Some("EC2B1A4F-3D8E-4C9A-A1B2-0F1E2D3C4B5A".to_string());
+        info.chassis.asset_tag = Some(AZURE_ASSET_TAG.to_string());
+        let hints = platform_hints(&info);
+        assert_eq!(platforms(&hints), vec![("AWS", false), ("Azure", false)]);
+        assert!(hints.iter().all(|h| h.cloud && !h.gaming));
+    }
+
+    #[test]
+    fn system_identity_matches_decode() {
+        let identity = system_identity(&table("bare-metal")).unwrap();
+        assert_eq!(identity.board_serial.as_deref(), Some("210685761101294"));
+        assert_eq!(identity.serial.as_deref(), Some("System Serial Number"));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn reads_sysfs_dumps() {
+        let linux = read_linux_table(&fixture("qemu")).unwrap();
+        assert_eq!((linux.major, linux.minor), (3, 0));
+        assert_eq!(linux.data, table("qemu").data);
+        assert!(read_linux_table(&fixture("missing")).unwrap_err().starts_with("Cannot read"));
+    }
+
+    #[test]
+    fn memory_sizes() {
+        let device = |size: u16, extended: u32| {
+            let mut formatted = vec![0u8; 0x20];
+            formatted[0x0C..0x0E].copy_from_slice(&size.to_le_bytes());
+            formatted[0x1C..0x20].copy_from_slice(&extended.to_le_bytes());
+            formatted
+        };
+        let size = |formatted: &[u8]| memory_size_mb(&Structure { kind: TYPE_MEMORY_DEVICE, handle: 0, formatted, strings: Vec::new() });
+        assert_eq!(size(&device(16384, 0)), Some(16384));
+        assert_eq!(size(&device(0x7FFF, 65536)), Some(65536));
+        assert_eq!(size(&device(0x8000 | 2048, 0)), Some(2)); // 2048 KB
+        assert_eq!(size(&device(0xFFFF, 0)), None);
+    }
+
+    #[test]
+    fn rejects_broken_tables() {
+        let data = table("qemu").data;
+        let mut short = data.clone();
+        short[1] = 2;
+        assert!(structures(&short).unwrap_err().contains("has length 2"));
+        assert!(structures(&data[..0x18 + 10]).unwrap_err().starts_with("Unterminated strings of type 0"));
+        // Nothing after the end-of-table marker is read
+        let mut trailing = data.clone();
+        trailing.extend_from_slice(&[0xFF; 8]);
+        assert_eq!(structures(&trailing).unwrap().last().unwrap().kind, TYPE_END);
+    }
+}
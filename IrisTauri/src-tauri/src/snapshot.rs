@@ -0,0 +1,363 @@
+//! System snapshot module - collects processes, devices, drivers, adapters and windows
+//! once per heartbeat cycle so every detector can run as a pure function over it
+
+use crate::hardware::{self, ProcessInfo, UsbDeviceInfo};
+use crate::hid::{self, HidDeviceInfo};
+use crate::pci::{self, PciDeviceInfo};
+use crate::smbios::{self, SmbiosInfo};
+use std::sync::Mutex;
+
+#[cfg(target_os = "windows")]
+use serde::Deserialize;
+#[cfg(target_os = "windows")]
+use wmi::{COMLibrary, WMIConnection};
+
+/// Kernel driver entry (Win32_SystemDriver)
+#[derive(Debug, Clone, Default)]
+pub struct DriverInfo {
+    pub name: String,
+    pub display_name: String,
+    pub path: Option<String>,
+    pub state: Option<String>,
+    pub start_mode: Option<String>,
+}
+
+/// Network adapter entry (Win32_NetworkAdapter)
+#[derive(Debug, Clone, Default)]
+pub struct NetworkAdapterInfo {
+    pub name: String,
+    pub description: String,
+    pub connection_status: Option<u16>,
+}
+
+/// Visible top-level window
+#[derive(Debug, Clone, Default)]
+pub struct WindowInfo {
+    pub title: String,
+    pub class_name: String,
+    pub pid: u32,
+    pub process_name: String,
+    pub topmost: bool,
+    pub layered: bool,
+    pub transparent: bool,
+}
+
+/// Module loaded in the Iris process
+#[derive(Debug, Clone, Default)]
+pub struct ModuleInfo {
+    pub name: String,
+    pub path: String,
+}
+
+/// Computer system, BIOS and GPU strings (used by VM / cloud PC detection)
+#[derive(Debug, Clone, Default)]
+pub struct PlatformInfo {
+    pub manufacturer: String,
+    pub model: String,
+    pub bios_manufacturer: String,
+    pub bios_version: String,
+    pub bios_serial: String,
+    pub gpus: Vec<String>,
+    pub smbios: Option<SmbiosInfo>, // Raw firmware table, decoded
+}
+
+/// Everything the detectors need, collected once per cycle
+#[derive(Debug, Clone, Default)]
+pub struct SystemSnapshot {
+    pub processes: Vec<ProcessInfo>,
+    pub usb_devices: Vec<UsbDeviceInfo>,
+    pub pci_devices: Vec<PciDeviceInfo>,
+    pub hid_devices: Vec<HidDeviceInfo>,
+    pub drivers: Vec<DriverInfo>,
+    pub network_adapters: Vec<NetworkAdapterInfo>,
+    pub windows: Vec<WindowInfo>,
+    pub modules: Vec<ModuleInfo>,
+    pub platform: PlatformInfo,
+    pub foreground_pid: Option<u32>,
+    pub collected_at: u64, // Timestamp in ms
+}
+
+impl SystemSnapshot {
+    /// Collect a new snapshot (single COM/WMI connection on Windows)
+    #[cfg(target_os = "windows")]
+    pub fn collect() -> Self {
+        let mut snapshot = SystemSnapshot {
+            collected_at: now_millis(),
+            ..Default::default()
+        };
+
+        if let Ok(com_con) = COMLibrary::new() {
+            if let Ok(wmi_con) = WMIConnection::new(com_con) {
+                snapshot.processes = hardware::get_all_processes(&wmi_con);
+                snapshot.usb_devices = hardware::get_all_usb_devices(&wmi_con);
+                snapshot.drivers = query_drivers(&wmi_con);
+                snapshot.network_adapters = query_network_adapters(&wmi_con);
+                snapshot.platform = query_platform(&wmi_con);
+            }
+        }
+        snapshot.platform.smbios = smbios::firmware_info();
+        snapshot.pci_devices = pci::list_devices();
+        snapshot.hid_devices = hid::list_devices();
+
+        snapshot.windows = enumerate_windows(&snapshot.processes);
+        snapshot.modules = enumerate_modules();
+        snapshot.foreground_pid = foreground_pid();
+
+        snapshot.log_if_changed();
+        snapshot
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    pub fn collect() -> Self {
+        let snapshot = SystemSnapshot {
+            processes: hardware::get_all_processes(),
+            usb_devices: hardware::get_all_usb_devices(),
+            pci_devices: pci::list_devices(),
+            hid_devices: hid::list_devices(),
+            platform: PlatformInfo { smbios: smbios::firmware_info(), ..Default::default() },
+            collected_at: now_millis(),
+            ..Default::default()
+        };
+
+        snapshot.log_if_changed();
+        snapshot
+    }
+
+    /// Print the inventory sizes when they differ from the previous snapshot
+    fn log_if_changed(&self) {
+        let summary = format!("{} processes, {} USB devices, {} PCI devices, {} HID interfaces, {} drivers, {} adapters, {} windows, {} modules",
+                              self.processes.len(), self.usb_devices.len(), self.pci_devices.len(), self.hid_devices.len(),
+                              self.drivers.len(), self.network_adapters.len(), self.windows.len(), self.modules.len());
+        if let Ok(mut last) = LAST_SUMMARY.lock() {
+            if *last != summary {
+                println!("[Snapshot] Collected: {}", summary);
+                *last = summary;
+            }
+        }
+    }
+
+    /// Find a process by PID
+    pub fn process(&self, pid: u32) -> Option<&ProcessInfo> {
+        self.processes.iter().find(|p| p.pid == pid)
+    }
+}
+
+lazy_static::lazy_static! {
+    // Inventory sizes of the previous snapshot (logged on change only)
+    static ref LAST_SUMMARY: Mutex<String> = Mutex::new(String::new());
+}
+
+fn now_millis() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(target_os = "windows")]
+fn query_drivers(wmi_con: &WMIConnection) -> Vec<DriverInfo> {
+    #[derive(Deserialize)]
+    #[allow(non_snake_case)]
+    struct Win32SystemDriver {
+        Name: Option<String>,
+        DisplayName: Option<String>,
+        PathName: Option<String>,
+        State: Option<String>,
+        StartMode: Option<String>,
+    }
+
+    wmi_con.raw_query::<Win32SystemDriver>(
+        "SELECT Name, DisplayName, PathName, State, StartMode FROM Win32_SystemDriver"
+    )
+    .map(|drivers| drivers.into_iter().map(|d| DriverInfo {
+        name: d.Name.unwrap_or_default(),
+        display_name: d.DisplayName.unwrap_or_default(),
+        path: d.PathName,
+        state: d.State,
+        start_mode: d.StartMode,
+    }).collect())
+    .unwrap_or_default()
+}
+
+#[cfg(target_os = "windows")]
+fn query_network_adapters(wmi_con: &WMIConnection) -> Vec<NetworkAdapterInfo> {
+    #[derive(Deserialize)]
+    #[allow(non_snake_case)]
+    struct Win32NetworkAdapter {
+        Name: Option<String>,
+        Description: Option<String>,
+        NetConnectionStatus: Option<u16>,
+    }
+
+    wmi_con.raw_query::<Win32NetworkAdapter>(
+        "SELECT Name, Description, NetConnectionStatus FROM Win32_NetworkAdapter WHERE NetConnectionStatus IS NOT NULL"
+    )
+    .map(|adapters| adapters.into_iter().map(|a| NetworkAdapterInfo {
+        name: a.Name.unwrap_or_default(),
+        description: a.Description.unwrap_or_default(),
+        connection_status: a.NetConnectionStatus,
+    }).collect())
+    .unwrap_or_default()
+}
+
+#[cfg(target_os = "windows")]
+fn query_platform(wmi_con: &WMIConnection) -> PlatformInfo {
+    let mut platform = PlatformInfo::default();
+
+    #[derive(Deserialize)]
+    #[allow(non_snake_case)]
+    struct Win32ComputerSystem {
+        Manufacturer: Option<String>,
+        Model: Option<String>,
+    }
+
+    if let Ok(systems) = wmi_con.raw_query::<Win32ComputerSystem>(
+        "SELECT Manufacturer, Model FROM Win32_ComputerSystem"
+    ) {
+        if let Some(system) = systems.into_iter().next() {
+            platform.manufacturer = system.Manufacturer.unwrap_or_default();
+            platform.model = system.Model.unwrap_or_default();
+        }
+    }
+
+    #[derive(Deserialize)]
+    #[allow(non_snake_case)]
+    struct Win32Bios {
+        Manufacturer: Option<String>,
+        SerialNumber: Option<String>,
+        Version: Option<String>,
+    }
+
+    if let Ok(bioses) = wmi_con.raw_query::<Win32Bios>(
+        "SELECT Manufacturer, SerialNumber, Version FROM Win32_BIOS"
+    ) {
+        if let Some(bios) = bioses.into_iter().next() {
+            platform.bios_manufacturer = bios.Manufacturer.unwrap_or_default();
+            platform.bios_serial = bios.SerialNumber.unwrap_or_default();
+            platform.bios_version = bios.Version.unwrap_or_default();
+        }
+    }
+
+    #[derive(Deserialize)]
+    #[allow(non_snake_case)]
+    struct Win32VideoController {
+        Name: Option<String>,
+    }
+
+    if let Ok(gpus) = wmi_con.raw_query::<Win32VideoController>(
+        "SELECT Name FROM Win32_VideoController"
+    ) {
+        platform.gpus = gpus.into_iter().filter_map(|g| g.Name).collect();
+    }
+
+    platform
+}
+
+/// Enumerate visible top-level windows (process names resolved from the snapshot)
+#[cfg(target_os = "windows")]
+fn enumerate_windows(processes: &[ProcessInfo]) -> Vec<WindowInfo> {
+    use windows::Win32::UI::WindowsAndMessaging::{
+        EnumWindows, GetWindowLongW, GetWindowTextW, GetClassNameW, GetWindowThreadProcessId,
+        GWL_EXSTYLE, WS_EX_TOPMOST, WS_EX_LAYERED, WS_EX_TRANSPARENT, IsWindowVisible,
+    };
+    use windows::Win32::Foundation::{HWND, LPARAM, BOOL};
+
+    unsafe extern "system" fn enum_window_proc(hwnd: HWND, lparam: LPARAM) -> BOOL {
+        let windows = &mut *(lparam.0 as *mut Vec<WindowInfo>);
+
+        if !IsWindowVisible(hwnd).as_bool() {
+            return BOOL(1);
+        }
+
+        let ex_style = GetWindowLongW(hwnd, GWL_EXSTYLE) as u32;
+
+        let mut title_buf = [0u16; 512];
+        let title_len = GetWindowTextW(hwnd, &mut title_buf);
+        let title = String::from_utf16_lossy(&title_buf[..title_len.max(0) as usize]);
+
+        let mut class_buf = [0u16; 256];
+        let class_len = GetClassNameW(hwnd, &mut class_buf);
+        let class_name = String::from_utf16_lossy(&class_buf[..class_len.max(0) as usize]);
+
+        let mut pid: u32 = 0;
+        GetWindowThreadProcessId(hwnd, Some(&mut pid));
+
+        windows.push(WindowInfo {
+            title,
+            class_name,
+            pid,
+            process_name: String::new(),
+            topmost: (ex_style & WS_EX_TOPMOST.0) != 0,
+            layered: (ex_style & WS_EX_LAYERED.0) != 0,
+            transparent: (ex_style & WS_EX_TRANSPARENT.0) != 0,
+        });
+
+        BOOL(1) // Continue enumeration
+    }
+
+    let mut windows: Vec<WindowInfo> = Vec::new();
+    unsafe {
+        let _ = EnumWindows(Some(enum_window_proc), LPARAM(&mut windows as *mut _ as isize));
+    }
+
+    for window in windows.iter_mut() {
+        if let Some(process) = processes.iter().find(|p| p.pid == window.pid) {
+            window.process_name = process.name.clone();
+        }
+    }
+
+    windows
+}
+
+/// Enumerate modules loaded in the current (Iris) process
+#[cfg(target_os = "windows")]
+fn enumerate_modules() -> Vec<ModuleInfo> {
+    use windows::Win32::System::ProcessStatus::{EnumProcessModules, GetModuleFileNameExW};
+    use windows::Win32::System::Threading::GetCurrentProcess;
+    use windows::Win32::Foundation::HMODULE;
+
+    let mut result = Vec::new();
+
+    unsafe {
+        let process = GetCurrentProcess();
+        let mut modules: [HMODULE; 1024] = [HMODULE::default(); 1024];
+        let mut needed: u32 = 0;
+
+        if EnumProcessModules(
+            process,
+            modules.as_mut_ptr(),
+            (modules.len() * std::mem::size_of::<HMODULE>()) as u32,
+            &mut needed
+        ).is_ok() {
+            let count = (needed as usize) / std::mem::size_of::<HMODULE>();
+
+            for module in modules.iter().take(count.min(modules.len())) {
+                let mut path_buf = [0u16; 512];
+                let len = GetModuleFileNameExW(process, *module, &mut path_buf);
+                if len > 0 {
+                    let path = String::from_utf16_lossy(&path_buf[..len as usize]);
+                    let name = path.split('\\').last().unwrap_or(&path).to_string();
+                    result.push(ModuleInfo { name, path });
+                }
+            }
+        }
+    }
+
+    result
+}
+
+/// PID owning the foreground window
+#[cfg(target_os = "windows")]
+fn foreground_pid() -> Option<u32> {
+    use windows::Win32::UI::WindowsAndMessaging::{GetForegroundWindow, GetWindowThreadProcessId};
+
+    unsafe {
+        let hwnd = GetForegroundWindow();
+        if hwnd.0 == 0 {
+            return None;
+        }
+        let mut pid: u32 = 0;
+        GetWindowThreadProcessId(hwnd, Some(&mut pid));
+        if pid > 0 { Some(pid) } else { None }
+    }
+}
@@ -0,0 +1,559 @@
+//! Spoofing module - HWID spoofer detection through cross-source consistency
+//!
+//! Spoofers patch whatever a game or anti-cheat usually reads: the registry copy of
+//! the BIOS strings, the WMI provider, the NIC's configured address, the disk
+//! driver's answer. They rarely patch every source the same way, so each identifier
+//! is read from independent places and compared:
+//! - Windows: raw SMBIOS table vs WMI vs registry, NIC permanent vs current MAC,
+//!   disk serial from the storage driver (IOCTL) vs WMI
+//! - Linux: raw DMI table vs sysfs DMI attributes, the two machine-id copies, disk
+//!   serial from sysfs vs udev, NICs whose address was set after boot
+//!
+//! Placeholder serials ("To be filled by O.E.M.", all zeros) are reported too: a
+//! spoofer that blanks every source leaves no contradiction behind.
+
+use crate::fingerprint;
+use serde::{Deserialize, Serialize};
+
+/// How two values of an identifier are compared
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdentifierKind {
+    Serial,     // Letters and digits; byte-swapped or hex-encoded copies are equal
+    Name,       // Letters and digits, case-insensitive
+    MacAddress, // Hex digits only
+}
+
+/// One identifier as read from one source
+#[derive(Debug, Clone)]
+pub struct IdentifierReading {
+    pub identifier: String, // "System serial", "Disk 0 serial"...
+    pub kind: IdentifierKind,
+    pub source: &'static str,
+    pub value: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum IndicatorKind {
+    Contradiction,   // Sources disagree on a serial or name
+    Placeholder,     // Every source returns a placeholder serial
+    AddressOverride, // NIC uses another address than its burned-in one
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SpoofingIndicator {
+    pub identifier: String,
+    pub kind: IndicatorKind,
+    pub detail: String,
+}
+
+impl SpoofingIndicator {
+    /// Risk points of the indicator
+    pub fn weight(&self) -> u32 {
+        match self.kind {
+            IndicatorKind::Contradiction => 80,
+            IndicatorKind::AddressOverride => 40,
+            IndicatorKind::Placeholder => 20,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct HwidConsistencyResult {
+    pub spoofing_detected: bool,
+    pub identifiers_checked: usize,
+    pub indicators: Vec<SpoofingIndicator>,
+    pub risk_score: u32,
+}
+
+/// Compare every identifier of the running machine across its sources
+pub fn check_hwid_consistency() -> HwidConsistencyResult {
+    let (readings, overrides) = collect();
+    let result = analyze(&readings, overrides);
+    if result.spoofing_detected {
+        println!("[Spoofing] {} HWID inconsistencies found", result.indicators.len());
+    }
+    result
+}
+
+/// Indicators of a set of readings, plus address overrides found while collecting them
+pub fn analyze(readings: &[IdentifierReading], overrides: Vec<SpoofingIndicator>) -> HwidConsistencyResult {
+    let mut result = HwidConsistencyResult::default();
+
+    // Group readings per identifier, in the order they were collected
+    let mut groups: Vec<(&str, Vec<&IdentifierReading>)> = Vec::new();
+    for reading in readings {
+        match groups.iter_mut().find(|(identifier, _)| *identifier == reading.identifier) {
+            Some((_, group)) => group.push(reading),
+            None => groups.push((&reading.identifier, vec![reading])),
+        }
+    }
+    result.identifiers_checked = groups.len();
+
+    for (identifier, group) in groups {
+        let kind = group[0].kind;
+        let first = comparison_forms(group[0]);
+        let disagreeing: Vec<&&IdentifierReading> = group.iter()
+            .filter(|reading| !comparison_forms(reading).iter().any(|form| first.contains(form)))
+            .collect();
+
+        if let Some(other) = disagreeing.first() {
+            let indicator = SpoofingIndicator {
+                identifier: identifier.to_string(),
+                kind: if kind == IdentifierKind::MacAddress { IndicatorKind::AddressOverride } else { IndicatorKind::Contradiction },
+                detail: format!("{} '{}' vs {} '{}'", group[0].source, group[0].value, other.source, other.value),
+            };
+            result.risk_score += indicator.weight();
+            result.indicators.push(indicator);
+        } else if kind == IdentifierKind::Serial && group.iter().all(|reading| fingerprint::is_placeholder(&reading.value)) {
+            let indicator = SpoofingIndicator {
+                identifier: identifier.to_string(),
+                kind: IndicatorKind::Placeholder,
+                detail: format!("{} '{}'", group[0].source, group[0].value),
+            };
+            result.risk_score += indicator.weight();
+            result.indicators.push(indicator);
+        }
+    }
+
+    for indicator in overrides {
+        result.risk_score += indicator.weight();
+        result.indicators.push(indicator);
+    }
+
+    result.spoofing_detected = result.indicators.iter().any(|i| i.kind != IndicatorKind::Placeholder);
+    result
+}
+
+/// Normalized forms a value can legitimately take in another source
+fn comparison_forms(reading: &IdentifierReading) -> Vec<String> {
+    let key = |value: &str, keep: fn(&char) -> bool| -> String {
+        value.chars().filter(keep).collect::<String>().to_uppercase()
+    };
+    match reading.kind {
+        IdentifierKind::MacAddress => vec![key(&reading.value, char::is_ascii_hexdigit)],
+        IdentifierKind::Name => vec![key(&reading.value, char::is_ascii_alphanumeric)],
+        IdentifierKind::Serial => {
+            // ATA serials are strings of byte-swapped words; some WMI providers
+            // return them unswapped or as a hex dump of the raw bytes
+            let mut raw = vec![reading.value.trim().to_string()];
+            let hex = raw[0].as_str();
+            if hex.len() % 2 == 0 && !hex.is_empty() && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+                if let Ok(bytes) = hex::decode(hex) {
+                    raw.push(String::from_utf8_lossy(&bytes).into_owned());
+                }
+            }
+            let mut forms = Vec::new();
+            for value in raw {
+                let bytes = value.as_bytes();
+                let swapped: Vec<u8> = bytes.chunks(2).flat_map(|pair| pair.iter().rev().copied()).collect();
+                forms.push(key(&value, char::is_ascii_alphanumeric));
+                forms.push(key(&String::from_utf8_lossy(&swapped), char::is_ascii_alphanumeric));
+            }
+            forms.retain(|form| !form.is_empty());
+            forms
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn collect() -> (Vec<IdentifierReading>, Vec<SpoofingIndicator>) {
+    use crate::hardware::read_hklm_string;
+    use crate::smbios;
+    use wmi::{COMLibrary, WMIConnection};
+
+    let mut readings = Vec::new();
+    let mut push = |identifier: &str, kind: IdentifierKind, source: &'static str, value: Option<String>| {
+        if let Some(value) = value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty()) {
+            readings.push(IdentifierReading { identifier: identifier.to_string(), kind, source, value });
+        }
+    };
+
+    // Raw firmware table first: the other sources are derived from it
+    match smbios::firmware_info().map(|info| info.system) {
+        Some(identity) => {
+            push("System UUID", IdentifierKind::Serial, "SMBIOS table", identity.uuid);
+            push("System serial", IdentifierKind::Serial, "SMBIOS table", identity.serial);
+            push("System manufacturer", IdentifierKind::Name, "SMBIOS table", identity.manufacturer);
+            push("System product", IdentifierKind::Name, "SMBIOS table", identity.product);
+            push("Baseboard serial", IdentifierKind::Serial, "SMBIOS table", identity.board_serial);
+            push("Baseboard manufacturer", IdentifierKind::Name, "SMBIOS table", identity.board_manufacturer);
+            push("Baseboard product", IdentifierKind::Name, "SMBIOS table", identity.board_product);
+        }
+        None => println!("[Spoofing] SMBIOS table unavailable"),
+    }
+
+    let mut disks: Vec<(u32, Option<String>)> = Vec::new();
+    let mut adapters = Vec::new();
+
+    if let Ok(com_con) = COMLibrary::new() {
+        if let Ok(wmi_con) = WMIConnection::new(com_con) {
+            #[derive(Deserialize)]
+            #[allow(non_snake_case)]
+            struct Win32ComputerSystemProduct {
+                UUID: Option<String>,
+                IdentifyingNumber: Option<String>,
+                Vendor: Option<String>,
+                Name: Option<String>,
+            }
+
+            #[derive(Deserialize)]
+            #[allow(non_snake_case)]
+            struct Win32BaseBoard {
+                SerialNumber: Option<String>,
+                Manufacturer: Option<String>,
+                Product: Option<String>,
+            }
+
+            #[derive(Deserialize)]
+            #[allow(non_snake_case)]
+            struct Win32DiskDrive {
+                Index: Option<u32>,
+                SerialNumber: Option<String>,
+            }
+
+            if let Ok(results) = wmi_con.raw_query::<Win32ComputerSystemProduct>(
+                "SELECT UUID, IdentifyingNumber, Vendor, Name FROM Win32_ComputerSystemProduct"
+            ) {
+                if let Some(product) = results.into_iter().next() {
+                    push("System UUID", IdentifierKind::Serial, "WMI", product.UUID);
+                    push("System serial", IdentifierKind::Serial, "WMI", product.IdentifyingNumber);
+                    push("System manufacturer", IdentifierKind::Name, "WMI", product.Vendor);
+                    push("System product", IdentifierKind::Name, "WMI", product.Name);
+                }
+            }
+
+            if let Ok(results) = wmi_con.raw_query::<Win32BaseBoard>(
+                "SELECT SerialNumber, Manufacturer, Product FROM Win32_BaseBoard"
+            ) {
+                if let Some(board) = results.into_iter().next() {
+                    push("Baseboard serial", IdentifierKind::Serial, "WMI", board.SerialNumber);
+                    push("Baseboard manufacturer", IdentifierKind::Name, "WMI", board.Manufacturer);
+                    push("Baseboard product", IdentifierKind::Name, "WMI", board.Product);
+                }
+            }
+
+            if let Ok(results) = wmi_con.raw_query::<Win32DiskDrive>(
+                "SELECT Index, SerialNumber FROM Win32_DiskDrive WHERE MediaType = 'Fixed hard disk media'"
+            ) {
+                disks = results.into_iter().filter_map(|d| d.Index.map(|index| (index, d.SerialNumber))).collect();
+            }
+        }
+    }
+
+    if let Ok(com_con) = COMLibrary::new() {
+        if let Ok(wmi_con) = WMIConnection::with_namespace_path("root\\StandardCimv2", com_con) {
+            #[derive(Deserialize)]
+            #[allow(non_snake_case)]
+            struct MsftNetAdapter {
+                Name: Option<String>,
+                PermanentAddress: Option<String>,
+                NetworkAddresses: Option<Vec<String>>,
+                NdisPhysicalMedium: Option<u32>,
+            }
+
+            if let Ok(results) = wmi_con.raw_query::<MsftNetAdapter>(
+                "SELECT Name, PermanentAddress, NetworkAddresses, NdisPhysicalMedium FROM MSFT_NetAdapter WHERE HardwareInterface = TRUE"
+            ) {
+                adapters = results;
+            }
+        }
+    }
+
+    // Registry copy written by the kernel at boot
+    let bios = "HARDWARE\\DESCRIPTION\\System\\BIOS";
+    push("System manufacturer", IdentifierKind::Name, "registry", read_hklm_string(bios, "SystemManufacturer"));
+    push("System product", IdentifierKind::Name, "registry", read_hklm_string(bios, "SystemProductName"));
+    push("Baseboard manufacturer", IdentifierKind::Name, "registry", read_hklm_string(bios, "BaseBoardManufacturer"));
+    push("Baseboard product", IdentifierKind::Name, "registry", read_hklm_string(bios, "BaseBoardProduct"));
+
+    for (index, serial) in disks {
+        let identifier = format!("Disk {} serial", index);
+        push(&identifier, IdentifierKind::Serial, "storage driver", query_disk_serial(index));
+        push(&identifier, IdentifierKind::Serial, "WMI", serial);
+    }
+
+    for adapter in adapters {
+        // Wi-Fi adapters randomize their address per network by design (1 = 802.11, 9 = native 802.11)
+        if matches!(adapter.NdisPhysicalMedium, Some(1) | Some(9)) {
+            continue;
+        }
+        let identifier = format!("NIC {} address", adapter.Name.unwrap_or_default());
+        push(&identifier, IdentifierKind::MacAddress, "permanent", adapter.PermanentAddress);
+        for address in adapter.NetworkAddresses.unwrap_or_default() {
+            push(&identifier, IdentifierKind::MacAddress, "current", Some(address));
+        }
+    }
+
+    (readings, Vec::new())
+}
+
+/// Serial the storage driver reports for `\\.\PhysicalDrive<index>` (no admin rights needed)
+#[cfg(target_os = "windows")]
+fn query_disk_serial(index: u32) -> Option<String> {
+    use windows::core::PCWSTR;
+    use windows::Win32::Foundation::{CloseHandle, HANDLE};
+    use windows::Win32::Storage::FileSystem::{
+        CreateFileW, FILE_FLAGS_AND_ATTRIBUTES, FILE_SHARE_READ, FILE_SHARE_WRITE, OPEN_EXISTING,
+    };
+    use windows::Win32::System::IO::DeviceIoControl;
+
+    const IOCTL_STORAGE_QUERY_PROPERTY: u32 = 0x002D_1400;
+
+    let path: Vec<u16> = format!("\\\\.\\PhysicalDrive{}\0", index).encode_utf16().collect();
+    // STORAGE_PROPERTY_QUERY: StorageDeviceProperty (0), PropertyStandardQuery (0)
+    let query = [0u8; 12];
+    let mut buffer = vec![0u8; 1024];
+    let mut returned = 0u32;
+
+    unsafe {
+        let handle = CreateFileW(
+            PCWSTR(path.as_ptr()),
+            0, // Query access only
+            FILE_SHARE_READ | FILE_SHARE_WRITE,
+            None,
+            OPEN_EXISTING,
+            FILE_FLAGS_AND_ATTRIBUTES(0),
+            HANDLE::default(),
+        ).ok()?;
+        let queried = DeviceIoControl(
+            handle,
+            IOCTL_STORAGE_QUERY_PROPERTY,
+            Some(query.as_ptr() as *const _),
+            query.len() as u32,
+            Some(buffer.as_mut_ptr() as *mut _),
+            buffer.len() as u32,
+            Some(&mut returned),
+            None,
+        );
+        let _ = CloseHandle(handle);
+        queried.ok()?;
+    }
+
+    // STORAGE_DEVICE_DESCRIPTOR.SerialNumberOffset, NUL-terminated string (0 = none)
+    buffer.truncate(returned as usize);
+    let offset = u32::from_le_bytes(buffer.get(24..28)?.try_into().ok()?) as usize;
+    if offset == 0 {
+        return None;
+    }
+    let serial = buffer.get(offset..)?;
+    let end = serial.iter().position(|&b| b == 0).unwrap_or(serial.len());
+    Some(String::from_utf8_lossy(&serial[..end]).trim().to_string()).filter(|s| !s.is_empty())
+}
+
+#[cfg(target_os = "linux")]
+fn collect() -> (Vec<IdentifierReading>, Vec<SpoofingIndicator>) {
+    read_linux_readings(std::path::Path::new("/"))
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+fn collect() -> (Vec<IdentifierReading>, Vec<SpoofingIndicator>) {
+    (Vec::new(), Vec::new())
+}
+
+/// Readings and address overrides of a Linux root (the raw DMI table and DMI
+/// serials need root; without them only names, machine ids, disks and NICs are compared)
+#[cfg(target_os = "linux")]
+pub fn read_linux_readings(root: &std::path::Path) -> (Vec<IdentifierReading>, Vec<SpoofingIndicator>) {
+    use crate::fingerprint::{is_usb, list_dir, read_trimmed};
+    use crate::smbios;
+
+    let mut readings = Vec::new();
+    let mut push = |identifier: &str, kind: IdentifierKind, source: &'static str, value: Option<String>| {
+        if let Some(value) = value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty()) {
+            readings.push(IdentifierReading { identifier: identifier.to_string(), kind, source, value });
+        }
+    };
+
+    if let Ok(identity) = smbios::read_linux_table(&root.join("sys/firmware/dmi/tables")).and_then(|table| smbios::system_identity(&table)) {
+        push("System UUID", IdentifierKind::Serial, "SMBIOS table", identity.uuid);
+        push("System serial", IdentifierKind::Serial, "SMBIOS table", identity.serial);
+        push("System manufacturer", IdentifierKind::Name, "SMBIOS table", identity.manufacturer);
+        push("System product", IdentifierKind::Name, "SMBIOS table", identity.product);
+        push("Baseboard serial", IdentifierKind::Serial, "SMBIOS table", identity.board_serial);
+        push("Baseboard manufacturer", IdentifierKind::Name, "SMBIOS table", identity.board_manufacturer);
+        push("Baseboard product", IdentifierKind::Name, "SMBIOS table", identity.board_product);
+    }
+
+    let dmi = root.join("sys/class/dmi/id");
+    for (identifier, kind, attribute) in [
+        ("System UUID", IdentifierKind::Serial, "product_uuid"),
+        ("System serial", IdentifierKind::Serial, "product_serial"),
+        ("System manufacturer", IdentifierKind::Name, "sys_vendor"),
+        ("System product", IdentifierKind::Name, "product_name"),
+        ("Baseboard serial", IdentifierKind::Serial, "board_serial"),
+        ("Baseboard manufacturer", IdentifierKind::Name, "board_vendor"),
+        ("Baseboard product", IdentifierKind::Name, "board_name"),
+    ] {
+        push(identifier, kind, "sysfs", read_trimmed(&dmi.join(attribute)));
+    }
+
+    push("Machine ID", IdentifierKind::Serial, "/etc/machine-id", read_trimmed(&root.join("etc/machine-id")));
+    push("Machine ID", IdentifierKind::Serial, "D-Bus machine-id", read_trimmed(&root.join("var/lib/dbus/machine-id")));
+
+    // udev keeps the serial it read when the disk appeared
+    for (name, serial) in fingerprint::read_linux_disk_serials(root) {
+        let identifier = format!("Disk {} serial", name);
+        push(&identifier, IdentifierKind::Serial, "sysfs", Some(serial));
+        let udev = read_trimmed(&root.join("sys/block").join(&name).join("dev"))
+            .and_then(|dev| std::fs::read_to_string(root.join("run/udev/data").join(format!("b{}", dev))).ok())
+            .and_then(|data| data.lines().find_map(|line| line.strip_prefix("E:ID_SERIAL_SHORT=").map(str::to_string)));
+        push(&identifier, IdentifierKind::Serial, "udev", udev);
+    }
+
+    // addr_assign_type 3 (NET_ADDR_SET): the address was changed after the driver set it
+    let mut overrides = Vec::new();
+    let net = root.join("sys/class/net");
+    for name in list_dir(&net) {
+        let dir = net.join(&name);
+        let wireless = dir.join("wireless").exists() || dir.join("phy80211").exists();
+        if !dir.join("device").exists() || wireless || is_usb(&dir) {
+            continue;
+        }
+        if read_trimmed(&dir.join("addr_assign_type")).as_deref() == Some("3") {
+            overrides.push(SpoofingIndicator {
+                identifier: format!("NIC {} address", name),
+                kind: IndicatorKind::AddressOverride,
+                detail: format!("address {} set after boot", read_trimmed(&dir.join("address")).unwrap_or_default()),
+            });
+        }
+    }
+
+    (readings, overrides)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reading(identifier: &str, kind: IdentifierKind, source: &'static str, value: &str) -> IdentifierReading {
+        IdentifierReading { identifier: identifier.to_string(), kind, source, value: value.to_string() }
+    }
+
+    fn serial(source: &'static str, value: &str) -> IdentifierReading {
+        reading("Disk 0 serial", IdentifierKind::Serial, source, value)
+    }
+
+    fn kinds(result: &HwidConsistencyResult) -> Vec<(&str, IndicatorKind)> {
+        result.indicators.iter().map(|i| (i.identifier.as_str(), i.kind)).collect()
+    }
+
+    #[test]
+    fn ata_serial_copies_are_equal() {
+        let driver = "WD-WCC4Y0000001";
+        for wmi in [
+            "DWW-CCY40000001",                // Byte-swapped words
+            "57442d574343345930303030303031", // Hex dump of the raw bytes
+            "4457572d4343593430303030303031", // Hex dump of the byte-swapped words
+            "  WD-WCC4Y0000001  ",
+        ] {
+            let result = analyze(&[serial("storage driver", driver), serial("WMI", wmi)], Vec::new());
+            assert!(result.indicators.is_empty(), "{}: {:?}", wmi, result.indicators);
+        }
+
+        let forms = comparison_forms(&serial("WMI", "57442d574343345930303030303031"));
+        assert!(forms.contains(&"WDWCC4Y0000001".to_string()));
+        assert!(forms.contains(&"DWWCCY40000001".to_string()));
+    }
+
+    #[test]
+    fn case_and_punctuation_are_not_contradictions() {
+        let result = analyze(&[
+            reading("System manufacturer", IdentifierKind::Name, "SMBIOS table", "ASUSTeK COMPUTER INC."),
+            reading("System manufacturer", IdentifierKind::Name, "registry", "ASUSTeK Computer Inc"),
+            serial("storage driver", "ab-12 cd"),
+            serial("WMI", "AB12CD"),
+        ], Vec::new());
+        assert_eq!(result.identifiers_checked, 2);
+        assert!(result.indicators.is_empty());
+        assert!(!result.spoofing_detected);
+    }
+
+    #[test]
+    fn disagreeing_sources_are_contradictions() {
+        let result = analyze(&[
+            reading("System product", IdentifierKind::Name, "SMBIOS table", "ROG STRIX B550-F GAMING"),
+            reading("System product", IdentifierKind::Name, "WMI", "ROG STRIX B550-F GAMING"),
+            reading("System product", IdentifierKind::Name, "registry", "PRIME B450M-A"),
+            serial("storage driver", "S4EWNX0R312345"),
+            serial("WMI", "S4EWNX0R999999"),
+        ], Vec::new());
+        assert_eq!(kinds(&result), [("System product", IndicatorKind::Contradiction), ("Disk 0 serial", IndicatorKind::Contradiction)]);
+        assert_eq!(result.indicators[0].detail, "SMBIOS table 'ROG STRIX B550-F GAMING' vs registry 'PRIME B450M-A'");
+        assert_eq!(result.risk_score, 160);
+        assert!(result.spoofing_detected);
+    }
+
+    #[test]
+    fn placeholder_only_groups() {
+        let result = analyze(&[
+            reading("System serial", IdentifierKind::Serial, "SMBIOS table", "To Be Filled By O.E.M."),
+            reading("System serial", IdentifierKind::Serial, "WMI", "To be filled by O.E.M."),
+            reading("Baseboard serial", IdentifierKind::Serial, "SMBIOS table", "000000000000"),
+            reading("System product", IdentifierKind::Name, "SMBIOS table", "Default string"), // Names are not checked
+        ], Vec::new());
+        assert_eq!(kinds(&result), [("System serial", IndicatorKind::Placeholder), ("Baseboard serial", IndicatorKind::Placeholder)]);
+        assert_eq!(result.indicators[0].detail, "SMBIOS table 'To Be Filled By O.E.M.'");
+        assert_eq!(result.risk_score, 40);
+        assert!(!result.spoofing_detected); // Blank firmware alone is not spoofing
+
+        // A placeholder in one source only is a contradiction
+        let result = analyze(&[serial("storage driver", "S4EWNX0R312345"), serial("WMI", "0000000000000000")], Vec::new());
+        assert_eq!(kinds(&result), [("Disk 0 serial", IndicatorKind::Contradiction)]);
+    }
+
+    #[test]
+    fn permanent_and_current_mac_addresses() {
+        let nic = |source, value| reading("NIC Ethernet address", IdentifierKind::MacAddress, source, value);
+        let same = analyze(&[nic("permanent", "00-E0-4C-68-00-01"), nic("current", "00e04c680001")], Vec::new());
+        assert!(same.indicators.is_empty());
+
+        let changed = analyze(&[nic("permanent", "00-E0-4C-68-00-01"), nic("current", "02:11:22:33:44:55")], Vec::new());
+        assert_eq!(kinds(&changed), [("NIC Ethernet address", IndicatorKind::AddressOverride)]);
+        assert_eq!(changed.indicators[0].detail, "permanent '00-E0-4C-68-00-01' vs current '02:11:22:33:44:55'");
+        assert_eq!(changed.risk_score, 40);
+        assert!(changed.spoofing_detected);
+
+        // Overrides found while collecting are added as they are
+        let overrides = vec![SpoofingIndicator {
+            identifier: "NIC eth0 address".to_string(),
+            kind: IndicatorKind::AddressOverride,
+            detail: String::new(),
+        }];
+        let result = analyze(&[], overrides);
+        assert_eq!((result.identifiers_checked, result.risk_score, result.spoofing_detected), (0, 40, true));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn linux_root() {
+        let tree = crate::sysfs::fixture::Tree::new("spoofing-root.txt");
+        let (readings, overrides) = read_linux_readings(&tree.0);
+
+        let disks: Vec<(&str, &str, &str)> = readings.iter()
+            .filter(|r| r.identifier.starts_with("Disk"))
+            .map(|r| (r.identifier.as_str(), r.source, r.value.as_str()))
+            .collect();
+        assert_eq!(disks, [
+            ("Disk nvme0n1 serial", "sysfs", "S4EWNX0R312345"),
+            ("Disk nvme0n1 serial", "udev", "S4EWNX0R999999"),
+            ("Disk sda serial", "sysfs", "WD-WCC4Y0000001"),
+            ("Disk sda serial", "udev", "WD-WCC4Y0000001"),
+        ]);
+        // Wi-Fi, USB and virtual interfaces are skipped
+        let overridden: Vec<(&str, &str)> = overrides.iter().map(|o| (o.identifier.as_str(), o.detail.as_str())).collect();
+        assert_eq!(overridden, [("NIC enp0s31f6 address", "address 02:11:22:33:44:55 set after boot")]);
+
+        let result = analyze(&readings, overrides);
+        assert_eq!(kinds(&result), [
+            ("System serial", IndicatorKind::Placeholder),
+            ("Disk nvme0n1 serial", IndicatorKind::Contradiction),
+            ("NIC enp0s31f6 address", IndicatorKind::AddressOverride),
+        ]);
+        assert_eq!(result.identifiers_checked, 9); // 6 DMI attributes, machine id, 2 disks
+        assert_eq!(result.risk_score, 140);
+        assert!(result.spoofing_detected);
+    }
+}
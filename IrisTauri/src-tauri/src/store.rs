@@ -2,6 +2,7 @@
 
 use obfstr::obfstr;
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 use std::sync::Mutex;
 
 fn service_name() -> String {
@@ -16,6 +17,10 @@ fn key_user() -> String {
     obfstr!("user").to_string()
 }
 
+fn key_journal() -> String {
+    obfstr!("journal").to_string()
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct UserSession {
     pub user_id: String,
@@ -112,9 +117,56 @@ pub fn delete_user() -> Result<(), String> {
     Ok(())
 }
 
+/// Key of the local hardware journal, and whether it was just created
+/// (kept on logout: the journal follows the machine, not the account)
+pub fn journal_key() -> Result<(Vec<u8>, bool), String> {
+    use ring::rand::{SecureRandom, SystemRandom};
+
+    let entry = keyring::Entry::new(&service_name(), &key_journal())
+        .map_err(|e| format!("Failed to create keyring entry: {}", e))?;
+
+    match entry.get_password() {
+        Ok(encoded) => {
+            let key = hex::decode(encoded).map_err(|e| format!("Invalid journal key: {}", e))?;
+            if key.len() != 32 {
+                return Err(format!("Invalid journal key length {}", key.len()));
+            }
+            Ok((key, false))
+        }
+        Err(keyring::Error::NoEntry) => {
+            let mut key = vec![0u8; 32];
+            SystemRandom::new().fill(&mut key).map_err(|_| "Failed to generate journal key".to_string())?;
+            entry.set_password(&hex::encode(&key))
+                .map_err(|e| format!("Failed to save journal key: {}", e))?;
+            Ok((key, true))
+        }
+        Err(e) => Err(format!("Failed to read journal key: {}", e)),
+    }
+}
+
 /// Clear all stored data (logout)
 pub fn clear_all() -> Result<(), String> {
     delete_token()?;
     delete_user()?;
     Ok(())
 }
+
+/// Local data directory for cached files (%LOCALAPPDATA%\Iris, ~/.local/share/iris)
+pub fn data_dir() -> PathBuf {
+    #[cfg(target_os = "windows")]
+    {
+        let base = std::env::var("LOCALAPPDATA").unwrap_or_else(|_| ".".to_string());
+        PathBuf::from(base).join(obfstr!("Iris"))
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        let base = std::env::var("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| {
+                PathBuf::from(std::env::var("HOME").unwrap_or_else(|_| ".".to_string()))
+                    .join(".local")
+                    .join("share")
+            });
+        base.join(obfstr!("iris"))
+    }
+}
@@ -0,0 +1,396 @@
+//! Sysfs module - Linux device inventories read from /sys
+//!
+//! USB devices come from `/sys/bus/usb/devices`: one directory per device (`1-1.2`)
+//! with its descriptors, plus one per interface (`1-1.2:1.0`) whose `driver` link
+//! names the bound kernel driver. Device ids are built in the Windows PnP format
+//! (`USB\VID_xxxx&PID_xxxx\serial`) so the existing VID/PID rules apply unchanged.
+//! The directory name is the port path, which also gives the parent hub.
+//!
+//! PCI functions come from `/sys/bus/pci/devices/<segment:bus:device.function>`:
+//! hex id attributes, the `resource` table (one line per BAR and window) and the
+//! `config` space, of which non-root users only get the first 64 bytes.
+//!
+//! HID interfaces come from `/sys/class/hidraw/hidrawN/device`: the `uevent`
+//! (HID_ID, HID_NAME, HID_PHYS) and the raw `report_descriptor`.
+//! The root is a parameter so a fixture tree can be read the same way.
+
+use crate::hardware::UsbDeviceInfo;
+use crate::hid::HidDeviceInfo;
+use crate::pci::PciDeviceInfo;
+use std::fs;
+use std::path::Path;
+
+/// USB devices of the live system
+pub fn list_usb_devices() -> Vec<UsbDeviceInfo> {
+    read_usb_devices(Path::new("/sys/bus/usb/devices"))
+}
+
+/// USB devices of a `/sys/bus/usb/devices` tree
+pub fn read_usb_devices(root: &Path) -> Vec<UsbDeviceInfo> {
+    let mut names: Vec<String> = match fs::read_dir(root) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .collect(),
+        Err(e) => {
+            println!("[Sysfs] Cannot read {}: {}", root.display(), e);
+            return Vec::new();
+        }
+    };
+    names.sort();
+
+    let mut devices: Vec<UsbDeviceInfo> = names.iter()
+        .filter(|name| !name.contains(':')) // Interfaces are read with their device
+        .filter_map(|name| read_usb_device(root, name, &names))
+        .collect();
+
+    // Parent port path -> device id (`1-1.2` sits on hub `1-1`, which sits on root hub `usb1`)
+    let parents: Vec<Option<String>> = devices.iter()
+        .map(|device| {
+            let parent = usb_parent_port(device.location.as_deref()?)?;
+            devices.iter()
+                .find(|d| d.location.as_deref() == Some(parent.as_str()))
+                .map(|d| d.device_id.clone())
+        })
+        .collect();
+    for (device, parent_id) in devices.iter_mut().zip(parents) {
+        device.parent_id = parent_id;
+    }
+    devices
+}
+
+/// Port path of the upstream hub (None for root hubs)
+fn usb_parent_port(port: &str) -> Option<String> {
+    match port.rsplit_once('.') {
+        Some((parent, _)) => Some(parent.to_string()),
+        None => port.split_once('-').map(|(bus, _)| format!("usb{}", bus)),
+    }
+}
+
+fn read_usb_device(root: &Path, name: &str, entries: &[String]) -> Option<UsbDeviceInfo> {
+    let dir = root.join(name);
+    let vid = read_attr(&dir, "idVendor")?.to_lowercase();
+    let pid = read_attr(&dir, "idProduct")?.to_lowercase();
+    let manufacturer = read_attr(&dir, "manufacturer");
+    let product = read_attr(&dir, "product");
+    let serial = read_attr(&dir, "serial");
+
+    // Drivers bound to the interfaces of this device (`<name>:<config>.<interface>`)
+    let interface_prefix = format!("{}:", name);
+    let mut drivers: Vec<String> = entries.iter()
+        .filter(|entry| entry.starts_with(&interface_prefix))
+        .filter_map(|entry| fs::read_link(root.join(entry).join("driver")).ok())
+        .filter_map(|link| link.file_name().map(|n| n.to_string_lossy().into_owned()))
+        .collect();
+    drivers.sort();
+    drivers.dedup();
+
+    let device_id = format!(
+        "USB\\VID_{}&PID_{}\\{}",
+        vid.to_uppercase(),
+        pid.to_uppercase(),
+        serial.as_deref().unwrap_or(name)
+    );
+
+    Some(UsbDeviceInfo {
+        name: product.unwrap_or_else(|| format!("USB device {}:{}", vid, pid)),
+        device_id,
+        manufacturer,
+        serial,
+        drivers,
+        parent_id: None,
+        location: Some(name.to_string()),
+    })
+}
+
+/// PCI functions of the live system
+pub fn list_pci_devices() -> Vec<PciDeviceInfo> {
+    read_pci_devices(Path::new("/sys/bus/pci/devices"))
+}
+
+/// PCI functions of a `/sys/bus/pci/devices` tree
+pub fn read_pci_devices(root: &Path) -> Vec<PciDeviceInfo> {
+    let mut names: Vec<String> = match fs::read_dir(root) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .collect(),
+        Err(e) => {
+            println!("[Sysfs] Cannot read {}: {}", root.display(), e);
+            return Vec::new();
+        }
+    };
+    names.sort();
+
+    names.iter()
+        .filter_map(|name| read_pci_device(root, name))
+        .collect()
+}
+
+fn read_pci_device(root: &Path, name: &str) -> Option<PciDeviceInfo> {
+    let dir = root.join(name);
+    let hex = |attr: &str| read_attr(&dir, attr)
+        .and_then(|value| u32::from_str_radix(value.trim_start_matches("0x"), 16).ok());
+    let vendor_id = hex("vendor")? as u16;
+    let device_id = hex("device")? as u16;
+
+    let driver = fs::read_link(dir.join("driver")).ok()
+        .and_then(|link| link.file_name().map(|n| n.to_string_lossy().into_owned()));
+    // start end flags per line; unassigned BARs are all zeros
+    let resources = fs::read_to_string(dir.join("resource")).ok().map(|table| {
+        table.lines()
+            .filter_map(|line| line.split_whitespace().nth(1))
+            .filter(|end| u64::from_str_radix(end.trim_start_matches("0x"), 16).unwrap_or(0) != 0)
+            .count()
+    });
+
+    Some(PciDeviceInfo {
+        location: name.to_string(),
+        name: driver.as_ref()
+            .map(|driver| format!("PCI device {:04x}:{:04x} ({})", vendor_id, device_id, driver))
+            .unwrap_or_else(|| format!("PCI device {:04x}:{:04x}", vendor_id, device_id)),
+        vendor_id,
+        device_id,
+        subsystem_vendor_id: hex("subsystem_vendor").unwrap_or(0) as u16,
+        subsystem_id: hex("subsystem_device").unwrap_or(0) as u16,
+        revision: hex("revision").unwrap_or(0) as u8,
+        class_code: hex("class").unwrap_or(0),
+        driver,
+        problem_code: None,
+        resources,
+        config: fs::read(dir.join("config")).unwrap_or_default(),
+        // Entries link to /sys/devices/pci0000:00/<root port>/.../<function>: the parent directory is the bridge
+        upstream: fs::canonicalize(&dir).ok()
+            .and_then(|path| path.parent()?.file_name()?.to_str().map(str::to_string))
+            .filter(|parent| is_pci_address(parent)),
+    })
+}
+
+/// segment:bus:device.function ("0000:00:1c.0"), unlike the host bridge directory ("pci0000:00")
+fn is_pci_address(name: &str) -> bool {
+    let bytes = name.as_bytes();
+    bytes.len() == 12 && bytes[4] == b':' && bytes[7] == b':' && bytes[10] == b'.'
+        && name.chars().filter(|c| !matches!(c, ':' | '.')).all(|c| c.is_ascii_hexdigit())
+}
+
+/// HID interfaces of the live system
+pub fn list_hid_devices() -> Vec<HidDeviceInfo> {
+    read_hid_devices(Path::new("/sys/class/hidraw"))
+}
+
+/// HID interfaces of a `/sys/class/hidraw` tree
+pub fn read_hid_devices(root: &Path) -> Vec<HidDeviceInfo> {
+    let mut names: Vec<String> = match fs::read_dir(root) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .collect(),
+        Err(e) => {
+            println!("[Sysfs] Cannot read {}: {}", root.display(), e);
+            return Vec::new();
+        }
+    };
+    names.sort();
+
+    names.iter()
+        .filter_map(|name| read_hid_device(root, name))
+        .collect()
+}
+
+fn read_hid_device(root: &Path, name: &str) -> Option<HidDeviceInfo> {
+    let dir = root.join(name).join("device");
+    // HID_ID=<bus>:<vendor>:<product>, HID_NAME=..., HID_PHYS=usb-0000:00:14.0-1/input0
+    let uevent = fs::read_to_string(dir.join("uevent")).ok()?;
+    let field = |key: &str| uevent.lines()
+        .find_map(|line| line.strip_prefix(key).and_then(|rest| rest.strip_prefix('=')))
+        .map(|value| value.trim().to_string());
+
+    let ids: Vec<String> = field("HID_ID")?.split(':').map(|part| part.to_lowercase()).collect();
+    let (vid, pid) = match ids.as_slice() {
+        [_, vendor, product] => (
+            format!("{:0>4}", vendor.trim_start_matches('0')),
+            format!("{:0>4}", product.trim_start_matches('0')),
+        ),
+        _ => return None,
+    };
+    let descriptor = fs::read(dir.join("report_descriptor")).ok()?;
+    // Interfaces of one device share the physical path up to `/inputN`
+    let phys = field("HID_PHYS").unwrap_or_default();
+    let device_key = match phys.rsplit_once('/') {
+        Some((device, _)) if !device.is_empty() => device.to_string(),
+        _ => format!("{}:{}:{}", vid, pid, name),
+    };
+
+    Some(HidDeviceInfo {
+        device_key,
+        name: field("HID_NAME").unwrap_or_else(|| format!("HID device {}:{}", vid, pid)),
+        vid,
+        pid,
+        collections: crate::hid::parse_descriptor(&descriptor),
+        descriptor,
+    })
+}
+
+/// Trimmed sysfs attribute (None when missing or empty)
+fn read_attr(dir: &Path, attr: &str) -> Option<String> {
+    fs::read_to_string(dir.join(attr)).ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+/// Fake /sys trees for the tests of the modules reading it
+#[cfg(test)]
+pub(crate) mod fixture {
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+
+    /// Temporary copy of a fixture tree described by `tests/fixtures/sysfs/<manifest>`
+    pub struct Tree(pub PathBuf);
+
+    impl Tree {
+        pub fn new(manifest: &str) -> Tree {
+            let source = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/sysfs").join(manifest);
+            let root = std::env::temp_dir().join(format!("iris-sysfs-{}-{}-{}",
+                std::process::id(), NEXT.fetch_add(1, Ordering::Relaxed), manifest));
+            let _ = fs::remove_dir_all(&root);
+
+            for line in fs::read_to_string(&source).unwrap().lines() {
+                let line = line.trim();
+                if line.is_empty() || line.starts_with('#') {
+                    continue;
+                }
+                if let Some((path, target)) = line.split_once(" -> ") {
+                    let path = root.join(path);
+                    fs::create_dir_all(path.parent().unwrap()).unwrap();
+                    std::os::unix::fs::symlink(target, path).unwrap();
+                } else if let Some((path, value)) = line.split_once(" = ") {
+                    let path = root.join(path);
+                    fs::create_dir_all(path.parent().unwrap()).unwrap();
+                    let content = match value.strip_prefix("hex:") {
+                        Some(bytes) => hex::decode(bytes).unwrap(),
+                        None => format!("{}\n", value.replace("\\n", "\n")).into_bytes(),
+                    };
+                    fs::write(path, content).unwrap();
+                } else {
+                    panic!("{}: bad line '{}'", manifest, line);
+                }
+            }
+            Tree(root)
+        }
+    }
+
+    impl Drop for Tree {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fixture::Tree;
+    use super::*;
+
+    #[test]
+    fn reads_usb_devices_with_drivers_and_parents() {
+        let tree = Tree::new("usb-devices.txt");
+        let devices = read_usb_devices(&tree.0);
+        let ids: Vec<&str> = devices.iter().map(|d| d.device_id.as_str()).collect();
+        assert_eq!(ids, vec![
+            "USB\\VID_05E3&PID_0610\\1-1",
+            "USB\\VID_2341&PID_8036\\1-1.2",
+            "USB\\VID_046D&PID_C077\\4C0A12",
+            "USB\\VID_1D6B&PID_0002\\0000:00:14.0",
+        ]);
+
+        let hub = &devices[0];
+        assert_eq!(hub.name, "USB2.0 Hub");
+        assert_eq!(hub.drivers, vec!["hub"]);
+        assert_eq!(hub.parent_id.as_deref(), Some("USB\\VID_1D6B&PID_0002\\0000:00:14.0"));
+
+        let arduino = &devices[1];
+        assert_eq!(arduino.manufacturer.as_deref(), Some("Arduino LLC"));
+        assert_eq!(arduino.serial, None);
+        assert_eq!(arduino.drivers, vec!["cdc_acm", "usbhid"]);
+        assert_eq!(arduino.parent_id.as_deref(), Some(hub.device_id.as_str()));
+        assert_eq!(arduino.location.as_deref(), Some("1-1.2"));
+
+        let mouse = &devices[2];
+        assert_eq!(mouse.name, "USB device 046d:c077");
+        assert_eq!(mouse.drivers, vec!["usbhid"]);
+        assert_eq!(mouse.parent_id.as_deref(), Some(devices[3].device_id.as_str()));
+        assert_eq!(devices[3].parent_id, None);
+    }
+
+    #[test]
+    fn usb_parent_ports() {
+        assert_eq!(usb_parent_port("1-1.2.4").as_deref(), Some("1-1.2"));
+        assert_eq!(usb_parent_port("3-2").as_deref(), Some("usb3"));
+        assert_eq!(usb_parent_port("usb1"), None);
+    }
+
+    #[test]
+    fn reads_pci_functions() {
+        let tree = Tree::new("pci-devices.txt");
+        let devices = read_pci_devices(&tree.0);
+        assert_eq!(devices.len(), 2);
+
+        let audio = &devices[0];
+        assert_eq!(audio.location, "0000:00:1f.3");
+        assert_eq!((audio.vendor_id, audio.device_id), (0x8086, 0xa348));
+        assert_eq!((audio.subsystem_vendor_id, audio.subsystem_id), (0x1043, 0x8724));
+        assert_eq!((audio.revision, audio.class_code), (0x10, 0x040300));
+        assert_eq!(audio.driver.as_deref(), Some("snd_hda_intel"));
+        assert_eq!(audio.name, "PCI device 8086:a348 (snd_hda_intel)");
+        assert_eq!(audio.resources, Some(1));
+        assert_eq!(audio.config.len(), 64);
+
+        let card = &devices[1];
+        assert_eq!(card.name, "PCI device 10ee:0666");
+        assert_eq!((card.driver.as_deref(), card.resources), (None, None));
+        assert_eq!((card.subsystem_vendor_id, card.subsystem_id), (0, 0));
+        assert_eq!(&card.config[..4], &[0xee, 0x10, 0x66, 0x06]);
+        assert_eq!((audio.upstream.as_deref(), card.upstream.as_deref()), (None, None));
+    }
+
+    #[test]
+    fn reads_pci_upstream_bridges() {
+        let tree = Tree::new("pci-topology.txt");
+        let devices = read_pci_devices(&tree.0.join("bus/pci/devices"));
+        let upstream: Vec<(&str, Option<&str>)> = devices.iter()
+            .map(|d| (d.location.as_str(), d.upstream.as_deref()))
+            .collect();
+        assert_eq!(upstream, vec![
+            ("0000:00:1c.0", None), // Host bridge directory pci0000:00
+            ("0000:04:00.0", Some("0000:00:1c.0")),
+            ("0000:05:01.0", Some("0000:04:00.0")),
+        ]);
+    }
+
+    #[test]
+    fn reads_hid_interfaces() {
+        let tree = Tree::new("hidraw.txt");
+        let devices = read_hid_devices(&tree.0);
+        assert_eq!(devices.len(), 3);
+
+        let mouse = &devices[0];
+        assert_eq!((mouse.vid.as_str(), mouse.pid.as_str()), ("046d", "c077"));
+        assert_eq!(mouse.name, "Logitech USB Optical Mouse");
+        assert_eq!(mouse.device_key, "usb-0000:00:14.0-2");
+        let pointer = &mouse.collections[0];
+        assert_eq!((pointer.usage_page, pointer.usage), (1, 2));
+        assert_eq!((pointer.buttons, pointer.axis_bits, pointer.relative), (3, 8, true));
+
+        let keyboard = &devices[1];
+        assert_eq!((keyboard.vid.as_str(), keyboard.pid.as_str()), ("2341", "8036"));
+        assert_eq!(keyboard.device_key, "usb-0000:00:14.0-1.2");
+        assert_eq!(keyboard.collections[0].usage, 6);
+        assert_eq!(keyboard.collections[0].key_max, 0x65);
+
+        // No physical path: one device per interface
+        assert_eq!(devices[2].device_key, "054c:05c4:hidraw2");
+    }
+}
@@ -0,0 +1,238 @@
+//! TPM module - Linux TPM discovery (sysfs) and raw TPM 2.0 commands
+//!
+//! Presence and version come from `/sys/class/tpm/tpm*` (`tpm_version_major`,
+//! `device/description`, `device/caps` on TPM 1.2). The manufacturer of a TPM 2.0
+//! is only known by the chip itself, so it is asked with TPM2_GetCapability through
+//! the kernel resource manager (`/dev/tpmrm0`). Roots are parameters so a fixture
+//! tree or a swtpm-backed device (`swtpm chardev --vtpm-proxy`) can be used.
+
+use crate::attestation::{self, Transport};
+use crate::hardware::TpmStatus;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::Path;
+
+const TPM_ST_NO_SESSIONS: u16 = 0x8001;
+const TPM_CC_GET_CAPABILITY: u32 = 0x0000_017A;
+const TPM_CAP_TPM_PROPERTIES: u32 = 0x0000_0006;
+const TPM_PT_MANUFACTURER: u32 = 0x0000_0105;
+
+/// Largest response of the kernel TPM drivers
+const MAX_RESPONSE: usize = 4096;
+
+/// TPM status of the live system
+pub fn check_status() -> TpmStatus {
+    read_status(Path::new("/sys/class/tpm"), Path::new("/dev"))
+}
+
+/// TPM status from a `/sys/class/tpm` tree and a `/dev` directory
+pub fn read_status(class_root: &Path, dev_root: &Path) -> TpmStatus {
+    let mut status = TpmStatus::default();
+
+    let mut chips: Vec<String> = fs::read_dir(class_root).into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| name.starts_with("tpm") && name[3..].parse::<u32>().is_ok())
+        .collect();
+    chips.sort();
+    let (chip_name, chip) = match chips.first() {
+        Some(name) => (name.as_str(), class_root.join(name)),
+        None => return status,
+    };
+    status.present = true;
+
+    let device = chip.join("device");
+    let caps = read_attr(&device, "caps");
+    let resource_manager = dev_root.join(format!("tpmrm{}", &chip_name[3..]));
+
+    // tpm_version_major exists since Linux 5.6; older kernels only expose caps (1.2)
+    let caps_version = caps.as_deref().and_then(caps_version);
+    status.version = match (read_attr(&chip, "tpm_version_major").as_deref(), caps_version) {
+        (Some("2"), _) => "2.0".to_string(),
+        (Some("1"), version) | (None, version @ Some(_)) => version.unwrap_or_else(|| "1.2".to_string()),
+        (Some(other), _) => other.to_string(),
+        (None, None) if resource_manager.exists() => "2.0".to_string(),
+        (None, None) => String::new(),
+    };
+
+    // A TPM 2.0 disabled in firmware is not enumerated; TPM 1.2 reports its state
+    status.enabled = if status.version.starts_with('2') {
+        true
+    } else {
+        read_attr(&device, "enabled").as_deref() == Some("1") && read_attr(&device, "active").as_deref() != Some("0")
+    };
+
+    status.manufacturer = if status.version.starts_with('2') && resource_manager.exists() {
+        match get_manufacturer(&resource_manager) {
+            Ok(manufacturer) => Some(manufacturer),
+            Err(e) => {
+                println!("[TPM] Manufacturer query failed: {}", e);
+                None
+            }
+        }
+    } else {
+        None
+    }
+    .or_else(|| caps.as_deref().and_then(caps_manufacturer))
+    .or_else(|| read_attr(&device, "description"))
+    .unwrap_or_default();
+
+    println!("[TPM] {}: version={}, enabled={}, manufacturer={}",
+             chip.display(), status.version, status.enabled, status.manufacturer);
+    status
+}
+
+/// Manufacturer id of a TPM 2.0 ("IFX", "INTC", "MSFT"...)
+pub fn get_manufacturer(device: &Path) -> Result<String, String> {
+    get_manufacturer_with(&mut Connection::open(device)?)
+}
+
+/// Manufacturer id through any transport (device file, TBS, test double)
+pub fn get_manufacturer_with(tpm: &mut dyn Transport) -> Result<String, String> {
+    let mut command = Vec::with_capacity(22);
+    command.extend_from_slice(&TPM_ST_NO_SESSIONS.to_be_bytes());
+    command.extend_from_slice(&22u32.to_be_bytes());
+    command.extend_from_slice(&TPM_CC_GET_CAPABILITY.to_be_bytes());
+    command.extend_from_slice(&TPM_CAP_TPM_PROPERTIES.to_be_bytes());
+    command.extend_from_slice(&TPM_PT_MANUFACTURER.to_be_bytes());
+    command.extend_from_slice(&1u32.to_be_bytes());
+
+    let response = tpm.submit(&command)?;
+    // moreData(1) capability(4) count(4) property(4) value(4)
+    let body = response.get(10..).ok_or("Short response")?;
+    if body.len() < 17 || be_u32(&body[1..5]) != TPM_CAP_TPM_PROPERTIES || be_u32(&body[9..13]) != TPM_PT_MANUFACTURER {
+        return Err("Unexpected GetCapability response".to_string());
+    }
+    Ok(vendor_id(&body[13..17]))
+}
+
+/// Open TPM device. Transient objects live as long as the connection: the resource
+/// manager flushes them when the file is closed.
+pub struct Connection {
+    file: File,
+}
+
+impl Connection {
+    pub fn open(device: &Path) -> Result<Self, String> {
+        let file = OpenOptions::new().read(true).write(true).open(device)
+            .map_err(|e| format!("Cannot open {}: {}", device.display(), e))?;
+        Ok(Connection { file })
+    }
+
+    /// Send one command and return the response (error on a non-zero response code)
+    pub fn transact(&mut self, command: &[u8]) -> Result<Vec<u8>, String> {
+        self.file.write_all(command).map_err(|e| format!("TPM write failed: {}", e))?;
+
+        let mut response = vec![0u8; MAX_RESPONSE];
+        let len = self.file.read(&mut response).map_err(|e| format!("TPM read failed: {}", e))?;
+        response.truncate(len);
+        attestation::check_response(response)
+    }
+}
+
+/// "TCG version: 1.2" line of a TPM 1.2 caps file
+fn caps_version(caps: &str) -> Option<String> {
+    caps.lines()
+        .find_map(|line| line.strip_prefix("TCG version:"))
+        .map(|v| v.trim().to_string())
+}
+
+/// "Manufacturer: 0x49465800" line of a TPM 1.2 caps file
+fn caps_manufacturer(caps: &str) -> Option<String> {
+    let hex = caps.lines().find_map(|line| line.strip_prefix("Manufacturer:"))?.trim();
+    let id = u32::from_str_radix(hex.trim_start_matches("0x"), 16).ok()?;
+    Some(vendor_id(&id.to_be_bytes()))
+}
+
+/// TCG vendor id: 4 ASCII characters, space/NUL padded
+fn vendor_id(bytes: &[u8]) -> String {
+    bytes.iter()
+        .filter(|b| b.is_ascii_graphic())
+        .map(|b| *b as char)
+        .collect()
+}
+
+fn be_u32(bytes: &[u8]) -> u32 {
+    u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+fn read_attr(dir: &Path, attr: &str) -> Option<String> {
+    fs::read_to_string(dir.join(attr)).ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/tpm").join(name)
+    }
+
+    fn read(name: &str) -> TpmStatus {
+        read_status(&fixture(name), &fixture("no-dev"))
+    }
+
+    /// Answers one GetCapability(TPM_PT_MANUFACTURER) with a fixed vendor id
+    struct Manufacturer(&'static [u8; 4]);
+
+    impl Transport for Manufacturer {
+        fn submit(&mut self, command: &[u8]) -> Result<Vec<u8>, String> {
+            assert_eq!(command.len(), 22);
+            assert_eq!(be_u32(&command[6..10]), TPM_CC_GET_CAPABILITY);
+            assert_eq!(be_u32(&command[14..18]), TPM_PT_MANUFACTURER);
+
+            let mut response = vec![0x80, 0x01, 0, 0, 0, 27, 0, 0, 0, 0, 0];
+            response.extend_from_slice(&TPM_CAP_TPM_PROPERTIES.to_be_bytes());
+            response.extend_from_slice(&1u32.to_be_bytes());
+            response.extend_from_slice(&TPM_PT_MANUFACTURER.to_be_bytes());
+            response.extend_from_slice(self.0);
+            attestation::check_response(response)
+        }
+    }
+
+    #[test]
+    fn reads_tpm20_chip() {
+        let status = read("tpm20");
+        assert!(status.present && status.enabled);
+        assert_eq!(status.version, "2.0");
+        // No resource manager to ask: falls back to the ACPI description
+        assert_eq!(status.manufacturer, "TPM 2.0 Device");
+    }
+
+    #[test]
+    fn reads_tpm12_caps() {
+        let status = read("tpm12");
+        assert!(status.present && status.enabled);
+        assert_eq!(status.version, "1.2");
+        assert_eq!(status.manufacturer, "IFX");
+
+        let disabled = read("tpm12-disabled");
+        assert!(disabled.present && !disabled.enabled);
+    }
+
+    #[test]
+    fn no_chip() {
+        assert!(!read("none").present);
+        assert!(!read("missing").present);
+    }
+
+    #[test]
+    fn manufacturer_from_get_capability() {
+        assert_eq!(get_manufacturer_with(&mut Manufacturer(b"INTC")).unwrap(), "INTC");
+        assert_eq!(get_manufacturer_with(&mut Manufacturer(b"IBM\0")).unwrap(), "IBM");
+    }
+
+    /// Needs a swtpm-backed device (root, `tpm_vtpm_proxy` module):
+    /// `swtpm chardev --vtpm-proxy --tpm2 --tpmstate dir=/tmp/swtpm` prints /dev/tpmN,
+    /// then `IRIS_TEST_TPM=/dev/tpmrmN cargo test -- --ignored swtpm`
+    #[test]
+    #[ignore]
+    fn swtpm_manufacturer() {
+        let device = std::env::var("IRIS_TEST_TPM").expect("IRIS_TEST_TPM is not set");
+        assert_eq!(get_manufacturer(Path::new(&device)).unwrap(), "IBM");
+    }
+}
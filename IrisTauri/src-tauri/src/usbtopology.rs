@@ -0,0 +1,590 @@
+//! USB topology module - serial bridges and USB NICs next to input devices
+//!
+//! KMBox, Cronus with serial passthrough and "second PC" aim setups drive a mouse
+//! through a CH340/CP210x/FTDI serial bridge or a USB NIC. On their own these are
+//! common (Arduino boards, dongles), so they are only reported when correlated
+//! with an input device:
+//! - one USB device exposing both the bridge and the input interface
+//! - serial bridge and input device behind the same external hub (docks put a NIC
+//!   next to the keyboard, so NICs do not count here)
+//! - bridge and input device plugged in together (first seen in the same snapshot)
+//!
+//! Only a serial bridge on the input device itself is enough alone; otherwise two
+//! different indicators are needed, and NIC indicators weigh less than serial ones.
+//! Single weak indicators stay in the result as informational matches.
+//!
+//! Topology comes from `parent_id`/`location` of the USB inventory: sysfs port
+//! paths (`1-1.2`) on Linux, the configuration manager device tree on Windows.
+
+use crate::hardware::{DetectedDevice, UsbDeviceInfo};
+use crate::rules;
+use crate::whitelist::{self, Suppression, Whitelist, WhitelistType};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Devices first seen this close together count as plugged in together
+const APPEARANCE_WINDOW_MS: u64 = 10_000;
+
+/// USB-to-serial bridges: (vid, pid, chip)
+const SERIAL_BRIDGES: &[(&str, &str, &str)] = &[
+    ("1a86", "7523", "CH340"),
+    ("1a86", "7522", "CH340K"),
+    ("1a86", "5523", "CH341"),
+    ("1a86", "55d3", "CH343"),
+    ("1a86", "55d4", "CH9102"),
+    ("10c4", "ea60", "CP210x"),
+    ("10c4", "ea70", "CP2105"),
+    ("10c4", "ea71", "CP2108"),
+    ("0403", "6001", "FTDI FT232R"),
+    ("0403", "6010", "FTDI FT2232"),
+    ("0403", "6011", "FTDI FT4232"),
+    ("0403", "6014", "FTDI FT232H"),
+    ("0403", "6015", "FTDI FT-X"),
+    ("067b", "2303", "PL2303"),
+    ("067b", "23a3", "PL2303GC"),
+];
+
+/// USB network controllers: (vid, pid, chip)
+const NETWORK_ADAPTERS: &[(&str, &str, &str)] = &[
+    ("0bda", "8152", "Realtek RTL8152"),
+    ("0bda", "8153", "Realtek RTL8153"),
+    ("0bda", "8156", "Realtek RTL8156"),
+    ("0b95", "1790", "ASIX AX88179"),
+    ("0b95", "772b", "ASIX AX88772B"),
+    ("0b95", "7720", "ASIX AX88772"),
+];
+
+/// Linux interface drivers: (driver, role)
+const ROLE_DRIVERS: &[(&str, UsbRole)] = &[
+    ("ch341", UsbRole::Serial),
+    ("cp210x", UsbRole::Serial),
+    ("ftdi_sio", UsbRole::Serial),
+    ("pl2303", UsbRole::Serial),
+    ("cdc_acm", UsbRole::Serial),
+    ("rndis_host", UsbRole::Network),
+    ("cdc_ether", UsbRole::Network),
+    ("cdc_ncm", UsbRole::Network),
+    ("cdc_eem", UsbRole::Network),
+    ("r8152", UsbRole::Network),
+    ("ax88179_178a", UsbRole::Network),
+    ("asix", UsbRole::Network),
+    ("usbhid", UsbRole::Input),
+];
+
+/// Device name keywords (Windows names come from the INF): (keyword, role)
+const ROLE_KEYWORDS: &[(&str, UsbRole)] = &[
+    ("usb-serial", UsbRole::Serial),
+    ("serial port", UsbRole::Serial),
+    ("serial converter", UsbRole::Serial),
+    ("(com", UsbRole::Serial),
+    ("rndis", UsbRole::Network),
+    ("ethernet", UsbRole::Network),
+    ("gbe family controller", UsbRole::Network),
+    ("input device", UsbRole::Input),
+    ("mouse", UsbRole::Input),
+    ("keyboard", UsbRole::Input),
+];
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum UsbRole {
+    Serial,  // USB-to-serial bridge / CDC ACM
+    Network, // USB NIC / RNDIS
+    Input,   // Mouse, keyboard, HID
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TopologyIndicatorKind {
+    SameDevice,       // Bridge and input interface on one USB device
+    SameHub,          // Bridge and input device behind the same external hub
+    AppearedTogether, // Bridge and input device plugged in together
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TopologyIndicator {
+    pub kind: TopologyIndicatorKind,
+    pub detail: String,
+}
+
+impl TopologyIndicatorKind {
+    /// Risk points of the indicator for a bridge of the given role
+    pub fn weight(self, role: UsbRole) -> u32 {
+        match (self, role) {
+            (TopologyIndicatorKind::SameDevice, UsbRole::Network) => 50,
+            (TopologyIndicatorKind::SameDevice, _) => 100,
+            (TopologyIndicatorKind::SameHub, _) => 40,
+            (TopologyIndicatorKind::AppearedTogether, UsbRole::Network) => 20,
+            (TopologyIndicatorKind::AppearedTogether, _) => 30,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TopologyMatch {
+    pub device: DetectedDevice, // The bridge / NIC, with its topology path
+    pub input_device: String,   // Input device it was correlated with
+    pub indicators: Vec<TopologyIndicator>,
+    pub risk_score: u32,
+    pub correlated: bool, // Enough evidence for a detection; otherwise the match is informational
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct UsbTopologyResult {
+    pub bridge_detected: bool,
+    pub devices_scanned: usize,
+    pub matches: Vec<TopologyMatch>,
+    pub risk_score: u32,
+    pub suppressed: Vec<Suppression>, // Whitelist entries that fired
+}
+
+/// Physical USB device: a device node with its interface nodes (Windows lists them separately)
+struct PhysicalDevice<'a> {
+    node: &'a UsbDeviceInfo,
+    vid: String,
+    pid: String,
+    bridge: Option<(UsbRole, String)>, // Role and chip (VID:PID when unknown); serial wins over network
+    input: bool,
+    hub: Option<&'a UsbDeviceInfo>,
+    first_seen: Option<u64>, // None when present since the first snapshot
+}
+
+/// device_id -> first seen (ms); None until the first snapshot is recorded
+type Arrivals = Mutex<Option<HashMap<String, Option<u64>>>>;
+
+lazy_static::lazy_static! {
+    static ref FIRST_SEEN: Arrivals = Mutex::new(None);
+}
+
+/// Correlate the USB inventory of a snapshot (records arrivals between snapshots)
+pub fn detect(devices: &[UsbDeviceInfo], collected_at: u64) -> UsbTopologyResult {
+    detect_with(&FIRST_SEEN, devices, collected_at, &whitelist::current())
+}
+
+fn detect_with(arrivals: &Arrivals, devices: &[UsbDeviceInfo], collected_at: u64, allowed: &Whitelist) -> UsbTopologyResult {
+    let first_seen = record_arrivals(arrivals, devices, collected_at);
+    correlate_with(devices, &first_seen, allowed)
+}
+
+/// Update the arrival times: devices present in the first snapshot have none
+fn record_arrivals(arrivals: &Arrivals, devices: &[UsbDeviceInfo], now: u64) -> HashMap<String, Option<u64>> {
+    let mut state = match arrivals.lock() {
+        Ok(state) => state,
+        Err(_) => return HashMap::new(),
+    };
+    let baseline = state.is_none();
+    let previous = state.take().unwrap_or_default();
+
+    let seen: HashMap<String, Option<u64>> = devices.iter()
+        .map(|d| {
+            let first_seen = match previous.get(&d.device_id) {
+                Some(first_seen) => *first_seen,
+                None if baseline => None,
+                None => Some(now),
+            };
+            (d.device_id.clone(), first_seen)
+        })
+        .collect();
+    *state = Some(seen.clone());
+    seen
+}
+
+/// Correlate bridges / NICs with input devices
+pub fn correlate(devices: &[UsbDeviceInfo], first_seen: &HashMap<String, Option<u64>>) -> UsbTopologyResult {
+    correlate_with(devices, first_seen, &whitelist::current())
+}
+
+fn correlate_with(devices: &[UsbDeviceInfo], first_seen: &HashMap<String, Option<u64>>, allowed: &Whitelist) -> UsbTopologyResult {
+    let mut result = UsbTopologyResult { devices_scanned: devices.len(), ..Default::default() };
+    let physical = physical_devices(devices, first_seen);
+
+    for bridge in &physical {
+        let (role, chip) = match &bridge.bridge {
+            Some(bridge) => bridge,
+            None => continue,
+        };
+        let kind = match role {
+            UsbRole::Network => "USB network adapter",
+            _ => "Serial bridge",
+        };
+
+        let mut indicators = Vec::new();
+        let mut input_device = None;
+        if bridge.input {
+            indicators.push(TopologyIndicator {
+                kind: TopologyIndicatorKind::SameDevice,
+                detail: format!("{} ({}) and an input interface on one USB device", kind, chip),
+            });
+            input_device = Some(bridge.node.name.clone());
+        }
+        for input in physical.iter().filter(|p| p.input && p.bridge.is_none()) {
+            let mut found = false;
+            if let (Some(hub), Some(input_hub), UsbRole::Serial) = (bridge.hub, input.hub, role) {
+                if hub.device_id == input_hub.device_id && !is_root_hub(hub) {
+                    indicators.push(TopologyIndicator {
+                        kind: TopologyIndicatorKind::SameHub,
+                        detail: format!("{} ({}) behind the same hub as {} ({})", kind, chip, input.node.name, hub.name),
+                    });
+                    found = true;
+                }
+            }
+            if let (Some(a), Some(b)) = (bridge.first_seen, input.first_seen) {
+                if a.abs_diff(b) <= APPEARANCE_WINDOW_MS {
+                    indicators.push(TopologyIndicator {
+                        kind: TopologyIndicatorKind::AppearedTogether,
+                        detail: format!("{} ({}) plugged in together with {}", kind, chip, input.node.name),
+                    });
+                    found = true;
+                }
+            }
+            if found && input_device.is_none() {
+                input_device = Some(input.node.name.clone());
+            }
+        }
+        if indicators.is_empty() {
+            continue;
+        }
+
+        let vid_pid = format!("{}:{}", bridge.vid, bridge.pid);
+        let candidates = whitelist::usb_subjects(&vid_pid, Some(&bridge.vid), Some(&bridge.pid), &bridge.node.name);
+        if allowed.suppress(WhitelistType::UsbDevice, &candidates, &mut result.suppressed) {
+            continue;
+        }
+
+        let mut kinds: Vec<TopologyIndicatorKind> = indicators.iter().map(|i| i.kind).collect();
+        kinds.sort_by_key(|k| *k as u8);
+        kinds.dedup();
+        let risk_score = kinds.iter().map(|k| k.weight(*role)).sum::<u32>().min(100);
+        let correlated = kinds.len() >= 2 || (kinds == [TopologyIndicatorKind::SameDevice] && *role == UsbRole::Serial);
+        if correlated {
+            result.risk_score += risk_score;
+        }
+        result.matches.push(TopologyMatch {
+            device: DetectedDevice {
+                name: bridge.node.name.clone(),
+                device_type: format!("{} ({})", kind, chip),
+                vid: Some(bridge.vid.clone()),
+                pid: Some(bridge.pid.clone()),
+                hid_fingerprint: None,
+                topology: Some(topology_path(bridge.node, devices)),
+            },
+            input_device: input_device.unwrap_or_default(),
+            indicators,
+            risk_score,
+            correlated,
+        });
+    }
+
+    result.bridge_detected = result.matches.iter().any(|m| m.correlated);
+    if result.bridge_detected {
+        println!("[USB] {} serial bridge / NIC correlation(s) with input devices",
+                 result.matches.iter().filter(|m| m.correlated).count());
+    }
+    result
+}
+
+/// Group interface nodes under their device and resolve roles, hub and arrival
+fn physical_devices<'a>(devices: &'a [UsbDeviceInfo], first_seen: &HashMap<String, Option<u64>>) -> Vec<PhysicalDevice<'a>> {
+    let by_id: HashMap<&str, &UsbDeviceInfo> = devices.iter().map(|d| (d.device_id.as_str(), d)).collect();
+    let mut physical: Vec<PhysicalDevice> = Vec::new();
+
+    for device in devices {
+        let (vid, pid) = match rules::parse_usb_ids(&device.device_id) {
+            (Some(vid), Some(pid)) => (vid, pid),
+            _ => continue,
+        };
+        // Climb interface nodes (same VID/PID) up to the device node
+        let mut node = device;
+        while let Some(parent) = node.parent_id.as_deref().and_then(|id| by_id.get(id)) {
+            if rules::parse_usb_ids(&parent.device_id) != (Some(vid.clone()), Some(pid.clone())) {
+                break;
+            }
+            node = parent;
+        }
+
+        let index = match physical.iter().position(|p| std::ptr::eq(p.node, node)) {
+            Some(index) => index,
+            None => {
+                physical.push(PhysicalDevice {
+                    node,
+                    vid: vid.clone(),
+                    pid: pid.clone(),
+                    bridge: None,
+                    input: false,
+                    hub: node.parent_id.as_deref().and_then(|id| by_id.get(id)).copied(),
+                    first_seen: None,
+                });
+                physical.len() - 1
+            }
+        };
+        let entry = &mut physical[index];
+        for role in roles(device, &vid, &pid) {
+            match (role, &entry.bridge) {
+                (UsbRole::Input, _) => entry.input = true,
+                (_, None) | (UsbRole::Serial, Some((UsbRole::Network, _))) => entry.bridge = Some((role, chip_name(&vid, &pid))),
+                _ => {}
+            }
+        }
+        if let Some(Some(seen)) = first_seen.get(&device.device_id) {
+            entry.first_seen = Some(entry.first_seen.map_or(*seen, |s| s.min(*seen)));
+        }
+    }
+    physical
+}
+
+/// Roles of one device node from its ids, bound drivers and name
+pub fn roles(device: &UsbDeviceInfo, vid: &str, pid: &str) -> Vec<UsbRole> {
+    let name = device.name.to_lowercase();
+    let mut roles: Vec<UsbRole> = Vec::new();
+    let mut add = |role: UsbRole| {
+        if !roles.contains(&role) {
+            roles.push(role);
+        }
+    };
+
+    if SERIAL_BRIDGES.iter().any(|(v, p, _)| *v == vid && *p == pid) {
+        add(UsbRole::Serial);
+    }
+    if NETWORK_ADAPTERS.iter().any(|(v, p, _)| *v == vid && *p == pid) {
+        add(UsbRole::Network);
+    }
+    for (driver, role) in ROLE_DRIVERS {
+        if device.drivers.iter().any(|d| d == driver) {
+            add(*role);
+        }
+    }
+    for (keyword, role) in ROLE_KEYWORDS {
+        if name.contains(keyword) {
+            add(*role);
+        }
+    }
+    roles
+}
+
+/// Known chip, otherwise the VID:PID
+fn chip_name(vid: &str, pid: &str) -> String {
+    SERIAL_BRIDGES.iter().chain(NETWORK_ADAPTERS.iter())
+        .find(|(v, p, _)| *v == vid && *p == pid)
+        .map(|(_, _, chip)| chip.to_string())
+        .unwrap_or_else(|| format!("{}:{}", vid, pid))
+}
+
+/// Root hubs are shared by every device on the controller
+fn is_root_hub(device: &UsbDeviceInfo) -> bool {
+    device.device_id.to_uppercase().contains("ROOT_HUB") || device.device_id.to_uppercase().starts_with("USB\\VID_1D6B&")
+}
+
+/// `Root hub > Hub [1-1] > Device [1-1.2]` from the root down to the device
+pub fn topology_path(device: &UsbDeviceInfo, devices: &[UsbDeviceInfo]) -> String {
+    let mut chain = vec![device];
+    let mut current = device;
+    while let Some(parent) = current.parent_id.as_deref().and_then(|id| devices.iter().find(|d| d.device_id == id)) {
+        if chain.iter().any(|d| std::ptr::eq(*d, parent)) || chain.len() > 16 {
+            break;
+        }
+        chain.push(parent);
+        current = parent;
+    }
+
+    chain.iter().rev()
+        .map(|d| match &d.location {
+            Some(location) => format!("{} [{}]", d.name, location),
+            None => d.name.clone(),
+        })
+        .collect::<Vec<_>>()
+        .join(" > ")
+}
+
+/// Parent device id and port location of a PnP device
+#[cfg(target_os = "windows")]
+pub fn device_topology(device_id: &str) -> (Option<String>, Option<String>) {
+    use windows::core::PCWSTR;
+    use windows::Win32::Devices::DeviceAndDriverInstallation::{
+        CM_Get_DevNode_Registry_PropertyW, CM_Get_Device_IDW, CM_Get_Parent, CM_Locate_DevNodeW,
+        CM_DRP_LOCATION_INFORMATION, CM_LOCATE_DEVNODE_NORMAL, CR_SUCCESS,
+    };
+
+    let to_string = |buffer: &[u16]| {
+        let len = buffer.iter().position(|c| *c == 0).unwrap_or(buffer.len());
+        Some(String::from_utf16_lossy(&buffer[..len])).filter(|s| !s.is_empty())
+    };
+    let wide: Vec<u16> = device_id.encode_utf16().chain(std::iter::once(0)).collect();
+    let mut devinst = 0u32;
+
+    unsafe {
+        if CM_Locate_DevNodeW(&mut devinst, PCWSTR(wide.as_ptr()), CM_LOCATE_DEVNODE_NORMAL) != CR_SUCCESS {
+            return (None, None);
+        }
+
+        // "Port_#0002.Hub_#0003"
+        let mut buffer = [0u16; 256];
+        let mut length = (buffer.len() * 2) as u32;
+        let location = if CM_Get_DevNode_Registry_PropertyW(devinst, CM_DRP_LOCATION_INFORMATION, None,
+                                                            Some(buffer.as_mut_ptr() as *mut _), &mut length, 0) == CR_SUCCESS {
+            to_string(&buffer)
+        } else {
+            None
+        };
+
+        let mut parent = 0u32;
+        let mut buffer = [0u16; 512];
+        let parent_id = if CM_Get_Parent(&mut parent, devinst, 0) == CR_SUCCESS
+            && CM_Get_Device_IDW(parent, &mut buffer, 0) == CR_SUCCESS {
+            to_string(&buffer)
+        } else {
+            None
+        };
+
+        (parent_id, location)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::whitelist::WhitelistEntry;
+
+    fn usb(name: &str, vid_pid: (&str, &str), location: &str, parent: Option<&str>, drivers: &[&str]) -> UsbDeviceInfo {
+        UsbDeviceInfo {
+            name: name.to_string(),
+            device_id: format!("USB\\VID_{}&PID_{}\\{}", vid_pid.0, vid_pid.1, location),
+            drivers: drivers.iter().map(|d| d.to_string()).collect(),
+            parent_id: parent.map(|p| p.to_string()),
+            location: Some(location.to_string()),
+            ..Default::default()
+        }
+    }
+
+    /// Root hub > external hub [1-1] > the given devices on ports 1-1.1, 1-1.2...
+    fn behind_hub(devices: &[(&str, (&str, &str), &[&str])]) -> Vec<UsbDeviceInfo> {
+        let root = usb("Root hub", ("1D6B", "0002"), "usb1", None, &[]);
+        let hub = usb("USB hub", ("05E3", "0610"), "1-1", Some(&root.device_id), &["hub"]);
+        let mut all = vec![root, hub.clone()];
+        for (i, (name, ids, drivers)) in devices.iter().enumerate() {
+            all.push(usb(name, *ids, &format!("1-1.{}", i + 1), Some(&hub.device_id), drivers));
+        }
+        all
+    }
+
+    fn arrived(devices: &[UsbDeviceInfo], at: u64) -> HashMap<String, Option<u64>> {
+        devices.iter().map(|d| (d.device_id.clone(), Some(at))).collect()
+    }
+
+    fn kinds(m: &TopologyMatch) -> Vec<TopologyIndicatorKind> {
+        m.indicators.iter().map(|i| i.kind).collect()
+    }
+
+    #[test]
+    fn dock_nic_next_to_keyboard_is_ignored() {
+        let dock = behind_hub(&[("USB Ethernet", ("0BDA", "8153"), &["r8152"]), ("Keyboard", ("046D", "C31C"), &["usbhid"])]);
+        let result = correlate(&dock, &HashMap::new());
+        assert!(result.matches.is_empty());
+        assert!(!result.bridge_detected);
+    }
+
+    #[test]
+    fn nic_plugged_in_with_mouse_is_informational() {
+        let devices = behind_hub(&[("USB Ethernet", ("0BDA", "8153"), &["r8152"]), ("Mouse", ("046D", "C08B"), &["usbhid"])]);
+        let result = correlate(&devices, &arrived(&devices, 1_000));
+        assert_eq!(kinds(&result.matches[0]), vec![TopologyIndicatorKind::AppearedTogether]);
+        assert!(!result.matches[0].correlated);
+        assert!(!result.bridge_detected);
+        assert_eq!(result.risk_score, 0);
+    }
+
+    #[test]
+    fn serial_bridge_behind_hub_alone_is_informational() {
+        let devices = behind_hub(&[("USB-SERIAL CH340", ("1A86", "7523"), &["ch341"]), ("Mouse", ("046D", "C08B"), &["usbhid"])]);
+        let result = correlate(&devices, &HashMap::new());
+        assert_eq!(kinds(&result.matches[0]), vec![TopologyIndicatorKind::SameHub]);
+        assert!(!result.bridge_detected);
+    }
+
+    #[test]
+    fn serial_bridge_behind_hub_plugged_in_together_is_detected() {
+        let devices = behind_hub(&[("USB-SERIAL CH340", ("1A86", "7523"), &["ch341"]), ("Mouse", ("046D", "C08B"), &["usbhid"])]);
+        let result = correlate(&devices, &arrived(&devices, 1_000));
+        let m = &result.matches[0];
+        assert_eq!(kinds(m), vec![TopologyIndicatorKind::SameHub, TopologyIndicatorKind::AppearedTogether]);
+        assert!(m.correlated);
+        assert_eq!(m.risk_score, 70);
+        assert_eq!(m.input_device, "Mouse");
+        assert!(result.bridge_detected);
+    }
+
+    #[test]
+    fn serial_and_input_on_one_device_is_detected() {
+        let devices = behind_hub(&[("KMBox", ("1A86", "7523"), &["ch341", "usbhid"])]);
+        let result = correlate(&devices, &HashMap::new());
+        assert_eq!(kinds(&result.matches[0]), vec![TopologyIndicatorKind::SameDevice]);
+        assert_eq!(result.matches[0].risk_score, 100);
+        assert!(result.bridge_detected);
+    }
+
+    #[test]
+    fn nic_with_serial_role_counts_as_serial() {
+        let devices = behind_hub(&[("Gadget", ("0BDA", "8153"), &["r8152", "cdc_acm"]), ("Mouse", ("046D", "C08B"), &["usbhid"])]);
+        let result = correlate(&devices, &arrived(&devices, 1_000));
+        assert_eq!(kinds(&result.matches[0]), vec![TopologyIndicatorKind::SameHub, TopologyIndicatorKind::AppearedTogether]);
+        assert!(result.bridge_detected);
+    }
+
+    #[test]
+    fn arrivals_are_recorded_after_the_first_snapshot() {
+        let devices = behind_hub(&[("USB-SERIAL CH340", ("1A86", "7523"), &["ch341"]), ("Mouse", ("046D", "C08B"), &["usbhid"])]);
+        let hubs = &devices[..2];
+        let arrivals = Mutex::new(None);
+        let allowed = whitelist::current();
+
+        // Everything in the first snapshot was already there
+        let first = record_arrivals(&arrivals, hubs, 1_000);
+        assert_eq!(first.len(), 2);
+        assert!(first.values().all(|seen| seen.is_none()));
+
+        // Bridge and mouse arrive together under the existing hub
+        let result = detect_with(&arrivals, &devices, 5_000, &allowed);
+        assert!(result.bridge_detected);
+        assert_eq!(kinds(&result.matches[0]), vec![TopologyIndicatorKind::SameHub, TopologyIndicatorKind::AppearedTogether]);
+
+        // Arrival times are kept while the devices stay, and reset once they are unplugged
+        let later = record_arrivals(&arrivals, &devices, 9_000);
+        assert_eq!((later[&devices[0].device_id], later[&devices[2].device_id]), (None, Some(5_000)));
+        assert!(!detect_with(&arrivals, hubs, 12_000, &allowed).bridge_detected);
+        assert_eq!(record_arrivals(&arrivals, &devices, 20_000)[&devices[3].device_id], Some(20_000));
+    }
+
+    #[test]
+    fn topology_path_runs_from_the_root() {
+        let devices = behind_hub(&[("USB-SERIAL CH340", ("1A86", "7523"), &["ch341"])]);
+        assert_eq!(topology_path(&devices[2], &devices), "Root hub [usb1] > USB hub [1-1] > USB-SERIAL CH340 [1-1.1]");
+
+        // Unknown location and a parent loop
+        let mut a = usb("A", ("1A86", "7523"), "1-2", Some("USB\\VID_05E3&PID_0610\\B"), &[]);
+        let mut b = usb("B", ("05E3", "0610"), "1-3", Some(&a.device_id), &[]);
+        b.device_id = "USB\\VID_05E3&PID_0610\\B".to_string();
+        a.location = None;
+        let looped = [a, b];
+        assert_eq!(topology_path(&looped[0], &looped), "B [1-3] > A");
+    }
+
+    #[test]
+    fn whitelisted_bridges_are_suppressed() {
+        let devices = behind_hub(&[("USB-SERIAL CH340", ("1A86", "7523"), &["ch341"]), ("Mouse", ("046D", "C08B"), &["usbhid"])]);
+        let allowed = Whitelist::new(None, vec![WhitelistEntry {
+            id: "ch340".to_string(),
+            entry_type: "usb_device".to_string(),
+            identifier: "1a86:7523".to_string(),
+            secondary_identifier: None,
+            match_type: Some("usb".to_string()),
+        }]);
+        let result = correlate_with(&devices, &arrived(&devices, 1_000), &allowed);
+        assert!(result.matches.is_empty());
+        assert!(!result.bridge_detected);
+        assert_eq!(result.risk_score, 0);
+        assert_eq!((result.suppressed[0].entry_id.as_str(), result.suppressed[0].matched.as_str()), ("ch340", "1a86:7523"));
+    }
+}
@@ -0,0 +1,356 @@
+//! Whitelist module - server-managed suppressions (IrisWhitelist) applied by the detectors
+//!
+//! Active entries are fetched with ETag caching and swapped in at runtime. They are
+//! only kept in memory: a local copy could be edited to whitelist a cheat. Identifiers
+//! are case-insensitive literals unless the entry's `matchType` names one of the rule
+//! kinds of rules.rs (`glob`, `path`, ...). Each detector reports the server entries
+//! that suppressed one of its findings.
+
+use crate::rules::{MatchRule, Subject};
+use crate::api;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, RwLock};
+
+/// Our own files and windows (never flagged, not reported)
+const BUILTIN_SUPPRESSIONS: &[(WhitelistType, &str)] = &[
+    (WhitelistType::Dll, "glob:*iris-anticheat*"),
+    (WhitelistType::Dll, "glob:*iris_anticheat*"),
+    (WhitelistType::Dll, "iris.exe"),
+    (WhitelistType::Dll, "path:*iris-anticheat*"),
+    (WhitelistType::Dll, "path:*iris_anticheat*"),
+    (WhitelistType::Dll, "path:*\\appdata\\local\\iris\\*"),
+    (WhitelistType::Dll, "path:*\\.tauri\\*"),
+    (WhitelistType::CheatWindow, "glob:*iris-anticheat*"),
+    (WhitelistType::CheatWindow, "glob:*iris anticheat*"),
+    (WhitelistType::CheatWindow, "glob:*iris - nomercy*"),
+    (WhitelistType::CheatWindow, "glob:*nomercy anticheat*"),
+];
+
+/// Detection type of an entry (IrisWhitelist.type)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WhitelistType {
+    Driver,
+    Process,
+    Registry,
+    Macro,
+    Overlay,
+    Dll,
+    VpnAdapter,
+    VpnProcess,
+    UsbDevice,
+    CheatWindow,
+}
+
+impl WhitelistType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WhitelistType::Driver => "driver",
+            WhitelistType::Process => "process",
+            WhitelistType::Registry => "registry",
+            WhitelistType::Macro => "macro",
+            WhitelistType::Overlay => "overlay",
+            WhitelistType::Dll => "dll",
+            WhitelistType::VpnAdapter => "vpn_adapter",
+            WhitelistType::VpnProcess => "vpn_process",
+            WhitelistType::UsbDevice => "usb_device",
+            WhitelistType::CheatWindow => "cheat_window",
+        }
+    }
+}
+
+/// Entry as delivered by the API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WhitelistEntry {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub entry_type: String,
+    pub identifier: String,
+    pub secondary_identifier: Option<String>, // e.g. PID when the identifier is a VID
+    #[serde(default)]
+    pub match_type: Option<String>, // Rule kind of the identifier, exact when absent
+}
+
+/// Server entry that suppressed a finding
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Suppression {
+    pub entry_id: String,
+    pub entry_type: String,
+    pub identifier: String,
+    pub matched: String, // What the detector found
+}
+
+struct CompiledEntry {
+    entry: Option<WhitelistEntry>, // None for built-in suppressions
+    entry_type: &'static str,
+    rule: MatchRule,
+    secondary: Option<String>,
+}
+
+pub struct Whitelist {
+    etag: Option<String>,
+    server_entries: usize,
+    entries: Vec<CompiledEntry>,
+}
+
+impl Whitelist {
+    pub(crate) fn new(etag: Option<String>, server: Vec<WhitelistEntry>) -> Self {
+        let mut entries: Vec<CompiledEntry> = BUILTIN_SUPPRESSIONS.iter()
+            .map(|(kind, rule)| CompiledEntry {
+                entry: None,
+                entry_type: kind.as_str(),
+                rule: MatchRule::builtin(rule),
+                secondary: None,
+            })
+            .collect();
+
+        let mut server_entries = 0;
+        for entry in server {
+            let entry_type = match ALL_TYPES.iter().find(|t| t.as_str() == entry.entry_type) {
+                Some(kind) => kind.as_str(),
+                None => continue, // Type not handled by this client
+            };
+            match entry_rule(&entry) {
+                Ok(rule) => {
+                    server_entries += 1;
+                    entries.push(CompiledEntry {
+                        entry_type,
+                        rule,
+                        secondary: entry.secondary_identifier.clone().filter(|s| !s.trim().is_empty()),
+                        entry: Some(entry),
+                    });
+                }
+                Err(e) => println!("[Whitelist] Ignoring entry {}: {}", entry.id, e),
+            }
+        }
+
+        Whitelist { etag, server_entries, entries }
+    }
+
+    /// Whether any candidate is whitelisted for `kind`. Server entries that fire
+    /// are recorded once in `fired`.
+    pub fn suppress(&self, kind: WhitelistType, candidates: &[Subject], fired: &mut Vec<Suppression>) -> bool {
+        for compiled in self.entries.iter().filter(|e| e.entry_type == kind.as_str()) {
+            let hit = candidates.iter().find(|subject| {
+                compiled.rule.matches(subject) && match (&compiled.secondary, subject.pid) {
+                    (Some(secondary), Some(pid)) => secondary.eq_ignore_ascii_case(pid),
+                    _ => true,
+                }
+            });
+
+            if let Some(subject) = hit {
+                if let Some(entry) = &compiled.entry {
+                    let suppression = Suppression {
+                        entry_id: entry.id.clone(),
+                        entry_type: entry.entry_type.clone(),
+                        identifier: entry.identifier.clone(),
+                        matched: describe(subject),
+                    };
+                    if !fired.contains(&suppression) {
+                        fired.push(suppression);
+                    }
+                }
+                return true;
+            }
+        }
+        false
+    }
+}
+
+/// Rule of a server entry: the identifier is never parsed as a rule unless a match type says so
+fn entry_rule(entry: &WhitelistEntry) -> Result<MatchRule, String> {
+    match entry.match_type.as_deref() {
+        None | Some("exact") => Ok(MatchRule::literal(&entry.identifier)),
+        Some(kind @ ("prefix" | "glob" | "re" | "path" | "usb" | "sha256")) => {
+            MatchRule::parse(&format!("{}:{}", kind, entry.identifier.trim()))
+        }
+        Some(other) => Err(format!("Unknown match type '{}'", other)),
+    }
+}
+
+const ALL_TYPES: &[WhitelistType] = &[
+    WhitelistType::Driver, WhitelistType::Process, WhitelistType::Registry, WhitelistType::Macro,
+    WhitelistType::Overlay, WhitelistType::Dll, WhitelistType::VpnAdapter, WhitelistType::VpnProcess,
+    WhitelistType::UsbDevice, WhitelistType::CheatWindow,
+];
+
+fn describe(subject: &Subject) -> String {
+    match (subject.name, subject.path, subject.vid, subject.pid) {
+        ("", _, Some(vid), Some(pid)) => format!("{}:{}", vid, pid),
+        ("", Some(path), _, _) => path.to_string(),
+        (name, _, Some(_), Some(pid)) => format!("{}:{}", name, pid), // VID subject
+        (name, _, _, _) => name.to_string(),
+    }
+}
+
+/// Subjects for a USB device: "vid:pid", the VID alone (with PID as secondary, also
+/// matched by `usb:` rules) and its name
+pub fn usb_subjects<'a>(vid_pid: &'a str, vid: Option<&'a str>, pid: Option<&'a str>, name: &'a str) -> Vec<Subject<'a>> {
+    let mut subjects = Vec::new();
+    if !vid_pid.is_empty() {
+        subjects.push(Subject::name(vid_pid));
+    }
+    if let Some(vid) = vid {
+        subjects.push(Subject { name: vid, ..Subject::usb(Some(vid), pid) });
+    }
+    subjects.push(Subject::name(name));
+    subjects
+}
+
+lazy_static::lazy_static! {
+    static ref CURRENT: RwLock<Arc<Whitelist>> = RwLock::new(Arc::new(Whitelist::new(None, Vec::new())));
+}
+
+/// Whitelist currently used by the detectors
+pub fn current() -> Arc<Whitelist> {
+    CURRENT.read()
+        .map(|whitelist| whitelist.clone())
+        .unwrap_or_else(|_| Arc::new(Whitelist::new(None, Vec::new())))
+}
+
+/// Number of active server entries
+pub fn entry_count() -> usize {
+    current().server_entries
+}
+
+/// Fetch the whitelist if it changed since the last sync (ETag)
+pub async fn refresh(api_client: &api::IrisApiClient, token: &str) -> Result<usize, String> {
+    let etag = current().etag.clone();
+
+    let response = match api_client.get_whitelist(token, etag.as_deref()).await? {
+        Some(response) => response,
+        None => return Ok(entry_count()), // 304 Not Modified
+    };
+
+    let entries: Vec<WhitelistEntry> = response.entries.into_iter()
+        .filter_map(|value| serde_json::from_value(value).ok())
+        .collect();
+    let whitelist = Whitelist::new(response.etag, entries);
+    let count = whitelist.server_entries;
+    if let Ok(mut current) = CURRENT.write() {
+        *current = Arc::new(whitelist);
+    }
+
+    println!("[Whitelist] {} active entries", count);
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: &str, entry_type: &str, identifier: &str, secondary: Option<&str>, match_type: Option<&str>) -> WhitelistEntry {
+        WhitelistEntry {
+            id: id.to_string(),
+            entry_type: entry_type.to_string(),
+            identifier: identifier.to_string(),
+            secondary_identifier: secondary.map(str::to_string),
+            match_type: match_type.map(str::to_string),
+        }
+    }
+
+    fn whitelist(entries: Vec<WhitelistEntry>) -> Whitelist {
+        Whitelist::new(None, entries)
+    }
+
+    #[test]
+    fn identifiers_are_literals_by_default() {
+        let list = whitelist(vec![
+            entry("1", "process", "glob:*", None, None),
+            entry("2", "process", "Tool !glob:*.exe", None, Some("exact")),
+            entry("3", "process", "re:(", None, None), // Would not even parse as a rule
+        ]);
+        assert_eq!(list.server_entries, 3);
+
+        let mut fired = Vec::new();
+        assert!(!list.suppress(WhitelistType::Process, &[Subject::name("cheat.exe")], &mut fired));
+        assert!(list.suppress(WhitelistType::Process, &[Subject::name("GLOB:*")], &mut fired));
+        assert!(list.suppress(WhitelistType::Process, &[Subject::name("tool !glob:*.exe")], &mut fired));
+        assert!(list.suppress(WhitelistType::Process, &[Subject::name("re:(")], &mut fired));
+        assert_eq!(fired.iter().map(|s| s.entry_id.as_str()).collect::<Vec<_>>(), ["1", "2", "3"]);
+    }
+
+    #[test]
+    fn explicit_match_types() {
+        let list = whitelist(vec![
+            entry("1", "overlay", "*discord*", None, Some("glob")),
+            entry("2", "dll", "*\\steam\\*", None, Some("path")),
+            entry("3", "driver", "^vbox", None, Some("re")),
+            entry("4", "macro", "not a vid", None, Some("usb")), // Invalid: ignored
+            entry("5", "macro", "logi", None, Some("wildcard")), // Unknown match type: ignored
+        ]);
+        assert_eq!(list.server_entries, 3);
+
+        let mut fired = Vec::new();
+        assert!(list.suppress(WhitelistType::Overlay, &[Subject::name("Discord.exe")], &mut fired));
+        assert!(list.suppress(WhitelistType::Dll, &[Subject::file("gameoverlay.dll", Some("C:/Program Files/Steam/gameoverlay.dll"))], &mut fired));
+        assert!(list.suppress(WhitelistType::Driver, &[Subject::name("VBoxSup")], &mut fired));
+        assert!(!list.suppress(WhitelistType::Macro, &[Subject::name("logi")], &mut fired));
+        assert_eq!(fired[1].matched, "gameoverlay.dll");
+    }
+
+    #[test]
+    fn usb_primary_and_secondary_identifiers() {
+        let list = whitelist(vec![
+            entry("vidpid", "usb_device", "046d:c077", None, None),
+            entry("vid", "usb_device", "2341", Some("8036"), None),
+            entry("name", "usb_device", "Xbox Controller", None, None),
+            entry("rule", "usb_device", "1a86:7523", None, Some("usb")),
+        ]);
+
+        // VID:PID as primary identifier
+        let mut fired = Vec::new();
+        assert!(list.suppress(WhitelistType::UsbDevice, &usb_subjects("046D:C077", Some("046d"), Some("c077"), "USB mouse"), &mut fired));
+        assert_eq!(fired, [Suppression {
+            entry_id: "vidpid".to_string(),
+            entry_type: "usb_device".to_string(),
+            identifier: "046d:c077".to_string(),
+            matched: "046D:C077".to_string(),
+        }]);
+
+        // VID with the PID as secondary identifier
+        let mut fired = Vec::new();
+        assert!(list.suppress(WhitelistType::UsbDevice, &usb_subjects("2341:8036", Some("2341"), Some("8036"), "Leonardo"), &mut fired));
+        assert_eq!((fired[0].entry_id.as_str(), fired[0].matched.as_str()), ("vid", "2341:8036"));
+        assert!(!list.suppress(WhitelistType::UsbDevice, &usb_subjects("2341:0043", Some("2341"), Some("0043"), "Uno"), &mut fired));
+        assert_eq!(fired.len(), 1);
+
+        // Device name
+        assert!(list.suppress(WhitelistType::UsbDevice, &usb_subjects("", None, None, "xbox controller"), &mut fired));
+        assert_eq!(fired[1].entry_id, "name");
+
+        // usb: match type
+        assert!(list.suppress(WhitelistType::UsbDevice, &usb_subjects("1A86:7523", Some("1A86"), Some("7523"), "CH340"), &mut fired));
+        assert_eq!((fired[2].entry_id.as_str(), fired[2].matched.as_str()), ("rule", "1A86:7523"));
+        assert!(!list.suppress(WhitelistType::UsbDevice, &usb_subjects("1A86:55D4", Some("1A86"), Some("55D4"), "CH9102"), &mut fired));
+    }
+
+    #[test]
+    fn entries_only_apply_to_their_type() {
+        let list = whitelist(vec![
+            entry("1", "vpn_adapter", "WireGuard Tunnel", None, None),
+            entry("2", "anti_tamper", "WireGuard Tunnel", None, None), // Type unknown to this client
+        ]);
+        assert_eq!(list.server_entries, 1);
+
+        let mut fired = Vec::new();
+        let subjects = [Subject::name("WireGuard Tunnel")];
+        assert!(!list.suppress(WhitelistType::VpnProcess, &subjects, &mut fired));
+        assert!(!list.suppress(WhitelistType::Process, &subjects, &mut fired));
+        assert!(list.suppress(WhitelistType::VpnAdapter, &subjects, &mut fired));
+        assert!(list.suppress(WhitelistType::VpnAdapter, &subjects, &mut fired));
+        assert_eq!(fired.len(), 1); // Recorded once
+    }
+
+    #[test]
+    fn builtin_suppressions_are_not_reported() {
+        let list = whitelist(Vec::new());
+        let mut fired = Vec::new();
+        assert!(list.suppress(WhitelistType::Dll, &[Subject::file("x.dll", Some("C:\\Users\\Bob\\AppData\\Local\\Iris\\x.dll"))], &mut fired));
+        assert!(list.suppress(WhitelistType::CheatWindow, &[Subject::name("Iris - NoMercy")], &mut fired));
+        assert!(!list.suppress(WhitelistType::Process, &[Subject::name("iris.exe")], &mut fired));
+        assert!(fired.is_empty());
+        assert_eq!(list.server_entries, 0);
+    }
+}
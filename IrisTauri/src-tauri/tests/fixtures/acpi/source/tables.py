@@ -0,0 +1,90 @@
+#!/usr/bin/env python3
+"""Writes the DMAR and IVRS fixtures of the acpi tests.
+
+    dmar.bin  VT-d: two DRHDs (the second INCLUDE_PCI_ALL), an RMRR and an ATSR,
+              interrupt remapping and DMA_CTRL_PLATFORM_OPT_IN set
+    ivrs.bin  AMD-Vi: one IOMMU listed as IVHD 0x10, 0x11 and 0x40, a second one
+              as IVHD 0x40 only (with an ACPI HID entry), an IVMD, IVinfo DMA remap set
+
+The same bytes are inlined in tests/fixtures/sysfs/iommu-*.txt.
+"""
+import struct
+import sys
+
+
+def table(signature, body):
+    """ACPI header + body with a valid checksum"""
+    data = bytearray(signature + struct.pack("<IBB6s8sI4sI", 36 + len(body), 1, 0, b"IRIS  ", b"FIXTURE ",
+                                             1, b"IRIS", 1) + body)
+    data[9] = -sum(data) & 0xFF
+    return bytes(data)
+
+
+# ====== DMAR ======
+
+def scope(kind, bus, *path, enumeration=0):
+    hops = b"".join(bytes(hop) for hop in path)
+    return struct.pack("<BBHBB", kind, 6 + len(hops), 0, enumeration, bus) + hops
+
+
+def drhd(flags, base, *scopes):
+    body = b"".join(scopes)
+    return struct.pack("<HHBBHQ", 0, 16 + len(body), flags, 0, 0, base) + body
+
+
+def dmar():
+    body = struct.pack("<BB10x", 38, 0x05)  # 39-bit addresses, INTR_REMAP | DMA_CTRL_PLATFORM_OPT_IN
+    body += drhd(0, 0xFED90000, scope(1, 0, (0x02, 0)))                # Integrated graphics
+    body += drhd(1, 0xFED91000,
+                 scope(3, 0, (0x1E, 7), enumeration=2),                 # IOAPIC
+                 scope(4, 0, (0x1E, 6)),                                # HPET
+                 scope(2, 0, (0x1C, 0), (0x00, 0)))                     # Bridge behind a root port
+    rmrr_scope = scope(1, 0, (0x14, 0))
+    body += struct.pack("<HHHHQQ", 1, 24 + len(rmrr_scope), 0, 0, 0x7A000000, 0x7A3FFFFF) + rmrr_scope
+    body += struct.pack("<HHBBH", 2, 8, 0, 0, 0)                        # ATSR
+    return table(b"DMAR", body)
+
+
+# ====== IVRS ======
+
+def ivhd(kind, device_id, base, entries):
+    header = struct.pack("<BBHHHQHHI", kind, 0xB0, 0, device_id, 0x40, base, 0, 0, 0)
+    if kind != 0x10:
+        header += bytes(16)  # EFR register image
+    header = header[:2] + struct.pack("<H", len(header) + len(entries)) + header[4:]
+    return header + entries
+
+
+def entry(kind, device_id, data=0):
+    return struct.pack("<BHB", kind, device_id, data)
+
+
+def special(variety, handle, source):
+    return struct.pack("<BHBBHB", 0x48, 0, 0xD7, handle, source, variety)
+
+
+def acpi_hid(device_id, hid, uid):
+    return struct.pack("<BHB8s8sBB", 0xF0, device_id, 0, hid, bytes(8), 2, len(uid)) + uid
+
+
+def ivrs():
+    body = struct.pack("<I8x", (48 << 8) | (64 << 15) | 0x02)  # 48-bit PA, 64-bit VA, DMA remap support
+    first = (entry(0x01, 0)                                     # All devices
+             + entry(0x02, 0x0008)                              # 00:01.0
+             + entry(0x03, 0x0100) + entry(0x04, 0x01FF)        # Bus 01
+             + special(1, 0x21, 0x00A0)                         # IOAPIC
+             + special(2, 0x00, 0x00A0))                        # HPET
+    body += ivhd(0x10, 0x0002, 0xFEB80000, first)
+    body += ivhd(0x11, 0x0002, 0xFEB80000, first + entry(0x02, 0x0010))
+    body += ivhd(0x40, 0x0002, 0xFEB80000, first + entry(0x02, 0x0010))
+    second = entry(0x02, 0x4100) + acpi_hid(0x00A5, b"AMDI0020", b"ID00") + entry(0x02, 0x4200)
+    body += ivhd(0x40, 0x4002, 0xFEB81000, second)
+    body += struct.pack("<BBHHHQQQ", 0x21, 0x08, 32, 0x0008, 0, 0, 0x9D000000, 0x100000)  # IVMD
+    return table(b"IVRS", body)
+
+
+if __name__ == "__main__":
+    out = sys.argv[1] if len(sys.argv) > 1 else ".."
+    for name, data in (("dmar.bin", dmar()), ("ivrs.bin", ivrs())):
+        open(f"{out}/{name}", "wb").write(data)
+        print(name, data.hex())
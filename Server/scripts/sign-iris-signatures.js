@@ -0,0 +1,78 @@
+/**
+ * Script to sign an Iris signature bundle
+ * Run with: node scripts/sign-iris-signatures.js <bundle.json> <ed25519-private-key.pem>
+ * Print the client public key: node scripts/sign-iris-signatures.js --public-key <ed25519-private-key.pem>
+ *
+ * The bundle must contain a numeric "version" higher than the one currently deployed.
+ * Output is written to iris-signatures/signatures.signed.json (served by GET /api/iris/signatures).
+ * Keep the private key offline - the matching public key is embedded in the Iris client
+ * at build time. Key generation and rotation: scripts/iris-signing-key.md
+ */
+
+import crypto from 'crypto';
+import fs from 'fs';
+import path from 'path';
+import { fileURLToPath } from 'url';
+
+const __filename = fileURLToPath(import.meta.url);
+const __dirname = path.dirname(__filename);
+
+// Raw 32-byte Ed25519 public key as hex (the value of IRIS_BUNDLE_PUBLIC_KEY)
+function printPublicKey(keyPath) {
+  try {
+    const privateKey = crypto.createPrivateKey(fs.readFileSync(keyPath));
+    if (privateKey.asymmetricKeyType !== 'ed25519') {
+      throw new Error(`Expected an Ed25519 key, got ${privateKey.asymmetricKeyType}`);
+    }
+    const spki = crypto.createPublicKey(privateKey).export({ format: 'der', type: 'spki' });
+    console.log(spki.subarray(spki.length - 32).toString('hex'));
+    process.exit(0);
+  } catch (error) {
+    console.error('Error:', error);
+    process.exit(1);
+  }
+}
+
+function signBundle() {
+  const [bundlePath, keyPath] = process.argv.slice(2);
+
+  if (!bundlePath || !keyPath) {
+    console.error('Usage: node scripts/sign-iris-signatures.js <bundle.json> <private-key.pem>');
+    process.exit(1);
+  }
+
+  try {
+    const bundle = JSON.parse(fs.readFileSync(bundlePath, 'utf8'));
+    if (!Number.isInteger(bundle.version) || bundle.version <= 0) {
+      throw new Error('Bundle "version" must be a positive integer');
+    }
+
+    // Sign the exact bytes the client will verify
+    const payload = Buffer.from(JSON.stringify(bundle), 'utf8');
+    const privateKey = crypto.createPrivateKey(fs.readFileSync(keyPath));
+    const signature = crypto.sign(null, payload, privateKey);
+
+    const signed = {
+      version: bundle.version,
+      payload: payload.toString('base64'),
+      signature: signature.toString('base64')
+    };
+
+    const outputDir = path.join(__dirname, '../iris-signatures');
+    fs.mkdirSync(outputDir, { recursive: true });
+    const outputPath = path.join(outputDir, 'signatures.signed.json');
+    fs.writeFileSync(outputPath, JSON.stringify(signed, null, 2));
+
+    console.log(`Signed bundle v${bundle.version} written to ${outputPath}`);
+    process.exit(0);
+  } catch (error) {
+    console.error('Error:', error);
+    process.exit(1);
+  }
+}
+
+if (process.argv[2] === '--public-key') {
+  printPublicKey(process.argv[3]);
+} else {
+  signBundle();
+}
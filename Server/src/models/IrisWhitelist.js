@@ -47,6 +47,14 @@ const irisWhitelistSchema = new mongoose.Schema({
     trim: true
   },
 
+  // How the client compares the identifier: 'exact' is a case-insensitive literal,
+  // the others are the Iris rule kinds ('glob' and 'path' wildcards, 're' regex...)
+  matchType: {
+    type: String,
+    enum: ['exact', 'prefix', 'glob', 're', 'path', 'usb', 'sha256'],
+    default: 'exact'
+  },
+
   // Display name for the whitelist entry (human-readable)
   displayName: {
     type: String,
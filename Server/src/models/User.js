@@ -358,6 +358,75 @@ const userSchema = new mongoose.Schema({
     type: Date,
     default: null
   },
+  // Iris Anticheat - hardware id of the pre-v2 client, kept so the machine stays matched
+  irisLegacyHardwareId: {
+    type: String,
+    default: null,
+    sparse: true,
+    index: true
+  },
+  // Iris Anticheat - hashed hardware components behind irisHardwareId
+  irisHardwareFingerprint: {
+    version: Number,
+    components: mongoose.Schema.Types.Mixed, // Component name -> hashed values
+    changedComponents: [String], // Components that differ from the previous registration
+    previousMatchScore: Number,
+    updatedAt: Date
+  },
+  // Iris Anticheat - churn of the fingerprint components recorded by the client journal
+  irisHardwareChurn: {
+    trackingSince: Date,
+    sessions: Number,
+    journalReset: Boolean,
+    changedNow: [String],
+    components: [{
+      component: String,
+      changes: Number,
+      recentChanges: Number,
+      reverts: Number,
+      distinctValues: Number,
+      lastChange: Date
+    }],
+    verdict: { type: String, enum: ['stable', 'upgrade', 'reset', 'spoofing'] },
+    reasons: [String],
+    updatedAt: Date
+  },
+  // Iris Anticheat - TPM attestation key bound to the hardware id
+  irisAttestation: {
+    akPublic: String,
+    akName: {
+      type: String,
+      sparse: true,
+      index: true
+    },
+    ekPublic: String,
+    ekCertificates: [{
+      subject: String,
+      issuer: String,
+      serialNumber: String,
+      fingerprint256: String,
+      validTo: Date,
+      publicKeyMatches: Boolean
+    }],
+    ekChainLinked: Boolean,
+    ekCertified: Boolean, // ECC EK certificate chains to a manufacturer root
+    ekRoot: String,
+    ekBound: Boolean, // AK and EK proven on the same TPM by credential activation
+    ekBoundAt: Date,
+    ekBindingError: String,
+    hardwareId: String,
+    registeredAt: Date,
+    missedQuotes: { type: Number, default: 0 }, // Nonces left unanswered while connected
+    lastQuote: {
+      verified: Boolean,
+      reason: String,
+      pcrs: mongoose.Schema.Types.Mixed,
+      resetCount: Number,
+      restartCount: Number,
+      hardwareId: String,
+      verifiedAt: Date
+    }
+  },
   irisSecurityStatus: {
     tpm: {
       present: Boolean,
@@ -365,10 +434,39 @@ const userSchema = new mongoose.Schema({
       version: String
     },
     secureBoot: Boolean,
+    secureBootSetupMode: Number, // Raw EFI SetupMode: 1 = firmware accepts custom keys
+    legacyBios: Boolean,
+    // Secure Boot key databases: a custom or test platform key can sign any bootloader
+    secureBootKeys: {
+      class: String, // standard, custom, test_key, no_platform_key
+      reasons: [String],
+      pk: [{ subject: String, issuer: String, sha256: String, owner: String }],
+      kek: [{ subject: String, issuer: String, sha256: String, owner: String }],
+      db: [{ subject: String, issuer: String, sha256: String, owner: String }],
+      dbHashes: Number,
+      dbxCount: Number,
+      microsoftKek: Boolean
+    },
     virtualization: Boolean,
     virtualizationType: String,
     iommu: Boolean,
     kernelDmaProtection: Boolean, // Critical: IOMMU enforced at OS level to block DMA cheats
+    // ACPI DMAR (Intel VT-d) / IVRS (AMD-Vi) table behind iommu and kernelDmaProtection
+    iommuReport: {
+      table: String,
+      oemId: String,
+      addressWidth: Number,
+      remappingUnits: [{ segment: Number, registerBase: String, includeAll: Boolean, deviceId: String, scopes: Number }],
+      deviceScopes: [{ unit: Number, kind: String, device: String }],
+      reservedRegions: Number,
+      interruptRemapping: Boolean,
+      x2apicOptOut: Boolean,
+      dmaPlatformOptIn: Boolean,
+      activeUnits: Number,
+      kernelDmaProtection: Boolean,
+      error: String,
+      reasons: [String]
+    },
     hvci: Boolean,
     vbs: Boolean,
     defender: Boolean,
@@ -377,12 +475,19 @@ const userSchema = new mongoose.Schema({
     processes: [{
       name: String,
       pid: Number,
-      path: String
+      path: String,
+      cmdline: String,
+      ppid: Number,
+      uid: Number,
+      start_time: Number,
+      wine: Boolean
     }],
     usbDevices: [{
       name: String,
       device_id: String,
-      manufacturer: String
+      manufacturer: String,
+      serial: String,
+      drivers: [String]
     }],
     cheatDetection: {
       found: { type: Boolean, default: false },
@@ -393,6 +498,7 @@ const userSchema = new mongoose.Schema({
         name: String,
         vid: String,
         pid: String,
+        hidFingerprint: String, // Report-descriptor fingerprint of HID injector detections
         manufacturer: String,
         severity: { type: String, enum: ['low', 'medium', 'high', 'critical'], default: 'critical' }
       }],
@@ -400,7 +506,8 @@ const userSchema = new mongoose.Schema({
         name: String,
         pid: Number,
         path: String,
-        matchedCheat: String
+        matchedCheat: String,
+        peInfo: mongoose.Schema.Types.Mixed // PE headers of the image (version info, imports, sections)
       }],
       suspiciousUsb: [{
         name: String,
@@ -446,6 +553,58 @@ const userSchema = new mongoose.Schema({
       }],
       riskScore: { type: Number, default: 0 }
     },
+    // HWID consistency: the same identifier read from independent sources (SMBIOS, WMI, registry, drivers)
+    hwidConsistency: {
+      spoofingDetected: { type: Boolean, default: false },
+      identifiersChecked: { type: Number, default: 0 },
+      indicators: [{
+        identifier: String,
+        kind: { type: String, enum: ['contradiction', 'placeholder', 'address_override'] },
+        detail: String
+      }],
+      riskScore: { type: Number, default: 0 }
+    },
+    // DMA devices: PCIe FPGA cards (PCILeech, Squirrel, Screamer) found in the PCI inventory
+    dmaDeviceDetection: {
+      dmaDeviceDetected: { type: Boolean, default: false },
+      devicesScanned: { type: Number, default: 0 },
+      suspiciousDevices: [{
+        location: String,
+        name: String,
+        ids: String,
+        subsystem: String,
+        classCode: String,
+        driver: String,
+        indicators: [{
+          kind: { type: String, enum: ['dma_card_id', 'fpga_vendor', 'class_mismatch', 'subsystem_mismatch', 'implausible_config', 'driver_missing'] },
+          detail: String
+        }],
+        riskScore: Number
+      }],
+      riskScore: { type: Number, default: 0 }
+    },
+    // USB topology: serial bridges / USB NICs correlated with input devices (KMBox, Cronus passthrough)
+    usbTopology: {
+      bridgeDetected: { type: Boolean, default: false },
+      devicesScanned: { type: Number, default: 0 },
+      matches: [{
+        device: {
+          name: String,
+          deviceType: String,
+          vid: String,
+          pid: String,
+          topology: String
+        },
+        inputDevice: String,
+        indicators: [{
+          kind: { type: String, enum: ['same_device', 'same_hub', 'appeared_together'] },
+          detail: String
+        }],
+        riskScore: Number,
+        correlated: Boolean
+      }],
+      riskScore: { type: Number, default: 0 }
+    },
     // Driver Integrity (suspicious kernel drivers)
     driverIntegrity: {
       suspiciousFound: { type: Boolean, default: false },
@@ -453,7 +612,8 @@ const userSchema = new mongoose.Schema({
         name: String,
         displayName: String,
         path: String,
-        reason: String
+        reason: String,
+        signature: mongoose.Schema.Types.Mixed
       }],
       riskScore: { type: Number, default: 0 }
     },
@@ -484,7 +644,9 @@ const userSchema = new mongoose.Schema({
       suspiciousDlls: [{
         name: String,
         path: String,
-        reason: String
+        reason: String,
+        peInfo: mongoose.Schema.Types.Mixed,
+        signature: mongoose.Schema.Types.Mixed
       }],
       riskScore: { type: Number, default: 0 }
     },
@@ -502,7 +664,81 @@ const userSchema = new mongoose.Schema({
       cloudIndicators: [String],
       isGamingCloud: { type: Boolean, default: false },
       riskScore: { type: Number, default: 0 }
-    }
+    },
+    // Hash Reputation (SHA-256 of running executables)
+    hashReputation: {
+      badFound: { type: Boolean, default: false },
+      knownBad: [{
+        name: String,
+        pid: Number,
+        path: String,
+        sha256: String,
+        matchedName: String
+      }],
+      knownGoodCount: { type: Number, default: 0 },
+      unknownCount: { type: Number, default: 0 },
+      riskScore: { type: Number, default: 0 }
+    },
+    // Byte pattern (YARA-style) scan of executables on disk
+    bytePatternScan: {
+      matchesFound: { type: Boolean, default: false },
+      matches: [{
+        path: String,
+        source: String,
+        processName: String,
+        pid: Number,
+        rule: String,
+        description: String,
+        strings: [String],
+        risk: Number
+      }],
+      ruleCount: { type: Number, default: 0 },
+      pendingCount: { type: Number, default: 0 },
+      riskScore: { type: Number, default: 0 }
+    },
+    // TPM event log replay: what booted, Secure Boot as measured, debug/test-signing flags
+    measuredBoot: {
+      verdict: { type: String, enum: ['trusted', 'insecure', 'unverified', 'unavailable'], default: 'unavailable' },
+      reasons: [String],
+      source: String,
+      error: String,
+      cryptoAgile: Boolean,
+      algorithms: [String],
+      eventCount: Number,
+      pcrs: [{ index: Number, algorithm: String, digest: String }],
+      tpmVerified: Boolean,
+      pcrMismatches: [Number],
+      components: [{ pcr: Number, kind: String, path: String, digest: String }],
+      secureBoot: Boolean,
+      secureBootVariables: [String],
+      authorities: [String],
+      indicators: [String]
+    },
+    // Weighted risk score computed by the client risk engine, with one explanation per finding
+    riskAssessment: {
+      score: { type: Number, default: 0 },
+      level: { type: String, enum: ['low', 'medium', 'high', 'critical'], default: 'low' },
+      configVersion: { type: Number, default: 0 },
+      findings: [{
+        kind: String,
+        subject: String,
+        detail: String,
+        base: Number,
+        weight: Number,
+        decay: Number,
+        lastSeenSecs: Number,
+        points: Number,
+        explanation: String
+      }]
+    },
+    // Whitelist entries that suppressed a client-side finding
+    whitelistSuppressions: [{
+      detector: String,
+      entryId: String,
+      entryType: String,
+      identifier: String,
+      matched: String
+    }]
   },
   
   // Iris client verification
@@ -17,6 +17,8 @@ import IrisWhitelist from '../models/IrisWhitelist.js';
 import { verifyToken } from '../middleware/auth.middleware.js';
 import { verifyIrisSignature, decryptIrisPayload } from '../middleware/iris.security.middleware.js';
 import { createIrisScanChannel, sendIrisConnectionStatus, logIrisConnectionStatus, alertIrisMatchDisconnected, sendIrisShadowBan, sendIrisSecurityWarning, sendIrisSecurityChange, sendIrisScreenshots, deleteIrisScanModeChannel, sendIrisExtendedAlert, sendIrisGameMismatchAlert, sendIrisLowActivityAlert, sendIrisUpdateNotification } from '../services/discordBot.service.js';
+import { verifyIdentity, verifyQuote, createNonce, makeCredential, verifyActivation, DEFAULT_QUOTE_PCRS } from '../services/irisAttestation.service.js';
+import { sanitizeFingerprint, compareFingerprints, candidateFilter, sanitizeChurnReport, churnVerdict, SAME_MACHINE_SCORE } from '../services/irisFingerprint.service.js';
 import fetch from 'node-fetch';
 
 const router = express.Router();
@@ -64,10 +66,28 @@ const pendingChallenges = new Map();
 const pendingAuthSessions = new Map();
 const CHALLENGE_EXPIRY_MS = 60 * 1000; // 1 minute
 
+// Pending TPM quote nonces and EK credentials by user id (in production, use Redis)
+const pendingAttestations = new Map();
+const pendingActivations = new Map();
+const ATTESTATION_EXPIRY_MS = 5 * 60 * 1000; // 5 minutes
+const ATTESTATION_INTERVAL_MS = 10 * 60 * 1000; // New quote every 10 minutes
+
 // Verified sessions (in production, use Redis)
 const verifiedSessions = new Map();
 const SESSION_EXPIRY_MS = 30 * 60 * 1000; // 30 minutes
 
+/**
+ * Record a TPM challenge that expired unanswered. Only connected clients are
+ * flagged: one that keeps sending heartbeats without the answer cannot produce it.
+ */
+const recordUnansweredChallenge = (userId, update, what) => {
+  User.findOneAndUpdate({ _id: userId, irisWasConnected: true }, update)
+    .then((user) => {
+      if (user) console.warn('[Iris] Unanswered', what, 'from', user.username);
+    })
+    .catch((err) => console.error('[Iris] Error recording unanswered', what + ':', err.message));
+};
+
 // Cleanup expired challenges/sessions periodically
 setInterval(() => {
   const now = Date.now();
@@ -77,6 +97,32 @@ setInterval(() => {
   for (const [key, data] of verifiedSessions.entries()) {
     if (now > data.expiresAt) verifiedSessions.delete(key);
   }
+  for (const [key, data] of pendingAttestations.entries()) {
+    if (now > data.expiresAt) {
+      pendingAttestations.delete(key);
+      recordUnansweredChallenge(key, {
+        'irisAttestation.lastQuote': {
+          verified: false,
+          reason: 'Nonce not answered',
+          pcrs: {},
+          resetCount: null,
+          restartCount: null,
+          hardwareId: data.hardwareId,
+          verifiedAt: new Date()
+        },
+        $inc: { 'irisAttestation.missedQuotes': 1 }
+      }, 'TPM quote nonce');
+    }
+  }
+  for (const [key, data] of pendingActivations.entries()) {
+    if (now > data.expiresAt) {
+      pendingActivations.delete(key);
+      recordUnansweredChallenge(key, {
+        'irisAttestation.ekBound': false,
+        'irisAttestation.ekBindingError': 'Credential not activated'
+      }, 'EK credential');
+    }
+  }
 }, 60 * 1000);
 
 // Background job to detect Iris disconnections and send notifications
@@ -1156,7 +1202,9 @@ router.post('/register-hardware', verifyIrisSignature, decryptIrisPayload, async
       });
     }
 
-    const { hardwareId, systemInfo } = req.body;
+    const { hardwareId, systemInfo, attestation } = req.body;
+    const fingerprint = sanitizeFingerprint(req.body.fingerprint);
+    const churn = sanitizeChurnReport(req.body.churn);
 
     if (!hardwareId) {
       return res.status(400).json({
@@ -1165,9 +1213,13 @@ router.post('/register-hardware', verifyIrisSignature, decryptIrisPayload, async
       });
     }
 
-    // Check if hardware ID is already registered to another user
-    const existingUser = await User.findOne({ 
-      irisHardwareId: hardwareId,
+    // Check if hardware ID is already registered to another user (also under its pre-v2 id)
+    const legacyId = fingerprint?.legacyId;
+    const existingUser = await User.findOne({
+      $or: [
+        { irisHardwareId: { $in: legacyId ? [hardwareId, legacyId] : [hardwareId] } },
+        ...(legacyId ? [{ irisLegacyHardwareId: legacyId }] : [])
+      ],
       _id: { $ne: user._id }
     });
 
@@ -1178,19 +1230,127 @@ router.post('/register-hardware', verifyIrisSignature, decryptIrisPayload, async
       });
     }
 
+    // Fingerprint breakdown: the same machine with one changed part is still the same machine
+    let irisHardwareFingerprint = null;
+    if (fingerprint) {
+      const filter = candidateFilter(fingerprint);
+      const candidates = filter
+        ? await User.find({ ...filter, _id: { $ne: user._id } }).select('username irisHardwareFingerprint').limit(50)
+        : [];
+      for (const candidate of candidates) {
+        const match = compareFingerprints(fingerprint, candidate.irisHardwareFingerprint);
+        if (match.score >= SAME_MACHINE_SCORE) {
+          console.log('[Iris] Fingerprint of', user.username, 'matches', candidate.username, '- components:', match.matched.join(', '));
+          return res.status(403).json({
+            success: false,
+            message: 'This machine is already registered to another account'
+          });
+        }
+      }
+
+      const previous = compareFingerprints(fingerprint, user.irisHardwareFingerprint);
+      if (legacyId && user.irisHardwareId === legacyId) {
+        console.log('[Iris] Hardware id of', user.username, 'migrated from legacy id', legacyId);
+      } else if (user.irisHardwareId && user.irisHardwareId !== hardwareId && user.irisHardwareFingerprint) {
+        console.log('[Iris] Hardware id of', user.username, 'changed - changed components:', previous.changed.join(', ') || 'none',
+          '| score:', previous.score);
+      }
+      irisHardwareFingerprint = {
+        ...fingerprint,
+        changedComponents: previous.changed,
+        previousMatchScore: user.irisHardwareFingerprint ? previous.score : null,
+        updatedAt: new Date()
+      };
+    }
+
+    // Local component history: a one-off upgrade vs serials that change every session
+    let irisHardwareChurn = null;
+    if (churn) {
+      irisHardwareChurn = { ...churn, ...churnVerdict(churn, irisHardwareFingerprint?.changedComponents), updatedAt: new Date() };
+      if (irisHardwareChurn.verdict === 'spoofing') {
+        console.warn('[Iris] Hardware churn of', user.username, 'looks like spoofing:', irisHardwareChurn.reasons.join('; '));
+      }
+    }
+
+    // TPM attestation key: the same silicon cannot back two accounts
+    let irisAttestation = null;
+    let attestationCredential = null;
+    let pendingActivation = null;
+    if (attestation) {
+      try {
+        irisAttestation = { ...verifyIdentity(attestation), hardwareId };
+      } catch (error) {
+        console.log('[Iris] Invalid attestation identity for', user.username, '-', error.message);
+        return res.status(400).json({
+          success: false,
+          message: 'Invalid attestation key'
+        });
+      }
+
+      const akOwner = await User.findOne({
+        'irisAttestation.akName': irisAttestation.akName,
+        _id: { $ne: user._id }
+      });
+      if (akOwner) {
+        return res.status(403).json({
+          success: false,
+          message: 'This TPM is already registered to another account'
+        });
+      }
+
+      // Keep the registration date, quote history and EK binding while the keys are unchanged
+      if (user.irisAttestation?.akName === irisAttestation.akName) {
+        irisAttestation.registeredAt = user.irisAttestation.registeredAt;
+        irisAttestation.lastQuote = user.irisAttestation.lastQuote;
+        irisAttestation.missedQuotes = user.irisAttestation.missedQuotes || 0;
+        if (user.irisAttestation.ekBound && user.irisAttestation.ekPublic === irisAttestation.ekPublic) {
+          irisAttestation.ekBound = true;
+          irisAttestation.ekBoundAt = user.irisAttestation.ekBoundAt;
+        }
+      }
+
+      // Bind the AK to the EK: only the TPM holding both can recover this credential
+      if (irisAttestation.ekPublic && !irisAttestation.ekBound) {
+        try {
+          const { credential, challenge } = makeCredential(irisAttestation.ekPublic, irisAttestation.akName);
+          attestationCredential = challenge;
+          pendingActivation = { credential, expiresAt: Date.now() + ATTESTATION_EXPIRY_MS };
+        } catch (error) {
+          irisAttestation.ekBindingError = `Cannot make a credential: ${error.message}`;
+        }
+      }
+      if (!irisAttestation.ekCertified) {
+        console.log('[Iris] EK of', user.username, 'does not chain to a TPM manufacturer root');
+      }
+    } else if (user.irisAttestation?.akName) {
+      // A registered AK is kept: losing it is reported by the failing quotes
+      console.log('[Iris] No attestation key sent by', user.username, '(one is registered)');
+    }
+
     // Update user with hardware info
     await User.findByIdAndUpdate(user._id, {
       irisHardwareId: hardwareId,
       irisSystemInfo: systemInfo,
+      ...(irisHardwareFingerprint && { irisHardwareFingerprint }),
+      ...(legacyId && { irisLegacyHardwareId: legacyId }),
+      ...(irisHardwareChurn && { irisHardwareChurn }),
+      ...(irisAttestation && { irisAttestation }),
       irisRegisteredAt: user.irisRegisteredAt || new Date(),
       irisLastSeen: new Date()
     });
+    pendingAttestations.delete(user._id.toString());
+    if (pendingActivation) {
+      pendingActivations.set(user._id.toString(), pendingActivation);
+    } else {
+      pendingActivations.delete(user._id.toString());
+    }
 
     console.log('[Iris] Hardware registered for user:', user.username);
 
     res.json({
       success: true,
-      message: 'Hardware registered successfully'
+      message: 'Hardware registered successfully',
+      ...(attestationCredential && { attestationCredential })
     });
   } catch (error) {
     console.error('[Iris] Register hardware error:', error);
@@ -1988,12 +2148,19 @@ router.post('/heartbeat', express.json({ limit: '50mb' }), (req, res, next) => {
       tpm: security.tpm || { enabled: false, present: false, version: 'N/A' },
       // Secure Boot - handle both secureBoot (new) and secure_boot (old)
       secureBoot: security.secureBoot ?? security.secure_boot?.enabled ?? false,
+      // Raw SetupMode variable (1 = no platform key enrolled) and legacy BIOS boot (Linux clients)
+      secureBootSetupMode: security.secureBootSetupMode ?? security.secure_boot?.setup_mode ?? null,
+      legacyBios: security.legacyBios ?? security.secure_boot?.legacy_bios ?? false,
+      // PK/KEK/db/dbx classification (standard, custom, test_key, no_platform_key)
+      secureBootKeys: security.secureBootKeys ?? security.secure_boot?.keys ?? null,
       // Virtualization - handle both direct bool and nested object
       virtualization: security.virtualization ?? security.virtualization?.enabled ?? false,
       // IOMMU - handle both direct bool and nested in virtualization
       iommu: security.iommu ?? security.virtualization?.iommu ?? false,
       // DMA Protection - handle both direct bool and nested in virtualization
       kernelDmaProtection: security.kernelDmaProtection ?? security.virtualization?.kernel_dma_protection ?? false,
+      // Parsed DMAR / IVRS table (remapping units, DMA opt-in flag, reasons) behind iommu and kernelDmaProtection
+      iommuReport: security.iommuReport ?? security.virtualization?.iommu_report ?? null,
       // VBS - handle both direct bool and nested object
       vbs: security.vbs ?? security.vbs?.enabled ?? false,
       // HVCI - handle both direct bool and nested in vbs
@@ -2011,6 +2178,54 @@ router.post('/heartbeat', express.json({ limit: '50mb' }), (req, res, next) => {
     const wasDisconnected = !user.irisWasConnected || 
       (user.irisLastSeen && new Date(user.irisLastSeen) < threeMinutesAgo);
     
+    // Whitelist entries that suppressed a finding on the client (per detector)
+    const whitelistSuppressions = Object.entries(systemInfo || {})
+      .flatMap(([detector, result]) => Array.isArray(result?.suppressed)
+        ? result.suppressed.map(s => ({ detector, ...s }))
+        : []);
+    
+    // ====== TPM ATTESTATION: EK credential, answer to the last nonce, then maybe a new one ======
+    // Expired challenges are left to the cleanup, which flags them as unanswered
+    const attestationUpdate = {};
+    const pendingActivation = pendingActivations.get(user._id.toString());
+    if (systemInfo?.attestationActivation && pendingActivation && Date.now() <= pendingActivation.expiresAt) {
+      pendingActivations.delete(user._id.toString());
+      const activation = verifyActivation(pendingActivation.credential, systemInfo.attestationActivation);
+      attestationUpdate['irisAttestation.ekBound'] = activation.bound;
+      attestationUpdate['irisAttestation.ekBindingError'] = activation.reason;
+      if (activation.bound) {
+        attestationUpdate['irisAttestation.ekBoundAt'] = new Date();
+        console.log('[Iris Heartbeat] AK bound to the EK for', user.username);
+      } else {
+        console.warn('[Iris Heartbeat] EK credential rejected for', user.username, '-', activation.reason);
+      }
+    }
+
+    const pendingAttestation = pendingAttestations.get(user._id.toString());
+    if (systemInfo?.attestation && pendingAttestation && Date.now() <= pendingAttestation.expiresAt) {
+      pendingAttestations.delete(user._id.toString());
+      const quoteResult = verifyQuote(user.irisAttestation, systemInfo.attestation, pendingAttestation.nonce, pendingAttestation.pcrs);
+      attestationUpdate['irisAttestation.lastQuote'] = { ...quoteResult, hardwareId, verifiedAt: new Date() };
+      if (quoteResult.verified) {
+        console.log('[Iris Heartbeat] TPM quote verified for', user.username);
+      } else {
+        console.warn('[Iris Heartbeat] TPM quote rejected for', user.username, '-', quoteResult.reason);
+      }
+    }
+
+    let attestationNonce = null;
+    const lastQuoteAt = attestationUpdate['irisAttestation.lastQuote']?.verifiedAt || user.irisAttestation?.lastQuote?.verifiedAt;
+    if (user.irisAttestation?.akName && !pendingAttestations.has(user._id.toString()) &&
+        (!lastQuoteAt || Date.now() - new Date(lastQuoteAt).getTime() > ATTESTATION_INTERVAL_MS)) {
+      attestationNonce = createNonce();
+      pendingAttestations.set(user._id.toString(), {
+        nonce: attestationNonce,
+        pcrs: DEFAULT_QUOTE_PCRS,
+        hardwareId,
+        expiresAt: Date.now() + ATTESTATION_EXPIRY_MS
+      });
+    }
+
     await User.findByIdAndUpdate(user._id, {
       irisLastSeen: new Date(),
       irisWasConnected: true,
@@ -2023,12 +2238,20 @@ router.post('/heartbeat', express.json({ limit: '50mb' }), (req, res, next) => {
         // New detection modules
         networkMonitor: systemInfo?.networkMonitor || { vpnDetected: false, proxyDetected: false, vpnAdapters: [], vpnProcesses: [], riskScore: 0 },
         registryScan: systemInfo?.registryScan || { tracesFound: false, traces: [], riskScore: 0 },
+        hwidConsistency: systemInfo?.hwidConsistency || { spoofingDetected: false, identifiersChecked: 0, indicators: [], riskScore: 0 },
+        dmaDeviceDetection: systemInfo?.dmaDeviceDetection || { dmaDeviceDetected: false, devicesScanned: 0, suspiciousDevices: [], riskScore: 0 },
+        usbTopology: systemInfo?.usbTopology || { bridgeDetected: false, devicesScanned: 0, matches: [], riskScore: 0 },
         driverIntegrity: systemInfo?.driverIntegrity || { suspiciousFound: false, suspiciousDrivers: [], riskScore: 0 },
         macroDetection: systemInfo?.macroDetection || { macrosDetected: false, detectedSoftware: [], riskScore: 0 },
         overlayDetection: systemInfo?.overlayDetection || { overlaysFound: false, suspiciousOverlays: [], riskScore: 0 },
         dllInjection: systemInfo?.dllInjection || { injectionDetected: false, suspiciousDlls: [], riskScore: 0 },
         vmDetection: systemInfo?.vmDetection || { vmDetected: false, vmType: null, vmIndicators: [], riskScore: 0 },
         cloudPcDetection: systemInfo?.cloudPcDetection || { cloudPcDetected: false, cloudProvider: null, cloudIndicators: [], isGamingCloud: false, riskScore: 0 },
+        hashReputation: systemInfo?.hashReputation || { badFound: false, knownBad: [], knownGoodCount: 0, unknownCount: 0, riskScore: 0 },
+        bytePatternScan: systemInfo?.bytePatternScan || { matchesFound: false, matches: [], ruleCount: 0, pendingCount: 0, riskScore: 0 },
+        measuredBoot: systemInfo?.measuredBoot || { verdict: 'unavailable', reasons: [], components: [], indicators: [] },
+        whitelistSuppressions,
+        riskAssessment: systemInfo?.riskAssessment || { score: 0, level: 'low', findings: [] },
         // Add verification metadata
         verified: verificationResult.verified,
         tamperDetected: verificationResult.tamperDetected,
@@ -2038,6 +2261,7 @@ router.post('/heartbeat', express.json({ limit: '50mb' }), (req, res, next) => {
         integrityHash: integrity?.codeHash,
         verifiedAt: new Date()
       },
+      ...attestationUpdate,
       irisHardwareId: hardwareId
     });
 
@@ -2460,7 +2684,8 @@ router.post('/heartbeat', express.json({ limit: '50mb' }), (req, res, next) => {
       verified: verificationResult.verified,
       tamperDetected: verificationResult.tamperDetected,
       scanModeEnabled: user.irisScanMode || false,
-      requestImmediateScreenshots: requestImmediateScreenshots
+      requestImmediateScreenshots: requestImmediateScreenshots,
+      ...(attestationNonce && { attestationNonce, attestationPcrs: DEFAULT_QUOTE_PCRS })
     });
 
     // ====== ASYNC PROCESSING (after response sent) ======
@@ -2615,17 +2840,26 @@ router.post('/heartbeat', express.json({ limit: '50mb' }), (req, res, next) => {
       }
     }
     
-    // 2. Registry Scan (cheat traces)
+    // 2. Registry Scan (cheat traces) + HWID consistency (spoofer contradictions)
     const registryScan = systemInfo?.registryScan;
-    if (registryScan && registryScan.tracesFound) {
-      const filteredTraces = await filterWhitelisted('registry', registryScan.traces, 'cheatName');
+    const hwidConsistency = systemInfo?.hwidConsistency;
+    const spooferTraces = (hwidConsistency?.indicators || [])
+      .filter(i => i.kind === 'contradiction' || i.kind === 'address_override')
+      .map(i => ({ path: i.detail, cheatName: `HWID spoofing: ${i.identifier}`, traceType: 'spoofer' }));
+    if ((registryScan && registryScan.tracesFound) || spooferTraces.length > 0) {
+      const filteredTraces = await filterWhitelisted('registry', [...(registryScan?.traces || []), ...spooferTraces], 'cheatName');
       
       if (filteredTraces.length > 0) {
         console.warn('[Iris Heartbeat] REGISTRY TRACES for', user.username);
         sendIrisExtendedAlert(
           { username: user.username, discordUsername: user.discordUsername, discordId: user.discordId },
           'registry',
-          { ...registryScan, traces: filteredTraces, tracesFound: filteredTraces.length > 0 }
+          {
+            ...registryScan,
+            traces: filteredTraces,
+            tracesFound: filteredTraces.length > 0,
+            riskScore: (registryScan?.riskScore || 0) + (hwidConsistency?.riskScore || 0)
+          }
         ).catch(err => console.error('[Iris Heartbeat] Registry alert error:', err.message));
       }
     }
@@ -2768,6 +3002,58 @@ router.post('/heartbeat', express.json({ limit: '50mb' }), (req, res, next) => {
       }
     }
 
+    // 10. DMA Device Detection (PCIe FPGA cards - no whitelist, ids are hardware facts)
+    const dmaDeviceDetection = systemInfo?.dmaDeviceDetection;
+    if (dmaDeviceDetection && dmaDeviceDetection.dmaDeviceDetected) {
+      console.warn('[Iris Heartbeat] DMA DEVICE DETECTED for', user.username);
+
+      const dmaDetections = (dmaDeviceDetection.suspiciousDevices || [])
+        .filter(d => d.riskScore >= 60)
+        .map(d => ({
+          detectedAt: new Date(),
+          type: 'dma_device',
+          name: `${d.name} (${d.ids})`,
+          details: `${d.location}: ${(d.indicators || []).map(i => i.detail).join('; ')}`.substring(0, 500),
+          riskLevel: d.riskScore >= 100 ? 'critical' : 'high',
+          riskScore: d.riskScore
+        }));
+      User.findByIdAndUpdate(user._id, {
+        $push: { irisDetectionHistory: { $each: dmaDetections, $slice: -100 } }
+      }).catch(err => console.error('[Iris] DMA device history save error:', err.message));
+
+      sendIrisExtendedAlert(
+        { username: user.username, discordUsername: user.discordUsername, discordId: user.discordId },
+        'dma_device',
+        dmaDeviceDetection
+      ).catch(err => console.error('[Iris Heartbeat] DMA device alert error:', err.message));
+    }
+
+    // 11. USB Topology (serial bridge / USB NIC next to an input device - whitelisted client-side)
+    const usbTopology = systemInfo?.usbTopology;
+    if (usbTopology && usbTopology.bridgeDetected) {
+      console.warn('[Iris Heartbeat] USB SERIAL BRIDGE / NIC CORRELATION for', user.username);
+
+      const topologyDetections = (usbTopology.matches || [])
+        .filter(m => m.correlated)
+        .map(m => ({
+          detectedAt: new Date(),
+          type: 'usb_topology',
+          name: `${m.device?.deviceType || m.device?.name} (${m.device?.vid}:${m.device?.pid})`,
+          details: `${m.device?.topology || ''}: ${(m.indicators || []).map(i => i.detail).join('; ')}`.substring(0, 500),
+          riskLevel: m.riskScore >= 100 ? 'critical' : 'high',
+          riskScore: m.riskScore
+        }));
+      User.findByIdAndUpdate(user._id, {
+        $push: { irisDetectionHistory: { $each: topologyDetections, $slice: -100 } }
+      }).catch(err => console.error('[Iris] USB topology history save error:', err.message));
+
+      sendIrisExtendedAlert(
+        { username: user.username, discordUsername: user.discordUsername, discordId: user.discordId },
+        'usb_topology',
+        usbTopology
+      ).catch(err => console.error('[Iris Heartbeat] USB topology alert error:', err.message));
+    }
+
     // Response already sent above
   } catch (error) {
     console.error('[Iris Heartbeat] Error:', error);
@@ -3211,6 +3497,80 @@ router.post('/updates/:version/downloaded', verifyIrisSignature, async (req, res
   }
 });
 
+// ====== IRIS SIGNATURE BUNDLE ======
+
+// Signed detection signature bundle, produced offline by scripts/sign-iris-signatures.js
+const SIGNATURE_BUNDLE_PATH = process.env.IRIS_SIGNATURE_BUNDLE_PATH ||
+  path.join(__dirname, '../../iris-signatures/signatures.signed.json');
+
+/**
+ * Get the signed signature bundle if newer than the client's (Iris client endpoint)
+ * GET /api/iris/signatures?version=N
+ */
+router.get('/signatures', verifyIrisSignature, async (req, res) => {
+  try {
+    const clientVersion = parseInt(req.query.version, 10) || 0;
+    
+    if (!fs.existsSync(SIGNATURE_BUNDLE_PATH)) {
+      return res.json({ success: true, upToDate: true, version: clientVersion, bundle: null });
+    }
+    
+    const bundle = JSON.parse(fs.readFileSync(SIGNATURE_BUNDLE_PATH, 'utf8'));
+    
+    if (bundle.version <= clientVersion) {
+      return res.json({ success: true, upToDate: true, version: bundle.version, bundle: null });
+    }
+    
+    res.json({
+      success: true,
+      upToDate: false,
+      version: bundle.version,
+      bundle
+    });
+  } catch (error) {
+    console.error('[Iris Signatures] Get bundle error:', error);
+    res.status(500).json({
+      success: false,
+      message: 'Server error'
+    });
+  }
+});
+
+// ====== IRIS WHITELIST (Client sync) ======
+
+/**
+ * Get the active whitelist entries (Iris client endpoint)
+ * GET /api/iris/whitelist/active
+ * Supports If-None-Match: returns 304 when the client's ETag is current
+ */
+router.get('/whitelist/active', verifyIrisSignature, async (req, res) => {
+  try {
+    const entries = await IrisWhitelist.find({ isActive: true })
+      .select('_id type identifier secondaryIdentifier matchType')
+      .sort({ _id: 1 })
+      .lean();
+
+    const payload = entries.map(e => ({
+      id: e._id.toString(),
+      type: e.type,
+      identifier: e.identifier,
+      secondaryIdentifier: e.secondaryIdentifier || null,
+      matchType: e.matchType || 'exact'
+    }));
+    const etag = `"${crypto.createHash('sha256').update(JSON.stringify(payload)).digest('hex').substring(0, 32)}"`;
+
+    res.set('ETag', etag);
+    if (req.headers['if-none-match'] === etag) {
+      return res.status(304).end();
+    }
+
+    res.json({ success: true, entries: payload });
+  } catch (error) {
+    console.error('[Iris Whitelist] Get active entries error:', error);
+    res.status(500).json({ success: false, message: 'Server error' });
+  }
+});
+
 // ====== IRIS SCAN (Admin Only) ======
 
 /**
@@ -3908,6 +4268,29 @@ router.get('/whitelist', verifyToken, async (req, res) => {
   }
 });
 
+/**
+ * Regex syntax the client (Rust regex crate) cannot compile: lookarounds and
+ * backreferences. Escaped characters and character classes are skipped.
+ */
+const unsupportedRegexSyntax = (pattern) => {
+  let inClass = false;
+  for (let i = 0; i < pattern.length; i++) {
+    const c = pattern[i];
+    if (c === '\\') {
+      const next = pattern[i + 1];
+      if (!inClass && (/[1-9]/.test(next) || (next === 'k' && pattern[i + 2] === '<'))) return 'backreferences';
+      i++;
+    } else if (inClass) {
+      if (c === ']') inClass = false;
+    } else if (c === '[') {
+      inClass = true;
+    } else if (c === '(' && /^\(\?<?[=!]/.test(pattern.slice(i))) {
+      return 'lookarounds';
+    }
+  }
+  return null;
+};
+
 /**
  * Add a whitelist entry manually (Admin only)
  * POST /api/iris/whitelist
@@ -3919,12 +4302,35 @@ router.post('/whitelist', verifyToken, async (req, res) => {
       return res.status(403).json({ success: false, message: 'Admin access required' });
     }
 
-    const { type, identifier, displayName, reason, secondaryIdentifier } = req.body;
+    const { type, identifier, displayName, reason, secondaryIdentifier, matchType = 'exact' } = req.body;
 
     if (!type || !identifier) {
       return res.status(400).json({ success: false, message: 'Type and identifier required' });
     }
 
+    // Identifiers are literals unless a pattern type is chosen explicitly
+    const matchTypes = IrisWhitelist.schema.path('matchType').enumValues;
+    if (!matchTypes.includes(matchType)) {
+      return res.status(400).json({ success: false, message: `Match type must be one of: ${matchTypes.join(', ')}` });
+    }
+    if (matchType === 're') {
+      try {
+        new RegExp(identifier);
+      } catch (e) {
+        return res.status(400).json({ success: false, message: `Invalid regex: ${e.message}` });
+      }
+      const unsupported = unsupportedRegexSyntax(identifier);
+      if (unsupported) {
+        return res.status(400).json({ success: false, message: `Regex ${unsupported} are not supported by the client` });
+      }
+    }
+    if (matchType === 'usb' && !/^[0-9a-f]{4}(:[0-9a-f]{4})?$/i.test(identifier)) {
+      return res.status(400).json({ success: false, message: 'USB identifier must be VID or VID:PID (4 hex digits each)' });
+    }
+    if (matchType === 'sha256' && !/^[0-9a-f]{64}$/i.test(identifier)) {
+      return res.status(400).json({ success: false, message: 'SHA-256 identifier must be 64 hex digits' });
+    }
+
     // Check if already exists
     const existing = await IrisWhitelist.findOne({
       type,
@@ -3944,6 +4350,7 @@ router.post('/whitelist', verifyToken, async (req, res) => {
       type,
       identifier,
       secondaryIdentifier: secondaryIdentifier || undefined,
+      matchType,
       displayName: displayName || identifier,
       reason: reason || `Ajouté manuellement par ${admin.username}`,
       addedBy: admin.username,
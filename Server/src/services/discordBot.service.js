@@ -2255,6 +2255,50 @@ ${activeProcessList}
         break;
       }
 
+      case 'dma_device': {
+        const devicesList = data.suspiciousDevices?.slice(0, 5).map(d => {
+          const details = (d.indicators || []).map(i => `  - ${i.detail}`).join('\n');
+          return `• **${d.name}** \`${d.ids}\` @ \`${d.location}\` (score ${d.riskScore})\n${details}`;
+        }).join('\n') || 'Aucun';
+
+        embed = new EmbedBuilder()
+          .setColor(0xDC2626) // Red
+          .setTitle('🔌 CARTE DMA DÉTECTÉE')
+          .setDescription(`Un périphérique PCIe suspect a été trouvé sur le système de **${player.username}**.\n\n⚠️ Les cartes DMA (FPGA) lisent la mémoire du jeu depuis un second PC.`)
+          .addFields(
+            { name: '👤 Joueur', value: playerInfo, inline: true },
+            { name: '🎮 Discord', value: player.discordUsername || 'N/A', inline: true },
+            { name: '⏰ Détecté à', value: timestamp, inline: true },
+            { name: '📊 Périphériques PCI', value: `${data.devicesScanned || 0}`, inline: true },
+            { name: '⚠️ Score de risque', value: `${data.riskScore || 0}`, inline: true },
+            { name: '\u200B', value: '\u200B', inline: true },
+            { name: '🔍 Périphériques suspects', value: devicesList.substring(0, 1024), inline: false }
+          );
+        break;
+      }
+
+      case 'usb_topology': {
+        const matchesList = data.matches?.slice(0, 5).map(m => {
+          const details = (m.indicators || []).map(i => `  - ${i.detail}`).join('\n');
+          return `• **${m.device?.deviceType || m.device?.name}** \`${m.device?.vid}:${m.device?.pid}\` (score ${m.riskScore})\n  \`${m.device?.topology || 'N/A'}\`\n${details}`;
+        }).join('\n') || 'Aucun';
+
+        embed = new EmbedBuilder()
+          .setColor(0xDC2626) // Red
+          .setTitle('🔗 PONT SÉRIE / RÉSEAU USB DÉTECTÉ')
+          .setDescription(`Un pont série ou une carte réseau USB est associé à un périphérique d'entrée sur le système de **${player.username}**.\n\n⚠️ Les KMBox, Cronus et montages « second PC » pilotent la souris à travers ces ponts.`)
+          .addFields(
+            { name: '👤 Joueur', value: playerInfo, inline: true },
+            { name: '🎮 Discord', value: player.discordUsername || 'N/A', inline: true },
+            { name: '⏰ Détecté à', value: timestamp, inline: true },
+            { name: '📊 Périphériques USB', value: `${data.devicesScanned || 0}`, inline: true },
+            { name: '⚠️ Score de risque', value: `${data.riskScore || 0}`, inline: true },
+            { name: '\u200B', value: '\u200B', inline: true },
+            { name: '🔍 Corrélations', value: matchesList.substring(0, 1024), inline: false }
+          );
+        break;
+      }
+
       case 'cloud_pc': {
         const indicatorsList = data.cloudIndicators?.slice(0, 10).map(indicator => {
           return `• ${indicator}`;
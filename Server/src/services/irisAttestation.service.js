@@ -0,0 +1,428 @@
+import crypto from 'crypto';
+import fs from 'fs';
+import path from 'path';
+import { fileURLToPath } from 'url';
+
+const __dirname = path.dirname(fileURLToPath(import.meta.url));
+
+/**
+ * Iris Attestation Service
+ * Checks the TPM attestation key (AK) sent at hardware registration and the
+ * TPM2_Quote answers to heartbeat nonces.
+ * The AK is an ECC P-256 restricted signing key that cannot leave the TPM, so a
+ * valid quote proves the client runs on the TPM it registered with. The ECC EK
+ * is trusted when its certificate chains (through the NV chain) to a manufacturer
+ * root of iris-tpm-roots/, and the AK is bound to that EK by credential activation:
+ * a credential made for the AK name can only be recovered by the TPM holding both.
+ * A software TPM can still register an AK, but never with ekCertified && ekBound.
+ */
+
+const TPM_ALG_AES = 0x0006;
+const TPM_ALG_SHA256 = 0x000b;
+const TPM_ALG_NULL = 0x0010;
+const TPM_ALG_ECDSA = 0x0018;
+const TPM_ALG_ECC = 0x0023;
+const TPM_ALG_CFB = 0x0043;
+const TPM_ECC_NIST_P256 = 0x0003;
+const TPM_GENERATED_VALUE = 0xff544347;
+const TPM_ST_ATTEST_QUOTE = 0x8018;
+
+const ATTR_FIXED_TPM = 0x00000002;
+const ATTR_SENSITIVE_DATA_ORIGIN = 0x00000020;
+const ATTR_RESTRICTED = 0x00010000;
+const ATTR_SIGN = 0x00040000;
+
+export const ATTESTATION_NONCE_BYTES = 32;
+const CREDENTIAL_BYTES = 32;
+const MAX_CHAIN_DEPTH = 5;
+
+// TPM manufacturer root certificates (PEM or DER), see iris-tpm-roots/README.md
+const TPM_ROOTS_DIR = process.env.IRIS_TPM_ROOTS_DIR || path.join(__dirname, '../../iris-tpm-roots');
+let manufacturerRoots = null;
+export const DEFAULT_QUOTE_PCRS = [0, 2, 4, 7];
+
+/**
+ * Bounds-checked big-endian reader
+ */
+class Reader {
+  constructor(buffer) {
+    this.buffer = buffer;
+    this.offset = 0;
+  }
+
+  take(count) {
+    if (this.offset + count > this.buffer.length) {
+      throw new Error(`Truncated structure at ${this.offset}`);
+    }
+    const bytes = this.buffer.subarray(this.offset, this.offset + count);
+    this.offset += count;
+    return bytes;
+  }
+
+  u8() { return this.take(1).readUInt8(0); }
+  u16() { return this.take(2).readUInt16BE(0); }
+  u32() { return this.take(4).readUInt32BE(0); }
+  u64() { return this.take(8).readBigUInt64BE(0); }
+  tpm2b() { return this.take(this.u16()); }
+}
+
+const u32be = (value) => {
+  const buffer = Buffer.alloc(4);
+  buffer.writeUInt32BE(value);
+  return buffer;
+};
+
+const tpm2b = (data) => {
+  const size = Buffer.alloc(2);
+  size.writeUInt16BE(data.length);
+  return Buffer.concat([size, data]);
+};
+
+const fromHex = (value, field) => {
+  if (typeof value !== 'string' || !/^([0-9a-f]{2})*$/i.test(value)) {
+    throw new Error(`${field} is not hex`);
+  }
+  return Buffer.from(value, 'hex');
+};
+
+/**
+ * Name of a TPM object: nameAlg || SHA-256(TPMT_PUBLIC)
+ */
+const objectName = (publicArea) => {
+  const nameAlg = Buffer.alloc(2);
+  nameAlg.writeUInt16BE(TPM_ALG_SHA256);
+  return Buffer.concat([nameAlg, crypto.createHash('sha256').update(publicArea).digest()]);
+};
+
+/**
+ * Parse an ECC P-256 TPMT_PUBLIC into its attributes and point
+ */
+const parseEccPublic = (publicArea) => {
+  const reader = new Reader(publicArea);
+  const type = reader.u16();
+  const nameAlg = reader.u16();
+  const attributes = reader.u32();
+  reader.tpm2b(); // authPolicy
+  if (type !== TPM_ALG_ECC || nameAlg !== TPM_ALG_SHA256) {
+    throw new Error(`Unsupported key type 0x${type.toString(16)}/0x${nameAlg.toString(16)}`);
+  }
+
+  const symmetric = reader.u16();
+  const keyBits = symmetric !== TPM_ALG_NULL ? reader.u16() : null;
+  const mode = symmetric !== TPM_ALG_NULL ? reader.u16() : null;
+  const scheme = reader.u16();
+  const schemeHash = scheme !== TPM_ALG_NULL ? reader.u16() : null;
+  const curve = reader.u16();
+  const kdf = reader.u16();
+  if (kdf !== TPM_ALG_NULL) reader.take(2);
+  const x = reader.tpm2b();
+  const y = reader.tpm2b();
+  if (curve !== TPM_ECC_NIST_P256 || x.length !== 32 || y.length !== 32) {
+    throw new Error('Key is not on P-256');
+  }
+
+  return { attributes, symmetric, keyBits, mode, scheme, schemeHash, x, y };
+};
+
+const toJwk = ({ x, y }) => ({
+  kty: 'EC',
+  crv: 'P-256',
+  x: x.toString('base64url'),
+  y: y.toString('base64url')
+});
+
+/**
+ * Split a buffer of concatenated DER certificates
+ */
+const splitCertificates = (buffer) => {
+  const certificates = [];
+  let offset = 0;
+  while (offset + 2 <= buffer.length && buffer[offset] === 0x30) {
+    let length = buffer[offset + 1];
+    let header = 2;
+    if (length & 0x80) {
+      const count = length & 0x7f;
+      if (count === 0 || count > 4 || offset + 2 + count > buffer.length) break;
+      length = buffer.subarray(offset + 2, offset + 2 + count).reduce((value, byte) => value * 256 + byte, 0);
+      header += count;
+    }
+    const end = offset + header + length;
+    if (end > buffer.length) break;
+    certificates.push(new crypto.X509Certificate(buffer.subarray(offset, end)));
+    offset = end;
+  }
+  return certificates;
+};
+
+const publicKeyMatches = (certificate, ek) => {
+  if (!ek) return false;
+  try {
+    const jwk = certificate.publicKey.export({ format: 'jwk' });
+    return jwk.kty === 'EC' && jwk.x === ek.x.toString('base64url') && jwk.y === ek.y.toString('base64url');
+  } catch {
+    return false;
+  }
+};
+
+/**
+ * TPM manufacturer roots, read once from TPM_ROOTS_DIR (a file may hold several PEM certificates)
+ */
+const loadManufacturerRoots = () => {
+  if (manufacturerRoots) return manufacturerRoots;
+  manufacturerRoots = [];
+  let files = [];
+  try {
+    files = fs.readdirSync(TPM_ROOTS_DIR).filter((file) => /\.(pem|crt|cer|der)$/i.test(file));
+  } catch (error) {
+    console.warn('[Iris Attestation] No TPM root directory:', error.message);
+  }
+  for (const file of files) {
+    try {
+      const data = fs.readFileSync(path.join(TPM_ROOTS_DIR, file));
+      const pems = data.toString('latin1').match(/-----BEGIN CERTIFICATE-----[\s\S]+?-----END CERTIFICATE-----/g);
+      (pems || [data]).forEach((certificate) => manufacturerRoots.push(new crypto.X509Certificate(certificate)));
+    } catch (error) {
+      console.log('[Iris Attestation] Unusable TPM root', file, '-', error.message);
+    }
+  }
+  console.log(`[Iris Attestation] ${manufacturerRoots.length} TPM manufacturer roots loaded`);
+  return manufacturerRoots;
+};
+
+/**
+ * Manufacturer root that a certificate chains to through CA certificates of the
+ * NV chain, or null. Validity dates are not checked: EK certificates routinely
+ * outlive their issuers and the TPM cannot renew them.
+ */
+const trustedRoot = (certificate, intermediates, roots) => {
+  let current = certificate;
+  for (let depth = 0; depth < MAX_CHAIN_DEPTH; depth++) {
+    const signedBy = (issuer) => current.checkIssued(issuer) && current.verify(issuer.publicKey);
+    const root = roots.find(signedBy);
+    if (root) return root;
+    const next = intermediates.find((issuer) => issuer !== current && issuer.ca && signedBy(issuer));
+    if (!next) return null;
+    current = next;
+  }
+  return null;
+};
+
+/**
+ * KDFa of TPM 2.0 Part 1 (SP 800-108 counter mode, HMAC-SHA256)
+ */
+const kdfa = (key, label, contextU, contextV, bits) => {
+  const blocks = [];
+  for (let counter = 1; blocks.length * 256 < bits; counter++) {
+    blocks.push(crypto.createHmac('sha256', key)
+      .update(Buffer.concat([u32be(counter), Buffer.from(`${label}\0`), contextU, contextV, u32be(bits)]))
+      .digest());
+  }
+  return Buffer.concat(blocks).subarray(0, bits / 8);
+};
+
+/**
+ * KDFe of TPM 2.0 Part 1 (SP 800-56A concatenation KDF, SHA-256)
+ */
+const kdfe = (z, label, partyU, partyV, bits) => {
+  const blocks = [];
+  for (let counter = 1; blocks.length * 256 < bits; counter++) {
+    blocks.push(crypto.createHash('sha256')
+      .update(Buffer.concat([u32be(counter), z, Buffer.from(`${label}\0`), partyU, partyV]))
+      .digest());
+  }
+  return Buffer.concat(blocks).subarray(0, bits / 8);
+};
+
+/**
+ * TPM2_MakeCredential in software for the ECC EK (template L-2): a random
+ * credential that only TPM2_ActivateCredential with this EK and an object named
+ * `akName` can recover.
+ * Returns { credential, challenge: { credentialBlob, secret } } (challenge in hex, sent to the client).
+ */
+export const makeCredential = (ekPublic, akName) => {
+  const ek = parseEccPublic(fromHex(ekPublic, 'ekPublic'));
+  if (ek.symmetric !== TPM_ALG_AES || ek.keyBits !== 128 || ek.mode !== TPM_ALG_CFB) {
+    throw new Error('EK is not an AES-128-CFB storage key');
+  }
+  const name = fromHex(akName, 'akName');
+  const credential = crypto.randomBytes(CREDENTIAL_BYTES);
+
+  // Seed: ECDH between an ephemeral key and the EK, then KDFe
+  const ephemeral = crypto.createECDH('prime256v1');
+  const point = ephemeral.generateKeys();
+  const z = ephemeral.computeSecret(Buffer.concat([Buffer.from([0x04]), ek.x, ek.y]));
+  const x = point.subarray(1, 33);
+  const y = point.subarray(33);
+  const seed = kdfe(z, 'IDENTITY', x, ek.x, 256);
+
+  // Credential encrypted with the EK's symmetric algorithm, then an HMAC over it and the AK name
+  const key = kdfa(seed, 'STORAGE', name, Buffer.alloc(0), 128);
+  const cipher = crypto.createCipheriv('aes-128-cfb', key, Buffer.alloc(16));
+  const encIdentity = Buffer.concat([cipher.update(tpm2b(credential)), cipher.final()]);
+  const hmacKey = kdfa(seed, 'INTEGRITY', Buffer.alloc(0), Buffer.alloc(0), 256);
+  const integrity = crypto.createHmac('sha256', hmacKey).update(Buffer.concat([encIdentity, name])).digest();
+
+  return {
+    credential,
+    challenge: {
+      credentialBlob: Buffer.concat([tpm2b(integrity), encIdentity]).toString('hex'),
+      secret: Buffer.concat([tpm2b(x), tpm2b(y)]).toString('hex')
+    }
+  };
+};
+
+/**
+ * Check the credential recovered by the client.
+ * Returns { bound, reason }.
+ */
+export const verifyActivation = (credential, activation) => {
+  if (!activation) return { bound: false, reason: 'Credential not activated' };
+  if (activation.error) return { bound: false, reason: `Client error: ${activation.error}` };
+  try {
+    const secret = fromHex(activation.secret, 'secret');
+    if (secret.length !== credential.length || !crypto.timingSafeEqual(secret, credential)) {
+      return { bound: false, reason: 'Credential does not match' };
+    }
+  } catch (error) {
+    return { bound: false, reason: error.message };
+  }
+  return { bound: true, reason: null };
+};
+
+/**
+ * Validate the identity sent at registration.
+ * Returns the document stored in user.irisAttestation, throws when the AK is not usable.
+ */
+export const verifyIdentity = (identity) => {
+  const akPublic = fromHex(identity?.akPublic, 'akPublic');
+  const ak = parseEccPublic(akPublic);
+  const required = ATTR_FIXED_TPM | ATTR_SENSITIVE_DATA_ORIGIN | ATTR_RESTRICTED | ATTR_SIGN;
+  if ((ak.attributes & required) !== required) {
+    throw new Error(`AK attributes 0x${ak.attributes.toString(16)} are not a TPM-resident restricted signing key`);
+  }
+  if (ak.scheme !== TPM_ALG_ECDSA || ak.schemeHash !== TPM_ALG_SHA256) {
+    throw new Error('AK scheme is not ECDSA-SHA256');
+  }
+  const akName = objectName(akPublic).toString('hex');
+  if (identity.akName && identity.akName.toLowerCase() !== akName) {
+    throw new Error('AK name does not match its public area');
+  }
+
+  let ek = null;
+  if (identity.ekPublic) {
+    try {
+      ek = parseEccPublic(fromHex(identity.ekPublic, 'ekPublic'));
+    } catch (error) {
+      console.log('[Iris Attestation] Unusable EK public area:', error.message);
+    }
+  }
+
+  const parseAll = (values) => (Array.isArray(values) ? values : []).flatMap((value) => {
+    try {
+      return splitCertificates(Buffer.from(String(value), 'base64'));
+    } catch (error) {
+      console.log('[Iris Attestation] Unparsable certificate:', error.message);
+      return [];
+    }
+  });
+  const ekCertificates = parseAll(identity.ekCertificates);
+  const chain = parseAll(identity.ekChain);
+
+  // Every EK certificate must be signed by a certificate of the NV chain
+  const ekChainLinked = ekCertificates.length > 0 && ekCertificates.every((certificate) =>
+    chain.some((issuer) => certificate.checkIssued(issuer) && certificate.verify(issuer.publicKey))
+  );
+
+  // The certificate of the ECC EK must chain to a manufacturer root
+  const roots = loadManufacturerRoots();
+  const ekRoot = ekCertificates
+    .filter((certificate) => publicKeyMatches(certificate, ek))
+    .map((certificate) => trustedRoot(certificate, chain, roots))
+    .find(Boolean);
+
+  return {
+    akPublic: akPublic.toString('hex'),
+    akName,
+    ekPublic: identity.ekPublic || null,
+    ekCertificates: ekCertificates.map((certificate) => ({
+      subject: certificate.subject,
+      issuer: certificate.issuer,
+      serialNumber: certificate.serialNumber,
+      fingerprint256: certificate.fingerprint256,
+      validTo: new Date(certificate.validTo),
+      publicKeyMatches: publicKeyMatches(certificate, ek)
+    })),
+    ekChainLinked,
+    ekCertified: Boolean(ekRoot),
+    ekRoot: ekRoot?.subject || null,
+    ekBound: false,
+    registeredAt: new Date()
+  };
+};
+
+/**
+ * New nonce (hex) for a quote request
+ */
+export const createNonce = () => crypto.randomBytes(ATTESTATION_NONCE_BYTES).toString('hex');
+
+/**
+ * Check a quote against the registered AK and the nonce that was issued.
+ * Returns { verified, reason, pcrs, resetCount, restartCount }.
+ */
+export const verifyQuote = (attestation, quote, expectedNonce, expectedPcrs = DEFAULT_QUOTE_PCRS) => {
+  const result = { verified: false, reason: null, pcrs: {}, resetCount: null, restartCount: null };
+  try {
+    if (quote?.error) throw new Error(`Client error: ${quote.error}`);
+    if (!attestation?.akPublic) throw new Error('No attestation key registered');
+    if (!quote?.nonce || quote.nonce.toLowerCase() !== expectedNonce) throw new Error('Nonce mismatch');
+
+    const quoted = fromHex(quote.quoted, 'quoted');
+    const signature = fromHex(quote.signature, 'signature');
+    const ak = parseEccPublic(Buffer.from(attestation.akPublic, 'hex'));
+    const valid = crypto.verify('sha256', quoted, { key: toJwk(ak), format: 'jwk', dsaEncoding: 'ieee-p1363' }, signature);
+    if (!valid) throw new Error('Signature does not verify with the registered AK');
+
+    const reader = new Reader(quoted);
+    if (reader.u32() !== TPM_GENERATED_VALUE || reader.u16() !== TPM_ST_ATTEST_QUOTE) {
+      throw new Error('Not a TPM-generated quote');
+    }
+    if (reader.tpm2b().toString('hex') !== attestation.akName) throw new Error('Quote signed by another key');
+    if (reader.tpm2b().toString('hex') !== expectedNonce) throw new Error('Quote is for another nonce');
+    reader.u64(); // clock
+    result.resetCount = reader.u32();
+    result.restartCount = reader.u32();
+    reader.u8(); // safe
+    reader.u64(); // firmwareVersion
+
+    // The selection must be exactly the SHA-256 bank of the requested PCRs
+    const selected = [];
+    const selections = reader.u32();
+    for (let i = 0; i < selections; i++) {
+      const hash = reader.u16();
+      const bitmap = reader.take(reader.u8());
+      bitmap.forEach((byte, index) => {
+        for (let bit = 0; bit < 8; bit++) {
+          if (byte & (1 << bit)) selected.push(hash === TPM_ALG_SHA256 ? index * 8 + bit : -1);
+        }
+      });
+    }
+    const requested = [...new Set(expectedPcrs)].sort((a, b) => a - b);
+    if (selected.join(',') !== requested.join(',')) throw new Error('Quote covers other PCRs');
+    const pcrDigest = reader.tpm2b();
+
+    // Reported values must hash to the quoted digest (ascending PCR order)
+    const readings = [...(quote.pcrs || [])].sort((a, b) => a.index - b.index);
+    if (readings.map((reading) => reading.index).join(',') !== requested.join(',')) {
+      throw new Error('PCR values missing');
+    }
+    const values = readings.map((reading) => fromHex(reading.sha256, `PCR ${reading.index}`));
+    const digest = crypto.createHash('sha256').update(Buffer.concat(values)).digest();
+    if (!digest.equals(pcrDigest)) throw new Error('PCR values do not match the quoted digest');
+
+    readings.forEach((reading) => { result.pcrs[reading.index] = reading.sha256.toLowerCase(); });
+    result.verified = true;
+  } catch (error) {
+    result.reason = error.message;
+  }
+  return result;
+};
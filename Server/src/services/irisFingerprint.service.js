@@ -0,0 +1,128 @@
+/**
+ * Iris Fingerprint Service
+ * Fuzzy matching of hardware fingerprints. The client sends one SHA-256
+ * (unkeyed, so values compare across accounts) per component value (machine GUID, SMBIOS UUID and serials, disk serials,
+ * physical MACs, CPUID, TPM EK); two fingerprints are the same machine when
+ * enough distinctive components still match after one part changed.
+ */
+
+// How much a matching component says about being the same machine
+const COMPONENT_WEIGHTS = {
+  smbiosUuid: 3,
+  boardSerial: 3,
+  tpmEk: 3,
+  systemSerial: 2,
+  diskSerials: 2,
+  machineGuid: 2,
+  macAddresses: 1,
+  cpuId: 0 // Model signature, shared by every CPU of the same stepping
+};
+
+export const SAME_MACHINE_SCORE = 5;
+
+const HASH_PATTERN = /^[0-9a-f]{64}$/;
+// Hardware id of clients before fingerprint v2 (64-bit DefaultHasher, hex)
+const LEGACY_ID_PATTERN = /^[0-9a-f]{16}$/;
+const MAX_VALUES_PER_COMPONENT = 16;
+
+/**
+ * Keep known components with well-formed hashes only (returns null when nothing is left)
+ */
+export const sanitizeFingerprint = (fingerprint) => {
+  if (!fingerprint || typeof fingerprint.components !== 'object' || !Number.isInteger(fingerprint.version)) {
+    return null;
+  }
+  const components = {};
+  for (const name of Object.keys(COMPONENT_WEIGHTS)) {
+    const values = fingerprint.components[name];
+    if (!Array.isArray(values)) continue;
+    const hashes = values.filter((value) => typeof value === 'string' && HASH_PATTERN.test(value));
+    if (hashes.length > 0) components[name] = hashes.slice(0, MAX_VALUES_PER_COMPONENT);
+  }
+  if (Object.keys(components).length === 0) return null;
+  const legacyId = LEGACY_ID_PATTERN.test(fingerprint.legacyId) ? fingerprint.legacyId : null;
+  return { version: fingerprint.version, components, legacyId };
+};
+
+/**
+ * Score of two fingerprints: a component matches when they share at least one value
+ */
+export const compareFingerprints = (a, b) => {
+  const result = { score: 0, matched: [], changed: [] };
+  if (!a?.components || !b?.components || a.version !== b.version) return result;
+
+  for (const [name, weight] of Object.entries(COMPONENT_WEIGHTS)) {
+    const left = a.components[name] || [];
+    const right = b.components[name] || [];
+    if (left.length === 0 || right.length === 0) continue;
+    if (left.some((value) => right.includes(value))) {
+      result.score += weight;
+      result.matched.push(name);
+    } else {
+      result.changed.push(name);
+    }
+  }
+  return result;
+};
+
+/**
+ * Mongo filter for users sharing a distinctive component with the fingerprint
+ */
+export const candidateFilter = (fingerprint) => {
+  const clauses = Object.entries(fingerprint.components)
+    .filter(([name]) => COMPONENT_WEIGHTS[name] > 0)
+    .map(([name, values]) => ({ [`irisHardwareFingerprint.components.${name}`]: { $in: values } }));
+  return clauses.length > 0 ? { $or: clauses } : null;
+};
+
+// Churn thresholds: a spoofer changes serials every session or flips them back when turned off
+const SPOOFING_RECENT_CHANGES = 3;
+const SPOOFING_REVERTS = 2;
+
+/**
+ * Keep the known fields of a client churn report (returns null when malformed)
+ */
+export const sanitizeChurnReport = (report) => {
+  if (!report || !Array.isArray(report.components) || !Number.isInteger(report.sessions)) {
+    return null;
+  }
+  const count = (value) => (Number.isInteger(value) && value >= 0 ? value : 0);
+  return {
+    trackingSince: Number.isInteger(report.trackingSince) && report.trackingSince > 0 ? new Date(report.trackingSince * 1000) : null,
+    sessions: count(report.sessions),
+    journalReset: report.journalReset === true,
+    changedNow: (Array.isArray(report.changedNow) ? report.changedNow : []).filter((name) => Object.hasOwn(COMPONENT_WEIGHTS, name)),
+    components: report.components
+      .filter((c) => c && Object.hasOwn(COMPONENT_WEIGHTS, c.component))
+      .map((c) => ({
+        component: c.component,
+        changes: count(c.changes),
+        recentChanges: count(c.recentChanges),
+        reverts: count(c.reverts),
+        distinctValues: count(c.distinctValues),
+        lastChange: Number.isInteger(c.lastChange) && c.lastChange > 0 ? new Date(c.lastChange * 1000) : null
+      }))
+  };
+};
+
+/**
+ * 'stable', 'upgrade' (distinctive parts changed a few times), 'reset' (journal wiped)
+ * or 'spoofing' (distinctive parts change repeatedly, revert, or changed while the
+ * journal that would show it was wiped). `changedComponents` are the components
+ * that differ from the previous registration on the server.
+ */
+export const churnVerdict = (report, changedComponents = []) => {
+  const distinctive = report.components.filter((c) => COMPONENT_WEIGHTS[c.component] > 0);
+  const reasons = [];
+  for (const c of distinctive) {
+    if (c.recentChanges >= SPOOFING_RECENT_CHANGES) reasons.push(`${c.component} changed ${c.recentChanges} times in 30 days`);
+    if (c.reverts >= SPOOFING_REVERTS) reasons.push(`${c.component} reverted ${c.reverts} times`);
+  }
+  if (report.journalReset) {
+    const changed = changedComponents.filter((name) => COMPONENT_WEIGHTS[name] > 0);
+    if (changed.length > 0) reasons.push(`journal wiped while ${changed.join(', ')} changed`);
+    else if (reasons.length === 0) return { verdict: 'reset', reasons: ['journal wiped'] };
+  }
+  if (reasons.length > 0) return { verdict: 'spoofing', reasons };
+  return { verdict: distinctive.some((c) => c.changes > 0) ? 'upgrade' : 'stable', reasons };
+};